@@ -0,0 +1,67 @@
+// Shader propio fuera de la biblioteca: un planeta a cuadros dibujado sin ventana y guardado como PNG.
+// cargo run --release --example custom_shader -- [archivo.png]
+use lab4_shaders::camera::Camera;
+use lab4_shaders::color::Color;
+use lab4_shaders::export::save_png;
+use lab4_shaders::fragments::Fragments;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::pipeline::{build_uniforms, BodyUniforms, FrameUniforms, FOV};
+use lab4_shaders::scene::{load_meshes, render_scene, scene_bounds, scene_depth_range, BodyConfig, MeshKind, RenderOptions, SceneConfig, SceneState};
+use lab4_shaders::shaders::{spherical_uv, FragmentShader, SceneShader};
+use nalgebra_glm::Vec3;
+use std::path::Path;
+
+const WIDTH: usize = 400;
+const HEIGHT: usize = 300;
+
+// Casillas en latitud y longitud, iluminadas con la luz que trae el fragmento
+struct Checkerboard {
+    squares: f32,
+    light: Color,
+    dark: Color,
+}
+
+impl FragmentShader for Checkerboard {
+    fn shade(&self, fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+        let uv = spherical_uv(fragment.vertex_pos) * self.squares;
+        let color = if (uv.x.floor() + uv.y.floor()) as i32 % 2 == 0 { self.light } else { self.dark };
+        color * fragment.light
+    }
+
+    fn name(&self) -> &str {
+        "checkerboard"
+    }
+
+    fn albedo(&self) -> Option<Color> {
+        Some(self.light.lerp(&self.dark, 0.5))
+    }
+}
+
+fn main() {
+    let path = std::env::args().nth(1).unwrap_or_else(|| "custom_shader.png".to_string());
+
+    let shader = SceneShader::custom(Checkerboard { squares: 12.0, light: Color::new(235, 225, 205), dark: Color::new(40, 70, 120) });
+    let scene = SceneConfig::new(vec![BodyConfig::new("Tablero", shader, MeshKind::Sphere, Vec3::zeros(), 1.0)]);
+    let meshes = load_meshes(true, None);
+
+    let mut camera = Camera::new(Vec3::new(0.0, 0.6, 5.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+    let bounds = scene_bounds(&scene.bodies(0.0), &meshes);
+    camera.frame_bounds(bounds.center, bounds.radius, FOV);
+
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    framebuffer.set_background_color(scene.background);
+    framebuffer.clear();
+    let depth_range = scene_depth_range(&scene.bodies_seen_from(0.0, camera.eye, &meshes), &meshes, &camera);
+    let uniforms = build_uniforms(&camera, 0.0, WIDTH, HEIGHT, depth_range);
+    let options = RenderOptions::default();
+    let mut state = SceneState::new(&scene, options.seed, &meshes);
+    render_scene(&mut framebuffer, 1, &scene, &uniforms, &meshes, &mut state, &options);
+
+    match save_png(&framebuffer, Path::new(&path)) {
+        Ok(()) => println!("Guardado {}", path),
+        Err(err) => {
+            eprintln!("No se pudo guardar {}: {}", path, err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,40 @@
+// Cuánto cuesta la matriz de las normales: calculada una vez por draw (BodyUniforms) contra invertirla
+// en cada vértice como antes. Transforma las normales de una esfera muchas veces y muestra el tiempo
+// de cada forma y la diferencia más grande entre sus resultados.
+// cargo run --release --example normal_matrix_bench -- [repeticiones]
+use lab4_shaders::mesh::generate_uv_sphere;
+use lab4_shaders::pipeline::{create_model_matrix, normal_matrix, BodyUniforms};
+use nalgebra_glm::{Vec3, Mat4};
+use std::hint::black_box;
+use std::time::Instant;
+
+fn main() {
+    let repetitions: usize = std::env::args().nth(1).and_then(|value| value.parse().ok()).unwrap_or(200);
+    let vertices = generate_uv_sphere(64, 128);
+    // Escala distinta en cada eje e inclinada, como un planeta achatado
+    let model: Mat4 = create_model_matrix(Vec3::new(1.0, 2.0, 3.0), Vec3::new(1.2, 1.1, 1.2), Vec3::new(0.3, 0.5, 0.2));
+
+    let start = Instant::now();
+    let mut per_vertex = Vec::with_capacity(vertices.len());
+    for _ in 0..repetitions {
+        per_vertex.clear();
+        per_vertex.extend(vertices.iter().map(|vertex| normal_matrix(black_box(&model)) * vertex.normal));
+    }
+    let per_vertex_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut per_draw = Vec::with_capacity(vertices.len());
+    for _ in 0..repetitions {
+        let body = BodyUniforms::new(*black_box(&model));
+        per_draw.clear();
+        per_draw.extend(vertices.iter().map(|vertex| body.normal_matrix() * vertex.normal));
+    }
+    let per_draw_time = start.elapsed();
+
+    let difference = per_vertex.iter().zip(&per_draw).map(|(a, b)| (a - b).amax()).fold(0.0, f32::max);
+    let count = (repetitions * vertices.len()) as f64;
+    println!("{} vértices x {} repeticiones", vertices.len(), repetitions);
+    println!("inversa por vértice: {:.1} ms ({:.1} ns por vértice)", per_vertex_time.as_secs_f64() * 1000.0, per_vertex_time.as_secs_f64() * 1e9 / count);
+    println!("una vez por draw:    {:.1} ms ({:.1} ns por vértice)", per_draw_time.as_secs_f64() * 1000.0, per_draw_time.as_secs_f64() * 1e9 / count);
+    println!("diferencia máxima entre las normales: {}", difference);
+}
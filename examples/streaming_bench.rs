@@ -0,0 +1,84 @@
+// Cuánto se gana con los fragmentos en streaming: render (cada fragmento va al sombreado apenas sale del
+// triángulo, con el RenderScratch reusado) contra juntar antes todos los fragmentos de cada triángulo en
+// un Vec como se hacía antes. Dibuja una esfera grande muchas veces con un shader de color fijo (donde
+// pesa más el costo del pipeline) y con el de la Tierra, y muestra el tiempo por frame de cada forma y
+// si las imágenes son iguales.
+// cargo run --release --example streaming_bench -- [repeticiones]
+use lab4_shaders::camera::Camera;
+use lab4_shaders::color::Color;
+use lab4_shaders::fragments::Fragments;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::mesh::generate_uv_sphere;
+use lab4_shaders::pipeline::{build_uniforms, create_model_matrix, diffuse_light, render, BodyUniforms, FragmentBudget, FrameUniforms, RenderScratch, RenderStats, DEFAULT_DEPTH_RANGE};
+use lab4_shaders::shaders::{vertex_shader, FragmentShader, ShaderType};
+use lab4_shaders::triangle::triangle;
+use lab4_shaders::vertex::Vertex;
+use nalgebra_glm::Vec3;
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 800;
+const HEIGHT: usize = 600;
+
+// Color fijo: casi todo el tiempo es rasterizar y probar la profundidad
+struct Solid;
+
+impl FragmentShader for Solid {
+    fn shade(&self, _fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+        Color::new(200, 200, 200)
+    }
+}
+
+// El camino de antes: todos los fragmentos de cada triángulo en un Vec y después el sombreado y la
+// prueba de profundidad de cada uno
+fn render_materialized(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, vertices: &[Vertex], shader: &dyn FragmentShader) {
+    framebuffer.set_current_id(body.body_id);
+    let transformed: Vec<Vertex> = vertices.iter().map(|vertex| vertex_shader(vertex, frame, body)).collect();
+    for tri in transformed.chunks_exact(3) {
+        for mut fragment in triangle(&tri[0], &tri[1], &tri[2], &frame.viewport) {
+            fragment.normal = fragment.normal.normalize();
+            fragment.light = diffuse_light(&fragment.normal, &fragment.world_pos, &frame.lights, &frame.ambient_light(&fragment.normal));
+            fragment.intensity = (fragment.light.x + fragment.light.y + fragment.light.z) / 3.0;
+            framebuffer.set_current_color(shader.shade(&fragment, frame, body));
+            framebuffer.point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth);
+        }
+    }
+}
+
+// Tiempo total de dibujar `repetitions` frames, limpiando el framebuffer antes de cada uno
+fn time_frames(framebuffer: &mut Framebuffer, repetitions: usize, mut draw: impl FnMut(&mut Framebuffer)) -> Duration {
+    let start = Instant::now();
+    for _ in 0..repetitions {
+        framebuffer.clear();
+        draw(framebuffer);
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let repetitions: usize = std::env::args().nth(1).and_then(|value| value.parse().ok()).unwrap_or(100);
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::y());
+    let frame = build_uniforms(&camera, 1.5, WIDTH, HEIGHT, DEFAULT_DEPTH_RANGE);
+    // Llena buena parte de la pantalla, con triángulos de varios pixeles como un planeta de cerca
+    let body = BodyUniforms::new(create_model_matrix(Vec3::zeros(), Vec3::repeat(1.6), Vec3::new(0.4, 0.8, 0.0)));
+    let sphere = generate_uv_sphere(64, 128);
+    println!("{} triángulos a {}x{}, {} repeticiones", sphere.len() / 3, WIDTH, HEIGHT, repetitions);
+
+    let shaders: [(&str, &dyn FragmentShader); 2] = [("color fijo", &Solid), ("tierra", &ShaderType::Earth)];
+    for (name, shader) in shaders {
+        let mut materialized = Framebuffer::new(WIDTH, HEIGHT);
+        let materialized_time = time_frames(&mut materialized, repetitions, |framebuffer| render_materialized(framebuffer, &frame, &body, &sphere, shader));
+
+        let mut streamed = Framebuffer::new(WIDTH, HEIGHT);
+        let mut scratch = RenderScratch::new();
+        let streamed_time = time_frames(&mut streamed, repetitions, |framebuffer| {
+            render(framebuffer, &frame, &body, &sphere, shader, &mut FragmentBudget::default(), &mut RenderStats::default(), &mut scratch);
+        });
+
+        let per_frame = |time: Duration| time.as_secs_f64() * 1000.0 / repetitions as f64;
+        let same = materialized.buffer == streamed.buffer && materialized.zbuffer == streamed.zbuffer;
+        println!("{}:", name);
+        println!("  juntando los fragmentos: {:.2} ms por frame", per_frame(materialized_time));
+        println!("  en streaming:            {:.2} ms por frame ({:.0} % del tiempo)", per_frame(streamed_time), 100.0 * streamed_time.as_secs_f64() / materialized_time.as_secs_f64());
+        println!("  imágenes iguales: {}", same);
+    }
+}
@@ -0,0 +1,115 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::pipeline::{normal_matrix, project, to_camera, unproject, FrameUniforms};
+use crate::scene::{body_index, Body, SceneMeshes};
+use crate::text::{draw_text_blended, text_width, GLYPH_HEIGHT};
+
+// Color de las marcas que no dicen otro
+pub const ANCHOR_COLOR: Color = Color::new(255, 214, 90);
+const SHADOW_COLOR: Color = Color::new(0, 0, 0);
+// Medio ancho del rombo y largo (en cada eje) de la línea hasta el texto, en pixeles
+const DIAMOND_RADIUS: i32 = 4;
+const LEADER_LENGTH: f32 = 14.0;
+const LABEL_GAP: f32 = 2.0;
+// Coseno entre la normal del punto y la dirección a la cámara donde la marca ya no se ve y donde se ve
+// entera: al girar hacia el borde del disco se desvanece en lugar de desaparecer de golpe
+const FACING_FADE: (f32, f32) = (0.0, 0.25);
+// Opacidad de una marca que otro cuerpo tapa
+const HIDDEN_ALPHA: f32 = 0.35;
+
+// Marca pegada a un punto de la superficie de un cuerpo: como la dirección está en el espacio del objeto,
+// gira con el cuerpo y se mueve con su órbita
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceAnchor {
+    // Cuerpo al que está pegada, como en el buffer de ids (scene::body_id)
+    pub body_id: u16,
+    // Dirección unitaria desde el centro del cuerpo, antes de escalarlo, rotarlo e inclinarlo
+    pub direction: Vec3,
+    pub label: String,
+    pub color: Color,
+}
+
+// Cuerpo dibujado en el pixel (x, y) del framebuffer y la dirección de ese punto en el espacio del cuerpo,
+// con la profundidad que quedó en el zbuffer. `model_matrices` son las de los cuerpos del frame que se
+// dibujó, en el mismo orden (scene::body_index). None sobre el fondo
+pub fn pick_surface(framebuffer: &Framebuffer, uniforms: &FrameUniforms, model_matrices: &[Mat4], x: usize, y: usize) -> Option<(usize, Vec3)> {
+    let index = framebuffer.id_at(x, y).and_then(body_index)?;
+    let inverse = model_matrices.get(index)?.try_inverse()?;
+    let (_, depth) = framebuffer.get_pixel(x, y)?;
+    let world = unproject(x as f32 + 0.5, y as f32 + 0.5, depth, uniforms);
+    let local = inverse * Vec4::new(world.x, world.y, world.z, 1.0);
+    let direction = Vec3::new(local.x, local.y, local.z).try_normalize(f32::EPSILON)?;
+    direction.iter().all(|value| value.is_finite()).then_some((index, direction))
+}
+
+// Punto de la marca en el mundo (sobre la malla sin relieve) y su normal
+fn anchor_point(anchor: &SurfaceAnchor, body: &Body, meshes: &SceneMeshes) -> (Vec3, Vec3) {
+    let model = body.model_matrix();
+    let local = anchor.direction * meshes.radius(body.mesh);
+    let point = model * Vec4::new(local.x, local.y, local.z, 1.0);
+    let normal = (normal_matrix(&model) * anchor.direction).try_normalize(f32::EPSILON).unwrap_or(anchor.direction);
+    (Vec3::new(point.x, point.y, point.z), normal)
+}
+
+// Rombo en el punto de cada marca, con una línea hasta su texto, después de dibujar la escena. Solo se
+// ven las que dan hacia la cámara, y tenues si otro cuerpo las tapa
+pub fn draw_anchors(framebuffer: &mut Framebuffer, anchors: &[SurfaceAnchor], bodies: &[Body], meshes: &SceneMeshes, uniforms: &FrameUniforms) {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+
+    for anchor in anchors {
+        let Some(body) = body_index(anchor.body_id).and_then(|index| bodies.get(index)) else {
+            continue;
+        };
+        let (point, normal) = anchor_point(anchor, body, meshes);
+        let facing = normal.dot(&to_camera(uniforms, &point));
+        let (gone, full) = FACING_FADE;
+        let mut alpha = ((facing - gone) / (full - gone)).clamp(0.0, 1.0);
+        let Some(center) = project(uniforms, point).filter(|_| alpha > 0.0) else {
+            continue;
+        };
+        if !(0.0..width).contains(&center.x) || !(0.0..height).contains(&center.y) {
+            continue;
+        }
+        let (x, y) = (center.x as usize, center.y as usize);
+        if framebuffer.id_at(x, y).is_some_and(|id| id != anchor.body_id) {
+            alpha *= HIDDEN_ALPHA;
+        }
+
+        // Rombo con borde oscuro para que se vea sobre cualquier superficie
+        for dy in -DIAMOND_RADIUS..=DIAMOND_RADIUS {
+            for dx in -DIAMOND_RADIUS..=DIAMOND_RADIUS {
+                let distance = dx.abs() + dy.abs();
+                if distance <= DIAMOND_RADIUS {
+                    let color = if distance == DIAMOND_RADIUS { SHADOW_COLOR } else { anchor.color };
+                    blend_pixel(framebuffer, x as i32 + dx, y as i32 + dy, color, alpha);
+                }
+            }
+        }
+
+        // Línea hacia arriba a la derecha desde la punta del rombo y el texto al final
+        let start = (center.x + DIAMOND_RADIUS as f32 / 2.0, center.y - DIAMOND_RADIUS as f32 / 2.0);
+        let end = (center.x + LEADER_LENGTH, center.y - LEADER_LENGTH);
+        let steps = (end.0 - start.0).abs().max((end.1 - start.1).abs()).ceil() as usize;
+        for step in 0..=steps {
+            let t = step as f32 / steps.max(1) as f32;
+            let (px, py) = (start.0 + (end.0 - start.0) * t, start.1 + (end.1 - start.1) * t);
+            blend_pixel(framebuffer, px as i32, py as i32, anchor.color, alpha);
+        }
+
+        let label_width = text_width(&anchor.label, 1) as f32;
+        let label_x = (end.0 + LABEL_GAP).clamp(0.0, (width - label_width - 1.0).max(0.0)) as usize;
+        let label_y = (end.1 - GLYPH_HEIGHT as f32 / 2.0).clamp(0.0, (height - GLYPH_HEIGHT as f32 - 1.0).max(0.0)) as usize;
+        draw_text_blended(framebuffer, label_x + 1, label_y + 1, &anchor.label, SHADOW_COLOR, 1, alpha);
+        draw_text_blended(framebuffer, label_x, label_y, &anchor.label, anchor.color, 1, alpha);
+    }
+}
+
+fn blend_pixel(framebuffer: &mut Framebuffer, x: i32, y: i32, color: Color, alpha: f32) {
+    if x < 0 || y < 0 || x as usize >= framebuffer.width || y as usize >= framebuffer.height {
+        return;
+    }
+    let pixel = &mut framebuffer.buffer[y as usize * framebuffer.width + x as usize];
+    *pixel = Color::from_hex(*pixel).lerp(&color, alpha).to_hex_with_threshold(0.5);
+}
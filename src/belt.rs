@@ -0,0 +1,73 @@
+use nalgebra_glm::{Mat4, Vec3};
+use std::f32::consts::TAU;
+use crate::pipeline::create_model_matrix;
+use crate::rng;
+
+// Cinturón de asteroides de una escena: cuántas rocas hay y en qué franja orbitan alrededor del origen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BeltConfig {
+    pub count: usize,
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub thickness: f32,     // Alto total de la franja, en unidades del mundo
+    pub seed: u32,
+}
+
+// Una roca del cinturón: órbita circular inclinada, tamaño y giro propios
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Asteroid {
+    pub radius: f32,
+    pub phase: f32,
+    pub speed: f32,         // Radianes por tick
+    pub inclination: f32,   // Altura máxima sobre el plano XZ
+    pub node: f32,          // Ángulo donde la órbita cruza el plano
+    pub scale: f32,
+    pub rotation: Vec3,
+    pub spin: Vec3,
+}
+
+// Velocidad angular en el borde interior; más afuera cae como r^-1.5 (Kepler) y cada roca varía un poco
+const BELT_INNER_SPEED: f32 = 0.0018;
+const BELT_SPEED_JITTER: f32 = 0.15;
+// Escala de las rocas (la malla mide lo mismo que la esfera, radio 0.5)
+const ASTEROID_MIN_SCALE: f32 = 0.03;
+const ASTEROID_MAX_SCALE: f32 = 0.12;
+const ASTEROID_MAX_SPIN: f32 = 0.03;
+
+impl Asteroid {
+    pub fn position(&self, time: f32) -> Vec3 {
+        let angle = self.phase + self.speed * time;
+        let height = self.inclination * (angle - self.node).sin();
+        Vec3::new(self.radius * angle.cos(), height, -self.radius * angle.sin())
+    }
+
+    pub fn model_matrix(&self, time: f32) -> Mat4 {
+        create_model_matrix(self.position(time), Vec3::repeat(self.scale), self.rotation + self.spin * time)
+    }
+}
+
+impl BeltConfig {
+    // Cada roca depende solo de la semilla y su índice
+    pub fn asteroids(&self, seed: u32) -> Vec<Asteroid> {
+        let seed = rng::combine(seed, self.seed);
+        let width = self.outer_radius - self.inner_radius;
+        (0..self.count as u32).map(|index| {
+            let asteroid_seed = rng::combine(seed, index);
+            let random = |channel: u32| rng::hash1(asteroid_seed, channel);
+            let radius = self.inner_radius + random(0) * width;
+            let jitter = 1.0 + (random(1) - 0.5) * 2.0 * BELT_SPEED_JITTER;
+            // Las rocas más chicas son más comunes
+            let size = random(5) * random(6);
+            Asteroid {
+                radius,
+                phase: random(2) * TAU,
+                speed: BELT_INNER_SPEED * (self.inner_radius / radius).powf(1.5) * jitter,
+                inclination: (random(3) - 0.5) * self.thickness,
+                node: random(4) * TAU,
+                scale: ASTEROID_MIN_SCALE + size * (ASTEROID_MAX_SCALE - ASTEROID_MIN_SCALE),
+                rotation: Vec3::new(random(7), random(8), random(9)) * TAU,
+                spin: (Vec3::new(random(10), random(11), random(12)) - Vec3::repeat(0.5)) * 2.0 * ASTEROID_MAX_SPIN,
+            }
+        }).collect()
+    }
+}
@@ -0,0 +1,248 @@
+use serde::Serialize;
+use std::path::Path;
+use std::time::Duration;
+
+// Versión del formato del reporte; cambia solo si se quitan o renombran campos, así los scripts que
+// comparan reportes de distintos commits pueden revisarla
+pub const REPORT_VERSION: u32 = 1;
+// Bordes (en ms) del histograma de tiempos de frame: la última cubeta es todo lo que pasa el último borde
+pub const HISTOGRAM_EDGES_MS: [f64; 10] = [2.0, 4.0, 8.0, 12.0, 16.7, 25.0, 33.3, 50.0, 100.0, 250.0];
+
+// Tiempos de una etapa en todos los frames medidos. Se guardan todos (el modo de medición tiene un
+// número fijo de frames), así los percentiles son exactos y no dependen del orden
+#[derive(Clone, Debug, Default)]
+pub struct Samples {
+    values_ms: Vec<f64>,
+    sorted: bool,
+}
+
+impl Samples {
+    pub fn push(&mut self, duration: Duration) {
+        self.push_ms(duration.as_secs_f64() * 1000.0);
+    }
+
+    pub fn push_ms(&mut self, value: f64) {
+        self.values_ms.push(value);
+        self.sorted = false;
+    }
+
+    pub fn len(&self) -> usize {
+        self.values_ms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values_ms.is_empty()
+    }
+
+    // Percentil de 0 a 1 interpolando entre las dos muestras vecinas (la mediana de una cantidad par
+    // es el promedio de las dos del medio); 0 sin muestras
+    pub fn percentile(&mut self, fraction: f64) -> f64 {
+        if self.values_ms.is_empty() {
+            return 0.0;
+        }
+        if !self.sorted {
+            self.values_ms.sort_by(f64::total_cmp);
+            self.sorted = true;
+        }
+        let position = fraction.clamp(0.0, 1.0) * (self.values_ms.len() - 1) as f64;
+        let low = position.floor() as usize;
+        let high = (low + 1).min(self.values_ms.len() - 1);
+        self.values_ms[low] + (self.values_ms[high] - self.values_ms[low]) * (position - low as f64)
+    }
+
+    pub fn summary(&mut self) -> StageSummary {
+        let mean_ms = if self.values_ms.is_empty() { 0.0 } else { self.values_ms.iter().sum::<f64>() / self.values_ms.len() as f64 };
+        StageSummary {
+            min_ms: self.percentile(0.0),
+            median_ms: self.percentile(0.5),
+            p95_ms: self.percentile(0.95),
+            max_ms: self.percentile(1.0),
+            mean_ms,
+        }
+    }
+}
+
+// Etapas medidas en cada frame: el frame completo (sin esperar a la ventana), render_scene, las tres
+// etapas de dentro del pipeline sumadas en todos los cuerpos y lo que se hace con la imagen terminada
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct Stages<T> {
+    pub frame: T,
+    pub scene: T,
+    pub vertex: T,
+    pub raster: T,
+    pub fragment: T,
+    pub post: T,
+}
+
+impl<T> Stages<T> {
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> Stages<U> {
+        Stages {
+            frame: f(self.frame),
+            scene: f(self.scene),
+            vertex: f(self.vertex),
+            raster: f(self.raster),
+            fragment: f(self.fragment),
+            post: f(self.post),
+        }
+    }
+
+    fn each_mut<U>(&mut self, other: Stages<U>, mut f: impl FnMut(&mut T, U)) {
+        f(&mut self.frame, other.frame);
+        f(&mut self.scene, other.scene);
+        f(&mut self.vertex, other.vertex);
+        f(&mut self.raster, other.raster);
+        f(&mut self.fragment, other.fragment);
+        f(&mut self.post, other.post);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize)]
+pub struct StageSummary {
+    pub min_ms: f64,
+    pub median_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+}
+
+// Cantidad de frames en cada cubeta de HISTOGRAM_EDGES_MS: `counts[i]` son los que tardaron menos que
+// `edges_ms[i]` (y al menos el borde anterior) y el último los que tardaron eso o más
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Histogram {
+    pub edges_ms: Vec<f64>,
+    pub counts: Vec<u32>,
+}
+
+impl Histogram {
+    pub fn new() -> Self {
+        Histogram { edges_ms: HISTOGRAM_EDGES_MS.to_vec(), counts: vec![0; HISTOGRAM_EDGES_MS.len() + 1] }
+    }
+
+    pub fn add(&mut self, value_ms: f64) {
+        let bucket = self.edges_ms.iter().position(|&edge| value_ms < edge).unwrap_or(self.edges_ms.len());
+        self.counts[bucket] += 1;
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram::new()
+    }
+}
+
+// Escena que se dibujó desde un frame (numerados desde 1) y cuántos frames seguidos
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct SceneSpan {
+    pub scene: u32,
+    pub first_frame: u32,
+    pub frames: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Resolution {
+    pub width: usize,
+    pub height: usize,
+}
+
+// Lo que cambia el costo de un frame además de la escena, para no comparar reportes de corridas distintas
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RunSettings {
+    // De dónde salió la entrada: "teclado", "demo" o el archivo de --replay-input
+    pub input: String,
+    pub fixed_quality: bool,
+    pub target_fps: u32,
+    pub procedural: bool,
+    pub dither: bool,
+    pub toon: bool,
+    pub ambient_occlusion: bool,
+    pub particle_rings: bool,
+    pub adaptive_ring: bool,
+    pub progressive: bool,
+    pub checkerboard: bool,
+    pub fragment_budget: usize,
+    pub threads: usize,
+    pub seed: u32,
+}
+
+// Frames dibujados con cada escalón de la calidad automática
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TierFrames {
+    pub tier: String,
+    pub frames: u32,
+}
+
+// Reporte de --bench-exit. Los campos y su orden son fijos; los tiempos están en milisegundos
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BenchReport {
+    pub version: u32,
+    // Vueltas del bucle y cuántas se dibujaron completas (las demás no cambiaban la imagen)
+    pub frames: u32,
+    pub measured_frames: u32,
+    pub resolution: Resolution,
+    pub settings: RunSettings,
+    pub quality_tiers: Vec<TierFrames>,
+    pub scenes: Vec<SceneSpan>,
+    pub stages: Stages<StageSummary>,
+    pub histogram: Histogram,
+}
+
+impl BenchReport {
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        json.push('\n');
+        std::fs::write(path, json).map_err(|err| err.to_string())
+    }
+}
+
+// Junta los tiempos de cada frame mientras corre el bucle principal
+#[derive(Clone, Debug, Default)]
+pub struct BenchRecorder {
+    frames: u32,
+    stages: Stages<Samples>,
+    histogram: Histogram,
+    scenes: Vec<SceneSpan>,
+    tiers: Vec<TierFrames>,
+}
+
+impl BenchRecorder {
+    pub fn new() -> Self {
+        BenchRecorder::default()
+    }
+
+    // Una vuelta del bucle en esa escena, se haya dibujado o no
+    pub fn frame(&mut self, scene: u32) {
+        self.frames += 1;
+        match self.scenes.last_mut() {
+            Some(span) if span.scene == scene => span.frames += 1,
+            _ => self.scenes.push(SceneSpan { scene, first_frame: self.frames, frames: 1 }),
+        }
+    }
+
+    // Tiempos de un frame dibujado completo y el escalón de calidad con que se dibujó
+    pub fn measure(&mut self, times: Stages<Duration>, tier: &str) {
+        self.histogram.add(times.frame.as_secs_f64() * 1000.0);
+        self.stages.each_mut(times, Samples::push);
+        match self.tiers.iter_mut().find(|entry| entry.tier == tier) {
+            Some(entry) => entry.frames += 1,
+            None => self.tiers.push(TierFrames { tier: tier.to_string(), frames: 1 }),
+        }
+    }
+
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    pub fn report(self, resolution: Resolution, settings: RunSettings) -> BenchReport {
+        BenchReport {
+            version: REPORT_VERSION,
+            frames: self.frames,
+            measured_frames: self.stages.frame.len() as u32,
+            resolution,
+            settings,
+            quality_tiers: self.tiers,
+            scenes: self.scenes,
+            stages: self.stages.map(|mut samples| samples.summary()),
+            histogram: self.histogram,
+        }
+    }
+}
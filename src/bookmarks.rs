@@ -0,0 +1,118 @@
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use lab4_shaders::camera::{Camera, Projection};
+
+// Archivo con las cámaras guardadas, en el directorio desde donde se corre el programa
+pub const BOOKMARKS_FILE: &str = "bookmarks.toml";
+// Marcadores: Ctrl + 1 a 9 guardan, Shift + 1 a 9 vuelven
+pub const BOOKMARK_SLOTS: usize = 9;
+
+// Cámara guardada en un marcador, con la escena donde se guardó
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bookmark {
+    pub scene: u32,
+    pub eye: [f32; 3],
+    pub center: [f32; 3],
+    pub up: [f32; 3],
+    // Alto visible de la proyección ortográfica; sin él es en perspectiva
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub orthographic_height: Option<f32>,
+}
+
+impl Bookmark {
+    pub fn new(scene: u32, camera: &Camera) -> Self {
+        Bookmark {
+            scene,
+            eye: camera.eye.into(),
+            center: camera.center.into(),
+            up: camera.up.into(),
+            orthographic_height: match camera.projection {
+                Projection::Perspective => None,
+                Projection::Orthographic { height } => Some(height),
+            },
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        let mut camera = Camera::new(Vec3::from(self.eye), Vec3::from(self.center), Vec3::from(self.up));
+        if let Some(height) = self.orthographic_height {
+            camera.projection = Projection::Orthographic { height };
+        }
+        camera
+    }
+}
+
+// Cada marcador es una tabla con el número como nombre ([1], [2]...); los vacíos no aparecen
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct BookmarkFile {
+    slots: BTreeMap<String, Bookmark>,
+}
+
+pub struct Bookmarks {
+    slots: [Option<Bookmark>; BOOKMARK_SLOTS],
+    path: PathBuf,
+}
+
+impl Bookmarks {
+    // Sin archivo empiezan vacíos; si el archivo no se puede leer se renombra a `.bak` con un aviso, así
+    // el programa arranca igual y los marcadores viejos no se pierden al guardar uno nuevo
+    pub fn load(path: &Path) -> Self {
+        let mut bookmarks = Bookmarks { slots: [None; BOOKMARK_SLOTS], path: path.to_path_buf() };
+        if !path.exists() {
+            return bookmarks;
+        }
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|source| toml::from_str::<BookmarkFile>(&source).map_err(|err| err.to_string()))
+            .and_then(|file| bookmarks.fill(file));
+        if let Err(message) = parsed {
+            let aside = path.with_extension("toml.bak");
+            match std::fs::rename(path, &aside) {
+                Ok(()) => eprintln!("{}: {} (se movió a {})", path.display(), message, aside.display()),
+                Err(err) => eprintln!("{}: {} (no se pudo mover: {})", path.display(), message, err),
+            }
+            bookmarks.slots = [None; BOOKMARK_SLOTS];
+        }
+        bookmarks
+    }
+
+    fn fill(&mut self, file: BookmarkFile) -> Result<(), String> {
+        for (name, bookmark) in file.slots {
+            let slot = name.parse::<usize>().ok()
+                .filter(|slot| (1..=BOOKMARK_SLOTS).contains(slot))
+                .ok_or_else(|| format!("marcador desconocido: [{}] (van de 1 a {})", name, BOOKMARK_SLOTS))?;
+            self.slots[slot - 1] = Some(bookmark);
+        }
+        Ok(())
+    }
+
+    // Marcador del 1 al 9; None si está vacío
+    pub fn get(&self, slot: usize) -> Option<Bookmark> {
+        self.slots.get(slot.wrapping_sub(1)).copied().flatten()
+    }
+
+    // Guarda y escribe el archivo enseguida
+    pub fn set(&mut self, slot: usize, bookmark: Bookmark) {
+        let Some(entry) = self.slots.get_mut(slot.wrapping_sub(1)) else {
+            return;
+        };
+        *entry = Some(bookmark);
+        if let Err(err) = self.save() {
+            eprintln!("No se pudieron guardar los marcadores en {}: {}", self.path.display(), err);
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        let file = BookmarkFile {
+            slots: self.slots.iter().enumerate()
+                .filter_map(|(index, bookmark)| bookmark.map(|bookmark| ((index + 1).to_string(), bookmark)))
+                .collect(),
+        };
+        let source = toml::to_string(&file).map_err(|err| err.to_string())?;
+        std::fs::write(&self.path, source).map_err(|err| err.to_string())
+    }
+}
@@ -1,10 +1,21 @@
-use nalgebra_glm::{Vec3, rotate_vec3};
-use std::f32::consts::PI;
+use nalgebra_glm::{Mat3, Quat, Vec3, mat3_to_quat, quat_angle_axis, quat_dot, quat_normalize, quat_rotate_vec3, quat_slerp, rotate_vec3};
 
+// Proyección de la cámara; en la ortográfica el tamaño de las cosas no depende de la distancia
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+  Perspective,
+  Orthographic { height: f32 },  // Alto visible en unidades del mundo
+}
+
+// Alto mínimo de la vista ortográfica al hacer zoom
+const MIN_ORTHO_HEIGHT: f32 = 0.05;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Camera {
   pub eye: Vec3,
   pub center: Vec3,
   pub up: Vec3,
+  pub projection: Projection,
   pub has_changed: bool
 }
 
@@ -14,35 +25,62 @@ impl Camera {
       eye,
       center,
       up,
+      projection: Projection::Perspective,
       has_changed: true,
     }
   }
 
-  pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
-    let radius_vector = self.eye - self.center;
-    let radius = radius_vector.magnitude();
-
-    let current_yaw = radius_vector.z.atan2(radius_vector.x);
-
-    let radius_xz = (radius_vector.x * radius_vector.x + radius_vector.z * radius_vector.z).sqrt();
-    let current_pitch = (-radius_vector.y).atan2(radius_xz);
-
-    let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
-    let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+  // Orientación de la cámara: lleva el +z de la cámara a la dirección del centro al ojo y el +y a `up`
+  // (corregido para que quede perpendicular a la vista)
+  pub fn orientation(&self) -> Quat {
+    look_orientation(self.eye - self.center, self.up)
+  }
 
-    let new_eye = self.center + Vec3::new(
-      radius * new_yaw.cos() * new_pitch.cos(),
-      -radius * new_pitch.sin(),
-      radius * new_yaw.sin() * new_pitch.cos()
-    );
+  // Gira el ojo alrededor del centro con la orientación como cuaternión, sin límite en los polos: el
+  // giro horizontal es alrededor del eje vertical del mundo (del lado hacia donde apunta `up`, así
+  // cabeza abajo los controles no se invierten) y el vertical alrededor de la derecha de la cámara.
+  // Ninguno de los dos ladea la derecha de la cámara, así que el horizonte no rueda
+  pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
+    let radius = (self.eye - self.center).magnitude();
+    let vertical = if self.up.y < 0.0 { -Vec3::y() } else { Vec3::y() };
+    let yaw = quat_angle_axis(-delta_yaw, &vertical);
+    let pitch = quat_angle_axis(delta_pitch, &Vec3::x());
+    let orientation = quat_normalize(&(yaw * self.orientation() * pitch));
 
-    self.eye = new_eye;
+    self.eye = self.center + quat_rotate_vec3(&orientation, &Vec3::z()) * radius;
+    self.up = quat_rotate_vec3(&orientation, &Vec3::y());
     self.has_changed = true;
   }
 
+  // En la ortográfica acercar el ojo no cambia nada, así que se achica el alto visible
+  // en la misma proporción en que se acortaría la distancia al centro
   pub fn zoom(&mut self, delta: f32) {
-    let direction = (self.center - self.eye).normalize();
-    self.eye += direction * delta;
+    match self.projection {
+      Projection::Perspective => {
+        let direction = (self.center - self.eye).normalize();
+        self.eye += direction * delta;
+      }
+      Projection::Orthographic { height } => {
+        let distance = (self.center - self.eye).magnitude();
+        let scale = ((distance - delta) / distance).max(0.5);
+        self.projection = Projection::Orthographic { height: (height * scale).max(MIN_ORTHO_HEIGHT) };
+      }
+    }
+    self.has_changed = true;
+  }
+
+  // Cambia entre perspectiva y ortográfica sin que el centro cambie de tamaño en pantalla:
+  // el alto ortográfico es el que se ve a la distancia del centro y al volver se mueve el ojo a esa distancia
+  pub fn toggle_projection(&mut self, fov: f32) {
+    let offset = self.eye - self.center;
+    let half_tan = (fov / 2.0).tan();
+    self.projection = match self.projection {
+      Projection::Perspective => Projection::Orthographic { height: 2.0 * offset.magnitude() * half_tan },
+      Projection::Orthographic { height } => {
+        self.eye = self.center + offset.normalize() * (height / (2.0 * half_tan));
+        Projection::Perspective
+      }
+    };
     self.has_changed = true;
   }
 
@@ -61,4 +99,143 @@ impl Camera {
     self.center = self.eye + final_rotated.normalize() * radius;
     self.has_changed = true;
   }
+
+  pub fn focus_on(&mut self, target: Vec3) {
+    let offset = target - self.center;
+    self.center = target;
+    self.eye += offset;
+    self.has_changed = true;
+  }
+
+  // Aleja o acerca la cámara sobre su dirección actual para que la esfera quepa en el FOV vertical
+  // Ojos izquierdo y derecho del modo estéreo, separados perpendicular a la vista y mirando al mismo centro.
+  // La separación es una fracción de la distancia al centro, así el efecto no cambia al hacer zoom
+  pub fn stereo_eyes(&self, separation: f32) -> (Vec3, Vec3) {
+    let offset = self.eye - self.center;
+    let right = (-offset).cross(&self.up);
+    if right.magnitude() < f32::EPSILON {
+      return (self.eye, self.eye);
+    }
+    let half = right.normalize() * (separation * offset.magnitude() / 2.0);
+    (self.eye - half, self.eye + half)
+  }
+
+  pub fn frame_bounds(&mut self, center: Vec3, radius: f32, fov: f32) {
+    let margin = 1.1;
+    let offset = self.eye - self.center;
+    let direction = if offset.magnitude() > f32::EPSILON {
+      offset.normalize()
+    } else {
+      Vec3::new(0.0, 0.0, 1.0)
+    };
+
+    let distance = radius * margin / (fov / 2.0).sin();
+
+    self.center = center;
+    self.eye = center + direction * distance;
+    if let Projection::Orthographic { .. } = self.projection {
+      self.projection = Projection::Orthographic { height: 2.0 * radius * margin };
+    }
+    self.has_changed = true;
+  }
+}
+
+// Pose de la cámara en un instante de un recorrido (segundos desde el inicio)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+  pub time: f32,
+  pub eye: Vec3,
+  pub center: Vec3,
+}
+
+// Cuaternión que lleva el +z a `back` y el +y a `up` (los dos en el mundo); si son paralelos se
+// usa cualquier perpendicular como arriba
+pub fn look_orientation(back: Vec3, up: Vec3) -> Quat {
+  let z = back.try_normalize(f32::EPSILON).unwrap_or(Vec3::z());
+  let x = up.cross(&z).try_normalize(f32::EPSILON)
+    .or_else(|| Vec3::y().cross(&z).try_normalize(f32::EPSILON))
+    .unwrap_or(Vec3::x());
+  let y = z.cross(&x);
+  quat_normalize(&mat3_to_quat(&Mat3::from_columns(&[x, y, z])))
+}
+
+// Logaritmo de un cuaternión unitario: el eje por la mitad del ángulo (0 sin giro, donde el de
+// nalgebra da NaN)
+fn rotation_log(q: &Quat) -> Vec3 {
+  let vector = q.imag();
+  let sin = vector.magnitude();
+  if sin < f32::EPSILON {
+    return vector;
+  }
+  vector / sin * sin.atan2(q.w)
+}
+
+fn rotation_exp(v: &Vec3) -> Quat {
+  let angle = v.magnitude();
+  if angle < f32::EPSILON {
+    return quat_normalize(&Quat::from_parts(1.0, *v));
+  }
+  Quat::from_parts(angle.cos(), v / angle * angle.sin())
+}
+
+// Interpolación esférica cúbica (squad) entre `q1` y `q2` con las tangentes de sus vecinos: pasa por
+// cada pose sin cambios bruscos de velocidad, igual que Catmull-Rom pero sobre las rotaciones
+fn squad(q0: &Quat, q1: &Quat, q2: &Quat, q3: &Quat, t: f32) -> Quat {
+  let inner = |previous: &Quat, current: &Quat, next: &Quat| {
+    let inverse = current.conjugate();
+    let tangent = (rotation_log(&(inverse * next)) + rotation_log(&(inverse * previous))) * -0.25;
+    current * rotation_exp(&tangent)
+  };
+  let (s1, s2) = (inner(q0, q1, q2), inner(q1, q2, q3));
+  quat_slerp(&quat_slerp(q1, q2, t), &quat_slerp(&s1, &s2, t), 2.0 * t * (1.0 - t))
+}
+
+fn catmull_rom(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+  let t2 = t * t;
+  let t3 = t2 * t;
+  0.5 * (2.0 * p1 + (p2 - p0) * t + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2 + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+// Ojo y centro en el instante `time` de un recorrido: curva suave que pasa por cada pose. La dirección
+// del ojo desde el centro se interpola como rotación (squad), así dos poses de una órbita dan un arco y
+// no una recta, y pasar cerca de los polos no hace girar la vista de golpe
+pub fn sample_path(keyframes: &[Keyframe], time: f32) -> Option<(Vec3, Vec3)> {
+  let first = keyframes.first()?;
+  if keyframes.len() == 1 || time <= first.time {
+    return Some((first.eye, first.center));
+  }
+  let last = keyframes.last()?;
+  if time >= last.time {
+    return Some((last.eye, last.center));
+  }
+
+  // q y -q son la misma rotación: se elige la que queda del mismo lado que la anterior para tomar el camino corto
+  let mut rotations: Vec<Quat> = Vec::with_capacity(keyframes.len());
+  for keyframe in keyframes {
+    let rotation = look_orientation(keyframe.eye - keyframe.center, Vec3::y());
+    let flip = rotations.last().is_some_and(|previous| quat_dot(previous, &rotation) < 0.0);
+    rotations.push(if flip { -rotation } else { rotation });
+  }
+  let points: Vec<(f32, Vec3)> = keyframes.iter()
+    .map(|keyframe| ((keyframe.eye - keyframe.center).magnitude(), keyframe.center))
+    .collect();
+
+  let segment = keyframes.windows(2).position(|pair| time < pair[1].time).unwrap_or(keyframes.len() - 2);
+  let span = (keyframes[segment + 1].time - keyframes[segment].time).max(f32::EPSILON);
+  let t = (time - keyframes[segment].time) / span;
+  let clamp = |index: isize| index.clamp(0, points.len() as isize - 1) as usize;
+  let i = segment as isize;
+  let indices = [clamp(i - 1), clamp(i), clamp(i + 1), clamp(i + 2)];
+  let [p0, p1, p2, p3] = indices.map(|index| points[index]);
+  let [q0, q1, q2, q3] = indices.map(|index| rotations[index]);
+
+  let distance = catmull_rom(p0.0, p1.0, p2.0, p3.0, t).max(f32::EPSILON);
+  let center = Vec3::new(
+    catmull_rom(p0.1.x, p1.1.x, p2.1.x, p3.1.x, t),
+    catmull_rom(p0.1.y, p1.1.y, p2.1.y, p3.1.y, t),
+    catmull_rom(p0.1.z, p1.1.z, p2.1.z, p3.1.z, t),
+  );
+  let rotation = squad(&q0, &q1, &q2, &q3, t);
+  let eye = center + quat_rotate_vec3(&rotation, &Vec3::z()) * distance;
+  Some((eye, center))
 }
@@ -0,0 +1,118 @@
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::camera::{sample_path, Camera, Keyframe};
+
+// Archivo donde se guarda el recorrido grabado con K (Ctrl+K), en el directorio desde donde se corre el programa
+pub const CAMERA_PATH_FILE: &str = "path.toml";
+// Separación mínima entre dos poses, en ticks: las que caen en el mismo instante (grabadas con el tiempo en
+// pausa) se corren esto para que la curva no tenga un tramo de duración cero
+pub const MIN_KEYFRAME_GAP: f32 = 1.0;
+
+// Recorrido de la cámara armado a mano: poses en instantes del reloj de la simulación (ticks, no segundos
+// como el de la demo), así al adelantar o atrasar el tiempo la cámara va con él. Siempre tiene al menos dos
+// poses, ordenadas y separadas por MIN_KEYFRAME_GAP
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraPath {
+    keyframes: Vec<Keyframe>,
+    // Al pasar la última pose vuelve a la primera; si no, se queda en la última (y antes de la primera, en ella)
+    pub looping: bool,
+}
+
+// Formato del archivo: `loop = true` (opcional) y una tabla [[keyframe]] por pose
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraPathFile {
+    #[serde(default, rename = "loop")]
+    looping: bool,
+    #[serde(default, rename = "keyframe")]
+    keyframes: Vec<KeyframeFile>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeFile {
+    time: f32,
+    eye: [f32; 3],
+    center: [f32; 3],
+}
+
+impl CameraPath {
+    // Las poses se ordenan por tiempo (grabadas con el tiempo hacia atrás quedan al revés) y las repetidas
+    // se corren; con menos de dos no hay recorrido
+    pub fn new(mut keyframes: Vec<Keyframe>, looping: bool) -> Result<Self, String> {
+        if keyframes.len() < 2 {
+            return Err(format!("un recorrido necesita al menos 2 poses y hay {}", keyframes.len()));
+        }
+        if let Some(keyframe) = keyframes.iter().find(|keyframe| !keyframe.time.is_finite() || !keyframe.eye.iter().chain(keyframe.center.iter()).all(|value| value.is_finite())) {
+            return Err(format!("pose con valores inválidos en el tiempo {}", keyframe.time));
+        }
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        for index in 1..keyframes.len() {
+            keyframes[index].time = keyframes[index].time.max(keyframes[index - 1].time + MIN_KEYFRAME_GAP);
+        }
+        Ok(CameraPath { keyframes, looping })
+    }
+
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    // Instantes de la primera y la última pose
+    pub fn start(&self) -> f32 {
+        self.keyframes[0].time
+    }
+
+    pub fn end(&self) -> f32 {
+        self.keyframes[self.keyframes.len() - 1].time
+    }
+
+    // Ojo y centro en `time`, con la curva de camera::sample_path (Catmull-Rom para el centro y la distancia,
+    // squad para la dirección de la vista)
+    pub fn pose(&self, time: f32) -> (Vec3, Vec3) {
+        let time = if self.looping {
+            self.start() + (time - self.start()).rem_euclid(self.end() - self.start())
+        } else {
+            time
+        };
+        sample_path(&self.keyframes, time).unwrap_or((self.keyframes[0].eye, self.keyframes[0].center))
+    }
+
+    // Pone la cámara en la pose de `time`; solo la marca como cambiada si se movió, así con el tiempo en
+    // pausa la imagen queda quieta
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        let (eye, center) = self.pose(time);
+        if eye != camera.eye || center != camera.center {
+            camera.eye = eye;
+            camera.center = center;
+            camera.has_changed = true;
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let file: CameraPathFile = toml::from_str(source).map_err(|err| err.to_string())?;
+        let keyframes = file.keyframes.into_iter()
+            .map(|keyframe| Keyframe { time: keyframe.time, eye: Vec3::from(keyframe.eye), center: Vec3::from(keyframe.center) })
+            .collect();
+        Self::new(keyframes, file.looping)
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        let file = CameraPathFile {
+            looping: self.looping,
+            keyframes: self.keyframes.iter()
+                .map(|keyframe| KeyframeFile { time: keyframe.time, eye: keyframe.eye.into(), center: keyframe.center.into() })
+                .collect(),
+        };
+        toml::to_string(&file).map_err(|err| err.to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::parse(&source)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        std::fs::write(path, self.to_toml()?).map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,132 @@
+use std::time::Duration;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Peso de cada frame nuevo en el promedio de los tiempos de sombreado
+const TIME_SMOOTHING: f32 = 0.1;
+
+// Con la paridad `parity` de FrameUniforms::checkerboard se sombrean los pixeles con x + y + parity par
+pub fn is_shaded(x: usize, y: usize, parity: u32) -> bool {
+    (x + y + parity as usize).is_multiple_of(2)
+}
+
+// De dónde salieron los pixeles que no se sombrearon en el último frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckerFill {
+    // Del frame anterior, con la cámara quieta: dos frames seguidos dan la imagen completa
+    Previous,
+    // Promedio de los cuatro vecinos, que sí se sombrearon
+    Neighbors,
+}
+
+// Renderizado en tablero de ajedrez: cada frame sombrea solo la mitad de los pixeles, alternando cuál
+// mitad (ver parity), y resolve completa la otra antes del post-proceso. Guarda el frame resuelto
+// (color, profundidad e ids) para completar el siguiente
+pub struct Checkerboard {
+    frame: u32,
+    buffer: Vec<u32>,
+    zbuffer: Vec<f32>,
+    ids: Vec<u16>,
+    // Tiempo de sombreado promedio en milisegundos sin el tablero y con él
+    full_ms: Option<f32>,
+    checker_ms: Option<f32>,
+}
+
+impl Checkerboard {
+    pub fn new() -> Self {
+        Checkerboard { frame: 0, buffer: Vec::new(), zbuffer: Vec::new(), ids: Vec::new(), full_ms: None, checker_ms: None }
+    }
+
+    // Olvida el frame anterior (al cambiar de escena o de tamaño): el siguiente se completa con los vecinos
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.zbuffer.clear();
+        self.ids.clear();
+    }
+
+    // Mitad que se sombrea en este frame
+    pub fn parity(&self) -> u32 {
+        self.frame % 2
+    }
+
+    // Completa los pixeles que no se sombrearon con la paridad de este frame y pasa al siguiente.
+    // `camera_moved` elige entre el frame anterior y los vecinos; sin frame anterior del mismo tamaño
+    // siempre se usan los vecinos
+    pub fn resolve(&mut self, framebuffer: &mut Framebuffer, camera_moved: bool) -> CheckerFill {
+        let parity = self.parity();
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let fill = if !camera_moved && self.buffer.len() == framebuffer.buffer.len() { CheckerFill::Previous } else { CheckerFill::Neighbors };
+
+        for y in 0..height {
+            for x in (0..width).filter(|&x| !is_shaded(x, y, parity)) {
+                let index = y * width + x;
+                if fill == CheckerFill::Previous {
+                    framebuffer.buffer[index] = self.buffer[index];
+                    framebuffer.zbuffer[index] = self.zbuffer[index];
+                    framebuffer.ids[index] = self.ids[index];
+                    continue;
+                }
+                let mut sum = (0.0, 0.0, 0.0);
+                let mut count = 0.0;
+                let mut nearest = None;
+                for neighbor in neighbors(x, y, width, height).into_iter().flatten() {
+                    let color = Color::from_hex(framebuffer.buffer[neighbor]);
+                    sum = (sum.0 + color.r, sum.1 + color.g, sum.2 + color.b);
+                    count += 1.0;
+                    if nearest.is_none_or(|best: usize| framebuffer.zbuffer[neighbor] < framebuffer.zbuffer[best]) {
+                        nearest = Some(neighbor);
+                    }
+                }
+                // La profundidad y el cuerpo del vecino más cercano, así el pixel no queda a medio camino
+                // entre un cuerpo y el fondo
+                if let Some(nearest) = nearest {
+                    framebuffer.buffer[index] = Color { r: sum.0 / count, g: sum.1 / count, b: sum.2 / count }.to_hex_with_threshold(0.5);
+                    framebuffer.zbuffer[index] = framebuffer.zbuffer[nearest];
+                    framebuffer.ids[index] = framebuffer.ids[nearest];
+                }
+            }
+        }
+
+        self.buffer.clone_from(&framebuffer.buffer);
+        self.zbuffer.clone_from(&framebuffer.zbuffer);
+        self.ids.clone_from(&framebuffer.ids);
+        self.frame = self.frame.wrapping_add(1);
+        fill
+    }
+
+    // Tiempo de la etapa de fragmentos de un frame dibujado con el tablero o sin él
+    pub fn record_shading(&mut self, time: Duration, checkerboard: bool) {
+        let average = if checkerboard { &mut self.checker_ms } else { &mut self.full_ms };
+        let ms = time.as_secs_f32() * 1000.0;
+        *average = Some(average.map_or(ms, |previous| previous + (ms - previous) * TIME_SMOOTHING));
+    }
+
+    // Tiempo de sombreado promedio con el tablero y sin él (si ya se midió), en milisegundos
+    pub fn shading_ms(&self) -> (Option<f32>, Option<f32>) {
+        (self.checker_ms, self.full_ms)
+    }
+
+    // Fracción del tiempo de sombreado que se ahorra con el tablero
+    pub fn reduction(&self) -> Option<f32> {
+        match (self.checker_ms, self.full_ms) {
+            (Some(checker), Some(full)) if full > 0.0 => Some(1.0 - checker / full),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Checkerboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Índices de los vecinos de arriba, abajo, izquierda y derecha que están dentro de la imagen
+fn neighbors(x: usize, y: usize, width: usize, height: usize) -> [Option<usize>; 4] {
+    [
+        (x > 0).then(|| y * width + x - 1),
+        (x + 1 < width).then(|| y * width + x + 1),
+        (y > 0).then(|| (y - 1) * width + x),
+        (y + 1 < height).then(|| (y + 1) * width + x),
+    ]
+}
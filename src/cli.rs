@@ -0,0 +1,333 @@
+use std::path::PathBuf;
+use lab4_shaders::shaders::ShaderType;
+use lab4_shaders::quality::DEFAULT_TARGET_FPS;
+use lab4_shaders::session::CommandLineOptions;
+
+pub const USAGE: &str = "Uso: lab4-shaders [--procedural] [--dither] [--toon] [--graticule] [--particle-rings] [--adaptive-ring] [--ssao] [--starfield] [--thermal] [--cull-backfaces] [--saturn-rings] [--progressive [--freeze-idle]] [--checkerboard] [--demo] [--camera-path FILE.toml] [--model FILE.obj [--model-shader NAME]] [--scene-file FILE.toml] [--lut FILE.cube|FILE.csv] [--session FILE.toml | --no-session] [--seed N] [--fragment-budget N] [--threads N] [--target-fps N | --fixed-quality] [--record --scene N --frames N --fps N --out-dir DIR [--orbit] [--debug-mode N] [--accumulate N] [--dump-raw FILE.fbdump]] [--export-mesh FILE.ply [--scene N]] [--cubemap --at-body NAME [--scene N] [--size N] [--out PREFIX]] [--record-input FILE.log] [--replay-input FILE.log [--headless --out-dir DIR]] [--bench-exit N [--bench-out FILE.json]] [--diff OLD.png NEW.png OUT.png] [--check-shaders]";
+
+pub struct RecordOptions {
+    pub scene: u32,
+    pub frames: u32,
+    pub fps: u32,
+    pub out_dir: PathBuf,
+    pub orbit: bool,
+    pub debug_mode: u32,
+    // Imágenes movidas una fracción de pixel que se promedian en cada frame (1 = sin acumular)
+    pub accumulate: u32,
+    // Color y profundidad sin pérdida de cada frame además del PNG
+    pub dump_raw: Option<PathBuf>,
+}
+
+// Exportar la geometría de una escena sin abrir la ventana
+pub struct ExportOptions {
+    pub scene: u32,
+    pub path: PathBuf,
+}
+
+// Las seis caras del cielo vistas desde el centro de un cuerpo, guardadas como PREFIX_posx.png, etc.
+pub struct CubemapOptions {
+    pub scene: u32,
+    pub body: String,
+    pub size: usize,
+    pub prefix: PathBuf,
+}
+
+// Repetir una sesión grabada con --record-input
+pub struct ReplayOptions {
+    pub path: PathBuf,
+    // Sin ventana: cada frame se guarda como PNG en este directorio y se sale al terminar
+    pub headless_out_dir: Option<PathBuf>,
+}
+
+// Medir los tiempos de los primeros frames del bucle normal, guardar el reporte y salir
+pub struct BenchOptions {
+    pub frames: u32,
+    pub out: PathBuf,
+}
+
+// Comparar dos capturas sin abrir la ventana y guardar la vista de diferencias
+pub struct DiffOptions {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    pub out: PathBuf,
+}
+
+pub struct Args {
+    pub record: Option<RecordOptions>,
+    pub export_mesh: Option<ExportOptions>,
+    pub cubemap: Option<CubemapOptions>,
+    // Guardar la entrada de cada frame en este archivo
+    pub record_input: Option<PathBuf>,
+    pub replay: Option<ReplayOptions>,
+    pub bench: Option<BenchOptions>,
+    pub procedural: bool,
+    pub dither: bool,
+    pub toon: bool,
+    pub graticule: bool,
+    pub particle_rings: bool,
+    // Anillo con más segmentos cuanto más cerca está la cámara de su borde
+    pub adaptive_ring: bool,
+    pub ambient_occlusion: bool,
+    // Cielo de estrellas detrás de los cuerpos
+    pub starfield: bool,
+    // Vista infrarroja
+    pub thermal: bool,
+    // Descartar las caras traseras
+    pub cull_backfaces: bool,
+    // Anillos con el perfil de Saturno
+    pub saturn_rings: bool,
+    // Media resolución mientras se mueve la cámara
+    pub progressive: bool,
+    // Detener la animación mientras se muestra el frame refinado
+    pub freeze_idle: bool,
+    // Sombrear la mitad de los pixeles en cada frame
+    pub checkerboard: bool,
+    // Recorrer las escenas solo al iniciar
+    pub demo: bool,
+    // Recorrido de la cámara (grabado con K) que se reproduce desde el inicio, también al grabar frames
+    pub camera_path: Option<PathBuf>,
+    pub model: Option<String>,
+    pub scene_file: Option<String>,
+    // Curvas de color que se aplican a la imagen terminada
+    pub lut: Option<PathBuf>,
+    // Archivo de la sesión en lugar de session.toml, o no retomar ni guardar la sesión
+    pub session: Option<PathBuf>,
+    pub no_session: bool,
+    // Fragmentos máximos por frame
+    pub fragment_budget: Option<usize>,
+    // Hilos para rasterizar (None = uno por núcleo, 1 = sin hilos)
+    pub threads: Option<usize>,
+    // FPS que intenta mantener la calidad automática
+    pub target_fps: u32,
+    // Siempre la calidad completa, sin el control automático (para medir rendimiento)
+    pub fixed_quality: bool,
+    // None si no se pasó (la semilla es 0, o la de la sesión)
+    pub seed: Option<u32>,
+    pub model_shader: Option<ShaderType>,
+    // Revisar los shaders en puntos conocidos y salir
+    pub check_shaders: bool,
+    pub diff: Option<DiffOptions>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut record = false;
+        let mut scene = 1;
+        let mut frames = 600;
+        let mut fps = 60;
+        let mut out_dir = PathBuf::from("frames");
+        let mut orbit = false;
+        let mut debug_mode = 0;
+        let mut accumulate = 1;
+        let mut dump_raw = None;
+        let mut procedural = false;
+        let mut dither = false;
+        let mut toon = false;
+        let mut graticule = false;
+        let mut particle_rings = false;
+        let mut adaptive_ring = false;
+        let mut ambient_occlusion = false;
+        let mut starfield = false;
+        let mut thermal = false;
+        let mut cull_backfaces = false;
+        let mut saturn_rings = false;
+        let mut progressive = false;
+        let mut checkerboard = false;
+        let mut freeze_idle = false;
+        let mut demo = false;
+        let mut camera_path = None;
+        let mut model = None;
+        let mut scene_file = None;
+        let mut lut = None;
+        let mut session = None;
+        let mut no_session = false;
+        let mut fragment_budget = None;
+        let mut threads = None;
+        let mut target_fps = DEFAULT_TARGET_FPS;
+        let mut fixed_quality = false;
+        let mut seed = None;
+        let mut model_shader = None;
+        let mut export_path = None;
+        let mut check_shaders = false;
+        let mut cubemap = false;
+        let mut at_body = None;
+        let mut cube_size = 512;
+        let mut cube_prefix = PathBuf::from("cubemap");
+        let mut record_input = None;
+        let mut replay_input = None;
+        let mut headless = false;
+        let mut bench_exit = None;
+        let mut bench_out = None;
+        let mut diff = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => record = true,
+                "--orbit" => orbit = true,
+                "--procedural" => procedural = true,
+                "--dither" => dither = true,
+                "--toon" => toon = true,
+                "--graticule" => graticule = true,
+                "--particle-rings" => particle_rings = true,
+                "--adaptive-ring" => adaptive_ring = true,
+                "--ssao" => ambient_occlusion = true,
+                "--starfield" => starfield = true,
+                "--thermal" => thermal = true,
+                "--cull-backfaces" => cull_backfaces = true,
+                "--saturn-rings" => saturn_rings = true,
+                "--progressive" => progressive = true,
+                "--checkerboard" => checkerboard = true,
+                "--freeze-idle" => freeze_idle = true,
+                "--demo" => demo = true,
+                "--check-shaders" => check_shaders = true,
+                "--cubemap" => cubemap = true,
+                "--size" => cube_size = parse_value(&arg, args.next())?,
+                "--fixed-quality" => fixed_quality = true,
+                "--headless" => headless = true,
+                "--no-session" => no_session = true,
+                "--scene" => scene = parse_value(&arg, args.next())?,
+                "--frames" => frames = parse_value(&arg, args.next())?,
+                "--seed" => seed = Some(parse_value(&arg, args.next())?),
+                "--fragment-budget" => fragment_budget = Some(parse_value(&arg, args.next())?),
+                "--threads" => threads = Some(parse_value(&arg, args.next())?),
+                "--bench-exit" => bench_exit = Some(parse_value(&arg, args.next())?),
+                "--fps" => fps = parse_value(&arg, args.next())?,
+                "--target-fps" => target_fps = parse_value(&arg, args.next())?,
+                "--debug-mode" => debug_mode = parse_value(&arg, args.next())?,
+                "--accumulate" => accumulate = parse_value(&arg, args.next())?,
+                "--model-shader" => model_shader = Some(parse_value(&arg, args.next())?),
+                "--model" => {
+                    model = Some(args.next().ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--at-body" => {
+                    at_body = Some(args.next().ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--out" => {
+                    cube_prefix = args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?;
+                }
+                "--camera-path" => {
+                    camera_path = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--scene-file" => {
+                    scene_file = Some(args.next().ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--lut" => {
+                    lut = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--session" => {
+                    session = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--export-mesh" => {
+                    export_path = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--record-input" => {
+                    record_input = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--replay-input" => {
+                    replay_input = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--bench-out" => {
+                    bench_out = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--dump-raw" => {
+                    dump_raw = Some(args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?);
+                }
+                "--diff" => {
+                    let mut path = || args.next().map(PathBuf::from).ok_or_else(|| format!("{} necesita tres archivos", arg));
+                    diff = Some(DiffOptions { old: path()?, new: path()?, out: path()? });
+                }
+                "--out-dir" => {
+                    out_dir = args.next()
+                        .map(PathBuf::from)
+                        .ok_or_else(|| format!("Falta el valor para {}", arg))?;
+                }
+                _ => return Err(format!("Argumento desconocido: {}", arg)),
+            }
+        }
+
+        if fps == 0 {
+            return Err("--fps debe ser mayor que 0".to_string());
+        }
+        if target_fps == 0 {
+            return Err("--target-fps debe ser mayor que 0".to_string());
+        }
+        if accumulate == 0 {
+            return Err("--accumulate debe ser mayor que 0".to_string());
+        }
+        if headless && replay_input.is_none() {
+            return Err("--headless solo funciona con --replay-input".to_string());
+        }
+        if cube_size == 0 {
+            return Err("--size debe ser mayor que 0".to_string());
+        }
+        if cubemap && at_body.is_none() {
+            return Err("--cubemap necesita --at-body con el nombre del cuerpo".to_string());
+        }
+        if threads == Some(0) {
+            return Err("--threads debe ser mayor que 0".to_string());
+        }
+        if bench_exit == Some(0) {
+            return Err("--bench-exit debe ser mayor que 0".to_string());
+        }
+        if bench_out.is_some() && bench_exit.is_none() {
+            return Err("--bench-out solo funciona con --bench-exit".to_string());
+        }
+
+        let replay = replay_input.map(|path| ReplayOptions { path, headless_out_dir: headless.then(|| out_dir.clone()) });
+        let record = record.then_some(RecordOptions { scene, frames, fps, out_dir, orbit, debug_mode, accumulate, dump_raw });
+        let export_mesh = export_path.map(|path| ExportOptions { scene, path });
+        let cubemap = cubemap.then(|| CubemapOptions { scene, body: at_body.unwrap_or_default(), size: cube_size, prefix: cube_prefix });
+        let bench = bench_exit.map(|frames| BenchOptions { frames, out: bench_out.unwrap_or_else(|| PathBuf::from("bench.json")) });
+
+        Ok(Args { record, export_mesh, cubemap, record_input, replay, bench, procedural, dither, toon, graticule, particle_rings, adaptive_ring, ambient_occlusion, starfield, thermal, cull_backfaces, saturn_rings, progressive, freeze_idle, checkerboard, demo, camera_path, model, scene_file, lut, session, no_session, fragment_budget, threads, target_fps, fixed_quality, seed, model_shader, check_shaders, diff })
+    }
+
+    // Lo que se pasó de lo que también guarda la sesión, para ponerlo encima de la sesión retomada
+    pub fn command_line_options(&self) -> CommandLineOptions {
+        CommandLineOptions {
+            seed: self.seed,
+            model_shader: self.model_shader,
+            fragment_budget: self.fragment_budget,
+            toon: self.toon,
+            graticule: self.graticule,
+            particle_rings: self.particle_rings,
+            adaptive_ring: self.adaptive_ring,
+            ambient_occlusion: self.ambient_occlusion,
+            starfield: self.starfield,
+            thermal: self.thermal,
+            cull_backfaces: self.cull_backfaces,
+            saturn_rings: self.saturn_rings,
+            dither: self.dither,
+            progressive: self.progressive,
+            checkerboard: self.checkerboard,
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("Falta el valor para {}", flag))?;
+    value.parse().map_err(|_| format!("Valor inválido para {}: {}", flag, value))
+}
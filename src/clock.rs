@@ -0,0 +1,84 @@
+use serde::{Deserialize, Serialize};
+
+// Reloj de la simulación: avanza `speed` ticks por frame y se puede pausar, acelerar o invertir
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimClock {
+    time: f64,
+    speed: f64,
+    paused: bool,
+}
+
+// Rango del valor absoluto de la velocidad
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 16.0;
+
+impl SimClock {
+    pub fn new() -> Self {
+        SimClock { time: 0.0, speed: 1.0, paused: false }
+    }
+
+    pub fn tick(&mut self) {
+        if !self.paused {
+            self.time += self.speed;
+        }
+    }
+
+    pub fn time(&self) -> f32 {
+        self.time as f32
+    }
+
+    pub fn faster(&mut self) {
+        self.set_speed(self.speed * 2.0);
+    }
+
+    pub fn slower(&mut self) {
+        self.set_speed(self.speed / 2.0);
+    }
+
+    pub fn reverse(&mut self) {
+        self.speed = -self.speed;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn reset(&mut self) {
+        self.time = 0.0;
+    }
+
+    // Salta a un instante sin cambiar la velocidad ni la pausa (al empezar un recorrido de la cámara)
+    pub fn seek(&mut self, time: f32) {
+        self.time = time as f64;
+    }
+
+    // Con los valores de un archivo: la velocidad vuelve al rango y sin un número válido queda en x1
+    pub fn sanitized(mut self) -> Self {
+        if !self.time.is_finite() {
+            self.time = 0.0;
+        }
+        let speed = if self.speed.is_finite() && self.speed != 0.0 { self.speed } else { 1.0 };
+        self.set_speed(speed);
+        self
+    }
+
+    fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.abs().clamp(MIN_SPEED, MAX_SPEED).copysign(speed);
+    }
+
+    // Texto para el título de la ventana, por ejemplo "x2", "x-0.5" o "pausa (x1)"
+    pub fn label(&self) -> String {
+        if self.paused {
+            format!("pausa (x{})", self.speed)
+        } else {
+            format!("x{}", self.speed)
+        }
+    }
+}
+
+impl Default for SimClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,42 +1,85 @@
 use std::fmt;
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, Mul};
 
-#[derive(Debug, Clone, Copy)]
+// Los canales se guardan como flotantes en [0, 255] para no perder precisión
+// entre mezclas; se cuantizan a 8 bits solo al escribir en el framebuffer
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Color {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
 }
 
 impl Color {
-    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const BLACK: Color = Color { r: 0.0, g: 0.0, b: 0.0 };
 
-    pub fn new(r: u8, g: u8, b: u8) -> Self {
-        Color { r, g, b }
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Color { r: r as f32, g: g as f32, b: b as f32 }
     }
 
-    pub fn to_hex(self) -> u32 {
-        u32::from_be_bytes([0, self.r, self.g, self.b])
+    // Cuantiza cada canal sumando `threshold` (en [0, 1)) antes de truncar;
+    // 0.5 es redondeo normal y un umbral que varía por pixel produce dithering
+    pub fn to_hex_with_threshold(self, threshold: f32) -> u32 {
+        let quantize = |channel: f32| (channel + threshold).floor().clamp(0.0, 255.0) as u8;
+        u32::from_be_bytes([0, quantize(self.r), quantize(self.g), quantize(self.b)])
+    }
+
+    pub fn from_hex(hex: u32) -> Self {
+        let [_, r, g, b] = hex.to_be_bytes();
+        Color::new(r, g, b)
     }
 
     pub fn lerp(&self, other: &Color, t: f32) -> Self {
         let t = t.clamp(0.0, 1.0);
         Color {
-          r: (self.r as f32 + (other.r as f32 - self.r as f32) * t).round() as u8,
-          g: (self.g as f32 + (other.g as f32 - self.g as f32) * t).round() as u8,
-          b: (self.b as f32 + (other.b as f32 - self.b as f32) * t).round() as u8,
+          r: self.r + (other.r - self.r) * t,
+          g: self.g + (other.g - self.g) * t,
+          b: self.b + (other.b - self.b) * t,
         }
     }
+
+    // Color en `t` de un degradado con paradas (posición, color) ordenadas por posición; antes de la
+    // primera parada y después de la última se queda en su color
+    pub fn from_gradient(t: f32, stops: &[(f32, Color)]) -> Color {
+        let Some(&(first_position, first_color)) = stops.first() else {
+            return Color::BLACK;
+        };
+        if t.is_nan() || t <= first_position {
+            return first_color;
+        }
+        stops.windows(2)
+            .find(|pair| t <= pair[1].0)
+            .map(|pair| {
+                let ((start, from), (end, to)) = (pair[0], pair[1]);
+                let span = end - start;
+                if span > 0.0 { from.lerp(&to, (t - start) / span) } else { to }
+            })
+            .unwrap_or(stops[stops.len() - 1].1)
+    }
 }
 
+// Degradado tipo "inferno" de las cámaras térmicas: negro, violeta, rojo, naranja y amarillo claro
+pub const INFERNO: [(f32, Color); 8] = [
+    (0.0, Color::new(0, 0, 4)),
+    (0.14, Color::new(40, 11, 84)),
+    (0.29, Color::new(101, 21, 110)),
+    (0.43, Color::new(159, 42, 99)),
+    (0.57, Color::new(212, 72, 66)),
+    (0.71, Color::new(245, 125, 21)),
+    (0.86, Color::new(250, 193, 39)),
+    (1.0, Color::new(252, 255, 164)),
+];
+
 impl Add<Color> for Color {
     type Output = Color;
 
     fn add(self, other: Color) -> Color {
         Color {
-            r: self.r.saturating_add(other.r),
-            g: self.g.saturating_add(other.g),
-            b: self.b.saturating_add(other.b),
+            r: (self.r + other.r).min(255.0),
+            g: (self.g + other.g).min(255.0),
+            b: (self.b + other.b).min(255.0),
         }
     }
 }
@@ -46,15 +89,28 @@ impl Mul<f32> for Color {
 
     fn mul(self, scalar: f32) -> Color {
         Color {
-            r: (self.r as f32 * scalar).clamp(0.0, 255.0) as u8,
-            g: (self.g as f32 * scalar).clamp(0.0, 255.0) as u8,
-            b: (self.b as f32 * scalar).clamp(0.0, 255.0) as u8,
+            r: (self.r * scalar).clamp(0.0, 255.0),
+            g: (self.g * scalar).clamp(0.0, 255.0),
+            b: (self.b * scalar).clamp(0.0, 255.0),
+        }
+    }
+}
+
+// Multiplica cada canal por la luz del mismo canal (1 = sin cambio)
+impl Mul<Vec3> for Color {
+    type Output = Color;
+
+    fn mul(self, light: Vec3) -> Color {
+        Color {
+            r: (self.r * light.x).clamp(0.0, 255.0),
+            g: (self.g * light.y).clamp(0.0, 255.0),
+            b: (self.b * light.z).clamp(0.0, 255.0),
         }
     }
 }
 
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
+        write!(f, "Color(r: {:.0}, g: {:.0}, b: {:.0})", self.r, self.g, self.b)
     }
 }
@@ -0,0 +1,75 @@
+use nalgebra_glm::{perspective, Vec3};
+use std::f32::consts::FRAC_PI_2;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::lod::LodState;
+use crate::pipeline::build_uniforms;
+use crate::post;
+use crate::scene::{render_scene, scene_depth_range, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+
+// Una cara del cubo: hacia dónde mira y qué dirección del mundo queda arriba en la imagen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CubeFace {
+    pub name: &'static str,
+    pub direction: Vec3,
+    pub up: Vec3,
+}
+
+// Las seis caras en el orden de siempre (+X, -X, +Y, -Y, +Z, -Z) y con la orientación de los cube maps
+// de OpenGL y DirectX: las cuatro de los costados con +Y arriba, la de arriba con -Z arriba y la de
+// abajo con +Z. Así se arman en cualquier programa de skybox sin rotar ni espejar ninguna
+pub const CUBE_FACES: [CubeFace; 6] = [
+    CubeFace { name: "posx", direction: Vec3::new(1.0, 0.0, 0.0), up: Vec3::new(0.0, 1.0, 0.0) },
+    CubeFace { name: "negx", direction: Vec3::new(-1.0, 0.0, 0.0), up: Vec3::new(0.0, 1.0, 0.0) },
+    CubeFace { name: "posy", direction: Vec3::new(0.0, 1.0, 0.0), up: Vec3::new(0.0, 0.0, -1.0) },
+    CubeFace { name: "negy", direction: Vec3::new(0.0, -1.0, 0.0), up: Vec3::new(0.0, 0.0, 1.0) },
+    CubeFace { name: "posz", direction: Vec3::new(0.0, 0.0, 1.0), up: Vec3::new(0.0, 1.0, 0.0) },
+    CubeFace { name: "negz", direction: Vec3::new(0.0, 0.0, -1.0), up: Vec3::new(0.0, 1.0, 0.0) },
+];
+
+impl CubeFace {
+    // Dirección del mundo que pasa por el pixel (x, y) de la cara (en pixeles, desde la esquina de arriba
+    // a la izquierda); sirve para ubicar algo de la imagen en el cielo
+    pub fn pixel_direction(&self, x: f32, y: f32, size: usize) -> Vec3 {
+        let (s, t) = (2.0 * x / size as f32 - 1.0, 2.0 * y / size as f32 - 1.0);
+        (self.direction + self.left() * -s - self.up * t).normalize()
+    }
+
+    // Los cube maps se ven desde adentro, así que la izquierda de la imagen es la derecha de una cámara
+    // normal que mire hacia la cara
+    fn left(&self) -> Vec3 {
+        self.direction.cross(&self.up)
+    }
+}
+
+// La escena vista desde `position` en las seis direcciones, con 90° de campo de visión en cada cara para
+// que cubran todo el cielo sin solaparse. `options.hidden_body` no se dibuja (el cuerpo en cuyo centro
+// se está parado). La corrección de color de la escena ya va aplicada
+pub fn render_cubemap(scene: &SceneConfig, meshes: &SceneMeshes, position: Vec3, face_size: usize, time: f32, options: &RenderOptions) -> [Framebuffer; 6] {
+    let mut state = SceneState::new(scene, options.seed, meshes);
+    state.update(time);
+    let bodies: Vec<_> = state.bodies_seen_from(scene, time, position, meshes).into_iter().enumerate()
+        .filter(|(index, _)| Some(*index) != options.hidden_body)
+        .map(|(_, body)| body)
+        .collect();
+    let options = RenderOptions { highlighted_body: None, ..*options };
+
+    CUBE_FACES.map(|face| {
+        let camera = Camera::new(position, position + face.direction, face.up);
+        let depth_range = scene_depth_range(&bodies, meshes, &camera);
+        let mut uniforms = build_uniforms(&camera, time, face_size, face_size, depth_range);
+        uniforms.projection_matrix = perspective(1.0, FRAC_PI_2, depth_range.0, depth_range.1);
+
+        // Sin la histéresis de la cara anterior: cada cara elige el nivel de detalle desde cero, así un
+        // cuerpo partido entre dos caras se dibuja igual en las dos
+        state.lod = LodState::default();
+        let mut framebuffer = Framebuffer::new(face_size, face_size);
+        framebuffer.set_background_color(scene.background);
+        render_scene(&mut framebuffer, 0, scene, &uniforms, meshes, &mut state, &options);
+        post::color_grade(&mut framebuffer, &scene.grade);
+        for row in framebuffer.buffer.chunks_mut(face_size) {
+            row.reverse();
+        }
+        framebuffer
+    })
+}
@@ -0,0 +1,94 @@
+use nalgebra_glm::Vec3;
+use lab4_shaders::camera::{sample_path, Camera, Keyframe};
+
+// Segundos que tarda la cámara en ir de donde quedó al inicio del recorrido de la siguiente escena
+const TRANSITION_SECONDS: f32 = 1.5;
+// El recorrido avanza por frame como el reloj de la simulación (así se puede repetir con --replay-input)
+const FRAMES_PER_SECOND: f32 = 60.0;
+
+// Modo demo: recorre en bucle las escenas que tienen recorrido, moviendo la cámara según sus poses
+pub struct Demo {
+    scenes: Vec<u32>,
+    current: usize,
+    // Frames desde que empezó el recorrido actual
+    frames: u32,
+    // Recorrido de la escena actual, con la transición al principio
+    path: Vec<Keyframe>,
+}
+
+impl Demo {
+    // Empieza por la escena que se está viendo si está en la lista; None si no hay ninguna escena
+    pub fn new(scenes: Vec<u32>, scene_number: u32) -> Option<Self> {
+        if scenes.is_empty() {
+            return None;
+        }
+        let current = scenes.iter().position(|&scene| scene == scene_number).unwrap_or(0);
+        Some(Demo { scenes, current, frames: 0, path: Vec::new() })
+    }
+
+    fn elapsed(&self) -> f32 {
+        self.frames as f32 / FRAMES_PER_SECOND
+    }
+
+    // Escena que se debe mostrar; al terminar el recorrido pasa a la siguiente partiendo de la pose actual
+    pub fn update(&mut self, camera: &Camera, tour_of: impl Fn(u32) -> Vec<Keyframe>) -> u32 {
+        self.frames += 1;
+        let finished = self.path.last().is_none_or(|last| self.elapsed() >= last.time);
+        if finished {
+            if !self.path.is_empty() {
+                self.current = (self.current + 1) % self.scenes.len();
+            }
+            let start = Keyframe { time: 0.0, eye: camera.eye, center: camera.center };
+            let tour = tour_of(self.scenes[self.current]).into_iter()
+                .map(|keyframe| Keyframe { time: keyframe.time + TRANSITION_SECONDS, ..keyframe });
+            self.path = std::iter::once(start).chain(tour).collect();
+            self.frames = 0;
+        }
+        self.scenes[self.current]
+    }
+
+    pub fn apply(&self, camera: &mut Camera) {
+        if let Some((eye, center)) = sample_path(&self.path, self.elapsed()) {
+            camera.eye = eye;
+            camera.center = center;
+            camera.has_changed = true;
+        }
+    }
+}
+
+// Paso suave de la cámara desde donde está hasta otra pose, con la misma duración que entre las
+// escenas del modo demo
+pub struct Transition {
+    frames: u32,
+    path: Vec<Keyframe>,
+    up: Vec3,
+}
+
+impl Transition {
+    // La proyección cambia al empezar: no se puede interpolar entre perspectiva y ortográfica
+    pub fn new(camera: &mut Camera, target: &Camera) -> Self {
+        camera.projection = target.projection;
+        let path = vec![
+            Keyframe { time: 0.0, eye: camera.eye, center: camera.center },
+            Keyframe { time: TRANSITION_SECONDS, eye: target.eye, center: target.center },
+        ];
+        Transition { frames: 0, path, up: target.up }
+    }
+
+    // Mueve la cámara un frame por el camino; false cuando ya llegó. El `up` del destino se pone al
+    // llegar, así termina exactamente en esa pose
+    pub fn apply(&mut self, camera: &mut Camera) -> bool {
+        self.frames += 1;
+        let elapsed = self.frames as f32 / FRAMES_PER_SECOND;
+        if let Some((eye, center)) = sample_path(&self.path, elapsed) {
+            camera.eye = eye;
+            camera.center = center;
+            camera.has_changed = true;
+        }
+        let arrived = elapsed >= TRANSITION_SECONDS;
+        if arrived {
+            camera.up = self.up;
+        }
+        !arrived
+    }
+}
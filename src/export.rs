@@ -0,0 +1,148 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use crate::color::Color;
+use crate::fragments::Fragments;
+use crate::framebuffer::Framebuffer;
+use crate::pipeline::{create_view_matrix, diffuse_light, BodyUniforms, FrameUniforms};
+use crate::scene::{body_shader, body_uniforms, frame_uniforms, Body, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+use crate::shaders::{fragment_shader, vertex_shader, FragmentShader};
+use crate::vertex::Vertex;
+
+// Guarda el contenido del framebuffer como PNG RGB de 8 bits
+pub fn save_png(framebuffer: &Framebuffer, path: &Path) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), framebuffer.width as u32, framebuffer.height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(&framebuffer.as_rgb_bytes()).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+// Lee un PNG (cualquier formato de color) a un framebuffer del mismo tamaño; la profundidad queda vacía
+pub fn load_png(path: &Path) -> io::Result<Framebuffer> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(io::Error::other)?;
+    let mut bytes = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut bytes).map_err(io::Error::other)?;
+
+    let channels = info.color_type.samples();
+    let mut framebuffer = Framebuffer::new(info.width as usize, info.height as usize);
+    for (pixel, sample) in framebuffer.buffer.iter_mut().zip(bytes.chunks_exact(channels)) {
+        // En escala de grises el mismo valor va a los tres canales
+        let [r, g, b] = match channels {
+            1 | 2 => [sample[0]; 3],
+            _ => [sample[0], sample[1], sample[2]],
+        };
+        *pixel = u32::from_be_bytes([0, r, g, b]);
+    }
+    Ok(framebuffer)
+}
+
+// Identificador al principio de los archivos de dump_raw (cambia si cambia el formato)
+pub const RAW_DUMP_MAGIC: &[u8; 4] = b"FBD1";
+
+// Color y profundidad sin pérdida, para leerlos desde otros programas. Formato (enteros little-endian):
+//   bytes 0..4    "FBD1"
+//   bytes 4..8    ancho (u32)
+//   bytes 8..12   alto (u32)
+//   bytes 12..16  canales de color (u32, siempre 3)
+//   ancho * alto * 3 bytes de color, como Framebuffer::as_rgb_bytes (RGB, filas de arriba hacia abajo)
+//   ancho * alto f32 de profundidad (little-endian) en el mismo orden, como Framebuffer::get_pixel
+// En Python: numpy.fromfile(f, numpy.uint8, w * h * 3, offset=16) y numpy.fromfile(f, '<f4', offset=16 + w * h * 3)
+pub fn dump_raw(framebuffer: &Framebuffer, path: &Path) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(RAW_DUMP_MAGIC)?;
+    for value in [framebuffer.width as u32, framebuffer.height as u32, 3] {
+        out.write_all(&value.to_le_bytes())?;
+    }
+    out.write_all(&framebuffer.as_rgb_bytes())?;
+    for depth in framebuffer.depth_as_f32_slice() {
+        out.write_all(&depth.to_le_bytes())?;
+    }
+    out.flush()
+}
+
+// Guarda la geometría de todos los cuerpos de la escena como PLY de texto, en coordenadas del mundo
+// y con el color que da el shader de cada cuerpo en sus vértices (se usa la malla más detallada).
+// Lo que depende de la cámara (bordes, reflejos) se evalúa como si cada vértice se viera de frente.
+// Todos los cuerpos van en el mismo archivo uno después del otro, cada uno con sus propios vértices
+// aunque compartan malla; el encabezado dice con comentarios qué rango es de cada cuerpo.
+// Devuelve la cantidad de triángulos escritos
+pub fn save_ply(path: &Path, scene: &SceneConfig, uniforms: &FrameUniforms, meshes: &SceneMeshes, state: &SceneState, options: &RenderOptions) -> io::Result<usize> {
+    // Sin colores de depuración ni resaltado en la exportación
+    let options = RenderOptions { show_lod_tiers: false, highlighted_body: None, ..*options };
+    let bodies = state.bodies_seen_from(scene, uniforms.time, uniforms.camera_position, meshes);
+    let frame = frame_uniforms(uniforms, scene, &options);
+    let body_vertices = |index: usize, body: &Body| {
+        let vertices = state.mesh(meshes, index, body.mesh, 0);
+        &vertices[..vertices.len() / 3 * 3]
+    };
+    let vertex_count: usize = bodies.iter().enumerate().map(|(index, body)| body_vertices(index, body).len()).sum();
+
+    // El archivo puede ser grande: se escribe de a poco en lugar de armarlo en memoria
+    let mut out = BufWriter::new(File::create(path)?);
+    writeln!(out, "ply")?;
+    writeln!(out, "format ascii 1.0")?;
+    let mut first = 0;
+    for (index, body) in bodies.iter().enumerate() {
+        let count = body_vertices(index, body).len();
+        writeln!(out, "comment cuerpo \"{}\" ({:?}): vértices {} a {} (sin incluir el último)", body.name, body_shader(body, &options), first, first + count)?;
+        first += count;
+    }
+    writeln!(out, "element vertex {}", vertex_count)?;
+    for property in ["x", "y", "z", "nx", "ny", "nz"] {
+        writeln!(out, "property float {}", property)?;
+    }
+    for property in ["red", "green", "blue"] {
+        writeln!(out, "property uchar {}", property)?;
+    }
+    writeln!(out, "element face {}", vertex_count / 3)?;
+    writeln!(out, "property list uchar int vertex_indices")?;
+    writeln!(out, "end_header")?;
+
+    for (index, body) in bodies.iter().enumerate() {
+        let body_uniforms = body_uniforms(&bodies, index, 0, meshes, state, &options);
+        let mut facing_frame = frame.clone();
+        let shader = body_shader(body, &options);
+        for vertex in body_vertices(index, body) {
+            let transformed = vertex_shader(vertex, &frame, &body_uniforms);
+            let normal = transformed.transformed_normal.normalize();
+            face_vertex(&mut facing_frame, &transformed.world_position, &normal);
+            let color = shade_vertex(&transformed, &normal, &facing_frame, &body_uniforms, &shader);
+            let [_, r, g, b] = color.to_hex_with_threshold(0.5).to_be_bytes();
+            let p = transformed.world_position;
+            writeln!(out, "{} {} {} {} {} {} {} {} {}", p.x, p.y, p.z, normal.x, normal.y, normal.z, r, g, b)?;
+        }
+    }
+
+    // Los vértices no están indexados: cada grupo de 3 seguidos es un triángulo
+    for face in 0..vertex_count / 3 {
+        writeln!(out, "3 {} {} {}", face * 3, face * 3 + 1, face * 3 + 2)?;
+    }
+    out.flush()?;
+
+    Ok(vertex_count / 3)
+}
+
+// Pone la cámara sobre la normal del vértice, mirándolo
+fn face_vertex(uniforms: &mut FrameUniforms, position: &Vec3, normal: &Vec3) {
+    let eye = position + normal;
+    let up = if normal.y.abs() > 0.99 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+    uniforms.view_matrix = create_view_matrix(eye, *position, up);
+    uniforms.camera_position = eye;
+}
+
+// Color aproximado del vértice: el shader del cuerpo evaluado como si fuera un fragmento
+fn shade_vertex(vertex: &Vertex, normal: &Vec3, frame: &FrameUniforms, body: &BodyUniforms, shader: &dyn FragmentShader) -> Color {
+    let screen = vertex.transformed_position;
+    let mut fragment = Fragments::new(Vec2::new(screen.x, screen.y), screen.z, *normal, 0.0, vertex.position, vertex.color, vertex.world_position);
+    fragment.view_depth = vertex.view_depth;
+    fragment.light = diffuse_light(normal, &vertex.world_position, &frame.lights, &frame.ambient_light(normal));
+    fragment.intensity = (fragment.light.x + fragment.light.y + fragment.light.z) / 3.0;
+    fragment_shader(&fragment, frame, body, shader)
+}
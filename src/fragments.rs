@@ -1,12 +1,26 @@
 use nalgebra_glm::{Vec3, Vec2};
+use crate::color::Color;
 
 #[derive(Debug, Clone, Copy)]
 pub struct Fragments {
     pub position: Vec2,
     pub depth: f32,
+    // Normal en el mundo: para la luz y todo lo que depende de la cámara
     pub normal: Vec3,
     pub intensity: f32,
+    // Posición en el objeto, antes de la matriz de modelo: para los patrones (franjas, latitud,
+    // continentes), así giran y se inclinan con el cuerpo
     pub vertex_pos: Vec3,
+    pub color: Color,
+    // Posición en el mundo: para la luz, junto con `normal`
+    pub world_pos: Vec3,
+    // Luz que llega al fragmento por canal (difusa + ambiental, 1 = luz blanca completa)
+    pub light: Vec3,
+    // Profundidad en el espacio de la vista (la de `depth` es la del zbuffer)
+    pub view_depth: f32,
+    // Cuánto cambia `vertex_pos` un pixel a la derecha y uno abajo (cero si no viene de un triángulo)
+    pub vertex_pos_dx: Vec3,
+    pub vertex_pos_dy: Vec3,
 }
 
 impl Fragments {
@@ -16,6 +30,8 @@ impl Fragments {
         normal: Vec3,
         intensity: f32,
         vertex_pos: Vec3,
+        color: Color,
+        world_pos: Vec3,
     ) -> Self {
         Fragments {
             position,
@@ -23,6 +39,23 @@ impl Fragments {
             normal,
             intensity,
             vertex_pos,
+            color,
+            world_pos,
+            light: Vec3::new(intensity, intensity, intensity),
+            view_depth: 0.0,
+            vertex_pos_dx: Vec3::zeros(),
+            vertex_pos_dy: Vec3::zeros(),
         }
     }
+
+    // Cuánto cambia `field` de este pixel a los de al lado (como fwidth en una GPU): se evalúa en la
+    // posición del objeto un pixel a la derecha y uno abajo. Es 0 sin derivadas, así los bordes que se
+    // suavizan con esto quedan como escalones exactos
+    pub fn fwidth(&self, field: impl Fn(&Vec3) -> f32) -> f32 {
+        if self.vertex_pos_dx == Vec3::zeros() && self.vertex_pos_dy == Vec3::zeros() {
+            return 0.0;
+        }
+        let value = field(&self.vertex_pos);
+        (field(&(self.vertex_pos + self.vertex_pos_dx)) - value).abs() + (field(&(self.vertex_pos + self.vertex_pos_dy)) - value).abs()
+    }
 }
\ No newline at end of file
@@ -1,10 +1,71 @@
+use crate::color::Color;
+
+// Matriz de Bayer 8x8 para el dithering ordenado
+const BAYER_8X8: [[u8; 8]; 8] = [
+    [ 0, 32,  8, 40,  2, 34, 10, 42],
+    [48, 16, 56, 24, 50, 18, 58, 26],
+    [12, 44,  4, 36, 14, 46,  6, 38],
+    [60, 28, 52, 20, 62, 30, 54, 22],
+    [ 3, 35, 11, 43,  1, 33,  9, 41],
+    [51, 19, 59, 27, 49, 17, 57, 25],
+    [15, 47,  7, 39, 13, 45,  5, 37],
+    [63, 31, 55, 23, 61, 29, 53, 21],
+];
+
+// Rectángulo de pixeles del framebuffer donde se dibuja una vista
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub const fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Rect { x, y, width, height }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width && y < self.y + self.height
+    }
+
+    // El rectángulo más chico que cubre a los dos
+    pub fn union(&self, other: &Rect) -> Rect {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+        Rect::new(x, y, right - x, bottom - y)
+    }
+
+    // La parte que comparten; None si no se tocan
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        (x < right && y < bottom).then(|| Rect::new(x, y, right - x, bottom - y))
+    }
+}
+
+// Cómo se combina un fragmento con lo que ya hay en el pixel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    Opaque,         // Reemplaza el color y escribe la profundidad
+    AlphaBlend,     // Mezcla según la opacidad, sin escribir la profundidad
+    Additive,       // Suma el color escalado por la opacidad, sin escribir la profundidad
+}
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
     pub zbuffer: Vec<f32>,
+    // Cuerpo dibujado en cada pixel (0 = fondo)
+    pub ids: Vec<u16>,
+    pub dither: bool,
     background_color: u32,
-    current_color: u32,
+    current_color: Color,
+    current_id: u16,
 }
 
 impl Framebuffer {
@@ -14,35 +75,256 @@ impl Framebuffer {
             height,
             buffer: vec![0; width * height],
             zbuffer: vec![f32::INFINITY; width * height],
+            ids: vec![0; width * height],
+            dither: false,
             background_color: 0x000000,
-            current_color: 0xFFFFFF,
+            current_color: Color::new(255, 255, 255),
+            current_id: 0,
         }
     }
 
+    // Todo el framebuffer
+    pub fn rect(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
     pub fn clear(&mut self) {
-        for pixel in self.buffer.iter_mut() {
-            *pixel = self.background_color;
+        self.clear_rect(self.rect());
+    }
+
+    // Borra color, profundidad e ids solo dentro del rectángulo (recortado al framebuffer)
+    pub fn clear_rect(&mut self, rect: Rect) {
+        let end_x = (rect.x + rect.width).min(self.width);
+        let end_y = (rect.y + rect.height).min(self.height);
+        for y in rect.y.min(end_y)..end_y {
+            let row = y * self.width;
+            let span = row + rect.x.min(end_x)..row + end_x;
+            self.buffer[span.clone()].fill(self.background_color);
+            self.zbuffer[span.clone()].fill(f32::INFINITY);
+            self.ids[span].fill(0);
+        }
+    }
+
+    // Pinta con `color_at(x, y)` solo los pixeles del rectángulo donde no se dibujó nada (profundidad
+    // infinita); la profundidad y los ids no cambian, así lo que se dibuje después lo sigue viendo como
+    // fondo. Devuelve cuántos pixeles pintó
+    pub fn fill_empty(&mut self, rect: Rect, mut color_at: impl FnMut(usize, usize) -> Color) -> usize {
+        let end_x = (rect.x + rect.width).min(self.width);
+        let end_y = (rect.y + rect.height).min(self.height);
+        let mut filled = 0;
+        for y in rect.y.min(end_y)..end_y {
+            let row = y * self.width;
+            for x in rect.x.min(end_x)..end_x {
+                if self.zbuffer[row + x] == f32::INFINITY {
+                    self.buffer[row + x] = color_at(x, y).to_hex_with_threshold(self.threshold(x, y));
+                    filled += 1;
+                }
+            }
+        }
+        filled
+    }
+
+    // Borde de un pixel alrededor del rectángulo, sin tocar la profundidad
+    pub fn outline_rect(&mut self, rect: Rect, color: Color) {
+        let hex = color.to_hex_with_threshold(0.5);
+        let inside = |x: usize, y: usize| x < self.width && y < self.height;
+        let (right, bottom) = ((rect.x + rect.width).saturating_sub(1), (rect.y + rect.height).saturating_sub(1));
+        for x in rect.x..=right {
+            for y in [rect.y, bottom] {
+                if inside(x, y) {
+                    self.buffer[y * self.width + x] = hex;
+                }
+            }
+        }
+        for y in rect.y..=bottom {
+            for x in [rect.x, right] {
+                if inside(x, y) {
+                    self.buffer[y * self.width + x] = hex;
+                }
+            }
+        }
+    }
+
+    // Copia los colores de `source` (del mismo tamaño que el framebuffer) solo dentro del rectángulo,
+    // para borrar lo que se dibujó encima de una imagen guardada
+    pub fn restore_rect(&mut self, source: &[u32], rect: Rect) {
+        let end_x = (rect.x + rect.width).min(self.width);
+        let end_y = (rect.y + rect.height).min(self.height);
+        for y in rect.y.min(end_y)..end_y {
+            let span = y * self.width + rect.x.min(end_x)..y * self.width + end_x;
+            self.buffer[span.clone()].copy_from_slice(&source[span]);
+        }
+    }
+
+    // Oscurece los colores dentro del rectángulo (0 = negro, 1 = sin cambios), para poner texto encima
+    pub fn dim_rect(&mut self, rect: Rect, brightness: f32) {
+        let end_x = (rect.x + rect.width).min(self.width);
+        let end_y = (rect.y + rect.height).min(self.height);
+        for y in rect.y.min(end_y)..end_y {
+            for pixel in &mut self.buffer[y * self.width + rect.x.min(end_x)..y * self.width + end_x] {
+                *pixel = (Color::from_hex(*pixel) * brightness).to_hex_with_threshold(0.5);
+            }
         }
-        for depth in self.zbuffer.iter_mut() {
-            *depth = f32::INFINITY;
+    }
+
+    // Mezcla `color` con opacidad `alpha` dentro del rectángulo, con las esquinas redondeadas con radio
+    // `corner` (0 = en ángulo recto), para los paneles translúcidos con texto encima
+    pub fn blend_rect(&mut self, rect: Rect, color: Color, alpha: f32, corner: usize) {
+        let end_x = (rect.x + rect.width).min(self.width);
+        let end_y = (rect.y + rect.height).min(self.height);
+        let corner = corner.min(rect.width / 2).min(rect.height / 2) as f32;
+        // Distancia del centro del pixel al centro del arco, por eje; 0 fuera de las esquinas
+        let inset = |offset: usize, size: usize| {
+            let center = offset as f32 + 0.5;
+            (corner - center).max(center - (size as f32 - corner)).max(0.0)
+        };
+        for y in rect.y.min(end_y)..end_y {
+            let dy = inset(y - rect.y, rect.height);
+            for x in rect.x.min(end_x)..end_x {
+                let dx = inset(x - rect.x, rect.width);
+                if dx * dx + dy * dy > corner * corner {
+                    continue;
+                }
+                let pixel = &mut self.buffer[y * self.width + x];
+                *pixel = Color::from_hex(*pixel).lerp(&color, alpha).to_hex_with_threshold(0.5);
+            }
         }
     }
 
-    pub fn point(&mut self, x: usize, y: usize, depth: f32) {
+    // Devuelve true si el punto pasó la prueba de profundidad y se escribió
+    pub fn point(&mut self, x: usize, y: usize, depth: f32) -> bool {
         if x < self.width && y < self.height {
             let index = y * self.width + x;
             if self.zbuffer[index] > depth {
-                self.buffer[index] = self.current_color;
+                self.buffer[index] = self.current_color.to_hex_with_threshold(self.threshold(x, y));
                 self.zbuffer[index] = depth;
+                self.ids[index] = self.current_id;
+                return true;
+            }
+        }
+        false
+    }
+
+    // Como point, pero para superficies translúcidas: prueba la profundidad sin escribirla y mezcla el
+    // color actual con el que ya hay según `alpha` (en Opaque es igual a point). El id no cambia,
+    // así al elegir con el mouse se ve lo que hay detrás
+    pub fn blend_point(&mut self, x: usize, y: usize, depth: f32, alpha: f32, mode: BlendMode) -> bool {
+        if mode == BlendMode::Opaque {
+            return self.point(x, y, depth);
+        }
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let index = y * self.width + x;
+        if self.zbuffer[index] <= depth {
+            return false;
+        }
+        let behind = Color::from_hex(self.buffer[index]);
+        let color = match mode {
+            BlendMode::Additive => behind + self.current_color * alpha,
+            _ => behind.lerp(&self.current_color, alpha),
+        };
+        self.buffer[index] = color.to_hex_with_threshold(self.threshold(x, y));
+        true
+    }
+
+    // Umbral de cuantización de un pixel; con dithering depende del pixel, así el resultado es el mismo en cada corrida
+    pub fn threshold(&self, x: usize, y: usize) -> f32 {
+        if self.dither {
+            (BAYER_8X8[y % 8][x % 8] as f32 + 0.5) / 64.0
+        } else {
+            0.5
+        }
+    }
+
+    // Cuerpo visible en un pixel; None fuera del buffer o sobre el fondo
+    pub fn id_at(&self, x: usize, y: usize) -> Option<u16> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        Some(self.ids[y * self.width + x]).filter(|&id| id != 0)
+    }
+
+    // Color y profundidad de un pixel; la profundidad es la z del zbuffer (en [-1, 1] después de la
+    // proyección, más chica = más cerca) e infinito sobre el fondo. None fuera del buffer
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<(Color, f32)> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let index = y * self.width + x;
+        Some((Color::from_hex(self.buffer[index]), self.zbuffer[index]))
+    }
+
+    // Colores como bytes R, G, B seguidos, fila por fila de arriba hacia abajo y cada fila de
+    // izquierda a derecha (width * height * 3 bytes, sin relleno al final de las filas)
+    pub fn as_rgb_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.buffer.len() * 3);
+        for pixel in &self.buffer {
+            let [_, r, g, b] = pixel.to_be_bytes();
+            bytes.extend_from_slice(&[r, g, b]);
+        }
+        bytes
+    }
+
+    // Profundidad de cada pixel en el mismo orden que as_rgb_bytes (ver get_pixel)
+    pub fn depth_as_f32_slice(&self) -> &[f32] {
+        &self.zbuffer
+    }
+
+    // Copia este buffer (color, profundidad e ids) a uno de otro tamaño con el vecino más cercano
+    pub fn upscale_into(&self, target: &mut Framebuffer) {
+        for y in 0..target.height {
+            let source_y = y * self.height / target.height;
+            for x in 0..target.width {
+                let source_x = x * self.width / target.width;
+                let source = source_y * self.width + source_x;
+                let index = y * target.width + x;
+                target.buffer[index] = self.buffer[source];
+                target.zbuffer[index] = self.zbuffer[source];
+                target.ids[index] = self.ids[source];
             }
         }
     }
 
+    // Copia del rectángulo (color, profundidad e ids, con el mismo dithering y color e id actuales) como
+    // un framebuffer propio con (0, 0) en su esquina, para dibujarlo en otro hilo; `rect` tiene que caber
+    // en el framebuffer. Se vuelve a poner en su lugar con write_tile
+    pub fn tile(&self, rect: Rect) -> Framebuffer {
+        let mut tile = Framebuffer::new(rect.width, rect.height);
+        for row in 0..rect.height {
+            let source = (rect.y + row) * self.width + rect.x;
+            let (span, target) = (source..source + rect.width, row * rect.width..(row + 1) * rect.width);
+            tile.buffer[target.clone()].copy_from_slice(&self.buffer[span.clone()]);
+            tile.zbuffer[target.clone()].copy_from_slice(&self.zbuffer[span.clone()]);
+            tile.ids[target].copy_from_slice(&self.ids[span]);
+        }
+        tile.dither = self.dither;
+        tile.background_color = self.background_color;
+        tile.current_color = self.current_color;
+        tile.current_id = self.current_id;
+        tile
+    }
+
+    // Pone de vuelta en `rect` lo que se dibujó en una copia de Framebuffer::tile
+    pub fn write_tile(&mut self, tile: &Framebuffer, rect: Rect) {
+        for row in 0..rect.height {
+            let target = (rect.y + row) * self.width + rect.x;
+            let (span, source) = (target..target + rect.width, row * rect.width..(row + 1) * rect.width);
+            self.buffer[span.clone()].copy_from_slice(&tile.buffer[source.clone()]);
+            self.zbuffer[span.clone()].copy_from_slice(&tile.zbuffer[source.clone()]);
+            self.ids[span].copy_from_slice(&tile.ids[source]);
+        }
+    }
+
     pub fn set_background_color(&mut self, color: u32) {
         self.background_color = color;
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
+    pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
+
+    pub fn set_current_id(&mut self, id: u16) {
+        self.current_id = id;
+    }
 }
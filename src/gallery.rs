@@ -0,0 +1,165 @@
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::{Framebuffer, Rect};
+use lab4_shaders::pipeline::build_uniforms;
+use lab4_shaders::post;
+use lab4_shaders::scene::{render_scene, scene_depth_range, start_camera, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+use lab4_shaders::text::draw_text;
+use crate::input::InputFrame;
+use lab4_shaders::keymap::Action;
+
+// Tamaño máximo de cada miniatura; si no entran todas en la fila se achican sin cambiar la proporción
+const THUMBNAIL_SIZE: (usize, usize) = (120, 90);
+const THUMBNAIL_GAP: usize = 8;
+// Separación de la tira con el borde inferior de la ventana y entre la tira y las miniaturas
+const STRIP_MARGIN: usize = 10;
+const STRIP_PADDING: usize = 6;
+const STRIP_BRIGHTNESS: f32 = 0.25;
+const BORDER_COLOR: Color = Color::new(90, 95, 110);
+const SELECTED_COLOR: Color = Color::new(255, 235, 60);
+const NUMBER_COLOR: Color = Color::new(225, 228, 235);
+const NUMBER_PADDING: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GalleryState {
+    Closed,
+    Open,
+    // Ya se eligió con Enter; no se vuelve a abrir hasta soltar la tecla
+    Chosen,
+}
+
+// Tira de miniaturas de todas las escenas para cambiar de escena viéndolas (mantener G). Las miniaturas
+// se dibujan la primera vez que se abre, con la cámara inicial de cada escena, y se guardan
+pub struct Gallery {
+    // Número de cada escena y su miniatura (None hasta que se dibuja o después de recargar la escena)
+    thumbnails: Vec<(u32, Option<Framebuffer>)>,
+    size: (usize, usize),
+    selected: usize,
+    state: GalleryState,
+}
+
+impl Gallery {
+    pub fn new(scenes: Vec<u32>, framebuffer_width: usize) -> Self {
+        let count = scenes.len().max(1);
+        let available = framebuffer_width.saturating_sub(2 * (STRIP_MARGIN + STRIP_PADDING) + (count - 1) * THUMBNAIL_GAP);
+        let width = (available / count).clamp(1, THUMBNAIL_SIZE.0);
+        let height = (width * THUMBNAIL_SIZE.1 / THUMBNAIL_SIZE.0).max(1);
+        Gallery {
+            thumbnails: scenes.into_iter().map(|number| (number, None)).collect(),
+            size: (width, height),
+            selected: 0,
+            state: GalleryState::Closed,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.state == GalleryState::Open
+    }
+
+    // Miniatura seleccionada mientras la galería está abierta, para saber si hay que volver a dibujarla
+    pub fn selection(&self) -> Option<usize> {
+        self.is_open().then_some(self.selected)
+    }
+
+    // La miniatura de esa escena se vuelve a dibujar la próxima vez que se abra (al recargar el archivo)
+    pub fn invalidate(&mut self, scene_number: u32) {
+        for (_, thumbnail) in self.thumbnails.iter_mut().filter(|(number, _)| *number == scene_number) {
+            *thumbnail = None;
+        }
+    }
+
+    // Al presionar la tecla se abre en la escena actual y las flechas mueven la selección; al soltarla o con
+    // Enter devuelve la escena elegida
+    pub fn update(&mut self, input: &InputFrame, current_scene: u32) -> Option<u32> {
+        let held = input.triggered(Action::Gallery);
+        match self.state {
+            GalleryState::Closed if held && !self.thumbnails.is_empty() => {
+                self.selected = self.thumbnails.iter().position(|(number, _)| *number == current_scene).unwrap_or(0);
+                self.state = GalleryState::Open;
+                None
+            }
+            GalleryState::Open if !held || input.triggered(Action::GalleryChoose) => {
+                self.state = if held { GalleryState::Chosen } else { GalleryState::Closed };
+                Some(self.thumbnails[self.selected].0)
+            }
+            GalleryState::Open => {
+                let count = self.thumbnails.len() as isize;
+                let step = input.triggered(Action::GalleryNext) as isize - input.triggered(Action::GalleryPrevious) as isize;
+                self.selected = (self.selected as isize + step).rem_euclid(count) as usize;
+                None
+            }
+            GalleryState::Chosen if !held => {
+                self.state = GalleryState::Closed;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    // Dibuja las miniaturas que faltan; solo con la galería abierta, así no se paga nada si no se usa
+    pub fn generate(&mut self, scene_of: impl Fn(u32) -> SceneConfig, meshes: &SceneMeshes, options: &RenderOptions) {
+        if !self.is_open() {
+            return;
+        }
+        let (width, height) = self.size;
+        for (number, thumbnail) in self.thumbnails.iter_mut().filter(|(_, thumbnail)| thumbnail.is_none()) {
+            *thumbnail = Some(render_thumbnail(*number, &scene_of(*number), meshes, options, width, height));
+        }
+    }
+
+    // Tira centrada abajo con la miniatura seleccionada en un borde amarillo; devuelve la zona que tapó
+    pub fn draw(&self, framebuffer: &mut Framebuffer) -> Option<Rect> {
+        if !self.is_open() {
+            return None;
+        }
+        let (width, height) = self.size;
+        let count = self.thumbnails.len();
+        let strip_width = count * width + (count - 1) * THUMBNAIL_GAP + 2 * STRIP_PADDING;
+        let strip_height = height + 2 * STRIP_PADDING;
+        let strip = Rect::new(
+            framebuffer.width.saturating_sub(strip_width) / 2,
+            framebuffer.height.saturating_sub(strip_height + STRIP_MARGIN),
+            strip_width,
+            strip_height,
+        );
+        framebuffer.dim_rect(strip, STRIP_BRIGHTNESS);
+
+        for (index, (number, thumbnail)) in self.thumbnails.iter().enumerate() {
+            let rect = Rect::new(strip.x + STRIP_PADDING + index * (width + THUMBNAIL_GAP), strip.y + STRIP_PADDING, width, height);
+            if let Some(thumbnail) = thumbnail {
+                blit(framebuffer, thumbnail, rect.x, rect.y);
+            }
+            draw_text(framebuffer, rect.x + NUMBER_PADDING, rect.y + NUMBER_PADDING, &number.to_string(), NUMBER_COLOR, 1);
+            if index == self.selected {
+                // Dos pixeles de grosor para que se distinga del borde de las demás
+                framebuffer.outline_rect(rect, SELECTED_COLOR);
+                framebuffer.outline_rect(Rect::new(rect.x.saturating_sub(1), rect.y.saturating_sub(1), rect.width + 2, rect.height + 2), SELECTED_COLOR);
+            } else {
+                framebuffer.outline_rect(rect, BORDER_COLOR);
+            }
+        }
+        Some(strip)
+    }
+}
+
+// La escena en el instante 0 desde su cámara inicial, igual que el primer frame de --record
+fn render_thumbnail(scene_number: u32, scene: &SceneConfig, meshes: &SceneMeshes, options: &RenderOptions, width: usize, height: usize) -> Framebuffer {
+    let mut thumbnail = Framebuffer::new(width, height);
+    thumbnail.set_background_color(scene.background);
+    let camera = start_camera(scene, meshes);
+    let depth_range = scene_depth_range(&scene.bodies_seen_from(0.0, camera.eye, meshes), meshes, &camera);
+    let uniforms = build_uniforms(&camera, 0.0, width, height, depth_range);
+    let mut state = SceneState::new(scene, options.seed, meshes);
+    let options = RenderOptions { highlighted_body: None, ..*options };
+    render_scene(&mut thumbnail, scene_number, scene, &uniforms, meshes, &mut state, &options);
+    post::color_grade(&mut thumbnail, &scene.grade);
+    thumbnail
+}
+
+// Copia los colores de `source` con su esquina superior izquierda en (x, y), recortados al framebuffer
+fn blit(framebuffer: &mut Framebuffer, source: &Framebuffer, x: usize, y: usize) {
+    let columns = source.width.min(framebuffer.width.saturating_sub(x));
+    for row in 0..source.height.min(framebuffer.height.saturating_sub(y)) {
+        let target = (y + row) * framebuffer.width + x;
+        framebuffer.buffer[target..target + columns].copy_from_slice(&source.buffer[row * source.width..row * source.width + columns]);
+    }
+}
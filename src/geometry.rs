@@ -0,0 +1,213 @@
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+
+// Primitivas geométricas para las consultas sobre la escena (encuadre, selección, recorte): cajas y
+// esferas envolventes, rayos y el frustum de la cámara
+
+// Caja alineada con los ejes. Puede no tener volumen (un punto o un plano) pero min nunca pasa a max
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    // Las esquinas pueden venir en cualquier orden
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Aabb { min: a.inf(&b), max: a.sup(&b) }
+    }
+
+    // None sin puntos
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Aabb { min: first, max: first }, |aabb, point| Aabb { min: aabb.min.inf(&point), max: aabb.max.sup(&point) }))
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb { min: self.min.inf(&other.min), max: self.max.sup(&other.max) }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) / 2.0
+    }
+
+    // Con los bordes incluidos
+    pub fn contains(&self, point: Vec3) -> bool {
+        (0..3).all(|axis| (self.min[axis]..=self.max[axis]).contains(&point[axis]))
+    }
+
+    // Caja que envuelve a esta después de aplicarle una transformación afín (rotada ya no está alineada
+    // con los ejes, así que crece): el centro se transforma y cada eje nuevo suma lo que aportan los tres
+    // ejes viejos en valor absoluto, lo que también vale con escala negativa
+    pub fn transformed(&self, matrix: &Mat4) -> Aabb {
+        let center = transform_point(matrix, self.center());
+        let half = self.half_extents();
+        let extent = Vec3::from_fn(|row, _| (0..3).map(|column| matrix[(row, column)].abs() * half[column]).sum());
+        Aabb { min: center - extent, max: center + extent }
+    }
+}
+
+// Esfera envolvente
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        BoundingSphere { center, radius: radius.max(0.0) }
+    }
+
+    // Centrada en la caja de los puntos; no es la más chica posible pero se calcula en dos pasadas. None
+    // sin puntos
+    pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.center();
+        Some(Self::around(center, points))
+    }
+
+    // La más chica con ese centro que contiene a los puntos (radio 0 sin puntos)
+    pub fn around(center: Vec3, points: impl IntoIterator<Item = Vec3>) -> Self {
+        let radius = points.into_iter().map(|point| (point - center).magnitude()).fold(0.0, f32::max);
+        BoundingSphere { center, radius }
+    }
+
+    // La más chica que contiene a las dos; si una ya contiene a la otra queda esa
+    pub fn merge(&self, other: &BoundingSphere) -> BoundingSphere {
+        let offset = other.center - self.center;
+        let distance = offset.magnitude();
+
+        if distance + other.radius <= self.radius {
+            *self
+        } else if distance + self.radius <= other.radius {
+            *other
+        } else {
+            let radius = (distance + self.radius + other.radius) / 2.0;
+            let center = self.center + offset * ((radius - self.radius) / distance);
+            BoundingSphere { center, radius }
+        }
+    }
+
+    // Después de una transformación afín: el radio crece con el eje que más se estira (con escala no
+    // uniforme la esfera queda floja, pero sigue envolviendo)
+    pub fn transformed(&self, matrix: &Mat4) -> BoundingSphere {
+        let stretch = (0..3).map(|column| Vec3::new(matrix[(0, column)], matrix[(1, column)], matrix[(2, column)]).magnitude()).fold(0.0, f32::max);
+        BoundingSphere { center: transform_point(matrix, self.center), radius: self.radius * stretch }
+    }
+
+    // Con el borde incluido
+    pub fn contains(&self, point: Vec3) -> bool {
+        (point - self.center).magnitude_squared() <= self.radius * self.radius
+    }
+}
+
+// Semirrecta desde `origin`. `dir` no tiene que ser unitaria: los t que se devuelven están en unidades de
+// su largo (el punto es origin + dir * t)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    pub dir: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, dir: Vec3) -> Self {
+        Ray { origin, dir }
+    }
+
+    pub fn at(&self, t: f32) -> Vec3 {
+        self.origin + self.dir * t
+    }
+
+    // Primer punto de la superficie por delante del origen (t >= 0); desde adentro es por donde sale
+    pub fn intersect_sphere(&self, sphere: &BoundingSphere) -> Option<f32> {
+        let a = self.dir.magnitude_squared();
+        if a == 0.0 {
+            return None;
+        }
+        let offset = self.origin - sphere.center;
+        let b = offset.dot(&self.dir);
+        let c = offset.magnitude_squared() - sphere.radius * sphere.radius;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [(-b - root) / a, (-b + root) / a].into_iter().find(|&t| t >= 0.0)
+    }
+
+    // Igual que con la esfera, por el método de las franjas: en cada eje el rayo está entre los dos planos
+    // de la caja para un intervalo de t. Paralelo a un eje, el intervalo es todo o nada según dónde esté
+    // el origen (así no aparecen 0 * infinito)
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let (mut enter, mut exit) = (f32::NEG_INFINITY, f32::INFINITY);
+        for axis in 0..3 {
+            let (origin, dir) = (self.origin[axis], self.dir[axis]);
+            if dir == 0.0 {
+                if !(aabb.min[axis]..=aabb.max[axis]).contains(&origin) {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = ((aabb.min[axis] - origin) / dir, (aabb.max[axis] - origin) / dir);
+            enter = enter.max(a.min(b));
+            exit = exit.min(a.max(b));
+        }
+        if enter > exit || exit < 0.0 || exit == f32::INFINITY {
+            return None;
+        }
+        Some(if enter >= 0.0 { enter } else { exit })
+    }
+
+    // Plano por `point` con normal `normal` (de cualquier largo); None si el rayo es paralelo o lo cruzaría
+    // detrás del origen
+    pub fn intersect_plane(&self, point: Vec3, normal: Vec3) -> Option<f32> {
+        let denominator = normal.dot(&self.dir);
+        if denominator.abs() <= f32::EPSILON * normal.magnitude() * self.dir.magnitude() {
+            return None;
+        }
+        let t = normal.dot(&(point - self.origin)) / denominator;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+// Punto del rayo más cercano a `point`: lo que queda detrás del origen se acerca al origen mismo
+pub fn closest_point_on_ray(ray: &Ray, point: Vec3) -> Vec3 {
+    let length_squared = ray.dir.magnitude_squared();
+    if length_squared == 0.0 {
+        return ray.origin;
+    }
+    ray.at(((point - ray.origin).dot(&ray.dir) / length_squared).max(0.0))
+}
+
+// Los seis planos del volumen que ve la cámara, sacados de la matriz proyección * vista (cada uno es
+// (a, b, c, d) con a x + b y + c z + d >= 0 adentro)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub fn new(view_projection: &Mat4) -> Self {
+        let row = |index: usize| view_projection.row(index).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Frustum { planes: [w + x, w - x, w + y, w - y, w + z, w - z] }
+    }
+
+    // Si la esfera queda entera del lado de afuera de algún plano; cerca de las esquinas puede decir que
+    // no aunque no se vea, pero nunca descarta algo que se ve
+    pub fn excludes(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().any(|plane| {
+            let normal = plane.xyz();
+            let length = normal.magnitude();
+            length > 0.0 && (normal.dot(&sphere.center) + plane.w) / length < -sphere.radius
+        })
+    }
+}
+
+fn transform_point(matrix: &Mat4, point: Vec3) -> Vec3 {
+    (matrix * Vec4::new(point.x, point.y, point.z, 1.0)).xyz()
+}
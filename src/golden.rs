@@ -0,0 +1,229 @@
+use nalgebra_glm::Vec3;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::color::Color;
+use crate::export::{load_png, save_png};
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::pipeline::build_uniforms;
+use crate::post::{self, DIFF_DIM, OUTLINE_COLOR, OUTLINE_THRESHOLD};
+use crate::scene::{render_scene, scene_depth_range, start_camera, CameraConfig, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+
+// Imágenes de referencia: cada caso se dibuja sin ventana y se compara con tests/golden/<nombre>.png.
+// Con BLESS=1 las referencias se reemplazan en vez de comparar (después de un cambio buscado en un
+// shader): BLESS=1 cargo test golden
+
+// Carpeta de las referencias, desde la raíz del proyecto
+pub const GOLDEN_DIR: &str = "tests/golden";
+// Tamaño de las imágenes: chico para que los casos corran rápido también sin --release
+const GOLDEN_WIDTH: usize = 200;
+const GOLDEN_HEIGHT: usize = 150;
+
+// Un escenario: escena, instante, cámara (None = el encuadre inicial de la escena), tamaño y opciones
+#[derive(Clone, Copy, Debug)]
+pub struct GoldenCase {
+    pub name: &'static str,
+    pub scene: u32,
+    pub time: f32,
+    pub camera: Option<CameraConfig>,
+    pub width: usize,
+    pub height: usize,
+    pub options: RenderOptions,
+}
+
+impl GoldenCase {
+    pub fn new(name: &'static str, scene: u32, time: f32) -> Self {
+        GoldenCase { name, scene, time, camera: None, width: GOLDEN_WIDTH, height: GOLDEN_HEIGHT, options: RenderOptions::default() }
+    }
+
+    pub fn camera(mut self, eye: Vec3, center: Vec3) -> Self {
+        self.camera = Some(CameraConfig { eye, center, up: Vec3::new(0.0, 1.0, 0.0), orthographic: false });
+        self
+    }
+
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        (self.width, self.height) = (width, height);
+        self
+    }
+
+    pub fn options(mut self, options: RenderOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+// Todos los casos; para agregar uno basta una línea acá y correr con BLESS=1
+pub fn golden_cases() -> Vec<GoldenCase> {
+    let options = RenderOptions::default();
+    vec![
+        GoldenCase::new("sol", 1, 120.0),
+        GoldenCase::new("tierra", 2, 120.0),
+        GoldenCase::new("gaseoso_cuadricula", 3, 60.0).options(RenderOptions { graticule: true, ..options }),
+        GoldenCase::new("anillos", 4, 60.0),
+        GoldenCase::new("anillos_particulas", 4, 60.0).options(RenderOptions { particle_rings: true, ..options }),
+        GoldenCase::new("anillos_estrellas", 4, 60.0).options(RenderOptions { starfield: true, ..options }),
+        GoldenCase::new("anillos_saturno", 4, 60.0).options(RenderOptions { saturn_rings: true, ..options }),
+        GoldenCase::new("rocoso", 5, 200.0),
+        GoldenCase::new("rocoso_pesos_triplanares", 5, 200.0).options(RenderOptions { debug_mode: 3, ..options }),
+        GoldenCase::new("hielo", 6, 60.0),
+        GoldenCase::new("volcanico_caricatura", 7, 60.0).options(RenderOptions { toon: true, ..options }),
+        GoldenCase::new("volcanico_infrarrojo", 7, 60.0).options(RenderOptions { thermal: true, ..options }),
+        GoldenCase::new("sistema_solar", 8, 600.0),
+        GoldenCase::new("sistema_solar_arriba", 8, 600.0).camera(Vec3::new(0.0, 20.0, 8.0), Vec3::new(0.0, 0.0, 0.0)),
+        GoldenCase::new("gravedad", 9, 0.0),
+    ]
+}
+
+// Un pixel cuenta como distinto si algún canal cambia más que `channel_delta` (así el redondeo de un
+// bit no cuenta) y el caso falla si hay más de `pixels` pixeles distintos
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tolerance {
+    pub channel_delta: u8,
+    pub pixels: usize,
+}
+
+pub const GOLDEN_TOLERANCE: Tolerance = Tolerance { channel_delta: 8, pixels: 0 };
+
+// Diferencia entre dos imágenes: el cambio más grande en un canal, cuántos pixeles pasan la tolerancia
+// y el rectángulo que los contiene
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImageDiff {
+    pub max_delta: u8,
+    pub differing: usize,
+    pub bounds: Option<Rect>,
+}
+
+impl ImageDiff {
+    pub fn passes(&self, tolerance: &Tolerance) -> bool {
+        self.differing <= tolerance.pixels
+    }
+}
+
+fn channel_delta(a: u32, b: u32) -> u8 {
+    a.to_be_bytes().iter().zip(b.to_be_bytes()).map(|(a, b)| a.abs_diff(b)).max().unwrap_or(0)
+}
+
+// Si los tamaños no coinciden cuentan como distintos todos los pixeles de `actual`
+pub fn compare_images(expected: &Framebuffer, actual: &Framebuffer, tolerance: &Tolerance) -> ImageDiff {
+    if (expected.width, expected.height) != (actual.width, actual.height) {
+        return ImageDiff { max_delta: u8::MAX, differing: actual.buffer.len(), bounds: Some(actual.rect()) };
+    }
+
+    let mut diff = ImageDiff::default();
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+    for (index, (&a, &b)) in expected.buffer.iter().zip(&actual.buffer).enumerate() {
+        let delta = channel_delta(a, b);
+        diff.max_delta = diff.max_delta.max(delta);
+        if delta > tolerance.channel_delta {
+            let (x, y) = (index % actual.width, index / actual.width);
+            diff.differing += 1;
+            (min_x, min_y) = (min_x.min(x), min_y.min(y));
+            (max_x, max_y) = (max_x.max(x), max_y.max(y));
+        }
+    }
+    diff.bounds = (diff.differing > 0).then(|| Rect::new(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1));
+    diff
+}
+
+// Color de los pixeles distintos en la imagen de diferencias
+pub const GOLDEN_DIFF_COLOR: Color = Color::new(255, 0, 0);
+
+// La imagen nueva oscurecida con los pixeles que pasan la tolerancia en rojo puro
+pub fn diff_image(expected: &Framebuffer, actual: &Framebuffer, tolerance: &Tolerance) -> Framebuffer {
+    let same_size = (expected.width, expected.height) == (actual.width, actual.height);
+    let mut image = Framebuffer::new(actual.width, actual.height);
+    for (index, pixel) in image.buffer.iter_mut().enumerate() {
+        let differs = !same_size || channel_delta(expected.buffer[index], actual.buffer[index]) > tolerance.channel_delta;
+        let color = if differs { GOLDEN_DIFF_COLOR } else { Color::from_hex(actual.buffer[index]) * DIFF_DIM };
+        *pixel = color.to_hex_with_threshold(0.5);
+    }
+    image
+}
+
+// El caso dibujado como lo haría --record en el mismo instante (con el contorno, la oclusión ambiental
+// y la corrección de color de la escena)
+pub fn render_case(case: &GoldenCase, meshes: &SceneMeshes) -> Framebuffer {
+    let scene = SceneConfig::builtin(case.scene);
+    let camera = case.camera.map_or_else(|| start_camera(&scene, meshes), |camera| camera.camera());
+    let mut state = SceneState::new(&scene, case.options.seed, meshes);
+    state.update(case.time);
+
+    let depth_range = scene_depth_range(&state.bodies_seen_from(&scene, case.time, camera.eye, meshes), meshes, &camera);
+    let uniforms = build_uniforms(&camera, case.time, case.width, case.height, depth_range);
+    let mut framebuffer = Framebuffer::new(case.width, case.height);
+    framebuffer.set_background_color(scene.background);
+    render_scene(&mut framebuffer, case.scene, &scene, &uniforms, meshes, &mut state, &case.options);
+    if case.options.toon {
+        post::outline(&mut framebuffer, &uniforms.projection_matrix, OUTLINE_THRESHOLD, OUTLINE_COLOR);
+    }
+    if case.options.ambient_occlusion {
+        post::ambient_occlusion(&mut framebuffer, &uniforms.projection_matrix, &scene.ambient_occlusion);
+    }
+    post::color_grade(&mut framebuffer, &scene.grade);
+    framebuffer
+}
+
+// Resultado de un caso, con el mismo formato que las revisiones de los shaders
+pub struct GoldenResult {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+// Dónde se guardan la referencia, la imagen nueva y la de diferencias de un caso
+pub fn golden_paths(dir: &Path, name: &str) -> [PathBuf; 3] {
+    ["", "_actual", "_diff"].map(|suffix| dir.join(format!("{}{}.png", name, suffix)))
+}
+
+// Dibuja el caso y lo compara con su referencia. Si falla deja <nombre>_actual.png y <nombre>_diff.png
+// al lado de la referencia; si pasa (o con `bless`) borra los que hayan quedado de antes
+pub fn run_case(case: &GoldenCase, meshes: &SceneMeshes, dir: &Path, bless: bool, tolerance: &Tolerance) -> GoldenResult {
+    let result = |passed: bool, detail: String| GoldenResult { name: case.name.to_string(), passed, detail };
+    let [golden_path, actual_path, diff_path] = golden_paths(dir, case.name);
+    let actual = render_case(case, meshes);
+
+    if bless {
+        let saved = fs::create_dir_all(dir).and_then(|_| save_png(&actual, &golden_path));
+        return match saved {
+            Ok(()) => {
+                remove_stale(&[&actual_path, &diff_path]);
+                result(true, format!("referencia nueva en {}", golden_path.display()))
+            }
+            Err(err) => result(false, format!("no se pudo guardar {}: {}", golden_path.display(), err)),
+        };
+    }
+
+    let expected = match load_png(&golden_path) {
+        Ok(expected) => expected,
+        Err(err) => {
+            let saved = save_png(&actual, &actual_path).map_or_else(|err| format!(" (tampoco se pudo guardar la imagen nueva: {})", err), |_| String::new());
+            return result(false, format!("no se pudo leer {}: {}; correr con BLESS=1 para crearla{}", golden_path.display(), err, saved));
+        }
+    };
+
+    let diff = compare_images(&expected, &actual, tolerance);
+    if diff.passes(tolerance) {
+        remove_stale(&[&actual_path, &diff_path]);
+        return result(true, format!("diferencia máxima {}", diff.max_delta));
+    }
+
+    let saved = save_png(&actual, &actual_path).and_then(|_| save_png(&diff_image(&expected, &actual, tolerance), &diff_path));
+    let bounds = diff.bounds.map_or(String::new(), |bounds| format!(" en x {}..{}, y {}..{}", bounds.x, bounds.x + bounds.width, bounds.y, bounds.y + bounds.height));
+    let artifacts = match saved {
+        Ok(()) => format!("ver {} y {}", actual_path.display(), diff_path.display()),
+        Err(err) => format!("no se pudieron guardar las imágenes: {}", err),
+    };
+    result(false, format!("{} pixeles distintos{} (diferencia máxima {}); {}", diff.differing, bounds, diff.max_delta, artifacts))
+}
+
+fn remove_stale(paths: &[&Path]) {
+    for path in paths {
+        // Si no estaba no hay nada que hacer
+        let _ = fs::remove_file(path);
+    }
+}
+
+pub fn run_golden(meshes: &SceneMeshes, dir: &Path, bless: bool) -> Vec<GoldenResult> {
+    golden_cases().iter()
+        .map(|case| run_case(case, meshes, dir, bless, &GOLDEN_TOLERANCE))
+        .collect()
+}
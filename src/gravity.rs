@@ -0,0 +1,135 @@
+use nalgebra_glm::{DVec3, Vec3};
+use std::collections::VecDeque;
+use crate::scene::SceneConfig;
+
+// Constante de gravedad de las escenas con física, en unidades³ / (masa · tick²): con un sol de masa 4
+// un planeta a 3 unidades da una vuelta en unos 500 ticks
+pub const GRAVITY: f64 = 0.001;
+// Paso fijo de la integración en ticks. No depende de los FPS ni de la velocidad del reloj: a x16 se
+// dan más pasos por frame, no pasos más largos
+pub const PHYSICS_STEP: f64 = 1.0 / 16.0;
+// Distancia que se suma al calcular la atracción para que dos cuerpos que se atraviesan no se disparen;
+// los choques se ignoran y cada cuerpo sigue de largo
+const SOFTENING: f64 = 0.05;
+// Un punto de la estela cada TRAIL_INTERVAL pasos (2 ticks) y como mucho TRAIL_POINTS por cuerpo
+const TRAIL_INTERVAL: u64 = 32;
+pub const TRAIL_POINTS: usize = 400;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct PointMass {
+    mass: f64,
+    position: DVec3,
+    velocity: DVec3,
+}
+
+// Gravedad newtoniana entre todos los cuerpos de una escena, integrada con velocity Verlet. La
+// simulación solo avanza cuando el reloj avanza: en pausa o en reversa se queda quieta, y como el paso
+// es fijo la misma secuencia de tiempos da siempre las mismas trayectorias
+#[derive(Clone, Debug)]
+pub struct Simulation {
+    bodies: Vec<PointMass>,
+    accelerations: Vec<DVec3>,
+    // Ticks que avanzó el reloj desde el inicio (solo hacia adelante) y pasos ya integrados
+    elapsed: f64,
+    steps: u64,
+    last_time: Option<f32>,
+    trails: Vec<VecDeque<Vec3>>,
+}
+
+impl Simulation {
+    // Cada cuerpo empieza donde lo pondría su órbita (o su posición fija) en el instante 0, con su
+    // `mass` y su `velocity`; los que no tienen masa se mueven por la gravedad de los demás sin atraerlos
+    pub fn new(scene: &SceneConfig) -> Self {
+        let bodies: Vec<PointMass> = scene.bodies.iter()
+            .map(|body| PointMass {
+                mass: body.mass.max(0.0) as f64,
+                position: to_f64(body.at(0.0).translation),
+                velocity: to_f64(body.velocity),
+            })
+            .collect();
+        let accelerations = accelerations(&bodies);
+        let trails = bodies.iter().map(|body| VecDeque::from([to_f32(body.position)])).collect();
+        Simulation { bodies, accelerations, elapsed: 0.0, steps: 0, last_time: None, trails }
+    }
+
+    // Avanza lo que avanzó el reloj desde la última llamada; la primera llamada solo marca el inicio
+    pub fn update(&mut self, time: f32) {
+        if let Some(last_time) = self.last_time {
+            self.elapsed += (time - last_time).max(0.0) as f64;
+        }
+        self.last_time = Some(time);
+
+        let target = (self.elapsed / PHYSICS_STEP).floor() as u64;
+        while self.steps < target {
+            self.step();
+        }
+    }
+
+    fn step(&mut self) {
+        let dt = PHYSICS_STEP;
+        for (body, acceleration) in self.bodies.iter_mut().zip(&self.accelerations) {
+            body.velocity += acceleration * (dt / 2.0);
+            body.position += body.velocity * dt;
+        }
+        self.accelerations = accelerations(&self.bodies);
+        for (body, acceleration) in self.bodies.iter_mut().zip(&self.accelerations) {
+            body.velocity += acceleration * (dt / 2.0);
+        }
+
+        self.steps += 1;
+        if self.steps.is_multiple_of(TRAIL_INTERVAL) {
+            for (trail, body) in self.trails.iter_mut().zip(&self.bodies) {
+                if trail.len() == TRAIL_POINTS {
+                    trail.pop_front();
+                }
+                trail.push_back(to_f32(body.position));
+            }
+        }
+    }
+
+    pub fn position(&self, index: usize) -> Option<Vec3> {
+        self.bodies.get(index).map(|body| to_f32(body.position))
+    }
+
+    // Posiciones pasadas de cada cuerpo, de la más vieja a la más nueva
+    pub fn trails(&self) -> &[VecDeque<Vec3>] {
+        &self.trails
+    }
+
+    // Energía cinética más potencial (con el mismo suavizado que la fuerza): con un integrador estable
+    // casi no cambia, así que sirve para ver que la simulación no se dispara
+    pub fn energy(&self) -> f64 {
+        let kinetic: f64 = self.bodies.iter().map(|body| 0.5 * body.mass * body.velocity.magnitude_squared()).sum();
+        let mut potential = 0.0;
+        for (index, body) in self.bodies.iter().enumerate() {
+            for other in &self.bodies[index + 1..] {
+                let distance = ((other.position - body.position).magnitude_squared() + SOFTENING * SOFTENING).sqrt();
+                potential -= GRAVITY * body.mass * other.mass / distance;
+            }
+        }
+        kinetic + potential
+    }
+}
+
+fn accelerations(bodies: &[PointMass]) -> Vec<DVec3> {
+    bodies.iter()
+        .map(|body| {
+            bodies.iter()
+                .filter(|other| other.mass > 0.0 && !std::ptr::eq(*other, body))
+                .map(|other| {
+                    let offset = other.position - body.position;
+                    let distance_squared = offset.magnitude_squared() + SOFTENING * SOFTENING;
+                    offset * (GRAVITY * other.mass / (distance_squared * distance_squared.sqrt()))
+                })
+                .fold(DVec3::zeros(), |sum, acceleration| sum + acceleration)
+        })
+        .collect()
+}
+
+fn to_f64(vector: Vec3) -> DVec3 {
+    DVec3::new(vector.x as f64, vector.y as f64, vector.z as f64)
+}
+
+fn to_f32(vector: DVec3) -> Vec3 {
+    Vec3::new(vector.x as f32, vector.y as f32, vector.z as f32)
+}
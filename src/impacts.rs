@@ -0,0 +1,63 @@
+use nalgebra_glm::Vec3;
+use crate::rng;
+use crate::shaders::{moon_craters, sphere_direction};
+
+// Probabilidad de un impacto en cada tick (uno cada ~7 segundos a 60 FPS)
+const IMPACT_CHANCE: f32 = 1.0 / 400.0;
+// Ticks que dura el destello antes de dejar el cráter
+pub const FLASH_DURATION: u32 = 30;
+// Cráteres máximos; al pasarse se borra el más viejo
+const MAX_CRATERS: usize = 32;
+
+// Cráteres y destellos de una luna; cambia con el tiempo y se reinicia con la escena
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoonState {
+    pub craters: Vec<(Vec3, f32)>,        // Dirección desde el centro y radio angular
+    pub active_flashes: Vec<(Vec3, u32)>, // Dirección y tick del impacto
+    seed: u32,
+    last_update: u32,
+}
+
+impl MoonState {
+    // Empieza con los mismos cráteres que la luna sin impactos
+    pub fn new(seed: u32) -> Self {
+        MoonState {
+            craters: moon_craters(seed).to_vec(),
+            active_flashes: Vec::new(),
+            seed,
+            last_update: 0,
+        }
+    }
+
+    // Avanza los impactos tick por tick hasta `time`, así el resultado no depende de los FPS;
+    // si el tiempo retrocede se vuelve a calcular desde cero y se llega al mismo estado
+    pub fn update(&mut self, time: f32) {
+        let time = time.max(0.0) as u32;
+        if time < self.last_update {
+            *self = MoonState::new(self.seed);
+        }
+
+        let impact_seed = rng::combine(self.seed, 0x1A9AC7);
+        for tick in self.last_update + 1..=time {
+            let event_seed = rng::combine(impact_seed, tick);
+            if rng::hash1(event_seed, 0) < IMPACT_CHANCE {
+                let position = sphere_direction(rng::hash1(event_seed, 1), rng::hash1(event_seed, 2));
+                self.active_flashes.push((position, tick));
+            }
+
+            // Cuando el destello se apaga queda el cráter
+            while let Some(&(position, start)) = self.active_flashes.first() {
+                if tick - start < FLASH_DURATION {
+                    break;
+                }
+                let radius = 0.16 + rng::hash1(rng::combine(impact_seed, start), 3) * 0.24;
+                self.craters.push((position, radius));
+                if self.craters.len() > MAX_CRATERS {
+                    self.craters.remove(0);
+                }
+                self.active_flashes.remove(0);
+            }
+        }
+        self.last_update = time;
+    }
+}
@@ -0,0 +1,49 @@
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::text::{draw_text, line_height, text_width, wrap_text, GLYPH_HEIGHT};
+
+// Tarjeta en la esquina superior derecha: ancho máximo, margen con el borde de la ventana y relleno interior
+const CARD_WIDTH: usize = 240;
+const CARD_MARGIN: usize = 10;
+const CARD_PADDING: usize = 8;
+const CARD_CORNER: usize = 4;
+const CARD_BACKGROUND: Color = Color::new(12, 14, 20);
+const CARD_OPACITY: f32 = 0.75;
+const TITLE_COLOR: Color = Color::new(255, 214, 120);
+const TEXT_COLOR: Color = Color::new(225, 228, 235);
+// Renglón que reemplaza a los que no entran en el alto de la ventana
+const ELLIPSIS: &str = "...";
+
+// Dibuja los renglones de scene::body_info sobre un panel oscuro translúcido, con el primero (el nombre)
+// resaltado. Los largos se parten en palabras conservando la sangría y lo que no entra en la ventana se
+// corta con "..."; devuelve la zona tapada o None si la ventana es demasiado chica
+pub fn draw_info_card(framebuffer: &mut Framebuffer, lines: &[String]) -> Option<Rect> {
+    let width = CARD_WIDTH.min(framebuffer.width.saturating_sub(2 * CARD_MARGIN));
+    let inner_width = width.saturating_sub(2 * CARD_PADDING);
+    let max_height = framebuffer.height.saturating_sub(2 * CARD_MARGIN);
+    let max_rows = (max_height.saturating_sub(2 * CARD_PADDING) + line_height(1) - GLYPH_HEIGHT) / line_height(1);
+    if lines.is_empty() || inner_width < text_width(ELLIPSIS, 1) || max_rows == 0 {
+        return None;
+    }
+
+    // Cada renglón partido al ancho, con la marca de si es el título
+    let mut rows: Vec<(String, bool)> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let wrapped = wrap_text(line.trim_start(), inner_width.saturating_sub(text_width(indent, 1)), 1);
+        rows.extend(wrapped.into_iter().map(|text| (format!("{}{}", indent, text), index == 0)));
+    }
+    if rows.len() > max_rows {
+        rows.truncate(max_rows - 1);
+        rows.push((ELLIPSIS.to_string(), false));
+    }
+
+    let height = 2 * CARD_PADDING + (rows.len() - 1) * line_height(1) + GLYPH_HEIGHT;
+    let card = Rect::new(framebuffer.width - CARD_MARGIN - width, CARD_MARGIN, width, height);
+    framebuffer.blend_rect(card, CARD_BACKGROUND, CARD_OPACITY, CARD_CORNER);
+    for (row, (text, title)) in rows.iter().enumerate() {
+        let color = if *title { TITLE_COLOR } else { TEXT_COLOR };
+        draw_text(framebuffer, card.x + CARD_PADDING, card.y + CARD_PADDING + row * line_height(1), text, color, 1);
+    }
+    Some(card)
+}
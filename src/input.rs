@@ -0,0 +1,192 @@
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use lab4_shaders::keymap::{Action, KeyMap, Trigger};
+
+// Primera línea de los archivos de --record-input; si cambia el formato se cambia la versión
+const LOG_HEADER: &str = "# lab4-shaders input v1";
+
+// Todo lo que el programa lee del teclado y del mouse en un frame; el bucle principal solo usa esto,
+// así una sesión grabada se puede repetir sin ventana
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputFrame {
+    pub actions: Vec<Action>,
+    pub shift: bool,
+    pub ctrl: bool,
+    // Posición del mouse en la ventana (None fuera de ella)
+    pub mouse: Option<(f32, f32)>,
+    pub left_button: bool,
+    // Botón derecho o central
+    pub pan_button: bool,
+    pub scroll: Option<f32>,
+    // Se presionó alguna tecla en este frame
+    pub key_pressed: bool,
+    // Entrada que detiene el modo demo: teclas (salvo las de la demo), botones del mouse o la rueda
+    pub manual: bool,
+}
+
+impl InputFrame {
+    pub fn read(window: &Window, keymap: &KeyMap) -> Self {
+        let pressed = window.get_keys_pressed(KeyRepeat::No);
+        let demo_keys = keymap.keys_for(Action::ToggleDemo);
+        let left_button = window.get_mouse_down(MouseButton::Left);
+        let pan_button = window.get_mouse_down(MouseButton::Right) || window.get_mouse_down(MouseButton::Middle);
+        let scroll = window.get_scroll_wheel().map(|(_, y)| y);
+        InputFrame {
+            actions: triggered(keymap, window),
+            shift: window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift),
+            ctrl: window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl),
+            mouse: window.get_mouse_pos(MouseMode::Discard),
+            left_button,
+            pan_button,
+            scroll,
+            key_pressed: !pressed.is_empty(),
+            manual: pressed.iter().any(|key| !demo_keys.contains(key)) || left_button || pan_button || scroll.is_some(),
+        }
+    }
+
+    pub fn triggered(&self, action: Action) -> bool {
+        self.actions.contains(&action)
+    }
+
+    // Una línea: frame, tiempo, banderas, mouse, rueda y acciones; "-" cuando no hay nada
+    // (por ejemplo `120 120 SL 412.5,300 - orbit_left,scene_3`)
+    fn to_line(&self, frame: u64, time: f32) -> String {
+        let flags: String = [(self.shift, 'S'), (self.ctrl, 'C'), (self.left_button, 'L'), (self.pan_button, 'P'), (self.key_pressed, 'K'), (self.manual, 'M')]
+            .iter()
+            .filter_map(|&(set, flag)| set.then_some(flag))
+            .collect();
+        let mouse = self.mouse.map_or("-".to_string(), |(x, y)| format!("{},{}", x, y));
+        let scroll = self.scroll.map_or("-".to_string(), |y| y.to_string());
+        let actions = self.actions.iter().map(|action| action.name()).collect::<Vec<_>>().join(",");
+        let or_dash = |text: String| if text.is_empty() { "-".to_string() } else { text };
+        format!("{} {} {} {} {} {}", frame, time, or_dash(flags), mouse, scroll, or_dash(actions))
+    }
+
+    // Devuelve también el tiempo grabado, para avisar si la repetición se desincroniza
+    fn from_line(line: &str) -> Result<(InputFrame, f32), String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [_, time, flags, mouse, scroll, actions] = fields[..] else {
+            return Err(format!("se esperaban 6 campos y hay {}", fields.len()));
+        };
+        let number = |text: &str| text.parse::<f32>().map_err(|_| format!("número inválido: {}", text));
+        let has = |flag: char| flags.contains(flag);
+
+        let mouse = match mouse {
+            "-" => None,
+            _ => {
+                let (x, y) = mouse.split_once(',').ok_or(format!("posición del mouse inválida: {}", mouse))?;
+                Some((number(x)?, number(y)?))
+            }
+        };
+        let scroll = match scroll {
+            "-" => None,
+            _ => Some(number(scroll)?),
+        };
+        let actions = match actions {
+            "-" => Vec::new(),
+            _ => actions.split(',')
+                .map(|name| Action::from_name(name).ok_or(format!("acción desconocida: {}", name)))
+                .collect::<Result<_, _>>()?,
+        };
+
+        let frame = InputFrame {
+            actions,
+            shift: has('S'),
+            ctrl: has('C'),
+            mouse,
+            left_button: has('L'),
+            pan_button: has('P'),
+            scroll,
+            key_pressed: has('K'),
+            manual: has('M'),
+        };
+        Ok((frame, number(time)?))
+    }
+}
+
+// Acciones de este frame: las de mantener mientras la tecla está abajo y las demás solo al presionarla
+// (o repitiendo si la acción lo permite), cada una a lo sumo una vez
+fn triggered(keymap: &KeyMap, window: &Window) -> Vec<Action> {
+    keymap.bindings().iter()
+        .filter(|(action, keys)| keys.iter().any(|&key| match action.trigger() {
+            Trigger::Held => window.is_key_down(key),
+            Trigger::Press => window.is_key_pressed(key, KeyRepeat::No),
+            Trigger::Repeat => window.is_key_pressed(key, KeyRepeat::Yes),
+        }))
+        .map(|(action, _)| *action)
+        .collect()
+}
+
+// Escribe una línea por frame en el archivo de --record-input
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+    frame: u64,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", LOG_HEADER)?;
+        Ok(InputRecorder { writer, frame: 0 })
+    }
+
+    // `time` es el tiempo de la simulación con el que se dibujó el frame
+    pub fn write(&mut self, input: &InputFrame, time: f32) -> io::Result<()> {
+        self.frame += 1;
+        writeln!(self.writer, "{}", input.to_line(self.frame, time))
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// Frames leídos de un archivo de --record-input, en orden
+pub struct InputReplay {
+    frames: Vec<(InputFrame, f32)>,
+    next: usize,
+    desync_reported: bool,
+}
+
+impl InputReplay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| err.to_string())?;
+        let mut lines = BufReader::new(file).lines();
+        match lines.next() {
+            Some(Ok(header)) if header == LOG_HEADER => {}
+            _ => return Err(format!("no es un archivo de entrada grabada (falta \"{}\")", LOG_HEADER)),
+        }
+        let frames = lines
+            .enumerate()
+            .map(|(index, line)| {
+                let line = line.map_err(|err| err.to_string())?;
+                InputFrame::from_line(&line).map_err(|err| format!("línea {}: {}", index + 2, err))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(InputReplay { frames, next: 0, desync_reported: false })
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    // Entrada del siguiente frame; None al terminar la grabación
+    pub fn next_frame(&mut self) -> Option<InputFrame> {
+        let (input, _) = self.frames.get(self.next)?;
+        self.next += 1;
+        Some(input.clone())
+    }
+
+    // Compara el tiempo del frame que se acaba de repetir con el grabado; avisa solo la primera vez
+    pub fn check_time(&mut self, time: f32) {
+        let Some((_, recorded)) = self.next.checked_sub(1).and_then(|index| self.frames.get(index)) else {
+            return;
+        };
+        if time != *recorded && !self.desync_reported {
+            eprintln!("Aviso: la repetición se desincronizó en el frame {} (tiempo {} en lugar de {})", self.next, time, recorded);
+            self.desync_reported = true;
+        }
+    }
+}
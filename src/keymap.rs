@@ -0,0 +1,470 @@
+use minifb::Key;
+use std::path::Path;
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::text::{self, draw_text, line_height};
+
+// Archivo opcional con las teclas cambiadas, en el directorio desde donde se corre el programa
+pub const KEYBINDINGS_FILE: &str = "keybindings.toml";
+
+// Todo lo que se puede hacer con el teclado
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    ToggleHelp,
+    Scene(u32),
+    OrbitLeft,
+    OrbitRight,
+    OrbitUp,
+    OrbitDown,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    FrameScene,
+    ToggleProjection,
+    Faster,
+    Slower,
+    ReverseTime,
+    Pause,
+    ResetTime,
+    CycleDebugMode,
+    ToggleDither,
+    ToggleLodColors,
+    ToggleDepth,
+    ToggleDemo,
+    ToggleFrameDiff,
+    PrintStats,
+    ReloadScene,
+    ExportMesh,
+    Screenshot,
+    ToggleGraticule,
+    ToggleParticleRings,
+    ToggleMinimap,
+    ToggleAmbientOcclusion,
+    ToggleStarfield,
+    ToggleThermal,
+    ToggleAnaglyph,
+    StereoNarrower,
+    StereoWider,
+    ToggleMotionBlur,
+    ToggleToon,
+    ToggleProgressive,
+    ToggleCheckerboard,
+    ToggleStill,
+    ToggleLensFlare,
+    ToggleLoupe,
+    ToggleLabels,
+    ToggleInfoCard,
+    CameraPathKeyframe,
+    Gallery,
+    GalleryPrevious,
+    GalleryNext,
+    GalleryChoose,
+    GradeUp,
+    GradeDown,
+    ResetGrade,
+    ToggleLut,
+    SaveSession,
+}
+
+// Cuándo se dispara una acción: mientras la tecla está abajo, una vez por pulsación o repitiendo
+// si se mantiene (como al escribir)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trigger {
+    Held,
+    Press,
+    Repeat,
+}
+
+impl Action {
+    pub fn trigger(self) -> Trigger {
+        match self {
+            Action::OrbitLeft | Action::OrbitRight | Action::OrbitUp | Action::OrbitDown
+            | Action::PanLeft | Action::PanRight | Action::PanUp | Action::PanDown
+            | Action::ZoomIn | Action::ZoomOut | Action::Gallery => Trigger::Held,
+            Action::StereoNarrower | Action::StereoWider | Action::GradeUp | Action::GradeDown
+            | Action::GalleryPrevious | Action::GalleryNext => Trigger::Repeat,
+            _ => Trigger::Press,
+        }
+    }
+
+    // Nombre en keybindings.toml
+    pub fn name(self) -> String {
+        let name = match self {
+            Action::Scene(number) => return format!("scene_{}", number),
+            Action::Quit => "quit",
+            Action::ToggleHelp => "toggle_help",
+            Action::OrbitLeft => "orbit_left",
+            Action::OrbitRight => "orbit_right",
+            Action::OrbitUp => "orbit_up",
+            Action::OrbitDown => "orbit_down",
+            Action::PanLeft => "pan_left",
+            Action::PanRight => "pan_right",
+            Action::PanUp => "pan_up",
+            Action::PanDown => "pan_down",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::FrameScene => "frame_scene",
+            Action::ToggleProjection => "toggle_projection",
+            Action::Faster => "faster",
+            Action::Slower => "slower",
+            Action::ReverseTime => "reverse_time",
+            Action::Pause => "pause",
+            Action::ResetTime => "reset_time",
+            Action::CycleDebugMode => "cycle_debug_mode",
+            Action::ToggleDither => "toggle_dither",
+            Action::ToggleLodColors => "toggle_lod_colors",
+            Action::ToggleDepth => "toggle_depth",
+            Action::ToggleDemo => "toggle_demo",
+            Action::ToggleFrameDiff => "toggle_frame_diff",
+            Action::PrintStats => "print_stats",
+            Action::ReloadScene => "reload_scene",
+            Action::ExportMesh => "export_mesh",
+            Action::Screenshot => "screenshot",
+            Action::ToggleGraticule => "toggle_graticule",
+            Action::ToggleParticleRings => "toggle_particle_rings",
+            Action::ToggleMinimap => "toggle_minimap",
+            Action::ToggleAmbientOcclusion => "toggle_ambient_occlusion",
+            Action::ToggleStarfield => "toggle_starfield",
+            Action::ToggleThermal => "toggle_thermal",
+            Action::ToggleAnaglyph => "toggle_anaglyph",
+            Action::StereoNarrower => "stereo_narrower",
+            Action::StereoWider => "stereo_wider",
+            Action::ToggleMotionBlur => "toggle_motion_blur",
+            Action::ToggleToon => "toggle_toon",
+            Action::ToggleProgressive => "toggle_progressive",
+            Action::ToggleCheckerboard => "toggle_checkerboard",
+            Action::ToggleStill => "toggle_still",
+            Action::ToggleLensFlare => "toggle_lens_flare",
+            Action::ToggleLoupe => "toggle_loupe",
+            Action::ToggleLabels => "toggle_labels",
+            Action::ToggleInfoCard => "toggle_info_card",
+            Action::CameraPathKeyframe => "camera_path_keyframe",
+            Action::Gallery => "gallery",
+            Action::GalleryPrevious => "gallery_previous",
+            Action::GalleryNext => "gallery_next",
+            Action::GalleryChoose => "gallery_choose",
+            Action::GradeUp => "grade_up",
+            Action::GradeDown => "grade_down",
+            Action::ResetGrade => "reset_grade",
+            Action::ToggleLut => "toggle_lut",
+            Action::SaveSession => "save_session",
+        };
+        name.to_string()
+    }
+
+    // Acción con ese nombre de keybindings.toml
+    pub fn from_name(name: &str) -> Option<Action> {
+        DEFAULT_BINDINGS.iter().map(|(action, _)| *action).find(|action| action.name() == name)
+    }
+
+    // Descripción para la ayuda en pantalla
+    fn label(self) -> String {
+        let label = match self {
+            Action::Scene(number) => return format!("escena {} (ctrl/shift: guardar/volver a la cámara)", number),
+            Action::Quit => "salir",
+            Action::ToggleHelp => "mostrar u ocultar esta ayuda",
+            Action::OrbitLeft => "orbitar a la izquierda",
+            Action::OrbitRight => "orbitar a la derecha",
+            Action::OrbitUp => "orbitar hacia arriba",
+            Action::OrbitDown => "orbitar hacia abajo",
+            Action::PanLeft => "mover el centro a la izquierda",
+            Action::PanRight => "mover el centro a la derecha",
+            Action::PanUp => "mover el centro hacia arriba",
+            Action::PanDown => "mover el centro hacia abajo",
+            Action::ZoomIn => "acercar",
+            Action::ZoomOut => "alejar",
+            Action::FrameScene => "encuadrar la escena",
+            Action::ToggleProjection => "perspectiva u ortográfica",
+            Action::Faster => "tiempo más rápido",
+            Action::Slower => "tiempo más lento",
+            Action::ReverseTime => "invertir el tiempo",
+            Action::Pause => "pausar",
+            Action::ResetTime => "tiempo a cero",
+            Action::CycleDebugMode => "modo de depuración",
+            Action::ToggleDither => "dithering",
+            Action::ToggleLodColors => "colores del nivel de detalle",
+            Action::ToggleDepth => "buffer de profundidad",
+            Action::ToggleDemo => "modo demo",
+            Action::ToggleFrameDiff => "diferencias con el frame anterior",
+            Action::PrintStats => "estadísticas en la terminal",
+            Action::ReloadScene => "recargar el archivo de escena",
+            Action::ExportMesh => "exportar la geometría (.ply)",
+            Action::Screenshot => "captura de pantalla (.png)",
+            Action::ToggleGraticule => "cuadrícula y eje de rotación",
+            Action::ToggleParticleRings => "anillos de partículas",
+            Action::ToggleMinimap => "minimapa",
+            Action::ToggleAmbientOcclusion => "oclusión ambiental",
+            Action::ToggleStarfield => "cielo de estrellas",
+            Action::ToggleThermal => "vista infrarroja (temperatura)",
+            Action::ToggleAnaglyph => "anaglifo rojo-cian",
+            Action::StereoNarrower => "menos separación estéreo",
+            Action::StereoWider => "más separación estéreo",
+            Action::ToggleMotionBlur => "desenfoque de movimiento",
+            Action::ToggleToon => "modo caricatura",
+            Action::ToggleProgressive => "renderizado progresivo",
+            Action::ToggleCheckerboard => "sombrear la mitad de los pixeles (tablero)",
+            Action::ToggleStill => "modo foto",
+            Action::ToggleLensFlare => "lens flare",
+            Action::ToggleLoupe => "lupa (la rueda cambia el aumento)",
+            Action::ToggleLabels => "nombres de los cuerpos",
+            Action::ToggleInfoCard => "tarjeta con los datos del cuerpo",
+            Action::CameraPathKeyframe => "pose del recorrido (ctrl: guardar path.toml, shift: reproducirlo)",
+            Action::Gallery => "galería de escenas (mantener; al soltar cambia)",
+            Action::GalleryPrevious => "galería: escena anterior",
+            Action::GalleryNext => "galería: escena siguiente",
+            Action::GalleryChoose => "galería: cambiar a la elegida",
+            Action::GradeUp => "más exposición (shift contraste, ctrl saturación)",
+            Action::GradeDown => "menos exposición (shift contraste, ctrl saturación)",
+            Action::ResetGrade => "corrección de color de la escena",
+            Action::ToggleLut => "curvas de color (--lut)",
+            Action::SaveSession => "ctrl: guardar la sesión (session.toml)",
+        };
+        label.to_string()
+    }
+}
+
+// Teclas por defecto, en el orden en que aparecen en la ayuda
+const DEFAULT_BINDINGS: &[(Action, &[Key])] = &[
+    (Action::Quit, &[Key::Escape]),
+    (Action::ToggleHelp, &[Key::H]),
+    (Action::Scene(1), &[Key::Key1]),
+    (Action::Scene(2), &[Key::Key2]),
+    (Action::Scene(3), &[Key::Key3]),
+    (Action::Scene(4), &[Key::Key4]),
+    (Action::Scene(5), &[Key::Key5]),
+    (Action::Scene(6), &[Key::Key6]),
+    (Action::Scene(7), &[Key::Key7]),
+    (Action::Scene(8), &[Key::Key8]),
+    (Action::Scene(9), &[Key::Key9]),
+    (Action::OrbitLeft, &[Key::Left]),
+    (Action::OrbitRight, &[Key::Right]),
+    (Action::OrbitUp, &[Key::Up]),
+    (Action::OrbitDown, &[Key::Down]),
+    (Action::PanLeft, &[Key::A]),
+    (Action::PanRight, &[Key::D]),
+    (Action::PanUp, &[Key::W]),
+    (Action::PanDown, &[Key::S]),
+    (Action::ZoomIn, &[Key::M]),
+    (Action::ZoomOut, &[Key::N]),
+    (Action::FrameScene, &[Key::Home]),
+    (Action::ToggleProjection, &[Key::O]),
+    (Action::Faster, &[Key::Equal, Key::NumPadPlus]),
+    (Action::Slower, &[Key::Minus, Key::NumPadMinus]),
+    (Action::ReverseTime, &[Key::R]),
+    (Action::Pause, &[Key::Space]),
+    (Action::ResetTime, &[Key::Key0, Key::NumPad0]),
+    (Action::CycleDebugMode, &[Key::F1]),
+    (Action::ToggleDither, &[Key::F2]),
+    (Action::ToggleLodColors, &[Key::F4]),
+    (Action::ToggleDepth, &[Key::F5]),
+    (Action::ToggleDemo, &[Key::F6]),
+    (Action::ToggleFrameDiff, &[Key::F7]),
+    (Action::PrintStats, &[Key::F8]),
+    (Action::ReloadScene, &[Key::F9]),
+    (Action::ExportMesh, &[Key::F10]),
+    (Action::Screenshot, &[Key::F12]),
+    (Action::ToggleGraticule, &[Key::X]),
+    (Action::ToggleParticleRings, &[Key::Q]),
+    (Action::ToggleMinimap, &[Key::Tab]),
+    (Action::ToggleAmbientOcclusion, &[Key::C]),
+    (Action::ToggleStarfield, &[Key::Y]),
+    (Action::ToggleThermal, &[Key::F3]),
+    (Action::ToggleAnaglyph, &[Key::B]),
+    (Action::StereoNarrower, &[Key::LeftBracket]),
+    (Action::StereoWider, &[Key::RightBracket]),
+    (Action::ToggleMotionBlur, &[Key::V]),
+    (Action::ToggleToon, &[Key::T]),
+    (Action::ToggleProgressive, &[Key::P]),
+    (Action::ToggleCheckerboard, &[Key::F11]),
+    (Action::ToggleStill, &[Key::J]),
+    (Action::ToggleLensFlare, &[Key::L]),
+    (Action::ToggleLoupe, &[Key::Z]),
+    (Action::ToggleLabels, &[Key::E]),
+    (Action::ToggleInfoCard, &[Key::I]),
+    (Action::CameraPathKeyframe, &[Key::K]),
+    (Action::Gallery, &[Key::G]),
+    (Action::GalleryPrevious, &[Key::Left]),
+    (Action::GalleryNext, &[Key::Right]),
+    (Action::GalleryChoose, &[Key::Enter, Key::NumPadEnter]),
+    (Action::GradeUp, &[Key::PageUp]),
+    (Action::GradeDown, &[Key::PageDown]),
+    (Action::ResetGrade, &[Key::End]),
+    (Action::ToggleLut, &[Key::U]),
+    (Action::SaveSession, &[Key::S]),
+];
+
+// Teclas que se pueden asignar; el nombre en el archivo es el de minifb (A, Key1, F5, PageUp, NumPadPlus...)
+const KEYS: [Key; 106] = [
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::F13, Key::F14, Key::F15,
+    Key::Down, Key::Left, Key::Right, Key::Up, Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma,
+    Key::Equal, Key::LeftBracket, Key::Minus, Key::Period, Key::RightBracket, Key::Semicolon, Key::Slash,
+    Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Escape, Key::Home, Key::Insert, Key::Menu,
+    Key::PageDown, Key::PageUp, Key::Pause, Key::Space, Key::Tab, Key::NumLock, Key::CapsLock, Key::ScrollLock,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4, Key::NumPad5, Key::NumPad6,
+    Key::NumPad7, Key::NumPad8, Key::NumPad9, Key::NumPadDot, Key::NumPadSlash, Key::NumPadAsterisk,
+    Key::NumPadMinus, Key::NumPadPlus, Key::NumPadEnter,
+    Key::LeftAlt, Key::RightAlt, Key::LeftSuper, Key::RightSuper,
+];
+
+fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+// Sin distinguir mayúsculas
+fn parse_key(name: &str) -> Option<Key> {
+    KEYS.iter().copied().find(|&key| key_name(key).eq_ignore_ascii_case(name))
+}
+
+// Ayuda en pantalla
+const HELP_MARGIN: usize = 12;
+const HELP_PADDING: usize = 8;
+const HELP_COLUMN_GAP: usize = 16;
+const HELP_BRIGHTNESS: f32 = 0.25;
+const HELP_TITLE_COLOR: Color = Color::new(255, 235, 60);
+const HELP_KEY_COLOR: Color = Color::new(140, 210, 255);
+const HELP_TEXT_COLOR: Color = Color::new(225, 228, 235);
+
+// Teclas asignadas a cada acción, en el orden de la ayuda
+pub struct KeyMap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap {
+            bindings: DEFAULT_BINDINGS.iter().map(|(action, keys)| (*action, keys.to_vec())).collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    // Las teclas por defecto con lo que cambie el archivo; si el archivo no existe quedan las de defecto.
+    // Las advertencias (teclas o acciones desconocidas) se muestran en la terminal
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(KeyMap::default());
+        }
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let (keymap, warnings) = KeyMap::from_toml(&source)?;
+        for warning in warnings {
+            eprintln!("{}: {}", path.display(), warning);
+        }
+        Ok(keymap)
+    }
+
+    // Cada línea del archivo es `acción = "Tecla"` o `acción = ["Tecla", "Otra"]` y reemplaza las teclas
+    // de esa acción; una tecla que pasa a otra acción deja de hacer la anterior. Una misma tecla en
+    // dos acciones del archivo es un error
+    pub fn from_toml(source: &str) -> Result<(Self, Vec<String>), String> {
+        let table: toml::Table = source.parse().map_err(|err: toml::de::Error| err.to_string())?;
+        let mut warnings = Vec::new();
+        let mut overrides: Vec<(Action, Vec<Key>)> = Vec::new();
+
+        for (name, value) in &table {
+            let Some(action) = Action::from_name(name) else {
+                warnings.push(format!("acción desconocida \"{}\"", name));
+                continue;
+            };
+            let names = match value {
+                toml::Value::String(key) => vec![key.as_str()],
+                toml::Value::Array(keys) => keys.iter()
+                    .map(|key| key.as_str().ok_or(format!("{}: las teclas se escriben entre comillas", name)))
+                    .collect::<Result<_, _>>()?,
+                _ => return Err(format!("{}: se esperaba una tecla o una lista de teclas", name)),
+            };
+
+            let mut keys = Vec::new();
+            for key_name in names {
+                match parse_key(key_name) {
+                    Some(key) if !keys.contains(&key) => keys.push(key),
+                    Some(_) => {}
+                    None => warnings.push(format!("{}: tecla desconocida \"{}\"", name, key_name)),
+                }
+            }
+            for &key in &keys {
+                if let Some((other, _)) = overrides.iter().find(|(_, other_keys)| other_keys.contains(&key)) {
+                    return Err(format!("la tecla {} está asignada a {} y a {}", self::key_name(key), other.name(), name));
+                }
+            }
+            overrides.push((action, keys));
+        }
+
+        let mut keymap = KeyMap::default();
+        for (action, keys) in &mut keymap.bindings {
+            if let Some((_, new_keys)) = overrides.iter().find(|(overridden, _)| overridden == action) {
+                *keys = new_keys.clone();
+                continue;
+            }
+            keys.retain(|key| {
+                let taken = overrides.iter().any(|(_, new_keys)| new_keys.contains(key));
+                if taken {
+                    warnings.push(format!("{} ya no hace {}", key_name(*key), action.name()));
+                }
+                !taken
+            });
+        }
+        Ok((keymap, warnings))
+    }
+
+    // Cada acción con sus teclas, en el orden de la ayuda
+    pub fn bindings(&self) -> &[(Action, Vec<Key>)] {
+        &self.bindings
+    }
+
+    pub fn keys_for(&self, action: Action) -> &[Key] {
+        self.bindings.iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    // Lista de teclas y acciones sobre la imagen oscurecida, en las columnas que hagan falta; devuelve el panel
+    pub fn draw_help(&self, framebuffer: &mut Framebuffer) -> Rect {
+        let line = line_height(1);
+        let lines: Vec<(String, String)> = self.bindings.iter()
+            .map(|(action, keys)| {
+                let keys = if keys.is_empty() {
+                    "-".to_string()
+                } else {
+                    keys.iter().map(|&key| key_name(key)).collect::<Vec<_>>().join(" / ")
+                };
+                (keys, action.label())
+            })
+            .collect();
+
+        let panel = Rect::new(
+            HELP_MARGIN,
+            HELP_MARGIN,
+            framebuffer.width.saturating_sub(2 * HELP_MARGIN),
+            framebuffer.height.saturating_sub(2 * HELP_MARGIN),
+        );
+        framebuffer.dim_rect(panel, HELP_BRIGHTNESS);
+        let left = panel.x + HELP_PADDING;
+        let top = panel.y + HELP_PADDING;
+        draw_text(framebuffer, left, top, "Teclas (se cambian en keybindings.toml)", HELP_TITLE_COLOR, 1);
+
+        // Debajo del título; cada columna es tan ancha como sus textos más largos
+        let rows = (panel.height.saturating_sub(2 * HELP_PADDING + 2 * line) / line).max(1);
+        let mut x = left;
+        for column in lines.chunks(rows) {
+            let widest = |text_of: fn(&(String, String)) -> &str| column.iter().map(|line| text::text_width(text_of(line), 1)).max().unwrap_or(0);
+            let key_width = widest(|(keys, _)| keys) + HELP_COLUMN_GAP;
+            for (row, (keys, label)) in column.iter().enumerate() {
+                let y = top + (row + 2) * line;
+                draw_text(framebuffer, x, y, keys, HELP_KEY_COLOR, 1);
+                draw_text(framebuffer, x + key_width, y, label, HELP_TEXT_COLOR, 1);
+            }
+            x += key_width + widest(|(_, label)| label) + HELP_COLUMN_GAP;
+        }
+        framebuffer.outline_rect(panel, HELP_TITLE_COLOR);
+        panel
+    }
+}
@@ -0,0 +1,64 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::pipeline::{project, FrameUniforms};
+use crate::scene::{Body, SceneMeshes};
+use crate::text::{draw_text_blended, text_width, GLYPH_HEIGHT};
+
+const LABEL_COLOR: Color = Color::new(235, 238, 245);
+const SHADOW_COLOR: Color = Color::new(0, 0, 0);
+// Separación entre el borde del cuerpo y la etiqueta, en pixeles
+const LABEL_GAP: f32 = 3.0;
+// Radio en pantalla (pixeles) por debajo del cual la etiqueta se empieza a desvanecer y donde ya no se ve
+const FADE_RADIUS: (f32, f32) = (4.0, 1.5);
+// Opacidad de la etiqueta de un cuerpo tapado por otro
+const HIDDEN_ALPHA: f32 = 0.35;
+// Margen de profundidad para que la superficie del propio cuerpo no cuente como algo delante
+const DEPTH_TOLERANCE: f32 = 1e-3;
+
+// Nombre de cada cuerpo con etiqueta arriba a la derecha de él, después de dibujar la escena. Si algo lo
+// tapa la etiqueta se ve tenue, y se desvanece cuando el cuerpo ocupa apenas unos pixeles
+pub fn draw_labels(framebuffer: &mut Framebuffer, bodies: &[Body], meshes: &SceneMeshes, uniforms: &FrameUniforms) {
+    // Derecha y hacia la cámara en el mundo (filas de la matriz de vista)
+    let right = Vec3::new(uniforms.view_matrix[(0, 0)], uniforms.view_matrix[(0, 1)], uniforms.view_matrix[(0, 2)]);
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+
+    for body in bodies {
+        let Some(label) = &body.label else {
+            continue;
+        };
+        let radius = body.bounding_radius(meshes);
+        let (Some(center), Some(edge)) = (project(uniforms, body.translation), project(uniforms, body.translation + right * radius)) else {
+            continue;
+        };
+        let screen_radius = (edge.x - center.x).hypot(edge.y - center.y);
+        // Fuera de la ventana del todo no hay nada que nombrar
+        if center.x + screen_radius < 0.0 || center.x - screen_radius >= width || center.y + screen_radius < 0.0 || center.y - screen_radius >= height {
+            continue;
+        }
+
+        let (full, gone) = FADE_RADIUS;
+        let mut alpha = ((screen_radius - gone) / (full - gone)).clamp(0.0, 1.0);
+        if alpha <= 0.0 {
+            continue;
+        }
+
+        // Tapado si en el centro hay algo más cerca que el punto del cuerpo más cercano a la cámara
+        let toward_camera = (uniforms.camera_position - body.translation).try_normalize(0.0).unwrap_or(Vec3::zeros());
+        let front = project(uniforms, body.translation + toward_camera * radius).map_or(center.z, |front| front.z);
+        let drawn = framebuffer.get_pixel(center.x as usize, center.y as usize).filter(|_| center.x >= 0.0 && center.y >= 0.0);
+        if drawn.is_some_and(|(_, depth)| depth < front - DEPTH_TOLERANCE) {
+            alpha *= HIDDEN_ALPHA;
+        }
+
+        // Arriba a la derecha, fuera del disco del cuerpo, y siempre dentro de la ventana
+        let offset = screen_radius * std::f32::consts::FRAC_1_SQRT_2 + LABEL_GAP;
+        let label_width = text_width(label, 1) as f32;
+        let x = (center.x + offset).clamp(0.0, (width - label_width - 1.0).max(0.0));
+        let y = (center.y - offset - GLYPH_HEIGHT as f32).clamp(0.0, (height - GLYPH_HEIGHT as f32 - 1.0).max(0.0));
+        let (x, y) = (x as usize, y as usize);
+        draw_text_blended(framebuffer, x + 1, y + 1, label, SHADOW_COLOR, 1, alpha);
+        draw_text_blended(framebuffer, x, y, label, LABEL_COLOR, 1, alpha);
+    }
+}
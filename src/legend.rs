@@ -0,0 +1,61 @@
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::text::{draw_text, draw_text_blended, line_height, text_width, GLYPH_HEIGHT};
+
+// Barra de la vista infrarroja en el borde derecho: ancho, alto como fracción de la ventana y margen
+const BAR_WIDTH: usize = 12;
+const BAR_HEIGHT: f32 = 0.5;
+const MARGIN: usize = 12;
+// Marcas cada cuarto de la escala
+const TICKS: usize = 4;
+const TICK_LENGTH: usize = 4;
+const TEXT_GAP: usize = 4;
+const BORDER_COLOR: Color = Color::new(200, 205, 215);
+const TEXT_COLOR: Color = Color::new(225, 228, 235);
+const SHADOW_COLOR: Color = Color::new(0, 0, 0);
+
+// Degradado de la vista infrarroja de frío (abajo) a caliente (arriba), con las marcas de la temperatura
+// relativa, centrado en el borde derecho. Va sobre la imagen terminada, como los nombres de los cuerpos
+pub fn draw_thermal_legend(framebuffer: &mut Framebuffer, gradient: &[(f32, Color)]) {
+    let height = (framebuffer.height as f32 * BAR_HEIGHT) as usize;
+    let label_room = TICK_LENGTH + TEXT_GAP + text_width("0.00", 1);
+    if height < 2 || framebuffer.width < MARGIN + BAR_WIDTH + label_room {
+        return;
+    }
+    let bar = Rect {
+        x: framebuffer.width - MARGIN - BAR_WIDTH,
+        y: (framebuffer.height - height) / 2,
+        width: BAR_WIDTH,
+        height,
+    };
+
+    for row in 0..bar.height {
+        let t = 1.0 - row as f32 / (bar.height - 1) as f32;
+        let hex = Color::from_gradient(t, gradient).to_hex_with_threshold(0.5);
+        let start = (bar.y + row) * framebuffer.width + bar.x;
+        framebuffer.buffer[start..start + bar.width].fill(hex);
+    }
+    framebuffer.outline_rect(bar, BORDER_COLOR);
+
+    // Marcas a la izquierda de la barra con el valor de cada una
+    let border = BORDER_COLOR.to_hex_with_threshold(0.5);
+    for tick in 0..=TICKS {
+        let y = bar.y + (bar.height - 1) * (TICKS - tick) / TICKS;
+        let start = y * framebuffer.width + bar.x - TICK_LENGTH;
+        framebuffer.buffer[start..bar.x + y * framebuffer.width].fill(border);
+        let label = format!("{:.2}", tick as f32 / TICKS as f32);
+        let x = bar.x - TICK_LENGTH - TEXT_GAP - text_width(&label, 1);
+        let y = y.saturating_sub(GLYPH_HEIGHT / 2);
+        draw_text_blended(framebuffer, x + 1, y + 1, &label, SHADOW_COLOR, 1, 0.8);
+        draw_text(framebuffer, x, y, &label, TEXT_COLOR, 1);
+    }
+
+    // Qué extremo es cuál, arriba y abajo de la barra sin tapar el valor de las marcas de los extremos
+    let hot_y = bar.y.saturating_sub(GLYPH_HEIGHT / 2 + line_height(1));
+    let cold_y = bar.y + bar.height + line_height(1) - GLYPH_HEIGHT / 2;
+    for (text, y) in [("caliente", hot_y), ("frío", cold_y)] {
+        let x = (bar.x + bar.width).saturating_sub(text_width(text, 1));
+        draw_text_blended(framebuffer, x + 1, y + 1, text, SHADOW_COLOR, 1, 0.8);
+        draw_text(framebuffer, x, y, text, TEXT_COLOR, 1);
+    }
+}
@@ -0,0 +1,46 @@
+pub mod anchors;
+pub mod belt;
+pub mod bench;
+pub mod camera;
+pub mod camera_path;
+pub mod checkerboard;
+pub mod clock;
+pub mod color;
+pub mod cubemap;
+pub mod export;
+pub mod fragments;
+pub mod golden;
+pub mod gravity;
+pub mod impacts;
+pub mod info_card;
+pub mod framebuffer;
+pub mod geometry;
+pub mod keymap;
+pub mod labels;
+pub mod legend;
+pub mod lod;
+pub mod loupe;
+pub mod lut;
+pub mod mesh;
+pub mod mesh_cache;
+pub mod minimap;
+pub mod noise;
+pub mod obj_loader;
+pub mod orbit;
+pub mod particles;
+pub mod pipeline;
+pub mod post;
+pub mod quality;
+pub mod redraw;
+pub mod ring_profile;
+pub mod rng;
+pub mod scene;
+pub mod scene_file;
+pub mod session;
+pub mod shader_checks;
+pub mod shaders;
+pub mod text;
+pub mod texture;
+pub mod tiles;
+pub mod triangle;
+pub mod vertex;
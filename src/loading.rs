@@ -0,0 +1,265 @@
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::{Framebuffer, Rect};
+use lab4_shaders::obj_loader::Obj;
+use lab4_shaders::scene::{SceneMeshes, RING_OBJ, SPHERE_LOD_OBJS, SPHERE_OBJ};
+use lab4_shaders::text::{draw_text, line_height, text_width};
+use lab4_shaders::vertex::Vertex;
+
+// Panel centrado abajo con el archivo que se está leyendo y la barra de progreso
+const PANEL_WIDTH: usize = 360;
+const PANEL_MARGIN: usize = 10;
+const PANEL_PADDING: usize = 8;
+const PANEL_BRIGHTNESS: f32 = 0.25;
+const BAR_HEIGHT: usize = 8;
+const BAR_GAP: usize = 6;
+const BORDER_COLOR: Color = Color::new(90, 95, 110);
+const TEXT_COLOR: Color = Color::new(225, 228, 235);
+const ERROR_COLOR: Color = Color::new(255, 120, 100);
+const FILL_COLOR: Color = Color::new(120, 190, 255);
+// Franja que recorre el tramo del archivo actual, así se ve que sigue trabajando aunque uno solo tarde
+const SHIMMER_WIDTH: usize = 24;
+const SHIMMER_SPEED: f32 = 120.0;
+// Cuánto siguen en pantalla los errores después de terminar
+const ERROR_DISPLAY: Duration = Duration::from_secs(6);
+
+// Mallas que manda el hilo de carga a medida que las termina de leer
+enum Asset {
+    // La esfera y el anillo van juntos: si falta alguno se siguen usando las mallas generadas
+    Scene { sphere: Vec<Vertex>, ring: Vec<Vertex> },
+    SphereLod(Vec<Vertex>),
+    Model(Vec<Vertex>),
+}
+
+enum LoadMessage {
+    // Empieza a leer `name`; `done` y `total` en bytes de los archivos de la lista
+    Started { name: String, done: u64, total: u64 },
+    Loaded(Asset),
+    Failed { message: String, model: bool },
+    // Ya se mandó todo; si el canal se corta antes es que el hilo se cayó
+    Finished,
+}
+
+// Lo que cambió al recibir los mensajes de una vuelta del bucle
+#[derive(Default)]
+pub struct LoadUpdate {
+    pub meshes_changed: bool,
+    pub model_loaded: bool,
+    pub model_failed: bool,
+}
+
+// Lectura de los OBJ en otro hilo: la ventana se abre enseguida con las mallas generadas y cada malla se
+// cambia cuando llega. Los errores se muestran en la ventana y se sigue con lo que había, igual que al
+// cargar todo antes de abrirla (scene::load_meshes)
+pub struct AssetLoader {
+    receiver: Option<Receiver<LoadMessage>>,
+    current: String,
+    done: u64,
+    total: u64,
+    started: Instant,
+    errors: Vec<String>,
+    finished: Option<Instant>,
+}
+
+impl AssetLoader {
+    pub fn spawn(model_path: Option<String>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || load_assets(&sender, model_path.as_deref()));
+        AssetLoader {
+            receiver: Some(receiver),
+            current: String::new(),
+            done: 0,
+            total: 0,
+            started: Instant::now(),
+            errors: Vec::new(),
+            finished: None,
+        }
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.receiver.is_some()
+    }
+
+    // Aplica en `meshes` todo lo que llegó desde la última vuelta, sin esperar
+    pub fn poll(&mut self, meshes: &mut SceneMeshes) -> LoadUpdate {
+        let mut update = LoadUpdate::default();
+        while let Some(receiver) = &self.receiver {
+            let message = match receiver.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    // Si el hilo terminó sin avisar (por ejemplo un pánico al leer un archivo) se sigue con lo que hay
+                    self.fail(format!("La carga de {} se interrumpió", self.current));
+                    self.finish();
+                    break;
+                }
+            };
+            match message {
+                LoadMessage::Started { name, done, total } => {
+                    self.current = name;
+                    self.done = done;
+                    self.total = total;
+                }
+                LoadMessage::Loaded(asset) => {
+                    match asset {
+                        Asset::Scene { sphere, ring } => {
+                            meshes.set_sphere_lods(vec![sphere]);
+                            meshes.set_ring(ring);
+                        }
+                        Asset::SphereLod(lod) => meshes.add_sphere_lod(lod),
+                        Asset::Model(model) => {
+                            meshes.set_model(model);
+                            update.model_loaded = true;
+                        }
+                    }
+                    update.meshes_changed = true;
+                }
+                LoadMessage::Failed { message, model } => {
+                    self.fail(message);
+                    update.model_failed |= model;
+                }
+                LoadMessage::Finished => self.finish(),
+            }
+        }
+        update
+    }
+
+    fn finish(&mut self) {
+        self.receiver = None;
+        self.done = self.total;
+        self.finished = Some(Instant::now());
+    }
+
+    fn fail(&mut self, message: String) {
+        eprintln!("{}", message);
+        self.errors.push(message);
+    }
+
+    // Lo que se ve del panel, para saber si hay que volver a dibujarlo
+    pub fn overlay_state(&self) -> Option<(String, u64, usize, u32)> {
+        self.visible().then(|| (self.current.clone(), self.done, self.errors.len(), self.shimmer_offset() as u32))
+    }
+
+    fn visible(&self) -> bool {
+        match self.finished {
+            None => self.is_loading(),
+            Some(finished) => !self.errors.is_empty() && finished.elapsed() < ERROR_DISPLAY,
+        }
+    }
+
+    fn shimmer_offset(&self) -> f32 {
+        if self.is_loading() { self.started.elapsed().as_secs_f32() * SHIMMER_SPEED } else { 0.0 }
+    }
+
+    // Panel con el archivo actual, la barra de progreso y los errores; devuelve la zona que tapó
+    pub fn draw(&self, framebuffer: &mut Framebuffer) -> Option<Rect> {
+        if !self.visible() {
+            return None;
+        }
+        let line = line_height(1);
+        let width = PANEL_WIDTH.min(framebuffer.width.saturating_sub(2 * PANEL_MARGIN));
+        let height = 2 * PANEL_PADDING + line + BAR_GAP + BAR_HEIGHT + self.errors.len() * line;
+        let panel = Rect::new(
+            framebuffer.width.saturating_sub(width) / 2,
+            framebuffer.height.saturating_sub(height + PANEL_MARGIN),
+            width,
+            height,
+        );
+        if width <= 2 * PANEL_PADDING {
+            return None;
+        }
+        framebuffer.dim_rect(panel, PANEL_BRIGHTNESS);
+        framebuffer.outline_rect(panel, BORDER_COLOR);
+
+        let fraction = if self.total == 0 { 0.0 } else { self.done as f32 / self.total as f32 };
+        let (x, y) = (panel.x + PANEL_PADDING, panel.y + PANEL_PADDING);
+        let status = if self.is_loading() { format!("Cargando {}", self.current) } else { "Carga terminada".to_string() };
+        let percent = format!("{:.0}%", fraction * 100.0);
+        draw_text(framebuffer, x, y, &status, TEXT_COLOR, 1);
+        draw_text(framebuffer, (panel.x + panel.width).saturating_sub(PANEL_PADDING + text_width(&percent, 1)), y, &percent, TEXT_COLOR, 1);
+
+        let bar = Rect::new(x, y + line + BAR_GAP, panel.width - 2 * PANEL_PADDING, BAR_HEIGHT);
+        let filled = (bar.width as f32 * fraction) as usize;
+        let fill = FILL_COLOR.to_hex_with_threshold(0.5);
+        let shimmer = FILL_COLOR * 0.6;
+        let tail = bar.width - filled;
+        for row in bar.y..bar.y + bar.height {
+            let start = row * framebuffer.width + bar.x;
+            framebuffer.buffer[start..start + filled].fill(fill);
+            // Lo que falta del archivo actual: una franja que avanza y vuelve a empezar
+            if self.is_loading() && tail > 0 {
+                let offset = filled + self.shimmer_offset() as usize % tail;
+                let end = (offset + SHIMMER_WIDTH).min(bar.width);
+                framebuffer.buffer[start + offset..start + end].fill(shimmer.to_hex_with_threshold(0.5));
+            }
+        }
+        framebuffer.outline_rect(bar, BORDER_COLOR);
+
+        for (index, error) in self.errors.iter().enumerate() {
+            draw_text(framebuffer, x, bar.y + bar.height + BAR_GAP + index * line, error, ERROR_COLOR, 1);
+        }
+        Some(panel)
+    }
+}
+
+// Lee las mallas en orden (la esfera y el anillo, los niveles de la esfera y el modelo) y manda cada una
+// apenas está lista; si la ventana se cerró y nadie recibe, deja de leer
+fn load_assets(sender: &Sender<LoadMessage>, model_path: Option<&str>) {
+    let files: Vec<&str> = [SPHERE_OBJ, RING_OBJ].into_iter().chain(SPHERE_LOD_OBJS).chain(model_path).collect();
+    let sizes: Vec<u64> = files.iter().map(|path| std::fs::metadata(path).map_or(0, |metadata| metadata.len())).collect();
+    let total = sizes.iter().sum();
+    let mut done = 0;
+    let mut load = |index: usize| {
+        let path = files[index];
+        sender.send(LoadMessage::Started { name: path.to_string(), done, total }).ok()?;
+        let result = Obj::load_cached(path);
+        done += sizes[index];
+        Some(result)
+    };
+
+    let scene = match (load(0), load(1)) {
+        (Some(Ok(sphere)), Some(Ok(ring))) => Some(Asset::Scene { sphere, ring }),
+        (Some(Err(err)), _) | (_, Some(Err(err))) => {
+            let message = format!("No se pudieron cargar los modelos ({}), se usan mallas generadas", err);
+            if sender.send(LoadMessage::Failed { message, model: false }).is_err() {
+                return;
+            }
+            None
+        }
+        _ => return,
+    };
+    if let Some(scene) = scene {
+        if sender.send(LoadMessage::Loaded(scene)).is_err() {
+            return;
+        }
+        // Niveles de detalle extra; si no están se usa la esfera completa
+        for (index, path) in SPHERE_LOD_OBJS.iter().enumerate() {
+            let message = match load(2 + index) {
+                Some(Ok(vertices)) => LoadMessage::Loaded(Asset::SphereLod(vertices)),
+                Some(Err(err)) => LoadMessage::Failed { message: format!("No se pudo cargar {}: {}", path, err), model: false },
+                None => return,
+            };
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+    }
+
+    if let Some(path) = model_path {
+        let start = Instant::now();
+        let message = match load(files.len() - 1) {
+            Some(Ok(vertices)) => {
+                eprintln!("Modelo {} cargado en {:.1} ms ({} triángulos)", path, start.elapsed().as_secs_f64() * 1000.0, vertices.len() / 3);
+                LoadMessage::Loaded(Asset::Model(vertices))
+            }
+            Some(Err(err)) => LoadMessage::Failed { message: format!("No se pudo cargar el modelo {}: {}", path, err), model: true },
+            None => return,
+        };
+        if sender.send(message).is_err() {
+            return;
+        }
+    }
+    let _ = sender.send(LoadMessage::Finished);
+}
@@ -0,0 +1,103 @@
+use nalgebra_glm::Mat4;
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::pipeline::is_orthographic;
+
+// Radio mínimo en pixeles para usar cada nivel (0 = malla completa)
+const TIER_MIN_RADIUS: [f32; 2] = [40.0, 12.0];
+// Margen para no saltar entre niveles cuando el cuerpo está cerca del límite
+const HYSTERESIS: f32 = 0.2;
+
+// Radio aproximado en pixeles de una esfera vista a cierta distancia de la cámara
+// (en la proyección ortográfica la distancia no cambia el tamaño)
+pub fn projected_radius(world_radius: f32, distance: f32, projection_matrix: &Mat4, viewport_height: f32) -> f32 {
+    let focal = projection_matrix[(1, 1)].abs();
+    if is_orthographic(projection_matrix) {
+        return world_radius * focal * viewport_height / 2.0;
+    }
+    if distance <= world_radius {
+        return f32::INFINITY;
+    }
+    world_radius / distance * focal * viewport_height / 2.0
+}
+
+pub fn select_tier(current: usize, radius_px: f32) -> usize {
+    let tier = TIER_MIN_RADIUS.iter()
+        .position(|&min| radius_px >= min)
+        .unwrap_or(TIER_MIN_RADIUS.len());
+
+    // Solo se cambia de nivel si se pasa el límite por más del margen
+    let gaining_detail = tier < current && radius_px >= TIER_MIN_RADIUS[current - 1] * (1.0 + HYSTERESIS);
+    let losing_detail = tier > current && radius_px <= TIER_MIN_RADIUS[current] * (1.0 - HYSTERESIS);
+    if gaining_detail || losing_detail {
+        tier
+    } else {
+        current
+    }
+}
+
+// Radio en pixeles con el que se ve un círculo de radio `world_radius` en su punto que está a `distance` de
+// la cámara; para el borde del anillo es lo que importa, porque de cerca se ven sus segmentos aunque el
+// centro esté lejos
+pub fn projected_curvature(world_radius: f32, distance: f32, projection_matrix: &Mat4, viewport_height: f32) -> f32 {
+    let focal = projection_matrix[(1, 1)].abs();
+    if is_orthographic(projection_matrix) {
+        return world_radius * focal * viewport_height / 2.0;
+    }
+    world_radius / distance.max(f32::EPSILON) * focal * viewport_height / 2.0
+}
+
+// Distancia máxima en pixeles entre un segmento del borde del anillo y el círculo
+const RING_CHORD_ERROR: f32 = 0.5;
+
+// Segmentos para que el borde de un círculo de `radius_px` no se aleje del círculo más de RING_CHORD_ERROR:
+// la flecha de cada segmento es r (1 - cos(π / n)) ≈ r π² / (2 n²)
+pub fn ring_segments_needed(radius_px: f32) -> f32 {
+    PI * (radius_px / (2.0 * RING_CHORD_ERROR)).sqrt()
+}
+
+// Nivel del anillo (índice en `segments`, del más fino al más simple). Pasa a uno más fino apenas hace
+// falta, para que nunca se vean los segmentos, y a uno más simple solo cuando le sobra el margen
+pub fn select_ring_tier(current: usize, segments: &[u32], radius_px: f32) -> usize {
+    let needed = ring_segments_needed(radius_px);
+    let coarsest_with = |margin: f32| segments.iter().rposition(|&count| count as f32 * margin >= needed).unwrap_or(0);
+    let current = current.min(segments.len().saturating_sub(1));
+    let finer = coarsest_with(1.0);
+    if finer < current {
+        finer
+    } else {
+        coarsest_with(1.0 - HYSTERESIS).max(current)
+    }
+}
+
+pub fn tier_color(tier: usize) -> Color {
+    match tier {
+        0 => Color::new(0, 255, 0),
+        1 => Color::new(255, 255, 0),
+        _ => Color::new(255, 0, 0),
+    }
+}
+
+// Nivel actual de cada cuerpo de la escena, se conserva entre frames
+#[derive(Default)]
+pub struct LodState {
+    scene_number: u32,
+    tiers: Vec<usize>,
+}
+
+impl LodState {
+    // `select` recibe el nivel actual del cuerpo y devuelve el nuevo
+    pub fn update(&mut self, scene_number: u32, body_index: usize, select: impl Fn(usize) -> usize) -> usize {
+        if self.scene_number != scene_number {
+            self.scene_number = scene_number;
+            self.tiers.clear();
+        }
+        if self.tiers.len() <= body_index {
+            self.tiers.resize(body_index + 1, 0);
+        }
+
+        let tier = select(self.tiers[body_index]);
+        self.tiers[body_index] = tier;
+        tier
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::text::{draw_text, line_height};
+
+// Lado máximo de la lupa en pixeles; el real es un múltiplo del aumento con un número impar de celdas
+const LOUPE_SIZE: usize = 160;
+// Separación entre el cursor y la lupa
+const LOUPE_OFFSET: usize = 20;
+const MAGNIFICATION_RANGE: (usize, usize) = (2, 12);
+const DEFAULT_MAGNIFICATION: usize = 6;
+const BORDER_COLOR: Color = Color::new(200, 205, 215);
+const CROSSHAIR_COLOR: Color = Color::new(255, 235, 60);
+const TEXT_COLOR: Color = Color::new(225, 228, 235);
+const TEXT_PADDING: usize = 4;
+const TEXT_BRIGHTNESS: f32 = 0.25;
+
+// Lupa que sigue al mouse con los pixeles de alrededor agrandados, sin filtrar, y el color y la
+// profundidad exactos del pixel del centro
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Loupe {
+    pub magnification: usize,
+}
+
+impl Default for Loupe {
+    fn default() -> Self {
+        Loupe { magnification: DEFAULT_MAGNIFICATION }
+    }
+}
+
+impl Loupe {
+    // Un paso de aumento por cada movimiento de la rueda, hacia arriba acerca
+    pub fn adjust(&mut self, scroll: f32) {
+        let (min, max) = MAGNIFICATION_RANGE;
+        let step = if scroll > 0.0 { 1 } else if scroll < 0.0 { -1 } else { 0 };
+        self.magnification = self.magnification.saturating_add_signed(step).clamp(min, max);
+    }
+
+    // Con el aumento de un archivo, que puede estar fuera del rango (con 0 no se podría dibujar)
+    pub fn sanitized(self) -> Self {
+        let (min, max) = MAGNIFICATION_RANGE;
+        Loupe { magnification: self.magnification.clamp(min, max) }
+    }
+
+    // Dibuja la lupa al lado del cursor en (x, y), del otro lado si no entra y siempre dentro de la ventana.
+    // Va después de todo lo demás: lo que muestra es la imagen final. Devuelve la zona que tapó
+    pub fn draw(&self, framebuffer: &mut Framebuffer, x: usize, y: usize) -> Option<Rect> {
+        if x >= framebuffer.width || y >= framebuffer.height {
+            return None;
+        }
+        let cells = (LOUPE_SIZE / self.magnification - 1) | 1;
+        let side = cells * self.magnification;
+
+        // Se copian antes de dibujar porque la lupa puede tapar la zona que muestra; en los bordes
+        // se repite el último pixel
+        let half = (cells / 2) as isize;
+        let source: Vec<u32> = (-half..=half)
+            .flat_map(|dy| (-half..=half).map(move |dx| (dx, dy)))
+            .map(|(dx, dy)| {
+                let sx = (x as isize + dx).clamp(0, framebuffer.width as isize - 1) as usize;
+                let sy = (y as isize + dy).clamp(0, framebuffer.height as isize - 1) as usize;
+                framebuffer.buffer[sy * framebuffer.width + sx]
+            })
+            .collect();
+        let (color, depth) = framebuffer.get_pixel(x, y).expect("el cursor está dentro del framebuffer");
+        let [_, r, g, b] = color.to_hex_with_threshold(0.5).to_be_bytes();
+        let depth = if depth == f32::INFINITY { "fondo".to_string() } else { format!("{:.5}", depth) };
+        let lines = [format!("RGB {} {} {}", r, g, b), format!("Z {}", depth)];
+
+        let text_height = lines.len() * line_height(1) + TEXT_PADDING;
+        let (width, height) = (side, side + text_height);
+        let place = |cursor: usize, size: usize, limit: usize| {
+            if cursor + LOUPE_OFFSET + size <= limit {
+                cursor + LOUPE_OFFSET
+            } else {
+                cursor.saturating_sub(LOUPE_OFFSET + size).min(limit.saturating_sub(size))
+            }
+        };
+        let (left, top) = (place(x, width, framebuffer.width), place(y, height, framebuffer.height));
+
+        for (index, &hex) in source.iter().enumerate() {
+            let cell = Rect::new(left + (index % cells) * self.magnification, top + (index / cells) * self.magnification, self.magnification, self.magnification);
+            fill_rect(framebuffer, cell, hex);
+        }
+        let center = half as usize * self.magnification;
+        framebuffer.outline_rect(Rect::new(left + center, top + center, self.magnification, self.magnification), CROSSHAIR_COLOR);
+
+        let panel = Rect::new(left, top + side, width, text_height);
+        framebuffer.dim_rect(panel, TEXT_BRIGHTNESS);
+        for (row, line) in lines.iter().enumerate() {
+            draw_text(framebuffer, left + TEXT_PADDING, top + side + TEXT_PADDING + row * line_height(1), line, TEXT_COLOR, 1);
+        }
+        let area = Rect::new(left, top, width, height);
+        framebuffer.outline_rect(area, BORDER_COLOR);
+        Some(area)
+    }
+}
+
+// Rectángulo de un solo color, recortado al framebuffer y sin tocar la profundidad
+fn fill_rect(framebuffer: &mut Framebuffer, rect: Rect, hex: u32) {
+    let end_x = (rect.x + rect.width).min(framebuffer.width);
+    let end_y = (rect.y + rect.height).min(framebuffer.height);
+    for y in rect.y.min(end_y)..end_y {
+        framebuffer.buffer[y * framebuffer.width + rect.x.min(end_x)..y * framebuffer.width + end_x].fill(hex);
+    }
+}
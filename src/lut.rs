@@ -0,0 +1,129 @@
+use std::path::Path;
+use crate::color::Color;
+
+// Entradas de cada canal: una por cada valor de 8 bits
+pub const LUT_SIZE: usize = 256;
+// Tamaños que acepta LUT_1D_SIZE en los archivos .cube
+const CUBE_SIZE_RANGE: (usize, usize) = (2, 65536);
+
+// Curvas de color por canal (LUT 1D): para cada valor de entrada de 0 a 255 la salida entre 0 y 1.
+// Se cargan de un .cube (el formato de DaVinci y Photoshop) o de un CSV de 256 filas `r,g,b` de 0 a 255
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lut {
+    pub tables: [[f32; LUT_SIZE]; 3],
+}
+
+impl Lut {
+    pub fn identity() -> Self {
+        let table = std::array::from_fn(|index| index as f32 / (LUT_SIZE - 1) as f32);
+        Lut { tables: [table; 3] }
+    }
+
+    // Según la extensión: .cube o cualquier otra como CSV
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("cube") => Lut::from_cube(&source),
+            _ => Lut::from_csv(&source),
+        }
+    }
+
+    // 256 filas con la salida de rojo, verde y azul de 0 a 255; las líneas vacías y las que empiezan
+    // con # se saltan
+    pub fn from_csv(source: &str) -> Result<Self, String> {
+        let rows = data_lines(source)
+            .map(|(line, text)| parse_row(line, text, |c| c == ',', 255.0))
+            .collect::<Result<Vec<_>, String>>()?;
+        if rows.len() != LUT_SIZE {
+            return Err(format!("el CSV tiene {} filas y tienen que ser {}", rows.len(), LUT_SIZE));
+        }
+        Ok(Lut::from_rows(|index| rows[index].map(|value| value / 255.0)))
+    }
+
+    // Formato .cube 1D: LUT_1D_SIZE N y después N filas `r g b` de 0 a 1. Si N no es 256 se interpola
+    pub fn from_cube(source: &str) -> Result<Self, String> {
+        let mut size = None;
+        let mut rows = Vec::new();
+        for (line, text) in data_lines(source) {
+            let mut words = text.split_whitespace();
+            match words.next() {
+                Some("TITLE") => {}
+                Some("LUT_3D_SIZE") => return Err("solo se aceptan LUT 1D (LUT_1D_SIZE)".to_string()),
+                Some("LUT_1D_SIZE") => {
+                    let value = words.next().and_then(|value| value.parse::<usize>().ok())
+                        .filter(|value| (CUBE_SIZE_RANGE.0..=CUBE_SIZE_RANGE.1).contains(value))
+                        .ok_or_else(|| format!("línea {}: LUT_1D_SIZE tiene que estar entre {} y {}", line, CUBE_SIZE_RANGE.0, CUBE_SIZE_RANGE.1))?;
+                    size = Some(value);
+                }
+                Some(keyword @ ("DOMAIN_MIN" | "DOMAIN_MAX" | "LUT_1D_INPUT_RANGE")) => {
+                    let expected = if keyword == "DOMAIN_MIN" { 0.0 } else { 1.0 };
+                    let values: Vec<f32> = words.filter_map(|value| value.parse().ok()).collect();
+                    let matches = match keyword {
+                        "LUT_1D_INPUT_RANGE" => values == [0.0, 1.0],
+                        _ => values == [expected; 3],
+                    };
+                    if !matches {
+                        return Err(format!("línea {}: solo se acepta el dominio de 0 a 1", line));
+                    }
+                }
+                _ => rows.push(parse_row(line, text, char::is_whitespace, 1.0)?),
+            }
+        }
+        let size = size.ok_or("falta LUT_1D_SIZE")?;
+        if rows.len() != size {
+            return Err(format!("LUT_1D_SIZE es {} pero hay {} filas", size, rows.len()));
+        }
+        // Entrada i de 256 en la posición equivalente de las N filas
+        Ok(Lut::from_rows(|index| {
+            let position = index as f32 / (LUT_SIZE - 1) as f32 * (size - 1) as f32;
+            let (low, t) = (position.floor() as usize, position.fract());
+            let high = (low + 1).min(size - 1);
+            [0, 1, 2].map(|channel| rows[low][channel] + (rows[high][channel] - rows[low][channel]) * t)
+        }))
+    }
+
+    fn from_rows(row: impl Fn(usize) -> [f32; 3]) -> Self {
+        let rows: Vec<[f32; 3]> = (0..LUT_SIZE).map(row).collect();
+        Lut { tables: [0, 1, 2].map(|channel| std::array::from_fn(|index| rows[index][channel])) }
+    }
+
+    // Tablas de 8 bits para el framebuffer, con la salida redondeada
+    pub fn byte_tables(&self) -> [[u8; LUT_SIZE]; 3] {
+        self.tables.map(|table| table.map(|value| (value * 255.0).round().clamp(0.0, 255.0) as u8))
+    }
+
+    // Color con canales de 0 a 255 sin redondear (también por encima del blanco, que usa la última
+    // entrada): se interpola entre las dos entradas más cercanas para no volver a crear escalones
+    pub fn apply(&self, color: Color) -> Color {
+        let map = |table: &[f32; LUT_SIZE], value: f32| {
+            let position = value.clamp(0.0, (LUT_SIZE - 1) as f32);
+            let low = position.floor() as usize;
+            let high = (low + 1).min(LUT_SIZE - 1);
+            (table[low] + (table[high] - table[low]) * (position - low as f32)) * 255.0
+        };
+        Color { r: map(&self.tables[0], color.r), g: map(&self.tables[1], color.g), b: map(&self.tables[2], color.b) }
+    }
+}
+
+// Líneas con datos y su número (desde 1), sin comentarios ni líneas vacías
+fn data_lines(source: &str) -> impl Iterator<Item = (usize, &str)> {
+    source.lines().enumerate()
+        .map(|(index, text)| (index + 1, text.trim()))
+        .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'))
+}
+
+// Rojo, verde y azul entre 0 y `max`
+fn parse_row(line: usize, text: &str, separator: fn(char) -> bool, max: f32) -> Result<[f32; 3], String> {
+    let values: Vec<f32> = text.split(separator)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<f32>().map_err(|_| format!("línea {}: \"{}\" no es un número", line, value)))
+        .collect::<Result<_, _>>()?;
+    let [r, g, b] = values[..] else {
+        return Err(format!("línea {}: se esperaban 3 valores (rojo, verde y azul) y hay {}", line, values.len()));
+    };
+    match [r, g, b].into_iter().find(|value| !(0.0..=max).contains(value)) {
+        Some(value) => Err(format!("línea {}: {} está fuera del rango de 0 a {}", line, value, max)),
+        None => Ok([r, g, b]),
+    }
+}
@@ -1,347 +1,1459 @@
-use nalgebra_glm::{Vec3, Mat4, look_at, perspective};
-use minifb::{Key, Window, WindowOptions};
+use nalgebra_glm::{Mat4, Vec3};
+use minifb::{Window, WindowOptions};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::f32::consts::PI;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-mod triangle;
-mod obj_loader;
-mod color;
-mod shaders;
-mod framebuffer;
-mod vertex;
-mod fragments;
-mod camera;
-
-use vertex::Vertex;
-use camera::Camera;
-use obj_loader::Obj;
-use framebuffer::Framebuffer;
-use shaders::{fragment_shader, moon_position, vertex_shader, ShaderType};
-use triangle::triangle;
-
-pub struct Uniforms {
-    model_matrix: Mat4,
-    view_matrix: Mat4,
-    projection_matrix: Mat4,
-    viewport_matrix: Mat4,
-    time: u32,
-    debug_mode: u32,
-}
+mod bookmarks;
+mod cli;
+mod demo;
+mod gallery;
+mod input;
+mod loading;
+mod record;
 
-fn create_model_matrix(translation: Vec3, scale: f32, rotation: Vec3) -> Mat4 {
-    let (sin_x, cos_x) = rotation.x.sin_cos();
-    let (sin_y, cos_y) = rotation.y.sin_cos();
-    let (sin_z, cos_z) = rotation.z.sin_cos();
-
-    let rotation_matrix_x = Mat4::new(
-        1.0,  0.0,    0.0,   0.0,
-        0.0,  cos_x, -sin_x, 0.0,
-        0.0,  sin_x,  cos_x, 0.0,
-        0.0,  0.0,    0.0,   1.0,
-    );
-
-    let rotation_matrix_y = Mat4::new(
-        cos_y,  0.0,  sin_y, 0.0,
-        0.0,    1.0,  0.0,   0.0,
-        -sin_y, 0.0,  cos_y, 0.0,
-        0.0,    0.0,  0.0,   1.0,
-    );
-
-    let rotation_matrix_z = Mat4::new(
-        cos_z, -sin_z, 0.0, 0.0,
-        sin_z,  cos_z, 0.0, 0.0,
-        0.0,    0.0,  1.0, 0.0,
-        0.0,    0.0,  0.0, 1.0,
-    );
-
-    let rotation_matrix = rotation_matrix_z * rotation_matrix_y * rotation_matrix_x;
-
-    let transform_matrix = Mat4::new(
-        scale, 0.0,   0.0,   translation.x,
-        0.0,   scale, 0.0,   translation.y,
-        0.0,   0.0,   scale, translation.z,
-        0.0,   0.0,   0.0,   1.0,
-    );
-
-    transform_matrix * rotation_matrix
-}
+use lab4_shaders::bench::{BenchRecorder, Resolution, RunSettings, Stages};
+use lab4_shaders::camera::{Camera, Keyframe, Projection};
+use lab4_shaders::camera_path::{CameraPath, CAMERA_PATH_FILE};
+use lab4_shaders::checkerboard::Checkerboard;
+use lab4_shaders::clock::SimClock;
+use lab4_shaders::color::Color;
+use lab4_shaders::cubemap::{render_cubemap, CUBE_FACES};
+use lab4_shaders::export::{load_png, save_png, save_ply};
+use lab4_shaders::framebuffer::{Framebuffer, Rect};
+use lab4_shaders::anchors::{draw_anchors, pick_surface, SurfaceAnchor, ANCHOR_COLOR};
+use lab4_shaders::info_card::draw_info_card;
+use lab4_shaders::keymap::{Action, KeyMap, KEYBINDINGS_FILE};
+use lab4_shaders::labels::draw_labels;
+use lab4_shaders::legend::draw_thermal_legend;
+use lab4_shaders::mesh::RING_SEGMENT_TIERS;
+use lab4_shaders::loupe::Loupe;
+use lab4_shaders::lut::Lut;
+use lab4_shaders::minimap::Minimap;
+use lab4_shaders::pipeline::{build_uniforms, visualize_depth, RenderStats, FrameUniforms, FOV};
+use lab4_shaders::post::{self, Accumulator, ColorGrade, OUTLINE_COLOR, OUTLINE_THRESHOLD};
+use lab4_shaders::quality::{QualityController, QualitySettings, QualityTier};
+use lab4_shaders::redraw::{Redraw, RedrawTracker};
+use lab4_shaders::scene::{body_id, body_info, default_model_shader, focused_body, load_meshes, pick_body, procedural_meshes, render_scene, scene_bounds, scene_depth_range, start_camera, body_index, BodyStats, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+use lab4_shaders::session::{AppState, CommandLineOptions, SESSION_FILE, STEREO_SEPARATION_RANGE};
+use lab4_shaders::shader_checks;
+use lab4_shaders::shaders::{ShaderType, DEBUG_MODES};
+use lab4_shaders::text::{draw_text, line_height};
+use bookmarks::{Bookmark, Bookmarks, BOOKMARKS_FILE};
+use cli::{Args, BenchOptions, CubemapOptions, DiffOptions};
+use demo::{Demo, Transition};
+use gallery::Gallery;
+use loading::AssetLoader;
+use input::{InputFrame, InputRecorder, InputReplay};
 
+// Peso del frame nuevo en el desenfoque de movimiento
+const MOTION_BLUR_WEIGHT: f32 = 0.6;
 
-fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
-    look_at(&eye, &center, &up)
-}
+// Cuánto cambian la exposición (en pasos), el contraste y la saturación con cada tecla
+const EXPOSURE_STEP: f32 = 0.1;
+const CONTRAST_STEP: f32 = 0.05;
+const SATURATION_STEP: f32 = 0.05;
 
-fn create_perspective_matrix(window_width: f32, window_height: f32) -> Mat4 {
-    let fov = 45.0 * PI / 180.0;
-    let aspect_ratio = window_width / window_height;
-    let near = 0.1;
-    let far = 1000.0;
+// Cuánto cambia la separación de los ojos del modo estéreo con cada tecla
+const STEREO_SEPARATION_STEP: f32 = 1.25;
 
-    perspective(fov, aspect_ratio, near, far)
-}
+// Frames sin mover la cámara antes de dibujar a resolución completa (medio segundo)
+const IDLE_FRAMES_BEFORE_REFINE: u32 = 30;
 
-fn create_viewport_matrix(width: f32, height: f32) -> Mat4 {
-    Mat4::new(
-        width / 2.0, 0.0, 0.0, width / 2.0,
-        0.0, -height / 2.0, 0.0, height / 2.0,
-        0.0, 0.0, 1.0, 0.0,
-        0.0, 0.0, 0.0, 1.0
-    )
-}
+// Frames que se promedian en la imagen fija antes de dejar de dibujar con la ventana quieta
+const STILL_SETTLE_FRAMES: u32 = 64;
+// Espera de cada vuelta sin dibujar, para dejar libre el procesador
+const IDLE_SLEEP: Duration = Duration::from_millis(8);
 
-fn render_rings(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) {
-    let ring_uniforms = Uniforms {
-        model_matrix: create_model_matrix(Vec3::new(0.0, 0.0, 0.0), 0.6, Vec3::new(0.0, 0.0, 0.0)),
-        view_matrix: uniforms.view_matrix,
-        projection_matrix: uniforms.projection_matrix,
-        viewport_matrix: uniforms.viewport_matrix,
-        time: uniforms.time,
-        debug_mode: uniforms.debug_mode,
+// Número de escena del archivo de --scene-file (tecla 9, en lugar del sandbox de gravedad)
+const CUSTOM_SCENE: u32 = 9;
+// Texto con los segmentos del anillo adaptativo, arriba a la izquierda junto a los colores del LOD
+const RING_SEGMENTS_MARGIN: usize = 8;
+const RING_SEGMENTS_COLOR: Color = Color::new(225, 228, 235);
+
+fn main() {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            eprintln!("{}", cli::USAGE);
+            std::process::exit(2);
+        }
     };
-    let ring_shader = ShaderType::Ring; // Define un ShaderType para los anillos
-    render(framebuffer, &ring_uniforms, vertex_array, &ring_shader);
-}
 
-fn render(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex], current_shader: &ShaderType) {
-    // Vertex Shader Stage
-    let mut transformed_vertices = Vec::with_capacity(vertex_array.len());
-    for vertex in vertex_array {
-        let transformed = vertex_shader(vertex, uniforms);
-        transformed_vertices.push(transformed);
+    if args.check_shaders {
+        let checks = shader_checks::run_checks();
+        for check in &checks {
+            if check.passed {
+                println!("ok     {}", check.name);
+            } else {
+                println!("FALLA  {}: {}", check.name, check.detail);
+            }
+        }
+        let failed = checks.iter().filter(|check| !check.passed).count();
+        println!("{} revisiones, {} fallidas", checks.len(), failed);
+        std::process::exit(if failed == 0 { 0 } else { 1 });
     }
 
-    // Primitive Assembly Stage
-    let mut triangles = Vec::new();
-    for i in (0..transformed_vertices.len()).step_by(3) {
-        if i + 2 < transformed_vertices.len() {
-            triangles.push([
-                transformed_vertices[i].clone(),
-                transformed_vertices[i + 1].clone(),
-                transformed_vertices[i + 2].clone(),
-            ]);
+    if let Some(diff_options) = &args.diff {
+        match compare_screenshots(diff_options) {
+            Ok(stats) => println!("{}: {}", diff_options.out.display(), stats),
+            Err(message) => {
+                eprintln!("{}", message);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let window_width = 800;
+    let window_height = 600;
+    let framebuffer_width = 800;
+    let framebuffer_height = 600;
+
+    // Con ventana los OBJ se leen en otro hilo y mientras tanto se dibujan las mallas generadas; los modos
+    // que no abren ventana (o que tienen que salir siempre iguales) esperan a tenerlos
+    let load_in_background = !args.procedural && args.record.is_none() && args.export_mesh.is_none() && args.cubemap.is_none()
+        && args.replay.is_none() && args.bench.is_none();
+    let (mut meshes, mut loader) = if load_in_background {
+        (procedural_meshes(), Some(AssetLoader::spawn(args.model.clone())))
+    } else {
+        (load_meshes(args.procedural, args.model.as_deref()), None)
+    };
+    // El modelo de --model cuenta desde el principio aunque todavía se esté leyendo
+    let mut model_requested = meshes.has_model() || (loader.is_some() && args.model.is_some());
+
+    let mut custom_scene = match &args.scene_file {
+        Some(path) => match SceneConfig::from_file(path) {
+            Ok(scene) => Some(scene),
+            Err(err) => {
+                eprintln!("{}: {}", path, err);
+                std::process::exit(2);
+            }
+        },
+        None => None,
+    };
+
+    // Con --scene-file se empieza en esa escena y con --model en la 0, que muestra el modelo cargado
+    let scene_number = if custom_scene.is_some() {
+        CUSTOM_SCENE
+    } else if model_requested {
+        0
+    } else {
+        args.record.as_ref().map(|record| record.scene)
+            .or(args.export_mesh.as_ref().map(|export| export.scene))
+            .or(args.cubemap.as_ref().map(|cubemap| cubemap.scene))
+            .unwrap_or(1)
+    };
+    let mut scene = select_scene(scene_number, &custom_scene);
+
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    framebuffer.set_background_color(scene.background);
+    framebuffer.dither = args.dither;
+
+    // Framebuffer a menos resolución para el modo progresivo y la calidad automática; cambia de tamaño según haga falta
+    let mut preview = Framebuffer::new(framebuffer_width / 2, framebuffer_height / 2);
+    preview.set_background_color(scene.background);
+
+    let options = RenderOptions {
+        seed: args.seed.unwrap_or_default(),
+        model_shader: default_model_shader(args.model_shader, &meshes),
+        toon: args.toon,
+        graticule: args.graticule,
+        particle_rings: args.particle_rings,
+        adaptive_ring: args.adaptive_ring,
+        ambient_occlusion: args.ambient_occlusion,
+        starfield: args.starfield,
+        thermal: args.thermal,
+        cull_backfaces: args.cull_backfaces,
+        saturn_rings: args.saturn_rings,
+        fragment_budget: args.fragment_budget.unwrap_or(RenderOptions::default().fragment_budget),
+        threads: args.threads.unwrap_or(RenderOptions::default().threads),
+        ..RenderOptions::default()
+    };
+
+    // Curvas de color de --lut; si el archivo no sirve se sigue con la imagen sin cambios
+    let lut = args.lut.as_deref().map(|path| Lut::load(path).unwrap_or_else(|err| {
+        eprintln!("No se pudo cargar la LUT {}: {} (se usa la identidad)", path.display(), err);
+        Lut::identity()
+    }));
+
+    // Recorrido de --camera-path; si el archivo no sirve (o tiene menos de dos poses) se sigue sin él
+    let camera_path = args.camera_path.as_deref().and_then(|path| match CameraPath::load(path) {
+        Ok(camera_path) => Some(camera_path),
+        Err(err) => {
+            eprintln!("{}: {} (se sigue sin recorrido)", path.display(), err);
+            None
         }
+    });
+
+    if let Some(record_options) = &args.record {
+        if let Err(err) = record::record_frames(record_options, options, &scene, &mut framebuffer, &meshes, lut.as_ref(), camera_path.as_ref()) {
+            eprintln!("Error al grabar los frames: {}", err);
+            std::process::exit(1);
+        }
+        return;
     }
 
-    // Rasterization Stage
-    let mut fragments = Vec::new();
-    for tri in &triangles {
-        fragments.extend(triangle(&tri[0], &tri[1], &tri[2]));
+    if let Some(export_options) = &args.export_mesh {
+        let camera = start_camera(&scene, &meshes);
+        let depth_range = scene_depth_range(&scene.bodies_seen_from(0.0, camera.eye, &meshes), &meshes, &camera);
+        let uniforms = build_uniforms(&camera, 0.0, framebuffer_width, framebuffer_height, depth_range);
+        let state = SceneState::new(&scene, options.seed, &meshes);
+        if !export_scene(&export_options.path, &scene, &uniforms, &meshes, &state, &options) {
+            std::process::exit(1);
+        }
+        return;
     }
 
-    // Fragment Processing Stage
-    for fragment in fragments {
-        let x = fragment.position.x as usize;
-        let y = fragment.position.y as usize;
-        if x < framebuffer.width && y < framebuffer.height {
-            // Apply fragment shader
-            let shaded_color = fragment_shader(&fragment, &uniforms, current_shader);
-            let color = shaded_color.to_hex();
-            framebuffer.set_current_color(color);
-            framebuffer.point(x, y, fragment.depth);
+    if let Some(cubemap_options) = &args.cubemap {
+        if let Err(err) = bake_cubemap(cubemap_options, &scene, &meshes, options) {
+            eprintln!("{}", err);
+            std::process::exit(1);
         }
+        return;
     }
-}
 
-fn render_scene5(framebuffer: &mut Framebuffer, uniforms: &Uniforms, vertex_array: &[Vertex]) {
-    // agrega la luna
-    let moon_position = moon_position(uniforms.time as f32, 1.3);
-    let moon_shader = ShaderType::Moon;
-
-    // Llamamos a render para Marte (rocoso)
-    let current_shader = ShaderType::RockyPlanet;
-    render(framebuffer, uniforms, vertex_array, &current_shader);
-
-    // Llamamos a render para la luna
-    let moon_uniforms = Uniforms {
-        model_matrix: create_model_matrix(moon_position, 0.5, Vec3::new(0.0, 0.0, 0.0)),
-        view_matrix: uniforms.view_matrix,
-        projection_matrix: uniforms.projection_matrix,
-        viewport_matrix: uniforms.viewport_matrix,
-        time: uniforms.time,
-        debug_mode: uniforms.debug_mode,
+    let keymap = match KeyMap::load(Path::new(KEYBINDINGS_FILE)) {
+        Ok(keymap) => keymap,
+        Err(message) => {
+            eprintln!("{}: {}", KEYBINDINGS_FILE, message);
+            std::process::exit(2);
+        }
     };
-    render(framebuffer, &moon_uniforms, vertex_array, &moon_shader);
-}
 
-fn setup_scene(scene_number: u32) -> (Vec3, f32, Vec3, Vec3, Vec3) {
-    match scene_number {
-        1 => {
-            // Escena 1: Sol
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
-        },
-        2 => {
-            // Escena 2: Tierra
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
-        },
-        3 => {
-            // Escena 3: Planeta gaseoso
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
-        },
-        4 => {
-            // Escena 4: Planeta con anillos
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
-        },
-        5 => {
-            // Escena 5: Planeta rocoso con luna
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
+    // Con --replay-input la entrada sale del archivo en lugar del teclado y el mouse
+    let mut replay = match &args.replay {
+        Some(replay) => match InputReplay::load(&replay.path) {
+            Ok(log) => {
+                println!("Repitiendo {} frames de {}", log.frame_count(), replay.path.display());
+                Some(log)
+            }
+            Err(err) => {
+                eprintln!("{}: {}", replay.path.display(), err);
+                std::process::exit(2);
+            }
         },
-        6 => {
-            // Escena 6: Planeta de hielo
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
-        },
-        7 => {
-            // Escena 7: Planeta volcanico
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
+        None => None,
+    };
+    let mut recorder = match &args.record_input {
+        Some(path) => match InputRecorder::create(path) {
+            Ok(recorder) => Some(recorder),
+            Err(err) => {
+                eprintln!("No se pudo crear {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
         },
-        _ => {
-            // Escena predeterminada
-            (Vec3::new(0.0, 0.0, 0.0), 1.0, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 1.0, 0.0))
+        None => None,
+    };
+
+    // Sin ventana (--headless) cada frame se guarda en el directorio de salida
+    let headless_out_dir = args.replay.as_ref().and_then(|replay| replay.headless_out_dir.as_deref());
+    let mut window = match headless_out_dir {
+        Some(out_dir) => {
+            if let Err(err) = std::fs::create_dir_all(out_dir) {
+                eprintln!("No se pudo crear {}: {}", out_dir.display(), err);
+                std::process::exit(1);
+            }
+            None
+        }
+        None => match Window::new(
+            "Planets Render",
+            window_width,
+            window_height,
+            WindowOptions::default(),
+        ) {
+            Ok(window) => Some(window),
+            Err(err) => {
+                eprintln!("No se pudo abrir la ventana: {}", err);
+                std::process::exit(1);
+            }
         },
+    };
+
+    if let Some(window) = &mut window {
+        window.set_position(500, 500);
+        window.update();
     }
-}
 
-fn main() {
-    let window_width = 800;
-    let window_height = 600;
-    let framebuffer_width = 800;
-    let framebuffer_height = 600;
+    // Todo lo que se cambia con el teclado. Se retoma la sesión anterior (session.toml o --session) salvo
+    // con --no-session, al repetir una entrada o al medir tiempos, que tienen que empezar siempre igual
+    let session_path = (!args.no_session && args.replay.is_none() && args.bench.is_none())
+        .then(|| args.session.clone().unwrap_or_else(|| PathBuf::from(SESSION_FILE)));
+    let mut app = AppState {
+        scene: scene_number,
+        scene_file: args.scene_file.clone(),
+        options,
+        dither: args.dither,
+        progressive: args.progressive,
+        checkerboard: args.checkerboard,
+        use_lut: lut.is_some(),
+        ..AppState::default()
+    };
+    let restored = session_path.as_deref().is_some_and(|path| restore_session(path, &mut app, &args.command_line_options(), &mut custom_scene, model_requested));
+    if restored {
+        scene = select_scene(app.scene, &custom_scene);
+        framebuffer.set_background_color(scene.background);
+        preview.set_background_color(scene.background);
+    }
+    framebuffer.dither = app.dither;
+    // El recorrido de --camera-path empieza en su primera pose
+    if let Some(path) = &camera_path {
+        app.clock.seek(path.start());
+    }
 
-    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
-    let mut window = Window::new(
-        "Planets Render",
-        window_width,
-        window_height,
-        WindowOptions::default(),
-    )
-    .unwrap();
-
-    window.set_position(500, 500);
-    window.update();
-
-    framebuffer.set_background_color(0x335555);
-
-    let mut scene_number = 1;
-
-    // camera parameters
-    let mut camera = Camera::new(
-        Vec3::new(0.0, 0.0, 5.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0)
-    );
-
-    let sphere_loader = Obj::load("models/sphere.obj").expect("Failed to load sphere obj");
-    let sphere_vertex_arrays = sphere_loader.get_vertex_array();
-    
-    let ring_loader = Obj::load("models/ring.obj").expect("Failed to load ring obj");
-    let ring_vertex_array = ring_loader.get_vertex_array();
-
-    let mut time = 0;
-
-    while window.is_open() {
-        if window.is_key_down(Key::Escape) {
+    // La cámara restaurada no se vuelve a encuadrar
+    let mut framed_scenes = if restored { vec![app.scene] } else { Vec::new() };
+    let mut mouse = MouseState::default();
+    let mut scene_state = SceneState::new(&scene, app.options.seed, &meshes);
+    let mut accumulator = Accumulator::new();
+    // Acumulación de imágenes fijas con subpixeles y lo que se dibujó en ella
+    let mut still_accumulator = Accumulator::new();
+    let mut still_state = 0;
+    // Frame anterior y tiempos de sombreado del tablero de ajedrez
+    let mut checkerboard = Checkerboard::new();
+    let mut previous_scene = app.scene;
+    let mut idle_frames = 0;
+    let mut last_stats = Vec::new();
+    // Imagen del ojo izquierdo del modo estéreo mientras se dibuja el derecho
+    let mut left_eye = Vec::new();
+    // Vista desde arriba en una esquina (Tab), mientras app.minimap está activo
+    let mut minimap: Option<Minimap> = None;
+    let mut demo = if args.demo { Demo::new(demo_scenes(&custom_scene), app.scene) } else { None };
+    let mut show_help = false;
+    // Tarjeta con los datos del cuerpo enfocado (I)
+    let mut show_info_card = false;
+    // Poses grabadas con K, el archivo donde se guardan (Ctrl+K) y el recorrido que se está reproduciendo
+    let mut path_keyframes: Vec<Keyframe> = Vec::new();
+    let camera_path_file = args.camera_path.clone().unwrap_or_else(|| PathBuf::from(CAMERA_PATH_FILE));
+    let mut playing_path = camera_path;
+    // Frame anterior para la vista de diferencias (F7); solo se copia mientras está activa
+    let mut frame_diff: Option<Vec<u32>> = None;
+    // Miniaturas de las escenas (mantener G) y el paso de la cámara a la escena elegida en ella
+    let mut gallery = Gallery::new(gallery_scenes(model_requested), framebuffer.width);
+    let mut transition: Option<Transition> = None;
+    // Cámaras guardadas (Ctrl + número) y el marcador al que se volvió, mientras la cámara no se mueva
+    let mut bookmarks = Bookmarks::load(Path::new(BOOKMARKS_FILE));
+    let mut active_bookmark: Option<(usize, Bookmark)> = None;
+    // Marcas agregadas con Ctrl + click en cada escena, además de las del archivo de escena, y las matrices
+    // del último frame dibujado para saber a qué punto del cuerpo corresponde el pixel
+    let mut added_anchors: HashMap<u32, Vec<SurfaceAnchor>> = HashMap::new();
+    let mut drawn_frame: Option<(FrameUniforms, Vec<Mat4>)> = None;
+
+    // Calidad automática para mantener el FPS objetivo; al repetir una sesión se deja fija para que
+    // los frames salgan iguales
+    let mut quality_controller = QualityController::new(args.target_fps, args.fixed_quality || replay.is_some()).starting_at(app.quality.tier);
+    app.quality = quality_controller.settings();
+
+    // Sin cambios en la imagen no se vuelve a dibujar; `clean_frame` es el último frame completo sin
+    // la ayuda ni la lupa, y `overlay_area` la zona que estas taparon en la ventana
+    let mut redraw_tracker = RedrawTracker::new();
+    let mut clean_frame = Vec::new();
+    let mut overlay_area: Option<Rect> = None;
+
+    // El título muestra lo último que se seleccionó (cuerpo, modo de depuración) y la velocidad del reloj
+    let mut title_detail = String::new();
+    let mut current_title = String::new();
+    let mut frame_number = 0;
+    // Con --bench-exit se miden los tiempos de cada frame dibujado y se sale después de N vueltas
+    let mut bench = args.bench.as_ref().map(|_| BenchRecorder::new());
+
+    while window.as_ref().is_none_or(Window::is_open) {
+        if let (Some(bench), Some(bench_options)) = (&bench, &args.bench) {
+            if bench.frames() >= bench_options.frames {
+                break;
+            }
+        }
+        // Al terminar la repetición sigue el teclado; sin ventana el programa termina
+        let replayed = replay.as_mut().and_then(InputReplay::next_frame);
+        let input = match (replayed, &window) {
+            (Some(input), _) => input,
+            (None, Some(window)) => {
+                if replay.take().is_some() {
+                    println!("Fin de la repetición; la entrada vuelve al teclado");
+                }
+                InputFrame::read(window, &keymap)
+            }
+            (None, None) => break,
+        };
+        frame_number += 1;
+        let frame_start = Instant::now();
+        let triggered = |action: Action| input.triggered(action);
+        if triggered(Action::Quit) {
             break;
         }
 
+        // Mallas que terminaron de leerse: lo que dependía de las anteriores se vuelve a calcular
+        if let Some(loader) = &mut loader {
+            let update = loader.poll(&mut meshes);
+            if update.model_loaded {
+                app.options.model_shader = app.options.model_shader.or(default_model_shader(args.model_shader, &meshes));
+                // El encuadre de la escena 0 se hizo con la esfera que la reemplazaba
+                if !restored {
+                    framed_scenes.retain(|&number| number != 0);
+                }
+            }
+            if update.model_failed {
+                model_requested = false;
+                gallery = Gallery::new(gallery_scenes(model_requested), framebuffer.width);
+                if app.scene == 0 {
+                    app.scene = 1;
+                }
+            }
+            if update.meshes_changed {
+                scene_state.update_relief(&scene, app.options.seed, &meshes);
+                for number in gallery_scenes(model_requested) {
+                    gallery.invalidate(number);
+                }
+                if minimap.is_some() {
+                    minimap = Some(Minimap::new(app.scene, &scene, &meshes, &framebuffer));
+                }
+                redraw_tracker.invalidate();
+            }
+        }
+
+        // Ayuda con las teclas actuales
+        if triggered(Action::ToggleHelp) {
+            show_help = !show_help;
+        }
+        if triggered(Action::ToggleInfoCard) {
+            show_info_card = !show_info_card;
+        }
+
+        // Modo demo: F6 lo enciende o lo apaga y cualquier otra tecla o el mouse lo detienen
+        // sin mover la cámara de donde quedó
+        if triggered(Action::ToggleDemo) {
+            demo = match demo {
+                Some(_) => None,
+                None => Demo::new(demo_scenes(&custom_scene), app.scene),
+            };
+        } else if demo.is_some() && input.manual {
+            demo = None;
+        }
+        if let Some(demo) = &mut demo {
+            app.scene = demo.update(&app.camera, |number| select_scene(number, &custom_scene).tour);
+        }
+
+        // Galería: al soltar G (o con Enter) se cambia a la escena elegida y la cámara va deslizándose hasta
+        // su encuadre inicial; tocar cualquier tecla o el mouse la deja donde está
+        if input.manual {
+            transition = None;
+        }
+        if let Some(number) = gallery.update(&input, app.scene).filter(|&number| number != app.scene) {
+            let target = start_camera(&select_scene(number, &custom_scene), &meshes);
+            transition = Some(Transition::new(&mut app.camera, &target));
+            if !framed_scenes.contains(&number) {
+                framed_scenes.push(number);
+            }
+            app.scene = number;
+        }
+        gallery.generate(|number| select_scene(number, &custom_scene), &meshes, &app.options);
+
+        // Marcadores: Ctrl + número guarda la cámara y Shift + número vuelve a ella, pasando antes a la escena
+        // donde se guardó; un marcador vacío (o de una escena que ya no está) no hace nada
+        let bookmark_slot = input.actions.iter().find_map(|action| match action {
+            Action::Scene(number) if input.ctrl || input.shift => Some(*number as usize),
+            _ => None,
+        });
+        if let Some(slot) = bookmark_slot {
+            if input.ctrl {
+                let bookmark = Bookmark::new(app.scene, &app.camera);
+                bookmarks.set(slot, bookmark);
+                active_bookmark = Some((slot, bookmark));
+            } else if let Some(bookmark) = bookmarks.get(slot).filter(|bookmark| gallery_scenes(model_requested).contains(&bookmark.scene)) {
+                transition = Some(Transition::new(&mut app.camera, &bookmark.camera()));
+                if !framed_scenes.contains(&bookmark.scene) {
+                    framed_scenes.push(bookmark.scene);
+                }
+                app.scene = bookmark.scene;
+                active_bookmark = Some((slot, bookmark));
+            }
+        }
+
+        // Recorrido de la cámara: K agrega la pose actual en el tiempo de la simulación, Ctrl+K guarda las poses
+        // grabadas y Shift+K reproduce el archivo desde su primera pose (o lo detiene)
+        if triggered(Action::CameraPathKeyframe) {
+            if input.ctrl {
+                let saved = CameraPath::new(path_keyframes.clone(), false).and_then(|path| path.save(&camera_path_file).map(|()| path));
+                title_detail = match saved {
+                    Ok(path) => format!("recorrido de {} poses guardado en {}", path.keyframes().len(), camera_path_file.display()),
+                    Err(err) => format!("no se guardó el recorrido: {}", err),
+                };
+            } else if input.shift {
+                playing_path = match playing_path {
+                    Some(_) => {
+                        title_detail = "recorrido detenido".to_string();
+                        None
+                    }
+                    None => match CameraPath::load(&camera_path_file) {
+                        Ok(path) => {
+                            title_detail = format!("reproduciendo {}", camera_path_file.display());
+                            app.clock.seek(path.start());
+                            Some(path)
+                        }
+                        Err(err) => {
+                            title_detail = format!("{}: {}", camera_path_file.display(), err);
+                            None
+                        }
+                    },
+                };
+            } else {
+                let time = app.clock.time();
+                let repeated = path_keyframes.last().is_some_and(|last| last.time == time);
+                path_keyframes.push(Keyframe { time, eye: app.camera.eye, center: app.camera.center });
+                title_detail = if repeated {
+                    format!("pose {} en el mismo tiempo que la anterior (se corre 1 tick al guardar)", path_keyframes.len())
+                } else {
+                    format!("pose {} del recorrido en el tiempo {:.0}", path_keyframes.len(), time)
+                };
+            }
+        }
+
         // Cambiar escena
-        if window.is_key_down(Key::Key1) {
-            scene_number = 1;
-        } else if window.is_key_down(Key::Key2) {
-            scene_number = 2;
-        } else if window.is_key_down(Key::Key3) {
-            scene_number = 3;
-        } else if window.is_key_down(Key::Key4) {
-            scene_number = 4;
-        } else if window.is_key_down(Key::Key5) {
-            scene_number = 5;
-        } else if window.is_key_down(Key::Key6) {
-            scene_number = 6;
-        } else if window.is_key_down(Key::Key7) {
-            scene_number = 7;
-        }
-
-        let (translation, scale, rotation, _eye, _up) = setup_scene(scene_number);
-
-        let current_shader: ShaderType;
-        current_shader = match scene_number {
-            1 => ShaderType::Sun,
-            2 => ShaderType::Earth,
-            3 => ShaderType::GasPlanet,
-            4 => ShaderType::RingPlanet,
-            5 => ShaderType::RockyPlanet,
-            6 => ShaderType::IcyPlanet,
-            7 => ShaderType::VolcanicPlanet,
-            _ => ShaderType::Sun,
+        let next_scene = input.actions.iter().find_map(|action| match action {
+            Action::Scene(_) if input.ctrl || input.shift => None,
+            Action::Scene(number) => Some(*number),
+            _ => None,
+        });
+        if let Some(number) = next_scene {
+            app.scene = number;
+        }
+
+        // Recargar el archivo de --scene-file; si tiene errores se sigue con la versión anterior
+        if triggered(Action::ReloadScene) {
+            if let Some(path) = &args.scene_file {
+                match SceneConfig::from_file(path) {
+                    Ok(reloaded) => {
+                        custom_scene = Some(reloaded);
+                        gallery.invalidate(CUSTOM_SCENE);
+                        if app.scene == CUSTOM_SCENE {
+                            previous_scene = u32::MAX;
+                        }
+                    }
+                    Err(err) => eprintln!("{}: {}", path, err),
+                }
+            }
+        }
+
+        // Al cambiar de escena no deben quedar rastros de la anterior
+        if app.scene != previous_scene {
+            scene = select_scene(app.scene, &custom_scene);
+            framebuffer.set_background_color(scene.background);
+            preview.set_background_color(scene.background);
+            scene_state.reset(&scene, app.options.seed, &meshes);
+            accumulator.reset();
+            checkerboard.reset();
+            drawn_frame = None;
+            app.grade = None;
+            if minimap.is_some() {
+                minimap = Some(Minimap::new(app.scene, &scene, &meshes, &framebuffer));
+            }
+            previous_scene = app.scene;
+        }
+        if let Some(bench) = &mut bench {
+            bench.frame(app.scene);
+        }
+
+        // Encuadrar la escena la primera vez que se entra o al presionar Home
+        let first_visit = !framed_scenes.contains(&app.scene);
+        if first_visit || triggered(Action::FrameScene) {
+            match (first_visit, scene.camera) {
+                (true, Some(start)) => app.camera = start.camera(),
+                _ => {
+                    let bounds = scene_bounds(&scene_state.bodies(&scene, app.clock.time()), &meshes);
+                    app.camera.frame_bounds(bounds.center, bounds.radius, FOV);
+                }
+            }
+            if first_visit {
+                framed_scenes.push(app.scene);
+            }
+        }
+        if let Some(demo) = &demo {
+            demo.apply(&mut app.camera);
+        }
+        if transition.as_mut().is_some_and(|transition| !transition.apply(&mut app.camera)) {
+            transition = None;
+        }
+        if transition.is_none() && active_bookmark.is_some_and(|(_, bookmark)| bookmark != Bookmark::new(app.scene, &app.camera)) {
+            active_bookmark = None;
+        }
+
+        // Recorrer los modos de depuración de los shaders
+        if triggered(Action::CycleDebugMode) {
+            app.options.debug_mode = (app.options.debug_mode + 1) % DEBUG_MODES;
+            title_detail = format!("debug_mode {}", app.options.debug_mode);
+        }
+
+        // Dithering de la salida de 24 bits
+        if triggered(Action::ToggleDither) {
+            app.dither = !app.dither;
+            framebuffer.dither = app.dither;
+        }
+
+        // Colorear cuerpos según su nivel de detalle
+        if triggered(Action::ToggleLodColors) {
+            app.options.show_lod_tiers = !app.options.show_lod_tiers;
+        }
+
+        // Mostrar el zbuffer en lugar de los colores
+        if triggered(Action::ToggleDepth) {
+            app.show_depth = !app.show_depth;
+        }
+
+        // Modo caricatura: bandas de iluminación y contornos
+        if triggered(Action::ToggleToon) {
+            app.options.toon = !app.options.toon;
+        }
+
+        // Cuadrícula de latitud y longitud y eje de rotación de cada cuerpo
+        if triggered(Action::ToggleGraticule) {
+            app.options.graticule = !app.options.graticule;
+        }
+
+        // Cielo de estrellas detrás de los cuerpos en lugar del color de fondo
+        if triggered(Action::ToggleStarfield) {
+            app.options.starfield = !app.options.starfield;
+        }
+
+        // Vista infrarroja: temperatura en lugar de los colores, con la escala en el borde derecho
+        if triggered(Action::ToggleThermal) {
+            app.options.thermal = !app.options.thermal;
+        }
+
+        // Proyección ortográfica, para ver las órbitas como un diagrama
+        if triggered(Action::ToggleProjection) {
+            app.camera.toggle_projection(FOV);
+            title_detail = match app.camera.projection {
+                Projection::Perspective => "perspectiva".to_string(),
+                Projection::Orthographic { .. } => "ortográfica".to_string(),
+            };
+        }
+
+        // Anillos de partículas en lugar de la malla
+        if triggered(Action::ToggleParticleRings) {
+            app.options.particle_rings = !app.options.particle_rings;
+        }
+
+        // Minimapa con todo el sistema visto desde arriba
+        if triggered(Action::ToggleMinimap) {
+            app.minimap = !app.minimap;
+        }
+        if app.minimap != minimap.is_some() {
+            minimap = app.minimap.then(|| Minimap::new(app.scene, &scene, &meshes, &framebuffer));
+        }
+
+        // Anaglifo para lentes rojo-cian; [ y ] cambian la separación de los ojos
+        if triggered(Action::ToggleAnaglyph) {
+            app.anaglyph = !app.anaglyph;
+            accumulator.reset();
+        }
+        let separation_step = if triggered(Action::StereoWider) {
+            Some(STEREO_SEPARATION_STEP)
+        } else if triggered(Action::StereoNarrower) {
+            Some(1.0 / STEREO_SEPARATION_STEP)
+        } else {
+            None
+        };
+        if let Some(step) = separation_step.filter(|_| app.anaglyph) {
+            let (min, max) = STEREO_SEPARATION_RANGE;
+            app.stereo_separation = (app.stereo_separation * step).clamp(min, max);
+            still_accumulator.reset();
+            title_detail = format!("separación estéreo {:.3}", app.stereo_separation);
+        }
+
+        // Oclusión ambiental a partir del zbuffer
+        if triggered(Action::ToggleAmbientOcclusion) {
+            app.options.ambient_occlusion = !app.options.ambient_occlusion;
+        }
+
+        // Desenfoque de movimiento
+        if triggered(Action::ToggleMotionBlur) {
+            app.motion_blur = !app.motion_blur;
+            accumulator.reset();
+        }
+
+        // Imagen fija sin serrucho: se promedian frames movidos una fracción de pixel
+        if triggered(Action::ToggleStill) {
+            app.still = !app.still;
+            still_accumulator.reset();
+        }
+
+        // Corrección de color: Re Pág y Av Pág cambian la exposición (con Shift el contraste y con Ctrl
+        // la saturación) y Fin vuelve a la de la escena
+        let grade_step = if triggered(Action::GradeUp) {
+            Some(1.0)
+        } else if triggered(Action::GradeDown) {
+            Some(-1.0)
+        } else {
+            None
         };
+        if let Some(step) = grade_step {
+            let grade = app.grade.get_or_insert(scene.grade);
+            if input.shift {
+                grade.contrast = (grade.contrast + step * CONTRAST_STEP).max(0.0);
+            } else if input.ctrl {
+                grade.saturation = (grade.saturation + step * SATURATION_STEP).max(0.0);
+            } else {
+                grade.exposure += step * EXPOSURE_STEP;
+            }
+        }
+        let reset_grade = triggered(Action::ResetGrade);
+        if reset_grade {
+            app.grade = None;
+        }
+        let grade = app.grade.unwrap_or(scene.grade);
+        if grade_step.is_some() || reset_grade {
+            title_detail = format!("exposición {:+.1}, saturación {:.2}, contraste {:.2}", grade.exposure, grade.saturation, grade.contrast);
+        }
+
+        // Control del tiempo: + y - cambian la velocidad, R la invierte, espacio pausa y 0 vuelve al inicio
+        if triggered(Action::Faster) {
+            app.clock.faster();
+        }
+        if triggered(Action::Slower) {
+            app.clock.slower();
+        }
+        if triggered(Action::ReverseTime) {
+            app.clock.reverse();
+        }
+        if triggered(Action::Pause) {
+            app.clock.toggle_pause();
+        }
+        if triggered(Action::ResetTime) {
+            app.clock.reset();
+            scene_state.restart_physics(&scene);
+        }
+
+        let title = window_title(&title_detail, &app.clock, (!args.fixed_quality).then_some(app.quality.tier), active_bookmark.map(|(slot, _)| slot));
+        if let Some(window) = window.as_mut().filter(|_| title != current_title) {
+            window.set_title(&title);
+            current_title = title;
+        }
+
+        // Resumen de lo que se dibujó en el último frame
+        if triggered(Action::PrintStats) {
+            print_stats(&last_stats);
+        }
+
+        if triggered(Action::ToggleFrameDiff) {
+            app.frame_diff = !app.frame_diff;
+            if !app.frame_diff {
+                title_detail.clear();
+            }
+        }
+        if app.frame_diff != frame_diff.is_some() {
+            frame_diff = app.frame_diff.then(Vec::new);
+        }
+
+        // Lens flare del sol
+        if triggered(Action::ToggleLensFlare) {
+            app.lens_flare = !app.lens_flare;
+        }
+
+        // Curvas de color de --lut
+        if triggered(Action::ToggleLut) {
+            match lut {
+                Some(_) => app.use_lut = !app.use_lut,
+                None => eprintln!("No hay curvas de color: se cargan con --lut archivo.cube"),
+            }
+        }
+
+        // Renderizado progresivo: media resolución mientras se mueve la cámara
+        if triggered(Action::ToggleProgressive) {
+            app.progressive = !app.progressive;
+        }
+
+        // Tablero de ajedrez: cada frame sombrea la mitad de los pixeles y completa la otra
+        if triggered(Action::ToggleCheckerboard) {
+            app.checkerboard = !app.checkerboard;
+            checkerboard.reset();
+        }
+
+        if triggered(Action::ToggleLabels) {
+            app.show_labels = !app.show_labels;
+        }
+
+        // Lupa para ver pixeles sueltos; mientras está activa la rueda cambia el aumento en lugar del zoom
+        if triggered(Action::ToggleLoupe) {
+            app.loupe = match app.loupe {
+                Some(_) => None,
+                None => Some(Loupe::default()),
+            };
+        }
+        // Ctrl+S guarda la sesión sin salir (al salir se guarda sola); la S sola sigue moviendo la cámara
+        if input.ctrl && triggered(Action::SaveSession) {
+            title_detail = match &session_path {
+                Some(path) if save_session(&app, path) => format!("sesión guardada en {}", path.display()),
+                Some(_) => "no se pudo guardar la sesión".to_string(),
+                None => "la sesión no se guarda (--no-session)".to_string(),
+            };
+        }
+
+        // Con la galería abierta las flechas mueven la selección y no la cámara
+        let mut camera_input = input.clone();
+        if gallery.is_open() {
+            camera_input.actions.clear();
+        }
+        if input.ctrl {
+            camera_input.actions.retain(|&action| action != Action::PanDown);
+        }
+        if let (Some(loupe), Some(scroll)) = (&mut app.loupe, input.scroll) {
+            loupe.adjust(scroll);
+            title_detail = format!("lupa {}x", loupe.magnification);
+            camera_input.scroll = None;
+        }
+        handle_input(&camera_input, &mut app.camera, &mut mouse);
+
+        // Cualquier tecla nueva puede cambiar la imagen, así que también cuenta como actividad
+        if app.camera.has_changed || input.key_pressed {
+            idle_frames = 0;
+        } else {
+            idle_frames = (idle_frames + 1).min(IDLE_FRAMES_BEFORE_REFINE + 1);
+        }
+        let refine = idle_frames == IDLE_FRAMES_BEFORE_REFINE;
+        let refined = idle_frames > IDLE_FRAMES_BEFORE_REFINE;
+
+        // Con --freeze-idle la animación se detiene mientras se muestra el frame refinado
+        if !(app.progressive && refined && args.freeze_idle) {
+            app.clock.tick();
+        }
+        let time = app.clock.time();
+        // La cámara del recorrido sigue al reloj: al pausar, acelerar o invertir el tiempo la cámara va con él
+        if let Some(path) = &playing_path {
+            path.apply(&mut app.camera, time);
+        }
+        if let Some(replay) = &mut replay {
+            replay.check_time(time);
+        }
+        if let Some(Err(err)) = recorder.as_mut().map(|recorder| recorder.write(&input, time)) {
+            eprintln!("No se pudo grabar la entrada: {}", err);
+            recorder = None;
+        }
+
+        scene_state.update(time);
+
+        // Planos cercano y lejano ajustados a la escena en cada frame
+        let bodies = scene_state.bodies_seen_from(&scene, time, app.camera.eye, &meshes);
+        let depth_range = scene_depth_range(&bodies, &meshes, &app.camera);
+        let uniforms = build_uniforms(&app.camera, time, framebuffer_width, framebuffer_height, depth_range);
+
+        // Geometría de la escena con los colores de los shaders, para abrirla en otro programa
+        if triggered(Action::ExportMesh) {
+            export_scene(Path::new(&format!("escena{}.ply", app.scene)), &scene, &uniforms, &meshes, &scene_state, &app.options);
+        }
+
+        // Ctrl + click sobre un cuerpo: una marca en ese punto de su superficie, que se imprime para poder
+        // copiarla a un archivo de escena
+        let clicked = mouse.clicked.take();
+        let anchor_click = clicked.filter(|_| input.ctrl)
+            .filter(|&(x, y)| !minimap.as_ref().is_some_and(|map| map.rect.contains(x as usize, y as usize)));
+        if let (Some((x, y)), Some((picked_uniforms, model_matrices))) = (anchor_click, &drawn_frame) {
+            if let Some((index, direction)) = pick_surface(&framebuffer, picked_uniforms, model_matrices, x as usize, y as usize) {
+                let anchors = added_anchors.entry(app.scene).or_default();
+                let body = &bodies[index];
+                let number = anchors.iter().filter(|anchor| anchor.body_id == body_id(index)).count() + 1;
+                let anchor = SurfaceAnchor { body_id: body_id(index), direction, label: format!("{} {}", body.name, number), color: ANCHOR_COLOR };
+                println!("[[anchors]]\nbody = \"{}\"\ndirection = [{:.4}, {:.4}, {:.4}]\nlabel = \"{}\"", body.name, direction.x, direction.y, direction.z, anchor.label);
+                title_detail = format!("marca {}", anchor.label);
+                anchors.push(anchor);
+                redraw_tracker.invalidate();
+            }
+        }
 
+        // Click sobre un cuerpo: centrar la cámara en él (en el minimapa se usa el cuerpo dibujado en el pixel)
+        if let Some((x, y)) = clicked.filter(|_| !input.ctrl) {
+            let picked = match &minimap {
+                Some(map) if map.rect.contains(x as usize, y as usize) => framebuffer.id_at(x as usize, y as usize)
+                    .and_then(body_index)
+                    .filter(|&index| index < bodies.len()),
+                _ => pick_body(&bodies, &meshes, &uniforms, x, y),
+            };
+            if let Some(index) = picked {
+                app.camera.focus_on(bodies[index].translation);
+                title_detail = bodies[index].name.clone();
+                idle_frames = 0;
+            }
+        }
+        app.camera.has_changed = false;
+
+        // Cuerpo bajo el mouse (en el buffer de ids del frame anterior): se muestra en el título y se resalta
+        let hovered = input.mouse
+            .and_then(|(x, y)| framebuffer.id_at(x as usize, y as usize))
+            .and_then(body_index)
+            .filter(|&index| index < bodies.len());
+        if hovered != app.options.highlighted_body {
+            match hovered {
+                Some(index) => title_detail = format!("{} ({:?})", bodies[index].name, bodies[index].shader),
+                None => title_detail.clear(),
+            }
+            app.options.highlighted_body = hovered;
+        }
+
+        // Si cambia algo que se ve en la imagen el promedio se empieza de nuevo
+        let state = still_state_hash(&app.camera, app.scene, time, &app.clock, &app.options, [app.show_depth, framebuffer.dither, app.progressive, minimap.is_some(), app.anaglyph, app.checkerboard]);
+        if app.still && state != still_state {
+            still_accumulator.reset();
+            still_state = state;
+        }
+
+        // Con la ventana quieta no se dibuja nada; cualquier tecla vuelve a dibujar todo. Sin ventana
+        // cada frame se guarda, así que siempre se dibuja
+        if input.key_pressed {
+            redraw_tracker.invalidate();
+        }
+        let image_state = image_state_hash(state, &app.quality, &grade, app.stereo_separation, [app.still, app.lens_flare, app.motion_blur, app.show_labels, frame_diff.is_some(), app.use_lut]);
+        // Se arma en cada frame, así que sigue al cuerpo bajo el mouse, a su órbita y a la escena recargada (F9)
+        let info_lines = show_info_card.then(|| focused_body(&bodies, &app.options).map(|index| body_info(&scene, &bodies, index))).flatten();
+        let overlay_state = overlay_state_hash(show_help, info_lines.as_deref(), gallery.selection(), app.loupe.as_ref().map(|loupe| (loupe.magnification, input.mouse)), loader.as_ref().and_then(AssetLoader::overlay_state));
+        let settling = app.still && still_accumulator.frames() < STILL_SETTLE_FRAMES;
+        // Midiendo tiempos se dibujan todos los frames, como sin ventana
+        let redraw = match &window {
+            Some(_) if bench.is_none() => redraw_tracker.update(image_state, overlay_state, settling),
+            _ => Redraw::Full,
+        };
+        if redraw == Redraw::Nothing {
+            if let Some(window) = &mut window {
+                window.update();
+            }
+            std::thread::sleep(IDLE_SLEEP);
+            continue;
+        }
 
-        time += 1;
+        // El frame refinado se sigue mostrando sin volver a dibujarlo hasta que la cámara se mueva
+        if app.progressive && refined {
+            if !present(&mut window, &framebuffer, headless_out_dir, frame_number) {
+                break;
+            }
+            continue;
+        }
 
-        handle_input(&window, &mut camera);
+        // Solo cambió la ayuda, la galería o la lupa: se borran de la última imagen completa y se dibujan de nuevo
+        if redraw == Redraw::OverlaysOnly {
+            if let Some(area) = overlay_area.take() {
+                framebuffer.restore_rect(&clean_frame, area);
+            }
+            if triggered(Action::Screenshot) {
+                save_screenshot(&framebuffer);
+            }
+            overlay_area = draw_overlays(&mut framebuffer, show_help.then_some(&keymap), info_lines.as_deref(), &gallery, app.loupe.as_ref(), input.mouse, loader.as_ref());
+            if !present(&mut window, &framebuffer, headless_out_dir, frame_number) {
+                break;
+            }
+            continue;
+        }
 
-        framebuffer.clear();
+        let low_resolution = app.progressive && !refine;
+        // La vista previa del modo progresivo es a media resolución; la calidad automática puede bajarla más
+        let divisor = if low_resolution { app.quality.resolution_divisor.max(2) } else { app.quality.resolution_divisor };
+        let scaled = divisor > 1;
+        if scaled && preview.width != framebuffer_width / divisor {
+            preview = Framebuffer::new(framebuffer_width / divisor, framebuffer_height / divisor);
+            preview.set_background_color(scene.background);
+        }
+        let target = if scaled {
+            preview.dither = framebuffer.dither;
+            &mut preview
+        } else {
+            &mut framebuffer
+        };
+        let accumulate = app.still && !low_resolution;
+        // El tablero solo se usa a resolución completa y con una sola imagen: la vista previa, el anaglifo
+        // y la imagen fija no tienen un frame anterior comparable. Un frame sin tablero deja viejo el
+        // anterior, así que se olvida
+        let checkered = app.checkerboard && !scaled && !app.anaglyph && !accumulate;
+        if !checkered {
+            checkerboard.reset();
+        }
+        let camera_moved = idle_frames == 0;
 
-        let model_matrix = create_model_matrix(translation, scale, rotation);
-        let view_matrix = create_view_matrix(camera.eye, camera.center, camera.up);
-        let projection_matrix = create_perspective_matrix(window_width as f32, window_height as f32);
-        let viewport_matrix = create_viewport_matrix(framebuffer_width as f32, framebuffer_height as f32);
-        let debug_mode = 0;
-        let uniforms = Uniforms { 
-            model_matrix, 
-            view_matrix, 
-            projection_matrix, 
-            viewport_matrix, 
-            time, 
-            debug_mode,
+        // En el modo estéreo la escena se dibuja desde cada ojo (render_scene borra la profundidad
+        // entre los dos) y se combinan en un anaglifo
+        let mut scene_time = Duration::ZERO;
+        let mut pipeline_stats = RenderStats::default();
+        let eyes = if app.anaglyph {
+            let (left, right) = app.camera.stereo_eyes(app.stereo_separation);
+            vec![left, right]
+        } else {
+            vec![app.camera.eye]
         };
+        for (eye_index, &eye) in eyes.iter().enumerate() {
+            let mut render_uniforms = if scaled || app.anaglyph {
+                let mut view = Camera::new(eye, app.camera.center, app.camera.up);
+                view.projection = app.camera.projection;
+                build_uniforms(&view, time, target.width, target.height, depth_range)
+            } else {
+                uniforms.clone()
+            };
+            if accumulate {
+                post::jitter_uniforms(&mut render_uniforms, post::subpixel_jitter(still_accumulator.frames()));
+            }
+            if checkered {
+                render_uniforms.checkerboard = Some(checkerboard.parity());
+            }
 
-        framebuffer.set_current_color(0xFFDDDD);
-        render(&mut framebuffer, &uniforms, &sphere_vertex_arrays, &current_shader);
+            let scene_start = Instant::now();
+            last_stats = render_scene(target, app.scene, &scene, &render_uniforms, &meshes, &mut scene_state, &app.options);
+            scene_time += scene_start.elapsed();
+            for body in &last_stats {
+                pipeline_stats.add(&body.stats);
+            }
+            // Antes del post-proceso, que usa la profundidad de todos los pixeles
+            if checkered {
+                checkerboard.resolve(target, camera_moved);
+            }
+            if app.options.toon {
+                post::outline(target, &render_uniforms.projection_matrix, OUTLINE_THRESHOLD, OUTLINE_COLOR);
+            }
+            if app.options.ambient_occlusion && app.quality.post_effects && !app.show_depth {
+                post::ambient_occlusion(target, &render_uniforms.projection_matrix, &scene.ambient_occlusion);
+            }
+            if app.show_depth {
+                visualize_depth(target, &render_uniforms.projection_matrix, depth_range);
+            }
+            if eye_index + 1 < eyes.len() {
+                left_eye.clear();
+                left_eye.extend_from_slice(&target.buffer);
+            }
+        }
+        if !scaled && !app.anaglyph {
+            checkerboard.record_shading(pipeline_stats.fragment_time, checkered);
+        }
+        let post_start = Instant::now();
+        if app.anaglyph {
+            post::anaglyph(&left_eye, target);
+        }
+        if scaled {
+            preview.upscale_into(&mut framebuffer);
+        }
+        if let Some(map) = &minimap {
+            map.render(&mut framebuffer, time, &app.camera, &meshes, &mut scene_state, &app.options);
+        }
+        if accumulate {
+            still_accumulator.average(&mut framebuffer);
+        }
 
-        if scene_number == 4 {
-            render(&mut framebuffer, &uniforms, &sphere_vertex_arrays, &current_shader);
-            render_rings(&mut framebuffer, &uniforms, &ring_vertex_array);
+        // En la vista infrarroja los colores son la escala de temperatura: no se les agrega el lens flare
+        // ni se corrigen
+        let false_color = app.show_depth || app.options.thermal;
+        if app.lens_flare && app.quality.post_effects && !false_color {
+            for sun in bodies.iter().filter(|body| body.shader == ShaderType::Sun) {
+                post::lens_flare(&mut framebuffer, &uniforms, sun.translation, sun.bounding_radius(&meshes));
+            }
+        }
+        if !app.show_depth && app.motion_blur {
+            accumulator.apply(&mut framebuffer, MOTION_BLUR_WEIGHT);
+        }
+        if !false_color {
+            post::color_grade(&mut framebuffer, &grade);
+        }
+        if let Some(lut) = lut.as_ref().filter(|_| app.use_lut && !false_color) {
+            post::apply_lut(&mut framebuffer, lut);
+        }
+        if app.show_labels && !app.show_depth {
+            draw_labels(&mut framebuffer, &bodies, &meshes, &uniforms);
+            draw_anchors(&mut framebuffer, &scene.anchors, &bodies, &meshes, &uniforms);
+            if let Some(anchors) = added_anchors.get(&app.scene) {
+                draw_anchors(&mut framebuffer, anchors, &bodies, &meshes, &uniforms);
+            }
+        }
+        drawn_frame = Some((uniforms.clone(), bodies.iter().map(|body| body.model_matrix()).collect()));
+        if app.options.thermal && !app.show_depth {
+            draw_thermal_legend(&mut framebuffer, &scene.thermal_gradient);
+        }
+        if app.options.show_lod_tiers {
+            draw_ring_segments(&mut framebuffer, &last_stats);
+        }
+        if app.checkerboard {
+            draw_checkerboard_stats(&mut framebuffer, &checkerboard, checkered);
+        }
+        if let Some(previous) = &mut frame_diff {
+            let frame = framebuffer.buffer.clone();
+            let stats = post::diff_view(&mut framebuffer, previous);
+            *previous = frame;
+            title_detail = format!("diferencias: {}", stats);
+        }
+        // La calidad del siguiente frame según lo que tardó este (sin contar la espera de la ventana)
+        let next_quality = quality_controller.update(frame_start.elapsed().as_secs_f32() * 1000.0);
+        if next_quality != app.quality {
+            app.quality = next_quality;
+            accumulator.reset();
+            still_accumulator.reset();
+        }
+        // La captura se guarda sin la ayuda encima
+        if triggered(Action::Screenshot) {
+            save_screenshot(&framebuffer);
+        }
+        if window.is_some() {
+            clean_frame.clone_from(&framebuffer.buffer);
+        }
+        overlay_area = draw_overlays(&mut framebuffer, show_help.then_some(&keymap), info_lines.as_deref(), &gallery, app.loupe.as_ref(), input.mouse, loader.as_ref());
+        if let Some(bench) = &mut bench {
+            let times = Stages {
+                frame: frame_start.elapsed(),
+                scene: scene_time,
+                vertex: pipeline_stats.vertex_time,
+                raster: pipeline_stats.raster_time,
+                fragment: pipeline_stats.fragment_time,
+                post: post_start.elapsed(),
+            };
+            bench.measure(times, &format!("{:?}", app.quality.tier));
         }
 
-        if scene_number == 5 {
-            render_scene5(&mut framebuffer, &uniforms, &sphere_vertex_arrays);
+        if !present(&mut window, &framebuffer, headless_out_dir, frame_number) {
+            break;
         }
+    }
+
+    if let Some(path) = &session_path {
+        save_session(&app, path);
+    }
+    if let Some(Err(err)) = recorder.as_mut().map(InputRecorder::flush) {
+        eprintln!("No se pudo grabar la entrada: {}", err);
+    }
+    if let Some(out_dir) = headless_out_dir {
+        println!("{} frames guardados en {}", frame_number, out_dir.display());
+    }
+    if let (Some(bench), Some(bench_options)) = (bench, &args.bench) {
+        save_bench_report(bench, bench_options, &args, (framebuffer_width, framebuffer_height));
+    }
+}
 
-        window
-            .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
-            .unwrap();
+// Carga la sesión guardada en `app`; false si no hay o no se puede leer (se sigue con el estado de la
+// línea de comandos). Las opciones que se pasaron en la línea de comandos y --scene-file mandan sobre las
+// de la sesión, y si la escena guardada ya no existe (la 0 sin --model) se vuelve a la 1
+fn restore_session(path: &Path, app: &mut AppState, command_line: &CommandLineOptions, custom_scene: &mut Option<SceneConfig>, has_model: bool) -> bool {
+    let restored = match AppState::load(path) {
+        Ok(Some(restored)) => restored,
+        Ok(None) => return false,
+        Err(err) => {
+            eprintln!("{}: {} (se empieza sin la sesión)", path.display(), err);
+            return false;
+        }
+    };
+    let scene_file = match (&app.scene_file, &restored.scene_file) {
+        (Some(file), _) => Some(file.clone()),
+        (None, Some(file)) => match SceneConfig::from_file(file) {
+            Ok(scene) => {
+                *custom_scene = Some(scene);
+                Some(file.clone())
+            }
+            Err(err) => {
+                eprintln!("{}: {}", file, err);
+                None
+            }
+        },
+        (None, None) => None,
+    };
+    // Los hilos de --threads no se guardan en la sesión
+    let threads = app.options.threads;
+    *app = AppState { scene_file, ..restored }.with_command_line(command_line);
+    app.options.threads = threads;
+    if !gallery_scenes(has_model).contains(&app.scene) {
+        app.scene = 1;
     }
+    println!("Sesión retomada de {}", path.display());
+    true
 }
 
-fn handle_input(window: &Window, camera: &mut Camera) {
+fn save_session(app: &AppState, path: &Path) -> bool {
+    match app.save(path) {
+        Ok(()) => true,
+        Err(err) => {
+            eprintln!("No se pudo guardar la sesión en {}: {}", path.display(), err);
+            false
+        }
+    }
+}
+
+// Reporte de --bench-exit; si el bucle terminó antes (se cerró la ventana o se acabó la repetición) se
+// guarda igual con los frames que hubo y un aviso
+fn save_bench_report(bench: BenchRecorder, bench_options: &BenchOptions, args: &Args, (width, height): (usize, usize)) {
+    if bench.frames() < bench_options.frames {
+        eprintln!("Solo se midieron {} de los {} frames de --bench-exit", bench.frames(), bench_options.frames);
+    }
+    let settings = RunSettings {
+        input: match &args.replay {
+            Some(replay) => replay.path.display().to_string(),
+            None if args.demo => "demo".to_string(),
+            None => "teclado".to_string(),
+        },
+        fixed_quality: args.fixed_quality || args.replay.is_some(),
+        target_fps: args.target_fps,
+        procedural: args.procedural,
+        dither: args.dither,
+        toon: args.toon,
+        ambient_occlusion: args.ambient_occlusion,
+        particle_rings: args.particle_rings,
+        adaptive_ring: args.adaptive_ring,
+        progressive: args.progressive,
+        checkerboard: args.checkerboard,
+        fragment_budget: args.fragment_budget.unwrap_or(RenderOptions::default().fragment_budget),
+        threads: args.threads.unwrap_or(RenderOptions::default().threads),
+        seed: args.seed.unwrap_or_default(),
+    };
+    let report = bench.report(Resolution { width, height }, settings);
+    match report.save(&bench_options.out) {
+        Ok(()) => println!(
+            "Reporte de {} frames en {}: mediana {:.2} ms, p95 {:.2} ms",
+            report.measured_frames, bench_options.out.display(), report.stages.frame.median_ms, report.stages.frame.p95_ms,
+        ),
+        Err(err) => {
+            eprintln!("No se pudo guardar {}: {}", bench_options.out.display(), err);
+            std::process::exit(1);
+        }
+    }
+}
+
+// Muestra el frame en la ventana o, sin ventana, lo guarda como frame_NNNNNN.png; false si no se pudo
+fn present(window: &mut Option<Window>, framebuffer: &Framebuffer, out_dir: Option<&Path>, frame_number: u32) -> bool {
+    match (window, out_dir) {
+        (Some(window), _) => match window.update_with_buffer(&framebuffer.buffer, framebuffer.width, framebuffer.height) {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("No se pudo actualizar la ventana: {}", err);
+                false
+            }
+        },
+        (None, Some(out_dir)) => {
+            let path = out_dir.join(format!("frame_{:06}.png", frame_number));
+            match save_png(framebuffer, &path) {
+                Ok(()) => true,
+                Err(err) => {
+                    eprintln!("No se pudo guardar {}: {}", path.display(), err);
+                    false
+                }
+            }
+        }
+        (None, None) => false,
+    }
+}
+
+// Con la calidad automática también se muestra el escalón actual
+fn window_title(detail: &str, clock: &SimClock, quality: Option<QualityTier>, bookmark: Option<usize>) -> String {
+    let mut title = "Planets Render".to_string();
+    let bookmark = bookmark.map_or(String::new(), |slot| format!("marcador {}", slot));
+    for part in [detail, &clock.label(), quality.map_or("", QualityTier::label), &bookmark] {
+        if !part.is_empty() {
+            title = format!("{} - {}", title, part);
+        }
+    }
+    title
+}
+
+// Tarjeta del cuerpo, galería, lupa, carga de las mallas y ayuda encima de la imagen terminada; devuelve
+// la zona que taparon
+fn draw_overlays(framebuffer: &mut Framebuffer, help: Option<&KeyMap>, info_lines: Option<&[String]>, gallery: &Gallery, loupe: Option<&Loupe>, mouse: Option<(f32, f32)>, loader: Option<&AssetLoader>) -> Option<Rect> {
+    let info_area = info_lines.and_then(|lines| draw_info_card(framebuffer, lines));
+    let gallery_area = gallery.draw(framebuffer);
+    let loading_area = loader.and_then(|loader| loader.draw(framebuffer));
+    let loupe_area = match (loupe, mouse) {
+        (Some(loupe), Some((x, y))) => loupe.draw(framebuffer, x as usize, y as usize),
+        _ => None,
+    };
+    let help_area = help.map(|keymap| keymap.draw_help(framebuffer));
+    [info_area, gallery_area, loading_area, loupe_area, help_area].into_iter().flatten().reduce(|all, area| all.union(&area))
+}
+
+// Lo que cambia la imagen además de lo que reinicia la imagen fija: efectos, corrección de color y calidad
+fn image_state_hash(still_state: u64, quality: &QualitySettings, grade: &ColorGrade, stereo_separation: f32, flags: [bool; 6]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    still_state.hash(&mut hasher);
+    format!("{:?} {:?}", quality, grade).hash(&mut hasher);
+    stereo_separation.to_bits().hash(&mut hasher);
+    flags.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Lo que cambia la ayuda, la tarjeta, la galería, la lupa y la carga: si se ven, lo que dice la tarjeta, la
+// miniatura elegida, el aumento, dónde está el mouse y el avance de la carga
+fn overlay_state_hash(show_help: bool, info_lines: Option<&[String]>, gallery: Option<usize>, loupe: Option<(usize, Option<(f32, f32)>)>, loading: Option<(String, u64, usize, u32)>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    show_help.hash(&mut hasher);
+    info_lines.hash(&mut hasher);
+    gallery.hash(&mut hasher);
+    format!("{:?}", loupe).hash(&mut hasher);
+    loading.hash(&mut hasher);
+    hasher.finish()
+}
+
+// Resumen de lo que cambia la imagen: cámara, escena, tiempo, velocidad y opciones de los shaders
+fn still_state_hash(camera: &Camera, scene_number: u32, time: f32, clock: &SimClock, options: &RenderOptions, flags: [bool; 6]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for value in camera.eye.iter().chain(camera.center.iter()).chain(camera.up.iter()) {
+        value.to_bits().hash(&mut hasher);
+    }
+    format!("{:?}", camera.projection).hash(&mut hasher);
+    scene_number.hash(&mut hasher);
+    time.to_bits().hash(&mut hasher);
+    clock.label().hash(&mut hasher);
+    format!("{:?}", options).hash(&mut hasher);
+    flags.hash(&mut hasher);
+    hasher.finish()
+}
+
+// --diff: la vista de diferencias entre dos capturas del mismo tamaño, guardada como PNG
+fn compare_screenshots(options: &DiffOptions) -> Result<post::DiffStats, String> {
+    let load = |path: &Path| load_png(path).map_err(|err| format!("No se pudo leer {}: {}", path.display(), err));
+    let old = load(&options.old)?;
+    let mut new = load(&options.new)?;
+    if (old.width, old.height) != (new.width, new.height) {
+        return Err(format!(
+            "Las imágenes son de distinto tamaño: {}x{} y {}x{}",
+            old.width, old.height, new.width, new.height
+        ));
+    }
+    let stats = post::diff_view(&mut new, &old.buffer);
+    save_png(&new, &options.out).map_err(|err| format!("No se pudo guardar {}: {}", options.out.display(), err))?;
+    Ok(stats)
+}
+
+// Guarda la imagen en captura_N.png con el primer N libre
+fn save_screenshot(framebuffer: &Framebuffer) {
+    let path = (1..).map(|number| PathBuf::from(format!("captura_{:03}.png", number)))
+        .find(|path| !path.exists())
+        .expect("siempre hay un nombre libre");
+    match save_png(framebuffer, &path) {
+        Ok(()) => println!("Captura guardada en {}", path.display()),
+        Err(err) => eprintln!("No se pudo guardar la captura en {}: {}", path.display(), err),
+    }
+}
+
+// Devuelve false si no se pudo escribir el archivo
+fn export_scene(path: &Path, scene: &SceneConfig, uniforms: &FrameUniforms, meshes: &SceneMeshes, state: &SceneState, options: &RenderOptions) -> bool {
+    match save_ply(path, scene, uniforms, meshes, state, options) {
+        Ok(triangles) => {
+            println!("Escena exportada a {} ({} triángulos)", path.display(), triangles);
+            true
+        }
+        Err(err) => {
+            eprintln!("No se pudo exportar la escena a {}: {}", path.display(), err);
+            false
+        }
+    }
+}
+
+// --cubemap: el cielo visto desde el centro del cuerpo en el instante 0, sin el cuerpo, en seis PNG
+fn bake_cubemap(cubemap_options: &CubemapOptions, scene: &SceneConfig, meshes: &SceneMeshes, options: RenderOptions) -> Result<(), String> {
+    let bodies = scene.bodies(0.0);
+    let index = bodies.iter().position(|body| body.name.eq_ignore_ascii_case(&cubemap_options.body)).ok_or_else(|| {
+        let names: Vec<&str> = bodies.iter().map(|body| body.name.as_str()).collect();
+        format!("La escena {} no tiene un cuerpo llamado \"{}\" (tiene: {})", cubemap_options.scene, cubemap_options.body, names.join(", "))
+    })?;
+    let options = RenderOptions { hidden_body: Some(index), ..options };
+    let faces = render_cubemap(scene, meshes, bodies[index].translation, cubemap_options.size, 0.0, &options);
+    for (face, framebuffer) in CUBE_FACES.iter().zip(&faces) {
+        let mut name = cubemap_options.prefix.clone().into_os_string();
+        name.push(format!("_{}.png", face.name));
+        let path = PathBuf::from(name);
+        save_png(framebuffer, &path).map_err(|err| format!("No se pudo guardar {}: {}", path.display(), err))?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+// Nivel y segmentos con los que se dibujó cada anillo adaptativo
+fn draw_ring_segments(framebuffer: &mut Framebuffer, body_stats: &[BodyStats]) {
+    let rings = body_stats.iter().filter_map(|body| body.ring_segments.map(|segments| (&body.name, segments)));
+    for (row, (name, segments)) in rings.enumerate() {
+        let tier = RING_SEGMENT_TIERS.iter().position(|&count| count == segments).unwrap_or(0);
+        let text = format!("{}: nivel {}, {} segmentos", name, tier, segments);
+        draw_text(framebuffer, RING_SEGMENTS_MARGIN, RING_SEGMENTS_MARGIN + row * line_height(1), &text, RING_SEGMENTS_COLOR, 1);
+    }
+}
+
+// Tiempo de sombreado con el tablero y cuánto ahorra respecto de los frames completos, abajo a la izquierda
+fn draw_checkerboard_stats(framebuffer: &mut Framebuffer, checkerboard: &Checkerboard, active: bool) {
+    let text = match (active, checkerboard.shading_ms(), checkerboard.reduction()) {
+        (false, _, _) => "tablero: sin efecto en este modo".to_string(),
+        (true, (Some(checker), Some(full)), Some(reduction)) => {
+            format!("tablero: sombreado {:.2} ms (completo {:.2} ms, {:+.0}%)", checker, full, -reduction * 100.0)
+        }
+        (true, (Some(checker), _), _) => format!("tablero: sombreado {:.2} ms", checker),
+        (true, _, _) => "tablero".to_string(),
+    };
+    let y = framebuffer.height.saturating_sub(RING_SEGMENTS_MARGIN + line_height(1));
+    draw_text(framebuffer, RING_SEGMENTS_MARGIN, y, &text, RING_SEGMENTS_COLOR, 1);
+}
+
+fn print_stats(body_stats: &[BodyStats]) {
+    let mut total = RenderStats::default();
+    println!("Estadísticas del último frame:");
+    for body in body_stats {
+        match body.ring_segments {
+            Some(segments) => println!("  {} ({:?}, {} segmentos): {}", body.name, body.shader, segments, body.stats),
+            None => println!("  {} ({:?}): {}", body.name, body.shader, body.stats),
+        }
+        total.add(&body.stats);
+    }
+    println!("  Total: {}", total);
+}
+
+fn select_scene(scene_number: u32, custom_scene: &Option<SceneConfig>) -> SceneConfig {
+    match custom_scene {
+        Some(custom) if scene_number == CUSTOM_SCENE => custom.clone(),
+        _ => SceneConfig::builtin(scene_number),
+    }
+}
+
+// Escenas que tienen recorrido para el modo demo
+fn demo_scenes(custom_scene: &Option<SceneConfig>) -> Vec<u32> {
+    (1..=9)
+        .filter(|&number| !select_scene(number, custom_scene).tour.is_empty())
+        .collect()
+}
+
+// Escenas de la galería: la 0 solo con --model
+fn gallery_scenes(has_model: bool) -> Vec<u32> {
+    (0..=9).filter(|&number| number > 0 || has_model).collect()
+}
+
+#[derive(Default)]
+struct MouseState {
+    last_position: Option<(f32, f32)>,
+    press_position: Option<(f32, f32)>,
+    dragged: bool,
+    clicked: Option<(f32, f32)>,
+}
+
+fn handle_input(input: &InputFrame, camera: &mut Camera, mouse: &mut MouseState) {
+    let actions = &input.actions;
     let movement_speed = 1.0;
     let rotation_speed = PI/50.0;
     let zoom_speed = 0.1;
+    let mouse_orbit_speed = 0.01;  // radianes por pixel arrastrado
+    let mouse_pan_speed = 0.05;    // movimiento del centro por pixel arrastrado
+    let scroll_zoom_speed = 0.05;  // zoom por unidad de la rueda
+    let click_tolerance = 3.0;     // pixeles que se puede mover el mouse y seguir siendo click
    
     //  Camara orbital
-    if window.is_key_down(Key::Left) {
+    if actions.contains(&Action::OrbitLeft) {
       camera.orbit(rotation_speed, 0.0);
     }
-    if window.is_key_down(Key::Right) {
+    if actions.contains(&Action::OrbitRight) {
       camera.orbit(-rotation_speed, 0.0);
     }
-    if window.is_key_down(Key::Up) {
+    if actions.contains(&Action::OrbitUp) {
       camera.orbit(0.0, -rotation_speed);
     }
-    if window.is_key_down(Key::Down) {
+    if actions.contains(&Action::OrbitDown) {
       camera.orbit(0.0, rotation_speed);
     }
 
     // Camara movimiento
     let mut movement = Vec3::new(0.0, 0.0, 0.0);
-    if window.is_key_down(Key::A) {
+    if actions.contains(&Action::PanLeft) {
       movement.x -= movement_speed;
     }
-    if window.is_key_down(Key::D) {
+    if actions.contains(&Action::PanRight) {
       movement.x += movement_speed;
     }
-    if window.is_key_down(Key::W) {
+    if actions.contains(&Action::PanUp) {
       movement.y += movement_speed;
     }
-    if window.is_key_down(Key::S) {
+    if actions.contains(&Action::PanDown) {
       movement.y -= movement_speed;
     }
     if movement.magnitude() > 0.0 {
@@ -349,10 +1461,49 @@ fn handle_input(window: &Window, camera: &mut Camera) {
     }
 
     // Zoom
-    if window.is_key_down(Key::M) {
+    if actions.contains(&Action::ZoomIn) {
       camera.zoom(zoom_speed);
     }
-    if window.is_key_down(Key::N) {
+    if actions.contains(&Action::ZoomOut) {
       camera.zoom(-zoom_speed);
     }
+
+    // Mouse: arrastrar con el izquierdo orbita, con el derecho o el central mueve el centro
+    let left = input.left_button;
+    let pan = input.pan_button;
+
+    // Fuera de la ventana no hay posición; al volver se empieza de nuevo sin saltos
+    let position = input.mouse;
+    if let (Some((x, y)), Some((last_x, last_y))) = (position, mouse.last_position) {
+      let (dx, dy) = (x - last_x, y - last_y);
+      if left {
+        camera.orbit(-dx * mouse_orbit_speed, dy * mouse_orbit_speed);
+      } else if pan && (dx != 0.0 || dy != 0.0) {
+        camera.move_center(Vec3::new(dx * mouse_pan_speed, -dy * mouse_pan_speed, 0.0));
+      }
+    }
+    mouse.last_position = position;
+
+    // Un click es presionar y soltar sin arrastrar
+    match (left, mouse.press_position, position) {
+      (true, None, Some(pos)) => {
+        mouse.press_position = Some(pos);
+        mouse.dragged = false;
+      }
+      (true, Some((px, py)), Some((x, y)))
+        if (x - px).abs() > click_tolerance || (y - py).abs() > click_tolerance => {
+        mouse.dragged = true;
+      }
+      (false, Some(pos), _) => {
+        if !mouse.dragged {
+          mouse.clicked = Some(pos);
+        }
+        mouse.press_position = None;
+      }
+      _ => {}
+    }
+
+    if let Some(scroll_y) = input.scroll {
+      camera.zoom(scroll_y * scroll_zoom_speed);
+    }
 }
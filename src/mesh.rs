@@ -0,0 +1,313 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use crate::geometry::{Aabb, BoundingSphere};
+use crate::noise;
+use crate::shaders::spherical_uv;
+use crate::vertex::Vertex;
+
+// Mismo radio que models/sphere.obj, los shaders dependen de esa escala
+pub const SPHERE_RADIUS: f32 = 0.5;
+// Radios del anillo (los mismos de models/ring.obj)
+pub const RING_INNER_RADIUS: f32 = 1.49;
+pub const RING_OUTER_RADIUS: f32 = 2.87;
+
+// Caja y esfera envolventes de una malla en el espacio del objeto. La esfera está centrada en el origen
+// del objeto, alrededor del cual gira el cuerpo, así que con la traslación como centro sigue envolviéndolo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MeshBounds {
+    pub aabb: Aabb,
+    pub sphere: BoundingSphere,
+}
+
+impl MeshBounds {
+    // Sin puntos queda un punto en el origen
+    pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Self {
+        let origin = Vec3::zeros();
+        MeshBounds {
+            aabb: Aabb::from_points(points.clone()).unwrap_or(Aabb::new(origin, origin)),
+            sphere: BoundingSphere::around(origin, points),
+        }
+    }
+
+    pub fn of(vertices: &[Vertex]) -> Self {
+        Self::from_points(vertices.iter().map(|vertex| vertex.position))
+    }
+
+    // Las dos esferas están centradas en el origen, así que alcanza con la más grande
+    pub fn union(&self, other: &MeshBounds) -> MeshBounds {
+        MeshBounds { aabb: self.aabb.union(&other.aabb), sphere: BoundingSphere::new(self.sphere.center, self.sphere.radius.max(other.sphere.radius)) }
+    }
+}
+
+fn sphere_vertex(direction: Vec3, tex_coords: Vec2) -> Vertex {
+    Vertex::new(direction * SPHERE_RADIUS, direction, tex_coords)
+}
+
+pub fn generate_uv_sphere(stacks: u32, slices: u32) -> Vec<Vertex> {
+    let stacks = stacks.max(2);
+    let slices = slices.max(3);
+
+    // Misma convención que spherical_uv: u = 0.5 en +x y crece hacia +z, con la costura en -x. La
+    // columna de la costura se repite con u = 1 (en la misma posición que la de u = 0) para no estirar
+    // la textura
+    let point = |stack: u32, slice: u32| {
+        let theta = PI * stack as f32 / stacks as f32;
+        let longitude = 2.0 * PI * (slice % slices) as f32 / slices as f32 - PI;
+        let direction = Vec3::new(theta.sin() * longitude.cos(), theta.cos(), theta.sin() * longitude.sin());
+        let u = slice as f32 / slices as f32;
+        let v = stack as f32 / stacks as f32;
+        (direction, Vec2::new(u, v))
+    };
+
+    let mut vertices = Vec::with_capacity((stacks * slices * 6) as usize);
+    for stack in 0..stacks {
+        for slice in 0..slices {
+            let (a, uv_a) = point(stack, slice);
+            let (b, uv_b) = point(stack + 1, slice);
+            let (c, uv_c) = point(stack + 1, slice + 1);
+            let (d, uv_d) = point(stack, slice + 1);
+
+            // En los polos cada triángulo tiene su propio vértice con u en el centro de la columna
+            let pole_u = (slice as f32 + 0.5) / slices as f32;
+
+            // La longitud crece hacia +z, así que a, d, b queda en sentido antihorario visto desde afuera
+            if stack == 0 {
+                let top = Vec2::new(pole_u, 0.0);
+                vertices.extend([sphere_vertex(a, top), sphere_vertex(c, uv_c), sphere_vertex(b, uv_b)]);
+            } else if stack == stacks - 1 {
+                let bottom = Vec2::new(pole_u, 1.0);
+                vertices.extend([sphere_vertex(a, uv_a), sphere_vertex(d, uv_d), sphere_vertex(b, bottom)]);
+            } else {
+                vertices.extend([sphere_vertex(a, uv_a), sphere_vertex(d, uv_d), sphere_vertex(b, uv_b)]);
+                vertices.extend([sphere_vertex(d, uv_d), sphere_vertex(c, uv_c), sphere_vertex(b, uv_b)]);
+            }
+        }
+    }
+
+    vertices
+}
+
+// Vértices a menos de esto del eje o del plano z = 0 cuentan como polo o como parte de la costura
+const SEAM_EPSILON: f32 = 1e-6;
+
+fn is_pole(direction: &Vec3) -> bool {
+    direction.x.abs() < SEAM_EPSILON && direction.z.abs() < SEAM_EPSILON
+}
+
+fn is_on_seam(direction: &Vec3) -> bool {
+    direction.x < 0.0 && direction.z.abs() < SEAM_EPSILON && !is_pole(direction)
+}
+
+// Punto donde la arista cruza el plano z = 0, llevado a la esfera. Se calcula siempre desde el extremo con
+// z positiva para que las dos caras que comparten la arista obtengan exactamente el mismo vértice
+fn seam_crossing(a: Vec3, b: Vec3) -> Vec3 {
+    let (from, to) = if a.z > 0.0 { (a, b) } else { (b, a) };
+    let t = from.z / (from.z - to.z);
+    (from + (to - from) * t).normalize()
+}
+
+// Las partes de la cara con z >= 0 y con z <= 0 (cada una en abanico desde su primer vértice), con el mismo
+// sentido de giro que la cara
+fn split_at_seam(triangle: &[Vec3; 3]) -> Vec<(bool, [Vec3; 3])> {
+    let mut parts = Vec::new();
+    for positive in [true, false] {
+        let inside = |point: &Vec3| point.z.abs() < SEAM_EPSILON || (point.z > 0.0) == positive;
+        let mut polygon = Vec::with_capacity(4);
+        for i in 0..3 {
+            let (current, next) = (triangle[i], triangle[(i + 1) % 3]);
+            if inside(&current) {
+                polygon.push(current);
+            }
+            let crosses = current.z.abs() >= SEAM_EPSILON && next.z.abs() >= SEAM_EPSILON && (current.z > 0.0) != (next.z > 0.0);
+            if crosses {
+                polygon.push(seam_crossing(current, next));
+            }
+        }
+        for i in 1..polygon.len().saturating_sub(1) {
+            parts.push((positive, [polygon[0], polygon[i], polygon[i + 1]]));
+        }
+    }
+    parts
+}
+
+pub fn generate_icosphere(subdivisions: u32) -> Vec<Vertex> {
+    let t = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let corners = [
+        Vec3::new(-1.0, t, 0.0), Vec3::new(1.0, t, 0.0), Vec3::new(-1.0, -t, 0.0), Vec3::new(1.0, -t, 0.0),
+        Vec3::new(0.0, -1.0, t), Vec3::new(0.0, 1.0, t), Vec3::new(0.0, -1.0, -t), Vec3::new(0.0, 1.0, -t),
+        Vec3::new(t, 0.0, -1.0), Vec3::new(t, 0.0, 1.0), Vec3::new(-t, 0.0, -1.0), Vec3::new(-t, 0.0, 1.0),
+    ];
+    let faces: [[usize; 3]; 20] = [
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    let mut triangles: Vec<[Vec3; 3]> = faces.iter()
+        .map(|face| face.map(|index| corners[index].normalize()))
+        .collect();
+
+    for _ in 0..subdivisions {
+        triangles = triangles.iter()
+            .flat_map(|[a, b, c]| {
+                let ab = ((a + b) / 2.0).normalize();
+                let bc = ((b + c) / 2.0).normalize();
+                let ca = ((c + a) / 2.0).normalize();
+                [[*a, ab, ca], [ab, *b, bc], [ca, bc, *c], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    // u sale de spherical_uv, igual que en la esfera UV. Las caras que cruzan la costura se parten en el
+    // plano z = 0 para que cada parte quede de un solo lado y u no tenga que salirse de [0, 1]
+    let mut parts: Vec<(bool, [Vec3; 3])> = Vec::with_capacity(triangles.len());
+    for triangle in &triangles {
+        let us: Vec<f32> = triangle.iter()
+            .filter(|direction| !is_pole(direction) && !is_on_seam(direction))
+            .map(|direction| spherical_uv(*direction).x)
+            .collect();
+        let min_u = us.iter().copied().fold(f32::INFINITY, f32::min);
+        let max_u = us.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        if max_u - min_u > 0.5 {
+            parts.extend(split_at_seam(triangle));
+        } else {
+            // Los vértices sobre la costura toman la u del lado en el que está el resto de la cara
+            parts.push((triangle.iter().any(|direction| direction.z >= SEAM_EPSILON), *triangle));
+        }
+    }
+
+    let mut vertices = Vec::with_capacity(parts.len() * 3);
+    for (positive, triangle) in &parts {
+        let mut uvs = triangle.map(spherical_uv);
+        for i in 0..3 {
+            if is_on_seam(&triangle[i]) {
+                uvs[i].x = if *positive { 1.0 } else { 0.0 };
+            }
+        }
+
+        // En los polos u no está definido, se usa el promedio de los otros dos vértices
+        for i in 0..3 {
+            if is_pole(&triangle[i]) {
+                uvs[i].x = (uvs[(i + 1) % 3].x + uvs[(i + 2) % 3].x) / 2.0;
+            }
+        }
+
+        for (direction, uv) in triangle.iter().zip(uvs) {
+            vertices.push(sphere_vertex(*direction, uv));
+        }
+    }
+
+    vertices
+}
+
+// Anillo plano en el plano XZ con la normal hacia +Y (u = ángulo, v = radio)
+pub fn generate_ring(inner_r: f32, outer_r: f32, segments: u32) -> Vec<Vertex> {
+    let segments = segments.max(3);
+    let normal = Vec3::new(0.0, 1.0, 0.0);
+
+    let point = |segment: u32, outer: bool| {
+        let angle = 2.0 * PI * segment as f32 / segments as f32;
+        let radius = if outer { outer_r } else { inner_r };
+        let position = Vec3::new(radius * angle.cos(), 0.0, -radius * angle.sin());
+        let tex_coords = Vec2::new(segment as f32 / segments as f32, if outer { 1.0 } else { 0.0 });
+        Vertex::new(position, normal, tex_coords)
+    };
+
+    let mut vertices = Vec::with_capacity((segments * 6) as usize);
+    for segment in 0..segments {
+        let inner_a = point(segment, false);
+        let outer_a = point(segment, true);
+        let inner_b = point(segment + 1, false);
+        let outer_b = point(segment + 1, true);
+
+        vertices.extend([inner_a.clone(), outer_a, outer_b.clone()]);
+        vertices.extend([inner_a, outer_b, inner_b]);
+    }
+
+    vertices
+}
+
+// Segmentos de cada nivel del anillo adaptativo, del más fino al más simple. Cada uno es el doble del
+// siguiente, así los vértices de un nivel también están en los más finos y u (el ángulo) y v (el radio)
+// caen en el mismo lugar: las bandas no se mueven al cambiar de nivel
+pub const RING_SEGMENT_TIERS: [u32; 6] = [4096, 2048, 1024, 512, 256, 128];
+
+pub fn generate_ring_tiers(inner_r: f32, outer_r: f32) -> Vec<Vec<Vertex>> {
+    RING_SEGMENT_TIERS.iter().map(|&segments| generate_ring(inner_r, outer_r, segments)).collect()
+}
+
+// Semilla del ruido que deforma la roca de los asteroides
+pub const ROCK_SEED: u32 = 0x0A57;
+// Cuánto se hunde o sobresale la superficie de la roca, en fracción del radio
+const ROCK_ROUGHNESS: f32 = 0.45;
+
+// Roca de pocos polígonos: icosfera con cada vértice movido según ruido (el mismo en los vértices
+// compartidos, así no se abren huecos) y una normal por cara para que se vean las facetas
+pub fn generate_rock(seed: u32) -> Vec<Vertex> {
+    let displaced = |direction: Vec3| {
+        let bump = noise::fbm3(seed, direction * 1.5, 2) - 0.5;
+        direction * SPHERE_RADIUS * (1.0 + bump * 2.0 * ROCK_ROUGHNESS)
+    };
+
+    generate_icosphere(1).chunks_exact(3)
+        .flat_map(|triangle| {
+            let positions = [0, 1, 2].map(|i| displaced(triangle[i].normal));
+            let normal = (positions[1] - positions[0]).cross(&(positions[2] - positions[0])).normalize();
+            // Hacia afuera sin importar el orden de los vértices
+            let normal = if normal.dot(&positions[0]) < 0.0 { -normal } else { normal };
+            [0, 1, 2].map(|i| Vertex::new(positions[i], normal, triangle[i].tex_coords))
+        })
+        .collect()
+}
+
+// Relieve de un cuerpo: cuánto sube o baja la superficie (fracción del radio) y la escala y octavas del ruido
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DisplaceParams {
+    pub amplitude: f32,
+    pub frequency: f32,
+    pub octaves: u32,
+}
+
+// Vértices a menos de 1/WELD_PRECISION en cada eje se toman como el mismo punto de la superficie
+const WELD_PRECISION: f32 = 1e5;
+
+fn weld_key(position: &Vec3) -> [i64; 3] {
+    [position.x, position.y, position.z].map(|value| (value * WELD_PRECISION).round() as i64)
+}
+
+// Copia de la malla con cada vértice movido hacia afuera o hacia adentro del centro según ruido fbm
+// de su dirección: los vértices que coinciden se mueven juntos, así no se abren grietas entre los
+// triángulos. Las normales se vuelven a calcular promediando las caras de alrededor (pesadas por su
+// área), para que la luz siga al relieve nuevo
+pub fn displace(vertices: &[Vertex], seed: u32, amplitude: f32, frequency: f32, octaves: u32) -> Vec<Vertex> {
+    let mut groups: HashMap<[i64; 3], usize> = HashMap::new();
+    let mut positions: Vec<Vec3> = Vec::new();
+    let group_of: Vec<usize> = vertices.iter()
+        .map(|vertex| *groups.entry(weld_key(&vertex.position)).or_insert_with(|| {
+            let direction = vertex.position.try_normalize(f32::EPSILON).unwrap_or(vertex.normal);
+            let height = noise::fbm3(seed, direction * frequency, octaves) * 2.0 - 1.0;
+            positions.push(vertex.position * (1.0 + amplitude * height));
+            positions.len() - 1
+        }))
+        .collect();
+
+    let mut normals = vec![Vec3::zeros(); positions.len()];
+    for triangle in group_of.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i]]);
+        let normal = (b - a).cross(&(c - a));
+        // Hacia afuera sin importar el orden de los vértices
+        let normal = if normal.dot(&(a + b + c)) < 0.0 { -normal } else { normal };
+        for &group in triangle {
+            normals[group] += normal;
+        }
+    }
+
+    vertices.iter().zip(&group_of)
+        .map(|(vertex, &group)| {
+            let normal = normals[group].try_normalize(f32::EPSILON).unwrap_or(vertex.normal);
+            Vertex { color: vertex.color, ..Vertex::new(positions[group], normal, vertex.tex_coords) }
+        })
+        .collect()
+}
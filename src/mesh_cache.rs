@@ -0,0 +1,87 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use crate::color::Color;
+use crate::vertex::Vertex;
+
+// Caché binaria de los vértices ya expandidos de un OBJ, junto al archivo original ("modelo.obj.mcache").
+// Formato little-endian: encabezado y luego 11 f32 por vértice (posición, normal, uv y color)
+const MAGIC: [u8; 4] = *b"LMC\0";
+const VERSION: u32 = 3;
+const HEADER_SIZE: usize = 4 + 4 + 8 + 8 + 4 + 8;
+const FLOATS_PER_VERTEX: usize = 11;
+const VERTEX_SIZE: usize = FLOATS_PER_VERTEX * 4;
+
+pub fn cache_path(source: &Path) -> PathBuf {
+    let mut path = source.as_os_str().to_owned();
+    path.push(".mcache");
+    PathBuf::from(path)
+}
+
+// Tamaño y fecha de modificación del archivo original; si cambian la caché ya no sirve
+fn source_stamp(metadata: &Metadata) -> (u64, u64, u32) {
+    let modified = metadata.modified().ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    (metadata.len(), modified.as_secs(), modified.subsec_nanos())
+}
+
+// Devuelve None si no hay caché, si es de otra versión, si está corrupta o si el OBJ cambió
+pub fn read(source: &Path) -> Option<Vec<Vertex>> {
+    let stamp = source_stamp(&fs::metadata(source).ok()?);
+    let data = fs::read(cache_path(source)).ok()?;
+    if data.len() < HEADER_SIZE || data[0..4] != MAGIC {
+        return None;
+    }
+
+    let u32_at = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+    let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+    if u32_at(4) != VERSION || (u64_at(8), u64_at(16), u32_at(24)) != stamp {
+        return None;
+    }
+
+    let count = usize::try_from(u64_at(28)).ok()?;
+    let body = &data[HEADER_SIZE..];
+    if count.checked_mul(VERTEX_SIZE)? != body.len() {
+        return None;
+    }
+
+    let vertices = body.chunks_exact(VERTEX_SIZE).map(|chunk| {
+        let mut values = [0.0f32; FLOATS_PER_VERTEX];
+        for (value, bytes) in values.iter_mut().zip(chunk.chunks_exact(4)) {
+            *value = f32::from_le_bytes(bytes.try_into().unwrap());
+        }
+        let [px, py, pz, nx, ny, nz, u, v, r, g, b] = values;
+        let color = Color { r, g, b };
+        Vertex { color, ..Vertex::new(Vec3::new(px, py, pz), Vec3::new(nx, ny, nz), Vec2::new(u, v)) }
+    }).collect();
+
+    Some(vertices)
+}
+
+// Se escribe en un archivo temporal y se renombra, así nunca queda una caché a medias
+pub fn write(source: &Path, vertices: &[Vertex]) -> io::Result<()> {
+    let (size, secs, nanos) = source_stamp(&fs::metadata(source)?);
+
+    let mut data = Vec::with_capacity(HEADER_SIZE + vertices.len() * VERTEX_SIZE);
+    data.extend_from_slice(&MAGIC);
+    data.extend_from_slice(&VERSION.to_le_bytes());
+    data.extend_from_slice(&size.to_le_bytes());
+    data.extend_from_slice(&secs.to_le_bytes());
+    data.extend_from_slice(&nanos.to_le_bytes());
+    data.extend_from_slice(&(vertices.len() as u64).to_le_bytes());
+    for vertex in vertices {
+        let (p, n, t, c) = (&vertex.position, &vertex.normal, &vertex.tex_coords, &vertex.color);
+        for value in [p.x, p.y, p.z, n.x, n.y, n.z, t.x, t.y, c.r, c.g, c.b] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+
+    let path = cache_path(source);
+    let mut temp = path.clone().into_os_string();
+    temp.push(".tmp");
+    fs::write(&temp, &data)?;
+    fs::rename(&temp, &path)
+}
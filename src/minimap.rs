@@ -0,0 +1,130 @@
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+use crate::camera::{Camera, Projection};
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::geometry::BoundingSphere;
+use crate::pipeline::{build_viewport_uniforms, FrameUniforms};
+use crate::scene::{render_scene, scene_depth_range, system_bounds, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+
+// Lado del minimapa y separación de los bordes de la ventana, en pixeles
+pub const MINIMAP_SIZE: usize = 200;
+const MINIMAP_MARGIN: usize = 10;
+// Espacio alrededor del sistema dentro del minimapa
+const MINIMAP_PADDING: f32 = 1.1;
+const BORDER_COLOR: Color = Color::new(200, 205, 215);
+// Marcador de la cámara principal: triángulo que apunta hacia donde mira
+const MARKER_COLOR: Color = Color::new(255, 235, 60);
+const MARKER_LENGTH: f32 = 8.0;
+const MARKER_WIDTH: f32 = 6.0;
+// Trazo de las órbitas: puntos sueltos sobre el fondo, sin tapar los cuerpos
+const ORBIT_COLOR: Color = Color::new(70, 80, 100);
+const ORBIT_SAMPLES: usize = 360;
+
+// Vista fija desde arriba de todo el sistema en la esquina inferior derecha, con la posición de la cámara
+pub struct Minimap {
+    pub rect: Rect,
+    scene_number: u32,
+    camera: Camera,
+    // Sin niebla: desde arriba todo queda a distancias parecidas y se perdería en la bruma
+    scene: SceneConfig,
+}
+
+impl Minimap {
+    pub fn new(scene_number: u32, scene: &SceneConfig, meshes: &SceneMeshes, framebuffer: &Framebuffer) -> Self {
+        let size = MINIMAP_SIZE.min(framebuffer.width).min(framebuffer.height);
+        let rect = Rect::new(
+            framebuffer.width.saturating_sub(size + MINIMAP_MARGIN),
+            framebuffer.height.saturating_sub(size + MINIMAP_MARGIN),
+            size,
+            size,
+        );
+
+        // Mirando hacia abajo con -z hacia arriba del mapa, así la vista inicial (desde +z) queda abajo
+        let BoundingSphere { center, radius } = system_bounds(scene, meshes);
+        let mut camera = Camera::new(center + Vec3::new(0.0, 2.0 * radius, 0.0), center, Vec3::new(0.0, 0.0, -1.0));
+        camera.projection = Projection::Orthographic { height: 2.0 * radius * MINIMAP_PADDING };
+
+        Minimap { rect, scene_number, camera, scene: SceneConfig { fog: None, ..scene.clone() } }
+    }
+
+    // Dibuja el minimapa sobre lo que ya hay en el framebuffer; solo se borra su rectángulo
+    pub fn render(&self, framebuffer: &mut Framebuffer, time: f32, main_camera: &Camera, meshes: &SceneMeshes, state: &mut SceneState, options: &RenderOptions) {
+        let depth_range = scene_depth_range(&state.bodies_seen_from(&self.scene, time, self.camera.eye, meshes), meshes, &self.camera);
+        let uniforms = build_viewport_uniforms(&self.camera, time, self.rect, depth_range);
+        render_scene(framebuffer, self.scene_number, &self.scene, &uniforms, meshes, state, options);
+        self.draw_orbits(framebuffer, &uniforms);
+        self.draw_marker(framebuffer, &uniforms, main_camera);
+        framebuffer.outline_rect(self.rect, BORDER_COLOR);
+    }
+
+    // La elipse de cada órbita, solo en los pixeles donde quedó el fondo (con física las órbitas no se
+    // siguen y en su lugar se ven las estelas)
+    fn draw_orbits(&self, framebuffer: &mut Framebuffer, uniforms: &FrameUniforms) {
+        if self.scene.physics {
+            return;
+        }
+        let transform = uniforms.viewport_matrix * uniforms.projection_matrix * uniforms.view_matrix;
+        let hex = ORBIT_COLOR.to_hex_with_threshold(0.5);
+        for orbit in self.scene.bodies.iter().filter_map(|body| body.orbit) {
+            for point in orbit.path(ORBIT_SAMPLES) {
+                let screen = transform * Vec4::new(point.x, point.y, point.z, 1.0);
+                if screen.x < 0.0 || screen.y < 0.0 {
+                    continue;
+                }
+                let (x, y) = (screen.x as usize, screen.y as usize);
+                if self.rect.contains(x, y) && framebuffer.get_pixel(x, y).is_some_and(|(_, depth)| depth == f32::INFINITY) {
+                    framebuffer.buffer[y * framebuffer.width + x] = hex;
+                }
+            }
+        }
+    }
+
+    // Si la cámara está fuera del sistema el marcador queda en el borde del minimapa, apuntando igual
+    fn draw_marker(&self, framebuffer: &mut Framebuffer, uniforms: &FrameUniforms, main_camera: &Camera) {
+        let transform = uniforms.viewport_matrix * uniforms.projection_matrix * uniforms.view_matrix;
+        let project = |point: Vec3| {
+            let screen = transform * Vec4::new(point.x, point.y, point.z, 1.0);
+            Vec2::new(screen.x, screen.y)
+        };
+
+        let position = project(main_camera.eye);
+        let heading = project(main_camera.center) - position;
+        // Mirando justo hacia abajo no hay dirección en el plano, se dibuja apuntando hacia arriba
+        let direction = if heading.magnitude() > f32::EPSILON { heading.normalize() } else { Vec2::new(0.0, -1.0) };
+
+        let inset = MARKER_LENGTH + 1.0;
+        let position = Vec2::new(
+            position.x.clamp(self.rect.x as f32 + inset, (self.rect.x + self.rect.width) as f32 - inset),
+            position.y.clamp(self.rect.y as f32 + inset, (self.rect.y + self.rect.height) as f32 - inset),
+        );
+        let side = Vec2::new(-direction.y, direction.x) * (MARKER_WIDTH / 2.0);
+        let tip = position + direction * MARKER_LENGTH;
+        let back = position - direction * (MARKER_LENGTH / 2.0);
+        fill_triangle(framebuffer, self.rect, [tip, back + side, back - side], MARKER_COLOR);
+    }
+}
+
+// Triángulo sólido sin prueba de profundidad, recortado al rectángulo
+fn fill_triangle(framebuffer: &mut Framebuffer, clip: Rect, corners: [Vec2; 3], color: Color) {
+    let edge = |a: Vec2, b: Vec2, p: Vec2| (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x);
+    let [a, b, c] = corners;
+    let area = edge(a, b, c);
+    if area.abs() <= f32::EPSILON {
+        return;
+    }
+
+    let hex = color.to_hex_with_threshold(0.5);
+    let min_x = (a.x.min(b.x).min(c.x).floor().max(clip.x as f32)) as usize;
+    let min_y = (a.y.min(b.y).min(c.y).floor().max(clip.y as f32)) as usize;
+    let max_x = (a.x.max(b.x).max(c.x).ceil() as usize).min(clip.x + clip.width - 1).min(framebuffer.width - 1);
+    let max_y = (a.y.max(b.y).max(c.y).ceil() as usize).min(clip.y + clip.height - 1).min(framebuffer.height - 1);
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [edge(b, c, p), edge(c, a, p), edge(a, b, p)].map(|w| w / area);
+            if weights.iter().all(|&w| w >= 0.0) {
+                framebuffer.buffer[y * framebuffer.width + x] = hex;
+            }
+        }
+    }
+}
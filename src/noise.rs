@@ -0,0 +1,61 @@
+use nalgebra_glm::Vec3;
+use crate::rng;
+
+fn smooth(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Ruido de valor en 3D: hashes en los puntos enteros interpolados suavemente, resultado en [0, 1)
+pub fn value_noise3(seed: u32, point: Vec3) -> f32 {
+    let base = point.map(f32::floor);
+    let frac = point - base;
+    let (tx, ty, tz) = (smooth(frac.x), smooth(frac.y), smooth(frac.z));
+
+    let corner = |dx: f32, dy: f32, dz: f32| rng::hash3(seed, base.x + dx, base.y + dy, base.z + dz);
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let x00 = lerp(corner(0.0, 0.0, 0.0), corner(1.0, 0.0, 0.0), tx);
+    let x10 = lerp(corner(0.0, 1.0, 0.0), corner(1.0, 1.0, 0.0), tx);
+    let x01 = lerp(corner(0.0, 0.0, 1.0), corner(1.0, 0.0, 1.0), tx);
+    let x11 = lerp(corner(0.0, 1.0, 1.0), corner(1.0, 1.0, 1.0), tx);
+    lerp(lerp(x00, x10, ty), lerp(x01, x11, ty), tz)
+}
+
+// Suma de octavas de ruido de valor, normalizada a [0, 1)
+pub fn fbm3(seed: u32, point: Vec3, octaves: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max = 0.0;
+    for octave in 0..octaves {
+        total += value_noise3(rng::combine(seed, octave), point * frequency) * amplitude;
+        max += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / max
+}
+
+// Ruido de valor en 4D para animar ruido 3D: dos cortes de value_noise3 (con semillas distintas en cada
+// entero de `w`) interpolados suavemente, así el resultado cambia de forma continua con `w`
+pub fn value_noise4(seed: u32, point: Vec3, w: f32) -> f32 {
+    let base = w.floor();
+    let slice = |offset: f32| value_noise3(rng::combine(seed, (base + offset) as i32 as u32), point);
+    let (a, b) = (slice(0.0), slice(1.0));
+    a + (b - a) * smooth(w - base)
+}
+
+// Como fbm3 pero sobre value_noise4; `w` también se multiplica por la frecuencia de cada octava
+pub fn fbm4(seed: u32, point: Vec3, w: f32, octaves: u32) -> f32 {
+    let mut total = 0.0;
+    let mut amplitude = 1.0;
+    let mut frequency = 1.0;
+    let mut max = 0.0;
+    for octave in 0..octaves {
+        total += value_noise4(rng::combine(seed, octave), point * frequency, w * frequency) * amplitude;
+        max += amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    total / max
+}
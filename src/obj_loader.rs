@@ -1,62 +1,303 @@
-use tobj;
 use nalgebra_glm::{Vec2, Vec3};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use crate::color::Color;
+use crate::mesh::MeshBounds;
+use crate::mesh_cache;
 use crate::vertex::Vertex;
 
 pub struct Obj {
     meshes: Vec<Mesh>,
+    // Triángulos que venían al revés de sus normales y se dieron vuelta al leer el archivo
+    winding_fixes: usize,
 }
 
 struct Mesh {
     vertices: Vec<Vec3>,
     normals: Vec<Vec3>,
     texcoords: Vec<Vec2>,
+    // Colores por vértice de la extensión "v x y z r g b"; vacío si el archivo no los tiene
+    colors: Vec<Color>,
     indices: Vec<u32>,
+    // Envolventes de las posiciones, calculadas al leer el archivo
+    bounds: MeshBounds,
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(io::Error),
+    // `line` empieza en 1; 0 si el error no se puede ubicar en una línea
+    Parse { line: usize, message: String },
+    NoGeometry,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(err) => write!(f, "no se pudo leer el archivo: {}", err),
+            ObjError::Parse { line: 0, message } => write!(f, "OBJ inválido: {}", message),
+            ObjError::Parse { line, message } => write!(f, "OBJ inválido en la línea {}: {}", line, message),
+            ObjError::NoGeometry => write!(f, "el archivo no tiene triángulos"),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ObjError {
+    fn from(err: io::Error) -> Self {
+        ObjError::Io(err)
+    }
 }
 
 impl Obj {
-    pub fn load(filename: &str) -> Result<Self, tobj::LoadError> {
-        let (models, _) = tobj::load_obj(filename, &tobj::LoadOptions {
+    pub fn load(filename: &str) -> Result<Self, ObjError> {
+        let source = std::fs::read_to_string(filename)?;
+        Self::parse(&source)
+    }
+
+    // Vértices del modelo usando la caché binaria de al lado si sigue al día; si no, se lee
+    // el OBJ y se vuelve a guardar la caché (un error al guardarla solo se avisa)
+    pub fn load_cached(filename: &str) -> Result<Vec<Vertex>, ObjError> {
+        let path = Path::new(filename);
+        if let Some(vertices) = mesh_cache::read(path) {
+            return Ok(vertices);
+        }
+
+        let obj = Self::load(filename)?;
+        if obj.winding_fixes > 0 {
+            eprintln!("{}: se dieron vuelta {} triángulos que estaban al revés de sus normales", filename, obj.winding_fixes);
+        }
+        let vertices = obj.get_vertex_array();
+        match mesh_cache::write(path, &vertices) {
+            Ok(()) => eprintln!("Caché guardada en {}", mesh_cache::cache_path(path).display()),
+            Err(err) => eprintln!("No se pudo guardar la caché de {}: {}", filename, err),
+        }
+        Ok(vertices)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, ObjError> {
+        // Se revisa antes para poder reportar la línea del error
+        validate(source)?;
+
+        // Los materiales no se usan, mtllib se ignora
+        let (models, _) = tobj::load_obj_buf(&mut source.as_bytes(), &tobj::LoadOptions {
             single_index: true,
             triangulate: true,
             ..Default::default()
-        })?;
+        }, |_| Err(tobj::LoadError::OpenFileFailed))
+            .map_err(|err| ObjError::Parse { line: 0, message: err.to_string() })?;
 
-        let meshes = models.into_iter().map(|model| {
+        let mut meshes: Vec<Mesh> = models.into_iter().map(|model| {
             let mesh = model.mesh;
+            let vertices: Vec<Vec3> = mesh.positions.chunks(3)
+                .map(|v| Vec3::new(v[0], v[1], v[2]))
+                .collect();
             Mesh {
-                vertices: mesh.positions.chunks(3)
-                    .map(|v| Vec3::new(v[0], v[1], v[2]))
-                    .collect(),
+                bounds: MeshBounds::from_points(vertices.iter().copied()),
+                vertices,
                 normals: mesh.normals.chunks(3)
                     .map(|n| Vec3::new(n[0], n[1], n[2]))
                     .collect(),
                 texcoords: mesh.texcoords.chunks(2)
                     .map(|t| Vec2::new(t[0], 1.0 - t[1]))
                     .collect(),
+                colors: mesh.vertex_color.chunks(3)
+                    .map(|c| Color { r: c[0] * 255.0, g: c[1] * 255.0, b: c[2] * 255.0 })
+                    .collect(),
                 indices: mesh.indices,
             }
         }).collect();
 
-        Ok(Obj { meshes })
+        if meshes.iter().all(|mesh| mesh.indices.is_empty()) {
+            return Err(ObjError::NoGeometry);
+        }
+
+        let winding_fixes = meshes.iter_mut().map(Mesh::fix_winding).sum();
+        Ok(Obj { meshes, winding_fixes })
+    }
+
+    pub fn winding_fixes(&self) -> usize {
+        self.winding_fixes
+    }
+
+    // Caja y esfera que envuelven a todas las partes del modelo
+    pub fn bounds(&self) -> MeshBounds {
+        self.meshes.iter()
+            .filter(|mesh| !mesh.vertices.is_empty())
+            .map(|mesh| mesh.bounds)
+            .reduce(|bounds, other| bounds.union(&other))
+            .unwrap_or(MeshBounds::from_points([]))
+    }
+
+    pub fn has_vertex_colors(&self) -> bool {
+        self.meshes.iter().any(|mesh| !mesh.colors.is_empty())
     }
 
     pub fn get_vertex_array(&self) -> Vec<Vertex> {
         let mut vertices = Vec::new();
 
         for mesh in &self.meshes {
+            let smooth_normals = mesh.smooth_normals();
             for &index in &mesh.indices {
                 let position = mesh.vertices[index as usize];
-                let normal = mesh.normals.get(index as usize)
-                    .cloned()
+                // Si el archivo no trae normales (o vienen en cero) se usan las calculadas de las caras
+                let normal = mesh.file_normal(index)
+                    .or_else(|| smooth_normals.get(&position_key(&position)).cloned())
                     .unwrap_or(Vec3::new(0.0, 1.0, 0.0));
                 let tex_coords = mesh.texcoords.get(index as usize)
                     .cloned()
                     .unwrap_or(Vec2::new(0.0, 0.0));
+                let color = mesh.colors.get(index as usize)
+                    .cloned()
+                    .unwrap_or(Color::new(255, 255, 255));
 
-                vertices.push(Vertex::new(position, normal, tex_coords));
+                vertices.push(Vertex { color, ..Vertex::new(position, normal, tex_coords) });
             }
         }
 
         vertices
     }
 }
+
+// Normales más cortas que esto se consideran ausentes
+const MIN_NORMAL_LENGTH_SQUARED: f32 = 1e-12;
+// Coseno entre la cara y el promedio de sus normales por debajo del cual la cara está al revés (120°)
+const REVERSED_FACE_COS: f32 = -0.5;
+
+impl Mesh {
+    // Normal del archivo para un índice; None si no hay, si es cero o si no es un número finito
+    fn file_normal(&self, index: u32) -> Option<Vec3> {
+        self.normals.get(index as usize)
+            .filter(|normal| normal.magnitude_squared() > MIN_NORMAL_LENGTH_SQUARED && normal.iter().all(|value| value.is_finite()))
+            .cloned()
+    }
+
+    // Da vuelta el orden de los triángulos cuya cara geométrica (antihoraria vista de frente) apunta
+    // en contra de las normales del archivo; devuelve cuántos. Sin normales no hay contra qué comparar
+    fn fix_winding(&mut self) -> usize {
+        let mut fixed = 0;
+        for start in (0..self.indices.len() / 3).map(|face| face * 3) {
+            let face = [0, 1, 2].map(|corner| self.indices[start + corner]);
+            let Some(average) = face.iter().map(|&index| self.file_normal(index)).sum::<Option<Vec3>>() else {
+                continue;
+            };
+            let [a, b, c] = face.map(|index| self.vertices[index as usize]);
+            let geometric = (b - a).cross(&(c - a));
+            let lengths = geometric.magnitude() * average.magnitude();
+            if lengths > MIN_NORMAL_LENGTH_SQUARED && geometric.dot(&average) < REVERSED_FACE_COS * lengths {
+                self.indices.swap(start + 1, start + 2);
+                fixed += 1;
+            }
+        }
+        fixed
+    }
+
+    // Normal suave de cada posición: suma de las normales de las caras que la usan, pesadas por su área.
+    // Se agrupa por posición y no por índice para que las costuras de las uv no se noten en la luz
+    fn smooth_normals(&self) -> HashMap<[u32; 3], Vec3> {
+        let mut normals: HashMap<[u32; 3], Vec3> = HashMap::new();
+        if self.indices.iter().all(|&index| self.file_normal(index).is_some()) {
+            return normals;
+        }
+
+        for face in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| self.vertices[face[corner] as usize]);
+            // El producto cruz mide el doble del área; los triángulos degenerados no suman nada
+            let face_normal = (b - a).cross(&(c - a));
+            for position in [a, b, c] {
+                *normals.entry(position_key(&position)).or_insert_with(Vec3::zeros) += face_normal;
+            }
+        }
+
+        normals.retain(|_, normal| normal.magnitude_squared() > MIN_NORMAL_LENGTH_SQUARED);
+        for normal in normals.values_mut() {
+            *normal = normal.normalize();
+        }
+        normals
+    }
+}
+
+fn position_key(position: &Vec3) -> [u32; 3] {
+    [position.x.to_bits(), position.y.to_bits(), position.z.to_bits()]
+}
+
+// Revisa los vértices y caras línea por línea; las instrucciones desconocidas se ignoran
+fn validate(source: &str) -> Result<(), ObjError> {
+    let mut positions = 0;
+    let mut texcoords = 0;
+    let mut normals = 0;
+
+    for (number, line) in source.lines().enumerate() {
+        let line_number = number + 1;
+        let error = |message: String| ObjError::Parse { line: line_number, message };
+
+        let mut words = line.split_whitespace();
+        let Some(keyword) = words.next() else {
+            continue;
+        };
+        let values: Vec<&str> = words.collect();
+
+        match keyword {
+            "v" => {
+                parse_floats(&values, 3).map_err(error)?;
+                positions += 1;
+            }
+            "vt" => {
+                parse_floats(&values, 2).map_err(error)?;
+                texcoords += 1;
+            }
+            "vn" => {
+                parse_floats(&values, 3).map_err(error)?;
+                normals += 1;
+            }
+            "f" => {
+                if values.len() < 3 {
+                    return Err(error(format!("la cara necesita al menos 3 vértices, tiene {}", values.len())));
+                }
+                for value in &values {
+                    let mut parts = value.split('/');
+                    check_index(parts.next(), positions, "posición").map_err(error)?;
+                    if let Some(part) = parts.next().filter(|part| !part.is_empty()) {
+                        check_index(Some(part), texcoords, "coordenada de textura").map_err(error)?;
+                    }
+                    if let Some(part) = parts.next() {
+                        check_index(Some(part), normals, "normal").map_err(error)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_floats(values: &[&str], count: usize) -> Result<(), String> {
+    if values.len() < count {
+        return Err(format!("se esperaban {} números, hay {}", count, values.len()));
+    }
+    for value in values {
+        value.parse::<f32>().map_err(|_| format!("número inválido: {}", value))?;
+    }
+    Ok(())
+}
+
+// Índices de OBJ: empiezan en 1 y los negativos cuentan desde el último elemento
+fn check_index(value: Option<&str>, count: usize, kind: &str) -> Result<(), String> {
+    let value = value.unwrap_or("");
+    let index: i64 = value.parse().map_err(|_| format!("índice de {} inválido: {}", kind, value))?;
+    if index == 0 || index.unsigned_abs() as usize > count {
+        return Err(format!("índice de {} fuera de rango: {} (hay {})", kind, index, count));
+    }
+    Ok(())
+}
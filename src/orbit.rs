@@ -0,0 +1,95 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::TAU;
+
+// Órbita elíptica alrededor del origen. El plano de referencia es XZ; la órbita se inclina
+// alrededor del eje x y el periapsis se ubica con `periapsis` medido en el plano de la órbita
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Orbit {
+    pub semi_major_axis: f32,
+    // 0 = círculo; se limita a MAX_ECCENTRICITY
+    pub eccentricity: f32,
+    // Ángulo del periapsis (argumento del periapsis), en radianes
+    pub periapsis: f32,
+    pub inclination: f32,
+    // Ticks por vuelta
+    pub period: f32,
+    // Anomalía media en el tiempo 0
+    pub phase: f32,
+}
+
+// Más cerca de 1 la elipse se vuelve una línea y el cuerpo pasa casi por el origen
+pub const MAX_ECCENTRICITY: f32 = 0.95;
+// Newton converge en 3 o 4 pasos para excentricidades bajas; el resto es para las cercanas al límite
+const KEPLER_ITERATIONS: usize = 12;
+const KEPLER_TOLERANCE: f32 = 1e-6;
+
+impl Orbit {
+    // Círculo con la velocidad angular de antes (radianes por tick)
+    pub fn circular(radius: f32, speed: f32, phase: f32) -> Self {
+        Orbit { semi_major_axis: radius, eccentricity: 0.0, periapsis: 0.0, inclination: 0.0, period: TAU / speed, phase }
+    }
+
+    pub fn eccentric(mut self, eccentricity: f32, periapsis: f32) -> Self {
+        self.eccentricity = eccentricity;
+        self.periapsis = periapsis;
+        self
+    }
+
+    pub fn inclined(mut self, inclination: f32) -> Self {
+        self.inclination = inclination;
+        self
+    }
+
+    fn clamped_eccentricity(&self) -> f32 {
+        self.eccentricity.clamp(0.0, MAX_ECCENTRICITY)
+    }
+
+    // Distancia máxima al origen (apoapsis)
+    pub fn apoapsis(&self) -> f32 {
+        self.semi_major_axis * (1.0 + self.clamped_eccentricity())
+    }
+
+    // Anomalía media en `time`, en [0, 2π) también con el tiempo negativo del reloj en reversa
+    fn mean_anomaly(&self, time: f32) -> f32 {
+        (self.phase + TAU * time / self.period).rem_euclid(TAU)
+    }
+
+    // Punto de la órbita para una anomalía excéntrica; con e = 0 es el ángulo de siempre en el círculo
+    pub fn position_at(&self, eccentric_anomaly: f32) -> Vec3 {
+        let e = self.clamped_eccentricity();
+        let a = self.semi_major_axis;
+        let (sin_e, cos_e) = eccentric_anomaly.sin_cos();
+        let (x, z) = (a * (cos_e - e), a * (1.0 - e * e).sqrt() * sin_e);
+
+        let (sin_w, cos_w) = self.periapsis.sin_cos();
+        let (x, z) = (x * cos_w - z * sin_w, x * sin_w + z * cos_w);
+        let (sin_i, cos_i) = self.inclination.sin_cos();
+        Vec3::new(x, z * sin_i, z * cos_i)
+    }
+
+    // Puntos de toda la órbita repartidos en anomalía excéntrica: cerca del periapsis el cuerpo va
+    // más rápido y muestreando en el tiempo quedarían huecos ahí y puntos amontonados en el apoapsis
+    pub fn path(&self, samples: usize) -> Vec<Vec3> {
+        (0..samples).map(|index| self.position_at(TAU * index as f32 / samples as f32)).collect()
+    }
+}
+
+// Ecuación de Kepler M = E - e sin E, resuelta con Newton
+pub fn eccentric_anomaly(mean_anomaly: f32, eccentricity: f32) -> f32 {
+    // Con e alta la derivada casi se anula cerca de M = 0 y empezar desde π converge mejor
+    let mut anomaly = if eccentricity < 0.8 { mean_anomaly } else { std::f32::consts::PI };
+    for _ in 0..KEPLER_ITERATIONS {
+        let step = (anomaly - eccentricity * anomaly.sin() - mean_anomaly) / (1.0 - eccentricity * anomaly.cos());
+        anomaly -= step;
+        if step.abs() < KEPLER_TOLERANCE {
+            break;
+        }
+    }
+    anomaly
+}
+
+// Posición del cuerpo en `time`: se acelera al acercarse al periapsis y se frena en el apoapsis
+pub fn orbit_position(orbit: &Orbit, time: f32) -> Vec3 {
+    let mean_anomaly = orbit.mean_anomaly(time);
+    orbit.position_at(eccentric_anomaly(mean_anomaly, orbit.clamped_eccentricity()))
+}
@@ -0,0 +1,201 @@
+use nalgebra_glm::Vec3;
+use crate::mesh::SPHERE_RADIUS;
+use crate::rng;
+
+// Emisor fijo en la superficie de un cuerpo; las partículas viven en el espacio del objeto
+// así que giran junto con él al aplicar su matriz de modelo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParticleEmitter {
+    pub origin_dir: Vec3,   // Dirección desde el centro del cuerpo (no necesita estar normalizada)
+    pub rate: f32,          // Partículas por tick
+    pub speed: f32,         // Unidades del objeto por tick
+    pub lifetime: u32,      // Ticks que vive cada partícula
+    pub spread: f32,        // Apertura del chorro
+}
+
+pub struct Particle {
+    pub position: Vec3,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Particle {
+    // 1 al nacer, 0 al morir
+    pub fn fade(&self) -> f32 {
+        (1.0 - self.age / self.lifetime).clamp(0.0, 1.0)
+    }
+}
+
+impl ParticleEmitter {
+    // Partículas vivas en el instante `time`; cada una depende solo de su índice,
+    // así el resultado es el mismo sin importar cuántos frames se hayan dibujado
+    pub fn particles(&self, time: f32, seed: u32) -> Vec<Particle> {
+        if self.rate <= 0.0 || self.lifetime == 0 {
+            return Vec::new();
+        }
+
+        let lifetime = self.lifetime as f32;
+        let origin_dir = self.origin_dir.normalize();
+        let origin = origin_dir * SPHERE_RADIUS;
+
+        let first = ((time - lifetime) * self.rate).ceil().max(0.0) as u32;
+        let last = (time * self.rate).floor() as u32;
+
+        (first..=last).filter_map(|index| {
+            let age = time - index as f32 / self.rate;
+            if !(0.0..lifetime).contains(&age) {
+                return None;
+            }
+
+            let jitter = Vec3::new(
+                rng::hash1(seed, index * 3) - 0.5,
+                rng::hash1(seed, index * 3 + 1) - 0.5,
+                rng::hash1(seed, index * 3 + 2) - 0.5,
+            ) * 2.0 * self.spread;
+            let direction = (origin_dir + jitter).normalize();
+            let speed = self.speed * (0.7 + rng::hash1(seed ^ 0x5EED, index) * 0.6);
+
+            Some(Particle { position: origin + direction * speed * age, age, lifetime })
+        }).collect()
+    }
+}
+
+// Cola de un cometa: partículas en el espacio del mundo que salen del núcleo hacia el lado contrario
+// al sol. Hasta `falloff` del sol la cola está completa; más lejos el largo, la cantidad de partículas
+// y el brillo caen como falloff / distancia
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CometTail {
+    pub length: f32,        // Largo de la cola completa en unidades del mundo
+    pub width: f32,         // Radio del cono en la punta, en fracción del largo
+    pub curvature: f32,     // Cuánto se atrasa la punta respecto del movimiento, en fracción del largo
+    pub falloff: f32,       // Distancia al sol hasta la que la cola está completa
+    pub particles: u32,     // Partículas de la cola completa
+}
+
+// Fracción de la cola que recorre cada partícula por tick: van del núcleo a la punta en 250 ticks
+const TAIL_FLOW: f32 = 0.004;
+
+impl CometTail {
+    // 1 hasta `falloff` del sol y después falloff / distancia
+    pub fn strength(&self, sun_distance: f32) -> f32 {
+        (self.falloff / sun_distance.max(f32::EPSILON)).min(1.0)
+    }
+
+    // Partículas de la cola en el instante `time`. `away` es la dirección del sol al núcleo y `velocity`
+    // la del núcleo (la punta se curva hacia atrás). Cada partícula depende solo de su índice y del
+    // tiempo, así un frame exportado no depende de los anteriores. La edad es la fracción del largo
+    // recorrida, para que `fade` las apague hacia la punta
+    pub fn particles(&self, nucleus: Vec3, away: Vec3, velocity: Vec3, strength: f32, time: f32, seed: u32) -> Vec<Particle> {
+        let Some(away) = away.try_normalize(f32::EPSILON) else {
+            return Vec::new();
+        };
+        let length = self.length * strength;
+        let count = (self.particles as f32 * strength).round() as u32;
+
+        // Dos ejes perpendiculares a la cola para abrir el cono
+        let reference = if away.y.abs() < 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+        let side = away.cross(&reference).normalize();
+        let up = side.cross(&away);
+        // Solo cuenta la parte de la velocidad que cruza la cola
+        let behind = -(velocity - away * velocity.dot(&away)).try_normalize(f32::EPSILON).unwrap_or(Vec3::zeros());
+
+        (0..count).map(|index| {
+            let along = (rng::hash1(seed, index * 3) + time * TAIL_FLOW).rem_euclid(1.0);
+            let angle = rng::hash1(seed, index * 3 + 1) * std::f32::consts::TAU;
+            // Raíz para repartir las partículas parejas en el disco y no amontonadas en el eje
+            let radius = rng::hash1(seed, index * 3 + 2).sqrt() * self.width * along;
+            let spread = (side * angle.cos() + up * angle.sin()) * radius;
+            let position = nucleus + (away * along + spread + behind * (self.curvature * along * along)) * length;
+            Particle { position, age: along, lifetime: 1.0 }
+        }).collect()
+    }
+}
+
+// Partícula del anillo: órbita circular en el plano XZ del objeto (igual que la malla del anillo)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RingParticle {
+    pub radius: f32,
+    pub phase: f32,
+    pub height: f32,        // Desvío vertical, para que el anillo tenga algo de grosor
+    pub band: f32,          // 0 en el borde interior, 1 en el exterior (para el color)
+    pub brightness: f32,    // Variación de brillo de cada partícula
+}
+
+// Huecos del anillo como (centro, medio ancho) en fracción del ancho; el primero imita la división de Cassini
+const RING_GAPS: [(f32, f32); 3] = [(0.42, 0.035), (0.78, 0.012), (0.12, 0.01)];
+// Fracción de partículas que nacen agrupadas; los grupos se estiran con la rotación diferencial
+const RING_CLUMPED: f32 = 0.35;
+const RING_CLUMPS: u32 = 28;
+const RING_CLUMP_WIDTH: f32 = 0.06;     // Fracción del ancho del anillo
+const RING_CLUMP_SPREAD: f32 = 0.35;    // Radianes
+const RING_THICKNESS: f32 = 0.012;      // Unidades del objeto
+// Velocidad angular en el borde interior (radianes por tick); más afuera cae como r^-1.5 (Kepler)
+const RING_INNER_SPEED: f32 = 0.004;
+
+// Densidad relativa de partículas en una fracción del ancho, 0 dentro de los huecos
+// (la malla del anillo la usa como opacidad)
+pub fn ring_density(band: f32) -> f32 {
+    let gap = RING_GAPS.iter()
+        .map(|&(center, half_width)| ((band - center).abs() / half_width).min(1.0))
+        .fold(1.0, f32::min);
+    // Más denso en el centro del anillo y con ondas suaves
+    let profile = 0.55 + 0.3 * (band * std::f32::consts::PI).sin() + 0.15 * (band * 37.0).sin();
+    profile * gap * gap
+}
+
+// Fracción del ancho elegida por rechazo según la densidad del anillo
+fn sample_band(seed: u32) -> f32 {
+    const MAX_ATTEMPTS: u32 = 16;
+    let mut attempt = 0;
+    loop {
+        let band = rng::hash1(seed, 100 + attempt * 2);
+        if attempt + 1 == MAX_ATTEMPTS || rng::hash1(seed, 101 + attempt * 2) < ring_density(band) {
+            return band;
+        }
+        attempt += 1;
+    }
+}
+
+pub struct ParticleRing {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub particles: Vec<RingParticle>,
+}
+
+impl ParticleRing {
+    // Cada partícula depende solo de la semilla y su índice
+    pub fn new(seed: u32, count: usize, inner_radius: f32, outer_radius: f32) -> Self {
+        let width = outer_radius - inner_radius;
+        let particles = (0..count as u32).map(|index| {
+            let particle_seed = rng::combine(seed, index);
+            let clump = (rng::hash1(particle_seed, 0) < RING_CLUMPED)
+                .then(|| rng::hash_u32(seed, rng::hash_u32(particle_seed, 1) % RING_CLUMPS));
+
+            let (band, phase) = match clump {
+                Some(clump_seed) => {
+                    let band = sample_band(clump_seed) + (rng::hash1(particle_seed, 2) - 0.5) * RING_CLUMP_WIDTH;
+                    let phase = rng::hash1(clump_seed, 1) * std::f32::consts::TAU + (rng::hash1(particle_seed, 3) - 0.5) * RING_CLUMP_SPREAD;
+                    (band.clamp(0.0, 1.0), phase)
+                }
+                None => (sample_band(particle_seed), rng::hash1(particle_seed, 3) * std::f32::consts::TAU),
+            };
+
+            RingParticle {
+                radius: inner_radius + band * width,
+                phase,
+                height: (rng::hash1(particle_seed, 40) - 0.5) * RING_THICKNESS,
+                band,
+                brightness: 0.75 + rng::hash1(particle_seed, 41) * 0.5,
+            }
+        }).collect();
+
+        ParticleRing { inner_radius, outer_radius, particles }
+    }
+
+    // Posición de una partícula en el instante `time`; las interiores dan la vuelta más rápido
+    pub fn position(&self, particle: &RingParticle, time: f32) -> Vec3 {
+        let speed = RING_INNER_SPEED * (self.inner_radius / particle.radius).powf(1.5);
+        let angle = particle.phase + speed * time;
+        Vec3::new(particle.radius * angle.cos(), particle.height, -particle.radius * angle.sin())
+    }
+}
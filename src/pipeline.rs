@@ -0,0 +1,1160 @@
+use nalgebra_glm::{Vec3, Vec4, Mat3, Mat4, Quat, mat4_to_mat3, look_at, ortho, perspective, quat_angle_axis, quat_normalize, quat_to_mat4};
+use std::collections::VecDeque;
+use std::f32::consts::PI;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::camera::{Camera, Projection};
+use crate::checkerboard::is_shaded;
+use crate::color::Color;
+use crate::framebuffer::{BlendMode, Framebuffer, Rect};
+use crate::impacts::MoonState;
+use crate::particles::{Particle, ParticleRing};
+use crate::ring_profile::RingProfile;
+use crate::texture::AmbientCube;
+use crate::shaders::{fragment_shader, latitude_longitude, lit_temperature, ring_light, ring_particle_color, starfield, vertex_shader, FragmentShader, HIGHLIGHT_BOOST};
+use crate::fragments::Fragments;
+use crate::tiles::{histogram_label, PixelBounds, TileBins, TILE_HISTOGRAM_BUCKETS, TILE_SIZE};
+use crate::triangle::{screen_bounds, triangle_for_each};
+use crate::vertex::Vertex;
+
+// Lo que comparten todos los cuerpos de un frame: cámara, tiempo, luces de la escena y modos de
+// depuración. Se arma una vez por frame (ver build_uniforms)
+#[derive(Clone)]
+pub struct FrameUniforms {
+    pub view_matrix: Mat4,
+    pub projection_matrix: Mat4,
+    pub viewport_matrix: Mat4,
+    // Parte del framebuffer donde se dibuja; lo de afuera no se toca
+    pub viewport: Rect,
+    // Posición de la cámara en el mundo, para los reflejos
+    pub camera_position: Vec3,
+    pub time: f32,
+    pub debug_mode: u32,
+    pub lights: Vec<Light>,
+    pub ambient: Color,
+    // Luz del fondo que se suma a la ambiental según hacia dónde mira cada fragmento (ver scene::skylight)
+    pub skylight: AmbientCube,
+    // Modo caricatura: la iluminación se reduce a este número de bandas
+    pub cel_bands: Option<u32>,
+    pub fog: Option<FogParams>,
+    // Vista infrarroja: la temperatura de cada fragmento (FragmentShader::temperature) pasa por este
+    // degradado en lugar del color del shader
+    pub thermal: Option<Vec<(f32, Color)>>,
+    // Descarta los triángulos que dan la espalda a la cámara (salvo en los cuerpos de dos caras)
+    pub cull_backfaces: bool,
+    // Renderizado en tablero de ajedrez: solo se sombrean los pixeles de esta paridad (ver
+    // checkerboard::is_shaded); los demás los completa Checkerboard::resolve
+    pub checkerboard: Option<u32>,
+    // Hilos para rasterizar por teselas (ver render); con 1 todo va en este hilo
+    pub threads: usize,
+}
+
+impl FrameUniforms {
+    // Luz ambiental para una normal del mundo: la constante de la escena más la del fondo en esa dirección
+    pub fn ambient_light(&self, normal: &Vec3) -> Color {
+        self.ambient + self.skylight.sample(normal)
+    }
+}
+
+// Lo propio de cada cuerpo; se arma una vez por draw (ver scene::body_uniforms). La matriz de las
+// normales se calcula junto con la de modelo en vez de invertirla en cada vértice, así que las dos
+// solo se cambian juntas con BodyUniforms::new o set_model_matrix
+#[derive(Clone)]
+pub struct BodyUniforms {
+    pub(crate) model_matrix: Mat4,
+    pub(crate) normal_matrix: Mat3,
+    pub seed: u32,
+    pub debug_tint: Option<Color>,
+    // Cráteres e impactos de la luna; sin estado se usan los cráteres de la semilla
+    pub moon: Option<MoonState>,
+    // Id que se escribe en el buffer de ids (0 = fondo) y si el cuerpo está resaltado
+    pub body_id: u16,
+    pub highlighted: bool,
+    // Cuadrícula de latitud y longitud sobre la superficie, para revisar la orientación del cuerpo
+    pub graticule: bool,
+    // Luz reflejada por el planeta más cercano; solo la usan las lunas
+    pub planetshine: Option<Planetshine>,
+    // Anillo alrededor del cuerpo que le hace sombra; solo lo usa el planeta con anillos
+    pub ring_shadow: Option<RingShadow>,
+    // Perfil radial del anillo (color y profundidad óptica); sin perfil se usan las franjas procedurales
+    pub ring_profile: Option<Arc<RingProfile>>,
+    // La malla tiene relieve: los shaders que lo tienen en cuenta pasan a patrones tri-planares
+    pub displaced: bool,
+    // La superficie se deforma con el tiempo en el vertex shader
+    pub vertex_animation: Option<VertexAnimParams>,
+    // Translúcidos: se dibujan después de lo opaco sin escribir la profundidad (ver draw_order);
+    // la opacidad multiplica la que devuelve el shader en cada fragmento
+    pub blend: BlendMode,
+    pub opacity: f32,
+    // Superficie abierta que se ve de los dos lados (el anillo): nunca se descartan sus caras traseras y
+    // la normal se da vuelta hacia la cámara antes de iluminar
+    pub two_sided: bool,
+}
+
+impl BodyUniforms {
+    pub fn new(model_matrix: Mat4) -> Self {
+        BodyUniforms {
+            model_matrix,
+            normal_matrix: normal_matrix(&model_matrix),
+            seed: 0,
+            debug_tint: None,
+            moon: None,
+            body_id: 0,
+            highlighted: false,
+            graticule: false,
+            planetshine: None,
+            ring_shadow: None,
+            ring_profile: None,
+            displaced: false,
+            vertex_animation: None,
+            blend: BlendMode::Opaque,
+            opacity: 1.0,
+            two_sided: false,
+        }
+    }
+
+    pub fn model_matrix(&self) -> &Mat4 {
+        &self.model_matrix
+    }
+
+    // Inversa transpuesta de la parte 3x3 de la matriz de modelo: lleva las normales al mundo sin
+    // deformarlas cuando la escala no es igual en los tres ejes
+    pub fn normal_matrix(&self) -> &Mat3 {
+        &self.normal_matrix
+    }
+
+    pub fn set_model_matrix(&mut self, model_matrix: Mat4) {
+        self.model_matrix = model_matrix;
+        self.normal_matrix = normal_matrix(&model_matrix);
+    }
+}
+
+impl Default for BodyUniforms {
+    fn default() -> Self {
+        BodyUniforms::new(Mat4::identity())
+    }
+}
+
+pub fn normal_matrix(model_matrix: &Mat4) -> Mat3 {
+    mat4_to_mat3(model_matrix).transpose().try_inverse().unwrap_or(Mat3::identity())
+}
+
+// Rotación de un objeto: ángulos de Euler en radianes (se aplica la de X, después la de Y y al final
+// la de Z) o un cuaternión, que no depende del orden de los ejes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Rotation {
+    Euler(Vec3),
+    Quaternion(Quat),
+}
+
+impl From<Vec3> for Rotation {
+    fn from(angles: Vec3) -> Self {
+        Rotation::Euler(angles)
+    }
+}
+
+impl From<Quat> for Rotation {
+    fn from(quaternion: Quat) -> Self {
+        Rotation::Quaternion(quaternion)
+    }
+}
+
+impl Rotation {
+    pub fn matrix(&self) -> Mat4 {
+        let rotation = match *self {
+            Rotation::Euler(rotation) => rotation,
+            Rotation::Quaternion(quaternion) => return quat_to_mat4(&quat_normalize(&quaternion)),
+        };
+        let (sin_x, cos_x) = rotation.x.sin_cos();
+        let (sin_y, cos_y) = rotation.y.sin_cos();
+        let (sin_z, cos_z) = rotation.z.sin_cos();
+
+        let rotation_matrix_x = Mat4::new(
+            1.0,  0.0,    0.0,   0.0,
+            0.0,  cos_x, -sin_x, 0.0,
+            0.0,  sin_x,  cos_x, 0.0,
+            0.0,  0.0,    0.0,   1.0,
+        );
+
+        let rotation_matrix_y = Mat4::new(
+            cos_y,  0.0,  sin_y, 0.0,
+            0.0,    1.0,  0.0,   0.0,
+            -sin_y, 0.0,  cos_y, 0.0,
+            0.0,    0.0,  0.0,   1.0,
+        );
+
+        let rotation_matrix_z = Mat4::new(
+            cos_z, -sin_z, 0.0, 0.0,
+            sin_z,  cos_z, 0.0, 0.0,
+            0.0,    0.0,  1.0, 0.0,
+            0.0,    0.0,  0.0, 1.0,
+        );
+
+        rotation_matrix_z * rotation_matrix_y * rotation_matrix_x
+    }
+
+    // El mismo giro como cuaternión (unitario)
+    pub fn quaternion(&self) -> Quat {
+        match *self {
+            Rotation::Euler(rotation) => {
+                let axis = |angle: f32, axis: Vec3| quat_angle_axis(angle, &axis);
+                axis(rotation.z, Vec3::z()) * axis(rotation.y, Vec3::y()) * axis(rotation.x, Vec3::x())
+            }
+            Rotation::Quaternion(quaternion) => quat_normalize(&quaternion),
+        }
+    }
+}
+
+// `scale` es por eje y se aplica en el espacio del objeto, antes de rotar: un planeta achatado
+// sigue achatado sobre su propio eje aunque esté inclinado. La rotación puede ser un Vec3 con los
+// ángulos de Euler o un cuaternión
+pub fn create_model_matrix(translation: Vec3, scale: Vec3, rotation: impl Into<Rotation>) -> Mat4 {
+    let rotation_matrix = rotation.into().matrix();
+
+    let translation_matrix = Mat4::new(
+        1.0, 0.0, 0.0, translation.x,
+        0.0, 1.0, 0.0, translation.y,
+        0.0, 0.0, 1.0, translation.z,
+        0.0, 0.0, 0.0, 1.0,
+    );
+
+    let scale_matrix = Mat4::new(
+        scale.x, 0.0,     0.0,     0.0,
+        0.0,     scale.y, 0.0,     0.0,
+        0.0,     0.0,     scale.z, 0.0,
+        0.0,     0.0,     0.0,     1.0,
+    );
+
+    translation_matrix * rotation_matrix * scale_matrix
+}
+
+
+pub fn create_view_matrix(eye: Vec3, center: Vec3, up: Vec3) -> Mat4 {
+    look_at(&eye, &center, &up)
+}
+
+// Campo de visión vertical de la cámara
+pub const FOV: f32 = 45.0 * PI / 180.0;
+
+// Dirección hacia la luz de las escenas de un solo planeta, en coordenadas del mundo
+pub const LIGHT_DIRECTION: Vec3 = Vec3::new(0.0, 0.0, 1.0);
+// Solo se usan las primeras luces de la lista
+pub const MAX_LIGHTS: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LightSource {
+    Directional(Vec3),  // Dirección hacia la luz
+    Point(Vec3),        // Posición en el mundo
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Light {
+    pub source: LightSource,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn directional(direction: Vec3, color: Color, intensity: f32) -> Self {
+        Light { source: LightSource::Directional(direction.normalize()), color, intensity }
+    }
+
+    pub fn point(position: Vec3, color: Color, intensity: f32) -> Self {
+        Light { source: LightSource::Point(position), color, intensity }
+    }
+
+    // Dirección normalizada desde un punto del mundo hacia la luz
+    pub fn direction_from(&self, world_position: &Vec3) -> Vec3 {
+        match self.source {
+            LightSource::Directional(direction) => direction,
+            LightSource::Point(position) => (position - world_position).normalize(),
+        }
+    }
+
+    // Color de la luz escalado por su intensidad (1 = blanco completo)
+    fn radiance(&self) -> Vec3 {
+        Vec3::new(self.color.r, self.color.g, self.color.b) * (self.intensity / 255.0)
+    }
+}
+
+// Niebla por distancia: entre `start` y `end` (en el espacio de la vista) el color pasa al de la niebla
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FogParams {
+    pub color: Color,
+    pub start: f32,
+    pub end: f32,
+}
+
+impl FogParams {
+    pub fn apply(&self, color: Color, view_depth: f32) -> Color {
+        color.lerp(&self.color, self.amount(view_depth))
+    }
+
+    // Cuánto tapa la niebla a esa distancia (0 = nada, 1 = todo)
+    pub fn amount(&self, view_depth: f32) -> f32 {
+        let t = ((view_depth - self.start) / (self.end - self.start).max(f32::EPSILON)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+// Crepúsculo: banda de luz teñida a lo largo del terminador, donde la luz llega casi rasante.
+// `width` es el ancho de la banda en el término difuso y `strength` cuánto se acerca al tinte
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Twilight {
+    pub tint: Color,
+    pub width: f32,
+    pub strength: f32,
+}
+
+// Término difuso donde la banda es más fuerte (justo antes de la noche)
+pub const TWILIGHT_CENTER: f32 = 0.05;
+
+impl Twilight {
+    // En [0, 1]: un bulto suave centrado en TWILIGHT_CENTER que se apaga del todo al llegar a la noche
+    pub fn factor(&self, diffuse: f32) -> f32 {
+        if diffuse <= 0.0 {
+            return 0.0;
+        }
+        let t = ((diffuse - TWILIGHT_CENTER) / self.width.max(f32::EPSILON)).clamp(-1.0, 1.0);
+        let bump = (1.0 - t * t) * (1.0 - t * t);
+        let fade = (diffuse / TWILIGHT_CENTER).min(1.0);
+        bump * fade * fade * (3.0 - 2.0 * fade)
+    }
+
+    // Se aplica al color ya iluminado, antes de sumar lo que emite luz propia
+    pub fn apply(&self, color: Color, diffuse: f32) -> Color {
+        color.lerp(&self.tint, self.factor(diffuse) * self.strength)
+    }
+}
+
+// Luz del sol que un planeta refleja hacia una luna, para que su lado nocturno no quede negro
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Planetshine {
+    pub position: Vec3,
+    pub radius: f32,
+    // Color medio de la superficie del planeta
+    pub color: Color,
+}
+
+// Qué tanto de la luz del planeta llega a la luna (el resto lo pone su tamaño aparente)
+pub const PLANETSHINE_STRENGTH: f32 = 1.5;
+
+impl Planetshine {
+    // Luz por canal en un punto de la luna: crece con la parte iluminada del planeta que se ve desde
+    // ahí (su fase) y con el tamaño del planeta en el cielo de la luna
+    pub fn light(&self, normal: &Vec3, world_position: &Vec3, lights: &[Light]) -> Vec3 {
+        let offset = self.position - world_position;
+        let distance = offset.magnitude();
+        if distance <= self.radius {
+            return Vec3::zeros();
+        }
+        let to_planet = offset / distance;
+        let facing = normal.dot(&to_planet).max(0.0);
+        let phase = lights.iter().take(MAX_LIGHTS).fold(Vec3::zeros(), |total, light| {
+            total + light.radiance() * (1.0 - light.direction_from(&self.position).dot(&to_planet)) / 2.0
+        });
+        let albedo = Vec3::new(self.color.r, self.color.g, self.color.b) / 255.0;
+        let size = (self.radius / distance).powi(2);
+        phase.component_mul(&albedo) * facing * size * PLANETSHINE_STRENGTH
+    }
+}
+
+// Anillo que puede tapar la luz que llega a su planeta. En el espacio del objeto del anillo este
+// queda en el plano XZ, así la sombra sigue a la inclinación del anillo y a la de su planeta
+#[derive(Clone, Debug, PartialEq)]
+pub struct RingShadow {
+    // Inversa de la matriz de modelo del anillo
+    pub world_to_ring: Mat4,
+    // Perfil del anillo, para que la sombra tape lo mismo que se ve; sin perfil, las franjas procedurales
+    pub profile: Option<Arc<RingProfile>>,
+}
+
+// Pulsación de la superficie que se calcula en el vertex shader en cada frame: cada vértice sube o baja
+// sobre su normal hasta `amplitude` (fracción del radio) según ruido de su posición y del tiempo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VertexAnimParams {
+    pub amplitude: f32,
+    pub frequency: f32,     // Escala del ruido sobre la posición en el objeto
+    pub speed: f32,         // Cuánto avanza el ruido por tick
+    pub octaves: u32,
+}
+
+// Una luz blanca desde la cámara inicial y sin luz ambiental
+pub fn default_lights() -> Vec<Light> {
+    vec![Light::directional(LIGHT_DIRECTION, Color::new(255, 255, 255), 1.0)]
+}
+
+// Luz ambiental más la difusa de cada luz, por canal; `normal` debe estar normalizada
+pub fn diffuse_light(normal: &Vec3, world_position: &Vec3, lights: &[Light], ambient: &Color) -> Vec3 {
+    filtered_diffuse_light(normal, world_position, lights, ambient, |_| 1.0)
+}
+
+// Como diffuse_light, pero cada luz llega multiplicada por `transmittance` (0 = tapada del todo);
+// la luz ambiental no se tapa
+pub fn filtered_diffuse_light(normal: &Vec3, world_position: &Vec3, lights: &[Light], ambient: &Color, transmittance: impl Fn(&Light) -> f32) -> Vec3 {
+    let ambient = Vec3::new(ambient.r, ambient.g, ambient.b) / 255.0;
+    lights.iter().take(MAX_LIGHTS).fold(ambient, |total, light| {
+        let diffuse = normal.dot(&light.direction_from(world_position)).max(0.0);
+        total + light.radiance() * diffuse * transmittance(light)
+    })
+}
+
+// Brillo especular (Blinn-Phong) de todas las luces, teñido por su color
+pub fn specular_light(normal: &Vec3, world_position: &Vec3, view_direction: &Vec3, lights: &[Light], shininess: f32) -> Vec3 {
+    lights.iter().take(MAX_LIGHTS).fold(Vec3::zeros(), |total, light| {
+        let half_dir = (light.direction_from(world_position) + view_direction).normalize();
+        total + light.radiance() * normal.dot(&half_dir).max(0.0).powf(shininess)
+    })
+}
+
+// Dirección hacia la cámara, tomada como direccional (tercera fila de la matriz de vista)
+pub fn view_direction(frame: &FrameUniforms) -> Vec3 {
+    let view = &frame.view_matrix;
+    Vec3::new(view[(2, 0)], view[(2, 1)], view[(2, 2)])
+}
+
+// Rango de profundidad cuando no hay nada que encuadrar
+pub const DEFAULT_DEPTH_RANGE: (f32, f32) = (0.1, 1000.0);
+
+pub fn create_perspective_matrix(window_width: f32, window_height: f32, near: f32, far: f32) -> Mat4 {
+    let aspect_ratio = window_width / window_height;
+
+    perspective(aspect_ratio, FOV, near, far)
+}
+
+// Vista ortográfica de `width_units` x `height_units` unidades del mundo centrada en la cámara
+pub fn create_orthographic_matrix(width_units: f32, height_units: f32, near: f32, far: f32) -> Mat4 {
+    ortho(-width_units / 2.0, width_units / 2.0, -height_units / 2.0, height_units / 2.0, near, far)
+}
+
+// En la proyección ortográfica w siempre es 1
+pub fn is_orthographic(projection_matrix: &Mat4) -> bool {
+    projection_matrix[(3, 3)] == 1.0
+}
+
+// Profundidad en el espacio de la vista a partir de la z normalizada del zbuffer; en la ortográfica
+// la z es lineal y en la perspectiva es inversa a la distancia
+pub fn linear_depth(ndc_depth: f32, projection_matrix: &Mat4) -> f32 {
+    let (scale, offset) = (projection_matrix[(2, 2)], projection_matrix[(2, 3)]);
+    if is_orthographic(projection_matrix) {
+        (offset - ndc_depth) / scale
+    } else {
+        offset / (ndc_depth + scale)
+    }
+}
+
+// Punto del mundo en pantalla: x e y en pixeles y la z del zbuffer; None detrás de la cámara
+pub fn project(uniforms: &FrameUniforms, point: Vec3) -> Option<Vec3> {
+    let clip = uniforms.projection_matrix * uniforms.view_matrix * Vec4::new(point.x, point.y, point.z, 1.0);
+    if clip.w <= 0.0 {
+        return None;
+    }
+    let screen = uniforms.viewport_matrix * Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    Some(Vec3::new(screen.x, screen.y, screen.z))
+}
+
+// Lo contrario de project: el punto del mundo que se ve en (x, y) con la z del zbuffer `depth`. El
+// centro del pixel (x, y) del framebuffer está en (x + 0.5, y + 0.5). Con una cámara sin dirección
+// (matrices sin inversa) el resultado es NaN
+pub fn unproject(x: f32, y: f32, depth: f32, uniforms: &FrameUniforms) -> Vec3 {
+    let transform = uniforms.viewport_matrix * uniforms.projection_matrix * uniforms.view_matrix;
+    let Some(inverse) = transform.try_inverse() else {
+        return Vec3::repeat(f32::NAN);
+    };
+    let point = inverse * Vec4::new(x, y, depth, 1.0);
+    Vec3::new(point.x, point.y, point.z) / point.w
+}
+
+pub fn create_viewport_matrix(viewport: &Rect) -> Mat4 {
+    let (x, y) = (viewport.x as f32, viewport.y as f32);
+    let (width, height) = (viewport.width as f32, viewport.height as f32);
+    Mat4::new(
+        width / 2.0, 0.0, 0.0, x + width / 2.0,
+        0.0, -height / 2.0, 0.0, y + height / 2.0,
+        0.0, 0.0, 1.0, 0.0,
+        0.0, 0.0, 0.0, 1.0
+    )
+}
+
+// Conteos y tiempos de una llamada a render (o la suma de varias)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RenderStats {
+    pub vertices: usize,
+    pub triangles: usize,
+    pub culled_triangles: usize,    // Fuera de la pantalla o sin área
+    pub skipped_triangles: usize,   // Descartados por el presupuesto de fragmentos
+    pub fragments: usize,
+    pub depth_passed: usize,
+    pub invalid_fragments: usize,   // Con atributos no finitos (NaN o infinito), no se dibujan
+    // Teselas con algún triángulo y cuántas recibieron 1, 2-3, 4-7... (ver tiles::histogram_label);
+    // 0 si el cuerpo se dibujó sin teselas
+    pub tiles: usize,
+    pub tile_triangles: [usize; TILE_HISTOGRAM_BUCKETS],
+    pub vertex_time: Duration,
+    pub raster_time: Duration,
+    pub fragment_time: Duration,
+}
+
+impl RenderStats {
+    pub fn add(&mut self, other: &RenderStats) {
+        self.vertices += other.vertices;
+        self.triangles += other.triangles;
+        self.culled_triangles += other.culled_triangles;
+        self.skipped_triangles += other.skipped_triangles;
+        self.fragments += other.fragments;
+        self.depth_passed += other.depth_passed;
+        self.invalid_fragments += other.invalid_fragments;
+        self.tiles += other.tiles;
+        for (total, tiles) in self.tile_triangles.iter_mut().zip(other.tile_triangles) {
+            *total += tiles;
+        }
+        self.vertex_time += other.vertex_time;
+        self.raster_time += other.raster_time;
+        self.fragment_time += other.fragment_time;
+    }
+}
+
+impl fmt::Display for RenderStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} vértices, {} triángulos ({} recortados, {} descartados), {} fragmentos ({} visibles, {} inválidos) | vértices {:.2} ms, rasterizado {:.2} ms, fragmentos {:.2} ms",
+            self.vertices, self.triangles, self.culled_triangles, self.skipped_triangles, self.fragments, self.depth_passed, self.invalid_fragments,
+            self.vertex_time.as_secs_f64() * 1000.0, self.raster_time.as_secs_f64() * 1000.0, self.fragment_time.as_secs_f64() * 1000.0,
+        )?;
+        if self.tiles > 0 {
+            write!(f, " | {} teselas, triángulos por tesela {}", self.tiles, histogram_label(&self.tile_triangles))?;
+        }
+        Ok(())
+    }
+}
+
+// Fragmentos máximos por frame y por triángulo
+pub const DEFAULT_FRAGMENT_BUDGET: usize = 4_000_000;
+pub const DEFAULT_TRIANGLE_FRAGMENT_CAP: usize = 300_000;
+
+// Límite de fragmentos que queda para el resto del frame; los triángulos que lo superarían
+// (o que solos cubren más de `per_triangle` pixeles) se descartan en lugar de congelar el frame
+#[derive(Clone, Copy, Debug)]
+pub struct FragmentBudget {
+    pub remaining: usize,
+    pub per_triangle: usize,
+}
+
+impl FragmentBudget {
+    pub fn new(per_frame: usize, per_triangle: usize) -> Self {
+        FragmentBudget { remaining: per_frame, per_triangle }
+    }
+}
+
+impl Default for FragmentBudget {
+    fn default() -> Self {
+        FragmentBudget::new(DEFAULT_FRAGMENT_BUDGET, DEFAULT_TRIANGLE_FRAGMENT_CAP)
+    }
+}
+
+// Memoria que render reutiliza entre llamadas para no reservarla en cada frame
+#[derive(Default)]
+pub struct RenderScratch {
+    vertices: Vec<Vertex>,
+    fragments: Vec<Fragments>,
+    // Triángulos que quedan para rasterizar (el índice de su primer vértice) con su caja en pantalla
+    triangles: Vec<(usize, PixelBounds)>,
+    bins: TileBins,
+}
+
+impl RenderScratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+// Con menos pixeles que esto entre todas las cajas de un cuerpo (uno lejano) abrir los hilos cuesta más
+// de lo que ahorran y se dibuja en un solo hilo
+const MIN_TILED_COVERAGE: usize = 2 * TILE_SIZE * TILE_SIZE;
+
+// Cantidad de hilos por omisión para RenderOptions::threads: uno por núcleo
+pub fn default_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+// Se dibuja en dos fases. Primero se descartan los triángulos que no van (detrás de la cámara, de
+// espalda, fuera de la vista o más grandes que el límite por triángulo) y se anota la caja en pantalla de
+// los demás. Después, con varios hilos y si el presupuesto del frame alcanza para todas las cajas, cada
+// triángulo se agrega a las teselas que toca su caja y cada tesela se dibuja por separado en su propia
+// copia del framebuffer, con sus triángulos recortados a ella y en el orden original; como la prueba de
+// cobertura es por pixel y hermética, la imagen es idéntica a la de un solo hilo. Si no, se dibujan uno
+// detrás de otro, rasterizando y sombreando cada triángulo antes de pasar al siguiente
+#[allow(clippy::too_many_arguments)]
+pub fn render(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, vertex_array: &[Vertex], current_shader: &dyn FragmentShader, budget: &mut FragmentBudget, stats: &mut RenderStats, scratch: &mut RenderScratch) {
+    framebuffer.set_current_id(body.body_id);
+
+    // Vertex Shader Stage
+    let stage_start = Instant::now();
+    let transformed_vertices = &mut scratch.vertices;
+    transformed_vertices.clear();
+    transformed_vertices.extend(vertex_array.iter().map(|vertex| vertex_shader(vertex, frame, body)));
+    // Sombreado plano: todo el triángulo usa el color del primer vértice
+    if current_shader.flat_shading() {
+        for tri in transformed_vertices.chunks_exact_mut(3) {
+            let color = tri[0].color;
+            tri[1].color = color;
+            tri[2].color = color;
+        }
+    }
+    stats.vertices += transformed_vertices.len();
+    stats.vertex_time += stage_start.elapsed();
+
+    // Triangle setup: lo que no se va a dibujar se cuenta y se descarta
+    let stage_start = Instant::now();
+    let triangles = &mut scratch.triangles;
+    triangles.clear();
+    let mut total_coverage = 0;
+    for (index, tri) in transformed_vertices.chunks_exact(3).enumerate() {
+        stats.triangles += 1;
+        // No hay recorte contra el plano cercano: un vértice detrás de la cámara se proyectaría invertido
+        if tri.iter().any(|vertex| vertex.view_depth <= 0.0) {
+            stats.culled_triangles += 1;
+            continue;
+        }
+        if frame.cull_backfaces && !body.two_sided && is_back_facing(frame, tri) {
+            stats.culled_triangles += 1;
+            continue;
+        }
+        let Some(bounds) = screen_bounds(&tri[0], &tri[1], &tri[2], &frame.viewport) else {
+            stats.culled_triangles += 1;
+            continue;
+        };
+        let coverage = (bounds.2 - bounds.0 + 1) as usize * (bounds.3 - bounds.1 + 1) as usize;
+        if coverage > budget.per_triangle {
+            stats.skipped_triangles += 1;
+            continue;
+        }
+        triangles.push((index * 3, bounds));
+        total_coverage += coverage;
+    }
+    stats.raster_time += stage_start.elapsed();
+
+    // Cada caja tiene al menos tantos pixeles como fragmentos da su triángulo, así que si entran todas en
+    // el presupuesto ninguno se descarta por él y el orden en que se gasta no importa
+    if frame.threads > 1 && (MIN_TILED_COVERAGE..=budget.remaining).contains(&total_coverage) {
+        let bins = &mut scratch.bins;
+        bins.reset(framebuffer.width, framebuffer.height);
+        for &(first, bounds) in triangles.iter() {
+            bins.insert(first, bounds);
+        }
+        let tiles = bins.occupied();
+        if tiles.len() > 1 {
+            stats.tiles += tiles.len();
+            for (total, count) in stats.tile_triangles.iter_mut().zip(bins.histogram()) {
+                *total += count;
+            }
+            let fragments = render_tiles(framebuffer, frame, body, transformed_vertices, current_shader, &tiles, stats);
+            budget.remaining = budget.remaining.saturating_sub(fragments);
+            return;
+        }
+    }
+
+    let fragments = &mut scratch.fragments;
+    for &(first, _) in triangles.iter() {
+        let tri = &transformed_vertices[first..first + 3];
+        if budget.remaining == 0 {
+            stats.skipped_triangles += 1;
+            continue;
+        }
+
+        // Rasterization Stage
+        let stage_start = Instant::now();
+        fragments.clear();
+        rasterize(frame, tri, &frame.viewport, fragments);
+        budget.remaining = budget.remaining.saturating_sub(fragments.len());
+        stats.fragments += fragments.len();
+        stats.raster_time += stage_start.elapsed();
+
+        // Fragment Processing Stage
+        let stage_start = Instant::now();
+        for fragment in fragments.iter_mut() {
+            shade_fragment(framebuffer, (0, 0), frame, body, current_shader, fragment, stats);
+        }
+        stats.fragment_time += stage_start.elapsed();
+    }
+}
+
+// Fragmentos del triángulo dentro de `clip`. En el tablero de ajedrez la mitad se descarta acá, antes
+// de sombrearlos
+fn rasterize(frame: &FrameUniforms, tri: &[Vertex], clip: &Rect, fragments: &mut Vec<Fragments>) {
+    triangle_for_each(&tri[0], &tri[1], &tri[2], clip, |fragment| {
+        if frame.checkerboard.is_none_or(|parity| is_shaded(fragment.position.x as usize, fragment.position.y as usize, parity)) {
+            fragments.push(fragment);
+        }
+    });
+}
+
+// Dibuja las teselas en frame.threads hilos, que van tomando la siguiente libre (así una tesela llena de
+// triángulos no deja a los demás hilos esperando) y al final se copian todas de vuelta. Los tiempos de
+// rasterizado y sombreado se reparten el tiempo real de la fase según lo que sumó cada uno en los hilos.
+// Devuelve los fragmentos generados
+fn render_tiles(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, vertices: &[Vertex], shader: &dyn FragmentShader, tiles: &[(Rect, &[usize])], stats: &mut RenderStats) -> usize {
+    let phase_start = Instant::now();
+    let next = AtomicUsize::new(0);
+    let source = &*framebuffer;
+    let workers: Vec<(Vec<(Rect, Framebuffer)>, RenderStats)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..frame.threads.min(tiles.len())).map(|_| scope.spawn(|| {
+            let mut drawn = Vec::new();
+            let mut stats = RenderStats::default();
+            let mut fragments = Vec::new();
+            while let Some(&(rect, triangles)) = tiles.get(next.fetch_add(1, Ordering::Relaxed)) {
+                let Some(clip) = rect.intersection(&frame.viewport) else {
+                    continue;
+                };
+                let mut tile = source.tile(rect);
+                for &first in triangles {
+                    let stage_start = Instant::now();
+                    fragments.clear();
+                    rasterize(frame, &vertices[first..first + 3], &clip, &mut fragments);
+                    stats.fragments += fragments.len();
+                    stats.raster_time += stage_start.elapsed();
+
+                    let stage_start = Instant::now();
+                    for fragment in fragments.iter_mut() {
+                        shade_fragment(&mut tile, (rect.x, rect.y), frame, body, shader, fragment, &mut stats);
+                    }
+                    stats.fragment_time += stage_start.elapsed();
+                }
+                drawn.push((rect, tile));
+            }
+            (drawn, stats)
+        })).collect();
+        handles.into_iter().map(|handle| handle.join().expect("un hilo de las teselas se detuvo")).collect()
+    });
+
+    let mut tile_stats = RenderStats::default();
+    for (drawn, worker_stats) in &workers {
+        for (rect, tile) in drawn {
+            framebuffer.write_tile(tile, *rect);
+        }
+        tile_stats.add(worker_stats);
+    }
+    let elapsed = phase_start.elapsed();
+    let busy = (tile_stats.raster_time + tile_stats.fragment_time).as_secs_f64();
+    let raster_share = if busy > 0.0 { tile_stats.raster_time.as_secs_f64() / busy } else { 0.0 };
+    stats.raster_time += elapsed.mul_f64(raster_share);
+    stats.fragment_time += elapsed.mul_f64(1.0 - raster_share);
+    stats.fragments += tile_stats.fragments;
+    stats.depth_passed += tile_stats.depth_passed;
+    stats.invalid_fragments += tile_stats.invalid_fragments;
+    tile_stats.fragments
+}
+
+// `origin` es el pixel de la pantalla que está en (0, 0) de `framebuffer` (el de una tesela)
+fn shade_fragment(framebuffer: &mut Framebuffer, origin: (usize, usize), frame: &FrameUniforms, body: &BodyUniforms, current_shader: &dyn FragmentShader, fragment: &mut Fragments, stats: &mut RenderStats) {
+    // Normal por fragmento e iluminación de todas las luces de la escena
+    fragment.normal = fragment.normal.normalize();
+    if body.two_sided && fragment.normal.dot(&to_camera(frame, &fragment.world_pos)) < 0.0 {
+        fragment.normal = -fragment.normal;
+    }
+    // Una normal en cero o un vértice mal transformado darían un color NaN; mejor no dibujar nada
+    if !is_finite_fragment(fragment) {
+        stats.invalid_fragments += 1;
+        return;
+    }
+    fragment.light = diffuse_light(&fragment.normal, &fragment.world_pos, &frame.lights, &frame.ambient_light(&fragment.normal));
+    fragment.intensity = (fragment.light.x + fragment.light.y + fragment.light.z) / 3.0;
+
+    let x = (fragment.position.x as usize).wrapping_sub(origin.0);
+    let y = (fragment.position.y as usize).wrapping_sub(origin.1);
+    if x < framebuffer.width && y < framebuffer.height {
+        // Apply fragment shader
+        let mut shaded_color = fragment_shader(fragment, frame, body, current_shader);
+        if let Some(fog) = &frame.fog {
+            shaded_color = fog.apply(shaded_color, fragment.view_depth);
+        }
+        if let Some(tint) = body.debug_tint {
+            shaded_color = shaded_color.lerp(&tint, 0.5);
+        }
+        if body.graticule {
+            shaded_color = graticule(shaded_color, &fragment.vertex_pos);
+        }
+        framebuffer.set_current_color(shaded_color);
+        let alpha = match body.blend {
+            BlendMode::Opaque => 1.0,
+            _ => body.opacity * current_shader.alpha(fragment, body),
+        };
+        if framebuffer.blend_point(x, y, fragment.depth, alpha, body.blend) {
+            stats.depth_passed += 1;
+        }
+    }
+}
+
+// Dirección desde un punto del mundo hacia la cámara; en la ortográfica es la misma en todos lados
+pub fn to_camera(frame: &FrameUniforms, world_position: &Vec3) -> Vec3 {
+    if is_orthographic(&frame.projection_matrix) {
+        view_direction(frame)
+    } else {
+        (frame.camera_position - world_position).normalize()
+    }
+}
+
+// La cara geométrica (vértices en sentido antihorario vistos de frente) mira hacia el otro lado de la
+// cámara; se mide en el mundo, así vale igual en las dos proyecciones
+fn is_back_facing(frame: &FrameUniforms, tri: &[Vertex]) -> bool {
+    let [a, b, c] = [0, 1, 2].map(|corner| tri[corner].world_position);
+    (b - a).cross(&(c - a)).dot(&to_camera(frame, &a)) < 0.0
+}
+
+fn is_finite_fragment(fragment: &Fragments) -> bool {
+    fragment.position.iter()
+        .chain(fragment.normal.iter())
+        .chain(fragment.world_pos.iter())
+        .chain(fragment.vertex_pos.iter())
+        .chain([fragment.depth, fragment.view_depth].iter())
+        .all(|value| value.is_finite())
+}
+
+// Separación y grosor (en radianes) de las líneas de la cuadrícula
+const GRATICULE_SPACING: f32 = PI / 6.0;
+const GRATICULE_WIDTH: f32 = 0.008;
+const GRATICULE_DARKEN: f32 = 0.3;
+// El ecuador y el meridiano cero se pintan de otro color para distinguir hacia dónde gira
+const GRATICULE_ACCENT: Color = Color::new(255, 215, 80);
+
+// Oscurece los fragmentos que caen sobre una línea de latitud o longitud cada 30°,
+// medidas sobre la posición del objeto (así la cuadrícula sigue la rotación del cuerpo)
+fn graticule(color: Color, position: &Vec3) -> Color {
+    let (latitude, longitude) = latitude_longitude(position);
+    // Los meridianos se juntan en los polos, así que su distancia se achica con la latitud
+    let parallel_scale = latitude.cos();
+
+    let line_distance = |angle: f32| {
+        let steps = angle / GRATICULE_SPACING;
+        (steps - steps.round()).abs() * GRATICULE_SPACING
+    };
+
+    if latitude.abs() < GRATICULE_WIDTH || longitude.abs() * parallel_scale < GRATICULE_WIDTH {
+        GRATICULE_ACCENT
+    } else if line_distance(latitude) < GRATICULE_WIDTH || line_distance(longitude) * parallel_scale < GRATICULE_WIDTH {
+        color * GRATICULE_DARKEN
+    } else {
+        color
+    }
+}
+
+// Largo de cada segmento del eje, como fracción del radio del cuerpo
+const AXIS_LENGTH: f32 = 0.5;
+const AXIS_NORTH_COLOR: Color = Color::new(255, 90, 70);
+const AXIS_SOUTH_COLOR: Color = Color::new(90, 150, 255);
+
+// Dibuja el eje de rotación (el eje y del objeto) saliendo de cada polo: rojo el norte y azul el sur
+pub fn render_axis(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, radius: f32) {
+    framebuffer.set_current_id(body.body_id);
+    for (sign, color) in [(1.0, AXIS_NORTH_COLOR), (-1.0, AXIS_SOUTH_COLOR)] {
+        let pole = Vec3::new(0.0, sign * radius, 0.0);
+        let tip = pole * (1.0 + AXIS_LENGTH);
+        render_line(framebuffer, frame, body, &pole, &tip, color);
+    }
+}
+
+// Línea de un pixel de ancho con prueba de profundidad entre dos puntos del objeto;
+// se descarta si alguno de los extremos queda detrás de la cámara
+fn render_line(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, from: &Vec3, to: &Vec3, color: Color) {
+    let transform = frame.projection_matrix * frame.view_matrix * body.model_matrix;
+    let project = |point: &Vec3| project_point(&transform, &frame.viewport_matrix, point).map(|(screen, _)| screen);
+    let (Some(start), Some(end)) = (project(from), project(to)) else {
+        return;
+    };
+
+    framebuffer.set_current_color(color);
+    let steps = (end.x - start.x).abs().max((end.y - start.y).abs()).ceil().clamp(1.0, 4096.0) as usize;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let point = start.lerp(&end, t);
+        if point.x < 0.0 || point.y < 0.0 || !frame.viewport.contains(point.x as usize, point.y as usize) {
+            continue;
+        }
+        framebuffer.point(point.x as usize, point.y as usize, point.z);
+    }
+}
+
+// Punto del objeto en la pantalla (z = profundidad del zbuffer) y su w, que es la distancia
+// en el eje de la vista; None si queda detrás de la cámara
+fn project_point(transform: &Mat4, viewport_matrix: &Mat4, point: &Vec3) -> Option<(Vec4, f32)> {
+    let position = transform * Vec4::new(point.x, point.y, point.z, 1.0);
+    if position.w <= 0.0 {
+        return None;
+    }
+    let ndc = Vec4::new(position.x / position.w, position.y / position.w, position.z / position.w, 1.0);
+    Some((viewport_matrix * ndc, position.w))
+}
+
+// Cuadrado de `size` pixeles centrado en el punto, con prueba de profundidad y recortado a `clip`;
+// `shade` recibe el color que ya hay en cada pixel. Devuelve cuántos pixeles se escribieron
+fn splat<F: Fn(Color) -> Color>(framebuffer: &mut Framebuffer, clip: &Rect, screen: &Vec4, size: i32, shade: F) -> usize {
+    let start_x = screen.x as i32 - size / 2;
+    let start_y = screen.y as i32 - size / 2;
+    let mut written = 0;
+
+    for y in start_y..start_y + size {
+        for x in start_x..start_x + size {
+            if x < 0 || y < 0 || !clip.contains(x as usize, y as usize) {
+                continue;
+            }
+            let (x, y) = (x as usize, y as usize);
+            let behind = Color::from_hex(framebuffer.buffer[y * framebuffer.width + x]);
+            framebuffer.set_current_color(shade(behind));
+            if framebuffer.point(x, y, screen.z) {
+                written += 1;
+            }
+        }
+    }
+
+    written
+}
+
+// Pixeles por unidad del mundo a distancia 1 de la cámara
+fn focal_length(frame: &FrameUniforms) -> f32 {
+    frame.projection_matrix[(1, 1)].abs() * frame.viewport.height as f32 / 2.0
+}
+
+// Tamaño de una partícula en unidades del mundo, para calcular cuántos pixeles ocupa
+const PARTICLE_SIZE: f32 = 0.012;
+
+// Dibuja partículas como cuadrados de 1 a 3 pixeles con prueba de profundidad;
+// se mezclan con lo que ya hay en pantalla según su edad
+pub fn render_points(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, particles: &[Particle], color: Color) {
+    let transform = frame.projection_matrix * frame.view_matrix * body.model_matrix;
+    let focal = focal_length(frame);
+    framebuffer.set_current_id(body.body_id);
+
+    for particle in particles {
+        let Some((screen, w)) = project_point(&transform, &frame.viewport_matrix, &particle.position) else {
+            continue;
+        };
+        let size = (PARTICLE_SIZE * focal / w).round().clamp(1.0, 3.0) as i32;
+        let alpha = particle.fade() * 0.6;
+        splat(framebuffer, &frame.viewport, &screen, size, |behind| behind.lerp(&color, alpha));
+    }
+}
+
+// Estela de un cuerpo: un pixel por posición, con prueba de profundidad, cada vez más transparente hacia
+// las posiciones más viejas (la primera de `points`)
+pub fn render_trail(framebuffer: &mut Framebuffer, frame: &FrameUniforms, points: &VecDeque<Vec3>, color: Color) {
+    let transform = frame.projection_matrix * frame.view_matrix;
+    // No es un cuerpo: usa el id del fondo y no se puede elegir
+    framebuffer.set_current_id(0);
+
+    for (age, point) in points.iter().rev().enumerate() {
+        let Some((screen, _)) = project_point(&transform, &frame.viewport_matrix, point) else {
+            continue;
+        };
+        let alpha = 0.8 * (1.0 - age as f32 / points.len() as f32);
+        splat(framebuffer, &frame.viewport, &screen, 1, |behind| behind.lerp(&color, alpha));
+    }
+}
+
+// Tamaño de una partícula que brilla en unidades del mundo
+const GLOW_SIZE: f32 = 0.03;
+
+// Partículas en el mundo que se suman a lo que tienen detrás (por ejemplo la cola de un cometa): prueban
+// la profundidad pero no la escriben, así los cuerpos de adelante las tapan y no se tapan entre ellas.
+// Cada una aporta `color` escalado por su `fade`; la niebla las apaga en vez de teñirlas
+pub fn render_glow(framebuffer: &mut Framebuffer, frame: &FrameUniforms, particles: &[Particle], color: Color, stats: &mut RenderStats) {
+    let stage_start = Instant::now();
+    let transform = frame.projection_matrix * frame.view_matrix;
+    let focal = focal_length(frame);
+    framebuffer.set_current_color(color);
+
+    for particle in particles {
+        let Some((screen, w)) = project_point(&transform, &frame.viewport_matrix, &particle.position) else {
+            continue;
+        };
+        let size = (GLOW_SIZE * focal / w).round().clamp(1.0, 3.0) as i32;
+        let mut alpha = particle.fade();
+        if let Some(fog) = &frame.fog {
+            let position = particle.position;
+            alpha *= 1.0 - fog.amount(-(frame.view_matrix * Vec4::new(position.x, position.y, position.z, 1.0)).z);
+        }
+
+        let (start_x, start_y) = (screen.x as i32 - size / 2, screen.y as i32 - size / 2);
+        for y in start_y..start_y + size {
+            for x in start_x..start_x + size {
+                if x < 0 || y < 0 || !frame.viewport.contains(x as usize, y as usize) {
+                    continue;
+                }
+                if framebuffer.blend_point(x as usize, y as usize, screen.z, alpha, BlendMode::Additive) {
+                    stats.depth_passed += 1;
+                }
+                stats.fragments += 1;
+            }
+        }
+    }
+
+    stats.vertices += particles.len();
+    stats.fragment_time += stage_start.elapsed();
+}
+
+// Cielo de estrellas en los pixeles de la vista que quedaron sin geometría. Va después de lo opaco,
+// con la profundidad como máscara, así ninguna estrella se asoma por el borde de un planeta, y antes de
+// lo translúcido, que se mezcla con las estrellas (se ven por los huecos del anillo). En la ortográfica
+// todos los pixeles miran en la misma dirección y no hay cielo que mostrar. Devuelve los pixeles pintados
+pub fn render_starfield(framebuffer: &mut Framebuffer, frame: &FrameUniforms) -> usize {
+    render_sky(framebuffer, frame, |direction| starfield(direction, frame.time))
+}
+
+// Igual que render_starfield con cualquier cielo: `sky` da el color en una dirección normalizada del mundo
+pub fn render_sky(framebuffer: &mut Framebuffer, frame: &FrameUniforms, sky: impl Fn(&Vec3) -> Color) -> usize {
+    if is_orthographic(&frame.projection_matrix) {
+        return 0;
+    }
+    let Some(unproject) = (frame.viewport_matrix * frame.projection_matrix * frame.view_matrix).try_inverse() else {
+        return 0;
+    };
+    // El punto del plano lejano bajo cada pixel, en coordenadas homogéneas, menos la cámara (escalada por
+    // la misma w) es la dirección del pixel; las dos partes son lineales en x e y, y w no cambia de signo
+    let eye = frame.camera_position.push(1.0);
+    let ray = |x: f32, y: f32| {
+        let far = unproject * Vec4::new(x, y, 1.0, 1.0);
+        (far - eye * far.w).xyz() * far.w.signum()
+    };
+    let origin = ray(0.5, 0.5);
+    let (step_x, step_y) = (ray(1.5, 0.5) - origin, ray(0.5, 1.5) - origin);
+
+    framebuffer.fill_empty(frame.viewport, |x, y| {
+        let direction = (origin + step_x * x as f32 + step_y * y as f32).normalize();
+        sky(&direction)
+    })
+}
+
+// Tamaño de una partícula del anillo en unidades del objeto
+const RING_PARTICLE_SIZE: f32 = 0.01;
+
+// Anillo hecho de partículas: cuadrados opacos de 1 o 2 pixeles con el color de las franjas del anillo.
+// No pasan por el shader de fragmentos; la luz se calcula una vez para todo el anillo porque es plano
+pub fn render_ring_particles(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, ring: &ParticleRing, stats: &mut RenderStats) {
+    let stage_start = Instant::now();
+    let model_view = frame.view_matrix * body.model_matrix;
+    let transform = frame.projection_matrix * model_view;
+    let focal = focal_length(frame);
+    // La matriz de modelo escala igual en los tres ejes, así que basta con su columna y
+    let scale = body.model_matrix.fixed_view::<3, 1>(0, 1).magnitude();
+    framebuffer.set_current_id(body.body_id);
+
+    let center = body.model_matrix * Vec4::new(0.0, 0.0, 0.0, 1.0);
+    let normal = body.model_matrix * Vec4::new(0.0, 1.0, 0.0, 0.0);
+    let light = ring_light(&Vec3::new(normal.x, normal.y, normal.z).normalize(), &Vec3::new(center.x, center.y, center.z), &frame.lights);
+    let boost = if body.highlighted { 1.0 + HIGHLIGHT_BOOST } else { 1.0 };
+
+    for particle in &ring.particles {
+        let position = ring.position(particle, frame.time);
+        let Some((screen, w)) = project_point(&transform, &frame.viewport_matrix, &position) else {
+            continue;
+        };
+        let size = (RING_PARTICLE_SIZE * scale * focal / w).round().clamp(1.0, 2.0) as i32;
+
+        let mut color = match &frame.thermal {
+            Some(gradient) => Color::from_gradient(lit_temperature(light * particle.brightness), gradient),
+            None => ring_particle_color(particle.band, light) * (particle.brightness * boost),
+        };
+        if let Some(fog) = &frame.fog {
+            color = fog.apply(color, -(model_view * Vec4::new(position.x, position.y, position.z, 1.0)).z);
+        }
+        if let Some(tint) = body.debug_tint {
+            color = color.lerp(&tint, 0.5);
+        }
+
+        let written = splat(framebuffer, &frame.viewport, &screen, size, |_| color);
+        stats.fragments += (size * size) as usize;
+        stats.depth_passed += written;
+    }
+
+    stats.vertices += ring.particles.len();
+    stats.fragment_time += stage_start.elapsed();
+}
+
+// Un pixel con prueba de profundidad en una posición del mundo, para objetos más chicos que un pixel
+pub fn render_point(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, position: &Vec3, color: Color, stats: &mut RenderStats) {
+    let transform = frame.projection_matrix * frame.view_matrix;
+    let Some((screen, _)) = project_point(&transform, &frame.viewport_matrix, position) else {
+        return;
+    };
+
+    let mut color = color;
+    if let Some(fog) = &frame.fog {
+        color = fog.apply(color, -(frame.view_matrix * Vec4::new(position.x, position.y, position.z, 1.0)).z);
+    }
+    if let Some(tint) = body.debug_tint {
+        color = color.lerp(&tint, 0.5);
+    }
+
+    framebuffer.set_current_id(body.body_id);
+    stats.vertices += 1;
+    stats.fragments += 1;
+    stats.depth_passed += splat(framebuffer, &frame.viewport, &screen, 1, |_| color);
+}
+
+// Distancia del origen del objeto a la cámara en el eje de la vista
+pub fn view_space_depth(frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+    -(frame.view_matrix * body.model_matrix * Vec4::new(0.0, 0.0, 0.0, 1.0)).z
+}
+
+// Orden en que se dibujan los cuerpos: primero los opacos en el orden dado, que escriben la
+// profundidad, y después los translúcidos del más lejano al más cercano, así cada uno se mezcla
+// sobre todo lo que tiene detrás sin importar en qué orden llegaron
+pub fn draw_order(frame: &FrameUniforms, draws: &[BodyUniforms]) -> Vec<usize> {
+    let (mut order, mut translucent): (Vec<usize>, Vec<usize>) = (0..draws.len())
+        .partition(|&index| draws[index].blend == BlendMode::Opaque);
+    translucent.sort_by(|&a, &b| view_space_depth(frame, &draws[b]).total_cmp(&view_space_depth(frame, &draws[a])));
+    order.extend(translucent);
+    order
+}
+
+pub fn build_uniforms(camera: &Camera, time: f32, width: usize, height: usize, depth_range: (f32, f32)) -> FrameUniforms {
+    build_viewport_uniforms(camera, time, Rect::new(0, 0, width, height), depth_range)
+}
+
+// Uniforms del frame para dibujar en una parte del framebuffer (por ejemplo el minimapa)
+pub fn build_viewport_uniforms(camera: &Camera, time: f32, viewport: Rect, depth_range: (f32, f32)) -> FrameUniforms {
+    let (width, height) = (viewport.width, viewport.height);
+    let (near, far) = depth_range;
+    let projection_matrix = match camera.projection {
+        Projection::Perspective => create_perspective_matrix(width as f32, height as f32, near, far),
+        Projection::Orthographic { height: height_units } => {
+            create_orthographic_matrix(height_units * width as f32 / height as f32, height_units, near, far)
+        }
+    };
+    FrameUniforms {
+        view_matrix: create_view_matrix(camera.eye, camera.center, camera.up),
+        projection_matrix,
+        viewport_matrix: create_viewport_matrix(&viewport),
+        viewport,
+        camera_position: camera.eye,
+        time,
+        debug_mode: 0,
+        lights: default_lights(),
+        ambient: Color::BLACK,
+        skylight: AmbientCube::BLACK,
+        cel_bands: None,
+        fog: None,
+        thermal: None,
+        cull_backfaces: false,
+        checkerboard: None,
+        threads: 1,
+    }
+}
+
+// Reemplaza los colores por el zbuffer en escala de grises (cerca = blanco, lejos = negro)
+pub fn visualize_depth(framebuffer: &mut Framebuffer, projection_matrix: &Mat4, depth_range: (f32, f32)) {
+    let (near, far) = depth_range;
+    for (pixel, &depth) in framebuffer.buffer.iter_mut().zip(&framebuffer.zbuffer) {
+        if depth.is_infinite() {
+            *pixel = 0x000000;
+            continue;
+        }
+        let distance = linear_depth(depth, projection_matrix);
+        let t = ((distance - near) / (far - near)).clamp(0.0, 1.0);
+        let gray = Color::new(255, 255, 255) * (1.0 - t);
+        *pixel = gray.to_hex_with_threshold(0.5);
+    }
+}
@@ -0,0 +1,475 @@
+use crate::color::Color;
+use crate::framebuffer::{Framebuffer, Rect};
+use crate::lut::Lut;
+use std::fmt;
+use crate::pipeline::{is_orthographic, linear_depth, FrameUniforms};
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+// Mezcla cada frame con el historial de los anteriores: con un peso fijo da desenfoque de
+// movimiento y con `average` el promedio de todos los frames (para imágenes fijas sin serrucho)
+pub struct Accumulator {
+    history: Vec<Color>,
+    frames: u32,
+}
+
+impl Accumulator {
+    pub fn new() -> Self {
+        Accumulator { history: Vec::new(), frames: 0 }
+    }
+
+    // Olvida los frames anteriores, por ejemplo al cambiar de escena
+    pub fn reset(&mut self) {
+        self.history.clear();
+        self.frames = 0;
+    }
+
+    // Frames promediados desde el último reset
+    pub fn frames(&self) -> u32 {
+        self.frames
+    }
+
+    // Agrega el frame al promedio y deja el promedio en el framebuffer
+    pub fn average(&mut self, framebuffer: &mut Framebuffer) {
+        self.frames += 1;
+        self.apply(framebuffer, 1.0 / self.frames as f32);
+    }
+
+    // Resultado = nuevo * weight + anterior * (1 - weight); con weight 1 el frame queda igual
+    pub fn apply(&mut self, framebuffer: &mut Framebuffer, weight: f32) {
+        if weight >= 1.0 || self.history.len() != framebuffer.buffer.len() {
+            self.history = framebuffer.buffer.iter().map(|&pixel| Color::from_hex(pixel)).collect();
+            return;
+        }
+
+        for (pixel, previous) in framebuffer.buffer.iter_mut().zip(self.history.iter_mut()) {
+            *previous = previous.lerp(&Color::from_hex(*pixel), weight);
+            *pixel = previous.to_hex_with_threshold(0.5);
+        }
+    }
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Desplazamiento de subpixel del frame `index` para la acumulación, en [-0.5, 0.5) en cada eje
+// (secuencia de Halton en bases 2 y 3: cubre el pixel de forma pareja con pocos frames)
+pub fn subpixel_jitter(index: u32) -> (f32, f32) {
+    (halton(index + 1, 2) - 0.5, halton(index + 1, 3) - 0.5)
+}
+
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut result = 0.0;
+    let mut fraction = 1.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+// Mueve toda la imagen una fracción de pixel en la matriz del viewport
+pub fn jitter_uniforms(uniforms: &mut FrameUniforms, (x, y): (f32, f32)) {
+    uniforms.viewport_matrix[(0, 3)] += x;
+    uniforms.viewport_matrix[(1, 3)] += y;
+}
+
+// Color de los contornos del modo caricatura
+pub const OUTLINE_COLOR: Color = Color::new(20, 20, 25);
+
+// Diferencia relativa de profundidad entre vecinos a partir de la cual se dibuja un borde
+pub const OUTLINE_THRESHOLD: f32 = 0.08;
+
+// Oscurece los pixeles donde la profundidad cambia bruscamente (siluetas y cruces entre cuerpos);
+// el fondo no tiene profundidad y nunca se marca
+pub fn outline(framebuffer: &mut Framebuffer, projection_matrix: &Mat4, threshold: f32, color: Color) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let depth: Vec<f32> = framebuffer.zbuffer.iter()
+        .map(|&z| if z.is_finite() { linear_depth(z, projection_matrix) } else { f32::INFINITY })
+        .collect();
+
+    for y in 0..height {
+        for x in 0..width {
+            let center = depth[y * width + x];
+            if center.is_infinite() {
+                continue;
+            }
+
+            let neighbors = [
+                (x > 0).then(|| depth[y * width + x - 1]),
+                (x + 1 < width).then(|| depth[y * width + x + 1]),
+                (y > 0).then(|| depth[(y - 1) * width + x]),
+                (y + 1 < height).then(|| depth[(y + 1) * width + x]),
+            ];
+            // Solo el lado más cercano del borde se oscurece, así la línea queda de un pixel
+            let is_edge = neighbors.iter().flatten().any(|&other| (other - center) / center > threshold);
+            if is_edge {
+                framebuffer.buffer[y * width + x] = color.to_hex_with_threshold(0.5);
+            }
+        }
+    }
+}
+
+// Oclusión ambiental aproximada en el espacio de la pantalla, solo con el zbuffer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientOcclusion {
+    pub radius: f32,      // Radio de muestreo en unidades del mundo (en pixeles depende de la profundidad)
+    pub intensity: f32,   // Oscurecimiento con la oclusión máxima (0 = nada, 1 = negro)
+    pub bias: f32,        // Hueco mínimo, relativo a la profundidad del pixel, para que cuente como oclusión
+}
+
+impl AmbientOcclusion {
+    pub const DEFAULT: AmbientOcclusion = AmbientOcclusion {
+        radius: 0.3,
+        intensity: 0.7,
+        bias: 0.01,
+    };
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+// Direcciones de los pares de muestras opuestas (cada 22.5°) y su distancia relativa al radio
+const OCCLUSION_PAIRS: usize = 8;
+const OCCLUSION_PAIR_DISTANCES: [f32; 2] = [0.5, 1.0];
+// Límites del radio en pixeles, para que los cuerpos lejanos no pierdan el efecto y los cercanos no sean lentos
+const OCCLUSION_MIN_RADIUS: f32 = 2.0;
+const OCCLUSION_MAX_RADIUS: f32 = 32.0;
+// Pesos del desenfoque separable de la oclusión
+const OCCLUSION_BLUR: [f32; 5] = [1.0 / 16.0, 4.0 / 16.0, 6.0 / 16.0, 4.0 / 16.0, 1.0 / 16.0];
+
+// Oscurece los pixeles que quedan en un hueco de la profundidad: para cada par de muestras opuestas
+// se compara el pixel con el promedio de las dos, así una superficie plana o convexa (como una
+// esfera) no se oscurece a sí misma y solo cuenta lo que está delante de un lado, como el borde
+// del anillo sobre el planeta. Los huecos mucho más grandes que el radio se desvanecen. El fondo no se toca
+pub fn ambient_occlusion(framebuffer: &mut Framebuffer, projection_matrix: &Mat4, params: &AmbientOcclusion) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let depth: Vec<f32> = framebuffer.zbuffer.iter()
+        .map(|&z| if z.is_finite() { linear_depth(z, projection_matrix) } else { f32::INFINITY })
+        .collect();
+
+    // Pixeles que ocupa una unidad del mundo a distancia 1 (en la ortográfica, a cualquier distancia)
+    let pixels_per_unit = projection_matrix[(1, 1)] * height as f32 / 2.0;
+    let orthographic = is_orthographic(projection_matrix);
+    let offsets: Vec<(f32, f32)> = (0..OCCLUSION_PAIRS).map(|pair| {
+        let angle = pair as f32 * std::f32::consts::PI / OCCLUSION_PAIRS as f32;
+        let distance = OCCLUSION_PAIR_DISTANCES[pair % OCCLUSION_PAIR_DISTANCES.len()];
+        (angle.cos() * distance, angle.sin() * distance)
+    }).collect();
+    let sample = |x: i64, y: i64| depth[y.clamp(0, height as i64 - 1) as usize * width + x.clamp(0, width as i64 - 1) as usize];
+
+    let mut occlusion = vec![0.0f32; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let center = depth[y * width + x];
+            if center.is_infinite() {
+                continue;
+            }
+            let scale = if orthographic { pixels_per_unit } else { pixels_per_unit / center };
+            let radius = (params.radius * scale).clamp(OCCLUSION_MIN_RADIUS, OCCLUSION_MAX_RADIUS);
+
+            let mut total = 0.0;
+            for &(dx, dy) in &offsets {
+                let (dx, dy) = ((dx * radius).round() as i64, (dy * radius).round() as i64);
+                let (x, y) = (x as i64, y as i64);
+                let (a, b) = (sample(x + dx, y + dy), sample(x - dx, y - dy));
+                // Si una muestra cae en el fondo el par no dice nada (si no, los bordes se oscurecerían)
+                if a.is_infinite() || b.is_infinite() {
+                    continue;
+                }
+                let gap = center - (a + b) * 0.5 - params.bias * center;
+                if gap > 0.0 {
+                    total += (gap / params.radius).min(params.radius / gap);
+                }
+            }
+            occlusion[y * width + x] = total / OCCLUSION_PAIRS as f32;
+        }
+    }
+
+    let occlusion = blur(&blur(&occlusion, width, height, (1, 0)), width, height, (0, 1));
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            if depth[index].is_infinite() || occlusion[index] <= 0.0 {
+                continue;
+            }
+            let shade = 1.0 - params.intensity * occlusion[index].min(1.0);
+            let color = Color::from_hex(framebuffer.buffer[index]) * shade;
+            framebuffer.buffer[index] = color.to_hex_with_threshold(framebuffer.threshold(x, y));
+        }
+    }
+}
+
+// Una pasada del desenfoque en la dirección (dx, dy), repitiendo los bordes
+fn blur(values: &[f32], width: usize, height: usize, (dx, dy): (i64, i64)) -> Vec<f32> {
+    let half = (OCCLUSION_BLUR.len() / 2) as i64;
+    let mut result = vec![0.0; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            result[y * width + x] = OCCLUSION_BLUR.iter().enumerate().map(|(tap, weight)| {
+                let step = tap as i64 - half;
+                let sx = (x as i64 + dx * step).clamp(0, width as i64 - 1) as usize;
+                let sy = (y as i64 + dy * step).clamp(0, height as i64 - 1) as usize;
+                values[sy * width + sx] * weight
+            }).sum();
+        }
+    }
+    result
+}
+
+// Anaglifo rojo-cian: el rojo sale de la imagen del ojo izquierdo y el verde y el azul de la del
+// derecho, que es la que ya está en el framebuffer
+pub fn anaglyph(left: &[u32], right: &mut Framebuffer) {
+    for (pixel, &left) in right.buffer.iter_mut().zip(left) {
+        *pixel = (left & 0xFF0000) | (*pixel & 0x00FFFF);
+    }
+}
+
+// Pixeles que cambiaron entre dos frames
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DiffStats {
+    pub changed: usize,
+    // Tramos seguidos de pixeles cambiados en cada fila (cuántos bloques hay que redibujar)
+    pub runs: usize,
+    // Rectángulo más chico que contiene todos los cambios
+    pub bounds: Option<Rect>,
+}
+
+impl fmt::Display for DiffStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.bounds {
+            Some(bounds) => write!(
+                f,
+                "{} pixeles cambiaron en {} tramos, región {}x{} desde ({}, {})",
+                self.changed, self.runs, bounds.width, bounds.height, bounds.x, bounds.y
+            ),
+            None => write!(f, "sin cambios"),
+        }
+    }
+}
+
+// Vista de diferencias: lo que no cambió se oscurece y lo que cambió queda a brillo completo teñido de magenta
+pub const DIFF_DIM: f32 = 0.2;
+pub const DIFF_TINT: Color = Color::new(255, 0, 255);
+const DIFF_TINT_AMOUNT: f32 = 0.35;
+
+// Compara el frame con el anterior y lo reemplaza por la vista de diferencias. Si el anterior es de
+// otro tamaño (o todavía no hay) cuenta como que cambió todo
+pub fn diff_view(current: &mut Framebuffer, previous: &[u32]) -> DiffStats {
+    let same_size = previous.len() == current.buffer.len();
+    let mut stats = DiffStats::default();
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (usize::MAX, usize::MAX, 0, 0);
+    for y in 0..current.height {
+        let mut in_run = false;
+        for x in 0..current.width {
+            let index = y * current.width + x;
+            let changed = !same_size || previous[index] != current.buffer[index];
+            let color = Color::from_hex(current.buffer[index]);
+            let shown = if changed { color.lerp(&DIFF_TINT, DIFF_TINT_AMOUNT) } else { color * DIFF_DIM };
+            current.buffer[index] = shown.to_hex_with_threshold(0.5);
+            if changed {
+                stats.changed += 1;
+                stats.runs += usize::from(!in_run);
+                (min_x, min_y) = (min_x.min(x), min_y.min(y));
+                (max_x, max_y) = (max_x.max(x), max_y.max(y));
+            }
+            in_run = changed;
+        }
+    }
+    stats.bounds = (stats.changed > 0).then(|| Rect::new(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1));
+    stats
+}
+
+// Suma un círculo con degradado radial (máximo en el centro, cero en el borde) al framebuffer
+pub fn additive_circle(framebuffer: &mut Framebuffer, center: (f32, f32), radius: f32, color: Color, intensity: f32) {
+    let (cx, cy) = center;
+    let min_x = (cx - radius).floor().max(0.0) as i64;
+    let min_y = (cy - radius).floor().max(0.0) as i64;
+    let max_x = (cx + radius).ceil().min(framebuffer.width as f32 - 1.0) as i64;
+    let max_y = (cy + radius).ceil().min(framebuffer.height as f32 - 1.0) as i64;
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let distance = ((x as f32 + 0.5 - cx).powi(2) + (y as f32 + 0.5 - cy).powi(2)).sqrt();
+            let falloff = (1.0 - distance / radius).max(0.0);
+            if falloff <= 0.0 {
+                continue;
+            }
+            let index = y as usize * framebuffer.width + x as usize;
+            let glow = Color::from_hex(framebuffer.buffer[index]) + color * (falloff * falloff * intensity);
+            framebuffer.buffer[index] = glow.to_hex_with_threshold(0.5);
+        }
+    }
+}
+
+// Destellos del lens flare: `offset` es la posición sobre la línea del sol (0) al centro de la pantalla (1)
+struct FlareSprite {
+    offset: f32,
+    radius: f32,       // En pixeles
+    color: Color,
+    intensity: f32,
+}
+
+const LENS_FLARE_SPRITES: [FlareSprite; 7] = [
+    FlareSprite { offset: 0.0, radius: 160.0, color: Color::new(255, 210, 150), intensity: 0.5 },  // Brillo alrededor del sol
+    FlareSprite { offset: 0.0, radius: 45.0, color: Color::new(255, 255, 235), intensity: 0.8 },   // Núcleo
+    FlareSprite { offset: 0.45, radius: 22.0, color: Color::new(120, 200, 255), intensity: 0.25 },
+    FlareSprite { offset: 0.8, radius: 12.0, color: Color::new(255, 150, 80), intensity: 0.35 },
+    FlareSprite { offset: 1.2, radius: 36.0, color: Color::new(140, 255, 160), intensity: 0.15 },
+    FlareSprite { offset: 1.6, radius: 18.0, color: Color::new(200, 120, 255), intensity: 0.25 },
+    FlareSprite { offset: 2.1, radius: 55.0, color: Color::new(120, 160, 255), intensity: 0.12 },
+];
+
+// Parte de la pantalla (en coordenadas normalizadas) donde el flare se desvanece hacia el borde
+const LENS_FLARE_EDGE_FADE: f32 = 0.25;
+// Puntos del disco del sol que se revisan en el zbuffer para saber si está tapado
+const LENS_FLARE_SAMPLES: usize = 12;
+
+// Lens flare de un sol: no se dibuja si está fuera del frustum y se atenúa según la parte
+// del sol que tapan otros cuerpos y lo cerca que está del borde de la pantalla
+pub fn lens_flare(framebuffer: &mut Framebuffer, uniforms: &FrameUniforms, sun_position: Vec3, sun_radius: f32) {
+    let view = uniforms.view_matrix * Vec4::new(sun_position.x, sun_position.y, sun_position.z, 1.0);
+    let clip = uniforms.projection_matrix * view;
+    if clip.w <= 0.0 {
+        return;
+    }
+    let ndc = Vec4::new(clip.x / clip.w, clip.y / clip.w, clip.z / clip.w, 1.0);
+    let edge = 1.0 - ndc.x.abs().max(ndc.y.abs());
+    if edge <= 0.0 {
+        return;
+    }
+    let edge_fade = (edge / LENS_FLARE_EDGE_FADE).min(1.0);
+    let screen = uniforms.viewport_matrix * ndc;
+
+    // Un punto se ve si lo que hay en el zbuffer no está delante de la superficie del sol
+    let focal = uniforms.projection_matrix[(1, 1)].abs() * framebuffer.height as f32 / 2.0;
+    let sample_radius = (sun_radius * focal / clip.w * 0.5).min(20.0);
+    let front = -view.z - sun_radius * 1.05;
+    let visible = (0..=LENS_FLARE_SAMPLES).filter(|&i| {
+        let (dx, dy) = if i == 0 {
+            (0.0, 0.0)
+        } else {
+            let angle = i as f32 / LENS_FLARE_SAMPLES as f32 * std::f32::consts::TAU;
+            (angle.cos() * sample_radius, angle.sin() * sample_radius)
+        };
+        let (x, y) = (screen.x + dx, screen.y + dy);
+        if x < 0.0 || y < 0.0 || x >= framebuffer.width as f32 || y >= framebuffer.height as f32 {
+            return false;
+        }
+        let depth = framebuffer.zbuffer[y as usize * framebuffer.width + x as usize];
+        depth.is_infinite() || linear_depth(depth, &uniforms.projection_matrix) >= front
+    }).count();
+    let visibility = visible as f32 / (LENS_FLARE_SAMPLES + 1) as f32;
+
+    let strength = visibility * edge_fade;
+    if strength <= 0.0 {
+        return;
+    }
+
+    let center = (framebuffer.width as f32 / 2.0, framebuffer.height as f32 / 2.0);
+    for sprite in LENS_FLARE_SPRITES.iter() {
+        let position = (
+            screen.x + (center.0 - screen.x) * sprite.offset,
+            screen.y + (center.1 - screen.y) * sprite.offset,
+        );
+        additive_circle(framebuffer, position, sprite.radius, sprite.color, sprite.intensity * strength);
+    }
+}
+
+// Corrección de color de toda la imagen, guardada por escena; con los valores por defecto no cambia nada
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColorGrade {
+    pub exposure: f32,          // Pasos: +1 duplica la luz (en espacio lineal)
+    pub saturation: f32,        // 0 = gris, 1 = sin cambio
+    pub contrast: f32,          // Alrededor del gris medio, 1 = sin cambio
+    pub white_balance: Color,   // Multiplica cada canal; blanco = sin cambio
+}
+
+impl ColorGrade {
+    pub const NEUTRAL: ColorGrade = ColorGrade {
+        exposure: 0.0,
+        saturation: 1.0,
+        contrast: 1.0,
+        white_balance: Color::new(255, 255, 255),
+    };
+
+    pub fn is_neutral(&self) -> bool {
+        *self == Self::NEUTRAL
+    }
+}
+
+impl Default for ColorGrade {
+    fn default() -> Self {
+        Self::NEUTRAL
+    }
+}
+
+// Gamma aproximada de la salida de 8 bits
+const DISPLAY_GAMMA: f32 = 2.2;
+// Tabla para volver a codificar: se indexa con la raíz del valor lineal, que reparte mejor los
+// oscuros, y cubre hasta 4 veces el blanco (con exposición alta); se interpola entre entradas
+const ENCODE_TABLE_SIZE: usize = 1024;
+const ENCODE_TABLE_MAX_ROOT: f32 = 2.0;
+
+fn encode_table() -> Vec<f32> {
+    (0..=ENCODE_TABLE_SIZE)
+        .map(|index| (index as f32 / ENCODE_TABLE_SIZE as f32 * ENCODE_TABLE_MAX_ROOT).powf(2.0 / DISPLAY_GAMMA))
+        .collect()
+}
+
+fn encode(table: &[f32], linear: f32) -> f32 {
+    let position = (linear.sqrt() / ENCODE_TABLE_MAX_ROOT * ENCODE_TABLE_SIZE as f32).min(ENCODE_TABLE_SIZE as f32 - 0.001);
+    let index = position as usize;
+    let t = position - index as f32;
+    table[index] + (table[index + 1] - table[index]) * t
+}
+
+// Aplica la corrección al framebuffer: exposición, balance de blancos y saturación en espacio lineal,
+// y contraste sobre el valor ya codificado. Se cuantiza otra vez con el umbral del framebuffer, así el
+// dithering queda después de la corrección. Con la corrección neutra no se toca la imagen
+pub fn color_grade(framebuffer: &mut Framebuffer, grade: &ColorGrade) {
+    if grade.is_neutral() {
+        return;
+    }
+
+    let decode: Vec<f32> = (0..=255).map(|value| (value as f32 / 255.0).powf(DISPLAY_GAMMA)).collect();
+    let encode_table = encode_table();
+    let white = grade.white_balance;
+    let gain = Vec3::new(white.r, white.g, white.b) * (2.0f32.powf(grade.exposure) / 255.0);
+
+    for y in 0..framebuffer.height {
+        for x in 0..framebuffer.width {
+            let index = y * framebuffer.width + x;
+            let [_, r, g, b] = framebuffer.buffer[index].to_be_bytes();
+            let linear = Vec3::new(decode[r as usize] * gain.x, decode[g as usize] * gain.y, decode[b as usize] * gain.z);
+
+            let luminance = linear.dot(&Vec3::new(0.2126, 0.7152, 0.0722));
+            let saturated = linear.map(|channel| (luminance + (channel - luminance) * grade.saturation).max(0.0));
+
+            let encoded = saturated.map(|channel| {
+                let value = encode(&encode_table, channel);
+                ((value - 0.5) * grade.contrast + 0.5) * 255.0
+            });
+            let color = Color { r: encoded.x, g: encoded.y, b: encoded.z };
+            framebuffer.buffer[index] = color.to_hex_with_threshold(framebuffer.threshold(x, y));
+        }
+    }
+}
+
+// Curvas de color por canal sobre la imagen terminada, después de la corrección de color. Los colores ya
+// son de 8 bits, así que cada canal se busca directo en la tabla
+pub fn apply_lut(framebuffer: &mut Framebuffer, lut: &Lut) {
+    let [red, green, blue] = lut.byte_tables();
+    for pixel in framebuffer.buffer.iter_mut() {
+        let [_, r, g, b] = pixel.to_be_bytes();
+        *pixel = u32::from_be_bytes([0, red[r as usize], green[g as usize], blue[b as usize]]);
+    }
+}
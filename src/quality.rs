@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+// Escalones de calidad, del mejor al más barato; cada uno agrega un recorte al anterior
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QualityTier {
+    #[default]
+    Full,
+    NoPostEffects,
+    HalfResolution,
+    QuarterResolution,
+}
+
+const TIERS: [QualityTier; 4] = [QualityTier::Full, QualityTier::NoPostEffects, QualityTier::HalfResolution, QualityTier::QuarterResolution];
+
+impl QualityTier {
+    // Costo aproximado de un frame respecto a la calidad completa; sirve para estimar si el escalón
+    // de arriba entra en el presupuesto antes de subir
+    fn relative_cost(self) -> f32 {
+        match self {
+            QualityTier::Full => 1.0,
+            QualityTier::NoPostEffects => 0.8,
+            QualityTier::HalfResolution => 0.3,
+            QualityTier::QuarterResolution => 0.12,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            QualityTier::Full => "calidad completa",
+            QualityTier::NoPostEffects => "sin efectos",
+            QualityTier::HalfResolution => "media resolución",
+            QualityTier::QuarterResolution => "un cuarto de resolución",
+        }
+    }
+}
+
+// Lo que el bucle principal aplica en cada frame; en la sesión se guarda solo el escalón
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "QualityTier", into = "QualityTier")]
+pub struct QualitySettings {
+    pub tier: QualityTier,
+    // Oclusión ambiental y lens flare
+    pub post_effects: bool,
+    // La escena se dibuja a 1/N de la resolución y se agranda
+    pub resolution_divisor: usize,
+}
+
+impl From<QualitySettings> for QualityTier {
+    fn from(settings: QualitySettings) -> Self {
+        settings.tier
+    }
+}
+
+impl From<QualityTier> for QualitySettings {
+    fn from(tier: QualityTier) -> Self {
+        QualitySettings::of(tier)
+    }
+}
+
+impl Default for QualitySettings {
+    fn default() -> Self {
+        QualitySettings::of(QualityTier::Full)
+    }
+}
+
+impl QualitySettings {
+    fn of(tier: QualityTier) -> Self {
+        QualitySettings {
+            tier,
+            post_effects: tier == QualityTier::Full,
+            resolution_divisor: match tier {
+                QualityTier::Full | QualityTier::NoPostEffects => 1,
+                QualityTier::HalfResolution => 2,
+                QualityTier::QuarterResolution => 4,
+            },
+        }
+    }
+}
+
+pub const DEFAULT_TARGET_FPS: u32 = 30;
+// Milisegundos de frames medidos antes de decidir; con frames muy lentos alcanzan unos pocos, así se
+// baja en menos de un segundo
+const MEASURE_MS: f32 = 500.0;
+// Para subir se mide más tiempo, y solo si el escalón de arriba queda holgado dentro del presupuesto:
+// así no se sube y se baja todo el tiempo
+const UPGRADE_MEASURE_MS: f32 = 2000.0;
+const UPGRADE_MARGIN: f32 = 0.75;
+const MIN_SAMPLES: usize = 3;
+
+// Baja la calidad cuando el promedio de los últimos frames pasa el presupuesto del FPS objetivo y la
+// vuelve a subir cuando sobra tiempo
+pub struct QualityController {
+    budget_ms: f32,
+    tier: usize,
+    samples: VecDeque<f32>,
+    samples_ms: f32,
+    // Sin controlador (--fixed-quality o una repetición): siempre la calidad completa
+    pinned: bool,
+}
+
+impl QualityController {
+    pub fn new(target_fps: u32, pinned: bool) -> Self {
+        QualityController { budget_ms: 1000.0 / target_fps as f32, tier: 0, samples: VecDeque::new(), samples_ms: 0.0, pinned }
+    }
+
+    // Empieza en `tier` (el de la sesión anterior) en lugar de la calidad completa; fija no cambia
+    pub fn starting_at(mut self, tier: QualityTier) -> Self {
+        if !self.pinned {
+            self.tier = TIERS.iter().position(|&other| other == tier).unwrap_or(0);
+        }
+        self
+    }
+
+    pub fn settings(&self) -> QualitySettings {
+        QualitySettings::of(TIERS[self.tier])
+    }
+
+    // Agrega el tiempo del último frame dibujado y devuelve la calidad para el siguiente. Después de
+    // cada cambio se empieza a medir de nuevo, que hace de espera antes del próximo
+    pub fn update(&mut self, frame_ms: f32) -> QualitySettings {
+        if self.pinned {
+            return self.settings();
+        }
+        // Solo se guardan los frames que hacen falta para la medición más larga
+        self.samples.push_back(frame_ms);
+        self.samples_ms += frame_ms;
+        while let Some(&oldest) = self.samples.front().filter(|&&oldest| self.samples_ms - oldest >= UPGRADE_MEASURE_MS) {
+            self.samples.pop_front();
+            self.samples_ms -= oldest;
+        }
+
+        if self.average_over(MEASURE_MS).is_some_and(|average| average > self.budget_ms) && self.tier + 1 < TIERS.len() {
+            self.tier += 1;
+            self.restart();
+        } else if let Some(average) = self.average_over(UPGRADE_MEASURE_MS).filter(|_| self.tier > 0) {
+            let upgraded = average * TIERS[self.tier - 1].relative_cost() / TIERS[self.tier].relative_cost();
+            if upgraded < self.budget_ms * UPGRADE_MARGIN {
+                self.tier -= 1;
+                self.restart();
+            }
+        }
+        self.settings()
+    }
+
+    fn restart(&mut self) {
+        self.samples.clear();
+        self.samples_ms = 0.0;
+    }
+
+    // Promedio de los frames más recientes que suman al menos `span_ms`, sin el peor, así un pico suelto
+    // (cargar una malla, cambiar de escena) no baja la calidad. None si todavía no hay tantos
+    fn average_over(&self, span_ms: f32) -> Option<f32> {
+        let mut total = 0.0;
+        let mut worst = 0.0_f32;
+        for (count, &sample) in self.samples.iter().rev().enumerate() {
+            total += sample;
+            worst = worst.max(sample);
+            if total >= span_ms && count + 1 >= MIN_SAMPLES {
+                return Some((total - worst) / count as f32);
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,116 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use lab4_shaders::camera::Camera;
+use lab4_shaders::camera_path::CameraPath;
+use lab4_shaders::export::{dump_raw, save_png};
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::legend::draw_thermal_legend;
+use lab4_shaders::lut::Lut;
+use lab4_shaders::pipeline::build_uniforms;
+use lab4_shaders::post::{self, Accumulator, OUTLINE_COLOR, OUTLINE_THRESHOLD};
+use lab4_shaders::scene::{render_scene, scene_depth_range, start_camera, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+use crate::cli::RecordOptions;
+
+// Unidades de `time` por segundo (la ventana avanza 1 por frame a ~60 FPS)
+const TICKS_PER_SECOND: u32 = 60;
+// Velocidad de la órbita automática en radianes por segundo
+const ORBIT_SPEED: f32 = 0.2;
+
+pub fn record_frames(options: &RecordOptions, render_options: RenderOptions, scene: &SceneConfig, framebuffer: &mut Framebuffer, meshes: &SceneMeshes, lut: Option<&Lut>, camera_path: Option<&CameraPath>) -> io::Result<()> {
+    std::fs::create_dir_all(&options.out_dir)?;
+
+    // Ctrl-C termina el frame actual y luego sale
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    if let Err(err) = ctrlc::set_handler(move || flag.store(true, Ordering::SeqCst)) {
+        eprintln!("No se pudo instalar el manejador de Ctrl-C: {}", err);
+    }
+
+    let render_options = RenderOptions { debug_mode: options.debug_mode, ..render_options };
+    let mut state = SceneState::new(scene, render_options.seed, meshes);
+    let progress_step = (options.frames / 10).max(1);
+
+    let start_camera = start_camera(scene, meshes);
+    let mut accumulator = Accumulator::new();
+    // Con --camera-path el tiempo empieza en la primera pose y la cámara sigue el recorrido
+    let start_time = camera_path.map_or(0.0, CameraPath::start);
+
+    for frame in 0..options.frames {
+        // El tiempo depende solo del número de frame, no del reloj real
+        let time = start_time + (frame as u64 * TICKS_PER_SECOND as u64 / options.fps as u64) as f32;
+
+        let mut camera = Camera::new(start_camera.eye, start_camera.center, start_camera.up);
+        camera.projection = start_camera.projection;
+        if let Some(path) = camera_path {
+            path.apply(&mut camera, time);
+        } else if options.orbit {
+            camera.orbit(ORBIT_SPEED * frame as f32 / options.fps as f32, 0.0);
+        }
+
+        state.update(time);
+        let depth_range = scene_depth_range(&state.bodies_seen_from(scene, time, camera.eye, meshes), meshes, &camera);
+        let uniforms = build_uniforms(&camera, time, framebuffer.width, framebuffer.height, depth_range);
+
+        // Con --accumulate el frame es el promedio de varias imágenes en el mismo instante
+        accumulator.reset();
+        for sample in 0..options.accumulate {
+            let mut sample_uniforms = uniforms.clone();
+            if options.accumulate > 1 {
+                post::jitter_uniforms(&mut sample_uniforms, post::subpixel_jitter(sample));
+            }
+            render_scene(framebuffer, options.scene, scene, &sample_uniforms, meshes, &mut state, &render_options);
+            if render_options.toon {
+                post::outline(framebuffer, &sample_uniforms.projection_matrix, OUTLINE_THRESHOLD, OUTLINE_COLOR);
+            }
+            if render_options.ambient_occlusion {
+                post::ambient_occlusion(framebuffer, &sample_uniforms.projection_matrix, &scene.ambient_occlusion);
+            }
+            if options.accumulate > 1 {
+                accumulator.average(framebuffer);
+            }
+        }
+
+        // La vista infrarroja se guarda con su escala y sin corregir los colores
+        if render_options.thermal {
+            draw_thermal_legend(framebuffer, &scene.thermal_gradient);
+        } else {
+            post::color_grade(framebuffer, &scene.grade);
+            if let Some(lut) = lut {
+                post::apply_lut(framebuffer, lut);
+            }
+        }
+
+        let path = options.out_dir.join(format!("frame_{:06}.png", frame + 1));
+        save_png(framebuffer, &path)?;
+        if let Some(dump_path) = &options.dump_raw {
+            dump_raw(framebuffer, &numbered_dump_path(dump_path, frame + 1, options.frames))?;
+        }
+
+        if (frame + 1) % progress_step == 0 || frame + 1 == options.frames {
+            println!("{}/{} frames ({}%)", frame + 1, options.frames, (frame + 1) * 100 / options.frames);
+        }
+
+        if interrupted.load(Ordering::SeqCst) {
+            println!("Grabación interrumpida después de {} frames", frame + 1);
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// Con un solo frame se usa el nombre tal cual; con varios se agrega el número antes de la extensión
+// (salida.fbdump -> salida_000001.fbdump)
+fn numbered_dump_path(path: &Path, frame: u32, frames: u32) -> PathBuf {
+    if frames == 1 {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(extension) => format!("{}_{:06}.{}", stem, frame, extension.to_string_lossy()),
+        None => format!("{}_{:06}", stem, frame),
+    };
+    path.with_file_name(name)
+}
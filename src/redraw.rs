@@ -0,0 +1,57 @@
+// Decide cuánto hay que volver a hacer en cada frame para no ocupar un núcleo redibujando la misma
+// imagen con la cámara quieta y la animación en pausa
+
+// Lo que hace falta en un frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Redraw {
+    // Nada cambió: se deja la imagen que ya está en la ventana
+    Nothing,
+    // Solo cambió lo que va encima (ayuda, lupa): se restaura la zona que tapaban con la última imagen
+    // completa y se vuelven a dibujar. La escena y el post-proceso (corrección de color, dithering,
+    // escalado) no se vuelven a hacer; cuando cambia la escena se hacen enteros, no por zonas
+    OverlaysOnly,
+    // Escena y efectos completos
+    Full,
+}
+
+// Frames completos que se siguen dibujando después del último cambio: el desenfoque de movimiento
+// tarda unos frames en alcanzar la imagen quieta y el cuerpo bajo el mouse se resalta un frame después
+pub const SETTLE_FRAMES: u32 = 30;
+
+#[derive(Default)]
+pub struct RedrawTracker {
+    scene: Option<u64>,
+    overlays: Option<u64>,
+    unchanged_frames: u32,
+}
+
+impl RedrawTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `scene` resume todo lo que cambia la imagen debajo de las capas de encima y `overlays` lo que
+    // cambia esas capas. `settling` es para lo que cambia la imagen sin que cambie nada de eso (una
+    // acumulación que todavía no termina)
+    pub fn update(&mut self, scene: u64, overlays: u64, settling: bool) -> Redraw {
+        let overlays_changed = self.overlays.replace(overlays) != Some(overlays);
+        if self.scene.replace(scene) != Some(scene) || settling {
+            self.unchanged_frames = 0;
+            return Redraw::Full;
+        }
+        if self.unchanged_frames < SETTLE_FRAMES {
+            self.unchanged_frames += 1;
+            return Redraw::Full;
+        }
+        if overlays_changed {
+            Redraw::OverlaysOnly
+        } else {
+            Redraw::Nothing
+        }
+    }
+
+    // El próximo frame se dibuja completo pase lo que pase
+    pub fn invalidate(&mut self) {
+        self.scene = None;
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use crate::color::Color;
+use crate::texture::Texture;
+
+// Perfil incluido, de Saturno (ver assets/rings/saturn.csv)
+const SATURN_CSV: &str = include_str!("../assets/rings/saturn.csv");
+// Opacidad máxima que se lee de una tira PNG: con 1 la profundidad óptica sería infinita
+const MAX_STRIP_OPACITY: f32 = 0.99;
+// Muestras que se promedian en sample_filtered
+const FILTER_TAPS: usize = 8;
+
+// Un punto del perfil: fracción del ancho del anillo (0 = borde interior, 1 = exterior), profundidad
+// óptica normal y color de las partículas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RingSample {
+    pub radius: f32,
+    pub optical_depth: f32,
+    pub color: Color,
+}
+
+impl RingSample {
+    // Fracción de la luz que tapa el anillo en ese radio
+    pub fn opacity(&self) -> f32 {
+        1.0 - self.transmittance()
+    }
+
+    pub fn transmittance(&self) -> f32 {
+        (-self.optical_depth).exp()
+    }
+}
+
+// Perfil radial del anillo: el color y la opacidad de la malla y la sombra sobre el planeta salen de la
+// misma profundidad óptica. Entre dos muestras se interpola y fuera del rango queda la más cercana; dos
+// muestras con el mismo radio hacen un borde de golpe (los de los huecos)
+#[derive(Clone, Debug, PartialEq)]
+pub struct RingProfile {
+    samples: Vec<RingSample>,
+}
+
+impl RingProfile {
+    pub fn new(samples: Vec<RingSample>) -> Result<Self, String> {
+        if samples.is_empty() {
+            return Err("el perfil no tiene muestras".to_string());
+        }
+        for (index, sample) in samples.iter().enumerate() {
+            if !(0.0..=1.0).contains(&sample.radius) {
+                return Err(format!("muestra {}: el radio {} está fuera del rango de 0 a 1", index + 1, sample.radius));
+            }
+            if !sample.optical_depth.is_finite() || sample.optical_depth < 0.0 {
+                return Err(format!("muestra {}: la profundidad óptica {} no puede ser negativa", index + 1, sample.optical_depth));
+            }
+            if index > 0 && sample.radius < samples[index - 1].radius {
+                return Err(format!("muestra {}: los radios tienen que ir de menor a mayor", index + 1));
+            }
+        }
+        Ok(RingProfile { samples })
+    }
+
+    // Saturno con los anillos C, B y A, la división de Cassini y el hueco de Encke
+    pub fn saturn() -> Arc<RingProfile> {
+        static SATURN: OnceLock<Arc<RingProfile>> = OnceLock::new();
+        SATURN.get_or_init(|| Arc::new(RingProfile::from_csv(SATURN_CSV).expect("el perfil de Saturno incluido no es válido"))).clone()
+    }
+
+    // Según la extensión: .png como tira o cualquier otra como CSV
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("png") => RingProfile::from_strip(&Texture::load(path)?),
+            _ => RingProfile::from_csv(&std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?),
+        }
+    }
+
+    // Filas `radio, profundidad óptica, rojo, verde, azul` con el radio de 0 a 1 y el color de 0 a 255;
+    // las líneas vacías, las que empiezan con # y una primera fila de títulos se saltan
+    pub fn from_csv(source: &str) -> Result<Self, String> {
+        let mut samples = Vec::new();
+        let lines = source.lines().enumerate()
+            .map(|(index, text)| (index + 1, text.trim()))
+            .filter(|(_, text)| !text.is_empty() && !text.starts_with('#'));
+        for (position, (line, text)) in lines.enumerate() {
+            let fields: Vec<&str> = text.split(',').map(str::trim).collect();
+            let is_header = position == 0 && fields.first().is_some_and(|field| field.parse::<f32>().is_err());
+            if is_header {
+                continue;
+            }
+            let values = fields.iter()
+                .map(|value| value.parse::<f32>().map_err(|_| format!("línea {}: \"{}\" no es un número", line, value)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let [radius, optical_depth, r, g, b] = values[..] else {
+                return Err(format!("línea {}: se esperaban 5 valores (radio, profundidad óptica, rojo, verde y azul) y hay {}", line, values.len()));
+            };
+            if let Some(value) = [r, g, b].into_iter().find(|value| !(0.0..=255.0).contains(value)) {
+                return Err(format!("línea {}: {} está fuera del rango de 0 a 255", line, value));
+            }
+            samples.push(RingSample { radius, optical_depth, color: Color { r, g, b } });
+        }
+        RingProfile::new(samples).map_err(|err| format!("perfil del anillo: {}", err))
+    }
+
+    // Tira PNG de dos filas: la de arriba es el color y la de abajo, en gris, la opacidad. La columna x
+    // queda en el radio de su centro, (x + 0.5) / ancho
+    pub fn from_strip(texture: &Texture) -> Result<Self, String> {
+        if texture.height < 2 {
+            return Err(format!("la tira del anillo necesita 2 filas (color y opacidad) y tiene {}", texture.height));
+        }
+        let samples = (0..texture.width).map(|x| {
+            let opacity = texture.texels[texture.width + x];
+            let opacity = ((opacity.r + opacity.g + opacity.b) / (3.0 * 255.0)).min(MAX_STRIP_OPACITY);
+            RingSample {
+                radius: (x as f32 + 0.5) / texture.width as f32,
+                optical_depth: -(1.0 - opacity).ln(),
+                color: texture.texels[x],
+            }
+        }).collect();
+        RingProfile::new(samples)
+    }
+
+    pub fn samples(&self) -> &[RingSample] {
+        &self.samples
+    }
+
+    // Muestra en la fracción `band` del ancho, interpolada entre las dos más cercanas
+    pub fn sample(&self, band: f32) -> RingSample {
+        let first = self.samples[0];
+        let last = self.samples[self.samples.len() - 1];
+        if band.is_nan() || band <= first.radius {
+            return RingSample { radius: band, ..first };
+        }
+        if band >= last.radius {
+            return RingSample { radius: band, ..last };
+        }
+        // La primera con radio mayor: en un borde de golpe se toma el lado de afuera
+        let upper = self.samples.partition_point(|sample| sample.radius <= band);
+        let (low, high) = (self.samples[upper - 1], self.samples[upper]);
+        let t = (band - low.radius) / (high.radius - low.radius);
+        RingSample {
+            radius: band,
+            optical_depth: low.optical_depth + (high.optical_depth - low.optical_depth) * t,
+            color: low.color.lerp(&high.color, t),
+        }
+    }
+
+    // Promedio del perfil en un pixel que cubre `width` del ancho del anillo (ver Fragments::fwidth): de
+    // lejos los huecos finos se ven como una baja de opacidad en lugar de parpadear. Se promedia la luz
+    // que pasa, no la profundidad óptica, y el color pesa según lo que tapa cada muestra
+    pub fn sample_filtered(&self, band: f32, width: f32) -> RingSample {
+        if width <= 0.0 || !width.is_finite() {
+            return self.sample(band);
+        }
+        let taps: [RingSample; FILTER_TAPS] = std::array::from_fn(|tap| {
+            self.sample(band + width * ((tap as f32 + 0.5) / FILTER_TAPS as f32 - 0.5))
+        });
+        let transmittance = taps.iter().map(RingSample::transmittance).sum::<f32>() / FILTER_TAPS as f32;
+        let coverage: f32 = taps.iter().map(RingSample::opacity).sum();
+        let color = if coverage > 0.0 {
+            taps.iter().fold(Color::BLACK, |color, tap| color + tap.color * (tap.opacity() / coverage))
+        } else {
+            self.sample(band).color
+        };
+        RingSample { radius: band, optical_depth: -transmittance.max(f32::MIN_POSITIVE).ln(), color }
+    }
+}
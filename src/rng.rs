@@ -0,0 +1,39 @@
+// Aleatoriedad determinista basada en hashes: la misma semilla siempre da el mismo resultado
+
+fn mix(mut h: u32) -> u32 {
+    // Finalizador de murmur3
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+pub fn hash_u32(seed: u32, value: u32) -> u32 {
+    mix(seed.wrapping_mul(0x9e37_79b9) ^ mix(value))
+}
+
+fn to_unit(h: u32) -> f32 {
+    (h >> 8) as f32 / (1u32 << 24) as f32
+}
+
+// Combina la semilla global con la de un cuerpo
+pub fn combine(seed: u32, other: u32) -> u32 {
+    hash_u32(seed, other)
+}
+
+// Valor en [0, 1) para un índice entero
+pub fn hash1(seed: u32, index: u32) -> f32 {
+    to_unit(hash_u32(seed, index))
+}
+
+// Valor en [0, 1) para un punto 2D
+pub fn hash2(seed: u32, x: f32, y: f32) -> f32 {
+    to_unit(hash_u32(hash_u32(seed, x.to_bits()), y.to_bits()))
+}
+
+// Valor en [0, 1) para un punto 3D
+pub fn hash3(seed: u32, x: f32, y: f32, z: f32) -> f32 {
+    to_unit(hash_u32(hash_u32(hash_u32(seed, x.to_bits()), y.to_bits()), z.to_bits()))
+}
@@ -0,0 +1,1359 @@
+use nalgebra_glm::{Vec2, Vec3, Vec4, Mat4, Quat, quat_angle_axis};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use crate::anchors::SurfaceAnchor;
+use crate::belt::{Asteroid, BeltConfig};
+use crate::framebuffer::{BlendMode, Framebuffer};
+use crate::geometry::{BoundingSphere, Frustum, Ray};
+use crate::gravity::{Simulation, GRAVITY};
+use crate::impacts::MoonState;
+use crate::lod::{self, LodState};
+use crate::mesh::{self, DisplaceParams, MeshBounds};
+use crate::obj_loader::Obj;
+use crate::orbit::{orbit_position, Orbit};
+use crate::post::{AmbientOcclusion, ColorGrade};
+use crate::camera::{Camera, Keyframe};
+use crate::particles::{CometTail, ParticleEmitter, ParticleRing};
+use crate::pipeline::{create_model_matrix, create_view_matrix, default_lights, default_threads, draw_order, render, render_axis, render_point, render_points, render_glow, render_ring_particles, render_sky, render_starfield, render_trail, unproject, FogParams, FragmentBudget, Light, LightSource, Planetshine, RenderScratch, RingShadow, RenderStats, Rotation, BodyUniforms, FrameUniforms, VertexAnimParams, DEFAULT_DEPTH_RANGE, DEFAULT_FRAGMENT_BUDGET, DEFAULT_TRIANGLE_FRAGMENT_CAP, FOV};
+use crate::color::{Color, INFERNO};
+use crate::ring_profile::RingProfile;
+use crate::rng;
+use crate::shaders::{self, FragmentShader, SceneShader, ShaderType};
+use crate::texture::{AmbientCube, Skybox};
+use crate::vertex::Vertex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshKind {
+    Sphere,
+    Ring,
+    Model,
+}
+
+// Cuerpo ya ubicado en un instante; se arma a partir de un BodyConfig
+pub struct Body {
+    pub name: String,
+    pub shader: SceneShader,
+    pub mesh: MeshKind,
+    pub translation: Vec3,
+    pub scale: f32,
+    pub axis_scale: Vec3,
+    pub orientation: Quat,
+    pub tilt: f32,
+    pub lod: bool,
+    pub seed: u32,
+    pub emitters: Vec<ParticleEmitter>,
+    pub apparent_size: Option<f32>,
+    pub label: Option<String>,
+    pub displacement: Option<DisplaceParams>,
+    pub vertex_animation: Option<VertexAnimParams>,
+    pub tail: Option<CometTail>,
+    pub two_sided: bool,
+    pub ring_profile: Option<Arc<RingProfile>>,
+}
+
+impl Body {
+    // La inclinación va después de la rotación propia: el cuerpo gira alrededor de su eje ya inclinado,
+    // y el ecuador de los patrones (que usan la posición en el objeto) se inclina con él
+    pub fn model_matrix(&self) -> Mat4 {
+        let tilt = create_model_matrix(self.translation, Vec3::repeat(1.0), Vec3::new(0.0, 0.0, self.tilt));
+        tilt * create_model_matrix(Vec3::zeros(), self.axis_scale * self.scale, self.orientation)
+    }
+
+    // Esfera que envuelve al cuerpo en coordenadas de mundo, con las montañas más altas del relieve y lo
+    // más que sube la pulsación. Es la de la malla transformada por model_matrix, pero armada con la
+    // traslación y la escala (las rotaciones no la cambian) para que el redondeo del producto de matrices
+    // no mueva el encuadre
+    pub fn bounding_sphere(&self, meshes: &SceneMeshes) -> BoundingSphere {
+        let relief = 1.0 + self.displacement.map_or(0.0, |displacement| displacement.amplitude)
+            + self.vertex_animation.map_or(0.0, |animation| animation.amplitude);
+        BoundingSphere::new(self.translation, meshes.radius(self.mesh) * self.scale * self.axis_scale.max() * relief)
+    }
+
+    pub fn bounding_radius(&self, meshes: &SceneMeshes) -> f32 {
+        self.bounding_sphere(meshes).radius
+    }
+
+    // Con tamaño aparente la escala se ajusta a la distancia desde `eye` para que siempre ocupe el mismo ángulo
+    pub fn seen_from(mut self, eye: Vec3, meshes: &SceneMeshes) -> Self {
+        if let Some(angle) = self.apparent_size {
+            let distance = (self.translation - eye).magnitude();
+            self.scale = distance * (angle / 2.0).tan() / meshes.radius(self.mesh);
+        }
+        self
+    }
+}
+
+pub struct SceneMeshes {
+    // Niveles de detalle de la esfera, del más fino al más simple
+    pub sphere_lods: Vec<Vec<Vertex>>,
+    pub ring: Vec<Vertex>,
+    // Anillo generado con más o menos segmentos (mesh::RING_SEGMENT_TIERS) para --adaptive-ring
+    pub ring_tiers: Vec<Vec<Vertex>>,
+    // Modelo cargado con --model
+    pub model: Option<Vec<Vertex>>,
+    // Roca que comparten todos los asteroides del cinturón (siempre generada)
+    pub rock: Vec<Vertex>,
+    // Envolventes de cada malla, calculadas una vez al guardarla
+    sphere_bounds: MeshBounds,
+    ring_bounds: MeshBounds,
+    model_bounds: MeshBounds,
+    rock_bounds: MeshBounds,
+}
+
+impl SceneMeshes {
+    pub fn new(sphere_lods: Vec<Vec<Vertex>>, ring: Vec<Vertex>) -> Self {
+        let sphere_bounds = MeshBounds::of(&sphere_lods[0]);
+        let ring_bounds = MeshBounds::of(&ring);
+        let rock = mesh::generate_rock(mesh::ROCK_SEED);
+        let rock_bounds = MeshBounds::of(&rock);
+        let ring_tiers = mesh::generate_ring_tiers(mesh::RING_INNER_RADIUS, mesh::RING_OUTER_RADIUS);
+        SceneMeshes { sphere_lods, ring, ring_tiers, model: None, rock, sphere_bounds, ring_bounds, model_bounds: MeshBounds::of(&[]), rock_bounds }
+    }
+
+    // Reemplaza los niveles de la esfera (por ejemplo los generados mientras se leen los OBJ)
+    pub fn set_sphere_lods(&mut self, sphere_lods: Vec<Vec<Vertex>>) {
+        if let Some(finest) = sphere_lods.first() {
+            self.sphere_bounds = MeshBounds::of(finest);
+            self.sphere_lods = sphere_lods;
+        }
+    }
+
+    pub fn add_sphere_lod(&mut self, lod: Vec<Vertex>) {
+        self.sphere_lods.push(lod);
+    }
+
+    pub fn set_ring(&mut self, ring: Vec<Vertex>) {
+        self.ring_bounds = MeshBounds::of(&ring);
+        self.ring = ring;
+    }
+
+    pub fn set_model(&mut self, model: Vec<Vertex>) {
+        self.model_bounds = MeshBounds::of(&model);
+        self.model = Some(model);
+    }
+
+    pub fn has_model(&self) -> bool {
+        self.model.is_some()
+    }
+
+    // Si el modelo trae coordenadas de textura; el cargador deja en cero las que faltan
+    pub fn model_has_uvs(&self) -> bool {
+        self.model.as_ref().is_some_and(|model| model.iter().any(|vertex| vertex.tex_coords != Vec2::zeros()))
+    }
+
+    // Niveles de detalle que tiene esa malla
+    pub fn tiers(&self, kind: MeshKind) -> usize {
+        match kind {
+            MeshKind::Sphere => self.sphere_lods.len(),
+            MeshKind::Ring | MeshKind::Model => 1,
+        }
+    }
+
+    pub fn get(&self, kind: MeshKind, tier: usize) -> &[Vertex] {
+        match kind {
+            MeshKind::Sphere => &self.sphere_lods[tier.min(self.sphere_lods.len() - 1)],
+            MeshKind::Ring => &self.ring,
+            MeshKind::Model => self.model.as_deref().unwrap_or(&self.sphere_lods[0]),
+        }
+    }
+
+    pub fn adaptive_ring(&self, tier: usize) -> &[Vertex] {
+        &self.ring_tiers[tier.min(self.ring_tiers.len() - 1)]
+    }
+
+    pub fn bounds(&self, kind: MeshKind) -> &MeshBounds {
+        match kind {
+            MeshKind::Sphere => &self.sphere_bounds,
+            MeshKind::Ring => &self.ring_bounds,
+            MeshKind::Model if self.model.is_some() => &self.model_bounds,
+            MeshKind::Model => &self.sphere_bounds,
+        }
+    }
+
+    pub fn radius(&self, kind: MeshKind) -> f32 {
+        self.bounds(kind).sphere.radius
+    }
+
+    pub fn rock_bounds(&self) -> &MeshBounds {
+        &self.rock_bounds
+    }
+
+    pub fn rock_radius(&self) -> f32 {
+        self.rock_bounds.sphere.radius
+    }
+}
+
+
+// Descripción de un cuerpo con su movimiento
+#[derive(Clone, Debug, PartialEq)]
+pub struct BodyConfig {
+    pub name: String,
+    // Uno de los shaders incluidos o uno propio (SceneShader::custom)
+    pub shader: SceneShader,
+    pub mesh: MeshKind,
+    // Posición fija; se ignora si el cuerpo tiene órbita
+    pub translation: Vec3,
+    pub orbit: Option<Orbit>,
+    pub scale: f32,
+    // Escala en cada eje del objeto, multiplicada por `scale` (por ejemplo el achatamiento de los polos)
+    pub axis_scale: Vec3,
+    // Ángulos de Euler o un cuaternión
+    pub rotation: Rotation,
+    // Velocidad de giro propio en radianes por tick, en los ejes del cuerpo ya rotado por `rotation`:
+    // el cuerpo gira alrededor de ese eje sin importar cómo quedó volteado
+    pub spin: Vec3,
+    // Inclinación del eje de giro (el y del objeto) alrededor del eje z del mundo, en radianes
+    pub tilt: f32,
+    pub lod: bool,
+    pub seed: u32,
+    pub emitters: Vec<ParticleEmitter>,
+    // Diámetro aparente en radianes: el cuerpo se ve del mismo tamaño a cualquier distancia y no
+    // cuenta para encuadrar la escena (`scale` se ignora)
+    pub apparent_size: Option<f32>,
+    // Nombre que se dibuja junto al cuerpo (tecla E)
+    pub label: Option<String>,
+    // Relieve de la superficie: la malla se deforma con ruido al cargar la escena
+    pub displacement: Option<DisplaceParams>,
+    // Pulsación de la superficie que se calcula en cada frame (ver shaders::animate_vertex)
+    pub vertex_animation: Option<VertexAnimParams>,
+    // Masa y velocidad inicial (unidades por tick) en las escenas con física; sin masa el cuerpo no atrae
+    // a los demás
+    pub mass: f32,
+    pub velocity: Vec3,
+    // Cola de cometa que apunta en contra del sol (ver render_tails)
+    pub tail: Option<CometTail>,
+    // Se ve de los dos lados (ver BodyUniforms::two_sided); los anillos lo son por defecto
+    pub two_sided: bool,
+    // Perfil radial de color y profundidad óptica de un anillo; sin perfil, las franjas procedurales
+    // (o el de Saturno con RenderOptions::saturn_rings)
+    pub ring_profile: Option<Arc<RingProfile>>,
+}
+
+impl BodyConfig {
+    pub fn new(name: &str, shader: impl Into<SceneShader>, mesh: MeshKind, translation: Vec3, scale: f32) -> Self {
+        BodyConfig {
+            name: name.to_string(),
+            shader: shader.into(),
+            mesh,
+            translation,
+            orbit: None,
+            scale,
+            axis_scale: Vec3::new(1.0, 1.0, 1.0),
+            rotation: Rotation::Euler(Vec3::zeros()),
+            spin: Vec3::new(0.0, 0.0, 0.0),
+            tilt: 0.0,
+            lod: mesh == MeshKind::Sphere,
+            seed: 0,
+            emitters: Vec::new(),
+            apparent_size: None,
+            label: None,
+            displacement: None,
+            vertex_animation: None,
+            mass: 0.0,
+            velocity: Vec3::new(0.0, 0.0, 0.0),
+            tail: None,
+            two_sided: mesh == MeshKind::Ring,
+            ring_profile: None,
+        }
+    }
+
+    fn orbiting(name: &str, shader: ShaderType, mesh: MeshKind, orbit: Orbit, scale: f32) -> Self {
+        BodyConfig { orbit: Some(orbit), ..BodyConfig::new(name, shader, mesh, Vec3::new(0.0, 0.0, 0.0), scale) }
+    }
+
+    fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    // Achatado en los polos: el eje y queda en `polar` del radio del ecuador
+    fn oblate(mut self, polar: f32) -> Self {
+        self.axis_scale = Vec3::new(1.0, polar, 1.0);
+        self
+    }
+
+    fn tilted(mut self, tilt: f32) -> Self {
+        self.tilt = tilt;
+        self
+    }
+
+    // Etiqueta con el mismo nombre del cuerpo
+    fn labeled(mut self) -> Self {
+        self.label = Some(self.name.clone());
+        self
+    }
+
+    fn displaced(mut self, displacement: DisplaceParams) -> Self {
+        self.displacement = Some(displacement);
+        self
+    }
+
+    fn pulsing(mut self, animation: VertexAnimParams) -> Self {
+        self.vertex_animation = Some(animation);
+        self
+    }
+
+    // Masa y velocidad inicial para las escenas con física
+    fn moving(mut self, mass: f32, velocity: Vec3) -> Self {
+        self.mass = mass;
+        self.velocity = velocity;
+        self
+    }
+
+    fn with_tail(mut self, tail: CometTail) -> Self {
+        self.tail = Some(tail);
+        self
+    }
+
+    fn with_emitters(mut self, emitters: &[ParticleEmitter]) -> Self {
+        self.emitters = emitters.to_vec();
+        self
+    }
+
+    // Disco del sol en el cielo en el lugar de donde viene una luz; las direccionales lo ponen lejos en su dirección
+    fn sun_disc(light: &Light) -> Self {
+        let position = match light.source {
+            LightSource::Directional(direction) => direction * SUN_DISC_DISTANCE,
+            LightSource::Point(position) => position,
+        };
+        let mut sun = BodyConfig::new("Sol", ShaderType::Sun, MeshKind::Sphere, position, 1.0);
+        sun.apparent_size = Some(SUN_DISC_SIZE);
+        sun
+    }
+
+    // Primero `rotation` y después el giro propio acumulado hasta `time`, sobre el eje ya rotado. La
+    // velocidad es constante, así que el giro sale en un solo paso y no se acumula error frame a frame
+    pub fn orientation_at(&self, time: f32) -> Quat {
+        let base = self.rotation.quaternion();
+        let angle = self.spin.magnitude() * time;
+        if angle == 0.0 {
+            return base;
+        }
+        base * quat_angle_axis(angle, &self.spin)
+    }
+
+    // Posición y rotación del cuerpo en el instante `time`
+    pub fn at(&self, time: f32) -> Body {
+        let translation = match self.orbit {
+            Some(orbit) => orbit_position(&orbit, time),
+            None => self.translation,
+        };
+
+        Body {
+            name: self.name.clone(),
+            shader: self.shader.clone(),
+            mesh: self.mesh,
+            translation,
+            scale: self.scale,
+            axis_scale: self.axis_scale,
+            orientation: self.orientation_at(time),
+            tilt: self.tilt,
+            lod: self.lod,
+            seed: self.seed,
+            emitters: self.emitters.clone(),
+            apparent_size: self.apparent_size,
+            label: self.label.clone(),
+            displacement: self.displacement,
+            vertex_animation: self.vertex_animation,
+            tail: self.tail,
+            two_sided: self.two_sided,
+            ring_profile: self.ring_profile.clone(),
+        }
+    }
+
+    // Velocidad en unidades por tick en el instante `time`, por diferencia sobre la órbita; los cuerpos
+    // sin órbita se toman quietos
+    pub fn velocity_at(&self, time: f32) -> Vec3 {
+        match self.orbit {
+            Some(orbit) => orbit_position(&orbit, time + 0.5) - orbit_position(&orbit, time - 0.5),
+            None => Vec3::zeros(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraConfig {
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub up: Vec3,
+    pub orthographic: bool,
+}
+
+impl CameraConfig {
+    // En la ortográfica se ve el mismo alto que vería la perspectiva a la distancia del centro
+    pub fn camera(&self) -> Camera {
+        let mut camera = Camera::new(self.eye, self.center, self.up);
+        if self.orthographic {
+            camera.toggle_projection(FOV);
+        }
+        camera
+    }
+}
+
+// Color de fondo de las escenas incluidas
+pub const BACKGROUND_COLOR: u32 = 0x335555;
+// Fracción del color del fondo que llega como luz ambiental (ver skylight)
+pub const SKYLIGHT_STRENGTH: f32 = 0.2;
+
+// Escena completa: cuerpos, cámara inicial (None = encuadre automático), fondo, luces y corrección de color
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneConfig {
+    pub bodies: Vec<BodyConfig>,
+    pub camera: Option<CameraConfig>,
+    pub background: u32,
+    // Imagen equirectangular que se ve de fondo en lugar del color (y de las estrellas)
+    pub skybox: Option<Arc<Skybox>>,
+    pub lights: Vec<Light>,
+    pub ambient: Color,
+    // Cuánto ilumina el fondo a los cuerpos, como fracción de su color
+    pub skylight: f32,
+    pub fog: Option<FogParams>,
+    pub grade: ColorGrade,
+    // Parámetros de la oclusión ambiental (se activa con RenderOptions::ambient_occlusion)
+    pub ambient_occlusion: AmbientOcclusion,
+    // Recorrido de la cámara en el modo demo; vacío = la escena no entra en la demo
+    pub tour: Vec<Keyframe>,
+    // Degradado de la vista infrarroja (RenderOptions::thermal), de frío a caliente
+    pub thermal_gradient: Vec<(f32, Color)>,
+    // Cinturón de asteroides alrededor del origen
+    pub belt: Option<BeltConfig>,
+    // Los cuerpos se mueven por la gravedad de los demás (gravity::Simulation) en lugar de seguir sus órbitas
+    pub physics: bool,
+    // Estela con las posiciones pasadas de cada cuerpo (solo con física)
+    pub trails: bool,
+    // Marcas pegadas a puntos de la superficie de los cuerpos (ver anchors::draw_anchors)
+    pub anchors: Vec<SurfaceAnchor>,
+}
+
+impl SceneConfig {
+    pub fn new(bodies: Vec<BodyConfig>) -> Self {
+        SceneConfig {
+            bodies,
+            camera: None,
+            background: BACKGROUND_COLOR,
+            skybox: None,
+            lights: default_lights(),
+            ambient: Color::BLACK,
+            skylight: SKYLIGHT_STRENGTH,
+            fog: None,
+            grade: ColorGrade::NEUTRAL,
+            ambient_occlusion: AmbientOcclusion::DEFAULT,
+            tour: Vec::new(),
+            thermal_gradient: INFERNO.to_vec(),
+            belt: None,
+            physics: false,
+            trails: false,
+            anchors: Vec::new(),
+        }
+    }
+
+    // Escenas incluidas en el programa (teclas 1 a 9; la 0 muestra el modelo de --model y --scene-file
+    // reemplaza la 9)
+    pub fn builtin(scene_number: u32) -> Self {
+        let mut scene = SceneConfig { tour: builtin_tour(scene_number), ..Self::builtin_bodies(scene_number) };
+        // En las escenas de un planeta se ve de dónde viene la luz
+        if (2..=7).contains(&scene_number) {
+            let suns: Vec<BodyConfig> = scene.lights.iter().map(BodyConfig::sun_disc).collect();
+            scene.bodies.extend(suns);
+        }
+        scene
+    }
+
+    fn builtin_bodies(scene_number: u32) -> Self {
+        let origin = Vec3::new(0.0, 0.0, 0.0);
+        let sphere = MeshKind::Sphere;
+
+        match scene_number {
+            0 => {
+                let mut model = BodyConfig::new("Modelo", ShaderType::Moon, MeshKind::Model, origin, 1.0);
+                model.lod = false;
+                SceneConfig::new(vec![model])
+            },
+            2 => SceneConfig::new(vec![BodyConfig::new("Tierra", ShaderType::Earth, sphere, origin, 1.0)]),
+            3 => SceneConfig::new(vec![BodyConfig::new("Planeta gaseoso", ShaderType::GasPlanet, sphere, origin, 1.0).oblate(GAS_GIANT_POLAR)]),
+            4 => {
+                // El polo se asoma hacia la cámara para que el anillo no quede de canto
+                let facing = |body: BodyConfig| BodyConfig { rotation: Vec3::new(RINGED_TIP, 0.0, 0.0).into(), ..body.tilted(RINGED_TILT) };
+                SceneConfig::new(vec![
+                    facing(BodyConfig::new("Planeta con anillos", ShaderType::RingPlanet, sphere, origin, 1.0).oblate(GAS_GIANT_POLAR)),
+                    facing(BodyConfig::new("Anillos", ShaderType::Ring, MeshKind::Ring, origin, 0.6)),
+                ])
+            },
+            5 => SceneConfig {
+                // Luz de costado: con la luz detrás de la cámara la luna siempre se vería llena
+                lights: vec![Light::directional(MOON_SCENE_LIGHT, Color::new(255, 255, 255), 1.0)],
+                ..SceneConfig::new(vec![
+                    BodyConfig::new("Planeta rocoso", ShaderType::RockyPlanet, sphere, origin, 1.0).displaced(ROCKY_RELIEF),
+                    BodyConfig::orbiting("Luna", ShaderType::Moon, sphere, Orbit::circular(1.3, 0.01, 0.0), 0.5).with_seed(1).displaced(MOON_RELIEF),
+                ])
+            },
+            6 => {
+                // Gira despacio para que se vea que los géiseres están pegados a la superficie
+                let mut icy = BodyConfig::new("Planeta de hielo", ShaderType::IcyPlanet, sphere, origin, 1.0)
+                    .with_emitters(&ICY_GEYSERS);
+                icy.spin = Vec3::new(0.0, 0.003, 0.0);
+                SceneConfig { grade: ICY_GRADE, ..SceneConfig::new(vec![icy]) }
+            },
+            7 => SceneConfig {
+                grade: VOLCANIC_GRADE,
+                ..SceneConfig::new(vec![BodyConfig::new("Planeta volcanico", ShaderType::VolcanicPlanet, sphere, origin, 1.0)])
+            },
+            8 => {
+                // Sistema solar completo
+                let orbit = Orbit::circular;
+                let ringed = orbit(7.5, 0.0025, 2.1);
+                SceneConfig {
+                    // En el sistema solar la luz sale del sol, así cada planeta se ilumina desde su lado hacia el centro
+                    lights: vec![Light::point(origin, Color::new(255, 236, 200), 1.0)],
+                    ambient: Color::new(10, 14, 24),
+                    // Los planetas del fondo se pierden en una bruma azul oscura
+                    fog: Some(FogParams { color: Color::new(12, 18, 48), start: 32.0, end: 60.0 }),
+                    // Entre el planeta rocoso y el de hielo, como el cinturón entre Marte y Júpiter
+                    belt: Some(BeltConfig { count: 300, inner_radius: 10.6, outer_radius: 11.5, thickness: 0.5, seed: 8 }),
+                    ..SceneConfig::new(vec![
+                        BodyConfig::new("Sol", ShaderType::Sun, sphere, origin, 2.0).pulsing(SUN_PULSE).labeled(),
+                        BodyConfig::orbiting("Tierra", ShaderType::Earth, sphere, orbit(3.0, 0.006, 0.0), 0.5).labeled(),
+                        BodyConfig::orbiting("Planeta gaseoso", ShaderType::GasPlanet, sphere, orbit(5.0, 0.004, 4.0), 1.0).oblate(GAS_GIANT_POLAR).labeled(),
+                        BodyConfig::orbiting("Planeta con anillos", ShaderType::RingPlanet, sphere, ringed, 0.8).oblate(GAS_GIANT_POLAR).tilted(RINGED_TILT).labeled(),
+                        // Los anillos van con la etiqueta de su planeta
+                        BodyConfig::orbiting("Anillos", ShaderType::Ring, MeshKind::Ring, ringed, 0.48).tilted(RINGED_TILT),
+                        BodyConfig::orbiting("Planeta rocoso", ShaderType::RockyPlanet, sphere, orbit(10.0, 0.002, 5.3), 0.5).with_seed(2).displaced(ROCKY_RELIEF).labeled(),
+                        BodyConfig::orbiting("Planeta de hielo", ShaderType::IcyPlanet, sphere, orbit(12.0, 0.0015, 1.0), 0.45).labeled(),
+                        // Órbita excéntrica e inclinada, como la de Plutón: cruza las de los planetas de adentro por encima
+                        BodyConfig::orbiting("Planeta volcanico", ShaderType::VolcanicPlanet, sphere, orbit(14.0, 0.001, 3.3).eccentric(0.3, 0.6).inclined(0.12), 0.4).labeled(),
+                        // Pasa rápido cerca del sol y se aleja despacio hasta más allá del cinturón
+                        BodyConfig::orbiting("Cometa", ShaderType::IcyPlanet, sphere, orbit(10.0, 0.002, 5.6).eccentric(0.7, 2.2).inclined(0.35), 0.12)
+                            .with_seed(3)
+                            .with_tail(COMET_TAIL)
+                            .labeled(),
+                    ])
+                }
+            },
+            9 => sandbox(),
+            _ => SceneConfig::new(vec![BodyConfig::new("Sol", ShaderType::Sun, sphere, origin, 1.0).pulsing(SUN_PULSE)]),
+        }
+    }
+
+    // Cuerpos de la escena en el instante `time`
+    pub fn bodies(&self, time: f32) -> Vec<Body> {
+        self.bodies.iter().map(|body| body.at(time)).collect()
+    }
+
+    // Cuerpos como se ven desde `eye`, con los de tamaño aparente ya escalados
+    pub fn bodies_seen_from(&self, time: f32, eye: Vec3, meshes: &SceneMeshes) -> Vec<Body> {
+        self.bodies.iter().map(|body| body.at(time).seen_from(eye, meshes)).collect()
+    }
+}
+
+// Distancia a la que se pone el sol de una luz direccional y su diámetro aparente (3°)
+const SUN_DISC_DISTANCE: f32 = 100.0;
+const SUN_DISC_SIZE: f32 = 3.0 * std::f32::consts::PI / 180.0;
+
+// Duración del recorrido de cada escena en la demo (segundos)
+const TOUR_DURATION: f32 = 10.0;
+// Ángulo horizontal de la cámara inicial (mirando desde +z)
+const FRONT: f32 = std::f32::consts::FRAC_PI_2;
+
+// Órbita de la cámara alrededor de `center`: `sweep` radianes a una distancia y altura (ángulo) fijas
+fn orbit_tour(center: Vec3, distance: f32, pitch: f32, start: f32, sweep: f32, start_time: f32, end_time: f32) -> Vec<Keyframe> {
+    const STEPS: usize = 4;
+    (0..=STEPS).map(|step| {
+        let t = step as f32 / STEPS as f32;
+        let yaw = start + sweep * t;
+        let direction = Vec3::new(pitch.cos() * yaw.cos(), pitch.sin(), pitch.cos() * yaw.sin());
+        Keyframe { time: start_time + (end_time - start_time) * t, eye: center + direction * distance, center }
+    }).collect()
+}
+
+// Coreografía de la demo para las escenas incluidas
+fn builtin_tour(scene_number: u32) -> Vec<Keyframe> {
+    let origin = Vec3::new(0.0, 0.0, 0.0);
+    match scene_number {
+        1 => orbit_tour(origin, 1.6, 0.15, FRONT, 1.2, 0.0, TOUR_DURATION),
+        2 => orbit_tour(origin, 1.5, 0.35, FRONT, 1.5, 0.0, TOUR_DURATION),
+        3 => orbit_tour(origin, 1.6, -0.1, FRONT, -1.2, 0.0, TOUR_DURATION),
+        4 => {
+            // Se acerca desde lejos y luego rodea los anillos
+            let mut tour = vec![Keyframe { time: 0.0, eye: Vec3::new(0.0, 2.2, 5.5), center: origin }];
+            tour.extend(orbit_tour(origin, 3.2, 0.3, FRONT, 1.0, 3.5, TOUR_DURATION));
+            tour
+        }
+        5 => orbit_tour(origin, 3.2, 0.4, FRONT, 1.2, 0.0, TOUR_DURATION),
+        // Desde abajo, para ver los géiseres del polo sur
+        6 => orbit_tour(origin, 1.6, -0.35, FRONT, 1.4, 0.0, TOUR_DURATION),
+        7 => orbit_tour(origin, 1.5, 0.2, FRONT, -1.2, 0.0, TOUR_DURATION),
+        // Paneo amplio sobre el sistema solar mientras el centro se corre de un lado al otro
+        8 => vec![
+            Keyframe { time: 0.0, eye: Vec3::new(-18.0, 18.0, 34.0), center: Vec3::new(-4.0, 0.0, 0.0) },
+            Keyframe { time: TOUR_DURATION / 2.0, eye: Vec3::new(0.0, 20.0, 36.0), center: origin },
+            Keyframe { time: TOUR_DURATION, eye: Vec3::new(18.0, 18.0, 34.0), center: Vec3::new(4.0, 0.0, 0.0) },
+        ],
+        _ => Vec::new(),
+    }
+}
+
+// Masa del sol del sandbox; los planetas empiezan con la velocidad de una órbita circular a su distancia
+const SANDBOX_SUN_MASS: f32 = 4.0;
+
+// Sandbox de gravedad: dos planetas en órbitas estables (el gaseoso es lo bastante pesado para ir
+// torciendo la órbita de la Tierra) y una roca liviana que pasa rozando el sol y se escapa
+fn sandbox() -> SceneConfig {
+    let sphere = MeshKind::Sphere;
+    let circular_speed = |radius: f32| (GRAVITY as f32 * SANDBOX_SUN_MASS / radius).sqrt();
+    let mut bodies = vec![
+        BodyConfig::new("Sol", ShaderType::Sun, sphere, Vec3::new(0.0, 0.0, 0.0), 1.0).moving(SANDBOX_SUN_MASS, Vec3::zeros()).labeled(),
+        BodyConfig::new("Tierra", ShaderType::Earth, sphere, Vec3::new(3.0, 0.0, 0.0), 0.4).moving(0.01, Vec3::new(0.0, 0.0, -circular_speed(3.0))).labeled(),
+        BodyConfig::new("Planeta gaseoso", ShaderType::GasPlanet, sphere, Vec3::new(-6.0, 0.0, 0.0), 0.8)
+            .oblate(GAS_GIANT_POLAR)
+            .moving(0.08, Vec3::new(0.0, 0.0, circular_speed(6.0)))
+            .labeled(),
+        BodyConfig::new("Roca errante", ShaderType::Moon, sphere, Vec3::new(-7.0, 0.5, 9.0), 0.25).with_seed(3).moving(0.0005, Vec3::new(0.03, -0.0006, -0.022)).labeled(),
+    ];
+    // El sol compensa el impulso de los demás para que el sistema no se vaya de a poco hacia un lado
+    let momentum = bodies.iter().fold(Vec3::zeros(), |sum, body| sum + body.velocity * body.mass);
+    bodies[0].velocity = -momentum / SANDBOX_SUN_MASS;
+
+    SceneConfig {
+        lights: vec![Light::point(Vec3::new(0.0, 0.0, 0.0), Color::new(255, 236, 200), 1.0)],
+        ambient: Color::new(10, 14, 24),
+        // Desde arriba y de costado, para ver las estelas abiertas y no de canto
+        camera: Some(CameraConfig { eye: Vec3::new(0.0, 13.0, 15.0), center: Vec3::new(0.0, 0.0, 0.0), up: Vec3::new(0.0, 1.0, 0.0), orthographic: false }),
+        physics: true,
+        trails: true,
+        ..SceneConfig::new(bodies)
+    }
+}
+
+// Radio polar de los planetas gaseosos respecto al ecuatorial (giran rápido y se achatan)
+const GAS_GIANT_POLAR: f32 = 0.93;
+// Inclinación del planeta con anillos y sus anillos, la de Saturno (26.7°)
+const RINGED_TILT: f32 = 0.466;
+const RINGED_TIP: f32 = 0.35;
+
+// Montañas del planeta rocoso y relieve más suave de la luna (los cráteres los dibuja el shader)
+const ROCKY_RELIEF: DisplaceParams = DisplaceParams { amplitude: 0.06, frequency: 3.0, octaves: 5 };
+const MOON_RELIEF: DisplaceParams = DisplaceParams { amplitude: 0.03, frequency: 2.5, octaves: 3 };
+// El sol respira y se ondula despacio: unas pocas lomas que suben o bajan hasta 3% del radio y cambian
+// de forma en unos 3 segundos
+const SUN_PULSE: VertexAnimParams = VertexAnimParams { amplitude: 0.03, frequency: 1.6, speed: 0.006, octaves: 2 };
+
+// Dirección hacia el sol en la escena 5: desde la derecha y un poco desde arriba, así las fases de
+// la luna cambian mientras orbita (Light::directional la normaliza)
+const MOON_SCENE_LIGHT: Vec3 = Vec3::new(1.0, 0.3, 0.3);
+
+// Hielo: sombras levantadas y frías, colores un poco apagados
+const ICY_GRADE: ColorGrade = ColorGrade {
+    exposure: 0.15,
+    saturation: 0.85,
+    contrast: 0.85,
+    white_balance: Color::new(220, 238, 255),
+};
+
+// Volcánico: negros aplastados y luces calientes
+const VOLCANIC_GRADE: ColorGrade = ColorGrade {
+    exposure: 0.1,
+    saturation: 1.15,
+    contrast: 1.3,
+    white_balance: Color::new(255, 232, 205),
+};
+
+// Géiseres del planeta de hielo, concentrados cerca del polo sur como en Encélado
+const ICY_GEYSERS: [ParticleEmitter; 4] = [
+    ParticleEmitter { origin_dir: Vec3::new(0.3, -0.9, 0.3), rate: 0.6, speed: 0.004, lifetime: 120, spread: 0.12 },
+    ParticleEmitter { origin_dir: Vec3::new(-0.4, -0.85, 0.1), rate: 0.5, speed: 0.0035, lifetime: 140, spread: 0.1 },
+    ParticleEmitter { origin_dir: Vec3::new(0.1, -0.8, -0.5), rate: 0.5, speed: 0.005, lifetime: 100, spread: 0.15 },
+    ParticleEmitter { origin_dir: Vec3::new(0.8, 0.2, 0.55), rate: 0.3, speed: 0.003, lifetime: 110, spread: 0.1 },
+];
+
+// Color de las partículas de los géiseres
+const GEYSER_COLOR: Color = Color::new(235, 245, 255);
+
+// Cola del cometa del sistema solar: completa a la distancia de la órbita de la Tierra
+const COMET_TAIL: CometTail = CometTail { length: 4.0, width: 0.12, curvature: 0.25, falloff: 3.5, particles: 1500 };
+// Color que suma cada partícula de la cola cuando está completa
+const COMET_TAIL_COLOR: Color = Color::new(110, 150, 200);
+
+// Esfera que envuelve a todos los cuerpos de la escena, sin los de tamaño aparente
+pub fn scene_bounds(bodies: &[Body], meshes: &SceneMeshes) -> BoundingSphere {
+    enclosing_sphere(bodies.iter()
+        .filter(|body| body.apparent_size.is_none())
+        .map(|body| body.bounding_sphere(meshes)))
+}
+
+// Encuadre inicial igual que al entrar a la escena en la ventana
+pub fn start_camera(scene: &SceneConfig, meshes: &SceneMeshes) -> Camera {
+    match scene.camera {
+        Some(start) => start.camera(),
+        None => {
+            let mut camera = Camera::new(
+                Vec3::new(0.0, 0.0, 5.0),
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0)
+            );
+            let bounds = scene_bounds(&scene.bodies(0.0), meshes);
+            camera.frame_bounds(bounds.center, bounds.radius, FOV);
+            camera
+        }
+    }
+}
+
+// Esfera que contiene a los cuerpos en cualquier momento: las órbitas cuentan completas
+pub fn system_bounds(scene: &SceneConfig, meshes: &SceneMeshes) -> BoundingSphere {
+    enclosing_sphere(scene.bodies.iter().filter(|body| body.apparent_size.is_none()).map(|body| {
+        let radius = meshes.radius(body.mesh) * body.scale * body.axis_scale.max();
+        match body.orbit {
+            Some(orbit) => BoundingSphere::new(Vec3::new(0.0, 0.0, 0.0), orbit.apoapsis() + radius),
+            None => BoundingSphere::new(body.translation, radius),
+        }
+    }))
+}
+
+// Sin esferas, una unitaria en el origen
+fn enclosing_sphere(spheres: impl Iterator<Item = BoundingSphere>) -> BoundingSphere {
+    spheres.reduce(|bounds, sphere| bounds.merge(&sphere))
+        .unwrap_or(BoundingSphere::new(Vec3::new(0.0, 0.0, 0.0), 1.0))
+}
+
+// Profundidad mínima del plano cercano, para no perder precisión cuando la cámara está dentro de un cuerpo
+const MIN_NEAR: f32 = 0.05;
+// Margen alrededor de las esferas para que nada quede justo en los planos
+const DEPTH_MARGIN: f32 = 0.05;
+
+// Planos cercano y lejano ajustados a las esferas envolventes de la escena
+pub fn scene_depth_range(bodies: &[Body], meshes: &SceneMeshes, camera: &Camera) -> (f32, f32) {
+    let view = create_view_matrix(camera.eye, camera.center, camera.up);
+
+    let mut range: Option<(f32, f32)> = None;
+    for body in bodies {
+        let center = view * Vec4::new(body.translation.x, body.translation.y, body.translation.z, 1.0);
+        let depth = -center.z;
+        let radius = body.bounding_radius(meshes);
+        let (near, far) = (depth - radius, depth + radius);
+        // Lo que queda entero detrás de la cámara (como el sol del cielo) no mueve los planos
+        if far <= 0.0 {
+            continue;
+        }
+        range = Some(match range {
+            Some((min, max)) => (min.min(near), max.max(far)),
+            None => (near, far),
+        });
+    }
+
+    match range {
+        Some((_, far)) if far <= MIN_NEAR => DEFAULT_DEPTH_RANGE,
+        Some((near, far)) => {
+            let near = (near * (1.0 - DEPTH_MARGIN)).max(MIN_NEAR);
+            (near, far * (1.0 + DEPTH_MARGIN))
+        }
+        None => DEFAULT_DEPTH_RANGE,
+    }
+}
+
+// Lanza un rayo desde la cámara por el pixel (x, y) y devuelve el cuerpo más cercano que toca
+pub fn pick_body(bodies: &[Body], meshes: &SceneMeshes, uniforms: &FrameUniforms, x: f32, y: f32) -> Option<usize> {
+    let origin = unproject(x + 0.5, y + 0.5, -1.0, uniforms);
+    let ray = Ray::new(origin, unproject(x + 0.5, y + 0.5, 1.0, uniforms) - origin);
+
+    let mut nearest: Option<(usize, f32)> = None;
+    for (index, body) in bodies.iter().enumerate() {
+        // El cuerpo en el que está la cámara no se elige: taparía a todos los demás
+        let sphere = body.bounding_sphere(meshes);
+        if sphere.contains(ray.origin) {
+            continue;
+        }
+        let Some(hit) = ray.intersect_sphere(&sphere) else {
+            continue;
+        };
+        if nearest.is_none_or(|(_, best)| hit < best) {
+            nearest = Some((index, hit));
+        }
+    }
+
+    nearest.map(|(index, _)| index)
+}
+
+// Cuerpo del que habla la tarjeta de información: el que está bajo el mouse o, si no hay, el principal
+pub fn focused_body(bodies: &[Body], options: &RenderOptions) -> Option<usize> {
+    options.highlighted_body.filter(|&index| index < bodies.len()).or((!bodies.is_empty()).then_some(0))
+}
+
+// Renglones de la tarjeta de información del cuerpo `index`: el nombre primero y al final los parámetros
+// del shader (con sangría). El giro y la órbita salen de la configuración; la posición, del instante actual
+pub fn body_info(scene: &SceneConfig, bodies: &[Body], index: usize) -> Vec<String> {
+    let (Some(body), Some(config)) = (bodies.get(index), scene.bodies.get(index)) else {
+        return Vec::new();
+    };
+
+    let mut lines = vec![body.name.clone(), format!("shader: {}", body.shader.name())];
+    if body.axis_scale == Vec3::repeat(1.0) {
+        lines.push(format!("escala: {:.2}", body.scale));
+    } else {
+        let axes = body.axis_scale;
+        lines.push(format!("escala: {:.2} ({:.2}, {:.2}, {:.2})", body.scale, axes.x, axes.y, axes.z));
+    }
+    if config.spin == Vec3::zeros() {
+        lines.push("sin giro propio".to_string());
+    } else {
+        lines.push(format!("giro: {:.4} rad/tick", config.spin.magnitude()));
+    }
+    if config.tilt != 0.0 {
+        lines.push(format!("inclinación del eje: {:.1}°", config.tilt.to_degrees()));
+    }
+    match &config.orbit {
+        Some(orbit) => {
+            lines.push(format!("órbita: a {:.2}, e {:.2}, i {:.1}°", orbit.semi_major_axis, orbit.eccentricity, orbit.inclination.to_degrees()));
+            lines.push(format!("periodo: {:.0} ticks", orbit.period));
+        }
+        None => lines.push("sin órbita".to_string()),
+    }
+    lines.push(format!("distancia al centro: {:.2}", body.translation.magnitude()));
+    if config.mass > 0.0 {
+        lines.push(format!("masa: {}", config.mass));
+    }
+    lines.push(format!("semilla: {}", body.seed));
+
+    let parameters = body.shader.parameters();
+    if !parameters.is_empty() {
+        lines.push("parámetros del shader".to_string());
+        lines.extend(parameters.into_iter().map(|(name, value)| format!("  {}: {}", name, value)));
+    }
+    lines
+}
+
+// Se guarda en la sesión, salvo lo que depende del frame (el cuerpo resaltado y el oculto)
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderOptions {
+    pub show_lod_tiers: bool,
+    pub debug_mode: u32,
+    pub seed: u32,
+    // Shader para el modelo de --model en lugar del de la escena
+    pub model_shader: Option<ShaderType>,
+    // Modo caricatura (bandas de iluminación; el contorno se agrega después con post::outline)
+    pub toon: bool,
+    // Límites de fragmentos por frame y por triángulo
+    pub fragment_budget: usize,
+    pub triangle_fragment_cap: usize,
+    // Índice del cuerpo que está bajo el mouse
+    #[serde(skip)]
+    pub highlighted_body: Option<usize>,
+    // Índice de un cuerpo que no se dibuja (en el que está parada la cámara de render_cubemap)
+    #[serde(skip)]
+    pub hidden_body: Option<usize>,
+    // Cuadrícula de latitud y longitud y eje de rotación sobre cada cuerpo
+    pub graticule: bool,
+    // Anillos dibujados como partículas en órbita en lugar de la malla
+    pub particle_rings: bool,
+    // Anillos con más segmentos cuanto más cerca está la cámara de su borde, en lugar de la malla fija
+    pub adaptive_ring: bool,
+    // Oclusión ambiental en el espacio de la pantalla (se agrega después con post::ambient_occlusion)
+    pub ambient_occlusion: bool,
+    // Cielo de estrellas en lugar del color de fondo de la escena
+    pub starfield: bool,
+    // Vista infrarroja: cada cuerpo se dibuja con su temperatura en el degradado de la escena
+    pub thermal: bool,
+    // Descarta las caras traseras de los cuerpos que no son de dos caras
+    pub cull_backfaces: bool,
+    // Anillos sin perfil propio con el de Saturno (RingProfile::saturn) en lugar de las franjas procedurales
+    pub saturn_rings: bool,
+    // Hilos para rasterizar por teselas (1 = sin hilos); depende de la computadora, así que no se guarda
+    #[serde(skip)]
+    pub threads: usize,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            show_lod_tiers: false,
+            debug_mode: 0,
+            seed: 0,
+            model_shader: None,
+            toon: false,
+            fragment_budget: DEFAULT_FRAGMENT_BUDGET,
+            triangle_fragment_cap: DEFAULT_TRIANGLE_FRAGMENT_CAP,
+            highlighted_body: None,
+            hidden_body: None,
+            graticule: false,
+            particle_rings: false,
+            adaptive_ring: false,
+            ambient_occlusion: false,
+            starfield: false,
+            thermal: false,
+            cull_backfaces: false,
+            saturn_rings: false,
+            threads: default_threads(),
+        }
+    }
+}
+
+// Estadísticas de render de un cuerpo en el último frame
+#[derive(Clone, Debug)]
+pub struct BodyStats {
+    pub name: String,
+    pub shader: SceneShader,
+    pub stats: RenderStats,
+    // Segmentos del anillo adaptativo con los que se dibujó (None en los demás cuerpos)
+    pub ring_segments: Option<u32>,
+}
+
+// Bandas de iluminación del modo caricatura
+pub const TOON_BANDS: u32 = 3;
+
+// Partículas de cada anillo en el modo de anillos de partículas
+pub const RING_PARTICLE_COUNT: usize = 20_000;
+
+// Estado que cambia mientras se muestra una escena: histéresis del LOD, impactos en las lunas, partículas
+// de los anillos y mallas con relieve
+pub struct SceneState {
+    pub lod: LodState,
+    pub moons: Vec<Option<MoonState>>,  // Uno por cuerpo, solo para los que usan el shader de luna
+    pub rings: Vec<Option<ParticleRing>>,   // Uno por cuerpo, solo para los que usan la malla del anillo
+    pub asteroids: Vec<Asteroid>,           // Rocas del cinturón de la escena (vacío si no tiene)
+    pub displaced: Vec<Option<Vec<Vec<Vertex>>>>,   // Uno por cuerpo con relieve, con sus niveles de detalle
+    pub physics: Option<Simulation>,                // Solo en las escenas con física
+    pub scratch: RenderScratch,
+}
+
+impl SceneState {
+    pub fn new(scene: &SceneConfig, seed: u32, meshes: &SceneMeshes) -> Self {
+        let mut state = SceneState { lod: LodState::default(), moons: Vec::new(), rings: Vec::new(), asteroids: Vec::new(), displaced: Vec::new(), physics: None, scratch: RenderScratch::new() };
+        state.reset(scene, seed, meshes);
+        state
+    }
+
+    // Vuelve a los cráteres iniciales y a las posiciones iniciales de la física y genera las partículas
+    // de los anillos, las rocas del cinturón y el relieve de los cuerpos; el LOD se conserva porque ya
+    // distingue cada escena
+    pub fn reset(&mut self, scene: &SceneConfig, seed: u32, meshes: &SceneMeshes) {
+        self.moons = scene.bodies.iter()
+            .map(|body| (body.shader == ShaderType::Moon).then(|| MoonState::new(rng::combine(seed, body.seed))))
+            .collect();
+        self.rings = scene.bodies.iter()
+            .map(|body| (body.mesh == MeshKind::Ring).then(|| {
+                ParticleRing::new(rng::combine(seed, body.seed), RING_PARTICLE_COUNT, mesh::RING_INNER_RADIUS, mesh::RING_OUTER_RADIUS)
+            }))
+            .collect();
+        self.asteroids = scene.belt.map(|belt| belt.asteroids(seed)).unwrap_or_default();
+        self.update_relief(scene, seed, meshes);
+        self.restart_physics(scene);
+    }
+
+    // Vuelve a deformar las mallas de los cuerpos con relieve, sin tocar el resto del estado; hace falta
+    // cuando cambian las mallas (al terminar de leer los OBJ)
+    pub fn update_relief(&mut self, scene: &SceneConfig, seed: u32, meshes: &SceneMeshes) {
+        self.displaced = scene.bodies.iter()
+            .map(|body| body.displacement.map(|relief| {
+                (0..meshes.tiers(body.mesh))
+                    .map(|tier| mesh::displace(meshes.get(body.mesh, tier), rng::combine(seed, body.seed), relief.amplitude, relief.frequency, relief.octaves))
+                    .collect()
+            }))
+            .collect();
+    }
+
+    // La física vuelve a las posiciones y velocidades iniciales y empieza a contar desde la próxima update
+    pub fn restart_physics(&mut self, scene: &SceneConfig) {
+        self.physics = scene.physics.then(|| Simulation::new(scene));
+    }
+
+    // Malla con la que se dibuja el cuerpo `index`: la deformada si tiene relieve
+    pub fn mesh<'a>(&'a self, meshes: &'a SceneMeshes, index: usize, kind: MeshKind, tier: usize) -> &'a [Vertex] {
+        body_mesh(&self.displaced, meshes, index, kind, tier)
+    }
+
+    pub fn update(&mut self, time: f32) {
+        for moon in self.moons.iter_mut().flatten() {
+            moon.update(time);
+        }
+        if let Some(physics) = &mut self.physics {
+            physics.update(time);
+        }
+    }
+
+    // Cuerpos de la escena en `time`; con física la posición sale de la simulación en lugar de la órbita
+    pub fn bodies(&self, scene: &SceneConfig, time: f32) -> Vec<Body> {
+        let mut bodies = scene.bodies(time);
+        if let Some(physics) = &self.physics {
+            for (index, body) in bodies.iter_mut().enumerate() {
+                body.translation = physics.position(index).unwrap_or(body.translation);
+            }
+        }
+        bodies
+    }
+
+    // Como SceneConfig::bodies_seen_from, con las posiciones de la física
+    pub fn bodies_seen_from(&self, scene: &SceneConfig, time: f32, eye: Vec3, meshes: &SceneMeshes) -> Vec<Body> {
+        self.bodies(scene, time).into_iter().map(|body| body.seen_from(eye, meshes)).collect()
+    }
+}
+
+fn body_mesh<'a>(displaced: &'a [Option<Vec<Vec<Vertex>>>], meshes: &'a SceneMeshes, index: usize, kind: MeshKind, tier: usize) -> &'a [Vertex] {
+    match displaced.get(index).and_then(Option::as_ref) {
+        Some(tiers) => &tiers[tier.min(tiers.len() - 1)],
+        None => meshes.get(kind, tier),
+    }
+}
+
+// Id del buffer de ids para cada cuerpo; el 0 queda para el fondo
+pub fn body_id(index: usize) -> u16 {
+    (index + 1).min(u16::MAX as usize) as u16
+}
+
+// Cuerpo al que corresponde un id del buffer de ids
+pub fn body_index(id: u16) -> Option<usize> {
+    (id as usize).checked_sub(1)
+}
+
+// Dibuja todos los cuerpos de una escena en el framebuffer y devuelve las estadísticas de cada uno
+pub fn render_scene(framebuffer: &mut Framebuffer, scene_number: u32, scene: &SceneConfig, uniforms: &FrameUniforms, meshes: &SceneMeshes, state: &mut SceneState, options: &RenderOptions) -> Vec<BodyStats> {
+    framebuffer.clear_rect(uniforms.viewport);
+    let frame = frame_uniforms(uniforms, scene, options);
+    let mut budget = FragmentBudget::new(options.fragment_budget, options.triangle_fragment_cap);
+    let camera_position = camera_position(uniforms);
+    let frustum = Frustum::new(&(uniforms.projection_matrix * uniforms.view_matrix));
+
+    let bodies = state.bodies_seen_from(scene, uniforms.time, camera_position, meshes);
+    // Nivel de detalle según el tamaño del cuerpo en pantalla (en el anillo adaptativo, según lo cerca que
+    // se ve su borde); solo la vista que ocupa todo el framebuffer guarda el nivel (con histéresis), las
+    // demás lo eligen de nuevo en cada frame
+    let adaptive = |body: &Body| options.adaptive_ring && body.mesh == MeshKind::Ring;
+    let tiers: Vec<usize> = bodies.iter().enumerate().map(|(index, body)| {
+        let ring = adaptive(body);
+        if !ring && !body.lod {
+            return 0;
+        }
+        let radius_px = if ring {
+            ring_edge_radius(body, camera_position, uniforms)
+        } else {
+            let distance = (body.translation - camera_position).magnitude();
+            lod::projected_radius(body.bounding_radius(meshes), distance, &uniforms.projection_matrix, uniforms.viewport.height as f32)
+        };
+        let select = |current| if ring { lod::select_ring_tier(current, &mesh::RING_SEGMENT_TIERS, radius_px) } else { lod::select_tier(current, radius_px) };
+        if uniforms.viewport == framebuffer.rect() {
+            state.lod.update(scene_number, index, select)
+        } else {
+            select(0)
+        }
+    }).collect();
+
+    // Se arman los uniforms de todos los cuerpos antes de dibujar para saber en qué orden van
+    let draws: Vec<BodyUniforms> = (0..bodies.len())
+        .map(|index| body_uniforms(&bodies, index, tiers[index], meshes, state, options))
+        .collect();
+
+    // El cinturón es opaco, así que va antes que los cuerpos translúcidos
+    let belt_stats = render_belt(framebuffer, &frame, &frustum, meshes, state, options, &mut budget);
+
+    // El cielo (la imagen de la escena o las estrellas) va entre lo opaco y lo translúcido (ver
+    // render_starfield); en infrarrojo el espacio está frío y no se ve
+    let mut sky_pending = (scene.skybox.is_some() || options.starfield) && !options.thermal;
+    let mut body_stats = vec![None; bodies.len()];
+    for index in draw_order(&frame, &draws).into_iter().filter(|&index| Some(index) != options.hidden_body) {
+        let (body, body_uniforms) = (&bodies[index], &draws[index]);
+        if sky_pending && body_uniforms.blend != BlendMode::Opaque {
+            render_background_sky(framebuffer, &frame, scene);
+            sky_pending = false;
+        }
+        let shader = body_shader(body, options);
+        let mut stats = RenderStats::default();
+        let mut ring_segments = None;
+        // Lo que queda entero fuera de la vista no pasa por el pipeline; sus triángulos cuentan como recortados
+        let outside = frustum.excludes(&body.bounding_sphere(meshes));
+        match state.rings.get(index).and_then(Option::as_ref).filter(|_| options.particle_rings) {
+            _ if outside => stats.culled_triangles += meshes.get(body.mesh, tiers[index]).len() / 3,
+            Some(ring) => render_ring_particles(framebuffer, &frame, body_uniforms, ring, &mut stats),
+            None if adaptive(body) => {
+                render(framebuffer, &frame, body_uniforms, meshes.adaptive_ring(tiers[index]), &shader, &mut budget, &mut stats, &mut state.scratch);
+                ring_segments = Some(mesh::RING_SEGMENT_TIERS[tiers[index].min(mesh::RING_SEGMENT_TIERS.len() - 1)]);
+            }
+            None => {
+                // Sin pasar por SceneState::mesh, que tomaría todo el estado mientras se usa el scratch
+                let vertices = body_mesh(&state.displaced, meshes, index, body.mesh, tiers[index]);
+                render(framebuffer, &frame, body_uniforms, vertices, &shader, &mut budget, &mut stats, &mut state.scratch)
+            }
+        }
+        body_stats[index] = Some(BodyStats { name: body.name.clone(), shader, stats, ring_segments });
+
+        if body_uniforms.graticule {
+            render_axis(framebuffer, &frame, body_uniforms, meshes.radius(body.mesh));
+        }
+
+        for (emitter_index, emitter) in body.emitters.iter().enumerate() {
+            let particles = emitter.particles(uniforms.time, rng::combine(body_uniforms.seed, emitter_index as u32));
+            render_points(framebuffer, &frame, body_uniforms, &particles, GEYSER_COLOR);
+        }
+    }
+
+    if sky_pending {
+        render_background_sky(framebuffer, &frame, scene);
+    }
+
+    // Las colas no escriben la profundidad, así que van después de todos los cuerpos para que las tapen
+    // los que están adelante
+    for (index, body) in bodies.iter().enumerate().filter(|&(index, _)| Some(index) != options.hidden_body) {
+        if let (Some(tail), Some(Some(stats))) = (&body.tail, body_stats.get_mut(index)) {
+            render_tail(framebuffer, &frame, tail, body, scene.bodies[index].velocity_at(frame.time), &draws[index], &mut stats.stats);
+        }
+    }
+
+    if scene.trails {
+        render_trails(framebuffer, &frame, state, options);
+    }
+
+    body_stats.into_iter().flatten().chain(belt_stats).collect()
+}
+
+// Cola de un cometa en contra de la primera luz de la escena (el sol); una luz direccional está
+// infinitamente lejos y deja la cola completa
+fn render_tail(framebuffer: &mut Framebuffer, frame: &FrameUniforms, tail: &CometTail, body: &Body, velocity: Vec3, body_uniforms: &BodyUniforms, stats: &mut RenderStats) {
+    let Some(light) = frame.lights.first() else {
+        return;
+    };
+    let away = -light.direction_from(&body.translation);
+    let strength = match light.source {
+        LightSource::Point(position) => tail.strength((body.translation - position).magnitude()),
+        LightSource::Directional(_) => 1.0,
+    };
+    let particles = tail.particles(body.translation, away, velocity, strength, frame.time, body_uniforms.seed);
+    render_glow(framebuffer, frame, &particles, COMET_TAIL_COLOR * strength, stats);
+}
+
+const TRAIL_COLOR: Color = Color::new(150, 170, 210);
+
+// Estelas de los cuerpos de la simulación, detrás de los cuerpos y sin poder elegirse con el mouse
+fn render_trails(framebuffer: &mut Framebuffer, frame: &FrameUniforms, state: &SceneState, options: &RenderOptions) {
+    let Some(physics) = &state.physics else {
+        return;
+    };
+    for (_, trail) in physics.trails().iter().enumerate().filter(|(index, _)| Some(*index) != options.hidden_body) {
+        render_trail(framebuffer, frame, trail, TRAIL_COLOR);
+    }
+}
+
+// Radio en pixeles con el que se ve el borde exterior del anillo en su punto más cercano a la cámara
+fn ring_edge_radius(body: &Body, camera_position: Vec3, uniforms: &FrameUniforms) -> f32 {
+    let model = body.model_matrix();
+    let local = model.try_inverse().map_or(Vec4::zeros(), |inverse| inverse * camera_position.push(1.0));
+    let toward_camera = Vec3::new(local.x, 0.0, local.z).try_normalize(f32::EPSILON).unwrap_or(Vec3::new(1.0, 0.0, 0.0));
+    let edge = (model * (toward_camera * mesh::RING_OUTER_RADIUS).push(1.0)).xyz();
+    let center = (model * Vec4::new(0.0, 0.0, 0.0, 1.0)).xyz();
+    lod::projected_curvature((edge - center).magnitude(), (edge - camera_position).magnitude(), &uniforms.projection_matrix, uniforms.viewport.height as f32)
+}
+
+// Posición de la cámara en el mundo según la matriz de vista
+fn camera_position(uniforms: &FrameUniforms) -> Vec3 {
+    uniforms.view_matrix.try_inverse()
+        .map(|inverse| Vec3::new(inverse[(0, 3)], inverse[(1, 3)], inverse[(2, 3)]))
+        .unwrap_or_default()
+}
+
+// Cielo detrás de los cuerpos: la imagen de la escena si tiene una, si no las estrellas
+fn render_background_sky(framebuffer: &mut Framebuffer, frame: &FrameUniforms, scene: &SceneConfig) {
+    match &scene.skybox {
+        Some(skybox) => render_sky(framebuffer, frame, |direction| skybox.color(direction)),
+        None => render_starfield(framebuffer, frame),
+    };
+}
+
+// Todas las rocas del cinturón comparten una malla; cada una solo cambia la matriz de modelo.
+// Las que en pantalla miden menos que ASTEROID_POINT_RADIUS se dibujan como un pixel
+fn render_belt(framebuffer: &mut Framebuffer, frame: &FrameUniforms, frustum: &Frustum, meshes: &SceneMeshes, state: &mut SceneState, options: &RenderOptions, budget: &mut FragmentBudget) -> Option<BodyStats> {
+    if state.asteroids.is_empty() {
+        return None;
+    }
+    let camera_position = camera_position(frame);
+    // No son cuerpos, así que usan el id del fondo y no se pueden elegir
+    let mut rock_uniforms = BodyUniforms { seed: options.seed, ..BodyUniforms::default() };
+    let mut stats = RenderStats::default();
+    for (index, asteroid) in state.asteroids.iter().enumerate() {
+        let position = asteroid.position(frame.time);
+        let distance = (position - camera_position).magnitude();
+        let radius_px = lod::projected_radius(meshes.rock_radius() * asteroid.scale, distance, &frame.projection_matrix, frame.viewport.height as f32);
+        if radius_px < ASTEROID_POINT_RADIUS {
+            let color = match &frame.thermal {
+                Some(gradient) => Color::from_gradient(shaders::asteroid_point_temperature(&position, frame), gradient),
+                None => shaders::asteroid_point_color(&position, frame),
+            };
+            render_point(framebuffer, frame, &rock_uniforms, &position, color, &mut stats);
+        } else {
+            let model_matrix = asteroid.model_matrix(frame.time);
+            if frustum.excludes(&meshes.rock_bounds().sphere.transformed(&model_matrix)) {
+                stats.culled_triangles += meshes.rock.len() / 3;
+                continue;
+            }
+            rock_uniforms.set_model_matrix(model_matrix);
+            rock_uniforms.seed = rng::combine(options.seed, index as u32);
+            render(framebuffer, frame, &rock_uniforms, &meshes.rock, &ShaderType::Asteroid, budget, &mut stats, &mut state.scratch);
+        }
+    }
+    Some(BodyStats { name: "Cinturón de asteroides".to_string(), shader: ShaderType::Asteroid.into(), stats, ring_segments: None })
+}
+
+// Radio en pixeles por debajo del cual un asteroide se dibuja como un punto
+const ASTEROID_POINT_RADIUS: f32 = 1.5;
+
+// Uniforms del frame con lo que la escena y las opciones cambian para todos los cuerpos: luces, niebla y modos
+pub fn frame_uniforms(uniforms: &FrameUniforms, scene: &SceneConfig, options: &RenderOptions) -> FrameUniforms {
+    FrameUniforms {
+        debug_mode: options.debug_mode,
+        cel_bands: options.toon.then_some(TOON_BANDS),
+        lights: scene.lights.clone(),
+        ambient: scene.ambient,
+        skylight: skylight(scene, options),
+        fog: scene.fog,
+        thermal: options.thermal.then(|| scene.thermal_gradient.clone()),
+        cull_backfaces: options.cull_backfaces,
+        threads: options.threads,
+        ..uniforms.clone()
+    }
+}
+
+// Luz ambiental que llega del fondo que se ve detrás de los cuerpos: la imagen de la escena, el cielo de
+// estrellas o el color liso, escalada por scene.skylight. Así los lados de noche toman el tono del fondo
+pub fn skylight(scene: &SceneConfig, options: &RenderOptions) -> AmbientCube {
+    // En infrarrojo el fondo está frío y no calienta a nadie
+    if options.thermal {
+        return AmbientCube::BLACK;
+    }
+    let environment = match &scene.skybox {
+        Some(skybox) => skybox.ambient,
+        None if options.starfield => shaders::starfield_ambient(),
+        None => AmbientCube::uniform(Color::from_hex(scene.background)),
+    };
+    environment.scale(scene.skylight)
+}
+
+// Uniforms del cuerpo `index` de `bodies` (ya ubicados para este frame); `tier` solo se usa para colorear el LOD
+pub fn body_uniforms(bodies: &[Body], index: usize, tier: usize, meshes: &SceneMeshes, state: &SceneState, options: &RenderOptions) -> BodyUniforms {
+    let body = &bodies[index];
+    BodyUniforms {
+        seed: rng::combine(options.seed, body.seed),
+        debug_tint: (options.show_lod_tiers && body.lod).then(|| lod::tier_color(tier)),
+        moon: state.moons.get(index).cloned().flatten(),
+        body_id: body_id(index),
+        highlighted: options.highlighted_body == Some(index),
+        // Los anillos son planos, la cuadrícula no dice nada sobre ellos
+        graticule: options.graticule && body.mesh != MeshKind::Ring,
+        planetshine: planetshine(bodies, index, meshes),
+        ring_shadow: ring_shadow(bodies, index, options),
+        ring_profile: ring_profile(body, options),
+        displaced: body.displacement.is_some(),
+        vertex_animation: body.vertex_animation,
+        // El anillo de partículas se dibuja con puntos opacos
+        blend: if options.particle_rings && body.mesh == MeshKind::Ring { BlendMode::Opaque } else { body_shader(body, options).blend_mode() },
+        two_sided: body.two_sided,
+        ..BodyUniforms::new(body.model_matrix())
+    }
+}
+
+// Luz reflejada sobre una luna por el cuerpo que se ve más grande desde ella; los demás cuerpos no la reciben
+pub fn planetshine(bodies: &[Body], index: usize, meshes: &SceneMeshes) -> Option<Planetshine> {
+    let moon = &bodies[index];
+    if moon.shader != ShaderType::Moon {
+        return None;
+    }
+    bodies.iter().enumerate()
+        .filter(|(other, body)| *other != index && body.apparent_size.is_none())
+        .filter_map(|(_, body)| body.shader.albedo().map(|color| Planetshine {
+            position: body.translation,
+            radius: body.bounding_radius(meshes),
+            color,
+        }))
+        .max_by(|a, b| {
+            let size = |planet: &Planetshine| planet.radius / (planet.position - moon.translation).magnitude().max(f32::EPSILON);
+            size(a).total_cmp(&size(b))
+        })
+}
+
+// Sombra del anillo que tiene el mismo centro que el planeta con anillos; los demás cuerpos no la reciben
+pub fn ring_shadow(bodies: &[Body], index: usize, options: &RenderOptions) -> Option<RingShadow> {
+    let planet = &bodies[index];
+    if planet.shader != ShaderType::RingPlanet {
+        return None;
+    }
+    let ring = bodies.iter()
+        .find(|body| body.mesh == MeshKind::Ring && (body.translation - planet.translation).magnitude() < RING_CENTER_TOLERANCE)?;
+    ring.model_matrix().try_inverse().map(|world_to_ring| RingShadow { world_to_ring, profile: ring_profile(ring, options) })
+}
+
+// Perfil con el que se dibuja un anillo: el suyo o, con saturn_rings, el de Saturno
+pub fn ring_profile(body: &Body, options: &RenderOptions) -> Option<Arc<RingProfile>> {
+    if body.mesh != MeshKind::Ring {
+        return None;
+    }
+    body.ring_profile.clone().or_else(|| options.saturn_rings.then(RingProfile::saturn))
+}
+
+// Distancia máxima entre el centro del anillo y el del planeta para que se tomen como el mismo sistema
+const RING_CENTER_TOLERANCE: f32 = 1e-3;
+
+// Shader para el modelo de --model: el pedido con --model-shader o, si el modelo no trae uv, el
+// tri-planar (los demás dependen de la forma de esfera o de las uv y sobre un modelo cualquiera se estiran)
+pub fn default_model_shader(requested: Option<ShaderType>, meshes: &SceneMeshes) -> Option<ShaderType> {
+    requested.or_else(|| (meshes.has_model() && !meshes.model_has_uvs()).then_some(ShaderType::Triplanar))
+}
+
+// Shader de un cuerpo; el modelo de --model puede usar otro que el de la escena
+pub fn body_shader(body: &Body, options: &RenderOptions) -> SceneShader {
+    match (body.mesh, options.model_shader) {
+        (MeshKind::Model, Some(shader)) => shader.into(),
+        _ => body.shader.clone(),
+    }
+}
+
+// Mallas de las escenas: la esfera y el anillo, y los niveles de detalle extra de la esfera (opcionales)
+pub const SPHERE_OBJ: &str = "models/sphere.obj";
+pub const RING_OBJ: &str = "models/ring.obj";
+pub const SPHERE_LOD_OBJS: [&str; 2] = ["models/sphere_mid.obj", "models/sphere_low.obj"];
+
+pub fn load_meshes(procedural: bool, model_path: Option<&str>) -> SceneMeshes {
+    let mut meshes = load_scene_meshes(procedural);
+
+    if let Some(path) = model_path {
+        let start = Instant::now();
+        match Obj::load_cached(path) {
+            Ok(vertices) => {
+                eprintln!("Modelo {} cargado en {:.1} ms ({} triángulos)", path, start.elapsed().as_secs_f64() * 1000.0, vertices.len() / 3);
+                meshes.set_model(vertices);
+            }
+            Err(err) => eprintln!("No se pudo cargar el modelo {}: {}", path, err),
+        }
+    }
+
+    meshes
+}
+
+fn load_scene_meshes(procedural: bool) -> SceneMeshes {
+    if procedural {
+        return procedural_meshes();
+    }
+
+    let (sphere, ring) = match (Obj::load_cached(SPHERE_OBJ), Obj::load_cached(RING_OBJ)) {
+        (Ok(sphere), Ok(ring)) => (sphere, ring),
+        (Err(err), _) | (_, Err(err)) => {
+            eprintln!("No se pudieron cargar los modelos ({}), se usan mallas generadas", err);
+            return procedural_meshes();
+        }
+    };
+
+    // Niveles de detalle extra; si no están se usa la esfera completa
+    let mut sphere_lods = vec![sphere];
+    for path in SPHERE_LOD_OBJS {
+        match Obj::load_cached(path) {
+            Ok(vertices) => sphere_lods.push(vertices),
+            Err(err) => eprintln!("No se pudo cargar {}: {}", path, err),
+        }
+    }
+
+    SceneMeshes::new(sphere_lods, ring)
+}
+
+// Mallas equivalentes a los OBJ generadas en el programa; también son las que se dibujan mientras se leen
+pub fn procedural_meshes() -> SceneMeshes {
+    let sphere_lods = vec![
+        mesh::generate_uv_sphere(24, 48),
+        mesh::generate_icosphere(2),
+        mesh::generate_icosphere(1),
+    ];
+    SceneMeshes::new(sphere_lods, mesh::generate_ring(mesh::RING_INNER_RADIUS, mesh::RING_OUTER_RADIUS, 256))
+}
@@ -0,0 +1,566 @@
+use nalgebra_glm::{Quat, Vec3};
+use serde::Deserialize;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use crate::anchors::{SurfaceAnchor, ANCHOR_COLOR};
+use crate::belt::BeltConfig;
+use crate::camera::Keyframe;
+use crate::color::{Color, INFERNO};
+use crate::mesh::DisplaceParams;
+use crate::particles::{CometTail, ParticleEmitter};
+use crate::ring_profile::RingProfile;
+use crate::pipeline::{default_lights, FogParams, Light, VertexAnimParams};
+use crate::post::{AmbientOcclusion, ColorGrade};
+use crate::orbit::{Orbit, MAX_ECCENTRICITY};
+use crate::scene::{body_id, BodyConfig, CameraConfig, MeshKind, SceneConfig, BACKGROUND_COLOR, SKYLIGHT_STRENGTH};
+use crate::shaders::ShaderType;
+use crate::texture::Skybox;
+
+// Formato TOML de las escenas; los campos que faltan toman los mismos valores que las escenas incluidas
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    #[serde(default)]
+    bodies: Vec<BodyFile>,
+    camera: Option<CameraFile>,
+    background: Option<u32>,
+    // Ruta de un PNG equirectangular que se ve de fondo e ilumina los cuerpos con sus colores
+    skybox: Option<String>,
+    lights: Option<Vec<LightFile>>,
+    #[serde(default)]
+    ambient: [u8; 3],
+    // Cuánto ilumina el fondo a los cuerpos (0 lo apaga)
+    skylight: Option<f32>,
+    fog: Option<FogFile>,
+    grade: Option<GradeFile>,
+    ambient_occlusion: Option<AmbientOcclusionFile>,
+    // Degradado de la vista infrarroja en lugar del inferno
+    thermal_gradient: Option<Vec<GradientStopFile>>,
+    #[serde(default)]
+    tour: Vec<KeyframeFile>,
+    belt: Option<BeltFile>,
+    // Gravedad entre los cuerpos en lugar de sus órbitas, y las estelas de su recorrido
+    #[serde(default)]
+    physics: bool,
+    #[serde(default)]
+    trails: bool,
+    // Marcas en la superficie de los cuerpos
+    #[serde(default)]
+    anchors: Vec<AnchorFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BodyFile {
+    name: String,
+    shader: String,
+    #[serde(default = "default_mesh")]
+    mesh: String,
+    #[serde(default)]
+    translation: [f32; 3],
+    orbit: Option<OrbitFile>,
+    #[serde(default = "default_scale")]
+    scale: f32,
+    // Escala en cada eje, multiplicada por `scale`
+    #[serde(default = "default_axis_scale")]
+    axis_scale: [f32; 3],
+    // Ángulos de Euler en radianes, o en su lugar `orientation` como cuaternión [x, y, z, w]
+    #[serde(default)]
+    rotation: [f32; 3],
+    orientation: Option<[f32; 4]>,
+    // Giro propio en radianes por tick, en los ejes del cuerpo ya rotado
+    #[serde(default)]
+    spin: [f32; 3],
+    // Inclinación del eje de giro en grados
+    #[serde(default)]
+    tilt: f32,
+    lod: Option<bool>,
+    // Se ve de los dos lados; por defecto solo los anillos
+    two_sided: Option<bool>,
+    #[serde(default)]
+    seed: u32,
+    #[serde(default)]
+    emitters: Vec<EmitterFile>,
+    // Diámetro aparente en radianes
+    apparent_size: Option<f32>,
+    // Texto que se dibuja junto al cuerpo
+    label: Option<String>,
+    displacement: Option<DisplacementFile>,
+    // Pulsación de la superficie que cambia con el tiempo
+    pulse: Option<PulseFile>,
+    // Con `physics = true` en la escena: masa y velocidad inicial en unidades por tick
+    #[serde(default)]
+    mass: f32,
+    #[serde(default)]
+    velocity: [f32; 3],
+    // Cola de cometa en contra de la primera luz
+    tail: Option<TailFile>,
+    // Perfil radial de un anillo: "saturn" para el incluido o la ruta de un CSV o una tira PNG
+    ring_profile: Option<String>,
+}
+
+// Marca pegada a la superficie del cuerpo `body` (por su nombre); `direction` en el espacio del cuerpo
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnchorFile {
+    body: String,
+    direction: [f32; 3],
+    label: String,
+    color: Option<[u8; 3]>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrbitFile {
+    // Semieje mayor (el radio si la órbita es circular)
+    radius: f32,
+    // Velocidad angular media en radianes por tick
+    speed: f32,
+    #[serde(default)]
+    phase: f32,
+    #[serde(default)]
+    eccentricity: f32,
+    // Ángulo del periapsis y la inclinación, en grados
+    #[serde(default)]
+    periapsis: f32,
+    #[serde(default)]
+    inclination: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraFile {
+    eye: [f32; 3],
+    #[serde(default)]
+    center: [f32; 3],
+    #[serde(default = "default_up")]
+    up: [f32; 3],
+    #[serde(default)]
+    orthographic: bool,
+}
+
+// Luz direccional (`direction`) o puntual (`position`)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LightFile {
+    direction: Option<[f32; 3]>,
+    position: Option<[f32; 3]>,
+    #[serde(default = "default_light_color")]
+    color: [u8; 3],
+    #[serde(default = "default_scale")]
+    intensity: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FogFile {
+    color: [u8; 3],
+    start: f32,
+    end: f32,
+}
+
+// Pose de la cámara en el recorrido de la demo (`time` en segundos)
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeFile {
+    time: f32,
+    eye: [f32; 3],
+    #[serde(default)]
+    center: [f32; 3],
+}
+
+// Corrección de color; los campos que faltan quedan neutros
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GradeFile {
+    #[serde(default)]
+    exposure: f32,
+    #[serde(default = "default_scale")]
+    saturation: f32,
+    #[serde(default = "default_scale")]
+    contrast: f32,
+    #[serde(default = "default_light_color")]
+    white_balance: [u8; 3],
+}
+
+// Parada de un degradado: posición en [0, 1] y color
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GradientStopFile {
+    at: f32,
+    color: [u8; 3],
+}
+
+// Oclusión ambiental; los campos que faltan quedan con los valores por defecto
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AmbientOcclusionFile {
+    radius: Option<f32>,
+    intensity: Option<f32>,
+    bias: Option<f32>,
+}
+
+// Cinturón de asteroides alrededor del origen
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BeltFile {
+    count: usize,
+    inner_radius: f32,
+    outer_radius: f32,
+    #[serde(default)]
+    thickness: f32,
+    #[serde(default)]
+    seed: u32,
+}
+
+// Relieve de la superficie: `amplitude` en fracción del radio
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DisplacementFile {
+    amplitude: f32,
+    #[serde(default = "default_displacement_frequency")]
+    frequency: f32,
+    #[serde(default = "default_displacement_octaves")]
+    octaves: u32,
+}
+
+// Pulsación: `amplitude` en fracción del radio y `speed` en unidades de ruido por tick
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PulseFile {
+    amplitude: f32,
+    #[serde(default = "default_pulse_frequency")]
+    frequency: f32,
+    #[serde(default = "default_pulse_speed")]
+    speed: f32,
+    #[serde(default = "default_pulse_octaves")]
+    octaves: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct EmitterFile {
+    origin_dir: [f32; 3],
+    rate: f32,
+    speed: f32,
+    lifetime: u32,
+    #[serde(default = "default_spread")]
+    spread: f32,
+}
+
+// Cola de cometa: `length` y `falloff` en unidades del mundo, `width` y `curvature` en fracción del largo
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TailFile {
+    length: f32,
+    #[serde(default = "default_tail_width")]
+    width: f32,
+    #[serde(default = "default_tail_curvature")]
+    curvature: f32,
+    falloff: f32,
+    #[serde(default = "default_tail_particles")]
+    particles: u32,
+}
+
+fn default_mesh() -> String {
+    "sphere".to_string()
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_axis_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_light_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+fn default_spread() -> f32 {
+    0.1
+}
+
+fn default_tail_width() -> f32 {
+    0.1
+}
+
+fn default_tail_curvature() -> f32 {
+    0.2
+}
+
+fn default_tail_particles() -> u32 {
+    1000
+}
+
+fn default_displacement_frequency() -> f32 {
+    3.0
+}
+
+fn default_displacement_octaves() -> u32 {
+    4
+}
+
+fn default_pulse_frequency() -> f32 {
+    1.6
+}
+
+fn default_pulse_speed() -> f32 {
+    0.006
+}
+
+fn default_pulse_octaves() -> u32 {
+    2
+}
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneFileError::Io(err) => write!(f, "no se pudo leer el archivo de escena: {}", err),
+            SceneFileError::Parse(err) => write!(f, "archivo de escena inválido: {}", err),
+            SceneFileError::Invalid(message) => write!(f, "archivo de escena inválido: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SceneFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SceneFileError::Io(err) => Some(err),
+            SceneFileError::Parse(err) => Some(err),
+            SceneFileError::Invalid(_) => None,
+        }
+    }
+}
+
+fn vec3([x, y, z]: [f32; 3]) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+fn mesh_kind(name: &str) -> Result<MeshKind, String> {
+    match name {
+        "sphere" => Ok(MeshKind::Sphere),
+        "ring" => Ok(MeshKind::Ring),
+        "model" => Ok(MeshKind::Model),
+        _ => Err(format!("malla desconocida: {} (opciones: sphere, ring, model)", name)),
+    }
+}
+
+impl SceneConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SceneFileError> {
+        let source = std::fs::read_to_string(path).map_err(SceneFileError::Io)?;
+        Self::from_toml(&source)
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, SceneFileError> {
+        let file: SceneFile = toml::from_str(source).map_err(SceneFileError::Parse)?;
+
+        let bodies = file.bodies.into_iter()
+            .map(|body| {
+                let invalid = |message: String| SceneFileError::Invalid(format!("cuerpo \"{}\": {}", body.name, message));
+                let shader: ShaderType = body.shader.parse().map_err(invalid)?;
+                let mesh = mesh_kind(&body.mesh).map_err(invalid)?;
+
+                let mut config = BodyConfig::new(&body.name, shader, mesh, vec3(body.translation), body.scale);
+                if let Some(orbit) = &body.orbit {
+                    if !(0.0..=MAX_ECCENTRICITY).contains(&orbit.eccentricity) {
+                        return Err(invalid(format!("la excentricidad de la órbita debe estar entre 0 y {}", MAX_ECCENTRICITY)));
+                    }
+                }
+                config.orbit = body.orbit.as_ref().map(|orbit| {
+                    Orbit::circular(orbit.radius, orbit.speed, orbit.phase)
+                        .eccentric(orbit.eccentricity, orbit.periapsis.to_radians())
+                        .inclined(orbit.inclination.to_radians())
+                });
+                config.axis_scale = vec3(body.axis_scale);
+                config.rotation = match body.orientation {
+                    Some([x, y, z, w]) if x == 0.0 && y == 0.0 && z == 0.0 && w == 0.0 => {
+                        return Err(invalid(format!("la orientación de {} no puede ser un cuaternión nulo", body.name)));
+                    }
+                    Some([x, y, z, w]) => Quat::new(w, x, y, z).into(),
+                    None => vec3(body.rotation).into(),
+                };
+                config.spin = vec3(body.spin);
+                config.tilt = body.tilt.to_radians();
+                config.lod = body.lod.unwrap_or(config.lod);
+                config.two_sided = body.two_sided.unwrap_or(config.two_sided);
+                config.seed = body.seed;
+                config.apparent_size = body.apparent_size;
+                config.label = body.label;
+                if !(0.0..).contains(&body.mass) {
+                    return Err(invalid("la masa no puede ser negativa".to_string()));
+                }
+                config.mass = body.mass;
+                config.velocity = vec3(body.velocity);
+                if let Some(displacement) = &body.displacement {
+                    // Con amplitud 1 o más los valles atraviesan el centro y la malla se da vuelta
+                    if !(0.0..1.0).contains(&displacement.amplitude) || displacement.octaves == 0 {
+                        return Err(invalid("el relieve necesita 0 <= amplitude < 1 y al menos una octava".to_string()));
+                    }
+                }
+                config.displacement = body.displacement.map(|displacement| DisplaceParams {
+                    amplitude: displacement.amplitude,
+                    frequency: displacement.frequency,
+                    octaves: displacement.octaves,
+                });
+                if let Some(pulse) = &body.pulse {
+                    if !(0.0..1.0).contains(&pulse.amplitude) || pulse.octaves == 0 {
+                        return Err(invalid("la pulsación necesita 0 <= amplitude < 1 y al menos una octava".to_string()));
+                    }
+                }
+                config.vertex_animation = body.pulse.map(|pulse| VertexAnimParams {
+                    amplitude: pulse.amplitude,
+                    frequency: pulse.frequency,
+                    speed: pulse.speed,
+                    octaves: pulse.octaves,
+                });
+                if let Some(tail) = &body.tail {
+                    if !(tail.length > 0.0 && tail.falloff > 0.0 && tail.width >= 0.0) {
+                        return Err(invalid("la cola necesita length y falloff mayores que 0 y width no negativo".to_string()));
+                    }
+                }
+                config.ring_profile = match body.ring_profile.as_deref() {
+                    None => None,
+                    Some(_) if mesh != MeshKind::Ring => return Err(invalid("solo los anillos tienen perfil".to_string())),
+                    Some("saturn") => Some(RingProfile::saturn()),
+                    Some(path) => Some(Arc::new(RingProfile::load(Path::new(path)).map_err(invalid)?)),
+                };
+                config.tail = body.tail.map(|tail| CometTail {
+                    length: tail.length,
+                    width: tail.width,
+                    curvature: tail.curvature,
+                    falloff: tail.falloff,
+                    particles: tail.particles,
+                });
+                config.emitters = body.emitters.into_iter()
+                    .map(|emitter| ParticleEmitter {
+                        origin_dir: vec3(emitter.origin_dir),
+                        rate: emitter.rate,
+                        speed: emitter.speed,
+                        lifetime: emitter.lifetime,
+                        spread: emitter.spread,
+                    })
+                    .collect();
+                Ok(config)
+            })
+            .collect::<Result<Vec<_>, SceneFileError>>()?;
+
+        let lights = match file.lights {
+            Some(lights) => lights.into_iter()
+                .map(|light| {
+                    let [r, g, b] = light.color;
+                    let color = Color::new(r, g, b);
+                    match (light.direction, light.position) {
+                        (Some(direction), None) => Ok(Light::directional(vec3(direction), color, light.intensity)),
+                        (None, Some(position)) => Ok(Light::point(vec3(position), color, light.intensity)),
+                        _ => Err(SceneFileError::Invalid("cada luz necesita `direction` o `position` (solo uno)".to_string())),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => default_lights(),
+        };
+
+        if let Some(belt) = &file.belt {
+            if !(0.0 < belt.inner_radius && belt.inner_radius < belt.outer_radius) {
+                return Err(SceneFileError::Invalid("el cinturón necesita 0 < inner_radius < outer_radius".to_string()));
+            }
+        }
+
+        if let Some(stops) = &file.thermal_gradient {
+            let ordered = stops.windows(2).all(|pair| pair[0].at <= pair[1].at);
+            if stops.len() < 2 || !ordered || stops.iter().any(|stop| !(0.0..=1.0).contains(&stop.at)) {
+                return Err(SceneFileError::Invalid("thermal_gradient necesita al menos dos paradas con `at` entre 0 y 1, en orden".to_string()));
+            }
+        }
+
+        let anchors = file.anchors.into_iter()
+            .map(|anchor| {
+                let invalid = |message: String| SceneFileError::Invalid(format!("marca \"{}\": {}", anchor.label, message));
+                let index = bodies.iter().position(|body| body.name == anchor.body)
+                    .ok_or_else(|| invalid(format!("no hay un cuerpo llamado \"{}\"", anchor.body)))?;
+                let direction = vec3(anchor.direction).try_normalize(f32::EPSILON)
+                    .ok_or_else(|| invalid("`direction` no puede ser cero".to_string()))?;
+                Ok(SurfaceAnchor {
+                    body_id: body_id(index),
+                    direction,
+                    color: anchor.color.map_or(ANCHOR_COLOR, |[r, g, b]| Color::new(r, g, b)),
+                    label: anchor.label,
+                })
+            })
+            .collect::<Result<Vec<_>, SceneFileError>>()?;
+
+        let [r, g, b] = file.ambient;
+        let skylight = file.skylight.unwrap_or(SKYLIGHT_STRENGTH);
+        if !(skylight.is_finite() && skylight >= 0.0) {
+            return Err(SceneFileError::Invalid(format!("`skylight` no puede ser negativo y es {}", skylight)));
+        }
+        let skybox = match file.skybox {
+            Some(path) => Some(Arc::new(Skybox::load(Path::new(&path)).map_err(|err| SceneFileError::Invalid(format!("skybox: {}", err)))?)),
+            None => None,
+        };
+
+        Ok(SceneConfig {
+            bodies,
+            camera: file.camera.map(|camera| CameraConfig {
+                eye: vec3(camera.eye),
+                center: vec3(camera.center),
+                up: vec3(camera.up),
+                orthographic: camera.orthographic,
+            }),
+            background: file.background.unwrap_or(BACKGROUND_COLOR),
+            skybox,
+            lights,
+            ambient: Color::new(r, g, b),
+            skylight,
+            fog: file.fog.map(|fog| {
+                let [r, g, b] = fog.color;
+                FogParams { color: Color::new(r, g, b), start: fog.start, end: fog.end }
+            }),
+            grade: file.grade.map(|grade| {
+                let [r, g, b] = grade.white_balance;
+                ColorGrade { exposure: grade.exposure, saturation: grade.saturation, contrast: grade.contrast, white_balance: Color::new(r, g, b) }
+            }).unwrap_or_default(),
+            ambient_occlusion: file.ambient_occlusion.map(|occlusion| {
+                let default = AmbientOcclusion::DEFAULT;
+                AmbientOcclusion {
+                    radius: occlusion.radius.unwrap_or(default.radius),
+                    intensity: occlusion.intensity.unwrap_or(default.intensity),
+                    bias: occlusion.bias.unwrap_or(default.bias),
+                }
+            }).unwrap_or_default(),
+            tour: file.tour.into_iter()
+                .map(|keyframe| Keyframe { time: keyframe.time, eye: vec3(keyframe.eye), center: vec3(keyframe.center) })
+                .collect(),
+            belt: file.belt.map(|belt| BeltConfig {
+                count: belt.count,
+                inner_radius: belt.inner_radius,
+                outer_radius: belt.outer_radius,
+                thickness: belt.thickness,
+                seed: belt.seed,
+            }),
+            thermal_gradient: file.thermal_gradient
+                .map(|stops| stops.into_iter().map(|stop| {
+                    let [r, g, b] = stop.color;
+                    (stop.at, Color::new(r, g, b))
+                }).collect())
+                .unwrap_or_else(|| INFERNO.to_vec()),
+            physics: file.physics,
+            trails: file.trails,
+            anchors,
+        })
+    }
+}
@@ -0,0 +1,216 @@
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use crate::camera::Camera;
+use crate::clock::SimClock;
+use crate::loupe::Loupe;
+use crate::post::ColorGrade;
+use crate::quality::QualitySettings;
+use crate::scene::RenderOptions;
+use crate::shaders::{ShaderType, DEBUG_MODES};
+
+// Archivo de la sesión, en el directorio desde donde se corre el programa (o el de --session)
+pub const SESSION_FILE: &str = "session.toml";
+
+// Separación de los ojos en el modo estéreo, como fracción de la distancia al centro, y su rango
+pub const STEREO_SEPARATION: f32 = 0.03;
+pub const STEREO_SEPARATION_RANGE: (f32, f32) = (0.005, 0.2);
+
+// Todo lo que se cambia con el teclado mientras corre el programa: escena, cámara, reloj, opciones de
+// dibujo y efectos. El bucle principal lee y escribe esto en lugar de variables sueltas, y se guarda
+// entero en la sesión. Los campos que faltan en el archivo toman el valor por defecto y los que no se
+// conocen se ignoran, así las sesiones viejas siguen sirviendo cuando se agregan opciones
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppState {
+    pub scene: u32,
+    // Archivo de --scene-file de la escena 9, que se vuelve a cargar al restaurar
+    pub scene_file: Option<String>,
+    #[serde(with = "camera_pose")]
+    pub camera: Camera,
+    pub clock: SimClock,
+    pub options: RenderOptions,
+    // Corrección de color ajustada en vivo; None = la de la escena
+    pub grade: Option<ColorGrade>,
+    pub quality: QualitySettings,
+    pub dither: bool,
+    pub show_depth: bool,
+    pub motion_blur: bool,
+    pub lens_flare: bool,
+    // Curvas de --lut (sin --lut no hacen nada)
+    pub use_lut: bool,
+    pub still: bool,
+    pub progressive: bool,
+    pub checkerboard: bool,
+    pub anaglyph: bool,
+    pub stereo_separation: f32,
+    pub minimap: bool,
+    pub show_labels: bool,
+    pub frame_diff: bool,
+    pub loupe: Option<Loupe>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        AppState {
+            scene: 1,
+            scene_file: None,
+            camera: default_camera(),
+            clock: SimClock::new(),
+            options: RenderOptions::default(),
+            grade: None,
+            quality: QualitySettings::default(),
+            dither: false,
+            show_depth: false,
+            motion_blur: false,
+            lens_flare: false,
+            use_lut: true,
+            still: false,
+            progressive: false,
+            checkerboard: false,
+            anaglyph: false,
+            stereo_separation: STEREO_SEPARATION,
+            minimap: false,
+            show_labels: true,
+            frame_diff: false,
+            loupe: None,
+        }
+    }
+}
+
+// Opciones que se pasaron en la línea de comandos; al retomar la sesión mandan sobre lo guardado. Las
+// banderas solo pueden prender algo, así que false quiere decir que no se pasaron y queda lo de la sesión
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommandLineOptions {
+    pub seed: Option<u32>,
+    pub model_shader: Option<ShaderType>,
+    pub fragment_budget: Option<usize>,
+    pub toon: bool,
+    pub graticule: bool,
+    pub particle_rings: bool,
+    pub adaptive_ring: bool,
+    pub ambient_occlusion: bool,
+    pub starfield: bool,
+    pub thermal: bool,
+    pub cull_backfaces: bool,
+    pub saturn_rings: bool,
+    pub dither: bool,
+    pub progressive: bool,
+    pub checkerboard: bool,
+}
+
+fn default_camera() -> Camera {
+    Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+}
+
+impl AppState {
+    // Ok(None) si el archivo no existe; si existe pero no se puede leer es un error
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        AppState::from_toml(&source).map(Some)
+    }
+
+    pub fn from_toml(source: &str) -> Result<Self, String> {
+        toml::from_str::<AppState>(source)
+            .map(AppState::sanitized)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        toml::to_string(self).map_err(|err| err.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let source = self.to_toml()?;
+        std::fs::write(path, source).map_err(|err| err.to_string())
+    }
+
+    // La sesión con las opciones de la línea de comandos encima
+    pub fn with_command_line(mut self, command_line: &CommandLineOptions) -> Self {
+        let options = &mut self.options;
+        options.seed = command_line.seed.unwrap_or(options.seed);
+        options.model_shader = command_line.model_shader.or(options.model_shader);
+        options.fragment_budget = command_line.fragment_budget.unwrap_or(options.fragment_budget);
+        options.toon |= command_line.toon;
+        options.graticule |= command_line.graticule;
+        options.particle_rings |= command_line.particle_rings;
+        options.adaptive_ring |= command_line.adaptive_ring;
+        options.ambient_occlusion |= command_line.ambient_occlusion;
+        options.starfield |= command_line.starfield;
+        options.thermal |= command_line.thermal;
+        options.cull_backfaces |= command_line.cull_backfaces;
+        options.saturn_rings |= command_line.saturn_rings;
+        self.dither |= command_line.dither;
+        self.progressive |= command_line.progressive;
+        self.checkerboard |= command_line.checkerboard;
+        self
+    }
+
+    // Valores de un archivo editado a mano que no se pueden dibujar vuelven a los de defecto
+    fn sanitized(mut self) -> Self {
+        let camera = &self.camera;
+        let finite = camera.eye.iter().chain(camera.center.iter()).chain(camera.up.iter()).all(|value| value.is_finite());
+        if !finite || camera.eye == camera.center || camera.up.magnitude() == 0.0 {
+            self.camera = default_camera();
+        }
+        self.clock = self.clock.sanitized();
+        self.loupe = self.loupe.map(Loupe::sanitized);
+        self.options.debug_mode %= DEBUG_MODES;
+        let (min, max) = STEREO_SEPARATION_RANGE;
+        self.stereo_separation = if self.stereo_separation.is_finite() { self.stereo_separation.clamp(min, max) } else { STEREO_SEPARATION };
+        self
+    }
+}
+
+// La cámara se guarda como en los marcadores: ojo, centro, arriba y el alto de la ortográfica si lo es
+mod camera_pose {
+    use nalgebra_glm::Vec3;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use crate::camera::{Camera, Projection};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(default)]
+    struct CameraPose {
+        eye: [f32; 3],
+        center: [f32; 3],
+        up: [f32; 3],
+        #[serde(skip_serializing_if = "Option::is_none")]
+        orthographic_height: Option<f32>,
+    }
+
+    impl Default for CameraPose {
+        fn default() -> Self {
+            CameraPose::from(&super::default_camera())
+        }
+    }
+
+    impl From<&Camera> for CameraPose {
+        fn from(camera: &Camera) -> Self {
+            CameraPose {
+                eye: camera.eye.into(),
+                center: camera.center.into(),
+                up: camera.up.into(),
+                orthographic_height: match camera.projection {
+                    Projection::Perspective => None,
+                    Projection::Orthographic { height } => Some(height),
+                },
+            }
+        }
+    }
+
+    pub fn serialize<S: Serializer>(camera: &Camera, serializer: S) -> Result<S::Ok, S::Error> {
+        CameraPose::from(camera).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Camera, D::Error> {
+        let pose = CameraPose::deserialize(deserializer)?;
+        let mut camera = Camera::new(Vec3::from(pose.eye), Vec3::from(pose.center), Vec3::from(pose.up));
+        if let Some(height) = pose.orthographic_height.filter(|height| height.is_finite() && *height > 0.0) {
+            camera.projection = Projection::Orthographic { height };
+        }
+        Ok(camera)
+    }
+}
@@ -0,0 +1,1129 @@
+use nalgebra_glm::{Mat4, Vec2, Vec3, Vec4, quat_angle_axis, quat_rotate_vec3};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+use crate::belt::BeltConfig;
+use crate::camera::Camera;
+use crate::color::{Color, INFERNO};
+use crate::fragments::Fragments;
+use crate::golden::{render_case, GoldenCase};
+use crate::framebuffer::{BlendMode, Framebuffer, Rect};
+use crate::post::{diff_view, DIFF_DIM};
+use crate::ring_profile::RingProfile;
+use crate::pipeline::{build_uniforms, create_model_matrix, diffuse_light, draw_order, render, FragmentBudget, Light, Planetshine, RenderScratch, RenderStats, RingShadow, BodyUniforms, FrameUniforms, VertexAnimParams, DEFAULT_DEPTH_RANGE};
+use crate::shaders::{biome_altitude, biome_index, earth_is_land, earth_land, animate_vertex, fragment_shader, lava_factor, spherical_uv, triplanar_sample, triplanar_uvs, vertex_shader, ring_transmittance, FragmentShader, SceneShader, ShaderType, DEBUG_MODES, GAS_PLANET_BANDS, ROCKY_BIOMES, SHADER_NAMES, SUN};
+use crate::lod::select_ring_tier;
+use crate::mesh::{displace, generate_icosphere, generate_ring, generate_ring_tiers, generate_rock, generate_uv_sphere, RING_INNER_RADIUS, RING_OUTER_RADIUS, RING_SEGMENT_TIERS, ROCK_SEED};
+use crate::scene::{BodyConfig, MeshKind, RenderOptions, SceneConfig, SceneMeshes, BACKGROUND_COLOR, SKYLIGHT_STRENGTH};
+use crate::triangle::triangle_for_each;
+use crate::vertex::Vertex;
+
+// Revisión de los shaders sin dibujar nada: cada shader se evalúa en puntos conocidos y se comparan
+// los colores que devuelve. Se corre con --check-shaders
+
+// Radio de la esfera de los planetas en unidades del objeto
+const SPHERE_RADIUS: f32 = 0.5;
+
+// Fragmento armado a mano; por defecto es el centro de la esfera visto de frente con luz completa
+pub struct FragmentBuilder {
+    fragment: Fragments,
+}
+
+impl FragmentBuilder {
+    pub fn new() -> Self {
+        Self::on_sphere(Vec3::new(0.0, 0.0, 1.0))
+    }
+
+    // Punto de la esfera en esa dirección, con la normal hacia afuera
+    pub fn on_sphere(direction: Vec3) -> Self {
+        let normal = direction.normalize();
+        let position = normal * SPHERE_RADIUS;
+        let fragment = Fragments::new(Vec2::new(0.0, 0.0), 0.0, normal, 1.0, position, Color::new(255, 255, 255), position);
+        FragmentBuilder { fragment }
+    }
+
+    pub fn position(mut self, x: f32, y: f32) -> Self {
+        self.fragment.position = Vec2::new(x, y);
+        self
+    }
+
+    // Posición en el objeto (la que usan los patrones de los shaders)
+    pub fn vertex_pos(mut self, position: Vec3) -> Self {
+        self.fragment.vertex_pos = position;
+        self
+    }
+
+    pub fn world_pos(mut self, position: Vec3) -> Self {
+        self.fragment.world_pos = position;
+        self
+    }
+
+    pub fn normal(mut self, normal: Vec3) -> Self {
+        self.fragment.normal = normal.normalize();
+        self
+    }
+
+    pub fn depth(mut self, depth: f32) -> Self {
+        self.fragment.depth = depth;
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.fragment.color = color;
+        self
+    }
+
+    // Luz blanca uniforme de esa intensidad
+    pub fn intensity(mut self, intensity: f32) -> Self {
+        self.fragment.intensity = intensity;
+        self.fragment.light = Vec3::repeat(intensity);
+        self
+    }
+
+    // Iluminación calculada con las luces de los uniforms, igual que en el pipeline
+    pub fn lit_by(mut self, frame: &FrameUniforms) -> Self {
+        let light = diffuse_light(&self.fragment.normal, &self.fragment.world_pos, &frame.lights, &frame.ambient_light(&self.fragment.normal));
+        self.fragment.light = light;
+        self.fragment.intensity = (light.x + light.y + light.z) / 3.0;
+        self
+    }
+
+    pub fn build(self) -> Fragments {
+        self.fragment
+    }
+}
+
+impl Default for FragmentBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Uniforms de una cámara que mira al origen desde +z, con las luces por defecto
+pub struct UniformsBuilder {
+    eye: Vec3,
+    time: f32,
+    seed: u32,
+    lights: Option<Vec<Light>>,
+    debug_mode: u32,
+    model_matrix: Mat4,
+    planetshine: Option<Planetshine>,
+    ring_shadow: Option<RingShadow>,
+}
+
+impl UniformsBuilder {
+    pub fn new() -> Self {
+        UniformsBuilder {
+            eye: Vec3::new(0.0, 0.0, 5.0),
+            time: 0.0,
+            seed: 0,
+            lights: None,
+            debug_mode: 0,
+            model_matrix: Mat4::identity(),
+            planetshine: None,
+            ring_shadow: None,
+        }
+    }
+
+    pub fn eye(mut self, eye: Vec3) -> Self {
+        self.eye = eye;
+        self
+    }
+
+    pub fn time(mut self, time: f32) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn lights(mut self, lights: Vec<Light>) -> Self {
+        self.lights = Some(lights);
+        self
+    }
+
+    pub fn debug_mode(mut self, debug_mode: u32) -> Self {
+        self.debug_mode = debug_mode;
+        self
+    }
+
+    pub fn model_matrix(mut self, model_matrix: Mat4) -> Self {
+        self.model_matrix = model_matrix;
+        self
+    }
+
+    pub fn planetshine(mut self, planetshine: Planetshine) -> Self {
+        self.planetshine = Some(planetshine);
+        self
+    }
+
+    pub fn ring_shadow(mut self, ring_shadow: RingShadow) -> Self {
+        self.ring_shadow = Some(ring_shadow);
+        self
+    }
+
+    pub fn build(self) -> (FrameUniforms, BodyUniforms) {
+        let camera = Camera::new(self.eye, Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let uniforms = build_uniforms(&camera, self.time, 800, 600, DEFAULT_DEPTH_RANGE);
+        let frame = FrameUniforms {
+            debug_mode: self.debug_mode,
+            lights: self.lights.unwrap_or(uniforms.lights.clone()),
+            ..uniforms
+        };
+        let body = BodyUniforms {
+            seed: self.seed,
+            planetshine: self.planetshine,
+            ring_shadow: self.ring_shadow,
+            ..BodyUniforms::new(self.model_matrix)
+        };
+        (frame, body)
+    }
+}
+
+impl Default for UniformsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Resultado de una revisión
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &str, passed: bool, detail: String) -> Self {
+        Check { name: name.to_string(), passed, detail }
+    }
+}
+
+fn luminance(color: &Color) -> f32 {
+    0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+fn is_finite(color: &Color) -> bool {
+    color.r.is_finite() && color.g.is_finite() && color.b.is_finite()
+}
+
+fn shade(shader: ShaderType, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+    fragment_shader(fragment, frame, body, &shader)
+}
+
+// Direcciones de la esfera cada `steps` pasos en longitud y latitud, sin llegar a los polos
+fn sphere_samples(steps: usize) -> impl Iterator<Item = Vec3> {
+    (0..steps).flat_map(move |i| (1..steps).map(move |j| {
+        let longitude = i as f32 / steps as f32 * std::f32::consts::TAU;
+        let latitude = (j as f32 / steps as f32 - 0.5) * std::f32::consts::PI;
+        Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin())
+    }))
+}
+
+// Todas las revisiones, en orden
+pub fn run_checks() -> Vec<Check> {
+    let mut checks = vec![sun_limb(), earth_surface(), volcanic_lava(), icy_specular(), moon_phases(), moon_planetshine(), oblate_normals(), asteroid_belt(), rock_facets(), displaced_mesh(), spherical_uv_mapping(), transparency_order(), tilted_bands(), frame_diff(), watertight_edges(), rocky_biomes(), ring_shadow(), ring_profile_shadow(), custom_shader(), ring_tiers(), coastline_edges(), comet_tail(), triplanar_blend(), starfield_mask(), sun_pulsation(), thermal_view(), differential_rotation()];
+    checks.extend(every_debug_mode());
+    checks
+}
+
+// El centro del sol visto de frente es más brillante que el borde
+fn sun_limb() -> Check {
+    let (frame, body) = UniformsBuilder::new().build();
+    let center = shade(ShaderType::Sun, &FragmentBuilder::new().build(), &frame, &body);
+    let limb = shade(ShaderType::Sun, &FragmentBuilder::on_sphere(Vec3::new(0.95, 0.0, 0.3)).build(), &frame, &body);
+    Check::new(
+        "sol: el centro brilla más que el borde",
+        luminance(&center) > luminance(&limb),
+        format!("centro {:?}, borde {:?}", center, limb),
+    )
+}
+
+// En la Tierra, un punto de océano es más azul que verde y uno de tierra más verde que azul
+// (se buscan puntos iluminados y sin nubes para que solo cuente la superficie)
+fn earth_surface() -> Check {
+    let (frame, body) = UniformsBuilder::new().build();
+    let (clouds, _) = UniformsBuilder::new().debug_mode(2).build();
+    let clear_points: Vec<(bool, Color)> = sphere_samples(24)
+        .filter(|direction| direction.z > 0.5)
+        .map(|direction| FragmentBuilder::on_sphere(direction).lit_by(&frame).build())
+        .filter(|fragment| luminance(&shade(ShaderType::Earth, fragment, &clouds, &body)) == 0.0)
+        .map(|fragment| (earth_is_land(&fragment.vertex_pos), shade(ShaderType::Earth, &fragment, &frame, &body)))
+        .collect();
+
+    let ocean = clear_points.iter().find(|(land, _)| !land).map(|(_, color)| *color);
+    let land = clear_points.iter().find(|(land, _)| *land).map(|(_, color)| *color);
+    match (ocean, land) {
+        (Some(ocean), Some(land)) => Check::new(
+            "tierra: el océano es azul y los continentes verdes",
+            ocean.b > ocean.g && land.g > land.b,
+            format!("océano {:?}, tierra {:?}", ocean, land),
+        ),
+        _ => Check::new("tierra: el océano es azul y los continentes verdes", false, "no hay puntos sin nubes".to_string()),
+    }
+}
+
+// En el planeta volcánico la lava brilla (y es roja) más que la roca, incluso sin luz
+fn volcanic_lava() -> Check {
+    let (frame, body) = UniformsBuilder::new().lights(Vec::new()).build();
+    let candidates: Vec<Fragments> = sphere_samples(64)
+        .map(|direction| FragmentBuilder::on_sphere(direction).intensity(0.0).build())
+        .collect();
+    let lava = candidates.iter().find(|fragment| lava_factor(&fragment.vertex_pos, frame.time) > 0.9);
+    let rock = candidates.iter().find(|fragment| lava_factor(&fragment.vertex_pos, frame.time) == 0.0);
+
+    match (lava, rock) {
+        (Some(lava), Some(rock)) => {
+            let (lava, rock) = (shade(ShaderType::VolcanicPlanet, lava, &frame, &body), shade(ShaderType::VolcanicPlanet, rock, &frame, &body));
+            Check::new(
+                "volcánico: la lava emite luz roja y la roca no",
+                luminance(&lava) > luminance(&rock) + 10.0 && lava.r > lava.b,
+                format!("lava {:?}, roca {:?}", lava, rock),
+            )
+        }
+        _ => Check::new("volcánico: la lava emite luz roja y la roca no", false, "no se encontraron puntos de lava y de roca".to_string()),
+    }
+}
+
+// El brillo especular del hielo es máximo con la luz en la dirección del reflejo hacia la cámara
+// y casi nulo con la luz perpendicular
+fn icy_specular() -> Check {
+    let white = Color::new(255, 255, 255);
+    let fragment = FragmentBuilder::new().build();
+    let specular = |light_direction: Vec3| {
+        let (frame, body) = UniformsBuilder::new()
+            .debug_mode(3)
+            .lights(vec![Light::directional(light_direction, white, 1.0)])
+            .build();
+        shade(ShaderType::IcyPlanet, &fragment, &frame, &body)
+    };
+    let mirror = specular(Vec3::new(0.0, 0.0, 1.0));
+    let perpendicular = specular(Vec3::new(1.0, 0.0, 0.0));
+    Check::new(
+        "hielo: el reflejo especular sigue la dirección del espejo",
+        is_finite(&mirror) && luminance(&mirror) > 200.0 && luminance(&perpendicular) < 5.0,
+        format!("espejo {:?}, perpendicular {:?}", mirror, perpendicular),
+    )
+}
+
+// Luna de la escena 5: radio de la órbita y de la esfera, con el planeta rocoso en el origen
+const MOON_ORBIT: f32 = 1.3;
+const MOON_RADIUS: f32 = SPHERE_RADIUS * 0.5;
+// Luminancia a partir de la cual un punto de la luna cuenta como iluminado por el sol
+const MOON_LIT_LUMINANCE: f32 = 40.0;
+
+// Planeta rocoso en el origen como fuente de la luz reflejada
+fn rocky_planetshine() -> Planetshine {
+    let color = ShaderType::RockyPlanet.albedo().expect("el planeta rocoso tiene color");
+    Planetshine { position: Vec3::zeros(), radius: SPHERE_RADIUS, color }
+}
+
+// Fracción iluminada del disco de la luna vista desde el centro del planeta, con el sol en +z y la
+// luna a `angle` radianes del sol en su órbita; cada punto pesa según el área que ocupa en el disco
+fn moon_lit_fraction(angle: f32) -> f32 {
+    let (frame, body) = UniformsBuilder::new()
+        .lights(vec![Light::directional(Vec3::new(0.0, 0.0, 1.0), Color::new(255, 255, 255), 1.0)])
+        .planetshine(rocky_planetshine())
+        .build();
+    let center = Vec3::new(angle.sin(), 0.0, angle.cos()) * MOON_ORBIT;
+    let toward_planet = -center.normalize();
+    let (lit, total) = sphere_samples(48).fold((0.0, 0.0), |(lit, total), direction| {
+        let weight = direction.dot(&toward_planet).max(0.0) * (1.0 - direction.y * direction.y).sqrt();
+        if weight == 0.0 {
+            return (lit, total);
+        }
+        let fragment = FragmentBuilder::on_sphere(direction)
+            .world_pos(center + direction * MOON_RADIUS)
+            .lit_by(&frame)
+            .build();
+        let is_lit = luminance(&shade(ShaderType::Moon, &fragment, &frame, &body)) > MOON_LIT_LUMINANCE;
+        (lit + if is_lit { weight } else { 0.0 }, total + weight)
+    });
+    lit / total
+}
+
+// La luna pasa por sus fases al orbitar: llena del lado opuesto al sol, media a 90° y creciente
+// cerca del sol; la luna nueva (entre el planeta y el sol) solo tiene la luz reflejada
+fn moon_phases() -> Check {
+    let [full, half, crescent, new] = [PI, FRAC_PI_2, FRAC_PI_4, 0.0].map(moon_lit_fraction);
+    Check::new(
+        "luna: fases según la posición respecto al sol",
+        full > half && half > crescent && crescent > new,
+        format!("llena {:.2}, media {:.2}, creciente {:.2}, nueva {:.2}", full, half, crescent, new),
+    )
+}
+
+// El lado nocturno de la luna que mira al planeta recibe un poco de luz rojiza del planeta rocoso,
+// más si está más cerca
+fn moon_planetshine() -> Check {
+    let lights = vec![Light::directional(Vec3::new(0.0, 0.0, 1.0), Color::new(255, 255, 255), 1.0)];
+    let night_side = |distance: f32, planetshine: bool| {
+        let mut builder = UniformsBuilder::new().lights(lights.clone());
+        if planetshine {
+            builder = builder.planetshine(rocky_planetshine());
+        }
+        let (frame, body) = builder.build();
+        // Luna nueva, entre el planeta y el sol: el punto mira hacia el planeta (que se ve lleno) y le da
+        // la espalda al sol
+        let fragment = FragmentBuilder::on_sphere(Vec3::new(0.0, 0.0, -1.0))
+            .world_pos(Vec3::new(0.0, 0.0, distance - MOON_RADIUS))
+            .lit_by(&frame)
+            .build();
+        shade(ShaderType::Moon, &fragment, &frame, &body)
+    };
+    let (dark, near, far) = (night_side(MOON_ORBIT, false), night_side(MOON_ORBIT, true), night_side(2.0 * MOON_ORBIT, true));
+    Check::new(
+        "luna: el lado nocturno recibe la luz rojiza del planeta",
+        luminance(&dark) == 0.0 && luminance(&near) > luminance(&far) && luminance(&far) > 0.0 && near.r > near.b,
+        format!("sin planeta {:?}, cerca {:?}, lejos {:?}", dark, near, far),
+    )
+}
+
+// En una esfera achatada e inclinada las normales del vertex shader siguen unitarias, hacia afuera y
+// perpendiculares a la superficie del elipsoide
+fn oblate_normals() -> Check {
+    let (translation, scale, rotation) = (Vec3::new(1.0, 2.0, 3.0), Vec3::new(1.2, 1.2 * 0.93, 1.2), Vec3::new(0.3, 0.5, 0.2));
+    let (frame, body) = UniformsBuilder::new().model_matrix(create_model_matrix(translation, scale, rotation)).build();
+    let rotation_matrix = create_model_matrix(Vec3::zeros(), Vec3::new(1.0, 1.0, 1.0), rotation);
+
+    let worst = sphere_samples(24).map(|direction| {
+        let vertex = vertex_shader(&Vertex::new(direction * SPHERE_RADIUS, direction, Vec2::new(0.0, 0.0)), &frame, &body);
+        let normal = vertex.transformed_normal;
+        // Gradiente del elipsoide: la dirección de la esfera dividida por la escala de cada eje, ya rotada
+        let gradient = rotation_matrix * Vec4::new(direction.x / scale.x, direction.y / scale.y, direction.z / scale.z, 0.0);
+        let expected = Vec3::new(gradient.x, gradient.y, gradient.z).normalize();
+        let outward = normal.dot(&(vertex.world_position - translation)) > 0.0;
+        let error = (normal.magnitude() - 1.0).abs().max((normal - expected).magnitude());
+        if outward { error } else { f32::INFINITY }
+    }).fold(0.0f32, f32::max);
+    Check::new(
+        "vértices: normales unitarias y hacia afuera con escala distinta en cada eje",
+        worst < 1e-4,
+        format!("error máximo {}", worst),
+    )
+}
+
+// Las rocas del cinturón no salen de la franja mientras orbitan y la misma semilla da el mismo cinturón
+fn asteroid_belt() -> Check {
+    let belt = BeltConfig { count: 300, inner_radius: 10.6, outer_radius: 11.5, thickness: 0.5, seed: 8 };
+    let asteroids = belt.asteroids(7);
+    let outside = [0.0, 1000.0, 50_000.0].iter()
+        .flat_map(|&time| asteroids.iter().map(move |asteroid| asteroid.position(time)))
+        .filter(|position| {
+            let radius = Vec2::new(position.x, position.z).magnitude();
+            radius < belt.inner_radius - 1e-3 || radius > belt.outer_radius + 1e-3 || position.y.abs() > belt.thickness / 2.0 + 1e-3
+        })
+        .count();
+    let repeatable = belt.asteroids(7) == asteroids && belt.asteroids(8) != asteroids;
+    Check::new(
+        "cinturón: las rocas quedan dentro de la franja y dependen solo de la semilla",
+        outside == 0 && repeatable,
+        format!("{} posiciones fuera de la franja, repetible {}", outside, repeatable),
+    )
+}
+
+// Las caras de la roca de los asteroides miran hacia afuera aunque el ruido mueva los vértices
+fn rock_facets() -> Check {
+    let rock = generate_rock(ROCK_SEED);
+    let inward = rock.chunks_exact(3)
+        .filter(|triangle| {
+            let center = (triangle[0].position + triangle[1].position + triangle[2].position) / 3.0;
+            triangle[0].normal.dot(&center) <= 0.0
+        })
+        .count();
+    Check::new(
+        "roca: todas las caras miran hacia afuera",
+        !rock.is_empty() && inward == 0,
+        format!("{} de {} caras hacia adentro", inward, rock.len() / 3),
+    )
+}
+
+// El relieve no abre grietas: los vértices que estaban en el mismo lugar (la costura y los polos de la
+// esfera UV, las aristas compartidas de la icosfera) siguen juntos, y las normales nuevas miden 1
+fn displaced_mesh() -> Check {
+    let failures: Vec<String> = [("esfera UV", generate_uv_sphere(12, 24)), ("icosfera", generate_icosphere(2))].iter().filter_map(|(name, mesh)| {
+        let displaced = displace(mesh, 7, 0.2, 3.0, 4);
+        let mut separated = 0;
+        for i in 0..mesh.len() {
+            for j in i + 1..mesh.len() {
+                let coincident = (mesh[i].position - mesh[j].position).magnitude() < 1e-5;
+                if coincident && (displaced[i].position - displaced[j].position).magnitude() > 1e-5 {
+                    separated += 1;
+                }
+            }
+        }
+        let moved = mesh.iter().zip(&displaced).filter(|(before, after)| (before.position - after.position).magnitude() > 1e-3).count();
+        let not_unit = displaced.iter().filter(|vertex| (vertex.normal.magnitude() - 1.0).abs() > 1e-4).count();
+        (displaced.len() != mesh.len() || separated + not_unit > 0 || moved == 0).then(|| {
+            format!("{}: {} pares de vértices separados, {} normales que no miden 1, {} de {} vértices movidos", name, separated, not_unit, moved, mesh.len())
+        })
+    }).collect();
+    Check::new(
+        "relieve: los vértices compartidos se mueven juntos y las normales miden 1",
+        failures.is_empty(),
+        failures.join("; "),
+    )
+}
+
+// Cuadrado de frente a la cámara, de un solo color
+fn flat_square(color: Color) -> Vec<Vertex> {
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+    corners.iter().map(|&(x, y)| Vertex {
+        color,
+        ..Vertex::new(Vec3::new(x, y, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.0, 0.0))
+    }).collect()
+}
+
+// Dibuja las capas en el orden de draw_order y devuelve el color del centro
+fn layered_center(frame: &FrameUniforms, layers: &[(BodyUniforms, Vec<Vertex>)]) -> Color {
+    let mut framebuffer = Framebuffer::new(32, 32);
+    let mut budget = FragmentBudget::new(usize::MAX, usize::MAX);
+    let mut scratch = RenderScratch::new();
+    let draws: Vec<BodyUniforms> = layers.iter().map(|(body, _)| body.clone()).collect();
+    for index in draw_order(frame, &draws) {
+        render(&mut framebuffer, frame, &layers[index].0, &layers[index].1, &ShaderType::Flat, &mut budget, &mut RenderStats::default(), &mut scratch);
+    }
+    Color::from_hex(framebuffer.buffer[16 * framebuffer.width + 16])
+}
+
+// Un cuadrado opaco rojo atrás y dos translúcidos (verde y azul) adelante: el resultado no depende
+// del orden en que llegan y es la mezcla de atrás hacia adelante
+fn transparency_order() -> Check {
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let frame = build_uniforms(&camera, 0.0, 32, 32, DEFAULT_DEPTH_RANGE);
+    let layer = |color: Color, z: f32, blend: BlendMode| {
+        let body = BodyUniforms {
+            blend,
+            opacity: 0.5,
+            ..BodyUniforms::new(create_model_matrix(Vec3::new(0.0, 0.0, z), Vec3::repeat(1.0), Vec3::zeros()))
+        };
+        (body, flat_square(color))
+    };
+    let red = layer(Color::new(255, 0, 0), -1.0, BlendMode::Opaque);
+    let green = layer(Color::new(0, 255, 0), 0.0, BlendMode::AlphaBlend);
+    let blue = layer(Color::new(0, 0, 255), 1.0, BlendMode::AlphaBlend);
+
+    // Color de cada capa dibujada sola, ya iluminada
+    let alone = |(body, vertices): &(BodyUniforms, Vec<Vertex>)| layered_center(&frame, &[(BodyUniforms { blend: BlendMode::Opaque, ..body.clone() }, vertices.clone())]);
+    let expected = alone(&red).lerp(&alone(&green), 0.5).lerp(&alone(&blue), 0.5);
+
+    let back_to_front = layered_center(&frame, &[red.clone(), green.clone(), blue.clone()]);
+    let front_to_back = layered_center(&frame, &[blue, green, red]);
+    let close = |color: &Color| (color.r - expected.r).abs().max((color.g - expected.g).abs()).max((color.b - expected.b).abs()) <= 2.0;
+    Check::new(
+        "transparencia: el resultado no depende del orden en que se envían los cuerpos",
+        close(&back_to_front) && close(&front_to_back) && luminance(&expected) > 0.0,
+        format!("esperado {:?}, de atrás hacia adelante {:?}, de adelante hacia atrás {:?}", expected, back_to_front, front_to_back),
+    )
+}
+
+// Coordenadas UV de los ejes (-x va con la costura), de puntos pegados a los polos y a los dos lados de la costura,
+// a cualquier radio: siempre finitas y en [0, 1]
+fn spherical_uv_mapping() -> Check {
+    let axes = [
+        (Vec3::new(1.0, 0.0, 0.0), Vec2::new(0.5, 0.5)),
+        (Vec3::new(0.0, 0.0, 1.0), Vec2::new(0.75, 0.5)),
+        (Vec3::new(0.0, 0.0, -1.0), Vec2::new(0.25, 0.5)),
+        (Vec3::new(0.0, 1.0, 0.0), Vec2::new(0.5, 0.0)),
+        (Vec3::new(0.0, -1.0, 0.0), Vec2::new(0.5, 1.0)),
+    ];
+    let mut failures: Vec<String> = axes.iter()
+        .flat_map(|&(direction, expected)| [0.5, 1.0, 3.0].map(|radius| (direction * radius, expected)))
+        .filter_map(|(position, expected)| {
+            let uv = spherical_uv(position);
+            ((uv - expected).magnitude() > 1e-5).then(|| format!("{:?}: {:?} en lugar de {:?}", position, uv, expected))
+        })
+        .collect();
+
+    // -x está sobre la costura: u puede ser 0 o 1, pero a un lado y al otro tiene que acercarse a cada extremo
+    let seam = spherical_uv(Vec3::new(-1.0, 0.0, 0.0));
+    let before = spherical_uv(Vec3::new(-1.0, 0.0, 1e-4));
+    let after = spherical_uv(Vec3::new(-1.0, 0.0, -1e-4));
+    if (seam.x != 0.0 && seam.x != 1.0) || before.x < 0.99 || after.x > 0.01 {
+        failures.push(format!("costura: {:?}, antes {:?}, después {:?}", seam, before, after));
+    }
+
+    // Pegados a los polos, los polos con ceros negativos, el centro y la costura con -0
+    let edge_cases = [1e-7, 1e-4, -1e-7].iter()
+        .flat_map(|&offset| [1.0, -1.0].map(|y| Vec3::new(offset, y, -offset) * 2.0))
+        .chain([Vec3::new(-0.0, 1.0, -0.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, -0.0)]);
+    for position in edge_cases {
+        let uv = spherical_uv(position);
+        if !uv.iter().all(|value| value.is_finite() && (0.0..=1.0).contains(value)) {
+            failures.push(format!("{:?}: {:?}", position, uv));
+        }
+    }
+
+    Check::new("uv esféricas: ejes, polos y costura sin valores fuera de [0, 1]", failures.is_empty(), failures.join("; "))
+}
+
+// Ángulo en pantalla (en grados, entre 0 y 180) de la recta que más separa los colores del disco:
+// se prueba cada dirección, se agrupan los pixeles por su distancia al centro en esa dirección y
+// gana la que deja los grupos más distintos entre sí, o sea la que cruza las franjas
+fn band_normal_angle(framebuffer: &Framebuffer, center: Vec2, radius: f32) -> f32 {
+    const BINS: usize = 24;
+    let pixels: Vec<(Vec2, f32)> = (0..framebuffer.height)
+        .flat_map(|y| (0..framebuffer.width).map(move |x| (x, y)))
+        .map(|(x, y)| (Vec2::new(x as f32, y as f32) - center, framebuffer.buffer[y * framebuffer.width + x]))
+        .filter(|(offset, _)| offset.magnitude() < radius)
+        .map(|(offset, hex)| (offset / radius, luminance(&Color::from_hex(hex))))
+        .collect();
+    let spread = |degrees: f32| {
+        let direction = Vec2::new(degrees.to_radians().cos(), degrees.to_radians().sin());
+        let mut bins = [(0.0, 0); BINS];
+        for (offset, value) in &pixels {
+            let bin = (((offset.dot(&direction) + 1.0) / 2.0 * BINS as f32) as usize).min(BINS - 1);
+            bins[bin].0 += value;
+            bins[bin].1 += 1;
+        }
+        let means: Vec<f32> = bins.iter().filter(|(_, count)| *count > 0).map(|(sum, count)| sum / *count as f32).collect();
+        let average = means.iter().sum::<f32>() / means.len() as f32;
+        means.iter().map(|mean| (mean - average).powi(2)).sum::<f32>()
+    };
+    (0..180).map(|degrees| degrees as f32).max_by(|a, b| spread(*a).total_cmp(&spread(*b))).unwrap_or(0.0)
+}
+
+// Ángulo en pantalla del eje de giro proyectado y de la dirección que cruza las franjas, con el
+// planeta gaseoso inclinado `tilt` grados y dibujado solo con las franjas
+fn band_angles(tilt: f32) -> (f32, f32) {
+    let mut config = BodyConfig::new("Planeta gaseoso", ShaderType::GasPlanet, MeshKind::Sphere, Vec3::zeros(), 1.0);
+    config.tilt = tilt.to_radians();
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 2.5), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let frame = FrameUniforms { debug_mode: 1, ..build_uniforms(&camera, 0.0, 160, 160, DEFAULT_DEPTH_RANGE) };
+    let body = BodyUniforms::new(config.at(0.0).model_matrix());
+
+    let mut framebuffer = Framebuffer::new(160, 160);
+    let mut budget = FragmentBudget::new(usize::MAX, usize::MAX);
+    render(&mut framebuffer, &frame, &body, &generate_uv_sphere(32, 64), &ShaderType::GasPlanet, &mut budget, &mut RenderStats::default(), &mut RenderScratch::new());
+
+    let screen = |position: Vec3| {
+        let projected = vertex_shader(&Vertex::new(position, Vec3::new(0.0, 1.0, 0.0), Vec2::new(0.0, 0.0)), &frame, &body).transformed_position;
+        Vec2::new(projected.x, projected.y)
+    };
+    let center = screen(Vec3::zeros());
+    let pole = screen(Vec3::new(0.0, SPHERE_RADIUS, 0.0)) - center;
+    // Solo el centro del disco, lejos del borde oscurecido
+    (pole.y.atan2(pole.x).to_degrees().rem_euclid(180.0), band_normal_angle(&framebuffer, center, pole.magnitude() * 0.6))
+}
+
+// Shader propio de color fijo, sin luz
+struct SolidShader(Color);
+
+impl FragmentShader for SolidShader {
+    fn shade(&self, _fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+        self.0
+    }
+}
+
+// Un shader propio pasa por el mismo pipeline que los incluidos, y cada ShaderType apunta al shader
+// con su mismo nombre
+fn custom_shader() -> Check {
+    let color = Color::new(255, 0, 255);
+    let shader = SceneShader::custom(SolidShader(color));
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 2.5), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let frame = build_uniforms(&camera, 0.0, 32, 32, DEFAULT_DEPTH_RANGE);
+    let mut framebuffer = Framebuffer::new(32, 32);
+    let mut budget = FragmentBudget::new(usize::MAX, usize::MAX);
+    render(&mut framebuffer, &frame, &BodyUniforms::default(), &generate_uv_sphere(16, 32), &shader, &mut budget, &mut RenderStats::default(), &mut RenderScratch::new());
+    let center = Color::from_hex(framebuffer.buffer[16 * framebuffer.width + 16]);
+
+    let misnamed: Vec<&str> = SHADER_NAMES.iter().copied()
+        .filter(|name| name.parse::<ShaderType>().map(|shader| shader.as_shader().name() != *name).unwrap_or(true))
+        .collect();
+    Check::new(
+        "shader propio: se dibuja con render y los incluidos conservan su nombre",
+        center == color && misnamed.is_empty(),
+        format!("centro {:?}, nombres distintos: {:?}", center, misnamed),
+    )
+}
+
+// Diferencia entre dos ángulos de rectas, en [-90, 90)
+fn line_angle_difference(a: f32, b: f32) -> f32 {
+    (a - b + 90.0).rem_euclid(180.0) - 90.0
+}
+
+// Un planeta gaseoso inclinado 30° gira las franjas en pantalla lo mismo que su eje: se comparan con
+// las del planeta sin inclinar (las franjas tienen remolinos, así que no son del todo horizontales)
+fn tilted_bands() -> Check {
+    let tilt = 30.0;
+    let (upright_axis, upright_bands) = band_angles(0.0);
+    let (tilted_axis, tilted_bands) = band_angles(tilt);
+    let axis_turn = line_angle_difference(tilted_axis, upright_axis);
+    let band_turn = line_angle_difference(tilted_bands, upright_bands);
+    Check::new(
+        "gaseoso inclinado: las franjas siguen al ecuador inclinado",
+        (axis_turn.abs() - tilt).abs() < 3.0 && (band_turn - axis_turn).abs() < 3.0,
+        format!("el eje gira {:.1}° en pantalla y las franjas {:.1}°", axis_turn, band_turn),
+    )
+}
+
+// La vista de diferencias cuenta solo los pixeles que cambiaron, en tramos por fila, y oscurece el resto
+fn frame_diff() -> Check {
+    let gray = Color::new(200, 200, 200).to_hex_with_threshold(0.5);
+    let mut current = Framebuffer::new(8, 6);
+    current.buffer.fill(gray);
+    let previous = current.buffer.clone();
+    // Un bloque de 3x2 y un pixel suelto en la misma fila que el bloque
+    for (x, y) in [(2, 1), (3, 1), (4, 1), (2, 2), (3, 2), (4, 2), (7, 2)] {
+        current.buffer[y * current.width + x] = 0x102030;
+    }
+
+    let stats = diff_view(&mut current, &previous);
+    let dimmed = (Color::from_hex(gray) * DIFF_DIM).to_hex_with_threshold(0.5);
+    let expected_bounds = Some(Rect::new(2, 1, 6, 2));
+    let untouched_dimmed = current.buffer[0] == dimmed && current.buffer[5 * current.width + 7] == dimmed;
+    Check::new(
+        "diferencias: cuenta los pixeles cambiados, sus tramos y la región",
+        stats.changed == 7 && stats.runs == 3 && stats.bounds == expected_bounds && untouched_dimmed,
+        format!("{:?}, fondo {:06x} en lugar de {:06x}", stats, current.buffer[0], dimmed),
+    )
+}
+
+// El mapa de biomas tiene los cuatro biomas, casi siempre el mismo que el punto de al lado (regiones
+// grandes, no ruido de pixeles) y la misma altitud a los dos lados de la costura de la longitud
+fn rocky_biomes() -> Check {
+    let seed = 0;
+    let samples: Vec<Vec3> = sphere_samples(64).collect();
+    let mut counts = [0; 4];
+    for sample in &samples {
+        counts[biome_index(&ROCKY_BIOMES, biome_altitude(&ROCKY_BIOMES, seed, sample))] += 1;
+    }
+
+    let step = 0.01;
+    let same_as_neighbor = samples.iter().filter(|sample| {
+        let neighbor = (*sample + Vec3::new(step, step, -step)).normalize();
+        biome_index(&ROCKY_BIOMES, biome_altitude(&ROCKY_BIOMES, seed, sample)) == biome_index(&ROCKY_BIOMES, biome_altitude(&ROCKY_BIOMES, seed, &neighbor))
+    }).count() as f32 / samples.len() as f32;
+
+    let seam_gap = (-8..=8).map(|i| {
+        let latitude = i as f32 * 0.15;
+        let side = |longitude: f32| Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin());
+        (biome_altitude(&ROCKY_BIOMES, seed, &side(PI - 1e-4)) - biome_altitude(&ROCKY_BIOMES, seed, &side(-PI + 1e-4))).abs()
+    }).fold(0.0, f32::max);
+
+    Check::new(
+        "biomas: aparecen los cuatro en regiones continuas y sin costura",
+        counts.iter().all(|&count| count > 0) && same_as_neighbor > 0.9 && seam_gap < 1e-3,
+        format!("puntos por bioma {:?}, iguales al vecino {:.3}, salto en la costura {}", counts, same_as_neighbor, seam_gap),
+    )
+}
+
+// Veces que cada pixel de un framebuffer de 32x32 queda cubierto por los triángulos
+fn coverage_counts(triangles: &[[(f32, f32); 3]]) -> Vec<u32> {
+    let clip = Rect::new(0, 0, 32, 32);
+    let vertex = |(x, y): (f32, f32)| Vertex { transformed_position: Vec3::new(x, y, 0.0), ..Vertex::default() };
+    let mut counts = vec![0; clip.width * clip.height];
+    for [a, b, c] in triangles {
+        triangle_for_each(&vertex(*a), &vertex(*b), &vertex(*c), &clip, |fragment| {
+            counts[fragment.position.y as usize * clip.width + fragment.position.x as usize] += 1;
+        });
+    }
+    counts
+}
+
+// Un cuadrado partido por la diagonal (que pasa justo por centros de pixeles) y en abanico desde un
+// centro de pixel, con los triángulos en los dos sentidos: cada pixel de adentro se dibuja una sola vez
+// y ninguno de afuera. Con la característica float-raster esta revisión falla a propósito
+fn watertight_edges() -> Check {
+    let (low, high) = (2.25, 29.25);
+    let corners = [(low, low), (high, low), (high, high), (low, high)];
+    let center = (16.5, 16.5);
+    let diagonal = [[corners[0], corners[1], corners[2]], [corners[0], corners[3], corners[2]]];
+    let fan: Vec<[(f32, f32); 3]> = (0..4).map(|i| [center, corners[i], corners[(i + 1) % 4]]).collect();
+
+    let failures: Vec<String> = [("diagonal", diagonal.to_vec()), ("abanico", fan)].iter().filter_map(|(name, triangles)| {
+        let counts = coverage_counts(triangles);
+        let inside = |index: usize| {
+            let (x, y) = ((index % 32) as f32 + 0.5, (index / 32) as f32 + 0.5);
+            x > low && x < high && y > low && y < high
+        };
+        let holes = (0..counts.len()).filter(|&index| inside(index) && counts[index] == 0).count();
+        let doubled = counts.iter().filter(|&&count| count > 1).count();
+        let outside = (0..counts.len()).filter(|&index| !inside(index) && counts[index] > 0).count();
+        (holes + doubled + outside > 0).then(|| format!("{}: {} huecos, {} pixeles dibujados dos veces, {} afuera", name, holes, doubled, outside))
+    }).collect();
+    Check::new(
+        "rasterizado: las aristas compartidas no dejan huecos ni pixeles repetidos",
+        failures.is_empty(),
+        failures.join("; "),
+    )
+}
+
+// Todos los shaders en todos los modos de depuración devuelven colores finitos, en el centro, el borde y los polos
+fn every_debug_mode() -> Vec<Check> {
+    let directions = [
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        Vec3::new(0.0, -1.0, 0.0),
+        Vec3::new(-0.6, 0.3, -0.7),
+    ];
+    SHADER_NAMES.iter().map(|name| {
+        let shader: ShaderType = name.parse().expect("los nombres de SHADER_NAMES siempre se reconocen");
+        let failures: Vec<String> = (0..DEBUG_MODES).flat_map(|debug_mode| {
+            let (frame, body) = UniformsBuilder::new().debug_mode(debug_mode).time(1234.0).build();
+            directions.iter().filter_map(move |direction| {
+                let fragment = FragmentBuilder::on_sphere(*direction).lit_by(&frame).build();
+                let color = shade(shader, &fragment, &frame, &body);
+                (!is_finite(&color)).then(|| format!("debug_mode {} en {:?}: {:?}", debug_mode, direction, color))
+            }).collect::<Vec<_>>()
+        }).collect();
+        Check::new(&format!("{}: colores finitos en todos los modos de depuración", name), failures.is_empty(), failures.join("; "))
+    }).collect()
+}
+
+// El anillo en el plano XZ y la luz a 45° desde +x y +y: desde un punto a una altura -h bajo el eje
+// el rayo hacia la luz cruza el anillo a la distancia h del centro. Debajo de una franja densa la luz
+// baja, y en la división de Cassini, dentro del borde interior o sobre el anillo llega entera
+fn ring_shadow() -> Check {
+    let light_direction = Vec3::new(1.0, 1.0, 0.0).normalize();
+    let (frame, body) = UniformsBuilder::new()
+        .lights(vec![Light::directional(light_direction, Color::new(255, 255, 255), 1.0)])
+        .ring_shadow(RingShadow { world_to_ring: Mat4::identity(), profile: None })
+        .debug_mode(4)
+        .build();
+    let light_at = |position: Vec3| {
+        let fragment = FragmentBuilder::new().world_pos(position).normal(light_direction).lit_by(&frame).build();
+        luminance(&shade(ShaderType::RingPlanet, &fragment, &frame, &body)) / 255.0
+    };
+    let under_band = |band: f32| light_at(Vec3::new(0.0, -(RING_INNER_RADIUS + band * (RING_OUTER_RADIUS - RING_INNER_RADIUS)), 0.0));
+
+    let dense = under_band(0.3);
+    let cassini = under_band(0.42);
+    let inside = light_at(Vec3::new(0.0, -0.5 * RING_INNER_RADIUS, 0.0));
+    let above = light_at(Vec3::new(0.0, RING_INNER_RADIUS, 0.0));
+    Check::new(
+        "sombra del anillo: tapa la luz bajo las franjas y no en los huecos",
+        dense < 0.7 && cassini > 0.99 && inside > 0.99 && above > 0.99,
+        format!("luz bajo una franja densa {:.2}, bajo la división de Cassini {:.2}, dentro del borde {:.2}, sobre el anillo {:.2}", dense, cassini, inside, above),
+    )
+}
+
+// Con el perfil de Saturno la sombra sobre el planeta deja pasar justo lo que el anillo no tapa al
+// dibujarse en la misma franja, y bajo el anillo B queda más oscuro que bajo la división de Cassini
+fn ring_profile_shadow() -> Check {
+    let light = Light::directional(Vec3::new(1.0, 1.0, 0.0).normalize(), Color::new(255, 255, 255), 1.0);
+    let profile = RingProfile::saturn();
+    let shadow = RingShadow { world_to_ring: Mat4::identity(), profile: Some(profile.clone()) };
+    let ring = BodyUniforms { ring_profile: Some(profile), ..BodyUniforms::new(Mat4::identity()) };
+    let radius = |band: f32| RING_INNER_RADIUS + band * (RING_OUTER_RADIUS - RING_INNER_RADIUS);
+    let transmittance = |band: f32| ring_transmittance(&shadow, &Vec3::new(0.0, -radius(band), 0.0), &light);
+    let alpha = |band: f32| ShaderType::Ring.alpha(&FragmentBuilder::new().vertex_pos(Vec3::new(radius(band), 0.0, 0.0)).build(), &ring);
+
+    let worst = (1..40).map(|step| step as f32 / 40.0)
+        .map(|band| (transmittance(band) - (1.0 - alpha(band))).abs())
+        .fold(0.0, f32::max);
+    let (b_ring, cassini) = (transmittance(0.45), transmittance(0.72));
+    Check::new(
+        "perfil del anillo: la sombra tapa lo mismo que se ve del anillo",
+        worst < 1e-5 && b_ring < 0.2 && cassini > 0.8,
+        format!("diferencia máxima {:.6}, luz bajo el anillo B {:.2}, bajo la división de Cassini {:.2}", worst, b_ring, cassini),
+    )
+}
+
+// Al acercarse al anillo el borde nunca se aleja del círculo más de medio pixel (salvo con el nivel más
+// fino), al alejarse y volver por el mismo radio no cambia de nivel, y cada vértice de un nivel está
+// también en el más fino con las mismas coordenadas UV
+fn ring_tiers() -> Check {
+    let radii: Vec<f32> = (0..200).map(|step| 100.0 * 1.05f32.powi(step)).collect();
+    let mut tier = RING_SEGMENT_TIERS.len() - 1;
+    let mut worst_error: f32 = 0.0;
+    for &radius in &radii {
+        tier = select_ring_tier(tier, &RING_SEGMENT_TIERS, radius);
+        if tier > 0 {
+            // r (1 - cos x) escrito como 2 r sin²(x / 2), que no pierde precisión con ángulos chicos
+            worst_error = worst_error.max(2.0 * radius * (PI / (2.0 * RING_SEGMENT_TIERS[tier] as f32)).sin().powi(2));
+        }
+    }
+    // Un poco más lejos que el radio donde cambió de nivel no vuelve al anterior
+    let flickers = radii.windows(2).filter(|pair| {
+        let closer = select_ring_tier(RING_SEGMENT_TIERS.len() - 1, &RING_SEGMENT_TIERS, pair[1]);
+        select_ring_tier(closer, &RING_SEGMENT_TIERS, pair[0]) != closer
+    }).count();
+
+    // En el nivel más fino u avanza de a 1 / RING_SEGMENT_TIERS[0]; la posición tiene que salir de u y v
+    let finest = RING_SEGMENT_TIERS[0] as f32;
+    let unmatched = generate_ring_tiers(RING_INNER_RADIUS, RING_OUTER_RADIUS).iter().flatten()
+        .filter(|vertex| {
+            let (u, v) = (vertex.tex_coords.x, vertex.tex_coords.y);
+            let angle = 2.0 * PI * u;
+            let radius = RING_INNER_RADIUS + v * (RING_OUTER_RADIUS - RING_INNER_RADIUS);
+            let expected = Vec3::new(radius * angle.cos(), 0.0, -radius * angle.sin());
+            (u * finest - (u * finest).round()).abs() > 1e-2 || (expected - vertex.position).magnitude() > 1e-4
+        })
+        .count();
+    Check::new(
+        "anillo adaptativo: borde a menos de medio pixel, sin saltos de nivel y con las mismas UV",
+        worst_error <= 0.5 && flickers == 0 && unmatched == 0,
+        format!("error máximo {:.3} px, saltos {}, vértices que no están en el nivel más fino {}", worst_error, flickers, unmatched),
+    )
+}
+
+// Shader propio con solo la máscara de tierra de la Tierra: blanco en tierra, negro en el océano
+struct LandMaskShader;
+
+impl FragmentShader for LandMaskShader {
+    fn shade(&self, fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+        Color::new(255, 255, 255) * earth_land(fragment)
+    }
+}
+
+// Una costa vista muy de cerca pasa del océano a la tierra con al menos dos pixeles intermedios en
+// cada fila que la cruza, y en ningún lugar un pixel de océano puro queda pegado a uno de tierra pura
+fn coastline_edges() -> Check {
+    // Punto de la costa sobre el ecuador, buscado por bisección entre un punto de tierra y uno de océano
+    let on_equator = |angle: f32| Vec3::new(angle.cos(), 0.0, angle.sin()) * SPHERE_RADIUS;
+    let angles = (0..360).map(|degrees| (degrees as f32).to_radians());
+    let Some(mut range) = angles.clone().zip(angles.skip(1)).find(|&(a, b)| earth_is_land(&on_equator(a)) != earth_is_land(&on_equator(b))) else {
+        return Check::new("costa suavizada", false, "no hay costa sobre el ecuador".to_string());
+    };
+    for _ in 0..24 {
+        let middle = (range.0 + range.1) * 0.5;
+        if earth_is_land(&on_equator(middle)) == earth_is_land(&on_equator(range.0)) { range.0 = middle } else { range.1 = middle }
+    }
+    let coast = on_equator(range.0).normalize();
+
+    // La esfera agrandada y la cámara cerca de la superficie, mirando la costa de frente
+    let scale = 20.0;
+    let size = 64;
+    let camera = Camera::new(coast * (SPHERE_RADIUS * scale + 1.5), coast * SPHERE_RADIUS * scale, Vec3::new(0.0, 1.0, 0.0));
+    let frame = build_uniforms(&camera, 0.0, size, size, (0.01, 100.0));
+    let mut framebuffer = Framebuffer::new(size, size);
+    let mut budget = FragmentBudget::new(usize::MAX, usize::MAX);
+    render(&mut framebuffer, &frame, &BodyUniforms::new(Mat4::new_scaling(scale)), &generate_uv_sphere(64, 128), &LandMaskShader, &mut budget, &mut RenderStats::default(), &mut RenderScratch::new());
+
+    let level = |x: usize, y: usize| framebuffer.buffer[y * size + x] & 0xFF;
+    let hard_steps = (0..size).flat_map(|y| (0..size).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            let pure = |a: u32, b: u32| (a == 0 && b == 255) || (a == 255 && b == 0);
+            (x + 1 < size && pure(level(x, y), level(x + 1, y))) || (y + 1 < size && pure(level(x, y), level(x, y + 1)))
+        })
+        .count();
+    // Filas que tienen océano y tierra, y cuántas de ellas pasan de uno a otro con menos de dos pixeles en medio
+    let crossing_rows: Vec<usize> = (0..size).filter(|&y| (0..size).any(|x| level(x, y) == 0) && (0..size).any(|x| level(x, y) == 255)).collect();
+    let narrow_rows = crossing_rows.iter().filter(|&&y| (0..size).filter(|&x| (1..255).contains(&level(x, y))).count() < 2).count();
+
+    Check::new(
+        "costa suavizada: al menos dos pixeles intermedios entre océano y tierra",
+        crossing_rows.len() >= size / 2 && narrow_rows == 0 && hard_steps == 0,
+        format!("{} filas cruzan la costa, {} con menos de dos pixeles intermedios, {} saltos de un pixel", crossing_rows.len(), narrow_rows, hard_steps),
+    )
+}
+
+// La cola del cometa del sistema solar apunta en contra del sol en toda la órbita, se curva hacia atrás
+// del movimiento y en el periapsis es más larga y tiene más partículas que en el apoapsis
+fn comet_tail() -> Check {
+    let scene = SceneConfig::builtin(8);
+    let Some((comet, tail)) = scene.bodies.iter().find_map(|body| body.tail.map(|tail| (body, tail))) else {
+        return Check::new("cometa: cola en contra del sol", false, "el sistema solar no tiene cometa".to_string());
+    };
+    let times: Vec<f32> = (0..400).map(|step| step as f32 * 10.0).collect();
+    let distance = |time: f32| comet.at(time).translation.magnitude();
+    let near = times.iter().copied().min_by(|&a, &b| distance(a).total_cmp(&distance(b))).unwrap_or(0.0);
+    let far = times.iter().copied().max_by(|&a, &b| distance(a).total_cmp(&distance(b))).unwrap_or(0.0);
+
+    // Largo de la cola (lo más lejos que llega una partícula en contra del sol), si alguna partícula
+    // queda del lado del sol, y cuánto se corre en promedio hacia atrás del movimiento
+    let measure = |time: f32| {
+        let nucleus = comet.at(time).translation;
+        let away = nucleus.normalize();
+        let velocity = comet.velocity_at(time);
+        let behind = -(velocity - away * velocity.dot(&away)).normalize();
+        let particles = tail.particles(nucleus, away, velocity, tail.strength(nucleus.magnitude()), time, comet.seed);
+        let length = particles.iter().map(|particle| (particle.position - nucleus).dot(&away)).fold(0.0, f32::max);
+        let sunward = particles.iter().any(|particle| (particle.position - nucleus).dot(&away) < 0.0);
+        let lag = particles.iter().map(|particle| (particle.position - nucleus).dot(&behind)).sum::<f32>() / particles.len().max(1) as f32;
+        (particles.len(), length, sunward, lag)
+    };
+    let (near_count, near_length, near_sunward, near_lag) = measure(near);
+    let (far_count, far_length, far_sunward, _) = measure(far);
+    let sunward = near_sunward || far_sunward || times.iter().any(|&time| measure(time).2);
+
+    Check::new(
+        "cometa: la cola apunta en contra del sol y crece en el periapsis",
+        !sunward && near_length > 3.0 * far_length && near_count > 3 * far_count && near_lag > 0.0,
+        format!("hacia el sol {}, periapsis {} partículas y largo {:.2}, apoapsis {} y {:.2}, atraso {:.3}", sunward, near_count, near_length, far_count, far_length, near_lag),
+    )
+}
+
+// Tri-planar: con la normal alineada a un eje (de cualquier lado) el resultado es exactamente la muestra
+// de ese plano; a 45° entre X e Y es la mitad de cada uno sin nada de Z, y girar un poco la normal
+// cambia poco el color (la mezcla no salta)
+fn triplanar_blend() -> Check {
+    let pattern = |uv: Vec2| Color { r: (uv.x * 37.0).sin().abs() * 255.0, g: (uv.y * 53.0).cos().abs() * 255.0, b: (uv.x * uv.y * 11.0).sin().abs() * 255.0 };
+    let position = Vec3::new(0.31, -0.42, 0.17);
+    let scale = 3.0;
+    let uvs = triplanar_uvs(position, scale);
+
+    let axes = [Vec3::x(), Vec3::y(), Vec3::z()];
+    let aligned = axes.iter().enumerate()
+        .flat_map(|(plane, axis)| [(plane, *axis), (plane, -axis)])
+        .all(|(plane, normal)| triplanar_sample(&pattern, position, normal, scale) == pattern(uvs[plane]));
+
+    let diagonal = Vec3::new(1.0, 1.0, 0.0).normalize();
+    let blended = triplanar_sample(&pattern, position, diagonal, scale);
+    let (x, y) = (pattern(uvs[0]), pattern(uvs[1]));
+    let expected = Color { r: (x.r + y.r) / 2.0, g: (x.g + y.g) / 2.0, b: (x.b + y.b) / 2.0 };
+    let error = (blended.r - expected.r).abs().max((blended.g - expected.g).abs()).max((blended.b - expected.b).abs());
+
+    let tilted = quat_rotate_vec3(&quat_angle_axis(0.01, &Vec3::z()), &diagonal);
+    let nudged = triplanar_sample(&pattern, position, tilted, scale);
+    let jump = (nudged.r - blended.r).abs().max((nudged.g - blended.g).abs()).max((nudged.b - blended.b).abs());
+
+    Check::new(
+        "tri-planar: un plano en los ejes y mezcla estable a 45°",
+        aligned && error < 1e-3 && jump < 10.0,
+        format!("ejes exactos {}, error a 45° {:.5}, cambio al girar 0.01 rad {:.3}", aligned, error, jump),
+    )
+}
+
+// El cielo de estrellas solo pinta donde no se dibujó nada: con y sin él los pixeles con profundidad
+// cambian a lo sumo la luz que les daba el fondo liso (ver scene::skylight), el fondo cambia, dibujar dos
+// veces el mismo instante da lo mismo y en otro instante alguna estrella titila
+fn starfield_mask() -> Check {
+    let meshes = SceneMeshes::new(vec![generate_uv_sphere(32, 64)], generate_ring(RING_INNER_RADIUS, RING_OUTER_RADIUS, 64));
+    let case = |time: f32, starfield: bool| GoldenCase::new("cielo", 4, time).options(RenderOptions { starfield, ..RenderOptions::default() });
+    let (plain, stars) = (render_case(&case(60.0, false), &meshes), render_case(&case(60.0, true), &meshes));
+    let covered = |index: &usize| plain.zbuffer[*index] != f32::INFINITY;
+    let pixels = 0..plain.buffer.len();
+    let skylight = Color::from_hex(BACKGROUND_COLOR) * SKYLIGHT_STRENGTH;
+    let max_skylight = skylight.r.max(skylight.g).max(skylight.b).ceil() as i32 + 1;
+    let channel_delta = |a: u32, b: u32| a.to_be_bytes().iter().zip(b.to_be_bytes()).map(|(a, b)| (*a as i32 - b as i32).abs()).max().unwrap_or(0);
+    let changed_covered = pixels.clone().filter(covered).filter(|&index| channel_delta(plain.buffer[index], stars.buffer[index]) > max_skylight).count();
+    let changed_background = pixels.clone().filter(|index| !covered(index)).filter(|&index| plain.buffer[index] != stars.buffer[index]).count();
+    let repeatable = render_case(&case(60.0, true), &meshes).buffer == stars.buffer;
+    let later = render_case(&case(160.0, true), &meshes);
+    let twinkled = pixels.filter(|index| !covered(index) && later.zbuffer[*index] == f32::INFINITY).filter(|&index| later.buffer[index] != stars.buffer[index]).count();
+
+    Check::new(
+        "cielo de estrellas: solo en el fondo (los cuerpos solo cambian por la luz del fondo), igual en el mismo instante y titila con el tiempo",
+        changed_covered == 0 && changed_background > 0 && repeatable && twinkled > 0,
+        format!("pixeles cubiertos que cambian más que la luz del fondo {}, de fondo {}, repetible {}, cambian al titilar {}", changed_covered, changed_background, repeatable, twinkled),
+    )
+}
+
+// Pulsación del sol: los vértices repetidos de las costuras se mueven juntos (sin grietas), ninguno sale
+// de la esfera de recorte, el borde deja de ser un círculo y cambia con el tiempo, y la normal nueva es
+// continua entre vértices vecinos y vuelve a la de la esfera sin amplitud
+fn sun_pulsation() -> Check {
+    let Some(pulse) = SceneConfig::builtin(1).bodies.first().and_then(|sun| sun.vertex_animation) else {
+        return Check::new("sol: la superficie pulsa sin grietas", false, "el sol de la escena 1 no pulsa".to_string());
+    };
+    let sphere = generate_uv_sphere(32, 64);
+    let animate = |time: f32, vertex: &Vertex| animate_vertex(&vertex.position, &vertex.normal, &pulse, time, 0);
+    let frame = |time: f32| sphere.iter().map(|vertex| animate(time, vertex)).collect::<Vec<_>>();
+    let (start, later) = (frame(0.0), frame(300.0));
+
+    // Vértices repetidos (costura y polos): ordenados por x solo hace falta comparar con los siguientes
+    // mientras x siga cerca, en lugar de todos contra todos
+    let mut order: Vec<usize> = (0..sphere.len()).collect();
+    order.sort_by(|&a, &b| sphere[a].position.x.total_cmp(&sphere[b].position.x));
+    let mut crack: f32 = 0.0;
+    for (i, &a) in order.iter().enumerate() {
+        for &b in order[i + 1..].iter().take_while(|&&b| sphere[b].position.x - sphere[a].position.x < 1e-5) {
+            if (sphere[a].position - sphere[b].position).magnitude() < 1e-5 {
+                crack = crack.max((start[a].0 - start[b].0).magnitude());
+            }
+        }
+    }
+    let radii = start.iter().map(|(position, _)| position.magnitude());
+    let (min, max) = radii.clone().fold((f32::MAX, 0.0f32), |(min, max), radius| (min.min(radius), max.max(radius)));
+    let limit = SPHERE_RADIUS * (1.0 + pulse.amplitude) + 1e-4;
+    let moved = start.iter().zip(&later).map(|((a, _), (b, _))| (a - b).magnitude()).fold(0.0, f32::max);
+
+    // Triángulos de la malla: el ángulo más grande entre las normales nuevas de sus vértices
+    let bend = start.chunks(3).map(|triangle| {
+        let angle = |a: &Vec3, b: &Vec3| a.dot(b).clamp(-1.0, 1.0).acos();
+        angle(&triangle[0].1, &triangle[1].1).max(angle(&triangle[1].1, &triangle[2].1)).max(angle(&triangle[0].1, &triangle[2].1))
+    }).fold(0.0, f32::max);
+    let still = VertexAnimParams { amplitude: 0.0, ..pulse };
+    let flat = sphere.iter().map(|vertex| {
+        let (_, normal) = animate_vertex(&vertex.position, &vertex.normal, &still, 0.0, 0);
+        normal.dot(&vertex.normal.normalize())
+    }).fold(1.0, f32::min);
+
+    Check::new(
+        "sol: la superficie pulsa sin grietas ni saltos en la luz y dentro de su esfera de recorte",
+        crack < 1e-4 && max <= limit && max - min > SPHERE_RADIUS * pulse.amplitude && moved > 1e-3 && bend < 0.3 && flat > 0.9999,
+        format!("grieta {:.2e}, radio {:.4}..{:.4} (límite {:.4}), movimiento {:.4}, ángulo entre normales vecinas {:.3}, sin amplitud {:.5}", crack, min, max, limit, moved, bend, flat),
+    )
+}
+
+// Vista infrarroja: el degradado respeta sus extremos y el punto medio entre paradas, la lava está más
+// caliente que la roca aún sin luz, el lado de día de la Tierra más que el de noche, el sol queda cerca del
+// máximo y el color que sale del shader es el del degradado en la temperatura (con un valor finito en [0, 1]
+// para todos los shaders)
+fn thermal_view() -> Check {
+    let stops = [(0.0, Color::new(0, 0, 0)), (0.5, Color::new(200, 100, 0)), (1.0, Color::new(255, 255, 255))];
+    let gradient = Color::from_gradient(-1.0, &stops) == stops[0].1
+        && Color::from_gradient(2.0, &stops) == stops[2].1
+        && Color::from_gradient(0.25, &stops) == Color::new(100, 50, 0)
+        && Color::from_gradient(f32::NAN, &stops) == stops[0].1;
+
+    let (plain, body) = UniformsBuilder::new().build();
+    let frame = FrameUniforms { thermal: Some(INFERNO.to_vec()), ..plain };
+    let temperature = |shader: ShaderType, fragment: &Fragments| shader.temperature(fragment, &frame, &body);
+
+    let dark: Vec<Fragments> = sphere_samples(64).map(|direction| FragmentBuilder::on_sphere(direction).intensity(0.0).build()).collect();
+    let lava = dark.iter().find(|fragment| lava_factor(&fragment.vertex_pos, frame.time) > 0.9);
+    let rock = dark.iter().find(|fragment| lava_factor(&fragment.vertex_pos, frame.time) == 0.0);
+    let (lava, rock) = match (lava, rock) {
+        (Some(lava), Some(rock)) => (temperature(ShaderType::VolcanicPlanet, lava), temperature(ShaderType::VolcanicPlanet, rock)),
+        _ => (0.0, 0.0),
+    };
+
+    let day = temperature(ShaderType::Earth, &FragmentBuilder::new().intensity(1.0).build());
+    let night = temperature(ShaderType::Earth, &FragmentBuilder::new().intensity(0.0).build());
+    let sun = temperature(ShaderType::Sun, &FragmentBuilder::new().build());
+
+    let fragment = FragmentBuilder::new().lit_by(&frame).build();
+    let mapped = SHADER_NAMES.iter().filter_map(|name| name.parse::<ShaderType>().ok()).all(|shader| {
+        let value = temperature(shader, &fragment);
+        (0.0..=1.0).contains(&value) && shade(shader, &fragment, &frame, &body) == Color::from_gradient(value, &INFERNO)
+    });
+
+    Check::new(
+        "vista infrarroja: degradado, lava sobre roca, día sobre noche y sol al máximo",
+        gradient && lava > rock + 0.3 && day > night && sun > 0.8 && mapped,
+        format!("degradado {}, lava {:.2} y roca {:.2}, día {:.2} y noche {:.2}, sol {:.2}, colores del degradado {}", gradient, lava, rock, day, night, sun, mapped),
+    )
+}
+
+// Rotación diferencial: el ecuador gira más rápido que las latitudes medias y estas que los polos, en unos
+// cientos de ticks se nota la diferencia, los dos lados de la costura de -x siguen juntos, y la gran
+// tormenta del planeta gaseoso avanza entera con la rotación de su latitud (mide lo mismo de este a oeste
+// que al principio y ya no está donde empezó)
+fn differential_rotation() -> Check {
+    let longitude = |position: Vec3| position.z.atan2(position.x);
+    let at = |latitude: f32, longitude: f32| Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin()) * SPHERE_RADIUS;
+
+    let rotations = [SUN.rotation, GAS_PLANET_BANDS.rotation];
+    let ordered = rotations.iter().all(|rotation| rotation.rate(0.0) > rotation.rate(FRAC_PI_4) && rotation.rate(FRAC_PI_4) > rotation.rate(FRAC_PI_2));
+    let shear = rotations.map(|rotation| {
+        let equator = -longitude(rotation.unwind(at(0.0, 0.5), 300.0)) + 0.5;
+        let middle = -longitude(rotation.unwind(at(FRAC_PI_4, 0.5), 300.0)) + 0.5;
+        equator - middle
+    });
+    let seam = [0.0, 137.0, 600.0].iter().flat_map(|&time| rotations.map(|rotation| {
+        (rotation.unwind(at(0.3, PI - 1e-3), time) - rotation.unwind(at(0.3, -PI + 1e-3), time)).magnitude()
+    })).fold(0.0, f32::max);
+    let pole = rotations.iter().all(|rotation| rotation.unwind(Vec3::new(0.0, SPHERE_RADIUS, 0.0), 600.0) == Vec3::new(0.0, SPHERE_RADIUS, 0.0));
+
+    let storm = &GAS_PLANET_BANDS.storms[0];
+    let center = |time: f32| storm.longitude + time * (storm.drift + GAS_PLANET_BANDS.rotation.rate(storm.latitude));
+    let in_storm = |position: Vec3, time: f32| {
+        let (frame, body) = UniformsBuilder::new().time(time).debug_mode(2).build();
+        luminance(&shade(ShaderType::GasPlanet, &FragmentBuilder::on_sphere(position).build(), &frame, &body)) > 0.0
+    };
+    // Puntos a lo largo del paralelo del centro que caen dentro del óvalo
+    let width = |time: f32| (-40..=40).filter(|step| in_storm(at(storm.latitude, center(time) + *step as f32 * 0.01), time)).count();
+    let (start, later) = (width(0.0), width(400.0));
+    let moved = in_storm(at(storm.latitude, center(400.0)), 400.0) && !in_storm(at(storm.latitude, center(0.0)), 400.0);
+
+    Check::new(
+        "rotación diferencial: el ecuador adelanta, sin costura y con la tormenta entera",
+        ordered && shear.iter().all(|&shear| shear > 0.15) && seam < 1e-2 && pole && start > 0 && start.abs_diff(later) <= 1 && moved,
+        format!("orden {}, adelanto en 300 ticks {:?} rad, costura {:.2e}, polo {}, ancho de la tormenta {} y {}, se movió {}", ordered, shear, seam, pole, start, later, moved),
+    )
+}
@@ -1,18 +1,33 @@
-use nalgebra_glm::{Vec2, Vec3, Vec4, Mat3, dot, mat4_to_mat3};
+use nalgebra_glm::{Vec2, Vec3, Vec4, dot, mat4_to_mat3};
 use crate::vertex::Vertex;
-use crate::Uniforms;
+use crate::pipeline::{diffuse_light, filtered_diffuse_light, specular_light, to_camera, view_direction, Light, RingShadow, Twilight, BodyUniforms, FrameUniforms, VertexAnimParams, MAX_LIGHTS};
 use crate::fragments::Fragments;
+use crate::framebuffer::BlendMode;
+use crate::mesh::{RING_INNER_RADIUS, RING_OUTER_RADIUS};
+use crate::particles::ring_density;
+use crate::ring_profile::{RingProfile, RingSample};
 use crate::color::Color;
+use crate::texture::{AmbientCube, Texture};
+use crate::noise;
+use crate::rng;
+use crate::impacts::{MoonState, FLASH_DURATION};
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
+use std::sync::{Arc, OnceLock};
 
-pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
+pub fn vertex_shader(vertex: &Vertex, frame: &FrameUniforms, body: &BodyUniforms) -> Vertex {
+  let (object_position, object_normal) = match &body.vertex_animation {
+    Some(animation) => animate_vertex(&vertex.position, &vertex.normal, animation, frame.time, body.seed),
+    None => (vertex.position, vertex.normal),
+  };
   let position = Vec4::new(
-    vertex.position.x,
-    vertex.position.y,
-    vertex.position.z,
+    object_position.x,
+    object_position.y,
+    object_position.z,
     1.0
   );
-  let transformed = uniforms.projection_matrix * uniforms.view_matrix * uniforms.model_matrix * position;
+  let transformed = frame.projection_matrix * frame.view_matrix * body.model_matrix() * position;
+  let world = body.model_matrix() * position;
 
   let w = transformed.w;
   let ndc_position = Vec4::new(
@@ -22,53 +37,611 @@ pub fn vertex_shader(vertex: &Vertex, uniforms: &Uniforms) -> Vertex {
     1.0
   );
 
-  let screen_position = uniforms.viewport_matrix * ndc_position;
+  let screen_position = frame.viewport_matrix * ndc_position;
 
-  let model_mat3 = mat4_to_mat3(&uniforms.model_matrix); 
-  let normal_matrix = model_mat3.transpose().try_inverse().unwrap_or(Mat3::identity());
+  // Con escala distinta en cada eje las normales cambian de largo; se normalizan para que todas
+  // pesen lo mismo al interpolar
+  let transformed_normal = body.normal_matrix() * object_normal;
+  let transformed_normal = transformed_normal.try_normalize(0.0).unwrap_or(transformed_normal);
 
-  let transformed_normal = normal_matrix * vertex.normal;
+  debug_assert!(
+    world.iter().chain(transformed_normal.iter()).all(|value| value.is_finite()),
+    "vértice no finito: posición {:?}, normal {:?}", vertex.position, vertex.normal
+  );
+  debug_assert!(
+    w == 0.0 || screen_position.iter().all(|value| value.is_finite()),
+    "posición en pantalla no finita: {:?}", screen_position
+  );
 
+  // La posición en el objeto queda sin deformar para que los patrones de los shaders no se muevan con la pulsación
   Vertex {
     position: vertex.position,
-    normal: vertex.normal,
+    normal: object_normal,
     tex_coords: vertex.tex_coords,
     color: vertex.color,
     transformed_position: Vec3::new(screen_position.x, screen_position.y, screen_position.z),
     transformed_normal,
+    world_position: Vec3::new(world.x, world.y, world.z),
+    // Distancia en el eje de la vista (en la perspectiva es igual a w, en la ortográfica w es 1)
+    view_depth: -(frame.view_matrix * world).z,
   }
 }
 
+// Distancia a los vecinos con los que se vuelve a calcular la normal, en fracción del radio
+const VERTEX_ANIM_STEP: f32 = 0.01;
+
+// Mueve el vértice sobre su normal según ruido de baja frecuencia de su posición y del tiempo. La normal
+// nueva sale de mover igual a dos vecinos sobre el plano tangente y cruzar los lados, así la luz del
+// borde sigue a la superficie deformada. Los vértices repetidos de las costuras tienen la misma posición
+// y normal, así que se mueven juntos y no se abren grietas
+pub fn animate_vertex(position: &Vec3, normal: &Vec3, animation: &VertexAnimParams, time: f32, seed: u32) -> (Vec3, Vec3) {
+  let Some(normal) = normal.try_normalize(0.0) else {
+    return (*position, *normal);
+  };
+  let radius = position.magnitude();
+  let seed = rng::combine(seed, 0x9015E);
+  let w = time * animation.speed;
+  let lift = |point: Vec3| {
+    let noise = noise::fbm4(seed, point * animation.frequency, w, animation.octaves);
+    point + normal * (radius * animation.amplitude * (noise * 2.0 - 1.0))
+  };
+
+  let helper = if normal.x.abs() < 0.9 { Vec3::x() } else { Vec3::y() };
+  let tangent = normal.cross(&helper).normalize();
+  let bitangent = normal.cross(&tangent);
+  let step = radius.max(f32::EPSILON) * VERTEX_ANIM_STEP;
+  let center = lift(*position);
+  let along_tangent = lift(position + tangent * step) - center;
+  let along_bitangent = lift(position + bitangent * step) - center;
+  // tangente × (normal × tangente) = normal: sin relieve la normal no cambia
+  let animated = along_tangent.cross(&along_bitangent).try_normalize(0.0).unwrap_or(normal);
+  (center, animated)
+}
+
+// En la sesión se guarda con el mismo nombre de --model-shader
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
 pub enum ShaderType {
   Sun,
   Earth,
   GasPlanet,
   RingPlanet,
   RockyPlanet,
+  RockyBiome,
   IcyPlanet,
   VolcanicPlanet,
   Moon,
   Ring,
+  Asteroid,
+  VertexColor,
+  Flat,
+  Triplanar,
+}
+
+// Cantidad de valores de debug_mode (0 = shader completo); F1 los recorre
+pub const DEBUG_MODES: u32 = 8;
+// Modo de depuración que muestra la banda del crepúsculo en la Tierra, el planeta rocoso y el de hielo
+pub const TWILIGHT_DEBUG_MODE: u32 = 7;
+
+// Nombres que acepta --model-shader y los archivos de escena
+pub const SHADER_NAMES: [&str; 14] = [
+  "sun", "earth", "gas", "ringed", "rocky", "rocky-biome", "icy", "volcanic", "moon", "ring", "asteroid", "vertex-color", "flat", "triplanar",
+];
+
+impl std::str::FromStr for ShaderType {
+  type Err = String;
+
+  fn from_str(name: &str) -> Result<Self, Self::Err> {
+    match name {
+      "sun" => Ok(ShaderType::Sun),
+      "earth" => Ok(ShaderType::Earth),
+      "gas" => Ok(ShaderType::GasPlanet),
+      "ringed" => Ok(ShaderType::RingPlanet),
+      "rocky" => Ok(ShaderType::RockyPlanet),
+      "rocky-biome" => Ok(ShaderType::RockyBiome),
+      "icy" => Ok(ShaderType::IcyPlanet),
+      "volcanic" => Ok(ShaderType::VolcanicPlanet),
+      "moon" => Ok(ShaderType::Moon),
+      "ring" => Ok(ShaderType::Ring),
+      "asteroid" => Ok(ShaderType::Asteroid),
+      "vertex-color" => Ok(ShaderType::VertexColor),
+      "flat" => Ok(ShaderType::Flat),
+      "triplanar" => Ok(ShaderType::Triplanar),
+      _ => Err(format!("shader desconocido: {} (opciones: {})", name, SHADER_NAMES.join(", "))),
+    }
+  }
+}
+
+impl TryFrom<String> for ShaderType {
+  type Error = String;
+
+  fn try_from(name: String) -> Result<Self, Self::Error> {
+    name.parse()
+  }
+}
+
+impl From<ShaderType> for String {
+  fn from(shader: ShaderType) -> Self {
+    shader.name().to_string()
+  }
+}
+
+impl ShaderType {
+  // Nombre de --model-shader y de los archivos de escena
+  pub fn name(&self) -> &'static str {
+    SHADER_NAMES.iter().find(|name| name.parse() == Ok(*self)).copied().unwrap_or("sun")
+  }
+
+  // Shader del cuerpo para el pipeline; los archivos de escena y --model-shader siguen usando el enum
+  pub fn as_shader(&self) -> &'static dyn FragmentShader {
+    match self {
+      ShaderType::Sun => &SunShader,
+      ShaderType::Earth => &EarthShader,
+      ShaderType::GasPlanet => &GasPlanetShader,
+      ShaderType::RingPlanet => &RingPlanetShader,
+      ShaderType::RockyPlanet => &RockyPlanetShader,
+      ShaderType::RockyBiome => &RockyBiomeShader,
+      ShaderType::IcyPlanet => &IcyPlanetShader,
+      ShaderType::VolcanicPlanet => &VolcanicPlanetShader,
+      ShaderType::Moon => &MoonShader,
+      ShaderType::Ring => &RingShader,
+      ShaderType::Asteroid => &AsteroidShader,
+      ShaderType::VertexColor => &VertexColorShader,
+      ShaderType::Flat => &FlatShader,
+      ShaderType::Triplanar => &TRIPLANAR_ROCK,
+    }
+  }
+
+  pub fn albedo(&self) -> Option<Color> {
+    self.as_shader().albedo()
+  }
+
+  pub fn blend_mode(&self) -> BlendMode {
+    self.as_shader().blend_mode()
+  }
+}
+
+// Color de un fragmento. Para un aspecto nuevo basta con implementarlo fuera de la biblioteca y
+// ponerlo en el BodyConfig (ver examples/custom_shader.rs); las bandas y el resaltado del cuerpo bajo
+// el mouse los agrega fragment_shader a cualquier shader
+pub trait FragmentShader: Send + Sync {
+  fn shade(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color;
+
+  // Nombre en las estadísticas y en los mensajes
+  fn name(&self) -> &str {
+    "propio"
+  }
+
+  // Color medio de la superficie, para la luz que un planeta refleja sobre sus lunas;
+  // sin albedo el cuerpo no cuenta como planeta
+  fn albedo(&self) -> Option<Color> {
+    None
+  }
+
+  // Opacidad de cada fragmento; solo se usa con BlendMode::AlphaBlend
+  fn alpha(&self, _fragment: &Fragments, _body: &BodyUniforms) -> f32 {
+    1.0
+  }
+
+  fn blend_mode(&self) -> BlendMode {
+    BlendMode::Opaque
+  }
+
+  // Con true el pipeline le pasa a cada fragmento el color del triángulo en vez de interpolarlo
+  fn flat_shading(&self) -> bool {
+    false
+  }
+
+  // Temperatura relativa en [0, 1] para la vista infrarroja (ver FrameUniforms::thermal); por defecto
+  // solo depende de la luz que llega, los shaders con partes que emiten (lava, ciudades) las suman
+  fn temperature(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+    surface_temperature(fragment, frame, body)
+  }
+
+  // Parámetros principales con su valor ya escrito, para la tarjeta de información del cuerpo (tecla I)
+  fn parameters(&self) -> Vec<(String, String)> {
+    Vec::new()
+  }
+}
+
+// El enum se puede pasar directo donde se espera un shader
+impl FragmentShader for ShaderType {
+  fn shade(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+    self.as_shader().shade(fragment, frame, body)
+  }
+
+  fn name(&self) -> &str {
+    self.as_shader().name()
+  }
+
+  fn albedo(&self) -> Option<Color> {
+    self.as_shader().albedo()
+  }
+
+  fn alpha(&self, fragment: &Fragments, body: &BodyUniforms) -> f32 {
+    self.as_shader().alpha(fragment, body)
+  }
+
+  fn blend_mode(&self) -> BlendMode {
+    self.as_shader().blend_mode()
+  }
+
+  fn flat_shading(&self) -> bool {
+    self.as_shader().flat_shading()
+  }
+
+  fn temperature(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+    self.as_shader().temperature(fragment, frame, body)
+  }
+
+  fn parameters(&self) -> Vec<(String, String)> {
+    builtin_parameters(*self)
+  }
+}
+
+// Parámetros de los shaders incluidos: las constantes con las que se arma cada aspecto
+fn builtin_parameters(shader: ShaderType) -> Vec<(String, String)> {
+  let bands = |settings: &BandSettings| vec![
+    ("franjas", format!("{:.1}", settings.band_scale)),
+    ("flujo", format!("{}", settings.flow_speed)),
+    ("turbulencia", format!("{:.3}", settings.turbulence)),
+    ("remolinos", format!("{:.3}", settings.swirl)),
+    ("tormentas", settings.storms.len().to_string()),
+    ("borde", format!("{:.2} + {:.2} mu", settings.limb.a, settings.limb.b)),
+    ("rotación", format!("{} + {} cos² lat", settings.rotation.base_rate, settings.rotation.delta_rate)),
+  ];
+  let parameters = match shader {
+    ShaderType::Sun => vec![
+      ("granulación", format!("{:.0} ({} octavas)", SUN.granule_scale, SUN.granule_octaves)),
+      ("renovación", format!("{}", SUN.granule_speed)),
+      ("protuberancias", format!("{:.1}, umbral {:.2}", SUN.prominence_scale, SUN.prominence_threshold)),
+      ("emisión", format!("{:.2}", SUN.prominence_strength)),
+      ("rotación", format!("{} + {} cos² lat", SUN.rotation.base_rate, SUN.rotation.delta_rate)),
+    ],
+    ShaderType::Earth => vec![
+      ("continentes", format!("umbral {:.2}", EARTH_CONTINENT_THRESHOLD)),
+      ("nubes", format!("{} capas, opacidad {:.2}", EARTH_CLOUD_LAYERS.len(), EARTH_CLOUD_OPACITY)),
+      ("sombra de nubes", format!("{:.2}", EARTH_CLOUD_SHADOW_STRENGTH)),
+    ],
+    ShaderType::GasPlanet => bands(&GAS_PLANET_BANDS),
+    ShaderType::RingPlanet => bands(&RING_PLANET_BANDS),
+    ShaderType::RockyPlanet | ShaderType::RockyBiome => vec![
+      ("escala", format!("{:.1}", ROCKY_SCALE)),
+      ("casquetes", format!("{:.2} / {:.2} rad", ROCKY_NORTH_CAP, ROCKY_SOUTH_CAP)),
+      ("estaciones", format!("{}, {:.2}", ROCKY_SEASON_SPEED, ROCKY_SEASON_AMOUNT)),
+    ],
+    ShaderType::IcyPlanet => vec![
+      ("fresnel", format!("{:.2}", ICE_FRESNEL_F0)),
+      ("reflejo", format!("{:.2}", ICE_REFLECTION_STRENGTH)),
+      ("aurora", format!("{:.1} a {:.1} rad", AURORA_LATITUDE.0, AURORA_LATITUDE.1)),
+    ],
+    ShaderType::VolcanicPlanet => vec![
+      ("escala de lava", format!("{:.1}", VOLCANIC_LAVA_SCALE)),
+      ("umbral de lava", format!("{:.2}", VOLCANIC_LAVA_THRESHOLD)),
+      ("flujo", format!("{:.2}", VOLCANIC_FLOW_SPEED)),
+      ("pulso", format!("{:.2}, {:.2}", VOLCANIC_PULSE_SPEED, VOLCANIC_PULSE_AMOUNT)),
+      ("emisión", format!("{:.2}", VOLCANIC_EMISSION)),
+    ],
+    ShaderType::Moon => vec![
+      ("cráteres", MOON_CRATER_COUNT.to_string()),
+      ("bordes", format!("{:.2}, {:.2}", MOON_RIM_WIDTH, MOON_RIM_STRENGTH)),
+    ],
+    ShaderType::Ring => vec![("opacidad", format!("{:.2}", RING_OPACITY))],
+    ShaderType::Triplanar => vec![("escala", format!("{:.1}", TRIPLANAR_SCALE))],
+    ShaderType::Asteroid | ShaderType::VertexColor | ShaderType::Flat => Vec::new(),
+  };
+  parameters.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+}
+
+// Los shaders incluidos que solo cambian el color, el albedo y la temperatura
+macro_rules! surface_shader {
+  ($shader:ident, $name:literal, $shade:path, $albedo:expr) => {
+    surface_shader!($shader, $name, $shade, $albedo, surface_temperature);
+  };
+  ($shader:ident, $name:literal, $shade:path, $albedo:expr, $temperature:path) => {
+    pub struct $shader;
+
+    impl FragmentShader for $shader {
+      fn shade(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+        $shade(fragment, frame, body)
+      }
+
+      fn name(&self) -> &str {
+        $name
+      }
+
+      fn albedo(&self) -> Option<Color> {
+        $albedo
+      }
+
+      fn temperature(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+        $temperature(fragment, frame, body)
+      }
+    }
+  };
+}
+
+surface_shader!(SunShader, "sun", sun_shader, None, sun_temperature);
+surface_shader!(EarthShader, "earth", earth_shader, Some(Color::new(70, 110, 160)), earth_temperature);
+surface_shader!(GasPlanetShader, "gas", gas_planet_shader, Some(Color::new(200, 170, 130)));
+surface_shader!(RingPlanetShader, "ringed", ring_planet_shader, Some(Color::new(210, 190, 150)));
+surface_shader!(RockyPlanetShader, "rocky", rocky_planet_shader, Some(Color::new(190, 90, 70)));
+surface_shader!(RockyBiomeShader, "rocky-biome", rocky_biome_shader, Some(Color::new(150, 120, 85)));
+surface_shader!(IcyPlanetShader, "icy", icy_planet_shader, Some(Color::new(200, 225, 240)), icy_planet_temperature);
+surface_shader!(VolcanicPlanetShader, "volcanic", volcanic_planet_shader, Some(Color::new(70, 50, 45)), volcanic_planet_temperature);
+surface_shader!(MoonShader, "moon", moon_shader, Some(Color::new(180, 180, 180)), moon_temperature);
+surface_shader!(AsteroidShader, "asteroid", asteroid_shader, Some(ASTEROID_DARK.lerp(&ASTEROID_LIGHT, 0.5)));
+surface_shader!(VertexColorShader, "vertex-color", vertex_color_shader, Some(Color::new(200, 200, 200)));
+
+// Los anillos se ven a través de sus huecos
+pub struct RingShader;
+
+impl FragmentShader for RingShader {
+  fn shade(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+    ring_shader(fragment, frame, body)
+  }
+
+  fn name(&self) -> &str {
+    "ring"
+  }
+
+  fn alpha(&self, fragment: &Fragments, body: &BodyUniforms) -> f32 {
+    match &body.ring_profile {
+        Some(profile) => ring_profile_sample(profile, fragment).opacity(),
+        None => ring_opacity(None, ring_band(&fragment.vertex_pos)),
+    }
+  }
+
+  fn blend_mode(&self) -> BlendMode {
+    BlendMode::AlphaBlend
+  }
+}
+
+// Un color por triángulo, el del vértice que le pasa el pipeline
+pub struct FlatShader;
+
+impl FragmentShader for FlatShader {
+  fn shade(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+    vertex_color_shader(fragment, frame, body)
+  }
+
+  fn name(&self) -> &str {
+    "flat"
+  }
+
+  fn albedo(&self) -> Option<Color> {
+    Some(Color::new(200, 200, 200))
+  }
+
+  fn flat_shading(&self) -> bool {
+    true
+  }
+}
+
+fn vertex_color_shader(fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+  fragment.color * fragment.light
+}
+
+// Lo que el shader tri-planar proyecta en cada plano
+#[derive(Clone)]
+pub enum TriplanarPattern {
+  // Roca procedural que cambia con la semilla del cuerpo
+  Rock,
+  Texture(Arc<Texture>),
+  Custom(Arc<dyn Fn(Vec2) -> Color + Send + Sync>),
+}
+
+// Patrón proyectado desde los tres ejes del objeto y mezclado según la normal: no necesita uv ni que
+// la malla sea una esfera. Es el aspecto por defecto de los modelos de --model que no traen uv
+pub struct TriplanarShader {
+  pub pattern: TriplanarPattern,
+  pub scale: f32,     // Repeticiones del patrón por unidad del objeto
+}
+
+impl TriplanarShader {
+  pub fn new(pattern: impl Fn(Vec2) -> Color + Send + Sync + 'static, scale: f32) -> Self {
+    TriplanarShader { pattern: TriplanarPattern::Custom(Arc::new(pattern)), scale }
+  }
+
+  pub fn texture(texture: Texture, scale: f32) -> Self {
+    TriplanarShader { pattern: TriplanarPattern::Texture(Arc::new(texture)), scale }
+  }
+}
+
+// El de ShaderType::Triplanar
+const TRIPLANAR_SCALE: f32 = 4.0;
+static TRIPLANAR_ROCK: TriplanarShader = TriplanarShader { pattern: TriplanarPattern::Rock, scale: TRIPLANAR_SCALE };
+
+// Modo de depuración que muestra el peso de cada plano tri-planar (rojo X, verde Y, azul Z)
+pub const TRIPLANAR_DEBUG_MODE: u32 = 3;
+
+impl FragmentShader for TriplanarShader {
+  fn shade(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+    let normal = object_normal(fragment, body);
+    if frame.debug_mode == TRIPLANAR_DEBUG_MODE {
+      return triplanar_weights_color(normal);
+    }
+
+    let color = match &self.pattern {
+      TriplanarPattern::Rock => triplanar_sample(&|uv| triplanar_rock(body.seed, uv), fragment.vertex_pos, normal, self.scale),
+      TriplanarPattern::Texture(texture) => triplanar_texture(texture, fragment.vertex_pos, normal, self.scale),
+      TriplanarPattern::Custom(pattern) => triplanar_sample(&|uv| pattern(uv), fragment.vertex_pos, normal, self.scale),
+    };
+    color * fragment.light
+  }
+
+  fn name(&self) -> &str {
+    "triplanar"
+  }
+
+  fn albedo(&self) -> Option<Color> {
+    match self.pattern {
+      TriplanarPattern::Rock => Some(TRIPLANAR_ROCK_DARK.lerp(&TRIPLANAR_ROCK_LIGHT, 0.5)),
+      _ => Some(Color::new(200, 200, 200)),
+    }
+  }
+}
+
+const TRIPLANAR_ROCK_DARK: Color = Color::new(85, 75, 65);
+const TRIPLANAR_ROCK_LIGHT: Color = Color::new(175, 160, 140);
+
+// Roca gris parda: ruido fbm en el plano con vetas más oscuras
+fn triplanar_rock(seed: u32, uv: Vec2) -> Color {
+  let point = Vec3::new(uv.x, uv.y, 0.0);
+  let rock = noise::fbm3(seed, point * 3.0, 4);
+  let veins = (noise::fbm3(rng::combine(seed, 1), point * 1.5, 3) - 0.5).abs();
+  let shade = rock * (0.6 + 0.4 * (veins * 12.0).min(1.0));
+  TRIPLANAR_ROCK_DARK.lerp(&TRIPLANAR_ROCK_LIGHT, shade)
+}
+
+fn triplanar_weights_color(normal: Vec3) -> Color {
+  let weights = triplanar_weights(normal, TRIPLANAR_SHARPNESS);
+  Color { r: weights.x * 255.0, g: weights.y * 255.0, b: weights.z * 255.0 }
+}
+
+// Shader de un cuerpo de la escena: uno de los incluidos (los que nombran los archivos de escena) o uno
+// propio, para quien usa la biblioteca desde código
+#[derive(Clone)]
+pub enum SceneShader {
+  Builtin(ShaderType),
+  Custom(Arc<dyn FragmentShader>),
+}
+
+impl SceneShader {
+  pub fn custom(shader: impl FragmentShader + 'static) -> Self {
+    SceneShader::Custom(Arc::new(shader))
+  }
+
+  fn get(&self) -> &dyn FragmentShader {
+    match self {
+      SceneShader::Builtin(shader) => shader.as_shader(),
+      SceneShader::Custom(shader) => shader.as_ref(),
+    }
+  }
+}
+
+impl From<ShaderType> for SceneShader {
+  fn from(shader: ShaderType) -> Self {
+    SceneShader::Builtin(shader)
+  }
+}
+
+impl std::fmt::Debug for SceneShader {
+  fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    match self {
+      SceneShader::Builtin(shader) => write!(formatter, "{:?}", shader),
+      SceneShader::Custom(shader) => write!(formatter, "Custom({})", shader.name()),
+    }
+  }
 }
 
-pub fn fragment_shader(fragment: &Fragments, uniforms: &Uniforms, current_shader: &ShaderType) -> Color {
-  match current_shader {
-    ShaderType::Sun => sun_shader(fragment, uniforms),
-    ShaderType::Earth => earth_shader(fragment, uniforms),
-    ShaderType::GasPlanet => gas_planet_shader(fragment, uniforms),
-    ShaderType::RingPlanet => ring_planet_shader(fragment, uniforms),
-    ShaderType::RockyPlanet => rocky_planet_shader(fragment, uniforms),
-    ShaderType::IcyPlanet => icy_planet_shader(fragment, uniforms),
-    ShaderType::VolcanicPlanet => volcanic_planet_shader(fragment, uniforms),
-    ShaderType::Moon => moon_shader(fragment, uniforms),
-    ShaderType::Ring => ring_shader(fragment, uniforms),
+// Dos shaders propios son iguales solo si son el mismo objeto
+impl PartialEq for SceneShader {
+  fn eq(&self, other: &Self) -> bool {
+    match (self, other) {
+      (SceneShader::Builtin(a), SceneShader::Builtin(b)) => a == b,
+      (SceneShader::Custom(a), SceneShader::Custom(b)) => Arc::ptr_eq(a, b),
+      _ => false,
+    }
+  }
+}
+
+impl PartialEq<ShaderType> for SceneShader {
+  fn eq(&self, other: &ShaderType) -> bool {
+    *self == SceneShader::Builtin(*other)
+  }
+}
+
+impl FragmentShader for SceneShader {
+  fn shade(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+    self.get().shade(fragment, frame, body)
+  }
+
+  fn name(&self) -> &str {
+    self.get().name()
+  }
+
+  fn albedo(&self) -> Option<Color> {
+    self.get().albedo()
+  }
+
+  fn alpha(&self, fragment: &Fragments, body: &BodyUniforms) -> f32 {
+    self.get().alpha(fragment, body)
+  }
+
+  fn blend_mode(&self) -> BlendMode {
+    self.get().blend_mode()
+  }
+
+  fn flat_shading(&self) -> bool {
+    self.get().flat_shading()
+  }
+
+  fn temperature(&self, fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+    self.get().temperature(fragment, frame, body)
+  }
+
+  // Los incluidos los da el enum, no la estructura de cada uno
+  fn parameters(&self) -> Vec<(String, String)> {
+    match self {
+      SceneShader::Builtin(shader) => builtin_parameters(*shader),
+      SceneShader::Custom(shader) => shader.parameters(),
+    }
+  }
+}
+
+pub fn fragment_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms, current_shader: &dyn FragmentShader) -> Color {
+  // Sombreado por bandas: la intensidad se redondea hacia arriba a la banda más cercana
+  // y la luz de color se escala en la misma proporción
+  let quantized;
+  let fragment = match frame.cel_bands {
+    Some(bands) if bands > 0 && fragment.intensity > 0.0 => {
+      let bands = bands as f32;
+      let intensity = (fragment.intensity * bands).ceil() / bands;
+      let light = fragment.light * (intensity / fragment.intensity);
+      quantized = Fragments { intensity, light, ..*fragment };
+      &quantized
+    }
+    _ => fragment,
+  };
+
+  // Vista infrarroja: la temperatura del shader pasa por el degradado en lugar del color
+  let color = match &frame.thermal {
+    Some(gradient) => Color::from_gradient(current_shader.temperature(fragment, frame, body), gradient),
+    None => current_shader.shade(fragment, frame, body),
+  };
+
+  // Cuerpo bajo el mouse: un poco más brillante y con el borde iluminado
+  if body.highlighted {
+    let rim = 1.0 - dot(&fragment.normal, &view_direction(frame)).abs();
+    color * (1.0 + HIGHLIGHT_BOOST) + Color::new(255, 255, 255) * (rim.powi(3) * HIGHLIGHT_RIM)
+  } else {
+    color
   }
 }
 
+// Aumento de brillo y del borde del cuerpo resaltado
+pub const HIGHLIGHT_BOOST: f32 = 0.12;
+const HIGHLIGHT_RIM: f32 = 0.35;
+
+// Vista infrarroja: temperatura relativa del lado de noche y del de día de una superficie que no emite;
+// la noche no llega a 0 para que los cuerpos se distingan del espacio
+const THERMAL_NIGHT: f32 = 0.12;
+const THERMAL_DAY: f32 = 0.5;
+
+// Temperatura de una superficie que solo se calienta con la luz que recibe (`intensity` como en Fragments)
+pub fn lit_temperature(intensity: f32) -> f32 {
+  THERMAL_NIGHT + (THERMAL_DAY - THERMAL_NIGHT) * intensity.clamp(0.0, 1.0)
+}
+
+fn surface_temperature(fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> f32 {
+  lit_temperature(fragment.intensity)
+}
+
 
 
 // Planeta de hielo
-pub fn icy_planet_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
+pub fn icy_planet_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
   let base_color = Color::new(173, 216, 230); // Celeste
   let fracture_color = Color::new(255, 255, 255); // Blanco
 
@@ -80,58 +653,478 @@ pub fn icy_planet_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
   let fracture_factor = (1.0 - stripe_factor).powf(3.0);
   let fractured_surface = base_color.lerp(&fracture_color, fracture_factor);
 
-  // Reflejo
+  // Reflejo de las luces de la escena, en el espacio del mundo para que no gire con el planeta
   let normal = fragment.normal.normalize();
-  let light_dir = Vec3::new(0.0, 0.0, -1.0);
-  let view_dir = -fragment.vertex_pos.normalize();
-  let reflect_dir = (2.0 * dot(&light_dir, &normal) * normal - light_dir).normalize();
-  let specular_intensity = dot(&reflect_dir, &view_dir).max(0.0).powf(32.0);
+  let specular = specular_light(&normal, &fragment.world_pos, &view_direction(frame), &frame.lights, 32.0);
+  let specular_intensity = ((specular.x + specular.y + specular.z) / 3.0).min(1.0);
   let specular_color = Color::new(255, 255, 255);
   let reflected_surface = fractured_surface.lerp(&specular_color, specular_intensity * 0.5);
 
+  let diffuse = dominant_diffuse(fragment, frame);
+  let twilight = ICE_TWILIGHT.factor(diffuse);
+
+  // Reflejo del cielo en el mundo: tenue de frente y fuerte en los bordes (Fresnel de Schlick)
+  let incident = (fragment.world_pos - frame.camera_position).normalize();
+  let mirror_dir = incident - 2.0 * dot(&incident, &normal) * normal;
+  let environment_color = environment(&mirror_dir);
+  let facing = dot(&normal, &-incident).clamp(0.0, 1.0);
+  let fresnel = ICE_FRESNEL_F0 + (1.0 - ICE_FRESNEL_F0) * (1.0 - facing).powi(5);
+  let environment_reflection = environment_color * (fresnel * ICE_REFLECTION_STRENGTH);
+
+  // Aurora: emisiva, se suma después de la iluminación y se ve sobre todo en el lado nocturno
+  let (aurora_mask, aurora_color) = visible_aurora(fragment, frame, body);
+  let aurora_emission = aurora_color * (aurora_mask * AURORA_STRENGTH);
+
   // Depuración
-  match uniforms.debug_mode {
+  match frame.debug_mode {
       1 => base_color * fragment.intensity,            // Solo el color base
       2 => fracture_color * fracture_factor,           // Solo las grietas
       3 => specular_color * specular_intensity,        // Solo la reflexión especular
-      _ => reflected_surface * fragment.intensity,     // Shader completo
+      4 => Color::new(255, 255, 255) * aurora_mask,    // Solo la máscara de la aurora
+      5 => aurora_emission,                            // Solo el color de la aurora
+      6 => environment_color,                          // Solo el cielo reflejado, sin Fresnel
+      TWILIGHT_DEBUG_MODE => Color::new(255, 255, 255) * twilight, // Solo la banda del crepúsculo
+      _ => ICE_TWILIGHT.apply(reflected_surface * fragment.light, diffuse) + environment_reflection + aurora_emission, // Shader completo
   }
 }
 
+// Aurora del planeta de hielo en el fragmento, con la máscara apagada del lado de día
+fn visible_aurora(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> (f32, Color) {
+  let (mask, color) = aurora(fragment.vertex_pos, frame.time, rng::combine(body.seed, 0xA0A0));
+  let darkness = ((AURORA_NIGHT_START - fragment.intensity) / AURORA_NIGHT_START).clamp(0.0, 1.0);
+  (mask * darkness, color)
+}
+
+// En infrarrojo el hielo queda más frío que la roca y la aurora se ve como un brillo tibio
+const ICE_THERMAL_SCALE: f32 = 0.7;
+const AURORA_HEAT: f32 = 0.35;
+
+fn icy_planet_temperature(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+  let (aurora_mask, _) = visible_aurora(fragment, frame, body);
+  (lit_temperature(fragment.intensity) * ICE_THERMAL_SCALE + aurora_mask * AURORA_HEAT).clamp(0.0, 1.0)
+}
+
+// Reflejo del cielo en el planeta de hielo
+const ICE_FRESNEL_F0: f32 = 0.04;               // Reflectancia mirando de frente
+const ICE_REFLECTION_STRENGTH: f32 = 0.9;
+// Crepúsculo violeta
+const ICE_TWILIGHT: Twilight = Twilight { tint: Color::new(150, 90, 210), width: 0.1, strength: 0.35 };
+
+// Cielo de estrellas procedural, fijo en el mundo
+const ENVIRONMENT_SEED: u32 = 0x57A125;
+const STAR_GRID: (f32, f32) = (64.0, 32.0);     // Celdas en u y v, cada una con a lo sumo una estrella
+const STAR_DENSITY: f32 = 0.35;                 // Fracción de celdas con estrella
+const STAR_RADIUS: f32 = 0.3;                   // En fracción de la celda
+const SKY_COLOR: Color = Color::new(4, 6, 14);
+
+// Coordenadas esféricas de un punto a cualquier distancia del centro: (latitud, longitud) en radianes,
+// la latitud en [-π/2, π/2] positiva hacia +y y la longitud en [-π, π] desde +x hacia +z.
+// En los polos la longitud no está definida y se usa 0; el centro mismo da (0, 0)
+pub fn latitude_longitude(position: &Vec3) -> (f32, f32) {
+  let Some(direction) = position.try_normalize(0.0) else {
+    return (0.0, 0.0);
+  };
+  let latitude = direction.y.clamp(-1.0, 1.0).asin();
+  let longitude = if direction.x == 0.0 && direction.z == 0.0 { 0.0 } else { direction.z.atan2(direction.x) };
+  (latitude, longitude)
+}
+
+// Coordenadas UV equirectangulares, siempre en [0, 1]: u = 0.5 en +x y crece hacia +z, con la costura
+// (0 y 1) en -x; v = 0 en el polo norte (+y) y 1 en el sur, igual que el cargador de OBJ
+pub fn spherical_uv(position: Vec3) -> Vec2 {
+  let (latitude, longitude) = latitude_longitude(&position);
+  Vec2::new((0.5 + longitude / (2.0 * PI)).clamp(0.0, 1.0), (0.5 - latitude / PI).clamp(0.0, 1.0))
+}
+
+// Exponente de los pesos tri-planares: más alto da transiciones más cortas entre planos, más bajo
+// las mezcla en una franja ancha (con 1 se ven las tres proyecciones a la vez en las diagonales)
+pub const TRIPLANAR_SHARPNESS: f32 = 4.0;
+
+// Cuánto aporta cada plano de proyección (X, Y, Z) según la normal; suman 1. Una normal nula no
+// elige ningún plano y reparte por igual
+pub fn triplanar_weights(normal: Vec3, sharpness: f32) -> Vec3 {
+  let weights = normal.map(|component| component.abs().powf(sharpness));
+  let total = weights.x + weights.y + weights.z;
+  if total > 0.0 && total.is_finite() {
+    weights / total
+  } else {
+    Vec3::repeat(1.0 / 3.0)
+  }
+}
+
+// Coordenadas del punto en cada plano, con la misma orientación en las dos caras de cada eje (sin
+// espejar) para que el patrón sea continuo al pasar de +x a -x: X usa (z, y), Y usa (x, z) y Z usa (x, y)
+pub fn triplanar_uvs(pos: Vec3, scale: f32) -> [Vec2; 3] {
+  let pos = pos * scale;
+  [Vec2::new(pos.z, pos.y), Vec2::new(pos.x, pos.z), Vec2::new(pos.x, pos.y)]
+}
+
+// Patrón 2D proyectado desde los tres ejes y mezclado según la normal (las dos en el espacio del
+// objeto). No usa uv, así que no tiene costuras ni se estira en los polos como la proyección esférica
+// y sirve para mallas deformadas o modelos sin coordenadas de textura. Con una normal alineada a un eje
+// el resultado es exactamente la muestra de ese plano; los planos sin peso no se muestrean
+pub fn triplanar_sample(pattern: &impl Fn(Vec2) -> Color, pos: Vec3, normal: Vec3, scale: f32) -> Color {
+  let weights = triplanar_weights(normal, TRIPLANAR_SHARPNESS);
+  let uvs = triplanar_uvs(pos, scale);
+  let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+  for (weight, uv) in weights.iter().zip(uvs) {
+    if *weight > 0.0 {
+      let sample = pattern(uv);
+      r += sample.r * weight;
+      g += sample.g * weight;
+      b += sample.b * weight;
+    }
+  }
+  Color { r, g, b }
+}
+
+// Igual que triplanar_sample con una imagen; `scale` es cuántas veces se repite por unidad del objeto
+pub fn triplanar_texture(texture: &Texture, pos: Vec3, normal: Vec3, scale: f32) -> Color {
+  triplanar_sample(&|uv| texture.sample(uv), pos, normal, scale)
+}
+
+// Normal en el espacio del objeto a partir de la del mundo: la inversa de la matriz de las normales es
+// la transpuesta de la de modelo, así que vale también con escala distinta en cada eje
+pub fn object_normal(fragment: &Fragments, body: &BodyUniforms) -> Vec3 {
+  let normal = mat4_to_mat3(body.model_matrix()).transpose() * fragment.normal;
+  normal.try_normalize(0.0).unwrap_or(normal)
+}
+
+// Color del cielo en una dirección del mundo (normalizada), muestreado como mapa equirectangular
+pub fn environment(direction: &Vec3) -> Color {
+  let sky = sky_dust(direction);
+  match star(direction, &ENVIRONMENT_STARS, None) {
+    Some(star) => sky + star,
+    None => sky,
+  }
+}
+
+// Fondo de estrellas de la escena: el mismo cielo que environment con estrellas más chicas y más
+// separadas, que titilan despacio con `time` (cada una con su velocidad y su fase, así con el tiempo
+// quieto el cielo no cambia y un frame exportado no depende de los anteriores)
+pub fn starfield(direction: &Vec3, time: f32) -> Color {
+  let sky = sky_dust(direction);
+  match star(direction, &BACKGROUND_STARS, Some(time)) {
+    Some(star) => sky + star,
+    None => sky,
+  }
+}
+
+// Luz que da el cielo de estrellas (el del instante 0); se calcula la primera vez que se pide
+pub fn starfield_ambient() -> AmbientCube {
+  static AMBIENT: OnceLock<AmbientCube> = OnceLock::new();
+  *AMBIENT.get_or_init(|| AmbientCube::from_environment(|direction| starfield(direction, 0.0)))
+}
+
+// Fondo del cielo con una franja tenue de polvo alrededor de un círculo máximo inclinado
+fn sky_dust(direction: &Vec3) -> Color {
+  let band_axis = Vec3::new(0.3, 0.9, 0.3).normalize();
+  let band = (1.0 - dot(direction, &band_axis).abs() / 0.3).max(0.0);
+  if band == 0.0 {
+    return SKY_COLOR;
+  }
+  let dust = noise::fbm3(ENVIRONMENT_SEED, direction * 4.0, 2);
+  SKY_COLOR + Color::new(70, 55, 100) * (band * band * dust)
+}
+
+// Estrellas repartidas en una grilla de longitud y latitud, a lo sumo una por celda
+struct StarLayer {
+  seed: u32,
+  grid: (f32, f32),     // Celdas en u y v
+  density: f32,         // Fracción de celdas con estrella
+  radius: f32,          // En fracción de la celda
+}
+
+const ENVIRONMENT_STARS: StarLayer = StarLayer { seed: ENVIRONMENT_SEED, grid: STAR_GRID, density: STAR_DENSITY, radius: STAR_RADIUS };
+// Medio grado por celda: a 800 pixeles de ancho cada estrella ocupa uno o dos pixeles
+const BACKGROUND_STARS: StarLayer = StarLayer { seed: ENVIRONMENT_SEED ^ 0xB6, grid: (720.0, 360.0), density: 0.06, radius: 0.3 };
+// Velocidad del titilar en radianes por tick (entre una vuelta cada ~90 y cada ~300 ticks) y cuánto
+// baja el brillo en lo más oscuro
+const TWINKLE_SPEED: (f32, f32) = (0.02, 0.07);
+const TWINKLE_DEPTH: f32 = 0.45;
+
+// Luz de la estrella de la celda de `direction` en ese punto, si la celda tiene estrella y el punto
+// cae dentro de ella; con `twinkle` el brillo oscila con el tiempo
+fn star(direction: &Vec3, layer: &StarLayer, twinkle: Option<f32>) -> Option<Color> {
+  let uv = spherical_uv(*direction);
+  let (columns, rows) = layer.grid;
+  let grid = Vec2::new(uv.x * columns, uv.y * rows);
+  let cell = grid.map(f32::floor);
+  let seed = layer.seed;
+  if rng::hash2(seed, cell.x, cell.y) >= layer.density {
+    return None;
+  }
+
+  let center = Vec2::new(
+    0.3 + 0.4 * rng::hash2(seed ^ 1, cell.x, cell.y),
+    0.3 + 0.4 * rng::hash2(seed ^ 2, cell.x, cell.y),
+  );
+  // Las celdas se angostan hacia los polos; se corrige para que las estrellas queden redondas
+  let offset = grid - cell - center;
+  let latitude_scale = (PI * (uv.y - 0.5)).cos();
+  let distance = Vec2::new(offset.x * latitude_scale, offset.y).magnitude();
+  let glow = (1.0 - distance / layer.radius).max(0.0);
+  if glow == 0.0 {
+    return None;
+  }
+
+  let mut brightness = 0.3 + 0.7 * rng::hash2(seed ^ 3, cell.x, cell.y);
+  if let Some(time) = twinkle {
+    let (slow, fast) = TWINKLE_SPEED;
+    let speed = slow + (fast - slow) * rng::hash2(seed ^ 4, cell.x, cell.y);
+    let phase = rng::hash2(seed ^ 5, cell.x, cell.y) * 2.0 * PI;
+    brightness *= 1.0 - TWINKLE_DEPTH * (0.5 + 0.5 * (time * speed + phase).sin());
+  }
+  Some(Color::new(255, 248, 235) * (glow * glow * brightness))
+}
+
+// Aurora del planeta de hielo
+const AURORA_LATITUDE: (f32, f32) = (1.0, 1.3);  // Rango de |latitud| en radianes donde aparece
+const AURORA_CURTAIN_SCALE: f32 = 3.0;           // Cantidad de cortinas alrededor del polo
+const AURORA_DRIFT: f32 = 0.002;                 // Radianes por tick que avanzan las cortinas
+const AURORA_SHIMMER_SPEED: f32 = 0.01;          // Velocidad con la que cambia la forma de las cortinas
+const AURORA_NIGHT_START: f32 = 0.35;            // Intensidad difusa a partir de la cual ya no se ve
+const AURORA_STRENGTH: f32 = 0.9;
+
+// Máscara en [0, 1] y color de la aurora para un punto en coordenadas del objeto
+fn aurora(position: Vec3, time: f32, seed: u32) -> (f32, Color) {
+  let (latitude, longitude) = latitude_longitude(&position);
+  let north = latitude > 0.0;
+  let (latitude, longitude) = (latitude.abs(), longitude + time * AURORA_DRIFT);
+
+  // El ruido se toma sobre un círculo para que no haya costura en la longitud
+  let circle = Vec3::new(longitude.cos(), longitude.sin(), 0.0) * AURORA_CURTAIN_SCALE;
+  let pole = if north { 0.0 } else { 17.0 };
+  let flow = Vec3::new(0.0, 0.0, time * AURORA_SHIMMER_SPEED + pole);
+  let curtains = noise::fbm3(seed, circle + flow, 3);
+  let ripple = noise::value_noise3(seed ^ 0x5EED, circle * 2.0 + flow * 1.5);
+
+  // El borde inferior ondula con el ruido y la cortina se desvanece hacia el polo
+  let (start, end) = AURORA_LATITUDE;
+  let lower = start + (ripple - 0.5) * 0.12;
+  let height = ((latitude - lower) / (end - lower)).clamp(0.0, 1.0);
+  let rise = ((latitude - lower) / 0.04).clamp(0.0, 1.0);
+  let band = rise * (1.0 - height).powf(1.5);
+
+  let strands = 0.65 + 0.35 * (longitude * 40.0 + curtains * 8.0).sin();
+  let strength = ((curtains - 0.35) / 0.35).clamp(0.0, 1.0);
+  let mask = (band * strength * strands).clamp(0.0, 1.0);
+
+  let green = Color::new(60, 255, 140);
+  let purple = Color::new(170, 60, 255);
+  (mask, green.lerp(&purple, height))
+}
+
 // Planeta volcánico
-pub fn volcanic_planet_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
+const VOLCANIC_LAVA_SCALE: f32 = 15.0;        // Tamaño de las grietas de lava
+const VOLCANIC_FLOW_SPEED: f32 = 0.1;         // Movimiento de la lava
+const VOLCANIC_LAVA_THRESHOLD: f32 = 0.7;     // Ruido a partir del cual hay lava
+const VOLCANIC_CRUST_BAND: (f32, f32) = (0.02, 0.12); // Rango de lava_factor de la costra que se enfría
+const VOLCANIC_PULSE_SPEED: f32 = 0.03;       // Velocidad del pulso de los puntos más calientes
+const VOLCANIC_PULSE_AMOUNT: f32 = 0.25;      // Cuánto sube la temperatura en el pulso
+const VOLCANIC_EMISSION: f32 = 0.9;           // Emisión de la lava a temperatura máxima
+
+// Degradado de cuerpo negro: rojo oscuro -> naranja -> amarillo -> blanco
+const BLACKBODY: [(f32, Color); 4] = [
+  (0.0, Color::new(120, 10, 0)),
+  (0.4, Color::new(255, 100, 0)),
+  (0.75, Color::new(255, 220, 60)),
+  (1.0, Color::new(255, 255, 230)),
+];
+
+fn blackbody_color(temperature: f32) -> Color {
+  Color::from_gradient(temperature, &BLACKBODY)
+}
+
+// Cantidad de lava en un punto del planeta volcánico (0 = roca, 1 = lava en el centro de la grieta)
+pub fn lava_factor(position: &Vec3, time: f32) -> f32 {
+  filtered_lava_factor(position, time, 0.0)
+}
+
+// Ruido de las grietas antes de quedarse con la parte fraccionaria, de 0 a 1.5
+fn lava_noise(position: &Vec3, time: f32) -> f32 {
+  let noise_x = position.x * VOLCANIC_LAVA_SCALE + time * VOLCANIC_FLOW_SPEED;
+  let noise_y = position.y * VOLCANIC_LAVA_SCALE - time * VOLCANIC_FLOW_SPEED;
+  (noise_x.sin() * noise_y.cos()).abs() * 1.5
+}
+
+// Donde el ruido pasa por 1 la parte fraccionaria vuelve a 0 y la lava más caliente termina de golpe
+// contra la roca: ese salto se suaviza según `derivative_scale`, lo que cambia el ruido por pixel
+fn filtered_lava_factor(position: &Vec3, time: f32, derivative_scale: f32) -> f32 {
+  let noise = lava_noise(position, time);
+  let lava = |noise: f32| (noise - VOLCANIC_LAVA_THRESHOLD).max(0.0) / (1.0 - VOLCANIC_LAVA_THRESHOLD);
+  let wrapped = smooth_threshold(noise, 1.0, derivative_scale);
+  lava(noise) * (1.0 - wrapped) + lava(noise - 1.0) * wrapped
+}
+
+// Lava del fragmento (lava_factor) y su temperatura: los puntos más calientes pulsan, cada uno con su
+// propia fase
+fn volcanic_lava(fragment: &Fragments, time: f32) -> (f32, f32) {
+  let lava_factor = filtered_lava_factor(&fragment.vertex_pos, time, fragment.fwidth(|position| lava_noise(position, time)));
+  let phase = (fragment.vertex_pos.x * 7.3 + fragment.vertex_pos.y * 5.1 + fragment.vertex_pos.z * 3.7) * 4.0;
+  let pulse = ((time * VOLCANIC_PULSE_SPEED + phase).sin() * 0.5 + 0.5) * VOLCANIC_PULSE_AMOUNT;
+  (lava_factor, (lava_factor * (1.0 + pulse * lava_factor)).clamp(0.0, 1.0))
+}
+
+// En infrarrojo la lava va de VOLCANIC_LAVA_HEAT.0 (recién salida de la costra) a .1 (la más caliente);
+// la costra oscura se ve más fría que una roca cualquiera al lado de las grietas
+const VOLCANIC_LAVA_HEAT: (f32, f32) = (0.7, 1.0);
+const VOLCANIC_CRUST_THERMAL_SCALE: f32 = 0.6;
+
+fn volcanic_planet_temperature(fragment: &Fragments, frame: &FrameUniforms, _body: &BodyUniforms) -> f32 {
+  let (lava_factor, temperature) = volcanic_lava(fragment, frame.time);
+  let crust = lit_temperature(fragment.intensity) * VOLCANIC_CRUST_THERMAL_SCALE;
+  let (cool, hot) = VOLCANIC_LAVA_HEAT;
+  // Pasada la costra que se enfría ya es lava del todo, así las grietas se ven enteras
+  let coverage = (lava_factor / VOLCANIC_CRUST_BAND.1).min(1.0);
+  crust + (cool + (hot - cool) * temperature - crust) * coverage
+}
+
+pub fn volcanic_planet_shader(fragment: &Fragments, frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
   let rock_color = Color::new(50, 50, 50);    // Gris oscuro
-  let lava_color = Color::new(255, 100, 0);    // Naranja más intenso (más saturado)
-
-  // Lava
-  let lava_scale = 15.0;
-  let noise_x = fragment.vertex_pos.x * lava_scale + uniforms.time as f32 * 0.1;
-  let noise_y = fragment.vertex_pos.y * lava_scale - uniforms.time as f32 * 0.1;
-  let lava_noise = ((noise_x.sin() * noise_y.cos()).abs() * 1.5).fract();
-  let lava_factor = (lava_noise - 0.7).max(0.0) / 0.3;
+  let crust_color = Color::new(140, 60, 30);  // Costra que se está enfriando
+  let time = frame.time;
+
+  // Lava y su temperatura
+  let (lava_factor, temperature) = volcanic_lava(fragment, time);
+
+  let lava_color = blackbody_color(temperature);
   let surface_color = rock_color.lerp(&lava_color, lava_factor);
 
-  // Brillo
-  let glow_factor = (lava_factor.powf(2.0) * 0.8).clamp(0.0, 1.0);
-  let glow_color = lava_color.lerp(&Color::new(255, 255, 50), glow_factor);
-  let final_color = surface_color.lerp(&glow_color, glow_factor);
+  // Borde brillante entre la roca y la lava
+  let (crust_start, crust_end) = VOLCANIC_CRUST_BAND;
+  let crust_factor = if lava_factor > crust_start && lava_factor < crust_end {
+      1.0 - ((lava_factor - crust_start) / (crust_end - crust_start) * 2.0 - 1.0).abs()
+  } else {
+      0.0
+  };
+  let surface_color = surface_color.lerp(&crust_color, crust_factor);
 
-  // Luz de la lava
-  let lava_emission_factor = 0.8;
-  let lava_emitted_color = lava_color * lava_emission_factor;
-  let emitted_color = final_color.lerp(&lava_emitted_color, lava_factor);
+  // La emisión crece con la temperatura y no depende de la luz
+  let emission = lava_color * (temperature * temperature * VOLCANIC_EMISSION);
 
   // Depuración
-  match uniforms.debug_mode {
-      1 => rock_color * fragment.intensity,             // Only rock color
-      2 => lava_color * lava_factor,                    // Only lava regions
-      3 => glow_color * glow_factor,                    // Only glow effect
-      _ => emitted_color * fragment.intensity,          // Full shader with emission effect
+  match frame.debug_mode {
+      1 => rock_color * fragment.intensity,                 // Only rock color
+      2 => lava_color * lava_factor,                        // Only lava regions
+      3 => emission,                                        // Only glow effect
+      4 => Color::new(255, 255, 255) * temperature,         // Temperature field
+      _ => surface_color * fragment.light + emission,       // Full shader with emission effect
   }
 }
 
 // Sol
-pub fn sun_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
+// Granulación y protuberancias del sol
+pub struct SunSettings {
+  pub granule_scale: f32,       // Celdas de convección por unidad del objeto
+  pub granule_octaves: u32,
+  pub granule_speed: f32,       // Veces por tick que el patrón se renueva por completo
+  pub granule_drift: f32,       // Cuánto se mueve cada patrón mientras dura
+  pub granule_contrast: f32,    // Cuánto se corre el degradado entre el centro y el borde de una celda
+  pub prominence_width: f32,    // Ancho de la franja junto al borde, medido en 1 - coseno con la vista
+  pub prominence_scale: f32,    // Lenguas de fuego alrededor del borde
+  pub prominence_threshold: f32, // Valor del ruido desde el que aparece una lengua
+  pub prominence_softness: f32,
+  pub prominence_flicker: f32,  // Velocidad con la que cambian las lenguas
+  pub prominence_strength: f32, // Emisión de las lenguas respecto a su color base
+  pub rotation: DifferentialRotation, // La granulación gira más rápido en el ecuador
+}
+
+pub const SUN: SunSettings = SunSettings {
+  granule_scale: 20.0,
+  granule_octaves: 3,
+  granule_speed: 0.004,
+  granule_drift: 0.15,
+  granule_contrast: 0.3,
+  prominence_width: 0.35,
+  prominence_scale: 3.0,
+  prominence_threshold: 0.55,
+  prominence_softness: 0.12,
+  prominence_flicker: 0.02,
+  prominence_strength: 1.4,
+  rotation: DifferentialRotation { base_rate: 0.0006, delta_rate: 0.0012 },
+};
+
+// Por debajo de este coseno el fragmento mira hacia atrás (con margen para las normales interpoladas del borde)
+const SUN_HIDDEN_FACING: f32 = -0.3;
+const PROMINENCE_COLOR: Color = Color::new(255, 100, 40);
+
+// Granulación en [0, 1] para un punto del objeto: dos patrones de ruido de ciclos seguidos que se
+// mezclan, así la superficie hierve en lugar de desplazarse
+fn sun_granulation(position: &Vec3, time: f32, seed: u32) -> f32 {
+  let position = SUN.rotation.unwind(*position, time);
+  let phase = time * SUN.granule_speed;
+  let cycle = phase.floor();
+  let blend = phase - cycle;
+  // Cada patrón se mueve un poco mientras dura; al cambiar de ciclo el segundo pasa a ser el primero
+  let pattern = |next: u32| {
+    let cycle_seed = rng::combine(seed, (cycle as i64 as u32).wrapping_add(next));
+    let drift = Vec3::new(1.0, 0.6, -0.8) * (SUN.granule_drift * (blend - next as f32 + 0.5));
+    noise::fbm3(cycle_seed, position * SUN.granule_scale + drift, SUN.granule_octaves)
+  };
+  let t = blend * blend * (3.0 - 2.0 * blend);
+  // Al mezclar dos patrones independientes el contraste baja; se compensa para que no pulse
+  let spread = ((1.0 - t).powi(2) + t * t).sqrt();
+  (0.5 + (pattern(0) * (1.0 - t) + pattern(1) * t - 0.5) / spread).clamp(0.0, 1.0)
+}
+
+// Coseno entre la normal y la dirección desde el fragmento a la cámara: con la cámara cerca el borde
+// del disco no es perpendicular al eje de la vista
+fn sun_facing(fragment: &Fragments, frame: &FrameUniforms) -> f32 {
+  dot(&fragment.normal, &to_camera(frame, &fragment.world_pos))
+}
+
+// Máscara en [0, 1] de las protuberancias: lenguas de fuego en la franja junto al borde del disco,
+// repartidas según el ángulo alrededor del centro en pantalla
+// `facing` es el coseno entre la normal y la dirección a la cámara (ver sun_facing)
+fn sun_prominences(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms, facing: f32) -> f32 {
+  let view = &frame.view_matrix;
+  let right = Vec3::new(view[(0, 0)], view[(0, 1)], view[(0, 2)]);
+  let up = Vec3::new(view[(1, 0)], view[(1, 1)], view[(1, 2)]);
+  let facing = facing.clamp(0.0, 1.0);
+  let shell = ((1.0 - facing - (1.0 - SUN.prominence_width)) / SUN.prominence_width).clamp(0.0, 1.0);
+  if shell <= 0.0 {
+    return 0.0;
+  }
+
+  // El ruido se toma sobre un círculo para que no haya costura en el ángulo
+  let angle = dot(&fragment.normal, &up).atan2(dot(&fragment.normal, &right));
+  let circle = Vec3::new(angle.cos(), angle.sin(), 0.0) * SUN.prominence_scale;
+  let flicker = Vec3::new(0.0, 0.0, frame.time * SUN.prominence_flicker);
+  let seed = rng::combine(body.seed, 0x9A0E);
+  let tongues = noise::fbm3(seed, circle + flicker, 3);
+  let strands = noise::value_noise3(seed ^ 0x5EED, circle * 6.0 + flicker * 2.0);
+  // Cada lengua entra desde el borde hasta una altura que depende del ruido, con la punta deshilachada
+  let reach = ((tongues - SUN.prominence_threshold) / SUN.prominence_softness).clamp(0.0, 1.0) * (0.7 + 0.3 * strands);
+  (((shell - (1.0 - reach)) / 0.3).clamp(0.0, 1.0) * reach).sqrt()
+}
+
+// En infrarrojo el sol está al rojo blanco: la granulación va de SUN_HEAT.0 (bordes de las celdas) a .1
+// (centros), un poco menos hacia el borde del disco, y las protuberancias son más frías que la superficie
+const SUN_HEAT: (f32, f32) = (0.85, 1.0);
+const SUN_LIMB_COOLING: f32 = 0.15;
+const PROMINENCE_HEAT: f32 = 0.75;
+
+fn sun_temperature(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+  let facing = sun_facing(fragment, frame);
+  if facing < SUN_HIDDEN_FACING {
+    return 0.0;
+  }
+  let granulation = sun_granulation(&fragment.vertex_pos, frame.time, body.seed);
+  let (cool, hot) = SUN_HEAT;
+  let limb = dot(&fragment.normal, &view_direction(frame)).max(0.0);
+  let surface = (cool + (hot - cool) * granulation) * (1.0 - SUN_LIMB_COOLING * (1.0 - limb));
+  let prominences = sun_prominences(fragment, frame, body, facing);
+  surface + (PROMINENCE_HEAT - surface) * prominences
+}
+
+pub fn sun_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+  // La cara de atrás del sol siempre queda tapada por la de adelante; no vale la pena calcular el ruido
+  let facing = sun_facing(fragment, frame);
+  if facing < SUN_HIDDEN_FACING {
+    return Color::BLACK;
+  }
+
   // Colores base del degradado
   let color1 = Color::new(255, 255, 255); // Amarillo muy claro
   let color2 = Color::new(255, 230, 28); // Amarillo pastel
@@ -146,8 +1139,11 @@ pub fn sun_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
   let center = (0.0, 0.0);
   let radius = ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt();
 
+  // Las celdas de granulación corren el degradado: el centro de cada una más claro y los bordes más naranjas
+  let granulation = sun_granulation(&fragment.vertex_pos, frame.time, body.seed);
+
   // Radio normalizado entre 0 y 1
-  let t = radius.clamp(0.0, 1.0);
+  let t = (radius + (0.5 - granulation) * SUN.granule_contrast).clamp(0.0, 1.0);
 
   // Mezcla de colores según el radio
   let blended_color = if t < 0.33 {
@@ -158,93 +1154,450 @@ pub fn sun_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
       color3.lerp(&color4, (t - 0.66) / 0.34)
   };
 
-  // Emisión del sol
+  // Emisión del sol; no depende de las luces (en el sistema solar la luz está dentro del sol),
+  // solo se oscurece hacia el borde según la dirección de la cámara
   let emission_factor = 1.5;
   let emitted_color = blended_color * emission_factor;
+  let limb = dot(&fragment.normal, &view_direction(frame)).max(0.0);
+
+  // Las protuberancias tapan el borde oscurecido con su propia emisión, más roja
+  let prominences = sun_prominences(fragment, frame, body, facing);
 
   // Depuración
-  match uniforms.debug_mode {
+  match frame.debug_mode {
       1 => blended_color * fragment.intensity,                      // Degradado sin emisión
       2 => blended_color,                                           // Degradado puro
       3 => Color::new(255, 255, 255) * emission_factor,     // Solo emisión blanca
-      _ => emitted_color * fragment.intensity,                      // Shader completo
+      4 => Color::new(255, 255, 255) * granulation,                 // Solo la granulación
+      5 => Color::new(255, 255, 255) * prominences,                 // Solo las protuberancias
+      _ => (emitted_color * limb).lerp(&(PROMINENCE_COLOR * SUN.prominence_strength), prominences), // Shader completo
   }
 }
 
 // Planeta gaseoso
-pub fn gas_planet_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
-  let band_color1 = Color::new(139, 69, 19);  // Marrón más oscuro
-  let band_color2 = Color::new(205, 133, 63); // Marrón claro
-  let band_color3 = Color::new(222, 184, 135); // Beige
-
-
-  // Franjas horizontales
-  let band_scale = 4.0;
-  let flow_speed = 0.001;
-  let flow_offset = uniforms.time as f32 * flow_speed;
-  let y_position = fragment.vertex_pos.y + flow_offset;
-  let band_factor = ((y_position * band_scale).sin() * 0.5 + 0.5).fract();
-
-  // Mezcla entre colores según la posición en las bandas
-  let band_color = if band_factor < 0.33 {
-      band_color1.lerp(&band_color2, band_factor / 0.33)
+// Parámetros de las franjas, compartidos con el planeta con anillos
+pub struct BandSettings {
+  pub colors: [Color; 3],
+  pub band_scale: f32,     // Número de franjas
+  pub flow_speed: f32,     // Desplazamiento vertical de las franjas
+  pub turbulence: f32,     // Ondulación de los bordes de las franjas
+  pub swirl: f32,          // Remolinos cerca de los bordes
+  pub storms: &'static [Storm],
+  pub limb: LimbDarkening,
+  pub rotation: DifferentialRotation, // Las franjas del ecuador le sacan vuelta a las de latitudes medias
+}
+
+// Rotación diferencial de los cuerpos gaseosos: el patrón gira alrededor del eje y a
+// `base_rate + delta_rate * cos²(latitud)` radianes por tick, además del giro propio del cuerpo
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DifferentialRotation {
+  pub base_rate: f32,
+  pub delta_rate: f32,
+}
+
+impl DifferentialRotation {
+  pub fn rate(&self, latitude: f32) -> f32 {
+    self.base_rate + self.delta_rate * latitude.cos().powi(2)
+  }
+
+  // Punto del patrón sin girar que en `time` quedó en `position`: se gira hacia atrás lo que avanzó su
+  // latitud. Se gira el punto en 3D en lugar de correr la longitud de spherical_uv, así no hay costura
+  // en -x (ni salto en los polos, donde el giro no mueve nada)
+  pub fn unwind(&self, position: Vec3, time: f32) -> Vec3 {
+    let radius_squared = position.magnitude_squared();
+    if radius_squared == 0.0 {
+      return position;
+    }
+    // cos²(latitud) sin pasar por el ángulo
+    let equatorial = (position.x * position.x + position.z * position.z) / radius_squared;
+    let angle = -time * (self.base_rate + self.delta_rate * equatorial);
+    let (sin, cos) = angle.sin_cos();
+    Vec3::new(position.x * cos - position.z * sin, position.y, position.x * sin + position.z * cos)
+  }
+}
+
+// Oscurecimiento hacia el borde: el color se multiplica por `a + b * mu`, con mu el coseno entre la
+// normal y la dirección a la cámara (1 en el centro del disco, 0 en el borde)
+pub struct LimbDarkening {
+  pub a: f32,
+  pub b: f32,
+}
+
+impl LimbDarkening {
+  pub fn factor(&self, fragment: &Fragments, frame: &FrameUniforms) -> f32 {
+    let mu = dot(&fragment.normal, &view_direction(frame)).clamp(0.0, 1.0);
+    self.a + self.b * mu
+  }
+}
+
+// Tormenta ovalada: posición en radianes, deriva en radianes por tick respecto de la rotación de su
+// latitud (la tormenta entera avanza con la de su centro, así el óvalo no se deforma)
+pub struct Storm {
+  pub latitude: f32,
+  pub longitude: f32,
+  pub drift: f32,
+  pub radius: f32,
+  pub stretch: f32,        // Ancho / alto del óvalo
+  pub spin: f32,           // Giro de la espiral interior
+  pub color: Color,
+}
+
+pub const GAS_PLANET_BANDS: BandSettings = BandSettings {
+  colors: [
+      Color::new(139, 69, 19),   // Marrón más oscuro
+      Color::new(205, 133, 63),  // Marrón claro
+      Color::new(222, 184, 135), // Beige
+  ],
+  band_scale: 4.0,
+  flow_speed: 0.001,
+  turbulence: 0.06,
+  swirl: 0.05,
+  storms: &[
+      Storm { latitude: -0.45, longitude: 2.2, drift: 0.0006, radius: 0.16, stretch: 1.8, spin: 0.02, color: Color::new(255, 69, 0) },
+      Storm { latitude: 0.6, longitude: 0.4, drift: -0.0009, radius: 0.08, stretch: 1.5, spin: -0.03, color: Color::new(245, 235, 215) },
+      Storm { latitude: -0.15, longitude: 4.3, drift: 0.0012, radius: 0.06, stretch: 1.4, spin: 0.04, color: Color::new(160, 80, 40) },
+  ],
+  limb: LimbDarkening { a: 0.5, b: 0.5 },
+  rotation: DifferentialRotation { base_rate: 0.001, delta_rate: 0.002 },
+};
+
+const RING_PLANET_BANDS: BandSettings = BandSettings {
+  colors: [
+      Color::new(189, 155, 107), // Marrón claro
+      Color::new(210, 180, 140), // Beige
+      Color::new(255, 222, 173), // Crema
+  ],
+  band_scale: 9.0,               // Franjas más angostas para que se note cómo rodean la tormenta
+  flow_speed: 0.0008,            // Movimiento más lento que Júpiter
+  turbulence: 0.02,
+  swirl: 0.015,
+  storms: &[RING_PLANET_STORM],
+  limb: LimbDarkening { a: 0.6, b: 0.4 }, // Atmósfera más clara: se oscurece menos
+  rotation: DifferentialRotation { base_rate: 0.0008, delta_rate: 0.0012 },
+};
+
+// Gran tormenta persistente del planeta con anillos; las franjas se desvían alrededor
+const RING_STORM_LATITUDE: f32 = -0.35;        // Radianes, negativo = hemisferio sur
+const RING_STORM_SIZE: f32 = 0.22;             // Radio del óvalo en la esfera unitaria
+const RING_STORM_DRIFT: f32 = 0.0004;          // Radianes de longitud por tick
+const RING_STORM_REACH: f32 = 3.0;             // Radios de tormenta fuera del óvalo hasta donde llega el desvío
+const RING_PLANET_STORM: Storm = Storm {
+  latitude: RING_STORM_LATITUDE,
+  longitude: 1.0,
+  drift: RING_STORM_DRIFT,
+  radius: RING_STORM_SIZE,
+  stretch: 1.7,
+  spin: 0.015,
+  color: Color::new(190, 95, 55),
+};
+
+// Ruido suave en 3D a partir de senos; se evalúa sobre la posición en la esfera
+// así que no hay costura donde el patrón da la vuelta
+fn flow_noise(p: Vec3, time: f32) -> f32 {
+  let a = (p.x * 5.1 + p.z * 3.3 + time * 0.002).sin();
+  let b = (p.z * 6.7 - p.y * 2.9 - time * 0.0015).sin();
+  let c = (p.x * 9.3 - p.z * 7.1 + p.y * 4.7).sin();
+  (a * b + c * 0.5) / 1.5
+}
+
+fn band_color(settings: &BandSettings, band_factor: f32) -> Color {
+  let [band_color1, band_color2, band_color3] = &settings.colors;
+  if band_factor < 0.33 {
+      band_color1.lerp(band_color2, band_factor / 0.33)
   } else if band_factor < 0.66 {
-      band_color2.lerp(&band_color3, (band_factor - 0.33) / 0.33)
+      band_color2.lerp(band_color3, (band_factor - 0.33) / 0.33)
   } else {
-      band_color3.lerp(&band_color1, (band_factor - 0.66) / 0.34)
+      band_color3.lerp(band_color1, (band_factor - 0.66) / 0.34)
+  }
+}
+
+// Franjas horizontales con bordes turbulentos; `deflection` desplaza la coordenada de la franja.
+// `visibility` (stripe_visibility) las va llevando al color del medio cuando son más finas que un pixel
+fn bands(settings: &BandSettings, position: Vec3, time: f32, deflection: f32, visibility: f32) -> Color {
+  let band_factor = ((band_phase(settings, position, time, deflection).sin() * visibility) * 0.5 + 0.5).fract();
+  band_color(settings, band_factor)
+}
+
+// Fase del seno de las franjas en un punto
+fn band_phase(settings: &BandSettings, position: Vec3, time: f32, deflection: f32) -> f32 {
+  // Las franjas son horizontales: el giro solo mueve los detalles del ruido a lo largo de ellas
+  let position = settings.rotation.unwind(position, time);
+  let y_position = position.y + deflection + time * settings.flow_speed;
+
+  // Los bordes se ondulan con ruido de baja frecuencia a lo largo de la franja
+  let wavy_y = y_position + flow_noise(position, time) * settings.turbulence;
+
+  // Remolinos: el ruido se deforma con otro ruido, más fuerte donde cambia de franja
+  let boundary = (wavy_y * settings.band_scale).cos().abs();
+  let warp = flow_noise(position * 2.0, time) * 0.3;
+  let swirl = flow_noise(position * 3.0 + Vec3::new(warp, warp, -warp), time) * settings.swirl * boundary;
+  (wavy_y + swirl) * settings.band_scale
+}
+
+// Posición del punto relativa al centro de la tormenta (este con el óvalo ya corregido, norte),
+// o None si está del otro lado del planeta
+fn storm_offset(storm: &Storm, rotation: &DifferentialRotation, position: Vec3, time: f32) -> Option<(f32, f32)> {
+  let longitude = storm.longitude + time * (storm.drift + rotation.rate(storm.latitude));
+  let direction = Vec3::new(
+      storm.latitude.cos() * longitude.cos(),
+      storm.latitude.sin(),
+      storm.latitude.cos() * longitude.sin(),
+  );
+
+  // Coordenadas locales alrededor del centro (este, norte)
+  let east = Vec3::new(-longitude.sin(), 0.0, longitude.cos());
+  let north = direction.cross(&east);
+  let point = position.normalize();
+  if point.dot(&direction) <= 0.0 {
+      return None;
+  }
+  let offset = point - direction;
+  Some((offset.dot(&east) / storm.stretch, offset.dot(&north)))
+}
+
+// Intensidad de una tormenta en el punto y patrón en espiral de su interior
+fn storm_factor(storm: &Storm, rotation: &DifferentialRotation, position: Vec3, time: f32) -> f32 {
+  let Some((dx, dy)) = storm_offset(storm, rotation, position, time) else {
+      return 0.0;
   };
+  let distance = (dx * dx + dy * dy).sqrt() / storm.radius;
+  if distance >= 1.0 {
+      return 0.0;
+  }
+
+  let angle = dy.atan2(dx);
+  let spiral = ((angle * 2.0 + distance * 12.0 - time * storm.spin).sin() * 0.5 + 0.5) * 0.4 + 0.6;
+  (1.0 - distance).powf(0.6) * spiral
+}
 
-  // Vortice
-  let vortex_center = Vec2::new(-0.2, -0.2);
-  let vortex_radius = 0.3;
-  let distance_to_vortex = ((fragment.vertex_pos.x - vortex_center.x).powi(2)
-      + (fragment.vertex_pos.y - vortex_center.y).powi(2))
-      .sqrt();
-  let vortex_intensity = ((vortex_radius - distance_to_vortex).max(0.0f32) / vortex_radius).powf(2.0);
-  let vortex_color = Color::new(255, 69, 0);
-  let final_color = band_color.lerp(&vortex_color, vortex_intensity);
+// Desvío de las franjas como el flujo alrededor de un cilindro: cada franja pasa a la altura
+// `dy * (1 - 1 / d²)`, así todas se juntan en el borde del óvalo y se enderezan al alejarse
+fn storm_deflection(storm: &Storm, rotation: &DifferentialRotation, position: Vec3, time: f32) -> f32 {
+  let Some((dx, dy)) = storm_offset(storm, rotation, position, time) else {
+      return 0.0;
+  };
+  let distance = (dx * dx + dy * dy).sqrt() / storm.radius;
+  let fade = (1.0 - (distance - 1.0) / RING_STORM_REACH).clamp(0.0, 1.0);
+  // Las franjas usan la posición del modelo (radio 0.5), no la de la esfera unitaria
+  -0.5 * dy / distance.max(1.0).powi(2) * fade
+}
+
+fn storms(settings: &BandSettings, position: Vec3, time: f32, base: Color) -> (Color, Color) {
+  let mut color = base;
+  let mut storm_only = Color::BLACK;
+  for storm in settings.storms {
+      let factor = storm_factor(storm, &settings.rotation, position, time);
+      if factor > 0.0 {
+          color = color.lerp(&storm.color, factor);
+          storm_only = storm_only.lerp(&storm.color, factor);
+      }
+  }
+  (color, storm_only)
+}
+
+pub fn gas_planet_shader(fragment: &Fragments, frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+  let time = frame.time;
+  let visibility = stripe_visibility(fragment.fwidth(|position| band_phase(&GAS_PLANET_BANDS, *position, time, 0.0)));
+  let band_color = bands(&GAS_PLANET_BANDS, fragment.vertex_pos, time, 0.0, visibility);
+  let (final_color, storm_color) = storms(&GAS_PLANET_BANDS, fragment.vertex_pos, time, band_color);
 
   // Depuración
-  match uniforms.debug_mode {
+  match frame.debug_mode {
       1 => band_color * fragment.intensity,       // Solo franjas
-      2 => vortex_color * vortex_intensity,       // Solo vórtice
-      _ => final_color * fragment.intensity,      // Shader completo
+      2 => storm_color,                           // Solo tormentas
+      _ => final_color * fragment.light * GAS_PLANET_BANDS.limb.factor(fragment, frame), // Shader completo
   }
 }
 
 // Planeta rocoso
-pub fn rocky_planet_shader(fragment: &Fragments, _uniforms: &Uniforms) -> Color {
+pub fn rocky_planet_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+  // Coordenadas ajustadas con pseudoaleatoriedad
+  let x = fragment.vertex_pos.x;
+  let y = fragment.vertex_pos.y;
+  let z = fragment.vertex_pos.z;
+  let random_factor = rng::hash3(body.seed, x, y, z) * ROCKY_DETAIL_SCALE;
+
+  // El patrón es plano (en x, y): sobre la esfera lisa se ve bien, pero con relieve las laderas que miran
+  // hacia ±z lo estiran en franjas, así que con la malla deformada se proyecta desde los tres ejes
+  let rock = |uv: Vec2| rocky_rock(body.seed, uv, random_factor);
+  let object_normal = object_normal(fragment, body);
+  let rocky_surface = if body.displaced {
+      triplanar_sample(&rock, fragment.vertex_pos, object_normal, 1.0)
+  } else {
+      rock(Vec2::new(x, y))
+  };
+
+  // Casquetes polares: el borde se deforma con ruido y el tamaño cambia con las estaciones
+  let position = fragment.vertex_pos.normalize();
+  let (latitude, _) = latitude_longitude(&position);
+  let season = (frame.time * ROCKY_SEASON_SPEED).sin() * ROCKY_SEASON_AMOUNT;
+  let edge_noise = (noise::fbm3(body.seed, position * 6.0, 3) - 0.5) * ROCKY_CAP_ROUGHNESS;
+  let cap_latitude = if latitude > 0.0 {
+      ROCKY_NORTH_CAP - season
+  } else {
+      ROCKY_SOUTH_CAP + season
+  };
+  let cap_mask = ((latitude.abs() + edge_noise - cap_latitude) / ROCKY_CAP_EDGE).clamp(0.0, 1.0);
+
+  let cap_color = Color::new(235, 245, 255); // Blanco azulado
+  let surface = rocky_surface.lerp(&cap_color, cap_mask);
+
+  // El hielo brilla más que la roca
+  let shininess = 8.0 + cap_mask * 40.0;
+  let specular = specular_light(&fragment.normal, &fragment.world_pos, &view_direction(frame), &frame.lights, shininess)
+      * (0.05 + cap_mask * 0.5);
+
+  let diffuse = dominant_diffuse(fragment, frame);
+
+  // Depuración
+  match frame.debug_mode {
+      1 => Color::new(255, 255, 255) * cap_mask,                                   // Solo los casquetes
+      TRIPLANAR_DEBUG_MODE if body.displaced => triplanar_weights_color(object_normal), // Peso de cada plano
+      TWILIGHT_DEBUG_MODE => Color::new(255, 255, 255) * ROCKY_TWILIGHT.factor(diffuse), // Solo la banda del crepúsculo
+      _ => ROCKY_TWILIGHT.apply(surface * fragment.light, diffuse) + Color::new(255, 255, 255) * specular, // Shader completo
+  }
+}
+
+// Roca del planeta rocoso en un punto del plano; `random_factor` mueve un poco el patrón en cada punto
+fn rocky_rock(seed: u32, uv: Vec2, random_factor: f32) -> Color {
   // Colores base para la superficie rocosa
   let base_color = Color::new(139, 69, 19);    // Marrón rojizo oscuro
   let mid_color = Color::new(205, 92, 92);     // Rojo rosado
   let highlight_color = Color::new(255, 160, 122); // Salmón claro
 
-  // Generar ruido para simular textura rocosa
-  let rock_scale = 10.0; // Mayor escala para patrones más finos
-  let detail_scale = 0.3; // Escala para detalles pequeños
-
-  // Coordenadas ajustadas con pseudoaleatoriedad
-  let x = fragment.vertex_pos.x;
-  let y = fragment.vertex_pos.y;
-  let randomness = (x * 12.9898 + y * 78.233).sin() * 43758.5453;
-  let random_factor = randomness.fract() * detail_scale;
+  // La semilla también desplaza el patrón para que cada planeta sea distinto
+  let x = uv.x + rng::hash1(seed, 0) * 10.0;
+  let y = uv.y + rng::hash1(seed, 1) * 10.0;
 
   // Patrón principal con variaciones añadidas
-  let noise = (((x + random_factor) * rock_scale).sin() * ((y + random_factor) * rock_scale).cos()).abs();
+  let noise = (((x + random_factor) * ROCKY_SCALE).sin() * ((y + random_factor) * ROCKY_SCALE).cos()).abs();
 
   // Interpolación entre colores según el ruido
-  let rocky_surface = if noise < 0.4 {
+  if noise < 0.4 {
       base_color.lerp(&mid_color, noise / 0.4)
   } else {
       mid_color.lerp(&highlight_color, (noise - 0.4) / 0.6)
-  };
+  }
+}
+
+const ROCKY_SCALE: f32 = 10.0;          // Mayor escala para patrones más finos
+const ROCKY_DETAIL_SCALE: f32 = 0.3;    // Escala para detalles pequeños
+// Latitud (en radianes) donde empieza cada casquete; el sur es más pequeño
+const ROCKY_NORTH_CAP: f32 = 0.9;
+const ROCKY_SOUTH_CAP: f32 = 1.05;
+const ROCKY_CAP_ROUGHNESS: f32 = 0.35;  // Irregularidad del borde
+const ROCKY_CAP_EDGE: f32 = 0.04;       // Ancho de la transición roca-hielo
+const ROCKY_SEASON_SPEED: f32 = 0.0005; // Un año dura unos 3.5 minutos a 60 FPS
+const ROCKY_SEASON_AMOUNT: f32 = 0.15;  // Cuánto crece y se encoge cada casquete
+// Crepúsculo rojo oscuro
+const ROCKY_TWILIGHT: Twilight = Twilight { tint: Color::new(150, 25, 15), width: 0.1, strength: 0.4 };
+
+// Mapa de biomas del planeta rocoso: una altitud de ruido sobre la esfera se clasifica junto con la
+// latitud en cañones, llanuras, tierras altas y picos, con cauces secos oscuros en los valles angostos
+pub struct BiomeSettings {
+  pub altitude_scale: f32,     // Tamaño de los continentes (más chico = más grandes)
+  pub altitude_octaves: u32,
+  pub thresholds: [f32; 3],    // Altitud donde empiezan las llanuras, las tierras altas y los picos
+  pub blend: f32,              // Ancho de la mezcla entre dos biomas vecinos
+  pub polar_cooling: f32,      // Cuánto sube la altitud aparente hacia los polos (más frío, más picos)
+  pub colors: [Color; 4],      // Cañón, llanura, tierras altas y picos
+  pub debug_colors: [Color; 4],
+  pub riverbed_scale: f32,
+  pub riverbed_width: f32,     // Valor del ruido con cresta debajo del cual hay cauce
+  pub riverbed_color: Color,
+}
+
+pub const ROCKY_BIOMES: BiomeSettings = BiomeSettings {
+  altitude_scale: 1.8,
+  altitude_octaves: 4,
+  thresholds: [0.45, 0.56, 0.7],
+  blend: 0.025,
+  polar_cooling: 0.25,
+  colors: [
+      Color::new(95, 45, 30),    // Cañón rojo oscuro
+      Color::new(176, 140, 88),  // Llanura ocre
+      Color::new(120, 110, 85),  // Tierras altas grises
+      Color::new(235, 232, 225), // Picos nevados
+  ],
+  debug_colors: [
+      Color::new(200, 40, 40),
+      Color::new(230, 200, 40),
+      Color::new(40, 160, 60),
+      Color::new(60, 90, 220),
+  ],
+  riverbed_scale: 5.0,
+  riverbed_width: 0.06,
+  riverbed_color: Color::new(60, 38, 28),
+};
+
+// Altitud aparente en [0, 1) (la del ruido más el enfriamiento hacia los polos) de un punto de la esfera
+pub fn biome_altitude(settings: &BiomeSettings, seed: u32, position: &Vec3) -> f32 {
+  let direction = position.try_normalize(0.0).unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+  let altitude = noise::fbm3(seed, direction * settings.altitude_scale, settings.altitude_octaves);
+  altitude + settings.polar_cooling * direction.y * direction.y
+}
+
+// Bioma de una altitud: 0 cañón, 1 llanura, 2 tierras altas, 3 picos
+pub fn biome_index(settings: &BiomeSettings, altitude: f32) -> usize {
+  settings.thresholds.iter().filter(|&&threshold| altitude >= threshold).count()
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+  let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+  t * t * (3.0 - 2.0 * t)
+}
+
+// Escalón de `value` en `threshold` (0 abajo, 1 arriba) suavizado un pixel hacia cada lado, como
+// smoothstep con fwidth en una GPU: `derivative_scale` es cuánto cambia `value` de un pixel al siguiente
+// (Fragments::fwidth). Con 0 es el escalón exacto, así los bordes no se borronean sin derivadas
+pub fn smooth_threshold(value: f32, threshold: f32, derivative_scale: f32) -> f32 {
+  if derivative_scale <= 0.0 {
+      return if value > threshold { 1.0 } else { 0.0 };
+  }
+  smoothstep(threshold - derivative_scale, threshold + derivative_scale, value)
+}
+
+// Radianes por pixel desde los que una franja de seno se empieza a apagar y el ancho del paso: a π por
+// pixel (dos pixeles por ciclo) ya no se puede dibujar y solo parpadea, así que se deja su valor medio
+const STRIPE_FADE_RATE: f32 = PI / 2.0;
+const STRIPE_FADE_WIDTH: f32 = PI / 4.0;
+
+// Cuánto de una franja de seno se deja ver según lo que avanza su fase de un pixel al siguiente
+fn stripe_visibility(phase_rate: f32) -> f32 {
+  1.0 - smooth_threshold(phase_rate, STRIPE_FADE_RATE, STRIPE_FADE_WIDTH)
+}
+
+pub fn rocky_biome_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+  let settings = &ROCKY_BIOMES;
+  let position = fragment.vertex_pos;
+  let altitude = biome_altitude(settings, body.seed, &position);
+
+  // Cada umbral mezcla hacia el bioma siguiente en una franja angosta alrededor
+  let mut surface = settings.colors[0];
+  for (threshold, color) in settings.thresholds.iter().zip(&settings.colors[1..]) {
+    surface = surface.lerp(color, smoothstep(threshold - settings.blend, threshold + settings.blend, altitude));
+  }
+
+  // Ruido con cresta: cerca de cero forma líneas finas y ramificadas; solo por debajo de los picos
+  let direction = position.try_normalize(0.0).unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+  let ridge = (noise::fbm3(rng::combine(body.seed, 1), direction * settings.riverbed_scale, 3) * 2.0 - 1.0).abs();
+  let below_peaks = 1.0 - smoothstep(settings.thresholds[2] - settings.blend, settings.thresholds[2] + settings.blend, altitude);
+  let riverbed = (1.0 - smoothstep(0.0, settings.riverbed_width, ridge)) * below_peaks;
+  let surface = surface.lerp(&settings.riverbed_color, riverbed * 0.8);
+
+  let diffuse = dominant_diffuse(fragment, frame);
 
   // Depuración
-  rocky_surface * fragment.intensity
+  match frame.debug_mode {
+      1 => Color::new(255, 255, 255) * altitude.clamp(0.0, 1.0),                   // Altitud
+      2 => settings.debug_colors[biome_index(settings, altitude)],                  // Bioma de cada punto
+      TWILIGHT_DEBUG_MODE => Color::new(255, 255, 255) * ROCKY_TWILIGHT.factor(diffuse), // Solo la banda del crepúsculo
+      _ => ROCKY_TWILIGHT.apply(surface * fragment.light, diffuse),              // Shader completo
+  }
 }
 
 // Luna (del planeta rocoso)
-pub fn moon_shader(fragment: &Fragments, _uniforms: &Uniforms) -> Color {
+pub fn moon_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
   // Colores base para la luna
   let base_color = Color::new(169, 169, 169);    // Gris
   let mid_color = Color::new(190, 190, 190);     // Gris medio
@@ -257,8 +1610,11 @@ pub fn moon_shader(fragment: &Fragments, _uniforms: &Uniforms) -> Color {
   // Coordenadas ajustadas con pseudoaleatoriedad
   let x = fragment.vertex_pos.x;
   let y = fragment.vertex_pos.y;
-  let randomness = (x * 15.789 + y * 41.233).sin() * 43758.5453;
-  let random_factor = randomness.fract() * detail_scale;
+  let random_factor = rng::hash2(body.seed, x, y) * detail_scale;
+
+  // La semilla también desplaza el patrón para que cada luna sea distinta
+  let x = x + rng::hash1(body.seed, 0) * 10.0;
+  let y = y + rng::hash1(body.seed, 1) * 10.0;
 
   // Patrón principal de ruido
   let noise = (((x + random_factor) * rock_scale).sin() * ((y + random_factor) * rock_scale).cos()).abs();
@@ -270,169 +1626,465 @@ pub fn moon_shader(fragment: &Fragments, _uniforms: &Uniforms) -> Color {
       mid_color.lerp(&highlight_color, (noise - 0.5) / 0.5)
   };
 
-  // Configuración de cráteres
-  let crater_positions = [
-      (0.1, 0.2, 0.50), 
-      (-0.3, -0.1, 0.30),
-      (0.4, -0.3, 0.2), 
-      (-0.1, 0.5, 0.40),
-      (-0.5, -0.4, 0.25),
-      (0.3, 0.4, 0.35),
-      (0.1, 0.5, 0.20),
-      (0.2, -0.1, 0.25),
-      (0.0, -0.6, 0.28), 
-      (-0.4, 0.2, 0.22),
-      (0.5, 0.0, 0.30),  
-      (-0.2, -0.5, 0.18), 
-      (0.35, 0.5, 0.24),
-      (-0.45, -0.3, 0.20),
-  ];
+  // Cráteres del estado de la escena (van apareciendo con los impactos)
+  let initial_state;
+  let moon = match &body.moon {
+      Some(moon) => moon,
+      None => {
+          initial_state = MoonState::new(body.seed);
+          &initial_state
+      }
+  };
 
   let crater_color = Color::new(100, 100, 100); // Gris oscuro para los cráteres
+  let rim_color = Color::new(232, 232, 226);   // Borde elevado, más claro que la superficie
+  let position = fragment.vertex_pos.normalize();
+  // Ángulo sobre la esfera que cubre un pixel
+  let pixel_angle = fragment.fwidth(|neighbor| angular_distance(&neighbor.normalize(), &position));
 
-  // Combinar intensidades de todos los cráteres
+  // Combinar intensidades de todos los cráteres; las distancias son ángulos sobre la esfera,
+  // así los cráteres son redondos en cualquier latitud y no se repiten del otro lado
   let mut combined_crater_intensity = 0.0;
-  for &(cx, cy, radius) in crater_positions.iter() {
-      let distance = ((fragment.vertex_pos.x - cx).powi(2)
-          + (fragment.vertex_pos.y - cy).powi(2))
-          .sqrt();
-      let crater_intensity = ((radius - distance).max(0.0f32) / radius).powf(3.0);
+  let mut rim = 0.0f32;
+  for &(center, radius) in moon.craters.iter() {
+      let distance = angular_distance(&position, &center);
+      let crater_intensity = ((radius - distance).max(0.0f32) / radius).powf(1.5);
       combined_crater_intensity += crater_intensity;
+
+      // Un borde más angosto que un par de pixeles se ve como un anillo de puntos que parpadea al
+      // moverse; se va apagando a medida que se acerca a ese ancho
+      let rim_width = radius * MOON_RIM_WIDTH;
+      let rim_visibility = 1.0 - smooth_threshold(pixel_angle * MOON_RIM_MIN_PIXELS, rim_width, pixel_angle);
+      let outside = (distance - radius) / rim_width;
+      if (0.0..1.0).contains(&outside) {
+          rim = rim.max((outside * PI).sin() * rim_visibility);
+      }
   }
 
-  // Aplicar la intensidad de los cráteres a la superficie
-  let final_surface = rocky_surface.lerp(&crater_color, combined_crater_intensity);
+  // Aplicar la intensidad de los cráteres y los bordes a la superficie
+  let final_surface = rocky_surface
+      .lerp(&rim_color, rim * MOON_RIM_STRENGTH)
+      .lerp(&crater_color, combined_crater_intensity);
 
-  // Multiplicar por la intensidad para iluminación
-  final_surface * fragment.intensity
+  // Destellos de los impactos: emisivos, se apagan en FLASH_DURATION ticks
+  let flash = impact_flash(moon, &position, frame.time);
+
+  // Luz del sol que llega al fragmento (calculada con la normal y la posición en el mundo, así la fase
+  // depende de dónde está la luna respecto al sol y a la cámara) más la que refleja el planeta
+  let planetshine = body.planetshine
+      .map(|planet| planet.light(&fragment.normal, &fragment.world_pos, &frame.lights))
+      .unwrap_or_default();
+  final_surface * (fragment.light + planetshine) + Color::new(255, 255, 240) * flash
+}
+
+// Radio angular (en radianes) del destello de un impacto
+const MOON_FLASH_RADIUS: f32 = 0.24;
+// En infrarrojo un impacto reciente calienta la superficie hasta casi el máximo
+const MOON_FLASH_HEAT: f32 = 0.8;
+
+// Brillo en [0, 1] de los destellos de los impactos en un punto de la esfera unitaria
+fn impact_flash(moon: &MoonState, position: &Vec3, time: f32) -> f32 {
+  moon.active_flashes.iter().fold(0.0f32, |flash, &(center, start)| {
+      let fade = 1.0 - (time - start as f32).max(0.0) / FLASH_DURATION as f32;
+      let spot = (1.0 - angular_distance(position, &center) / MOON_FLASH_RADIUS).max(0.0);
+      flash.max(spot * spot * fade.max(0.0))
+  })
+}
+
+// Sin estado de la escena no hay impactos, así que tampoco destellos
+fn moon_temperature(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+  let flash = body.moon.as_ref().map_or(0.0, |moon| impact_flash(moon, &fragment.vertex_pos.normalize(), frame.time));
+  (lit_temperature(fragment.intensity) + flash * MOON_FLASH_HEAT).clamp(0.0, 1.0)
+}
+
+// Cráteres de la luna (dirección desde el centro y radio angular) generados a partir de la semilla
+const MOON_CRATER_COUNT: usize = 24;
+const MOON_CRATER_POLAR_DENSITY: f32 = 1.0;  // Densidad de cráteres en los polos respecto al ecuador (1 = pareja)
+const MOON_RIM_WIDTH: f32 = 0.2;             // Ancho del borde, en fracción del radio del cráter
+const MOON_RIM_STRENGTH: f32 = 0.35;
+const MOON_RIM_MIN_PIXELS: f32 = 2.0;        // Ancho en pixeles desde el que el borde se dibuja entero
+
+pub fn moon_craters(seed: u32) -> [(Vec3, f32); MOON_CRATER_COUNT] {
+  let crater_seed = rng::combine(seed, 0xC7A7);
+  std::array::from_fn(|i| {
+      let crater_seed = rng::combine(crater_seed, i as u32);
+      let center = weighted_sphere_direction(crater_seed, MOON_CRATER_POLAR_DENSITY);
+      let radius = 0.12 + rng::hash1(crater_seed ^ 0x5EED, 0) * 0.3;
+      (center, radius)
+  })
+}
+
+// Punto uniforme en la esfera unitaria a partir de dos valores en [0, 1)
+pub fn sphere_direction(u: f32, v: f32) -> Vec3 {
+  let y = 1.0 - 2.0 * u;
+  let ring = (1.0 - y * y).max(0.0).sqrt();
+  let angle = 2.0 * PI * v;
+  Vec3::new(ring * angle.cos(), y, ring * angle.sin())
+}
+
+// Punto al azar en la esfera con la densidad cambiando de forma lineal del ecuador a los polos;
+// se descartan puntos según la latitud y tras varios intentos se acepta el último
+fn weighted_sphere_direction(seed: u32, polar_density: f32) -> Vec3 {
+  const MAX_ATTEMPTS: u32 = 16;
+  let max_weight = polar_density.max(1.0);
+  let mut attempt = 0;
+  loop {
+      let index = attempt * 3;
+      let direction = sphere_direction(rng::hash1(seed, index), rng::hash1(seed, index + 1));
+      let weight = 1.0 + (polar_density - 1.0) * direction.y.abs();
+      if attempt + 1 == MAX_ATTEMPTS || rng::hash1(seed, index + 2) * max_weight < weight {
+          return direction;
+      }
+      attempt += 1;
+  }
 }
 
-// Movimiento orbital de la luna
-pub fn moon_position(time: f32, radius: f32) -> Vec3 {
-  let angle = time * 0.01;
-  Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin())
+// Ángulo entre dos direcciones normalizadas (distancia por el círculo máximo)
+fn angular_distance(a: &Vec3, b: &Vec3) -> f32 {
+  a.dot(b).clamp(-1.0, 1.0).acos()
 }
 
 // planeta con anillos
-pub fn ring_planet_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
-  let band_color1 = Color::new(189, 155, 107); // Marrón claro
-  let band_color2 = Color::new(210, 180, 140); // Beige
-  let band_color3 = Color::new(255, 222, 173); // Crema
-
-  // Franjas horizontales
-  let band_scale = 3.5; // Ajusta el número de franjas
-  let flow_speed = 0.0008; // Movimiento más lento que Júpiter
-  let flow_offset = uniforms.time as f32 * flow_speed;
-  let y_position = fragment.vertex_pos.y + flow_offset;
-  let band_factor = ((y_position * band_scale).sin() * 0.5 + 0.5).fract();
-
-  // Mezcla entre colores según la posición en las bandas
-  let band_color = if band_factor < 0.33 {
-      band_color1.lerp(&band_color2, band_factor / 0.33)
-  } else if band_factor < 0.66 {
-      band_color2.lerp(&band_color3, (band_factor - 0.33) / 0.33)
-  } else {
-      band_color3.lerp(&band_color1, (band_factor - 0.66) / 0.34)
-  };
+pub fn ring_planet_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+  // Franjas más tranquilas que las del planeta gaseoso, desviadas por la gran tormenta
+  let time = frame.time;
+  let rotation = &RING_PLANET_BANDS.rotation;
+  let deflection = storm_deflection(&RING_PLANET_STORM, rotation, fragment.vertex_pos, time);
+  let visibility = stripe_visibility(fragment.fwidth(|position| {
+      band_phase(&RING_PLANET_BANDS, *position, time, storm_deflection(&RING_PLANET_STORM, rotation, *position, time))
+  }));
+  let band_color = bands(&RING_PLANET_BANDS, fragment.vertex_pos, time, deflection, visibility);
+  let (final_color, storm_color) = storms(&RING_PLANET_BANDS, fragment.vertex_pos, time, band_color);
+
+  // La franja de sombra del anillo sobre las nubes, con los huecos del anillo más claros. Se escala la
+  // luz del fragmento (que puede venir en bandas del modo caricatura) en la parte que tapa el anillo
+  let light = body.ring_shadow.as_ref().map_or(fragment.light, |shadow| {
+      let ambient = frame.ambient_light(&fragment.normal);
+      let lit = diffuse_light(&fragment.normal, &fragment.world_pos, &frame.lights, &ambient);
+      let shadowed = filtered_diffuse_light(&fragment.normal, &fragment.world_pos, &frame.lights, &ambient, |light| ring_transmittance(shadow, &fragment.world_pos, light));
+      fragment.light.component_mul(&shadowed.component_div(&lit.map(|channel| channel.max(f32::EPSILON))))
+  });
 
   // Depuración
-  match uniforms.debug_mode {
+  match frame.debug_mode {
       1 => band_color * fragment.intensity, // Solo las franjas
-      _ => band_color * fragment.intensity, // Shader completo
+      2 => storm_color,                     // Solo la tormenta
+      3 => {
+          // Desvío de las franjas: rojo positivo, azul negativo
+          let amount = deflection / (0.5 * RING_STORM_SIZE);
+          Color::new(255, 80, 40) * amount.max(0.0) + Color::new(40, 120, 255) * (-amount).max(0.0)
+      }
+      4 => Color::new(255, 255, 255) * light,   // Solo la iluminación, con la sombra del anillo
+      _ => final_color * light * RING_PLANET_BANDS.limb.factor(fragment, frame), // Shader completo
   }
 }
 
 // Anillos
-fn ring_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
+fn ring_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
   // Colores base para el anillo
-  let base_color = Color::new(255, 220, 80); // Amarillo
-  let shadow_color = Color::new(150, 120, 60); // Sombra
+  let base_color = RING_COLOR;
 
-  // Interpolación de colores
-  let surface_color = base_color;
+  // Las mismas franjas que el anillo de partículas, según la distancia al centro; de lejos se apagan
+  // en lugar de parpadear
+  let band = ring_band(&fragment.vertex_pos);
+  let visibility = stripe_visibility(fragment.fwidth(|position| ring_stripe_phase(ring_band(position))));
 
-  // Iluminación básica para simular sombras
-  let light_direction = Vec3::new(1.0, 1.0, 1.0).normalize(); // Dirección de la luz
-  let normal = fragment.vertex_pos.normalize(); // Normal del fragmento
-  let light_intensity = (normal.dot(&light_direction)).clamp(0.2, 1.0); // Intensidad de la luz
+  let light_intensity = ring_light(&fragment.normal, &fragment.world_pos, &frame.lights);
+
+  // Con perfil el color sale de él, promediado en el ancho del pixel como la opacidad; la cara sin luz
+  // se oscurece tanto como la sombra de las franjas
+  let surface_color = match &body.ring_profile {
+      Some(profile) => ring_profile_sample(profile, fragment).color * (light_intensity + RING_UNLIT * (1.0 - light_intensity)),
+      None => ring_stripe_color(band, light_intensity, visibility),
+  };
 
   // Lógica de depuración
-  let final_color = match uniforms.debug_mode {
+  match frame.debug_mode {
       1 => base_color * fragment.intensity,                                                 // Solo el color base
-      _ => surface_color * light_intensity + shadow_color * (1.0 - light_intensity),      // Shader completo
-  };
+      2 => Color::new(255, 255, 255) * RingShader.alpha(fragment, body),   // Solo la opacidad
+      _ => surface_color,                                                                   // Shader completo
+  }
+}
+
+const RING_COLOR: Color = Color::new(255, 220, 80); // Amarillo
+const RING_SHADOW_COLOR: Color = Color::new(150, 120, 60); // Sombra
+// Opacidad del anillo donde es más denso
+const RING_OPACITY: f32 = 0.9;
+// Brillo del color del perfil donde no llega luz, como RING_SHADOW_COLOR respecto de RING_COLOR
+const RING_UNLIT: f32 = 0.6;
+
+// Opacidad del anillo en una fracción del ancho: la del perfil o, sin él, la de las franjas
+// procedurales. La malla y la sombra sobre el planeta la toman de acá para tapar lo mismo
+pub fn ring_opacity(profile: Option<&RingProfile>, band: f32) -> f32 {
+  match profile {
+      Some(profile) => profile.sample(band).opacity(),
+      None => ring_density(band) * RING_OPACITY,
+  }
+}
 
-  final_color
+// Perfil en el fragmento, filtrado en lo que cubre el pixel del ancho del anillo
+fn ring_profile_sample(profile: &RingProfile, fragment: &Fragments) -> RingSample {
+  profile.sample_filtered(ring_band(&fragment.vertex_pos), fragment.fwidth(ring_band))
+}
+
+// Fracción del ancho del anillo en un punto del objeto (0 = borde interior, 1 = exterior)
+fn ring_band(position: &Vec3) -> f32 {
+  let radius = Vec2::new(position.x, position.z).magnitude();
+  ((radius - RING_INNER_RADIUS) / (RING_OUTER_RADIUS - RING_INNER_RADIUS)).clamp(0.0, 1.0)
+}
+
+// Fracción de la luz que pasa por el anillo en el camino desde `world_position` hacia la luz: el rayo
+// se corta con el plano del anillo y, si cae entre sus bordes, pasa lo que deja la opacidad de esa franja
+pub fn ring_transmittance(shadow: &RingShadow, world_position: &Vec3, light: &Light) -> f32 {
+  let direction = light.direction_from(world_position);
+  let origin = shadow.world_to_ring * Vec4::new(world_position.x, world_position.y, world_position.z, 1.0);
+  let direction = shadow.world_to_ring * Vec4::new(direction.x, direction.y, direction.z, 0.0);
+  // Rayo paralelo al anillo o que se aleja de él
+  let t = -origin.y / direction.y;
+  if !t.is_finite() || t <= 0.0 {
+      return 1.0;
+  }
+  let hit = origin.xyz() + direction.xyz() * t;
+  let radius = Vec2::new(hit.x, hit.z).magnitude();
+  if !(RING_INNER_RADIUS..=RING_OUTER_RADIUS).contains(&radius) {
+      return 1.0;
+  }
+  1.0 - ring_opacity(shadow.profile.as_deref(), ring_band(&hit))
+}
+
+// Iluminación de las luces de la escena por las dos caras, porque el anillo es delgado
+pub fn ring_light(normal: &Vec3, world_position: &Vec3, lights: &[Light]) -> f32 {
+  lights.iter().take(MAX_LIGHTS)
+      .map(|light| normal.dot(&light.direction_from(world_position)).abs() * light.intensity)
+      .sum::<f32>()
+      .clamp(0.2, 1.0)
+}
+
+// Color de las partículas del anillo según su fracción del ancho (0 = borde interior): franjas
+// finas entre el color del anillo y el de la sombra, iluminadas igual que la malla
+pub fn ring_particle_color(band: f32, light_intensity: f32) -> Color {
+  ring_stripe_color(band, light_intensity, 1.0)
+}
+
+// Fase del seno de las franjas del anillo
+fn ring_stripe_phase(band: f32) -> f32 {
+  band * 43.0 + (band * 11.0).sin() * 1.5
+}
+
+// Color de las franjas con su contraste multiplicado por `visibility` (stripe_visibility)
+fn ring_stripe_color(band: f32, light_intensity: f32, visibility: f32) -> Color {
+  let stripes = 0.5 + 0.5 * ring_stripe_phase(band).sin() * visibility;
+  let surface_color = RING_SHADOW_COLOR.lerp(&RING_COLOR, 0.45 + 0.55 * stripes);
+  surface_color * light_intensity + RING_SHADOW_COLOR * (1.0 - light_intensity)
+}
+
+// Asteroides
+const ASTEROID_DARK: Color = Color::new(70, 66, 62);
+const ASTEROID_LIGHT: Color = Color::new(150, 146, 140);
+const ASTEROID_NOISE_SCALE: f32 = 7.0;
+
+// Roca gris con manchas de ruido; el relieve lo dan las facetas de la malla
+pub fn asteroid_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+  let grain = noise::fbm3(body.seed, fragment.vertex_pos * ASTEROID_NOISE_SCALE, 3);
+  let surface = ASTEROID_DARK.lerp(&ASTEROID_LIGHT, grain);
+
+  match frame.debug_mode {
+      1 => surface,                                   // Solo el color
+      2 => Color::new(255, 255, 255) * grain,         // Solo el ruido
+      _ => surface * fragment.light,                  // Shader completo
+  }
+}
+
+// Color de un asteroide que se dibuja como un solo pixel: la fracción iluminada del disco que se ve
+// (fase) por el brillo medio de una esfera difusa
+pub fn asteroid_point_color(world_position: &Vec3, frame: &FrameUniforms) -> Color {
+  ShaderType::Asteroid.albedo().unwrap_or(ASTEROID_DARK) * asteroid_point_light(world_position, frame)
+}
+
+// Temperatura de un asteroide que se dibuja como un pixel, con la misma luz que su color
+pub fn asteroid_point_temperature(world_position: &Vec3, frame: &FrameUniforms) -> f32 {
+  let light = asteroid_point_light(world_position, frame);
+  lit_temperature((light.x + light.y + light.z) / 3.0)
+}
+
+fn asteroid_point_light(world_position: &Vec3, frame: &FrameUniforms) -> Vec3 {
+  let view = view_direction(frame);
+  // Sin normal, la luz del fondo entra con el promedio de todas las direcciones
+  let ambient = frame.ambient + frame.skylight.average();
+  let ambient = Vec3::new(ambient.r, ambient.g, ambient.b) / 255.0;
+  frame.lights.iter().take(MAX_LIGHTS).fold(ambient, |total, light| {
+      let phase = (1.0 + light.direction_from(world_position).dot(&view)) / 2.0;
+      total + Vec3::new(light.color.r, light.color.g, light.color.b) * (light.intensity / 255.0 * phase * 2.0 / 3.0)
+  })
 }
 
 // Planeta Tierra
-pub fn earth_shader(fragment: &Fragments, uniforms: &Uniforms) -> Color {
-  let x = fragment.vertex_pos.x;
-  let y = fragment.vertex_pos.y;
-  let z = fragment.vertex_pos.z;
+const EARTH_CONTINENT_THRESHOLD: f32 = 0.55;
 
-  // Coordenadas esféricas
-  let theta = (y / 0.5).asin(); // Latitud
-  let phi = z.atan2(x);         // Longitud
-  let u = (phi / (2.0 * PI)) + 0.5; // Coordenada u [0, 1]
-  let v = (theta / PI) + 0.5;      // Coordenada v [0, 1]
+// Campo de los continentes en un punto de la esfera (de cualquier radio): tierra por encima de
+// EARTH_CONTINENT_THRESHOLD
+fn earth_land_noise(position: &Vec3) -> f32 {
+  // Los continentes se pensaron con v creciendo hacia el norte
+  let uv = spherical_uv(*position);
+  let (u, v) = (uv.x, 1.0 - uv.y);
 
   let scale = 7.2;
-  let noise = ((u * scale).sin() * (v * scale).cos()).abs();
-  let continent_threshold = 0.55;
+  ((u * scale).sin() * (v * scale).cos()).abs()
+}
+
+// Continentes de la Tierra: true si el punto de la esfera (de cualquier radio) es tierra firme
+pub fn earth_is_land(position: &Vec3) -> bool {
+  earth_land_noise(position) > EARTH_CONTINENT_THRESHOLD
+}
 
+// Cuánto del pixel es tierra (0 = océano, 1 = tierra firme): la costa se suaviza un pixel hacia cada
+// lado, así no se ve escalonada ni parpadea se mire de cerca o de lejos
+pub fn earth_land(fragment: &Fragments) -> f32 {
+  smooth_threshold(earth_land_noise(&fragment.vertex_pos), EARTH_CONTINENT_THRESHOLD, fragment.fwidth(earth_land_noise))
+}
+
+pub fn earth_shader(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> Color {
+  let land = earth_land(fragment);
   let land_color = Color::new(34, 139, 34); // Verde para los continentes
   let ocean_color = Color::new(0, 105, 148); // Azul para el océano
-  let base_color = if noise > continent_threshold { land_color } else { ocean_color };
-
-  // Parámetros de las nubes
-  let time = uniforms.time as f32 * 0.01; // Escala temporal para el movimiento de las nubes
-  let cloud_scale = 8.0;                 // Escala de dispersión de las nubes
-  let cloud_intensity = ((u * cloud_scale + time).sin() * (v * cloud_scale + time).cos()).abs();
-  let cloud_intensity = (cloud_intensity - 0.5).clamp(0.0, 1.0) * 0.5; // Intensidad y opacidad de las nubes
-
-  let cloud_color = Color::new(255, 255, 255); // Blanco para las nubes
-
-  // Crear círculos de nubes (en movimiento)
-  let cloud_radius = 1.5; // Radio máximo de la atmósfera con nubes
-  let distance_from_center = Vec2::new(u, v).norm(); // Distancia del centro para determinar si está dentro de la atmósfera
-  let is_in_atmosphere = distance_from_center < cloud_radius;
-
-  // Reducir el número de círculos de nubes y hacerlos más pequeños
-  let num_clouds = 6; // Menor número de círculos de nubes
-  let mut cloud_positions = Vec::new();
-
-  for i in 0..num_clouds {
-      let angle = (i as f32 / num_clouds as f32) * 2.0 * PI + time * 0.2; // Movimiento en el tiempo
-      let radius = 0.2 + (i as f32 * 0.05); // Radios de los círculos
-      let x_pos = (angle.cos() * radius + 0.5) % 1.0; // Posición en u
-      let y_pos = (angle.sin() * radius + 0.5) % 1.0; // Posición en v
-      cloud_positions.push(Vec2::new(x_pos, y_pos));
-  }
-
-  // Dibujar las nubes en círculos
-  let mut cloud_color_final = Color::new(0, 0, 0); // Comienza con un color negro
-  for cloud_pos in cloud_positions.iter() {
-      let frag_position = Vec2::new(u, v);
-      let distance_to_cloud = (frag_position - *cloud_pos).norm(); // Distancia a cada círculo de nube
-      let cloud_radius = 0.075; // Radio más pequeño para los círculos de nubes
-      let is_in_cloud = distance_to_cloud < cloud_radius;
-
-      // Si el fragmento está dentro de un círculo de nube, añade su color
-      if is_in_cloud {
-          cloud_color_final = cloud_color_final.lerp(&cloud_color, 0.7); // Aumentamos la mezcla para que sea más blanco
-      }
+  let base_color = ocean_color.lerp(&land_color, land);
+
+  // Nubes: dos capas de ruido que se desplazan a distinta velocidad y su sombra sobre la superficie
+  let time = frame.time;
+  let cloud_seed = rng::combine(body.seed, EARTH_CLOUD_SEED);
+  let position = fragment.vertex_pos.normalize();
+  let cloud_cover = cloud_coverage(position, time, cloud_seed);
+
+  // La sombra es la nube que está sobre el punto en dirección a la luz principal
+  let light_direction = dominant_light_direction(fragment, frame);
+  let model_rotation = mat4_to_mat3(body.model_matrix()).transpose();
+  let light_local = (model_rotation * light_direction).normalize();
+  let shadow_position = (position + light_local * EARTH_CLOUD_SHADOW_OFFSET).normalize();
+  let shadow_mask = cloud_coverage(shadow_position, time, cloud_seed) * EARTH_CLOUD_SHADOW_STRENGTH;
+
+  // Luces de las ciudades en el lado nocturno
+  let diffuse = dominant_diffuse(fragment, frame);
+  let light_mask = earth_city_glow(fragment, body, land, cloud_cover, diffuse);
+
+  let city_color = Color::new(255, 190, 90); // Amarillo anaranjado
+  let cloud_color = Color::new(255, 255, 255);
+  let lighting = fragment.light * (1.0 - EARTH_AMBIENT) + Vec3::repeat(EARTH_AMBIENT);
+  let lit_surface = base_color * (1.0 - shadow_mask) * lighting;
+  let lit_clouds = cloud_color * lighting;
+
+  // Depuración
+  match frame.debug_mode {
+      1 => Color::new(255, 255, 255) * light_mask,    // Solo la máscara de luces nocturnas
+      2 => Color::new(255, 255, 255) * cloud_cover,   // Solo la cobertura de nubes
+      3 => Color::new(255, 255, 255) * shadow_mask,   // Solo la sombra de las nubes
+      TWILIGHT_DEBUG_MODE => Color::new(255, 255, 255) * EARTH_TERMINATOR.factor(diffuse), // Solo la banda del crepúsculo
+      _ => EARTH_TERMINATOR.apply(lit_surface.lerp(&lit_clouds, cloud_cover * EARTH_CLOUD_OPACITY), diffuse) + city_color * light_mask, // Shader completo
   }
+}
+
+// Luces de las ciudades que se ven en el fragmento (0 a 1): solo en tierra y del lado nocturno, y las
+// nubes tapan las que están debajo. `diffuse` es el de dominant_diffuse
+fn earth_city_glow(fragment: &Fragments, body: &BodyUniforms, land: f32, cloud_cover: f32, diffuse: f32) -> f32 {
+  let (twilight_start, twilight_end) = EARTH_TWILIGHT;
+  let night_factor = ((twilight_start - diffuse) / (twilight_start - twilight_end)).clamp(0.0, 1.0);
+  let night_factor = night_factor * night_factor * (3.0 - 2.0 * night_factor);
+
+  let city_mask = if land > 0.0 { city_lights(fragment.vertex_pos, body.seed) * land } else { 0.0 };
+  city_mask * night_factor * (1.0 - cloud_cover * 0.8)
+}
 
-  // Determinar el color final
-  let final_color = if is_in_atmosphere {
-      // Mezclar nubes y superficie
-      base_color * (1.0 - cloud_intensity) + cloud_color_final
+// En infrarrojo la tierra firme se calienta más que el océano de día, las nubes están más frías que la
+// superficie y las ciudades se ven tibias de noche
+const EARTH_LAND_HEAT: f32 = 0.1;
+const EARTH_CLOUD_COOLING: f32 = 0.15;
+const EARTH_CITY_HEAT: f32 = 0.4;
+
+fn earth_temperature(fragment: &Fragments, frame: &FrameUniforms, body: &BodyUniforms) -> f32 {
+  let land = earth_land(fragment);
+  let cloud_cover = cloud_coverage(fragment.vertex_pos.normalize(), frame.time, rng::combine(body.seed, EARTH_CLOUD_SEED));
+  let diffuse = dominant_diffuse(fragment, frame);
+  let daylight = fragment.intensity.clamp(0.0, 1.0);
+  let surface = lit_temperature(daylight) + EARTH_LAND_HEAT * land * daylight - EARTH_CLOUD_COOLING * cloud_cover * EARTH_CLOUD_OPACITY;
+  (surface + EARTH_CITY_HEAT * earth_city_glow(fragment, body, land, cloud_cover, diffuse)).clamp(0.0, 1.0)
+}
+
+// Capa de nubes: ruido de `octaves` octavas que gira alrededor del eje del planeta
+struct CloudLayer {
+  scale: f32,
+  speed: f32,        // Radianes por tick; el signo da la dirección
+  tilt: f32,         // Inclinación del eje de giro respecto al del planeta
+  threshold: f32,    // Valor del ruido a partir del cual hay nube
+  softness: f32,     // Ancho del borde de las nubes
+  octaves: u32,
+}
+
+// Una capa alta de nubes grandes y otra baja, más rápida y en sentido contrario
+const EARTH_CLOUD_LAYERS: [CloudLayer; 2] = [
+  CloudLayer { scale: 3.5, speed: 0.0015, tilt: 0.0, threshold: 0.58, softness: 0.12, octaves: 3 },
+  CloudLayer { scale: 8.0, speed: -0.0025, tilt: 0.35, threshold: 0.64, softness: 0.1, octaves: 2 },
+];
+const EARTH_CLOUD_OPACITY: f32 = 0.85;
+const EARTH_CLOUD_SEED: u32 = 0xC10D;
+// Desplazamiento de la sombra hacia la luz y cuánto oscurece la superficie
+const EARTH_CLOUD_SHADOW_OFFSET: f32 = 0.06;
+const EARTH_CLOUD_SHADOW_STRENGTH: f32 = 0.55;
+
+// Cobertura de nubes en [0, 1] para un punto de la esfera unitaria; las capas se combinan como alfa
+fn cloud_coverage(position: Vec3, time: f32, seed: u32) -> f32 {
+  EARTH_CLOUD_LAYERS.iter().enumerate().fold(0.0, |cover, (index, layer)| {
+      let (sin_tilt, cos_tilt) = layer.tilt.sin_cos();
+      let tilted = Vec3::new(position.x, position.y * cos_tilt - position.z * sin_tilt, position.y * sin_tilt + position.z * cos_tilt);
+      let (sin_a, cos_a) = (layer.speed * time).sin_cos();
+      let advected = Vec3::new(tilted.x * cos_a - tilted.z * sin_a, tilted.y, tilted.x * sin_a + tilted.z * cos_a);
+
+      let value = noise::fbm3(rng::combine(seed, index as u32), advected * layer.scale, layer.octaves);
+      let t = ((value - layer.threshold) / layer.softness).clamp(0.0, 1.0);
+      let alpha = t * t * (3.0 - 2.0 * t);
+      cover + alpha * (1.0 - cover)
+  })
+}
+
+// Dirección en el mundo de la luz que más ilumina al fragmento
+fn dominant_light_direction(fragment: &Fragments, frame: &FrameUniforms) -> Vec3 {
+  frame.lights.iter().take(MAX_LIGHTS)
+      .map(|light| light.direction_from(&fragment.world_pos))
+      .max_by(|a, b| dot(&fragment.normal, a).total_cmp(&dot(&fragment.normal, b)))
+      .unwrap_or(Vec3::new(0.0, 0.0, 1.0))
+}
+
+// Término difuso de la luz que más ilumina al fragmento; sin luces todo es de noche
+fn dominant_diffuse(fragment: &Fragments, frame: &FrameUniforms) -> f32 {
+  if frame.lights.is_empty() {
+    -1.0
   } else {
-      base_color
-  };
+    dot(&fragment.normal, &dominant_light_direction(fragment, frame))
+  }
+}
 
-  final_color
+// Luz ambiental de la Tierra para que el lado nocturno no sea negro del todo
+const EARTH_AMBIENT: f32 = 0.12;
+// Rango del término difuso en el que aparecen las luces (transición del crepúsculo)
+const EARTH_TWILIGHT: (f32, f32) = (0.1, -0.2);
+// Banda anaranjada y rosada en el terminador
+const EARTH_TERMINATOR: Twilight = Twilight { tint: Color::new(235, 120, 95), width: 0.1, strength: 0.35 };
+// Tamaño de cada punto de luz y de las zonas donde se agrupan las ciudades
+const CITY_SPECKLE_SCALE: f32 = 160.0;
+const CITY_CLUSTER_SCALE: f32 = 7.0;
+
+// Puntos de luz agrupados: el ruido de baja frecuencia decide la densidad de cada región
+fn city_lights(position: Vec3, seed: u32) -> f32 {
+  let light_seed = rng::combine(seed, 0x11C7);
+  let cluster = noise::fbm3(light_seed, position * CITY_CLUSTER_SCALE, 3);
+  let density = ((cluster - 0.45) / 0.25).clamp(0.0, 1.0);
+
+  let cell = (position * CITY_SPECKLE_SCALE).map(f32::floor);
+  let speckle = rng::hash3(light_seed, cell.x, cell.y, cell.z);
+  if speckle < density * 0.6 {
+      // Cada punto tiene su propio brillo
+      0.6 + rng::hash3(light_seed ^ 0xB41, cell.x, cell.y, cell.z) * 0.4
+  } else {
+      0.0
+  }
 }
 
 
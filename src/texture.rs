@@ -0,0 +1,159 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts::PI;
+use std::path::Path;
+use crate::color::Color;
+use crate::export::load_png;
+use crate::shaders::spherical_uv;
+
+// Imagen para muestrear desde los shaders; las coordenadas se repiten fuera de [0, 1)
+// así que sirve igual para uv de una malla que para las proyecciones tri-planares
+#[derive(Clone, Debug, PartialEq)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    pub texels: Vec<Color>,
+}
+
+impl Texture {
+    pub fn new(width: usize, height: usize, texels: Vec<Color>) -> Result<Self, String> {
+        if width == 0 || height == 0 || texels.len() != width * height {
+            return Err(format!("textura de {}x{} con {} texels", width, height, texels.len()));
+        }
+        Ok(Texture { width, height, texels })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let image = load_png(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        let texels = image.buffer.iter().map(|&pixel| Color::from_hex(pixel)).collect();
+        Texture::new(image.width, image.height, texels)
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Color {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    // Interpolación bilineal entre los cuatro texels vecinos; v = 0 es la fila de arriba
+    pub fn sample(&self, uv: Vec2) -> Color {
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = uv.y * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0).lerp(&self.texel(x0 + 1, y0), tx);
+        let bottom = self.texel(x0, y0 + 1).lerp(&self.texel(x0 + 1, y0 + 1), tx);
+        top.lerp(&bottom, ty)
+    }
+}
+
+// Celdas de longitud y latitud con las que se promedia un fondo procedural
+const ENVIRONMENT_GRID: (usize, usize) = (64, 32);
+
+// Luz que llega del fondo en seis direcciones del mundo (+x, -x, +y, -y, +z, -z). Cada cara es el
+// promedio del fondo en el hemisferio hacia donde mira, pesado por el coseno, que es la luz difusa que
+// recibe una superficie orientada así; para las normales intermedias se mezclan las tres caras más cercanas
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AmbientCube {
+    pub faces: [Color; 6],
+}
+
+impl AmbientCube {
+    pub const BLACK: AmbientCube = AmbientCube { faces: [Color::BLACK; 6] };
+
+    // Un fondo de un solo color ilumina igual desde todas partes
+    pub fn uniform(color: Color) -> Self {
+        AmbientCube { faces: [color; 6] }
+    }
+
+    // Fondo procedural (una dirección normalizada del mundo da su color), muestreado en una grilla
+    pub fn from_environment(color_at: impl Fn(&Vec3) -> Color) -> Self {
+        let (width, height) = ENVIRONMENT_GRID;
+        Self::from_grid(width, height, |_, _, direction| color_at(direction))
+    }
+
+    // Imagen equirectangular con las coordenadas de shaders::spherical_uv; se promedian todos los texels
+    pub fn from_equirect(texture: &Texture) -> Self {
+        Self::from_grid(texture.width, texture.height, |x, y, _| texture.texels[y * texture.width + x])
+    }
+
+    // Recorre una grilla equirectangular con el centro de cada celda; el coseno de la latitud compensa
+    // que las celdas se achican hacia los polos
+    fn from_grid(width: usize, height: usize, color_at: impl Fn(usize, usize, &Vec3) -> Color) -> Self {
+        let mut sums = [Vec3::zeros(); 6];
+        let mut weights = [0.0f32; 6];
+        for y in 0..height {
+            let latitude = (0.5 - (y as f32 + 0.5) / height as f32) * PI;
+            let area = latitude.cos();
+            for x in 0..width {
+                let longitude = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+                let direction = Vec3::new(latitude.cos() * longitude.cos(), latitude.sin(), latitude.cos() * longitude.sin());
+                let color = color_at(x, y, &direction);
+                let facing = [direction.x, -direction.x, direction.y, -direction.y, direction.z, -direction.z];
+                for face in 0..6 {
+                    let weight = area * facing[face].max(0.0);
+                    if weight > 0.0 {
+                        sums[face] += Vec3::new(color.r, color.g, color.b) * weight;
+                        weights[face] += weight;
+                    }
+                }
+            }
+        }
+        let faces = std::array::from_fn(|face| {
+            let average = if weights[face] > 0.0 { sums[face] / weights[face] } else { Vec3::zeros() };
+            Color { r: average.x, g: average.y, b: average.z }
+        });
+        AmbientCube { faces }
+    }
+
+    // Misma luz más débil o más fuerte
+    pub fn scale(&self, factor: f32) -> Self {
+        AmbientCube { faces: self.faces.map(|face| face * factor) }
+    }
+
+    // Promedio de las seis caras, para lo que no tiene normal (los asteroides dibujados como puntos)
+    pub fn average(&self) -> Color {
+        let (r, g, b) = self.faces.iter().fold((0.0, 0.0, 0.0), |(r, g, b), face| (r + face.r, g + face.g, b + face.b));
+        Color { r: r / 6.0, g: g / 6.0, b: b / 6.0 }
+    }
+
+    // Luz para una normal del mundo: las caras hacia donde apunta en cada eje, pesadas por el cuadrado de
+    // cada componente (suman 1), así una normal alineada con un eje da exactamente esa cara
+    pub fn sample(&self, normal: &Vec3) -> Color {
+        let Some(normal) = normal.try_normalize(0.0) else {
+            return self.average();
+        };
+        let face = |value: f32, positive: usize| if value >= 0.0 { self.faces[positive] } else { self.faces[positive + 1] };
+        let (x, y, z) = (face(normal.x, 0), face(normal.y, 2), face(normal.z, 4));
+        let weights = normal.component_mul(&normal);
+        Color {
+            r: x.r * weights.x + y.r * weights.y + z.r * weights.z,
+            g: x.g * weights.x + y.g * weights.y + z.g * weights.z,
+            b: x.b * weights.x + y.b * weights.y + z.b * weights.z,
+        }
+    }
+}
+
+// Fondo de la escena cargado de una imagen equirectangular, con su luz ambiental calculada al cargarlo
+#[derive(Clone, Debug, PartialEq)]
+pub struct Skybox {
+    pub texture: Texture,
+    pub ambient: AmbientCube,
+}
+
+impl Skybox {
+    pub fn new(texture: Texture) -> Self {
+        let ambient = AmbientCube::from_equirect(&texture);
+        Skybox { texture, ambient }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        Texture::load(path).map(Skybox::new)
+    }
+
+    // Color del fondo en una dirección del mundo
+    pub fn color(&self, direction: &Vec3) -> Color {
+        self.texture.sample(spherical_uv(*direction))
+    }
+}
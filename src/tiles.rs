@@ -0,0 +1,98 @@
+use crate::framebuffer::Rect;
+
+// Lado de las teselas en pixeles. Tiene que ser múltiplo de 8: cada tesela se dibuja en una copia propia
+// con (0, 0) en su esquina y así el dithering (Bayer 8x8) cae igual que en el framebuffer
+pub const TILE_SIZE: usize = 64;
+// Cubetas del histograma de triángulos por tesela: 1, 2-3, 4-7, ... y la última con todo lo que pasa de 128
+pub const TILE_HISTOGRAM_BUCKETS: usize = 8;
+
+// Caja de pixeles de un triángulo (min_x, min_y, max_x, max_y), inclusiva y ya recortada a la vista
+pub type PixelBounds = (i32, i32, i32, i32);
+
+// Grilla de teselas de TILE_SIZE alineada a la esquina del framebuffer (no a la de la vista), con la lista
+// de triángulos que toca cada una en el orden en que se agregaron. Las listas se reutilizan entre llamadas
+#[derive(Default)]
+pub struct TileBins {
+    width: usize,
+    height: usize,
+    columns: usize,
+    bins: Vec<Vec<usize>>,
+}
+
+impl TileBins {
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut bins = TileBins::default();
+        bins.reset(width, height);
+        bins
+    }
+
+    // Vacía todas las listas para un framebuffer de `width` x `height`
+    pub fn reset(&mut self, width: usize, height: usize) {
+        let columns = width.div_ceil(TILE_SIZE);
+        let rows = height.div_ceil(TILE_SIZE);
+        (self.width, self.height, self.columns) = (width, height, columns);
+        self.bins.resize_with(columns * rows, Vec::new);
+        self.bins.truncate(columns * rows);
+        self.bins.iter_mut().for_each(Vec::clear);
+    }
+
+    // Agrega el triángulo `triangle` a cada tesela que toca su caja; lo que cae fuera del framebuffer no cuenta
+    pub fn insert(&mut self, triangle: usize, bounds: PixelBounds) {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        if max_x < 0 || max_y < 0 || min_x > max_x || min_y > max_y {
+            return;
+        }
+        let last_column = (max_x as usize).min(self.width.saturating_sub(1)) / TILE_SIZE;
+        let last_row = (max_y as usize).min(self.height.saturating_sub(1)) / TILE_SIZE;
+        for row in min_y.max(0) as usize / TILE_SIZE..=last_row {
+            for column in min_x.max(0) as usize / TILE_SIZE..=last_column {
+                self.bins[row * self.columns + column].push(triangle);
+            }
+        }
+    }
+
+    // Pixeles de la tesela `index` (las del borde derecho y el de abajo pueden ser más chicas)
+    pub fn rect(&self, index: usize) -> Rect {
+        let (x, y) = (index % self.columns * TILE_SIZE, index / self.columns * TILE_SIZE);
+        Rect::new(x, y, TILE_SIZE.min(self.width - x), TILE_SIZE.min(self.height - y))
+    }
+
+    // Teselas con algún triángulo, con su rectángulo y sus triángulos
+    pub fn occupied(&self) -> Vec<(Rect, &[usize])> {
+        self.bins.iter().enumerate()
+            .filter(|(_, triangles)| !triangles.is_empty())
+            .map(|(index, triangles)| (self.rect(index), triangles.as_slice()))
+            .collect()
+    }
+
+    // Cuántas teselas recibieron 1 triángulo, 2-3, 4-7...; las vacías no cuentan
+    pub fn histogram(&self) -> [usize; TILE_HISTOGRAM_BUCKETS] {
+        let mut histogram = [0; TILE_HISTOGRAM_BUCKETS];
+        for triangles in self.bins.iter().filter(|triangles| !triangles.is_empty()) {
+            histogram[histogram_bucket(triangles.len())] += 1;
+        }
+        histogram
+    }
+}
+
+// Cubeta del histograma para una tesela con `count` triángulos (al menos 1)
+pub fn histogram_bucket(count: usize) -> usize {
+    (count.max(1).ilog2() as usize).min(TILE_HISTOGRAM_BUCKETS - 1)
+}
+
+// El histograma como texto, "1: 4, 2-3: 10, ..., 128+: 2", sin las cubetas vacías
+pub fn histogram_label(histogram: &[usize; TILE_HISTOGRAM_BUCKETS]) -> String {
+    histogram.iter().enumerate()
+        .filter(|(_, &tiles)| tiles > 0)
+        .map(|(bucket, tiles)| {
+            let low = 1usize << bucket;
+            let range = match bucket {
+                0 => "1".to_string(),
+                _ if bucket == TILE_HISTOGRAM_BUCKETS - 1 => format!("{}+", low),
+                _ => format!("{}-{}", low, 2 * low - 1),
+            };
+            format!("{}: {}", range, tiles)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
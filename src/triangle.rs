@@ -1,64 +1,194 @@
-use nalgebra_glm::{Vec3, dot, Vec2};
+use nalgebra_glm::{Vec3, Vec2};
 use crate::fragments::Fragments;
+use crate::framebuffer::Rect;
 use crate::vertex::Vertex;
 
-pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex) -> Vec<Fragments> {
+pub fn triangle(v1: &Vertex, v2: &Vertex, v3: &Vertex, clip: &Rect) -> Vec<Fragments> {
   let mut fragments = Vec::new();
-  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+  triangle_for_each(v1, v2, v3, clip, |fragment| fragments.push(fragment));
+  fragments
+}
 
-  let (min_x, min_y, max_x, max_y) = calculate_bounding_box(&a, &b, &c);
+// Igual que `triangle` pero entrega cada fragmento a `emit` en lugar de juntarlos en un Vec
+pub fn triangle_for_each<F: FnMut(Fragments)>(v1: &Vertex, v2: &Vertex, v3: &Vertex, clip: &Rect, mut emit: F) {
+  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
 
-  let light_dir = Vec3::new(0.0, 0.0, 1.0);
+  // Solo se recorre la parte del triángulo que cae dentro del rectángulo de la vista
+  let Some(bounding_box) = calculate_bounding_box(&a, &b, &c, clip) else {
+    return;
+  };
 
   let triangle_area = edge_function(&a, &b, &c);
+  if !has_area(triangle_area) {
+    return;
+  }
+
+  // La interpolación es lineal en la pantalla, así que lo que cambia la posición del objeto de un pixel
+  // al siguiente es igual en todo el triángulo
+  let (vertex_pos_dx, vertex_pos_dy) = screen_derivatives(&a, &b, &c, triangle_area, [v1.position, v2.position, v3.position]);
+
+  for_each_covered_pixel(&a, &b, &c, triangle_area, bounding_box, |x, y, (w1, w2, w3)| {
+    // Interpolate normal (se normaliza y se ilumina en la etapa de fragmentos)
+    let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
+
+    // Interpolate depth
+    let depth = a.z * w1 + b.z * w2 + c.z * w3;
+
+    // Positions of the original vertex
+    let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+
+    // Interpolate world position (para las luces puntuales)
+    let world_position = v1.world_position * w1 + v2.world_position * w2 + v3.world_position * w3;
+
+    // Interpolate vertex color
+    let color = v1.color * w1 + v2.color * w2 + v3.color * w3;
+
+    // Interpolate view-space depth (para la niebla)
+    let view_depth = v1.view_depth * w1 + v2.view_depth * w2 + v3.view_depth * w3;
+
+    emit(Fragments {
+      view_depth,
+      vertex_pos_dx,
+      vertex_pos_dy,
+      ..Fragments::new(
+        Vec2::new(x as f32, y as f32),
+        depth,
+        normal,
+        0.0,
+        vertex_position,
+        color,
+        world_position,
+      )
+    });
+  });
+}
+
+// Pixeles de la caja envolvente cuyo centro cae dentro del triángulo, con sus pesos baricéntricos.
+// Con la característica `float-raster` se usa la prueba en flotantes de antes, para comparar
+fn for_each_covered_pixel<F: FnMut(i32, i32, (f32, f32, f32))>(a: &Vec3, b: &Vec3, c: &Vec3, area: f32, bounding_box: (i32, i32, i32, i32), emit: F) {
+  if cfg!(feature = "float-raster") || ![a, b, c].iter().all(|v| fits_fixed_point(v)) {
+    covered_pixels_float(a, b, c, area, bounding_box, emit);
+  } else {
+    covered_pixels_fixed(a, b, c, bounding_box, emit);
+  }
+}
 
-  // Iterate over each pixel in the bounding box
+// Prueba en flotantes: un pixel sobre una arista compartida puede quedar en los dos triángulos o en ninguno
+fn covered_pixels_float<F: FnMut(i32, i32, (f32, f32, f32))>(a: &Vec3, b: &Vec3, c: &Vec3, area: f32, bounding_box: (i32, i32, i32, i32), mut emit: F) {
+  let (min_x, min_y, max_x, max_y) = bounding_box;
   for y in min_y..=max_y {
     for x in min_x..=max_x {
       let point = Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 0.0);
+      let (w1, w2, w3) = barycentric_coordinates(&point, a, b, c, area);
+      if (0.0..=1.0).contains(&w1) && (0.0..=1.0).contains(&w2) && (0.0..=1.0).contains(&w3) {
+        emit(x, y, (w1, w2, w3));
+      }
+    }
+  }
+}
 
-      // Calculate barycentric coordinates
-      let (w1, w2, w3) = barycentric_coordinates(&point, &a, &b, &c, triangle_area);
-
-      // Check if the point is inside the triangle
-      if w1 >= 0.0 && w1 <= 1.0 && 
-         w2 >= 0.0 && w2 <= 1.0 &&
-         w3 >= 0.0 && w3 <= 1.0 {
-
-        // Interpolate normal
-        let normal = v1.transformed_normal * w1 + v2.transformed_normal * w2 + v3.transformed_normal * w3;
-        let normal = normal.normalize();
+// Bits de subpixel de las coordenadas en punto fijo (1/256 de pixel)
+const SUBPIXEL_BITS: u32 = 8;
+const SUBPIXEL: f32 = (1 << SUBPIXEL_BITS) as f32;
+// Coordenada máxima en pixeles para el punto fijo: así los productos de las funciones de arista caben
+// en i64. Los triángulos con vértices más lejos (fuera de la pantalla) van por la prueba en flotantes
+const MAX_FIXED_COORDINATE: f32 = (1 << 22) as f32;
 
-        // Calculate lighting intensity
-        let intensity = dot(&normal, &light_dir).max(0.0);
+fn fits_fixed_point(v: &Vec3) -> bool {
+  v.x.abs() < MAX_FIXED_COORDINATE && v.y.abs() < MAX_FIXED_COORDINATE
+}
 
-        // Interpolate depth
-        let depth = a.z * w1 + b.z * w2 + c.z * w3;
+fn to_fixed(v: &Vec3) -> (i64, i64) {
+  ((v.x * SUBPIXEL).round() as i64, (v.y * SUBPIXEL).round() as i64)
+}
 
-        // Positions of the original vertex
-        let vertex_position = v1.position * w1 + v2.position * w2 + v3.position * w3;
+// Prueba hermética: los vértices se ajustan a la grilla de subpixeles y las aristas se evalúan con
+// enteros, sin redondeo. Un pixel justo sobre una arista es del triángulo solo si es una arista
+// superior o izquierda, así cada pixel de una arista compartida lo dibuja exactamente un triángulo
+fn covered_pixels_fixed<F: FnMut(i32, i32, (f32, f32, f32))>(a: &Vec3, b: &Vec3, c: &Vec3, bounding_box: (i32, i32, i32, i32), mut emit: F) {
+  let (a, b, c) = (to_fixed(a), to_fixed(b), to_fixed(c));
+  let area = fixed_edge_function(a, b, c);
+  if area == 0 {
+    return;
+  }
+  // Con cualquier orden de los vértices el interior queda del lado positivo de las tres aristas
+  let orientation = area.signum();
+  let edges = [(b, c), (c, a), (a, b)];
+  let bias = edges.map(|(from, to)| if is_top_left(from, to, orientation) { 0 } else { -1 });
 
-        fragments.push(Fragments::new(
-            Vec2::new(x as f32, y as f32),
-            depth,
-            normal,
-            intensity,
-            vertex_position,
-        ));
+  let (min_x, min_y, max_x, max_y) = bounding_box;
+  let half = 1 << (SUBPIXEL_BITS - 1);
+  for y in min_y..=max_y {
+    for x in min_x..=max_x {
+      let point = (((x as i64) << SUBPIXEL_BITS) + half, ((y as i64) << SUBPIXEL_BITS) + half);
+      let weights = edges.map(|(from, to)| fixed_edge_function(from, to, point));
+      if (0..3).all(|edge| weights[edge] * orientation + bias[edge] >= 0) {
+        let [w1, w2, w3] = weights.map(|weight| weight as f32 / area as f32);
+        emit(x, y, (w1, w2, w3));
       }
     }
   }
+}
 
-  fragments
+// Con y hacia abajo, una arista es superior si es horizontal con el interior debajo e izquierda si el
+// interior queda a su derecha. Las dos caras de una arista compartida la recorren en sentidos opuestos,
+// así que solo una la considera superior o izquierda
+fn is_top_left(from: (i64, i64), to: (i64, i64), orientation: i64) -> bool {
+  let (dx, dy) = ((to.0 - from.0) * orientation, (to.1 - from.1) * orientation);
+  dy > 0 || (dy == 0 && dx < 0)
+}
+
+fn fixed_edge_function(a: (i64, i64), b: (i64, i64), c: (i64, i64)) -> i64 {
+  (c.0 - a.0) * (b.1 - a.1) - (c.1 - a.1) * (b.0 - a.0)
+}
+
+// Cantidad de pixeles de la caja envolvente dentro de la vista; None si el triángulo
+// queda fuera o no tiene área (no genera fragmentos)
+pub fn screen_coverage(v1: &Vertex, v2: &Vertex, v3: &Vertex, clip: &Rect) -> Option<usize> {
+  let (min_x, min_y, max_x, max_y) = screen_bounds(v1, v2, v3, clip)?;
+  Some((max_x - min_x + 1) as usize * (max_y - min_y + 1) as usize)
 }
 
-fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3) -> (i32, i32, i32, i32) {
-    let min_x = v1.x.min(v2.x).min(v3.x).floor() as i32;
-    let min_y = v1.y.min(v2.y).min(v3.y).floor() as i32;
-    let max_x = v1.x.max(v2.x).max(v3.x).ceil() as i32;
-    let max_y = v1.y.max(v2.y).max(v3.y).ceil() as i32;
+// Caja envolvente (min_x, min_y, max_x, max_y) dentro de la vista, inclusiva; todos los fragmentos del
+// triángulo caen en ella. None en los mismos casos que screen_coverage
+pub fn screen_bounds(v1: &Vertex, v2: &Vertex, v3: &Vertex, clip: &Rect) -> Option<(i32, i32, i32, i32)> {
+  let (a, b, c) = (v1.transformed_position, v2.transformed_position, v3.transformed_position);
+  if !has_area(edge_function(&a, &b, &c)) {
+    return None;
+  }
+  calculate_bounding_box(&a, &b, &c, clip)
+}
+
+// Área mínima (en pixeles, el doble del área real) para rasterizar un triángulo; con menos los pesos
+// baricéntricos se disparan. Un área NaN (vértices no finitos) también queda fuera
+const MIN_TRIANGLE_AREA: f32 = 1e-4;
+
+fn has_area(area: f32) -> bool {
+  area.abs() >= MIN_TRIANGLE_AREA
+}
+
+// Caja envolvente recortada al rectángulo de la vista; None si el triángulo queda fuera
+fn calculate_bounding_box(v1: &Vec3, v2: &Vec3, v3: &Vec3, clip: &Rect) -> Option<(i32, i32, i32, i32)> {
+    // Los límites se calculan en i32 para que las coordenadas negativas no den la vuelta
+    let min_x = (v1.x.min(v2.x).min(v3.x).floor() as i32).max(clip.x as i32);
+    let min_y = (v1.y.min(v2.y).min(v3.y).floor() as i32).max(clip.y as i32);
+    let max_x = (v1.x.max(v2.x).max(v3.x).ceil() as i32).min((clip.x + clip.width) as i32 - 1);
+    let max_y = (v1.y.max(v2.y).max(v3.y).ceil() as i32).min((clip.y + clip.height) as i32 - 1);
+
+    if min_x > max_x || min_y > max_y {
+        return None;
+    }
+
+    Some((min_x, min_y, max_x, max_y))
+}
 
-    (min_x, min_y, max_x, max_y)
+// Cambio de un atributo de los vértices al moverse un pixel en x y uno en y, medido desde el primer vértice
+fn screen_derivatives(a: &Vec3, b: &Vec3, c: &Vec3, area: f32, values: [Vec3; 3]) -> (Vec3, Vec3) {
+  let at = |x: f32, y: f32| {
+    let (w1, w2, w3) = barycentric_coordinates(&Vec3::new(a.x + x, a.y + y, 0.0), a, b, c, area);
+    values[0] * w1 + values[1] * w2 + values[2] * w3
+  };
+  (at(1.0, 0.0) - values[0], at(0.0, 1.0) - values[0])
 }
 
 fn barycentric_coordinates(p: &Vec3, a: &Vec3, b: &Vec3, c: &Vec3, area: f32) -> (f32, f32, f32) {
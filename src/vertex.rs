@@ -9,6 +9,9 @@ pub struct Vertex {
   pub color: Color,
   pub transformed_position: Vec3,
   pub transformed_normal: Vec3,
+  pub world_position: Vec3,
+  // Distancia a la cámara en el espacio de la vista (para la niebla)
+  pub view_depth: f32,
 }
 
 impl Vertex {
@@ -17,9 +20,11 @@ impl Vertex {
       position,
       normal,
       tex_coords,
-      color: Color::BLACK,
+      color: Color::new(255, 255, 255),
       transformed_position: position,
       transformed_normal: normal,
+      world_position: position,
+      view_depth: 0.0,
     }
   }
 }
@@ -30,9 +35,11 @@ impl Default for Vertex {
       position: Vec3::new(0.0, 0.0, 0.0),
       normal: Vec3::new(0.0, 1.0, 0.0),
       tex_coords: Vec2::new(0.0, 0.0),
-      color: Color::BLACK,
+      color: Color::new(255, 255, 255),
       transformed_position: Vec3::new(0.0, 0.0, 0.0),
       transformed_normal: Vec3::new(0.0, 1.0, 0.0),
+      world_position: Vec3::new(0.0, 0.0, 0.0),
+      view_depth: 0.0,
     }
   }
 }
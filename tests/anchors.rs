@@ -0,0 +1,135 @@
+// Marcas en la superficie: unproject deshace lo que hace project, el pixel de un punto conocido del cuerpo
+// devuelve su dirección en el espacio del cuerpo, y la marca se esconde cuando el giro la lleva al otro lado
+use nalgebra_glm::{Vec3, Vec4};
+use lab4_shaders::anchors::{draw_anchors, pick_surface, SurfaceAnchor, ANCHOR_COLOR};
+use lab4_shaders::camera::{Camera, Projection};
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::pipeline::{build_uniforms, project, unproject, FrameUniforms};
+use lab4_shaders::scene::{body_id, procedural_meshes, render_scene, scene_depth_range, BodyConfig, MeshKind, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+use lab4_shaders::shaders::ShaderType;
+
+const SIZE: usize = 128;
+// Giro del planeta en radianes por tick alrededor de su eje
+const SPIN: f32 = 0.3;
+
+fn camera() -> Camera {
+    Camera::new(Vec3::new(0.5, 1.0, 5.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+}
+
+fn assert_close(actual: Vec3, expected: Vec3, tolerance: f32, what: &str) {
+    assert!((actual - expected).magnitude() <= tolerance, "{}: {:?} en lugar de {:?}", what, actual, expected);
+}
+
+#[test]
+fn unproject_inverts_project() {
+    let mut orthographic = camera();
+    orthographic.projection = Projection::Orthographic { height: 4.0 };
+    let points = [Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.7, -0.3, 0.4), Vec3::new(-1.2, 0.8, -2.0), Vec3::new(0.1, 0.2, 3.5)];
+
+    for (name, camera) in [("perspectiva", camera()), ("ortográfica", orthographic)] {
+        let uniforms = build_uniforms(&camera, 0.0, 160, 120, (0.5, 20.0));
+        for point in points {
+            let screen = project(&uniforms, point).expect("el punto quedó detrás de la cámara");
+            let back = unproject(screen.x, screen.y, screen.z, &uniforms);
+            assert_close(back, point, 1e-3 * (1.0 + (point - camera.eye).magnitude()), name);
+        }
+    }
+
+    // El centro de la pantalla en el plano cercano está sobre la línea de la cámara al centro
+    let uniforms = build_uniforms(&camera(), 0.0, 160, 120, (0.5, 20.0));
+    let near = unproject(80.0, 60.0, -1.0, &uniforms);
+    let along = (near - camera().eye).normalize().dot(&(camera().center - camera().eye).normalize());
+    assert!((along - 1.0).abs() < 1e-4, "el centro de la pantalla no mira al centro de la cámara ({})", along);
+}
+
+// Un planeta inclinado que gira, dibujado en el instante `time`
+struct Planet {
+    scene: SceneConfig,
+    meshes: SceneMeshes,
+}
+
+impl Planet {
+    fn new() -> Self {
+        let mut body = BodyConfig::new("Gigante", ShaderType::GasPlanet, MeshKind::Sphere, Vec3::new(0.2, -0.1, 0.0), 1.5);
+        body.spin = Vec3::new(0.0, SPIN, 0.0);
+        body.tilt = 0.3;
+        Planet { scene: SceneConfig::new(vec![body]), meshes: procedural_meshes() }
+    }
+
+    fn draw(&self, time: f32) -> (Framebuffer, FrameUniforms) {
+        let mut state = SceneState::new(&self.scene, 0, &self.meshes);
+        let camera = camera();
+        let depth_range = scene_depth_range(&state.bodies(&self.scene, time), &self.meshes, &camera);
+        let uniforms = build_uniforms(&camera, time, SIZE, SIZE, depth_range);
+        let mut framebuffer = Framebuffer::new(SIZE, SIZE);
+        render_scene(&mut framebuffer, 1, &self.scene, &uniforms, &self.meshes, &mut state, &RenderOptions::default());
+        (framebuffer, uniforms)
+    }
+
+    // Dirección en el espacio del cuerpo del punto de la superficie que en `time` queda hacia `toward` (en el mundo)
+    fn direction_toward(&self, time: f32, toward: Vec3) -> Vec3 {
+        let body = &self.scene.bodies(time)[0];
+        let inverse = body.model_matrix().try_inverse().unwrap();
+        let local = inverse * Vec4::new(body.translation.x + toward.x, body.translation.y + toward.y, body.translation.z + toward.z, 1.0);
+        Vec3::new(local.x, local.y, local.z).normalize()
+    }
+
+    fn surface_point(&self, time: f32, direction: Vec3) -> Vec3 {
+        let body = &self.scene.bodies(time)[0];
+        let local = direction * self.meshes.radius(MeshKind::Sphere);
+        let world = body.model_matrix() * Vec4::new(local.x, local.y, local.z, 1.0);
+        Vec3::new(world.x, world.y, world.z)
+    }
+}
+
+#[test]
+fn picked_pixel_gives_body_direction() {
+    let planet = Planet::new();
+    let time = 2.0;
+    let (framebuffer, uniforms) = planet.draw(time);
+    let model_matrices: Vec<_> = planet.scene.bodies(time).iter().map(|body| body.model_matrix()).collect();
+
+    for toward in [Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.4, 0.5, 1.0), Vec3::new(-0.6, -0.2, 0.8)] {
+        let direction = planet.direction_toward(time, toward);
+        let screen = project(&uniforms, planet.surface_point(time, direction)).unwrap();
+        let (index, picked) = pick_surface(&framebuffer, &uniforms, &model_matrices, screen.x as usize, screen.y as usize)
+            .expect("el pixel del punto no tiene cuerpo");
+        assert_eq!(index, 0);
+        // Medio pixel y las caras planas de la malla alejan un poco el punto del de la esfera
+        assert_close(picked, direction, 0.03, "dirección del pixel");
+    }
+
+    // Sobre el fondo no hay nada que marcar
+    assert!(pick_surface(&framebuffer, &uniforms, &model_matrices, 0, 0).is_none());
+}
+
+#[test]
+fn anchor_follows_rotation_and_hides_on_far_side() {
+    let planet = Planet::new();
+    let anchors = [SurfaceAnchor {
+        body_id: body_id(0),
+        direction: planet.direction_toward(0.0, Vec3::new(0.2, 0.3, 1.0)),
+        label: "tormenta".to_string(),
+        color: ANCHOR_COLOR,
+    }];
+
+    let changed = |time: f32| {
+        let (mut framebuffer, uniforms) = planet.draw(time);
+        let before = framebuffer.buffer.clone();
+        draw_anchors(&mut framebuffer, &anchors, &planet.scene.bodies(time), &planet.meshes, &uniforms);
+        let changed: Vec<usize> = (0..before.len()).filter(|&index| before[index] != framebuffer.buffer[index]).collect();
+        (changed, uniforms)
+    };
+
+    // De frente la marca se dibuja justo sobre el punto, que se movió con el giro
+    for time in [0.0, 1.0] {
+        let (pixels, uniforms) = changed(time);
+        let screen = project(&uniforms, planet.surface_point(time, anchors[0].direction)).unwrap();
+        let at_point = screen.y as usize * SIZE + screen.x as usize;
+        assert!(pixels.contains(&at_point), "la marca no está sobre el punto en t = {}", time);
+    }
+
+    // Media vuelta después queda del otro lado y no se ve nada
+    let (pixels, _) = changed(std::f32::consts::PI / SPIN);
+    assert!(pixels.is_empty(), "la marca del otro lado cambió {} pixeles", pixels.len());
+}
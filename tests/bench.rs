@@ -0,0 +1,134 @@
+// Medición: los percentiles interpolan entre las dos muestras vecinas sin importar el orden en que
+// llegaron, cada tiempo cae en la cubeta correcta del histograma (un tiempo justo en un borde va a la
+// cubeta de arriba) y sin muestras o con una sola el resumen no se sale de lo esperable
+use std::time::Duration;
+use lab4_shaders::bench::{BenchRecorder, Histogram, Resolution, RunSettings, Samples, StageSummary, Stages, HISTOGRAM_EDGES_MS};
+use lab4_shaders::rng::hash_u32;
+
+fn close(value: f64, expected: f64) -> bool {
+    (value - expected).abs() < 1e-9
+}
+
+fn summary_of(values: &[f64]) -> StageSummary {
+    let mut samples = Samples::default();
+    for &value in values {
+        samples.push_ms(value);
+    }
+    samples.summary()
+}
+
+fn settings() -> RunSettings {
+    RunSettings {
+        input: "teclado".to_string(), fixed_quality: true, target_fps: 30, procedural: false, dither: false, toon: false,
+        ambient_occlusion: false, particle_rings: false, adaptive_ring: false, progressive: false, checkerboard: false, fragment_budget: 0, threads: 1, seed: 0,
+    }
+}
+
+#[test]
+fn percentiles_interpolate_between_neighbouring_samples() {
+    // Del 1 al 100 desordenados: la mediana de una cantidad par es el promedio de las dos del medio y el
+    // p95 cae entre la muestra 95 y la 96
+    let mut order: Vec<u32> = (1..=100).collect();
+    order.sort_by_key(|&value| hash_u32(7, value));
+    let values: Vec<f64> = order.iter().map(|&value| value as f64).collect();
+    let summary = summary_of(&values);
+    assert!(close(summary.min_ms, 1.0), "{:?}", summary);
+    assert!(close(summary.median_ms, 50.5), "{:?}", summary);
+    assert!(close(summary.p95_ms, 95.05), "{:?}", summary);
+    assert!(close(summary.max_ms, 100.0), "{:?}", summary);
+    assert!(close(summary.mean_ms, 50.5), "{:?}", summary);
+
+    // Una cantidad impar tiene una muestra justo en el medio
+    let summary = summary_of(&[9.0, 1.0, 5.0, 3.0, 7.0]);
+    assert!(close(summary.median_ms, 5.0), "{:?}", summary);
+    assert!(close(summary.p95_ms, 8.6), "{:?}", summary);
+
+    // Las fracciones fuera de [0, 1] se quedan en el mínimo y el máximo
+    let mut samples = Samples::default();
+    for value in [2.0, 4.0, 6.0] {
+        samples.push_ms(value);
+    }
+    assert!(close(samples.percentile(-0.5), 2.0));
+    assert!(close(samples.percentile(1.5), 6.0));
+}
+
+#[test]
+fn histogram_edges_go_to_the_bucket_above() {
+    let mut histogram = Histogram::new();
+    assert_eq!(histogram.counts.len(), HISTOGRAM_EDGES_MS.len() + 1);
+    // Un poco antes de cada borde cuenta en la cubeta del borde y justo en el borde en la siguiente
+    for (index, &edge) in HISTOGRAM_EDGES_MS.iter().enumerate() {
+        let mut histogram = Histogram::new();
+        histogram.add(edge - 1e-6);
+        histogram.add(edge);
+        let mut expected = vec![0; HISTOGRAM_EDGES_MS.len() + 1];
+        expected[index] += 1;
+        expected[index + 1] += 1;
+        assert_eq!(histogram.counts, expected, "borde {} ms", edge);
+    }
+
+    // El cero va a la primera y lo que pasa el último borde, por mucho que sea, a la última
+    for value in [0.0, 1e6, f64::INFINITY] {
+        histogram.add(value);
+    }
+    assert_eq!(histogram.counts[0], 1);
+    assert_eq!(histogram.counts[HISTOGRAM_EDGES_MS.len()], 2);
+    assert_eq!(histogram.counts.iter().sum::<u32>(), 3);
+}
+
+#[test]
+fn no_samples_and_a_single_sample() {
+    // Sin muestras todo el resumen es cero en lugar de fallar o dar NaN
+    let mut empty = Samples::default();
+    assert!(empty.is_empty());
+    assert_eq!(empty.percentile(0.5), 0.0);
+    assert_eq!(empty.summary(), StageSummary::default());
+
+    // Con una sola muestra (o varias iguales) cualquier percentil es esa muestra
+    for count in [1, 7] {
+        let mut samples = Samples::default();
+        for _ in 0..count {
+            samples.push(Duration::from_micros(4_250));
+        }
+        assert_eq!(samples.len(), count);
+        let summary = samples.summary();
+        for value in [summary.min_ms, summary.median_ms, summary.p95_ms, summary.max_ms, summary.mean_ms] {
+            assert!(close(value, 4.25), "{} muestras: {:?}", count, summary);
+        }
+    }
+
+    // Un reporte sin frames medidos tiene el histograma vacío y los tiempos en cero
+    let mut recorder = BenchRecorder::new();
+    recorder.frame(1);
+    let report = recorder.report(Resolution { width: 800, height: 600 }, settings());
+    assert_eq!((report.frames, report.measured_frames), (1, 0));
+    assert_eq!(report.stages.frame, StageSummary::default());
+    assert!(report.histogram.counts.iter().all(|&count| count == 0));
+}
+
+#[test]
+fn the_recorder_summarizes_frames_and_scene_spans() {
+    // Nueve de cada diez frames tardan 1 ms y uno 40 ms; las primeras 30 vueltas son de la escena 2
+    let mut recorder = BenchRecorder::new();
+    for index in 0..100 {
+        recorder.frame(if index < 30 { 2 } else { 5 });
+        let frame = Duration::from_millis(if index % 10 == 9 { 40 } else { 1 });
+        recorder.measure(Stages { frame, ..Stages::default() }, "Full");
+    }
+    let report = recorder.report(Resolution { width: 800, height: 600 }, settings());
+    let frame = report.stages.frame;
+    assert!(close(frame.median_ms, 1.0), "{:?}", frame);
+    assert!(close(frame.p95_ms, 40.0), "{:?}", frame);
+    assert!(close(frame.mean_ms, 4.9), "{:?}", frame);
+
+    let slow_bucket = HISTOGRAM_EDGES_MS.iter().position(|&edge| 40.0 < edge).unwrap();
+    assert_eq!(report.histogram.counts[0], 90);
+    assert_eq!(report.histogram.counts[slow_bucket], 10);
+    assert_eq!(report.histogram.counts.iter().sum::<u32>(), 100);
+
+    let spans: Vec<(u32, u32, u32)> = report.scenes.iter().map(|span| (span.scene, span.first_frame, span.frames)).collect();
+    assert_eq!(spans, [(2, 1, 30), (5, 31, 70)]);
+    assert_eq!(report.measured_frames, 100);
+    assert_eq!(report.quality_tiers.len(), 1);
+    assert_eq!(report.quality_tiers[0].frames, 100);
+}
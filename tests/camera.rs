@@ -0,0 +1,68 @@
+// Orientaciones con cuaterniones: la cámara que da la vuelta completa en vertical vuelve a la misma pose,
+// el recorrido pasa por cada pose siguiendo el arco alrededor del centro, y el giro propio de un cuerpo
+// volteado es alrededor de su propio eje
+use std::f32::consts::TAU;
+use nalgebra_glm::{quat_angle_axis, quat_rotate_vec3, Vec3};
+use lab4_shaders::camera::{sample_path, Camera, Keyframe};
+use lab4_shaders::scene::{BodyConfig, MeshKind};
+use lab4_shaders::shaders::ShaderType;
+
+const EPSILON: f32 = 1e-3;
+
+#[test]
+fn a_full_vertical_loop_returns_to_the_same_pose() {
+    // Pasa por los dos polos, donde una cámara con ángulos se trabaría o daría vuelta el arriba
+    let eye = Vec3::new(1.0, 2.0, 4.0);
+    let mut camera = Camera::new(eye, Vec3::zeros(), Vec3::y());
+    let start_up = quat_rotate_vec3(&camera.orientation(), &Vec3::y());
+    let steps = 36;
+    for _ in 0..steps {
+        camera.orbit(0.0, TAU / steps as f32);
+    }
+    assert!((camera.eye - eye).magnitude() < EPSILON, "el ojo terminó en {:?}", camera.eye);
+    assert!((camera.up - start_up).magnitude() < EPSILON, "el arriba terminó en {:?}", camera.up);
+}
+
+#[test]
+fn the_tour_passes_through_each_pose_along_the_arc() {
+    // La segunda pose está justo encima del polo
+    let keyframes = [
+        Keyframe { time: 0.0, eye: Vec3::new(0.0, 0.0, 5.0), center: Vec3::zeros() },
+        Keyframe { time: 2.0, eye: Vec3::new(0.0, 5.0, 0.0), center: Vec3::zeros() },
+        Keyframe { time: 4.0, eye: Vec3::new(6.0, 0.0, 0.0), center: Vec3::new(1.0, 0.0, 0.0) },
+    ];
+    for keyframe in &keyframes {
+        let (eye, center) = sample_path(&keyframes, keyframe.time).unwrap();
+        assert!((eye - keyframe.eye).magnitude() < EPSILON, "en {}: ojo {:?}", keyframe.time, eye);
+        assert!((center - keyframe.center).magnitude() < EPSILON, "en {}: centro {:?}", keyframe.time, center);
+    }
+
+    // Entre dos poses a la misma distancia del centro el ojo no se acerca ni se aleja
+    for step in 1..20 {
+        let time = step as f32 * 0.1;
+        let (eye, center) = sample_path(&keyframes, time).unwrap();
+        let distance = (eye - center).magnitude();
+        assert!((distance - 5.0).abs() < EPSILON, "en {}: a {} del centro", time, distance);
+    }
+}
+
+#[test]
+fn a_tilted_body_spins_around_its_own_pole() {
+    // El giro propio va después de `rotation`, alrededor del eje del cuerpo ya volteado
+    let mut body = BodyConfig::new("Volteado", ShaderType::GasPlanet, MeshKind::Sphere, Vec3::zeros(), 1.0);
+    body.rotation = Vec3::new(0.9, 0.4, 0.0).into();
+    body.spin = Vec3::new(0.0, 0.01, 0.0);
+    let time = 137.0;
+    let (start, later) = (body.orientation_at(0.0), body.orientation_at(time));
+
+    // El polo no se mueve al girar
+    let pole = quat_rotate_vec3(&start, &Vec3::y());
+    assert!((quat_rotate_vec3(&later, &Vec3::y()) - pole).magnitude() < 1e-4);
+
+    // Girar en los ejes del cuerpo es lo mismo que girar alrededor de ese polo en el mundo, y el ecuador
+    // sí se movió
+    let in_world = quat_angle_axis(0.01 * time, &pole) * start;
+    let marker = Vec3::new(1.0, 0.0, 0.0);
+    assert!((quat_rotate_vec3(&later, &marker) - quat_rotate_vec3(&in_world, &marker)).magnitude() < 1e-4);
+    assert!((quat_rotate_vec3(&later, &marker) - quat_rotate_vec3(&start, &marker)).magnitude() > 0.5);
+}
@@ -0,0 +1,111 @@
+// Recorridos de la cámara (K, Ctrl+K, --camera-path): el archivo de ida y vuelta, los casos que no son
+// un recorrido, las poses repetidas o desordenadas, el bucle y que la curva pase por las poses sin saltos
+use nalgebra_glm::Vec3;
+use lab4_shaders::camera::{Camera, Keyframe};
+use lab4_shaders::camera_path::{CameraPath, MIN_KEYFRAME_GAP};
+
+const EPSILON: f32 = 1e-4;
+
+fn keyframe(time: f32, eye: Vec3) -> Keyframe {
+    Keyframe { time, eye, center: Vec3::zeros() }
+}
+
+// Cinco poses alrededor del planeta con anillos, subiendo y bajando, como un vuelo grabado a mano
+fn swoop() -> Vec<Keyframe> {
+    vec![
+        keyframe(100.0, Vec3::new(0.0, 1.0, 6.0)),
+        keyframe(220.0, Vec3::new(5.0, 2.5, 3.0)),
+        keyframe(340.0, Vec3::new(4.0, -1.0, -4.0)),
+        keyframe(460.0, Vec3::new(-3.0, 0.5, -4.0)),
+        keyframe(580.0, Vec3::new(-4.0, 3.0, 2.0)),
+    ]
+}
+
+#[test]
+fn file_round_trip_keeps_poses_and_loop_flag() {
+    let path = CameraPath::new(swoop(), true).unwrap();
+    let source = path.to_toml().unwrap();
+    assert!(source.contains("loop = true") && source.contains("[[keyframe]]"), "{}", source);
+    assert_eq!(CameraPath::parse(&source).unwrap(), path);
+
+    // Sin `loop` se queda en la última pose
+    let minimal = "[[keyframe]]\ntime = 0.0\neye = [0.0, 0.0, 5.0]\ncenter = [0.0, 0.0, 0.0]\n\n[[keyframe]]\ntime = 60.0\neye = [5.0, 0.0, 0.0]\ncenter = [0.0, 0.0, 0.0]\n";
+    assert!(!CameraPath::parse(minimal).unwrap().looping);
+    assert!(CameraPath::parse(&format!("{}speed = 2.0\n", minimal)).is_err(), "los campos desconocidos son un error");
+}
+
+#[test]
+fn fewer_than_two_keyframes_is_not_a_path() {
+    assert!(CameraPath::new(Vec::new(), false).is_err());
+    assert!(CameraPath::new(vec![keyframe(0.0, Vec3::new(0.0, 0.0, 5.0))], false).is_err());
+    assert!(CameraPath::parse("loop = true\n").is_err());
+    assert!(CameraPath::new(vec![keyframe(0.0, Vec3::new(0.0, 0.0, 5.0)), keyframe(f32::NAN, Vec3::new(5.0, 0.0, 0.0))], false).is_err());
+}
+
+#[test]
+fn repeated_and_unordered_times_are_sorted_and_nudged() {
+    let keyframes = vec![
+        keyframe(50.0, Vec3::new(0.0, 0.0, 5.0)),
+        keyframe(10.0, Vec3::new(5.0, 0.0, 0.0)),
+        keyframe(50.0, Vec3::new(0.0, 5.0, 1.0)),
+        keyframe(50.0, Vec3::new(-5.0, 0.0, 0.0)),
+    ];
+    let path = CameraPath::new(keyframes, false).unwrap();
+    let times: Vec<f32> = path.keyframes().iter().map(|keyframe| keyframe.time).collect();
+    assert_eq!(times, vec![10.0, 50.0, 50.0 + MIN_KEYFRAME_GAP, 50.0 + 2.0 * MIN_KEYFRAME_GAP]);
+    // El orden entre las del mismo tiempo es el de grabación
+    assert_eq!(path.keyframes()[1].eye, Vec3::new(0.0, 0.0, 5.0));
+    assert_eq!(path.keyframes()[3].eye, Vec3::new(-5.0, 0.0, 0.0));
+}
+
+#[test]
+fn passes_through_each_pose_and_clamps_or_loops_at_the_ends() {
+    let clamped = CameraPath::new(swoop(), false).unwrap();
+    for keyframe in swoop() {
+        let (eye, center) = clamped.pose(keyframe.time);
+        assert!((eye - keyframe.eye).magnitude() < EPSILON && center.magnitude() < EPSILON, "{:?} en lugar de {:?}", eye, keyframe.eye);
+    }
+    assert_eq!(clamped.pose(0.0), clamped.pose(100.0));
+    assert_eq!(clamped.pose(10000.0), clamped.pose(580.0));
+
+    let looping = CameraPath::new(swoop(), true).unwrap();
+    let period = looping.end() - looping.start();
+    for time in [130.0, 300.0, 555.0] {
+        let (expected, _) = looping.pose(time);
+        for laps in [-2.0, 1.0, 3.0] {
+            let (eye, _) = looping.pose(time + laps * period);
+            assert!((eye - expected).magnitude() < 1e-3, "vuelta {}: {:?} en lugar de {:?}", laps, eye, expected);
+        }
+    }
+}
+
+#[test]
+fn motion_is_smooth_through_the_poses() {
+    let path = CameraPath::new(swoop(), false).unwrap();
+    let eye = |time: f32| path.pose(time).0;
+    // La velocidad justo antes y justo después de cada pose del medio es casi la misma (sin quiebres)
+    let step = 0.5;
+    for keyframe in &swoop()[1..4] {
+        let before = (eye(keyframe.time) - eye(keyframe.time - step)) / step;
+        let after = (eye(keyframe.time + step) - eye(keyframe.time)) / step;
+        assert!((before - after).magnitude() < 0.1 * before.magnitude().max(after.magnitude()), "en {}: {:?} y {:?}", keyframe.time, before, after);
+    }
+    // Un frame por tick: ningún salto entre frames seguidos es mucho más grande que el promedio
+    let positions: Vec<Vec3> = (100..=580).map(|tick| eye(tick as f32)).collect();
+    let steps: Vec<f32> = positions.windows(2).map(|pair| (pair[1] - pair[0]).magnitude()).collect();
+    let average = steps.iter().sum::<f32>() / steps.len() as f32;
+    assert!(steps.iter().all(|&distance| distance < 3.0 * average), "salto máximo {:?}, promedio {}", steps.iter().cloned().fold(0.0, f32::max), average);
+}
+
+#[test]
+fn apply_only_marks_the_camera_when_it_moves() {
+    let path = CameraPath::new(swoop(), false).unwrap();
+    let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::zeros(), Vec3::y());
+    path.apply(&mut camera, 300.0);
+    assert_eq!((camera.eye, camera.center), path.pose(300.0));
+    assert!(camera.has_changed);
+
+    camera.has_changed = false;
+    path.apply(&mut camera, 300.0);
+    assert!(!camera.has_changed, "con el tiempo en pausa la cámara no cambia");
+}
@@ -0,0 +1,114 @@
+// Tablero de ajedrez: con la cámara quieta dos frames seguidos dan exactamente la imagen completa
+// (color, profundidad e ids), cada frame sombrea la mitad de los fragmentos, y con la cámara en
+// movimiento los pixeles sombreados son los mismos que sin el tablero
+use lab4_shaders::checkerboard::{is_shaded, CheckerFill, Checkerboard};
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::pipeline::{build_uniforms, FrameUniforms};
+use lab4_shaders::scene::{procedural_meshes, render_scene, scene_depth_range, start_camera, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+const TIME: f32 = 60.0;
+
+struct Setup {
+    number: u32,
+    scene: SceneConfig,
+    meshes: SceneMeshes,
+    uniforms: FrameUniforms,
+}
+
+impl Setup {
+    fn new(number: u32) -> Self {
+        let scene = SceneConfig::builtin(number);
+        let meshes = procedural_meshes();
+        let camera = start_camera(&scene, &meshes);
+        let state = SceneState::new(&scene, 0, &meshes);
+        let depth_range = scene_depth_range(&state.bodies_seen_from(&scene, TIME, camera.eye, &meshes), &meshes, &camera);
+        let uniforms = build_uniforms(&camera, TIME, WIDTH, HEIGHT, depth_range);
+        Setup { number, scene, meshes, uniforms }
+    }
+
+    // Frame con la paridad `parity` (None = sin tablero) y los fragmentos que llegaron a sombrearse
+    fn draw(&self, framebuffer: &mut Framebuffer, parity: Option<u32>) -> usize {
+        let mut state = SceneState::new(&self.scene, 0, &self.meshes);
+        state.update(TIME);
+        let uniforms = FrameUniforms { checkerboard: parity, ..self.uniforms.clone() };
+        let stats = render_scene(framebuffer, self.number, &self.scene, &uniforms, &self.meshes, &mut state, &RenderOptions::default());
+        stats.iter().map(|body| body.stats.fragments).sum()
+    }
+
+    fn framebuffer(&self) -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+        framebuffer.set_background_color(self.scene.background);
+        framebuffer
+    }
+}
+
+#[test]
+fn still_camera_converges_to_full_image_in_two_frames() {
+    for number in [2, 4, 8] {
+        let setup = Setup::new(number);
+        let mut full = setup.framebuffer();
+        let full_fragments = setup.draw(&mut full, None);
+
+        let mut checkerboard = Checkerboard::new();
+        let mut framebuffer = setup.framebuffer();
+        let mut fragments = Vec::new();
+        for frame in 0..2 {
+            fragments.push(setup.draw(&mut framebuffer, Some(checkerboard.parity())));
+            let fill = checkerboard.resolve(&mut framebuffer, frame == 0);
+            assert_eq!(fill, if frame == 0 { CheckerFill::Neighbors } else { CheckerFill::Previous });
+        }
+
+        assert!(framebuffer.buffer == full.buffer, "escena {}: los colores no son los de la imagen completa", number);
+        assert!(framebuffer.ids == full.ids, "escena {}: los ids no son los de la imagen completa", number);
+        let same_depth = framebuffer.zbuffer.iter().zip(&full.zbuffer).all(|(a, b)| a.to_bits() == b.to_bits());
+        assert!(same_depth, "escena {}: la profundidad no es la de la imagen completa", number);
+
+        // Entre los dos frames se sombrean los mismos fragmentos que en uno completo, mitad y mitad. Los
+        // puntos de la escena 8 (la cola del cometa, el cinturón) no pasan por el sombreado y se dibujan
+        // enteros en cada frame
+        if number == 8 {
+            continue;
+        }
+        assert_eq!(fragments.iter().sum::<usize>(), full_fragments, "escena {}", number);
+        for count in fragments {
+            let share = count as f32 / full_fragments as f32;
+            assert!((0.45..=0.55).contains(&share), "escena {}: un frame sombreó {:.2} de los fragmentos", number, share);
+        }
+    }
+}
+
+#[test]
+fn moving_camera_keeps_shaded_pixels_and_fills_the_rest_from_neighbors() {
+    let setup = Setup::new(4);
+    let mut full = setup.framebuffer();
+    setup.draw(&mut full, None);
+
+    // Aunque haya un frame anterior, con la cámara en movimiento se usan los vecinos
+    let mut checkerboard = Checkerboard::new();
+    let mut framebuffer = setup.framebuffer();
+    framebuffer.buffer.fill(0xff00ff);
+    checkerboard.resolve(&mut framebuffer, true);
+    let parity = checkerboard.parity();
+    setup.draw(&mut framebuffer, Some(parity));
+    assert_eq!(checkerboard.resolve(&mut framebuffer, true), CheckerFill::Neighbors);
+
+    let mut total_error = 0.0;
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let index = y * WIDTH + x;
+            if is_shaded(x, y, parity) {
+                assert_eq!(framebuffer.buffer[index], full.buffer[index], "pixel sombreado ({}, {}) distinto", x, y);
+            } else {
+                let [_, r, g, b] = framebuffer.buffer[index].to_be_bytes();
+                let [_, fr, fg, fb] = full.buffer[index].to_be_bytes();
+                total_error += (r.abs_diff(fr) as f32 + g.abs_diff(fg) as f32 + b.abs_diff(fb) as f32) / 3.0;
+            }
+        }
+    }
+    // Nada del magenta del frame anterior, y el promedio de los vecinos cerca del valor real
+    assert!(!framebuffer.buffer.contains(&0xff00ff));
+    let mean_error = total_error / (WIDTH * HEIGHT / 2) as f32;
+    assert!(mean_error < 4.0, "error medio de los pixeles completados {:.2}", mean_error);
+}
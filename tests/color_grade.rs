@@ -0,0 +1,48 @@
+// Corrección de color: con los valores por defecto la imagen queda idéntica bit a bit (con y sin
+// dithering), y una corrección casi neutra solo se aleja un paso de cuantización
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::post::{color_grade, ColorGrade};
+
+// Cada valor de rojo y verde combinado con un azul distinto en cada pixel
+fn gradient(dither: bool) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(256, 256);
+    framebuffer.dither = dither;
+    for y in 0..256u32 {
+        for x in 0..256u32 {
+            let blue = (x * 7 + y * 13) % 256;
+            framebuffer.buffer[(y * 256 + x) as usize] = x << 16 | y << 8 | blue;
+        }
+    }
+    framebuffer
+}
+
+#[test]
+fn the_default_grade_leaves_the_image_untouched() {
+    // Por código y leída de un archivo de escena sin ningún campo
+    let from_file: ColorGrade = toml::from_str("").unwrap();
+    for grade in [ColorGrade::default(), ColorGrade::NEUTRAL, from_file] {
+        assert!(grade.is_neutral());
+        for dither in [false, true] {
+            let original = gradient(dither);
+            let mut graded = gradient(dither);
+            color_grade(&mut graded, &grade);
+            assert!(graded.buffer == original.buffer, "la corrección neutra cambió la imagen (dithering {})", dither);
+        }
+    }
+}
+
+#[test]
+fn an_almost_neutral_grade_moves_each_channel_at_most_one_step() {
+    // Sin el atajo de la corrección neutra la imagen pasa por lineal y de vuelta; no tiene que dar saltos
+    // al activar una corrección mínima
+    let grade = ColorGrade { exposure: 1e-4, ..ColorGrade::NEUTRAL };
+    assert!(!grade.is_neutral());
+    let original = gradient(false);
+    let mut graded = gradient(false);
+    color_grade(&mut graded, &grade);
+    for (index, (before, after)) in original.buffer.iter().zip(&graded.buffer).enumerate() {
+        for (channel, (a, b)) in before.to_be_bytes().iter().zip(after.to_be_bytes()).enumerate() {
+            assert!(a.abs_diff(b) <= 1, "pixel {} canal {}: {} pasó a {}", index, channel, a, b);
+        }
+    }
+}
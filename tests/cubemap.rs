@@ -0,0 +1,101 @@
+// Cube map: un cuerpo chico y lejano justo en el borde entre las caras +X y +Z aparece en las dos pegado
+// al borde que comparten, en las mismas filas y sin hueco ni pixeles repetidos. Los pixeles encendidos
+// de las dos caras son los que tocaría un rayo hacia la esfera por el centro de cada pixel
+use nalgebra_glm::Vec3;
+use lab4_shaders::color::Color;
+use lab4_shaders::cubemap::{render_cubemap, CUBE_FACES};
+use lab4_shaders::fragments::Fragments;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::mesh::{generate_ring, generate_uv_sphere, RING_INNER_RADIUS, RING_OUTER_RADIUS};
+use lab4_shaders::pipeline::{BodyUniforms, FrameUniforms};
+use lab4_shaders::scene::{BodyConfig, MeshKind, RenderOptions, SceneConfig, SceneMeshes};
+use lab4_shaders::shaders::{FragmentShader, SceneShader};
+
+const SIZE: usize = 96;
+const POSITIVE_X: usize = 0;
+const POSITIVE_Z: usize = 4;
+const DISTANCE: f32 = 40.0;
+
+// Color fijo, sin luz, para que cualquier pixel del cuerpo se distinga del fondo
+struct White;
+
+impl FragmentShader for White {
+    fn shade(&self, _fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+        Color::new(255, 255, 255)
+    }
+}
+
+struct Seam {
+    faces: [Framebuffer; 6],
+    center: Vec3,
+    radius: f32,
+}
+
+impl Seam {
+    // Una esfera que se ve de 8° de ancho, apenas sobre el horizonte y a mitad de camino entre +X y +Z
+    fn render() -> Self {
+        let center = Vec3::new(1.0, 0.2, 1.0).normalize() * DISTANCE;
+        let radius = DISTANCE * 4.0f32.to_radians().tan();
+        let meshes = SceneMeshes::new(vec![generate_uv_sphere(32, 64)], generate_ring(RING_INNER_RADIUS, RING_OUTER_RADIUS, 64));
+        let body = BodyConfig::new("Punto", SceneShader::custom(White), MeshKind::Sphere, center, radius / meshes.radius(MeshKind::Sphere));
+        let faces = render_cubemap(&SceneConfig::new(vec![body]), &meshes, Vec3::zeros(), SIZE, 0.0, &RenderOptions::default());
+        Seam { faces, center, radius }
+    }
+
+    fn lit(&self, face: usize, x: usize, y: usize) -> bool {
+        // La cara de -X no ve el cuerpo, así que su primer pixel es el fondo
+        self.faces[face].buffer[y * SIZE + x] != self.faces[1].buffer[0]
+    }
+
+    // Lo que tocaría un rayo desde el centro del cube map por el centro del pixel
+    fn expected(&self, face: usize, x: usize, y: usize) -> bool {
+        let direction = CUBE_FACES[face].pixel_direction(x as f32 + 0.5, y as f32 + 0.5, SIZE);
+        let along = self.center.dot(&direction);
+        along > 0.0 && (self.center - direction * along).magnitude() < self.radius
+    }
+
+    fn lit_rows(&self, face: usize, x: usize) -> Vec<usize> {
+        (0..SIZE).filter(|&y| self.lit(face, x, y)).collect()
+    }
+}
+
+#[test]
+fn lit_pixels_match_a_ray_through_each_pixel() {
+    let seam = Seam::render();
+    let mut mismatched = 0;
+    let mut expected = 0;
+    for face in [POSITIVE_X, POSITIVE_Z] {
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                mismatched += (seam.lit(face, x, y) != seam.expected(face, x, y)) as usize;
+                expected += seam.expected(face, x, y) as usize;
+            }
+        }
+    }
+    // El contorno rasterizado puede diferir del rayo en algún pixel del borde, no más del 5 %
+    assert!(expected > 0);
+    assert!(mismatched * 20 <= expected, "{} pixeles distintos al rayo de {}", mismatched, expected);
+}
+
+#[test]
+fn the_body_continues_across_the_shared_edge() {
+    let seam = Seam::render();
+    // En +X el borde con +Z es la columna de la izquierda y en +Z la de la derecha
+    let (left_rows, right_rows) = (seam.lit_rows(POSITIVE_X, 0), seam.lit_rows(POSITIVE_Z, SIZE - 1));
+    assert!(!left_rows.is_empty(), "el cuerpo no llega al borde de +X");
+    assert!(left_rows.len().abs_diff(right_rows.len()) <= 1, "filas {:?} y {:?}", left_rows, right_rows);
+    assert!(left_rows[0].abs_diff(right_rows[0]) <= 1, "filas {:?} y {:?}", left_rows, right_rows);
+
+    // A lo ancho de la fila del medio, sumando las dos caras, no falta ni sobra más de un pixel
+    let middle = left_rows[left_rows.len() / 2];
+    let width = |check: &dyn Fn(usize, usize) -> bool| [POSITIVE_X, POSITIVE_Z].iter().map(|&face| (0..SIZE).filter(|&x| check(face, x)).count()).sum::<usize>();
+    let (lit, expected) = (width(&|face, x| seam.lit(face, x, middle)), width(&|face, x| seam.expected(face, x, middle)));
+    assert!(lit.abs_diff(expected) <= 1, "ancho {} (esperado {})", lit, expected);
+}
+
+#[test]
+fn nothing_reaches_the_outer_edges() {
+    let seam = Seam::render();
+    assert_eq!(seam.lit_rows(POSITIVE_X, SIZE - 1), Vec::<usize>::new());
+    assert_eq!(seam.lit_rows(POSITIVE_Z, 0), Vec::<usize>::new());
+}
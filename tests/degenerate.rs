@@ -0,0 +1,94 @@
+// Geometría difícil: un OBJ sin normales se dibuja sin un solo fragmento NaN, y un triángulo sin área
+// no entra en pánico ni genera fragmentos
+use nalgebra_glm::{Vec2, Vec3};
+use lab4_shaders::camera::Camera;
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::obj_loader::Obj;
+use lab4_shaders::pipeline::{build_uniforms, create_model_matrix, render, BodyUniforms, FragmentBudget, FrameUniforms, RenderScratch, RenderStats, DEFAULT_DEPTH_RANGE};
+use lab4_shaders::shaders::ShaderType;
+use lab4_shaders::triangle::triangle;
+use lab4_shaders::vertex::Vertex;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+const WIDTH: usize = 64;
+const HEIGHT: usize = 48;
+const AMBIENT: Color = Color::new(40, 40, 40);
+
+fn frame() -> FrameUniforms {
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::y());
+    let mut frame = build_uniforms(&camera, 0.0, WIDTH, HEIGHT, DEFAULT_DEPTH_RANGE);
+    frame.ambient = AMBIENT;
+    frame
+}
+
+fn draw(framebuffer: &mut Framebuffer, body: &BodyUniforms, vertices: &[Vertex], shader: ShaderType) -> RenderStats {
+    let mut stats = RenderStats::default();
+    render(framebuffer, &frame(), body, vertices, &shader, &mut FragmentBudget::default(), &mut stats, &mut RenderScratch::new());
+    stats
+}
+
+#[test]
+fn an_obj_without_normals_gets_outward_normals() {
+    let obj = Obj::load(&format!("{}/no_normals.obj", FIXTURES)).expect("no se pudo leer el octaedro");
+    let vertices = obj.get_vertex_array();
+    assert_eq!(vertices.len(), 9 * 3);
+    for vertex in &vertices {
+        assert!((vertex.normal.magnitude() - 1.0).abs() < 1e-5, "normal {:?} en {:?}", vertex.normal, vertex.position);
+        // En las puntas la normal suave es la dirección de la punta; el vértice que solo está en la cara
+        // sin área no tiene caras que sumar y queda con la normal por omisión
+        if vertex.position.magnitude() > 0.99 {
+            assert!((vertex.normal - vertex.position).magnitude() < 1e-5, "normal {:?} en {:?}", vertex.normal, vertex.position);
+        }
+    }
+}
+
+#[test]
+fn an_obj_without_normals_renders_without_nan() {
+    let vertices = Obj::load(&format!("{}/no_normals.obj", FIXTURES)).unwrap().get_vertex_array();
+    for rotation in [Vec3::zeros(), Vec3::new(0.4, 0.7, 0.0), Vec3::new(1.2, -2.0, 0.3)] {
+        let body = BodyUniforms::new(create_model_matrix(Vec3::zeros(), Vec3::repeat(1.5), rotation));
+        for shader in [ShaderType::Flat, ShaderType::Earth, ShaderType::Moon, ShaderType::Triplanar] {
+            let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+            let stats = draw(&mut framebuffer, &body, &vertices, shader);
+            assert!(stats.depth_passed > 0, "{:?} {:?}: no se dibujó nada", shader, rotation);
+            assert_eq!(stats.invalid_fragments, 0, "{:?} {:?}", shader, rotation);
+            assert!(framebuffer.zbuffer.iter().all(|depth| !depth.is_nan()), "{:?} {:?}: profundidad NaN", shader, rotation);
+
+            // Un color NaN se escribe como negro; con luz ambiental ningún pixel plano queda negro
+            if shader == ShaderType::Flat {
+                for (index, depth) in framebuffer.zbuffer.iter().enumerate() {
+                    let (color, _) = framebuffer.get_pixel(index % WIDTH, index / WIDTH).unwrap();
+                    assert!(depth.is_infinite() || color.r > 0.0, "{:?}: pixel {} negro", rotation, index);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn degenerate_triangles_emit_no_fragments() {
+    let point = |x: f32, y: f32, z: f32| Vertex::new(Vec3::new(x, y, z), Vec3::z(), Vec2::zeros());
+    let cases = [
+        ("un solo punto", [point(0.3, 0.2, 0.0), point(0.3, 0.2, 0.0), point(0.3, 0.2, 0.0)]),
+        ("dos vértices iguales", [point(-1.0, -1.0, 0.0), point(1.0, 1.0, 0.0), point(1.0, 1.0, 0.0)]),
+        ("alineados", [point(-1.0, -1.0, 0.0), point(0.0, 0.0, 0.0), point(1.0, 1.0, 0.0)]),
+        // Un triángulo con área en el mundo que se ve de canto desde la cámara
+        ("de canto", [point(-1.0, 0.0, -1.0), point(1.0, 0.0, -1.0), point(0.0, 0.0, 1.0)]),
+        ("normal en cero", [point(-1.0, -1.0, 0.0), point(0.0, 0.0, 0.0), point(1.0, 1.0, 0.0)].map(|vertex| Vertex { normal: Vec3::zeros(), ..vertex })),
+    ];
+
+    for (name, vertices) in cases {
+        let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+        let stats = draw(&mut framebuffer, &BodyUniforms::default(), &vertices, ShaderType::Flat);
+        assert_eq!((stats.triangles, stats.fragments, stats.depth_passed), (1, 0, 0), "{}", name);
+        assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0), "{}", name);
+        assert!(framebuffer.zbuffer.iter().all(|depth| depth.is_infinite()), "{}", name);
+    }
+
+    // Lo mismo ya en pantalla, con los vértices justo sobre los centros de los pixeles
+    let at = |x: f32, y: f32| Vertex { transformed_position: Vec3::new(x, y, 0.5), ..point(0.0, 0.0, 0.0) };
+    let clip = Framebuffer::new(WIDTH, HEIGHT).rect();
+    assert!(triangle(&at(10.5, 10.5), &at(20.5, 20.5), &at(30.5, 30.5), &clip).is_empty());
+    assert!(triangle(&at(10.5, 10.5), &at(10.5, 10.5), &at(30.5, 12.5), &clip).is_empty());
+}
@@ -0,0 +1,48 @@
+// Niebla por distancia: antes de `start` el color no cambia, a mitad de camino queda justo entre los dos
+// colores y desde `end` en adelante solo se ve la niebla
+use lab4_shaders::color::Color;
+use lab4_shaders::pipeline::FogParams;
+
+const EPSILON: f32 = 1e-4;
+
+const FOG: FogParams = FogParams { color: Color::new(0, 100, 200), start: 10.0, end: 30.0 };
+
+fn assert_color(actual: Color, expected: Color, what: &str) {
+    let difference = (actual.r - expected.r).abs() + (actual.g - expected.g).abs() + (actual.b - expected.b).abs();
+    assert!(difference < EPSILON, "{}: {:?} en lugar de {:?}", what, actual, expected);
+}
+
+#[test]
+fn fog_starts_clear_and_ends_covering_everything() {
+    let color = Color::new(200, 50, 0);
+    for depth in [-5.0, 0.0, 10.0] {
+        assert_eq!(FOG.amount(depth), 0.0, "a {}", depth);
+        assert_color(FOG.apply(color, depth), color, "antes del inicio");
+    }
+    for depth in [30.0, 31.0, 1000.0, f32::INFINITY] {
+        assert_eq!(FOG.amount(depth), 1.0, "a {}", depth);
+        assert_color(FOG.apply(color, depth), FOG.color, "después del final");
+    }
+}
+
+#[test]
+fn fog_mixes_half_and_half_at_the_midpoint() {
+    // La curva es suave (smoothstep) pero simétrica: a mitad de camino tapa exactamente la mitad
+    let color = Color::new(200, 50, 0);
+    assert!((FOG.amount(20.0) - 0.5).abs() < EPSILON);
+    assert_color(FOG.apply(color, 20.0), Color::new(100, 75, 100), "a mitad de camino");
+
+    // Crece sin saltos entre el inicio y el final
+    let amounts: Vec<f32> = (10..=30).map(|depth| FOG.amount(depth as f32)).collect();
+    assert!(amounts.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", amounts);
+    assert!(FOG.amount(15.0) < 0.25 && FOG.amount(25.0) > 0.75);
+}
+
+#[test]
+fn fog_without_width_is_a_hard_cut() {
+    // Con start == end no se divide por cero: todo lo que pasa de esa distancia queda en niebla
+    let fog = FogParams { start: 20.0, end: 20.0, ..FOG };
+    assert_eq!(fog.amount(19.9), 0.0);
+    assert_eq!(fog.amount(20.1), 1.0);
+    assert!(fog.amount(20.0).is_finite());
+}
@@ -0,0 +1,54 @@
+// Lectura del framebuffer: as_rgb_bytes va fila por fila de arriba hacia abajo con los bytes en orden R,
+// G, B, la profundidad en el mismo orden, y get_pixel fuera del buffer da None en lugar de otro pixel
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::Framebuffer;
+
+// Dibuja cada pixel con point, como el pipeline, con un color y una profundidad propios
+fn paint(width: usize, height: usize, pixels: &[(usize, usize, Color, f32)]) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(width, height);
+    framebuffer.dither = false;
+    for &(x, y, color, depth) in pixels {
+        framebuffer.set_current_color(color);
+        assert!(framebuffer.point(x, y, depth));
+    }
+    framebuffer
+}
+
+#[test]
+fn rgb_bytes_go_row_by_row_in_rgb_order() {
+    let framebuffer = paint(2, 2, &[
+        (0, 0, Color::new(1, 2, 3), 0.1),
+        (1, 0, Color::new(4, 5, 6), 0.2),
+        (0, 1, Color::new(7, 8, 9), 0.3),
+        (1, 1, Color::new(10, 11, 12), 0.4),
+    ]);
+    assert_eq!(framebuffer.as_rgb_bytes(), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+    assert_eq!(framebuffer.depth_as_f32_slice(), [0.1, 0.2, 0.3, 0.4]);
+}
+
+#[test]
+fn rgb_bytes_of_a_wide_buffer_have_no_row_padding() {
+    // Más ancho que alto, para que cambiar ancho por alto no dé lo mismo; el fondo queda en negro
+    let framebuffer = paint(3, 2, &[
+        (2, 0, Color::new(255, 0, 0), 0.5),
+        (0, 1, Color::new(0, 255, 0), 0.5),
+        (2, 1, Color::new(0, 0, 255), 0.5),
+    ]);
+    assert_eq!(framebuffer.as_rgb_bytes(), [
+        0, 0, 0, 0, 0, 0, 255, 0, 0,
+        0, 255, 0, 0, 0, 0, 0, 0, 255,
+    ]);
+    assert_eq!(framebuffer.get_pixel(2, 0), Some((Color::new(255, 0, 0), 0.5)));
+    assert_eq!(framebuffer.get_pixel(1, 1), Some((Color::BLACK, f32::INFINITY)));
+}
+
+#[test]
+fn reading_outside_the_buffer_gives_none() {
+    let framebuffer = paint(3, 2, &[(2, 1, Color::new(9, 9, 9), 0.5)]);
+    // Justo afuera de cada borde: ni la fila siguiente ni el primer pixel de la otra fila
+    for (x, y) in [(3, 0), (3, 1), (0, 2), (2, 2), (usize::MAX, 0), (0, usize::MAX)] {
+        assert_eq!(framebuffer.get_pixel(x, y), None, "({}, {})", x, y);
+        assert_eq!(framebuffer.id_at(x, y), None, "({}, {})", x, y);
+    }
+    assert_eq!(framebuffer.get_pixel(2, 1), Some((Color::new(9, 9, 9), 0.5)));
+}
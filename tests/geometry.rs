@@ -0,0 +1,263 @@
+// Primitivas de geometry.rs, con los casos incómodos: el rayo que sale de adentro, el paralelo a una cara
+// de la caja, las cajas sin volumen y las transformaciones con escala negativa
+use nalgebra_glm::{Mat4, Vec3};
+use lab4_shaders::geometry::{closest_point_on_ray, Aabb, BoundingSphere, Frustum, Ray};
+use lab4_shaders::mesh::MeshBounds;
+use lab4_shaders::obj_loader::Obj;
+use lab4_shaders::camera::Camera;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::pipeline::{build_uniforms, create_model_matrix, create_perspective_matrix, create_view_matrix, project};
+use lab4_shaders::scene::{pick_body, procedural_meshes, render_scene, scene_depth_range, RenderOptions, SceneConfig, SceneState};
+
+const EPSILON: f32 = 1e-5;
+
+fn assert_close(actual: Vec3, expected: Vec3, what: &str) {
+    assert!((actual - expected).magnitude() < EPSILON, "{}: {:?} en lugar de {:?}", what, actual, expected);
+}
+
+fn assert_hit(hit: Option<f32>, expected: f32, what: &str) {
+    assert!(hit.is_some_and(|t| (t - expected).abs() < EPSILON), "{}: {:?} en lugar de {}", what, hit, expected);
+}
+
+fn unit_box() -> Aabb {
+    Aabb::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))
+}
+
+#[test]
+fn aabb_from_points_union_and_degenerate_boxes() {
+    let points = [Vec3::new(1.0, -2.0, 0.5), Vec3::new(-3.0, 4.0, 0.5), Vec3::new(0.0, 0.0, 2.0)];
+    let aabb = Aabb::from_points(points).unwrap();
+    assert_eq!(aabb, Aabb { min: Vec3::new(-3.0, -2.0, 0.5), max: Vec3::new(1.0, 4.0, 2.0) });
+    assert!(points.iter().all(|&point| aabb.contains(point)));
+    assert!(Aabb::from_points([]).is_none());
+    // Las esquinas al revés se ordenan
+    assert_eq!(Aabb::new(aabb.max, aabb.min), aabb);
+
+    let other = Aabb::new(Vec3::new(5.0, 5.0, 5.0), Vec3::new(6.0, 6.0, 6.0));
+    let union = aabb.union(&other);
+    assert_eq!(union, Aabb { min: Vec3::new(-3.0, -2.0, 0.5), max: Vec3::new(6.0, 6.0, 6.0) });
+    assert_eq!(union, other.union(&aabb));
+
+    // Un solo punto es una caja sin volumen que igual se puede unir y contiene a ese punto
+    let point = Aabb::from_points([Vec3::new(2.0, 2.0, 2.0)]).unwrap();
+    assert_eq!(point.half_extents(), Vec3::zeros());
+    assert!(point.contains(Vec3::new(2.0, 2.0, 2.0)));
+    assert!(!point.contains(Vec3::new(2.0, 2.0, 2.001)));
+    assert_eq!(point.union(&unit_box()).max, Vec3::new(2.0, 2.0, 2.0));
+}
+
+#[test]
+fn aabb_transform_rotates_scales_and_mirrors() {
+    let aabb = Aabb::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(2.0, 1.0, 1.0));
+
+    let moved = aabb.transformed(&create_model_matrix(Vec3::new(1.0, 2.0, 3.0), Vec3::repeat(1.0), Vec3::zeros()));
+    assert_close(moved.min, Vec3::new(1.0, 2.0, 3.0), "traslación");
+    assert_close(moved.max, Vec3::new(3.0, 3.0, 4.0), "traslación");
+
+    // Girada 45° alrededor de z la caja de 2 x 1 ocupa (2 + 1) / √2 en x e y
+    let rotation = create_model_matrix(Vec3::zeros(), Vec3::repeat(1.0), Vec3::new(0.0, 0.0, std::f32::consts::FRAC_PI_4));
+    let rotated = aabb.transformed(&rotation);
+    let half = 1.5 / 2.0f32.sqrt();
+    assert_close(rotated.half_extents(), Vec3::new(half, half, 0.5), "rotación");
+    let corners = [0.0, 2.0].iter().flat_map(|&x| [0.0, 1.0].iter().flat_map(move |&y| [0.0, 1.0].map(|z| Vec3::new(x, y, z))));
+    for corner in corners {
+        let moved = (rotation * corner.push(1.0)).xyz();
+        let grown = Aabb::new(rotated.min - Vec3::repeat(EPSILON), rotated.max + Vec3::repeat(EPSILON));
+        assert!(grown.contains(moved), "la esquina {:?} quedó afuera de {:?}", moved, rotated);
+    }
+
+    // Con escala negativa la caja se refleja pero min sigue por debajo de max
+    let mirrored = aabb.transformed(&create_model_matrix(Vec3::zeros(), Vec3::new(-2.0, 1.0, 1.0), Vec3::zeros()));
+    assert_close(mirrored.min, Vec3::new(-4.0, 0.0, 0.0), "reflejo");
+    assert_close(mirrored.max, Vec3::new(0.0, 1.0, 1.0), "reflejo");
+
+    // Una caja plana sigue siendo plana si el giro no la saca de su plano
+    let flat = Aabb::new(Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 0.0, 1.0));
+    let spun = flat.transformed(&create_model_matrix(Vec3::zeros(), Vec3::repeat(1.0), Vec3::new(0.0, 1.0, 0.0)));
+    assert!(spun.half_extents().y.abs() < EPSILON);
+}
+
+#[test]
+fn bounding_sphere_from_points_merge_and_transform() {
+    let points = [Vec3::new(-1.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 0.0)];
+    let sphere = BoundingSphere::from_points(points).unwrap();
+    assert_close(sphere.center, Vec3::new(1.0, 0.5, 0.0), "centro de la caja");
+    assert!(points.iter().all(|&point| sphere.contains(point)));
+    assert!(BoundingSphere::from_points([]).is_none());
+    assert_eq!(BoundingSphere::around(Vec3::zeros(), []).radius, 0.0);
+    assert!((BoundingSphere::around(Vec3::zeros(), points).radius - 3.0).abs() < EPSILON);
+
+    // Una adentro de la otra queda la grande, en cualquier orden
+    let big = BoundingSphere::new(Vec3::zeros(), 5.0);
+    let small = BoundingSphere::new(Vec3::new(1.0, 0.0, 0.0), 1.0);
+    assert_eq!(big.merge(&small), big);
+    assert_eq!(small.merge(&big), big);
+    // Separadas, la nueva toca a las dos por afuera
+    let left = BoundingSphere::new(Vec3::new(-2.0, 0.0, 0.0), 1.0);
+    let right = BoundingSphere::new(Vec3::new(3.0, 0.0, 0.0), 2.0);
+    let merged = left.merge(&right);
+    assert_close(merged.center, Vec3::new(1.0, 0.0, 0.0), "centro de la unión");
+    assert!((merged.radius - 4.0).abs() < EPSILON);
+    // Dos iguales en el mismo lugar no dividen por cero
+    assert_eq!(small.merge(&small), small);
+
+    // El radio crece con el eje que más se estira, también si la escala es negativa
+    let matrix = create_model_matrix(Vec3::new(0.0, 1.0, 0.0), Vec3::new(-3.0, 1.0, 0.5), Vec3::new(0.3, 0.2, 0.1));
+    let transformed = small.transformed(&matrix);
+    assert_close(transformed.center, (matrix * small.center.push(1.0)).xyz(), "centro transformado");
+    assert!((transformed.radius - 3.0).abs() < EPSILON, "radio {}", transformed.radius);
+}
+
+#[test]
+fn ray_sphere_hits_from_outside_inside_and_behind() {
+    let sphere = BoundingSphere::new(Vec3::new(0.0, 0.0, -5.0), 1.0);
+    let forward = Ray::new(Vec3::zeros(), Vec3::new(0.0, 0.0, -1.0));
+    assert_hit(forward.intersect_sphere(&sphere), 4.0, "de frente");
+    // Los t están en unidades del largo de dir
+    assert_hit(Ray::new(Vec3::zeros(), Vec3::new(0.0, 0.0, -2.0)).intersect_sphere(&sphere), 2.0, "dir de largo 2");
+    // Desde adentro, el punto por donde sale
+    assert_hit(Ray::new(sphere.center, Vec3::new(1.0, 0.0, 0.0)).intersect_sphere(&sphere), 1.0, "desde el centro");
+    assert_hit(Ray::new(Vec3::new(0.0, 0.0, -5.5), Vec3::new(0.0, 0.0, -1.0)).intersect_sphere(&sphere), 0.5, "desde adentro");
+    // Tangente, detrás del origen, sin tocarla y sin dirección
+    assert_hit(Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)).intersect_sphere(&sphere), 5.0, "tangente");
+    assert_eq!(Ray::new(Vec3::zeros(), Vec3::new(0.0, 0.0, 1.0)).intersect_sphere(&sphere), None);
+    assert_eq!(Ray::new(Vec3::new(1.01, 0.0, 0.0), Vec3::new(0.0, 0.0, -1.0)).intersect_sphere(&sphere), None);
+    assert_eq!(Ray::new(Vec3::zeros(), Vec3::zeros()).intersect_sphere(&sphere), None);
+    // Sin radio es un punto
+    assert_hit(forward.intersect_sphere(&BoundingSphere::new(sphere.center, 0.0)), 5.0, "radio cero");
+}
+
+#[test]
+fn ray_aabb_slabs_handle_parallel_rays_and_flat_boxes() {
+    let aabb = unit_box();
+    assert_hit(Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).intersect_aabb(&aabb), 4.0, "de frente");
+    assert_hit(Ray::new(Vec3::new(-5.0, -5.0, 0.0), Vec3::new(1.0, 1.0, 0.0)).intersect_aabb(&aabb), 4.0, "por la arista");
+    assert_hit(Ray::new(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0)).intersect_aabb(&aabb), 1.0, "desde adentro");
+    assert_eq!(Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0)).intersect_aabb(&aabb), None);
+    assert_eq!(Ray::new(Vec3::new(-5.0, 0.0, 0.0), Vec3::new(1.0, 2.0, 0.0)).intersect_aabb(&aabb), None);
+
+    // Paralelo a las caras de y: pega si está entre ellas (o justo sobre una), si no nunca
+    assert_hit(Ray::new(Vec3::new(-5.0, 0.5, 0.5), Vec3::new(1.0, 0.0, 0.0)).intersect_aabb(&aabb), 4.0, "paralelo adentro");
+    assert_hit(Ray::new(Vec3::new(-5.0, 1.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).intersect_aabb(&aabb), 4.0, "paralelo sobre la cara");
+    assert_eq!(Ray::new(Vec3::new(-5.0, 1.5, 0.0), Vec3::new(1.0, 0.0, 0.0)).intersect_aabb(&aabb), None);
+    assert_eq!(Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::zeros()).intersect_aabb(&aabb), None);
+
+    // Cajas sin volumen: un plano y un punto
+    let flat = Aabb::new(Vec3::new(-1.0, 0.0, -1.0), Vec3::new(1.0, 0.0, 1.0));
+    assert_hit(Ray::new(Vec3::new(0.5, 3.0, 0.5), Vec3::new(0.0, -1.0, 0.0)).intersect_aabb(&flat), 3.0, "caja plana");
+    assert_eq!(Ray::new(Vec3::new(-5.0, 0.1, 0.0), Vec3::new(1.0, 0.0, 0.0)).intersect_aabb(&flat), None);
+    let point = Aabb::new(Vec3::new(2.0, 0.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+    assert_hit(Ray::new(Vec3::zeros(), Vec3::new(1.0, 0.0, 0.0)).intersect_aabb(&point), 2.0, "caja punto");
+    assert_eq!(Ray::new(Vec3::zeros(), Vec3::new(1.0, 0.001, 0.0)).intersect_aabb(&point), None);
+}
+
+#[test]
+fn ray_plane_and_closest_point() {
+    let ray = Ray::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, -1.0, 0.0));
+    assert_hit(ray.intersect_plane(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0)), 2.0, "plano del piso");
+    // La orientación de la normal no importa
+    assert_hit(ray.intersect_plane(Vec3::zeros(), Vec3::new(0.0, -3.0, 0.0)), 2.0, "normal al revés");
+    assert_close(ray.at(2.0), Vec3::new(2.0, 0.0, 0.0), "punto del cruce");
+    assert_eq!(ray.intersect_plane(Vec3::new(0.0, 5.0, 0.0), Vec3::new(0.0, 1.0, 0.0)), None, "plano detrás");
+    assert_eq!(Ray::new(Vec3::new(0.0, 2.0, 0.0), Vec3::new(1.0, 0.0, 0.0)).intersect_plane(Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0)), None, "paralelo");
+    assert_hit(Ray::new(Vec3::zeros(), Vec3::new(1.0, 0.0, 0.0)).intersect_plane(Vec3::zeros(), Vec3::new(1.0, 0.0, 0.0)), 0.0, "origen en el plano");
+
+    let ray = Ray::new(Vec3::new(1.0, 1.0, 0.0), Vec3::new(2.0, 0.0, 0.0));
+    assert_close(closest_point_on_ray(&ray, Vec3::new(4.0, 3.0, 7.0)), Vec3::new(4.0, 1.0, 0.0), "punto de costado");
+    assert_close(closest_point_on_ray(&ray, Vec3::new(-4.0, 3.0, 0.0)), ray.origin, "punto detrás");
+    assert_close(closest_point_on_ray(&Ray::new(ray.origin, Vec3::zeros()), Vec3::new(4.0, 3.0, 7.0)), ray.origin, "sin dirección");
+}
+
+#[test]
+fn frustum_excludes_only_spheres_fully_outside() {
+    let view = create_view_matrix(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+    let projection = create_perspective_matrix(800.0, 600.0, 0.1, 100.0);
+    let frustum = Frustum::new(&(projection * view));
+
+    // La que rodea al ojo llega a pasar el plano cercano
+    let inside = [(Vec3::zeros(), 1.0), (Vec3::new(0.0, 0.0, 5.0), 0.2), (Vec3::new(0.0, 0.0, -94.0), 1.0)];
+    for (center, radius) in inside {
+        assert!(!frustum.excludes(&BoundingSphere::new(center, radius)), "se descartó la esfera en {:?}", center);
+    }
+    // Detrás de la cámara, más allá del plano lejano y muy a un costado; la del costado que llega a asomarse no
+    let outside = [(Vec3::new(0.0, 0.0, 8.0), 1.0), (Vec3::new(0.0, 0.0, -110.0), 1.0), (Vec3::new(30.0, 0.0, 0.0), 1.0)];
+    for (center, radius) in outside {
+        assert!(frustum.excludes(&BoundingSphere::new(center, radius)), "no se descartó la esfera en {:?}", center);
+    }
+    assert!(!frustum.excludes(&BoundingSphere::new(Vec3::new(30.0, 0.0, 0.0), 28.0)));
+    assert!(!frustum.excludes(&BoundingSphere::new(Vec3::new(0.0, 0.0, 8.0), 5.0)));
+}
+
+#[test]
+fn obj_and_mesh_bounds_are_cached_per_part() {
+    let obj = Obj::parse("\
+o a
+v 0 0 0
+v 2 0 0
+v 0 1 0
+f 1 2 3
+o b
+v 0 0 -3
+v 1 0 -3
+v 0 1 -3
+f 4 5 6
+").unwrap();
+    let bounds = obj.bounds();
+    assert_eq!(bounds.aabb, Aabb::new(Vec3::new(0.0, 0.0, -3.0), Vec3::new(2.0, 1.0, 0.0)));
+    // La esfera está centrada en el origen del modelo, no en el de la caja
+    assert_eq!(bounds.sphere.center, Vec3::zeros());
+    assert!((bounds.sphere.radius - 10.0f32.sqrt()).abs() < EPSILON);
+    assert_eq!(bounds, MeshBounds::of(&obj.get_vertex_array()));
+    assert_eq!(MeshBounds::of(&[]).sphere.radius, 0.0);
+}
+
+#[test]
+fn identity_transform_keeps_bounds() {
+    let aabb = Aabb::new(Vec3::new(-1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0));
+    assert_eq!(aabb.transformed(&Mat4::identity()), aabb);
+    let sphere = BoundingSphere::new(Vec3::new(1.0, 2.0, 3.0), 4.0);
+    assert_eq!(sphere.transformed(&Mat4::identity()), sphere);
+}
+
+#[test]
+fn scene_queries_use_the_body_spheres() {
+    let meshes = procedural_meshes();
+    let scene = SceneConfig::builtin(8);
+    let bodies = scene.bodies(600.0);
+    for body in &bodies {
+        let sphere = body.bounding_sphere(&meshes);
+        let transformed = meshes.bounds(body.mesh).sphere.transformed(&body.model_matrix());
+        assert_close(sphere.center, transformed.center, &body.name);
+        assert!(sphere.radius >= transformed.radius * (1.0 - EPSILON), "{}: radio {} menor que {}", body.name, sphere.radius, transformed.radius);
+    }
+
+    // El rayo por el pixel del centro de un cuerpo lo elige a él
+    let scene = SceneConfig::builtin(2);
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 6.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0));
+    let uniforms = build_uniforms(&camera, 0.0, 160, 120, (0.5, 50.0));
+    let bodies = scene.bodies(0.0);
+    let mut checked = 0;
+    for (index, body) in bodies.iter().enumerate().filter(|(_, body)| body.apparent_size.is_none()) {
+        let Some(screen) = project(&uniforms, body.translation).filter(|screen| (0.0..160.0).contains(&screen.x) && (0.0..120.0).contains(&screen.y)) else {
+            continue;
+        };
+        let picked = pick_body(&bodies, &meshes, &uniforms, screen.x.floor(), screen.y.floor());
+        let nearer = picked.is_some_and(|picked| picked == index || (bodies[picked].translation - camera.eye).magnitude() < (body.translation - camera.eye).magnitude());
+        assert!(nearer, "en el centro de {} se eligió {:?}", body.name, picked);
+        checked += 1;
+    }
+    assert!(checked > 0, "ningún cuerpo quedó en la pantalla");
+
+    // Mirando para el otro lado no se dibuja nada y todos los triángulos cuentan como recortados
+    let away = Camera::new(Vec3::new(0.0, 0.0, 6.0), Vec3::new(0.0, 0.0, 12.0), Vec3::new(0.0, 1.0, 0.0));
+    let mut state = SceneState::new(&scene, 0, &meshes);
+    let depth_range = scene_depth_range(&state.bodies_seen_from(&scene, 0.0, away.eye, &meshes), &meshes, &away);
+    let uniforms = build_uniforms(&away, 0.0, 160, 120, depth_range);
+    let mut framebuffer = Framebuffer::new(160, 120);
+    let stats = render_scene(&mut framebuffer, 2, &scene, &uniforms, &meshes, &mut state, &RenderOptions::default());
+    for body in stats.iter().filter(|body| body.stats.vertices == 0) {
+        assert_eq!(body.stats.fragments, 0, "{}", body.name);
+        assert!(body.stats.culled_triangles > 0, "{}", body.name);
+    }
+    assert!(stats.iter().any(|body| body.stats.vertices == 0), "no se descartó ningún cuerpo entero");
+}
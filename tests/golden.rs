@@ -0,0 +1,25 @@
+// Compara cada caso de lab4_shaders::golden con su imagen de referencia en tests/golden.
+// Después de un cambio buscado en los shaders: BLESS=1 cargo test golden
+use std::path::Path;
+use lab4_shaders::golden::{run_golden, GOLDEN_DIR};
+use lab4_shaders::scene::load_meshes;
+
+#[test]
+fn golden_images() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Las mallas se leen desde models/ con rutas relativas
+    std::env::set_current_dir(root).expect("no se pudo entrar a la carpeta del proyecto");
+    let bless = std::env::var("BLESS").is_ok_and(|value| value == "1");
+    let meshes = load_meshes(false, None);
+
+    let results = run_golden(&meshes, &root.join(GOLDEN_DIR), bless);
+    for result in &results {
+        if result.passed {
+            println!("ok     {}: {}", result.name, result.detail);
+        } else {
+            println!("FALLA  {}: {}", result.name, result.detail);
+        }
+    }
+    let failed: Vec<&str> = results.iter().filter(|result| !result.passed).map(|result| result.name.as_str()).collect();
+    assert!(failed.is_empty(), "{} de {} imágenes distintas de la referencia: {}", failed.len(), results.len(), failed.join(", "));
+}
@@ -0,0 +1,77 @@
+// Sandbox de gravedad (escena 9): avanzar de a un tick o de a 16 (como a x16) da las mismas trayectorias,
+// la energía casi no cambia, la Tierra se queda en su órbita mientras la roca errante se escapa, y con el
+// reloj en pausa o en reversa los cuerpos se quedan quietos
+use nalgebra_glm::Vec3;
+use lab4_shaders::gravity::Simulation;
+use lab4_shaders::scene::SceneConfig;
+
+const SANDBOX: u32 = 9;
+const SUN: usize = 0;
+const EARTH: usize = 1;
+const WANDERER: usize = 3;
+const DURATION: usize = 4000;
+
+fn positions(scene: &SceneConfig, simulation: &Simulation) -> Vec<Vec3> {
+    (0..scene.bodies.len()).filter_map(|index| simulation.position(index)).collect()
+}
+
+fn distance_to_sun(simulation: &Simulation, index: usize) -> f32 {
+    (simulation.position(index).unwrap() - simulation.position(SUN).unwrap()).magnitude()
+}
+
+// Avanza el reloj de 0 a DURATION de a `ticks_per_frame` y llama a `each` después de cada frame
+fn run(scene: &SceneConfig, ticks_per_frame: usize, mut each: impl FnMut(usize, &Simulation)) -> Simulation {
+    let mut simulation = Simulation::new(scene);
+    for time in (0..=DURATION).step_by(ticks_per_frame) {
+        simulation.update(time as f32);
+        each(time, &simulation);
+    }
+    simulation
+}
+
+#[test]
+fn the_same_paths_at_any_clock_speed() {
+    let scene = SceneConfig::builtin(SANDBOX);
+    let slow = run(&scene, 1, |_, _| {});
+    let fast = run(&scene, 16, |_, _| {});
+    assert_eq!(positions(&scene, &slow), positions(&scene, &fast));
+}
+
+#[test]
+fn energy_stays_constant() {
+    let scene = SceneConfig::builtin(SANDBOX);
+    let start = Simulation::new(&scene).energy();
+    let mut worst = 0.0f64;
+    run(&scene, 16, |_, simulation| worst = worst.max(((simulation.energy() - start) / start).abs()));
+    assert!(worst < 1e-4, "la energía varió {:.2e}", worst);
+}
+
+#[test]
+fn the_earth_keeps_its_orbit_and_the_wanderer_escapes() {
+    let scene = SceneConfig::builtin(SANDBOX);
+    let (mut closest, mut farthest) = (f32::INFINITY, 0.0f32);
+    let mut wanderer = Vec::new();
+    run(&scene, 16, |time, simulation| {
+        let earth = distance_to_sun(simulation, EARTH);
+        (closest, farthest) = (closest.min(earth), farthest.max(earth));
+        if time % 1000 == 0 {
+            wanderer.push(distance_to_sun(simulation, WANDERER));
+        }
+    });
+    // La Tierra empieza a 3 con la velocidad de una órbita circular
+    assert!(closest > 2.5 && farthest < 3.5, "la Tierra anduvo entre {:.2} y {:.2} del sol", closest, farthest);
+    // La roca se aleja cada vez más y termina lejos de todo el sistema
+    assert!(wanderer.windows(2).skip(1).all(|pair| pair[1] > pair[0]), "distancias de la roca {:?}", wanderer);
+    assert!(wanderer.last().is_some_and(|&distance| distance > 30.0), "distancias de la roca {:?}", wanderer);
+}
+
+#[test]
+fn bodies_stay_still_while_paused_or_reversed() {
+    let scene = SceneConfig::builtin(SANDBOX);
+    let simulation = run(&scene, 16, |_, _| {});
+    let mut stopped = simulation.clone();
+    for time in [DURATION as f32, DURATION as f32 - 300.0, 0.0] {
+        stopped.update(time);
+        assert_eq!(positions(&scene, &stopped), positions(&scene, &simulation), "en el instante {}", time);
+    }
+}
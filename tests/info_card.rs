@@ -0,0 +1,73 @@
+// Tarjeta de información del cuerpo (tecla I): el texto partido al ancho, el panel dentro de la ventana y
+// los datos que arma la escena para el cuerpo enfocado
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::{Framebuffer, Rect};
+use lab4_shaders::info_card::draw_info_card;
+use lab4_shaders::scene::{body_info, focused_body, RenderOptions, SceneConfig};
+use lab4_shaders::text::{text_width, wrap_text};
+
+#[test]
+fn wrap_text_breaks_on_words_and_splits_long_ones() {
+    let width = text_width("uno dos", 1);
+    assert_eq!(wrap_text("uno dos tres", width, 1), vec!["uno dos", "tres"]);
+    // Una palabra más larga que el ancho se corta letra por letra
+    assert_eq!(wrap_text("abcdefgh", text_width("abc", 1), 1), vec!["abc", "def", "gh"]);
+    // Aunque no entre ni una letra, cada renglón lleva al menos una
+    assert_eq!(wrap_text("ab", 1, 1), vec!["a", "b"]);
+    assert_eq!(wrap_text("", width, 1), vec![""]);
+    assert_eq!(wrap_text("  uno   dos  ", width, 1), vec!["uno dos"]);
+}
+
+#[test]
+fn blend_rect_skips_rounded_corners() {
+    let mut framebuffer = Framebuffer::new(20, 20);
+    let rect = Rect::new(2, 2, 16, 16);
+    framebuffer.blend_rect(rect, Color::new(255, 255, 255), 0.5, 4);
+    let pixel = |x: usize, y: usize| framebuffer.buffer[y * framebuffer.width + x];
+    assert_eq!(pixel(2, 2), 0, "la esquina redondeada no se toca");
+    assert_eq!(pixel(17, 17), 0, "la esquina redondeada no se toca");
+    assert_eq!(pixel(1, 10), 0, "afuera del rectángulo no se toca");
+    assert_ne!(pixel(10, 10), 0, "el centro se mezcla");
+    assert_ne!(pixel(2, 10), 0, "el borde recto se mezcla");
+}
+
+#[test]
+fn card_stays_inside_a_small_framebuffer() {
+    let lines: Vec<String> = (0..40).map(|index| format!("renglón {} con bastante texto para partirse", index)).collect();
+    let mut framebuffer = Framebuffer::new(120, 80);
+    let card = draw_info_card(&mut framebuffer, &lines).expect("entra en 120x80");
+    assert!(card.x + card.width <= framebuffer.width && card.y + card.height <= framebuffer.height, "{:?}", card);
+
+    // Lo que queda fuera del panel sigue intacto
+    let outside = framebuffer.buffer.iter().enumerate()
+        .filter(|(index, _)| !card.contains(index % framebuffer.width, index / framebuffer.width))
+        .all(|(_, &pixel)| pixel == 0);
+    assert!(outside);
+
+    let mut tiny = Framebuffer::new(20, 20);
+    assert_eq!(draw_info_card(&mut tiny, &lines), None);
+    assert!(tiny.buffer.iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn body_info_lists_the_gas_planet_parameters() {
+    let scene = SceneConfig::builtin(3);
+    let bodies = scene.bodies(0.0);
+    let mut options = RenderOptions::default();
+    assert_eq!(focused_body(&bodies, &options), Some(0));
+    options.highlighted_body = Some(bodies.len());
+    assert_eq!(focused_body(&bodies, &options), Some(0), "un índice viejo vuelve al cuerpo principal");
+
+    let lines = body_info(&scene, &bodies, 0);
+    assert_eq!(lines[0], "Planeta gaseoso");
+    assert!(lines.iter().any(|line| line == "parámetros del shader"), "{:?}", lines);
+    assert!(lines.iter().any(|line| line == "  franjas: 4.0"), "{:?}", lines);
+    assert!(lines.iter().any(|line| line == "  tormentas: 3"), "{:?}", lines);
+
+    // El disco del sol que se agrega en las escenas de un planeta también tiene su tarjeta
+    let sun = bodies.len() - 1;
+    options.highlighted_body = Some(sun);
+    assert_eq!(focused_body(&bodies, &options), Some(sun));
+    assert!(!body_info(&scene, &bodies, sun).is_empty());
+    assert!(body_info(&scene, &bodies, bodies.len()).is_empty());
+}
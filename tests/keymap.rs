@@ -0,0 +1,68 @@
+// Teclas de keybindings.toml: cada acción del archivo reemplaza sus teclas y las demás quedan como
+// estaban, una tecla en dos acciones del archivo es un error, las acciones y teclas desconocidas solo
+// avisan, y una tecla que pasa a otra acción deja de hacer la anterior
+use minifb::Key;
+use lab4_shaders::keymap::{Action, KeyMap};
+
+// Acciones cuyas teclas cambiaron respecto de las de por defecto
+fn changed(keymap: &KeyMap) -> Vec<Action> {
+    keymap.bindings().iter().zip(KeyMap::default().bindings())
+        .filter(|(ours, default)| ours != default)
+        .map(|((action, _), _)| *action)
+        .collect()
+}
+
+#[test]
+fn an_override_replaces_only_its_action() {
+    // Una tecla sola o una lista, sin distinguir mayúsculas; repetir una tecla en la lista no la duplica
+    let (keymap, warnings) = KeyMap::from_toml("zoom_in = [\"F13\", \"numpad9\", \"F13\"]\nscene_3 = \"f15\"\n").unwrap();
+    assert_eq!(warnings, Vec::<String>::new());
+    assert_eq!(keymap.keys_for(Action::ZoomIn), [Key::F13, Key::NumPad9]);
+    assert_eq!(keymap.keys_for(Action::Scene(3)), [Key::F15]);
+    assert_eq!(changed(&keymap), [Action::Scene(3), Action::ZoomIn]);
+
+    // Un archivo vacío deja todas las de por defecto
+    let (keymap, warnings) = KeyMap::from_toml("").unwrap();
+    assert!(warnings.is_empty());
+    assert_eq!(changed(&keymap), []);
+}
+
+#[test]
+fn the_same_key_in_two_actions_is_an_error() {
+    let error = KeyMap::from_toml("zoom_in = \"F13\"\nzoom_out = [\"F14\", \"f13\"]\n").err().unwrap();
+    assert!(error.contains("F13") && error.contains("zoom_in") && error.contains("zoom_out"), "{}", error);
+
+    // Las teclas que no son texto y lo que no es ni tecla ni lista también son errores
+    assert!(KeyMap::from_toml("zoom_in = [1]").is_err());
+    assert!(KeyMap::from_toml("zoom_in = true").is_err());
+    assert!(KeyMap::from_toml("zoom_in = ").is_err());
+}
+
+#[test]
+fn unknown_actions_and_keys_are_warnings() {
+    let (keymap, warnings) = KeyMap::from_toml("fly = \"F13\"\nzoom_in = [\"F14\", \"Hyper\"]\n").unwrap();
+    assert_eq!(warnings, ["acción desconocida \"fly\"", "zoom_in: tecla desconocida \"Hyper\""]);
+    // La acción desconocida no toca nada y de la lista quedan las teclas que sí existen
+    assert_eq!(keymap.keys_for(Action::ZoomIn), [Key::F14]);
+    assert_eq!(changed(&keymap), [Action::ZoomIn]);
+}
+
+#[test]
+fn a_key_moved_from_another_action_stops_doing_it() {
+    // F1 era de cycle_debug_mode, que se queda sin tecla
+    let (keymap, warnings) = KeyMap::from_toml("toggle_help = \"F1\"\n").unwrap();
+    assert_eq!(keymap.keys_for(Action::ToggleHelp), [Key::F1]);
+    assert_eq!(keymap.keys_for(Action::CycleDebugMode), []);
+    assert_eq!(warnings, ["F1 ya no hace cycle_debug_mode"]);
+
+    // Si la acción de antes tenía otra tecla se queda con esa; H ya no hace nada
+    let (keymap, warnings) = KeyMap::from_toml("toggle_help = \"Equal\"\n").unwrap();
+    assert_eq!(keymap.keys_for(Action::Faster), [Key::NumPadPlus]);
+    assert_eq!(warnings, ["Equal ya no hace faster"]);
+    assert!(keymap.bindings().iter().all(|(_, keys)| !keys.contains(&Key::H)));
+
+    // Una acción que aparece en el archivo no pierde la tecla aunque otra también la tuviera por defecto
+    let (keymap, warnings) = KeyMap::from_toml("toggle_help = \"F1\"\ncycle_debug_mode = \"F13\"\n").unwrap();
+    assert_eq!(keymap.keys_for(Action::CycleDebugMode), [Key::F13]);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+}
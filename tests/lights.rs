@@ -0,0 +1,52 @@
+// Luz difusa de varias luces de colores: sin luces queda solo la ambiental, cada luz suma su color por el
+// coseno y las que pasan de MAX_LIGHTS no cuentan
+use nalgebra_glm::Vec3;
+use lab4_shaders::color::Color;
+use lab4_shaders::pipeline::{diffuse_light, Light, MAX_LIGHTS};
+
+const EPSILON: f32 = 1e-6;
+
+fn assert_light(actual: Vec3, expected: Vec3, what: &str) {
+    assert!((actual - expected).magnitude() < EPSILON, "{}: {:?} en lugar de {:?}", what, actual, expected);
+}
+
+fn normals() -> [Vec3; 4] {
+    [Vec3::z(), -Vec3::z(), Vec3::x(), Vec3::new(0.3, -0.4, 0.5).normalize()]
+}
+
+#[test]
+fn without_lights_only_the_ambient_remains() {
+    let ambient = Color::new(51, 102, 255);
+    for normal in normals() {
+        assert_light(diffuse_light(&normal, &Vec3::new(1.0, 2.0, 3.0), &[], &ambient), Vec3::new(0.2, 0.4, 1.0), "ambiental");
+        assert_light(diffuse_light(&normal, &Vec3::zeros(), &[], &Color::BLACK), Vec3::zeros(), "sin luz");
+    }
+}
+
+#[test]
+fn each_light_adds_its_color_by_the_cosine() {
+    let red = Light::directional(Vec3::z(), Color::new(255, 0, 0), 1.0);
+    let blue = Light::point(Vec3::new(10.0, 0.0, 0.0), Color::new(0, 0, 255), 0.5);
+    let lights = [red, blue];
+    let ambient = Color::new(0, 51, 0);
+
+    // De frente a la roja y de costado a la azul; a 60° de la roja; de espaldas a las dos
+    assert_light(diffuse_light(&Vec3::z(), &Vec3::zeros(), &lights, &ambient), Vec3::new(1.0, 0.2, 0.0), "de frente");
+    assert_light(diffuse_light(&Vec3::x(), &Vec3::zeros(), &lights, &ambient), Vec3::new(0.0, 0.2, 0.5), "hacia la puntual");
+    let tilted = Vec3::new(0.0, 3.0f32.sqrt() / 2.0, 0.5);
+    assert_light(diffuse_light(&tilted, &Vec3::zeros(), &lights, &ambient), Vec3::new(0.5, 0.2, 0.0), "a 60°");
+    assert_light(diffuse_light(&-Vec3::z(), &Vec3::zeros(), &lights, &ambient), Vec3::new(0.0, 0.2, 0.0), "de espaldas");
+}
+
+#[test]
+fn lights_past_the_limit_are_ignored() {
+    // MAX_LIGHTS luces blancas débiles y después una roja fuerte que no llega a contar
+    let white = Light::directional(Vec3::z(), Color::new(255, 255, 255), 0.1);
+    let mut lights = vec![white; MAX_LIGHTS];
+    let limit = diffuse_light(&Vec3::z(), &Vec3::zeros(), &lights, &Color::BLACK);
+    assert_light(limit, Vec3::repeat(0.1 * MAX_LIGHTS as f32), "hasta el límite");
+
+    lights.push(Light::directional(Vec3::z(), Color::new(255, 0, 0), 5.0));
+    lights.push(white);
+    assert_light(diffuse_light(&Vec3::z(), &Vec3::zeros(), &lights, &Color::BLACK), limit, "con luces de más");
+}
@@ -0,0 +1,58 @@
+// Curvas de color: la LUT identidad no cambia ningún bit de un patrón con todos los valores de cada
+// canal, la que invierte (escrita como CSV) lo invierte exacto, entre dos entradas el color se
+// interpola y la curva de ejemplo que viene con el proyecto se carga
+use std::path::Path;
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::lut::Lut;
+use lab4_shaders::post::apply_lut;
+
+const EXAMPLE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets/luts/warm_filmic.cube");
+
+// Cada valor de 0 a 255 en el verde, combinado en cada fila con otro rojo y el azul al revés
+fn pattern() -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(256, 3);
+    for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+        let (value, row) = ((index % 256) as u8, index / 256);
+        *pixel = u32::from_be_bytes([0, value, value.wrapping_add(85 * row as u8), 255 - value]);
+    }
+    framebuffer
+}
+
+fn inversion_csv() -> String {
+    (0..=255).map(|value| format!("{0},{0},{0}\n", 255 - value)).collect()
+}
+
+#[test]
+fn the_identity_lut_changes_nothing() {
+    let mut framebuffer = pattern();
+    apply_lut(&mut framebuffer, &Lut::identity());
+    assert!(framebuffer.buffer == pattern().buffer, "la LUT identidad cambió la imagen");
+}
+
+#[test]
+fn the_inverting_lut_inverts_every_channel_exactly() {
+    let lut = Lut::from_csv(&inversion_csv()).unwrap();
+    let mut framebuffer = pattern();
+    apply_lut(&mut framebuffer, &lut);
+    for (index, (inverted, original)) in framebuffer.buffer.iter().zip(&pattern().buffer).enumerate() {
+        assert_eq!(*inverted, !original & 0x00ff_ffff, "pixel {}", index);
+    }
+}
+
+#[test]
+fn colors_between_entries_are_interpolated() {
+    let lut = Lut::from_csv(&inversion_csv()).unwrap();
+    let color = lut.apply(Color { r: 10.5, g: 10.25, b: 0.0 });
+    assert!((color.r - 244.5).abs() < 1e-3, "{:?}", color);
+    assert!((color.g - 244.75).abs() < 1e-3, "{:?}", color);
+    assert!((color.b - 255.0).abs() < 1e-3, "{:?}", color);
+    // Por encima del blanco se queda en la última entrada
+    assert!(lut.apply(Color { r: 300.0, g: 300.0, b: 300.0 }).r.abs() < 1e-3);
+}
+
+#[test]
+fn the_example_curve_loads() {
+    let lut = Lut::load(Path::new(EXAMPLE)).unwrap();
+    assert_eq!(lut, Lut::from_cube(include_str!("../assets/luts/warm_filmic.cube")).unwrap());
+}
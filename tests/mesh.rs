@@ -0,0 +1,89 @@
+// Generadores de mallas: cantidad de vértices, normales unitarias hacia afuera, UV dentro de [0, 1] sin
+// NaN en los polos y la misma convención de u en la esfera UV y la icosfera (la de spherical_uv), así la
+// textura no salta al cambiar de nivel de detalle
+use nalgebra_glm::Vec3;
+use lab4_shaders::mesh::{generate_icosphere, generate_uv_sphere, SPHERE_RADIUS};
+use lab4_shaders::shaders::spherical_uv;
+use lab4_shaders::vertex::Vertex;
+
+const EPSILON: f32 = 1e-4;
+
+fn is_pole(position: &Vec3) -> bool {
+    position.x.abs() < 1e-5 && position.z.abs() < 1e-5
+}
+
+// En la costura (-x, z = 0) u vale 0 de un lado y 1 del otro
+fn is_seam(position: &Vec3) -> bool {
+    position.x < 0.0 && position.z.abs() < 1e-5 && !is_pole(position)
+}
+
+fn check_sphere(name: &str, vertices: &[Vertex]) {
+    assert!(!vertices.is_empty() && vertices.len().is_multiple_of(3), "{}: {} vértices", name, vertices.len());
+    for vertex in vertices {
+        let (position, normal, uv) = (vertex.position, vertex.normal, vertex.tex_coords);
+        assert!((position.magnitude() - SPHERE_RADIUS).abs() < EPSILON, "{}: {:?} fuera de la esfera", name, position);
+        assert!((normal.magnitude() - 1.0).abs() < EPSILON, "{}: normal {:?} no mide 1", name, normal);
+        assert!(normal.dot(&position.normalize()) > 1.0 - EPSILON, "{}: normal {:?} no sale de {:?}", name, normal, position);
+        assert!(uv.iter().all(|value| value.is_finite() && (0.0..=1.0).contains(value)), "{}: UV {:?} en {:?}", name, uv, position);
+
+        // La misma u que spherical_uv fuera de los polos y la costura, y v siempre
+        let expected = spherical_uv(position);
+        assert!((uv.y - expected.y).abs() < EPSILON, "{}: v {} en lugar de {} en {:?}", name, uv.y, expected.y, position);
+        if is_seam(&position) {
+            assert!(uv.x < EPSILON || uv.x > 1.0 - EPSILON, "{}: u {} en la costura", name, uv.x);
+        } else if !is_pole(&position) {
+            assert!((uv.x - expected.x).abs() < EPSILON, "{}: u {} en lugar de {} en {:?}", name, uv.x, expected.x, position);
+        }
+    }
+
+    for triangle in vertices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| triangle[i].position);
+        // Caras en sentido antihorario vistas desde afuera y sin ningún triángulo que dé la vuelta a la textura
+        assert!((b - a).cross(&(c - a)).dot(&(a + b + c)) > 0.0, "{}: cara {:?} al revés", name, [a, b, c]);
+        let us = triangle.iter().map(|vertex| vertex.tex_coords.x);
+        let span = us.clone().fold(f32::NEG_INFINITY, f32::max) - us.fold(f32::INFINITY, f32::min);
+        assert!(span <= 0.5 + EPSILON, "{}: la cara {:?} cruza la costura", name, [a, b, c]);
+    }
+}
+
+#[test]
+fn uv_sphere_has_one_quad_per_cell_and_one_triangle_at_the_poles() {
+    for (stacks, slices) in [(2, 3), (12, 24), (24, 48)] {
+        let sphere = generate_uv_sphere(stacks, slices);
+        assert_eq!(sphere.len() as u32, 6 * slices * (stacks - 1), "{}x{}", stacks, slices);
+        check_sphere(&format!("esfera UV {}x{}", stacks, slices), &sphere);
+    }
+    // Menos que lo mínimo se lleva a 2 x 3
+    assert_eq!(generate_uv_sphere(0, 0).len(), generate_uv_sphere(2, 3).len());
+}
+
+#[test]
+fn icosphere_splits_only_the_faces_on_the_seam() {
+    for subdivisions in 0..4 {
+        let sphere = generate_icosphere(subdivisions);
+        let faces = 20 * 4usize.pow(subdivisions);
+        // Cada cara que cruza la costura se parte en dos o tres
+        assert!(sphere.len() > faces * 3 && sphere.len() < faces * 3 + 2 * 3 * 2usize.pow(subdivisions + 2), "{}: {} vértices", subdivisions, sphere.len());
+        check_sphere(&format!("icosfera {}", subdivisions), &sphere);
+    }
+}
+
+#[test]
+fn both_spheres_agree_on_uv_where_they_meet() {
+    // Un mismo punto da la misma UV en la esfera UV y en la icosfera
+    let uv_sphere = generate_uv_sphere(24, 48);
+    let icosphere = generate_icosphere(2);
+    let mut shared = 0;
+    for vertex in &icosphere {
+        let position = vertex.position;
+        if is_pole(&position) || is_seam(&position) {
+            continue;
+        }
+        if let Some(other) = uv_sphere.iter().find(|other| (other.position - position).magnitude() < EPSILON) {
+            assert!((other.tex_coords - vertex.tex_coords).magnitude() < EPSILON, "{:?}: {:?} y {:?}", position, other.tex_coords, vertex.tex_coords);
+            shared += 1;
+        }
+    }
+    // Los vértices del ecuador a 0° y 90° (y sus opuestos) están en las dos
+    assert!(shared > 0);
+}
@@ -0,0 +1,87 @@
+// Caché binaria de mallas: se usa mientras el OBJ no cambie, deja de servir si cambia su tamaño o su
+// fecha, y una caché cortada se ignora y se vuelve a generar en lugar de entrar en pánico
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use nalgebra_glm::{Vec2, Vec3};
+use lab4_shaders::color::Color;
+use lab4_shaders::mesh_cache::{self, cache_path};
+use lab4_shaders::obj_loader::Obj;
+use lab4_shaders::vertex::Vertex;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+// Copia del cuadrado de prueba en una carpeta propia de cada prueba, sin caché al lado
+fn fixture_copy(test: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("mesh_cache").join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("square.obj");
+    fs::copy(format!("{}/vertex_colors.obj", FIXTURES), &path).unwrap();
+    path
+}
+
+fn load_cached(path: &Path) -> Vec<Vertex> {
+    Obj::load_cached(path.to_str().unwrap()).expect("no se pudo cargar el cuadrado")
+}
+
+fn summary(vertices: &[Vertex]) -> Vec<(Vec3, Vec3, Vec2, Color)> {
+    vertices.iter().map(|vertex| (vertex.position, vertex.normal, vertex.tex_coords, vertex.color)).collect()
+}
+
+fn parsed(path: &Path) -> Vec<Vertex> {
+    Obj::load(path.to_str().unwrap()).unwrap().get_vertex_array()
+}
+
+#[test]
+fn an_up_to_date_cache_is_used_instead_of_the_obj() {
+    let path = fixture_copy("hit");
+    assert!(mesh_cache::read(&path).is_none());
+
+    // La primera carga lee el OBJ y guarda la caché con los mismos vértices
+    let loaded = load_cached(&path);
+    assert!(cache_path(&path).exists());
+    assert_eq!(summary(&loaded), summary(&parsed(&path)));
+    assert_eq!(summary(&mesh_cache::read(&path).unwrap()), summary(&loaded));
+
+    // Con una caché distinta pero al día se devuelve lo de la caché sin mirar el OBJ
+    let marker = vec![Vertex { color: Color::new(1, 2, 3), ..Vertex::new(Vec3::new(7.0, 8.0, 9.0), Vec3::y(), Vec2::new(0.25, 0.75)) }; 3];
+    mesh_cache::write(&path, &marker).unwrap();
+    assert_eq!(summary(&load_cached(&path)), summary(&marker));
+}
+
+#[test]
+fn a_cache_is_stale_once_the_obj_changes_size_or_date() {
+    let path = fixture_copy("stale");
+    let original = load_cached(&path);
+
+    // Otro tamaño: un comentario al final no cambia la malla pero sí el archivo
+    let source = fs::read_to_string(&path).unwrap();
+    fs::write(&path, format!("{}# editado\n", source)).unwrap();
+    assert!(mesh_cache::read(&path).is_none(), "la caché sigue valiendo con otro tamaño");
+    assert_eq!(summary(&load_cached(&path)), summary(&original));
+    assert!(mesh_cache::read(&path).is_some(), "no se volvió a guardar la caché");
+
+    // Mismo tamaño y otra fecha
+    let file = File::options().write(true).open(&path).unwrap();
+    file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+    drop(file);
+    assert!(mesh_cache::read(&path).is_none(), "la caché sigue valiendo con otra fecha");
+    assert_eq!(summary(&load_cached(&path)), summary(&original));
+    assert!(mesh_cache::read(&path).is_some());
+}
+
+#[test]
+fn a_truncated_cache_is_ignored_and_regenerated() {
+    let path = fixture_copy("truncated");
+    let original = load_cached(&path);
+    let full = fs::read(cache_path(&path)).unwrap();
+
+    // Cortada en medio de los vértices, en medio del encabezado y vacía
+    for length in [full.len() - 1, full.len() - 20, 30, 10, 0] {
+        fs::write(cache_path(&path), &full[..length]).unwrap();
+        assert!(mesh_cache::read(&path).is_none(), "se leyó una caché de {} bytes", length);
+        assert_eq!(summary(&load_cached(&path)), summary(&original), "{} bytes", length);
+        assert_eq!(fs::read(cache_path(&path)).unwrap(), full, "no se regeneró la caché de {} bytes", length);
+    }
+}
@@ -0,0 +1,76 @@
+// Cráteres de la luna sobre la esfera: vista de frente y de atrás tiene cráteres distintos, en lugar del
+// mismo dibujo espejado (como cuando se ubicaban solo con x e y)
+use nalgebra_glm::Vec3;
+use lab4_shaders::camera::Camera;
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::mesh::generate_uv_sphere;
+use lab4_shaders::pipeline::{build_uniforms, create_model_matrix, render, BodyUniforms, FragmentBudget, Light, RenderScratch, RenderStats, DEFAULT_DEPTH_RANGE};
+use lab4_shaders::shaders::{moon_craters, ShaderType};
+
+const WIDTH: usize = 80;
+const HEIGHT: usize = 80;
+const SEED: u32 = 7;
+// La roca va de gris 169 a 211 y su ruido cambia de un pixel a otro con la triangulación, así que dos
+// vistas de la misma roca pueden diferir en hasta 42. Más que eso solo lo da un cráter (gris 100)
+const CRATER_CONTRAST: f32 = 45.0;
+
+// La luna vista desde `eye`, con la luz desde la cámara para que las dos caras se iluminen igual
+fn moon_from(eye: Vec3) -> Framebuffer {
+    let camera = Camera::new(eye, Vec3::zeros(), Vec3::y());
+    let mut frame = build_uniforms(&camera, 0.0, WIDTH, HEIGHT, DEFAULT_DEPTH_RANGE);
+    frame.lights = vec![Light::directional(eye, Color::new(255, 255, 255), 1.0)];
+    let mut body = BodyUniforms::new(create_model_matrix(Vec3::zeros(), Vec3::repeat(3.0), Vec3::zeros()));
+    body.seed = SEED;
+
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    render(&mut framebuffer, &frame, &body, &generate_uv_sphere(32, 64), &ShaderType::Moon, &mut FragmentBudget::default(), &mut RenderStats::default(), &mut RenderScratch::new());
+    framebuffer
+}
+
+fn brightness(framebuffer: &Framebuffer, x: usize, y: usize) -> Option<f32> {
+    let (color, depth) = framebuffer.get_pixel(x, y).unwrap();
+    depth.is_finite().then_some((color.r + color.g + color.b) / 3.0)
+}
+
+// Fracción de los pixeles dibujados en las dos imágenes que cambian de brillo en más de `threshold`;
+// con `mirrored` la segunda se lee dada vuelta de izquierda a derecha
+fn changed_fraction(a: &Framebuffer, b: &Framebuffer, mirrored: bool, threshold: f32) -> f32 {
+    let (mut both, mut changed) = (0, 0);
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let other_x = if mirrored { WIDTH - 1 - x } else { x };
+            if let (Some(first), Some(second)) = (brightness(a, x, y), brightness(b, other_x, y)) {
+                both += 1;
+                if (first - second).abs() > threshold {
+                    changed += 1;
+                }
+            }
+        }
+    }
+    assert!(both > WIDTH * HEIGHT / 4, "la luna casi no se ve");
+    changed as f32 / both as f32
+}
+
+#[test]
+fn both_hemispheres_have_craters() {
+    // La semilla de la prueba reparte cráteres grandes de los dos lados
+    let craters = moon_craters(SEED);
+    let big = |front: bool| craters.iter().filter(|(center, radius)| (center.z > 0.3) == front && center.z.abs() > 0.3 && *radius > 0.2).count();
+    assert!(big(true) > 0 && big(false) > 0, "{:?}", craters);
+}
+
+#[test]
+fn the_far_side_is_not_the_near_side_mirrored() {
+    let front = moon_from(Vec3::new(0.0, 0.0, 6.0));
+    let back = moon_from(Vec3::new(0.0, 0.0, -6.0));
+
+    // Mirando desde atrás la pantalla queda espejada respecto a la de adelante. La roca solo depende
+    // de x e y, así que lo que cambia al espejar son los cráteres (con los de antes no cambiaba nada)
+    let mirrored = changed_fraction(&front, &back, true, CRATER_CONTRAST);
+    assert!(mirrored > 0.04, "solo el {:.1}% de los pixeles cambia: la cara de atrás es la de adelante espejada", mirrored * 100.0);
+    assert!(changed_fraction(&front, &back, false, CRATER_CONTRAST) > 0.04);
+
+    // Y la misma cara dibujada dos veces no cambia
+    assert_eq!(changed_fraction(&front, &moon_from(Vec3::new(0.0, 0.0, 6.0)), false, 0.0), 0.0);
+}
@@ -0,0 +1,105 @@
+// Cargador de OBJ: cada archivo roto devuelve su ObjError con la línea del problema en lugar de entrar
+// en pánico, y los colores por vértice llegan a Vertex::color
+use nalgebra_glm::Vec3;
+use lab4_shaders::color::Color;
+use lab4_shaders::obj_loader::{Obj, ObjError};
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+const TRIANGLE: &str = "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+";
+
+// Línea y mensaje de un error de lectura; falla con cualquier otra variante
+fn parse_error(source: &str) -> (usize, String) {
+    match Obj::parse(source) {
+        Err(ObjError::Parse { line, message }) => (line, message),
+        Err(other) => panic!("se esperaba ObjError::Parse, llegó {:?}", other),
+        Ok(_) => panic!("el OBJ inválido se leyó sin error"),
+    }
+}
+
+#[test]
+fn truncated_face_reports_its_line() {
+    let (line, message) = parse_error(&format!("{}f 1 2\n", TRIANGLE));
+    assert_eq!(line, 4);
+    assert!(message.contains("al menos 3"), "{}", message);
+
+    // Un vértice sin sus tres coordenadas también está cortado
+    let (line, message) = parse_error("v 0 0 0\nv 1 0\n");
+    assert_eq!(line, 2);
+    assert!(message.contains("se esperaban 3"), "{}", message);
+}
+
+#[test]
+fn bad_float_reports_its_line() {
+    for (source, expected_line) in [
+        ("v 0 0 0\nv 1 abc 0\n", 2),
+        ("v 0 0 0\n\n# comentario\nvt 0.5 x\n", 4),
+        ("vn 0 0 1,5\n", 1),
+    ] {
+        let (line, message) = parse_error(source);
+        assert_eq!(line, expected_line, "{:?}", source);
+        assert!(message.contains("número inválido"), "{}", message);
+    }
+}
+
+#[test]
+fn out_of_range_indices_report_their_line() {
+    for (face, kind) in [
+        ("f 1 2 4", "posición"),
+        ("f 0 1 2", "posición"),
+        ("f -4 -2 -1", "posición"),
+        ("f 1/1 2/1 3/1", "coordenada de textura"),
+        ("f 1//2 2//2 3//2", "normal"),
+        ("f 1 2 x", "posición"),
+    ] {
+        let (line, message) = parse_error(&format!("{}{}\n", TRIANGLE, face));
+        assert_eq!(line, 4, "{}", face);
+        assert!(message.contains(kind), "{}: {}", face, message);
+    }
+    // Los negativos cuentan desde el último vértice
+    assert!(Obj::parse(&format!("{}f -3 -2 -1\n", TRIANGLE)).is_ok());
+}
+
+#[test]
+fn files_without_triangles_have_no_geometry() {
+    for source in ["", "\n\n", "# solo un comentario\n", TRIANGLE] {
+        assert!(matches!(Obj::parse(source), Err(ObjError::NoGeometry)), "{:?}", source);
+    }
+    assert!(matches!(Obj::load("no_existe.obj"), Err(ObjError::Io(_))));
+}
+
+#[test]
+fn errors_read_with_their_line_number() {
+    let error = ObjError::Parse { line: 7, message: "número inválido: abc".to_string() };
+    assert_eq!(error.to_string(), "OBJ inválido en la línea 7: número inválido: abc");
+    assert_eq!(ObjError::NoGeometry.to_string(), "el archivo no tiene triángulos");
+}
+
+#[test]
+fn vertex_colors_reach_each_vertex() {
+    let obj = Obj::load(&format!("{}/vertex_colors.obj", FIXTURES)).expect("no se pudo leer el cuadrado");
+    assert!(obj.has_vertex_colors());
+    let vertices = obj.get_vertex_array();
+    assert_eq!(vertices.len(), 6);
+
+    // Cada esquina con el color de su línea "v", pasado de 0-1 a 0-255
+    let expected = [
+        (Vec3::new(-1.0, -1.0, 0.0), Color::new(255, 0, 0)),
+        (Vec3::new(1.0, -1.0, 0.0), Color::new(0, 255, 0)),
+        (Vec3::new(1.0, 1.0, 0.0), Color::new(0, 0, 255)),
+        (Vec3::new(-1.0, 1.0, 0.0), Color::new(255, 255, 255)),
+    ];
+    for vertex in &vertices {
+        let (_, color) = expected.iter().find(|(position, _)| *position == vertex.position).expect("vértice que no está en el archivo");
+        assert_eq!(vertex.color, *color, "{:?}", vertex.position);
+    }
+
+    // Sin colores en el archivo todos quedan blancos
+    let plain = Obj::parse(&format!("{}f 1 2 3\n", TRIANGLE)).unwrap();
+    assert!(!plain.has_vertex_colors());
+    assert!(plain.get_vertex_array().iter().all(|vertex| vertex.color == Color::new(255, 255, 255)));
+}
@@ -0,0 +1,43 @@
+// Órbitas circulares: con e = 0 la órbita de Kepler sigue el mismo círculo que la fórmula de antes
+// (ángulo = fase + tiempo * velocidad). El ángulo ahora sale de TAU * tiempo / período y se lleva a
+// [0, 2π) con rem_euclid, así que no es idéntico bit a bit: se compara con una tolerancia
+use std::f32::consts::TAU;
+use nalgebra_glm::Vec3;
+use lab4_shaders::orbit::{orbit_position, Orbit};
+
+// Las dos cuentas redondean distinto un ángulo que llega a fase + 2π (menos de 4π, donde un ulp de f32
+// es ~1e-6 rad). Unos pocos ulp de diferencia en el ángulo son este error, medido sobre la circunferencia
+const ANGLE_TOLERANCE: f32 = 4e-6;
+const SAMPLES_PER_PERIOD: usize = 2000;
+
+// La posición como se calculaba antes de las órbitas elípticas
+fn old_circle(radius: f32, speed: f32, phase: f32, time: f32) -> Vec3 {
+    let angle = phase + time * speed;
+    Vec3::new(radius * angle.cos(), 0.0, radius * angle.sin())
+}
+
+#[test]
+fn circular_orbits_follow_the_old_circle_for_a_full_period() {
+    // Las órbitas circulares de las escenas incluidas: de la luna (rápida y chica) al planeta volcánico
+    for (radius, speed, phase) in [(1.3, 0.01, 0.0), (3.0, 0.006, 0.0), (5.0, 0.004, 4.0), (7.5, 0.0025, 2.1), (12.0, 0.0015, 1.0), (14.0, 0.001, 3.3)] {
+        let orbit = Orbit::circular(radius, speed, phase);
+        let period = TAU / speed;
+        let mut worst = 0.0f32;
+        for sample in 0..=SAMPLES_PER_PERIOD {
+            let time = period * sample as f32 / SAMPLES_PER_PERIOD as f32;
+            let error = (orbit_position(&orbit, time) - old_circle(radius, speed, phase, time)).magnitude();
+            worst = worst.max(error);
+        }
+        assert!(worst <= radius * ANGLE_TOLERANCE, "radio {} velocidad {}: se aleja {:.2e} del círculo (tolerancia {:.2e})", radius, speed, worst, radius * ANGLE_TOLERANCE);
+    }
+}
+
+#[test]
+fn circular_orbits_keep_their_radius_and_plane() {
+    let orbit = Orbit::circular(5.0, 0.004, 4.0);
+    for time in [-1000.0, 0.0, 123.4, 1570.8, 1e5] {
+        let position = orbit_position(&orbit, time);
+        assert!((position.magnitude() - 5.0).abs() < 1e-5, "{}: {:?}", time, position);
+        assert_eq!(position.y, 0.0, "{}", time);
+    }
+}
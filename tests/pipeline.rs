@@ -0,0 +1,218 @@
+// El pipeline completo sobre mallas chicas armadas a mano: qué pixeles escribe render, con qué color y
+// profundidad, la luz que llega a cada fragmento y lo que cuenta RenderStats
+use nalgebra_glm::{Vec2, Vec3};
+use lab4_shaders::camera::Camera;
+use lab4_shaders::color::Color;
+use lab4_shaders::fragments::Fragments;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::pipeline::{build_uniforms, create_model_matrix, project, render, BodyUniforms, FragmentBudget, FrameUniforms, RenderScratch, RenderStats, DEFAULT_DEPTH_RANGE};
+use lab4_shaders::shaders::{FragmentShader, ShaderType};
+use lab4_shaders::vertex::Vertex;
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 48;
+const RED: Color = Color::new(255, 0, 0);
+
+// Cámara en +z mirando al origen, con la luz por defecto (desde la cámara)
+fn frame() -> FrameUniforms {
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::y());
+    build_uniforms(&camera, 0.0, WIDTH, HEIGHT, DEFAULT_DEPTH_RANGE)
+}
+
+// Cuadrado de lado 2 en el plano z = 0, de frente a la cámara, en dos triángulos
+fn square(color: Color) -> Vec<Vertex> {
+    let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+    corners.iter().map(|&(x, y)| Vertex {
+        color,
+        ..Vertex::new(Vec3::new(x, y, 0.0), Vec3::z(), Vec2::zeros())
+    }).collect()
+}
+
+fn draw(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, vertices: &[Vertex], budget: &mut FragmentBudget) -> RenderStats {
+    let mut stats = RenderStats::default();
+    render(framebuffer, frame, body, vertices, &ShaderType::Flat, budget, &mut stats, &mut RenderScratch::new());
+    stats
+}
+
+#[test]
+fn render_fills_the_projected_square_with_its_lit_color() {
+    let frame = frame();
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let stats = draw(&mut framebuffer, &frame, &BodyUniforms::default(), &square(RED), &mut FragmentBudget::default());
+
+    // Las esquinas en pantalla; los pixeles con el centro adentro son del cuadrado
+    let low = project(&frame, Vec3::new(-1.0, 1.0, 0.0)).unwrap();
+    let high = project(&frame, Vec3::new(1.0, -1.0, 0.0)).unwrap();
+    let mut drawn = 0;
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let (center_x, center_y) = (x as f32 + 0.5, y as f32 + 0.5);
+            let inside = center_x > low.x && center_x < high.x && center_y > low.y && center_y < high.y;
+            let near_edge = (center_x - low.x).abs().min((center_x - high.x).abs()) < 1e-3 || (center_y - low.y).abs().min((center_y - high.y).abs()) < 1e-3;
+            if near_edge {
+                continue;
+            }
+            let (color, depth) = framebuffer.get_pixel(x, y).unwrap();
+            if inside {
+                // De frente a la luz blanca: el color de los vértices sin cambios, a la profundidad del plano
+                assert_eq!(color, RED, "({}, {})", x, y);
+                assert!((depth - low.z).abs() < 1e-5, "({}, {}): profundidad {}", x, y, depth);
+                drawn += 1;
+            } else {
+                assert_eq!((color, depth), (Color::BLACK, f32::INFINITY), "({}, {})", x, y);
+            }
+        }
+    }
+    assert!(drawn > 100);
+    assert_eq!(stats.depth_passed, framebuffer.zbuffer.iter().filter(|depth| depth.is_finite()).count());
+}
+
+#[test]
+fn render_places_the_body_with_its_model_matrix() {
+    // El mismo cuadrado corrido a la derecha y más chico: solo cambia la mitad derecha de la imagen
+    let frame = frame();
+    let body = BodyUniforms::new(create_model_matrix(Vec3::new(1.0, 0.0, 0.0), Vec3::repeat(0.25), Vec3::zeros()));
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    draw(&mut framebuffer, &frame, &body, &square(RED), &mut FragmentBudget::default());
+
+    let center = project(&frame, Vec3::new(1.0, 0.0, 0.0)).unwrap();
+    assert_eq!(framebuffer.get_pixel(center.x as usize, center.y as usize).unwrap().0, RED);
+    assert_eq!(framebuffer.get_pixel(WIDTH / 2, HEIGHT / 2).unwrap().1, f32::INFINITY);
+    let columns: Vec<usize> = (0..framebuffer.buffer.len()).filter(|&index| framebuffer.zbuffer[index].is_finite()).map(|index| index % WIDTH).collect();
+    assert!(!columns.is_empty() && columns.iter().all(|&x| x > WIDTH / 2));
+}
+
+// Pinta cada fragmento con su intensidad en gris (1 = blanco)
+struct IntensityShader;
+
+impl FragmentShader for IntensityShader {
+    fn shade(&self, fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+        let value = fragment.intensity * 255.0;
+        Color { r: value, g: value, b: value }
+    }
+}
+
+#[test]
+fn fragment_intensity_uses_the_normalized_normal() {
+    // Cuadrado de frente a la luz con las normales de los vértices inclinadas 45° hacia afuera a los
+    // costados: en el centro la normal interpolada mide cos 45° pero normalizada mira a la luz, así que
+    // la intensidad es 1; en cada columna es la z de la mezcla de las dos normales ya normalizada
+    let frame = frame();
+    let (left, right) = (Vec3::new(-1.0, 0.0, 1.0).normalize(), Vec3::new(1.0, 0.0, 1.0).normalize());
+    let vertices: Vec<Vertex> = square(RED).into_iter()
+        .map(|vertex| Vertex::new(vertex.position, if vertex.position.x < 0.0 { left } else { right }, Vec2::zeros()))
+        .collect();
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut stats = RenderStats::default();
+    render(&mut framebuffer, &frame, &BodyUniforms::default(), &vertices, &IntensityShader, &mut FragmentBudget::default(), &mut stats, &mut RenderScratch::new());
+
+    let (low, high) = (project(&frame, Vec3::new(-1.0, 0.0, 0.0)).unwrap(), project(&frame, Vec3::new(1.0, 0.0, 0.0)).unwrap());
+    let center = project(&frame, Vec3::zeros()).unwrap();
+    let (color, _) = framebuffer.get_pixel(center.x as usize, center.y as usize).unwrap();
+    assert!(color.r >= 254.0, "centro {:?}", color);
+
+    let mut checked = 0;
+    for x in 0..WIDTH {
+        let (color, depth) = framebuffer.get_pixel(x, center.y as usize).unwrap();
+        if depth.is_infinite() {
+            continue;
+        }
+        let t = (x as f32 + 0.5 - low.x) / (high.x - low.x);
+        let expected = left.lerp(&right, t).normalize().z;
+        assert!((color.r / 255.0 - expected).abs() < 2.0 / 255.0, "columna {}: {} en lugar de {}", x, color.r / 255.0, expected);
+        checked += 1;
+    }
+    assert!(checked > 10);
+}
+
+// Pixeles con el centro dentro del cuadrado de square() (ninguno cae justo sobre un borde)
+fn square_pixels(frame: &FrameUniforms) -> usize {
+    let low = project(frame, Vec3::new(-1.0, 1.0, 0.0)).unwrap();
+    let high = project(frame, Vec3::new(1.0, -1.0, 0.0)).unwrap();
+    let columns = (0..WIDTH).filter(|&x| (low.x..high.x).contains(&(x as f32 + 0.5))).count();
+    let rows = (0..HEIGHT).filter(|&y| (low.y..high.y).contains(&(y as f32 + 0.5))).count();
+    columns * rows
+}
+
+#[test]
+fn stats_count_vertices_triangles_and_fragments() {
+    let frame = frame();
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let stats = draw(&mut framebuffer, &frame, &BodyUniforms::default(), &square(RED), &mut FragmentBudget::default());
+    assert_eq!(square_pixels(&frame), 24 * 24);
+    assert_eq!(
+        (stats.vertices, stats.triangles, stats.culled_triangles, stats.skipped_triangles, stats.fragments, stats.depth_passed, stats.invalid_fragments),
+        (6, 2, 0, 0, 24 * 24, 24 * 24, 0),
+    );
+
+    // Otra vez en el mismo lugar: los fragmentos se generan pero ninguno queda más cerca
+    let again = draw(&mut framebuffer, &frame, &BodyUniforms::default(), &square(RED), &mut FragmentBudget::default());
+    assert_eq!((again.fragments, again.depth_passed), (24 * 24, 0));
+
+    // Con un triángulo detrás de la cámara y otro fuera de la pantalla se cuentan como recortados
+    let mut vertices = square(RED);
+    for offset in [Vec3::new(0.0, 0.0, 10.0), Vec3::new(40.0, 0.0, 0.0)] {
+        vertices.extend(square(RED)[..3].iter().map(|vertex| Vertex::new(vertex.position + offset, vertex.normal, vertex.tex_coords)));
+    }
+    let stats = draw(&mut Framebuffer::new(WIDTH, HEIGHT), &frame, &BodyUniforms::default(), &vertices, &mut FragmentBudget::default());
+    assert_eq!((stats.vertices, stats.triangles, stats.culled_triangles, stats.fragments), (12, 4, 2, 24 * 24));
+}
+
+#[test]
+fn triangles_over_the_per_triangle_cap_are_skipped_and_not_drawn() {
+    // Cada mitad del cuadrado tiene una caja de 25 x 25 pixeles
+    let frame = frame();
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut budget = FragmentBudget::new(usize::MAX, 25 * 25 - 1);
+    let stats = draw(&mut framebuffer, &frame, &BodyUniforms::default(), &square(RED), &mut budget);
+    assert_eq!((stats.triangles, stats.culled_triangles, stats.skipped_triangles, stats.fragments, stats.depth_passed), (2, 0, 2, 0, 0));
+    assert!(framebuffer.zbuffer.iter().all(|depth| depth.is_infinite()));
+    assert!(framebuffer.buffer.iter().all(|&pixel| pixel == 0));
+
+    // Justo en el límite se dibujan
+    let stats = draw(&mut framebuffer, &frame, &BodyUniforms::default(), &square(RED), &mut FragmentBudget::new(usize::MAX, 25 * 25));
+    assert_eq!((stats.skipped_triangles, stats.fragments), (0, 24 * 24));
+}
+
+#[test]
+fn the_frame_budget_skips_what_comes_after_it_runs_out() {
+    // El primer triángulo gasta lo que queda del frame y el segundo ya no entra
+    let frame = frame();
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut budget = FragmentBudget::new(10, usize::MAX);
+    let stats = draw(&mut framebuffer, &frame, &BodyUniforms::default(), &square(RED), &mut budget);
+    assert_eq!(stats.skipped_triangles, 1);
+    assert_eq!(budget.remaining, 0);
+    assert_eq!(stats.fragments, stats.depth_passed);
+    assert!(stats.fragments > 0 && stats.fragments < 24 * 24);
+    assert_eq!(framebuffer.zbuffer.iter().filter(|depth| depth.is_finite()).count(), stats.fragments);
+}
+
+#[test]
+fn the_front_body_id_wins_where_bodies_overlap() {
+    // Dos cuadrados que se tapan en parte: el 2 está más cerca de la cámara y corrido a la derecha
+    let frame = frame();
+    let mut back = BodyUniforms::default();
+    back.body_id = 1;
+    let mut front = BodyUniforms::new(create_model_matrix(Vec3::new(1.0, 0.0, 0.5), Vec3::repeat(1.0), Vec3::zeros()));
+    front.body_id = 2;
+    let overlap = project(&frame, Vec3::new(0.5, 0.0, 0.5)).unwrap();
+    let only_back = project(&frame, Vec3::new(-0.8, 0.0, 0.0)).unwrap();
+    let only_front = project(&frame, Vec3::new(1.8, 0.0, 0.5)).unwrap();
+
+    // El resultado no depende del orden en que se dibujan
+    for order in [[&back, &front], [&front, &back]] {
+        let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+        for body in order {
+            draw(&mut framebuffer, &frame, body, &square(RED), &mut FragmentBudget::default());
+        }
+        let id = |point: Vec3| framebuffer.id_at(point.x as usize, point.y as usize);
+        assert_eq!((id(overlap), id(only_back), id(only_front)), (Some(2), Some(1), Some(2)), "primero el {}", order[0].body_id);
+        assert_eq!(framebuffer.id_at(0, 0), None, "fondo");
+        assert_eq!(framebuffer.id_at(WIDTH, 0), None, "fuera del buffer");
+
+        framebuffer.clear();
+        assert!(framebuffer.ids.iter().all(|&id| id == 0));
+        assert_eq!(framebuffer.id_at(overlap.x as usize, overlap.y as usize), None);
+    }
+}
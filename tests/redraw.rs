@@ -0,0 +1,83 @@
+// Redibujado a demanda: sin cambios no se dibuja nada, un cambio en la escena (la cámara, por ejemplo)
+// redibuja todo y sigue haciéndolo SETTLE_FRAMES frames más, y si solo cambian las capas de encima con la
+// escena quieta se vuelven a dibujar solo ellas, sin la escena ni el post-proceso
+use lab4_shaders::redraw::{Redraw, RedrawTracker, SETTLE_FRAMES};
+
+const SCENE: u64 = 1;
+const MOVED_CAMERA: u64 = 2;
+const OVERLAYS: u64 = 10;
+const HELP_OPEN: u64 = 11;
+
+// Frames seguidos que dan Full con la escena y las capas sin cambiar, hasta el primero que no
+fn full_frames(tracker: &mut RedrawTracker, scene: u64, overlays: u64) -> u32 {
+    let mut frames = 0;
+    while tracker.update(scene, overlays, false) == Redraw::Full {
+        frames += 1;
+        assert!(frames <= SETTLE_FRAMES + 1, "sigue redibujando sin cambios");
+    }
+    frames
+}
+
+// Un tracker que ya dibujó la escena y terminó de asentarse
+fn settled() -> RedrawTracker {
+    let mut tracker = RedrawTracker::new();
+    assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::Full);
+    assert_eq!(full_frames(&mut tracker, SCENE, OVERLAYS), SETTLE_FRAMES);
+    tracker
+}
+
+#[test]
+fn nothing_is_drawn_once_the_scene_settles() {
+    // El primer frame y los SETTLE_FRAMES de después son completos; el siguiente ya no dibuja
+    let mut tracker = settled();
+    for _ in 0..100 {
+        assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::Nothing);
+    }
+}
+
+#[test]
+fn moving_the_camera_redraws_everything_and_restarts_the_tail() {
+    let mut tracker = settled();
+    assert_eq!(tracker.update(MOVED_CAMERA, OVERLAYS, false), Redraw::Full);
+    assert_eq!(full_frames(&mut tracker, MOVED_CAMERA, OVERLAYS), SETTLE_FRAMES);
+    assert_eq!(tracker.update(MOVED_CAMERA, OVERLAYS, false), Redraw::Nothing);
+
+    // Moverla en mitad de la cola la vuelve a empezar desde cero
+    assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::Full);
+    for _ in 0..SETTLE_FRAMES / 2 {
+        assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::Full);
+    }
+    assert_eq!(tracker.update(MOVED_CAMERA, OVERLAYS, false), Redraw::Full);
+    assert_eq!(full_frames(&mut tracker, MOVED_CAMERA, OVERLAYS), SETTLE_FRAMES);
+}
+
+#[test]
+fn toggling_an_overlay_while_paused_skips_the_scene_and_post_passes() {
+    let mut tracker = settled();
+    assert_eq!(tracker.update(SCENE, HELP_OPEN, false), Redraw::OverlaysOnly);
+    assert_eq!(tracker.update(SCENE, HELP_OPEN, false), Redraw::Nothing);
+    assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::OverlaysOnly);
+    assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::Nothing);
+
+    // Durante la cola el frame ya es completo y se lleva las capas nuevas
+    let mut tracker = RedrawTracker::new();
+    assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::Full);
+    assert_eq!(tracker.update(SCENE, HELP_OPEN, false), Redraw::Full);
+    assert_eq!(full_frames(&mut tracker, SCENE, HELP_OPEN), SETTLE_FRAMES - 1);
+    assert_eq!(tracker.update(SCENE, HELP_OPEN, false), Redraw::Nothing);
+}
+
+#[test]
+fn settling_and_invalidate_force_full_frames() {
+    // Mientras algo se está acumulando cada frame es completo, y la cola cuenta desde que termina
+    let mut tracker = settled();
+    for _ in 0..3 * SETTLE_FRAMES {
+        assert_eq!(tracker.update(SCENE, OVERLAYS, true), Redraw::Full);
+    }
+    assert_eq!(full_frames(&mut tracker, SCENE, OVERLAYS), SETTLE_FRAMES);
+
+    let mut tracker = settled();
+    tracker.invalidate();
+    assert_eq!(tracker.update(SCENE, OVERLAYS, false), Redraw::Full);
+    assert_eq!(full_frames(&mut tracker, SCENE, OVERLAYS), SETTLE_FRAMES);
+}
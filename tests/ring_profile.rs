@@ -0,0 +1,115 @@
+// Perfil radial de los anillos: en los puntos del perfil se devuelve la muestra tal cual, entre dos se
+// interpola, fuera del rango queda la más cercana y dos muestras con el mismo radio hacen un escalón
+use std::path::Path;
+use lab4_shaders::color::Color;
+use lab4_shaders::ring_profile::{RingProfile, RingSample};
+use lab4_shaders::texture::Texture;
+
+const PROFILE: &str = "\
+radius,optical_depth,r,g,b
+# Tres muestras y un hueco de golpe en 0.6
+0.2, 1.0, 100, 100, 100
+0.5, 3.0, 200, 160, 100
+0.6, 3.0, 200, 160, 100
+0.6, 0.0, 0, 0, 0
+0.8, 0.4, 50, 60, 70
+";
+
+fn assert_sample(sample: RingSample, optical_depth: f32, color: Color, what: &str) {
+    let same_color = [(sample.color.r, color.r), (sample.color.g, color.g), (sample.color.b, color.b)].iter().all(|(a, b)| (a - b).abs() < 1e-3);
+    assert!((sample.optical_depth - optical_depth).abs() < 1e-5 && same_color, "{}: {:?} en lugar de τ = {} y {:?}", what, sample, optical_depth, color);
+}
+
+#[test]
+fn exact_samples_come_back_unchanged() {
+    let profile = RingProfile::from_csv(PROFILE).unwrap();
+    assert_eq!(profile.samples().len(), 5);
+    assert_sample(profile.sample(0.2), 1.0, Color::new(100, 100, 100), "primera muestra");
+    assert_sample(profile.sample(0.5), 3.0, Color::new(200, 160, 100), "muestra del medio");
+    assert_sample(profile.sample(0.8), 0.4, Color::new(50, 60, 70), "última muestra");
+    // En el escalón vale el lado de afuera
+    assert_sample(profile.sample(0.6), 0.0, Color::new(0, 0, 0), "escalón");
+}
+
+#[test]
+fn between_samples_interpolates() {
+    let profile = RingProfile::from_csv(PROFILE).unwrap();
+    assert_sample(profile.sample(0.35), 2.0, Color::new(150, 130, 100), "mitad entre 0.2 y 0.5");
+    assert_sample(profile.sample(0.275), 1.5, Color::new(125, 115, 100), "un cuarto entre 0.2 y 0.5");
+    assert_sample(profile.sample(0.7), 0.2, Color::new(25, 30, 35), "después del escalón");
+    assert_sample(profile.sample(0.5999), 3.0, Color::new(200, 160, 100), "justo antes del escalón");
+
+    let opacity = profile.sample(0.35).opacity();
+    assert!((opacity - (1.0 - (-2.0f32).exp())).abs() < 1e-6, "opacidad {} para τ = 2", opacity);
+    assert!((profile.sample(0.35).transmittance() + opacity - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn out_of_range_clamps_to_the_nearest_sample() {
+    let profile = RingProfile::from_csv(PROFILE).unwrap();
+    for band in [0.0, 0.1, -3.0] {
+        assert_sample(profile.sample(band), 1.0, Color::new(100, 100, 100), "antes de la primera");
+    }
+    for band in [0.9, 1.0, 5.0] {
+        assert_sample(profile.sample(band), 0.4, Color::new(50, 60, 70), "después de la última");
+    }
+}
+
+#[test]
+fn filtering_averages_transmittance_across_the_pixel() {
+    let profile = RingProfile::from_csv(PROFILE).unwrap();
+    // Sin ancho es la muestra de ese punto
+    assert_eq!(profile.sample_filtered(0.35, 0.0), profile.sample(0.35));
+    // Un pixel que cubre por igual el anillo denso y el hueco deja pasar la mitad de la luz, más o menos
+    let straddling = profile.sample_filtered(0.6, 0.02);
+    let expected = (profile.sample(0.59).transmittance() + profile.sample(0.61).transmittance()) / 2.0;
+    assert!((straddling.transmittance() - expected).abs() < 0.05, "{} en lugar de {}", straddling.transmittance(), expected);
+    // El color es el del lado que tapa, no un promedio con el negro del hueco
+    assert!(straddling.color.r > 190.0, "color {:?}", straddling.color);
+}
+
+#[test]
+fn strip_png_reads_color_and_opacity_rows() {
+    let colors = [Color::new(255, 0, 0), Color::new(0, 255, 0), Color::new(0, 0, 255), Color::new(255, 255, 255)];
+    let opacities = [Color::new(0, 0, 0), Color::new(128, 128, 128), Color::new(255, 255, 255), Color::new(64, 64, 64)];
+    let texture = Texture::new(4, 2, colors.iter().chain(&opacities).copied().collect()).unwrap();
+    let profile = RingProfile::from_strip(&texture).unwrap();
+
+    let radii: Vec<f32> = profile.samples().iter().map(|sample| sample.radius).collect();
+    assert_eq!(radii, [0.125, 0.375, 0.625, 0.875]);
+    assert_eq!(profile.samples()[1].color, colors[1]);
+    assert!((profile.samples()[1].opacity() - 128.0 / 255.0).abs() < 1e-4);
+    assert!(profile.samples()[0].optical_depth == 0.0);
+    // El blanco no llega a tapar todo: la profundidad óptica tiene que ser finita
+    assert!((profile.samples()[2].opacity() - 0.99).abs() < 1e-4);
+
+    let one_row = Texture::new(4, 1, colors.to_vec()).unwrap();
+    assert!(RingProfile::from_strip(&one_row).is_err());
+}
+
+#[test]
+fn invalid_csv_reports_the_line() {
+    let cases = [
+        ("0.1, 1.0, 10, 10\n", "línea 1"),
+        ("0.1, 1.0, 10, 10, 10\n0.05, 1.0, 10, 10, 10\n", "de menor a mayor"),
+        ("0.1, -1.0, 10, 10, 10\n", "negativa"),
+        ("1.5, 1.0, 10, 10, 10\n", "rango de 0 a 1"),
+        ("0.1, 1.0, 10, 10, 300\n", "línea 1"),
+        ("# solo comentarios\n", "no tiene muestras"),
+        ("radio,tau,r,g,b\n0.1, 1.0, x, 10, 10\n", "línea 2"),
+    ];
+    for (source, message) in cases {
+        let error = RingProfile::from_csv(source).expect_err(source);
+        assert!(error.contains(message), "\"{}\" no menciona \"{}\"", error, message);
+    }
+}
+
+#[test]
+fn bundled_saturn_profile_matches_the_asset() {
+    let profile = RingProfile::load(Path::new("assets/rings/saturn.csv")).unwrap();
+    assert_eq!(&profile, RingProfile::saturn().as_ref());
+    // Anillo B denso, división de Cassini y hueco de Encke casi vacíos
+    assert!(profile.sample(0.45).opacity() > 0.9);
+    assert!(profile.sample(0.72).opacity() < 0.2);
+    assert_eq!(profile.sample(0.948).optical_depth, 0.0);
+}
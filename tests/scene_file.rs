@@ -0,0 +1,15 @@
+// Los archivos de escena de scenes/ que dicen repetir una escena incluida la repiten exacta
+use lab4_shaders::scene::SceneConfig;
+
+#[test]
+fn scene5_file_matches_builtin_scene() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/scenes/scene5.toml");
+    let file = SceneConfig::from_file(path).expect("no se pudo leer scenes/scene5.toml");
+    let builtin = SceneConfig::builtin(5);
+    assert_eq!(file.bodies.len(), builtin.bodies.len());
+    for (from_file, expected) in file.bodies.iter().zip(&builtin.bodies) {
+        assert_eq!(from_file, expected, "cuerpo {}", expected.name);
+    }
+    assert_eq!(file.tour, builtin.tour);
+    assert_eq!(file, builtin);
+}
@@ -0,0 +1,181 @@
+// La sesión guardada en session.toml tiene que volver exactamente igual, y las sesiones de versiones
+// anteriores (o editadas a mano) se tienen que poder seguir leyendo
+use nalgebra_glm::Vec3;
+use lab4_shaders::camera::{Camera, Projection};
+use lab4_shaders::clock::SimClock;
+use lab4_shaders::color::Color;
+use lab4_shaders::loupe::Loupe;
+use lab4_shaders::post::ColorGrade;
+use lab4_shaders::quality::{QualitySettings, QualityTier};
+use lab4_shaders::scene::RenderOptions;
+use lab4_shaders::session::{AppState, CommandLineOptions, STEREO_SEPARATION};
+use lab4_shaders::shaders::ShaderType;
+
+// Todos los campos distintos del valor por defecto, para que un campo que no se guarde se note
+fn populated_state() -> AppState {
+    let mut camera = Camera::new(Vec3::new(3.5, 2.0, -7.25), Vec3::new(0.5, -1.0, 2.0), Vec3::new(0.0, 0.0, 1.0));
+    camera.projection = Projection::Orthographic { height: 12.5 };
+    let mut clock = SimClock::new();
+    for _ in 0..3 {
+        clock.tick();
+    }
+    clock.faster();
+    clock.faster();
+    clock.reverse();
+    clock.toggle_pause();
+
+    AppState {
+        scene: 8,
+        scene_file: Some("escenas/binaria.toml".to_string()),
+        camera,
+        clock,
+        options: RenderOptions {
+            show_lod_tiers: true,
+            debug_mode: 5,
+            seed: 42,
+            model_shader: Some(ShaderType::GasPlanet),
+            toon: true,
+            fragment_budget: 123_456,
+            triangle_fragment_cap: 789,
+            graticule: true,
+            particle_rings: true,
+            adaptive_ring: true,
+            ambient_occlusion: true,
+            starfield: true,
+            thermal: true,
+            cull_backfaces: true,
+            saturn_rings: true,
+            ..RenderOptions::default()
+        },
+        grade: Some(ColorGrade { exposure: 0.7, saturation: 1.35, contrast: 0.85, white_balance: Color::new(250, 240, 230) }),
+        quality: QualitySettings::from(QualityTier::HalfResolution),
+        dither: true,
+        show_depth: true,
+        motion_blur: true,
+        lens_flare: true,
+        use_lut: false,
+        still: true,
+        progressive: true,
+        checkerboard: true,
+        anaglyph: true,
+        stereo_separation: 0.075,
+        minimap: true,
+        show_labels: false,
+        frame_diff: true,
+        loupe: Some(Loupe { magnification: 9 }),
+    }
+}
+
+#[test]
+fn session_round_trip() {
+    let state = populated_state();
+    let source = state.to_toml().expect("no se pudo escribir la sesión");
+    let loaded = AppState::from_toml(&source).expect("no se pudo leer la sesión");
+
+    assert_eq!(loaded.scene, state.scene);
+    assert_eq!(loaded.scene_file, state.scene_file);
+    assert_eq!(loaded.camera.eye, state.camera.eye);
+    assert_eq!(loaded.camera.center, state.camera.center);
+    assert_eq!(loaded.camera.up, state.camera.up);
+    assert_eq!(loaded.camera.projection, state.camera.projection);
+    assert_eq!(loaded.clock, state.clock);
+    assert_eq!(loaded.clock.label(), "pausa (x-4)");
+    assert_eq!(loaded.options, state.options);
+    assert_eq!(loaded.grade, state.grade);
+    assert_eq!(loaded.quality, state.quality);
+    assert_eq!(loaded.dither, state.dither);
+    assert_eq!(loaded.show_depth, state.show_depth);
+    assert_eq!(loaded.motion_blur, state.motion_blur);
+    assert_eq!(loaded.lens_flare, state.lens_flare);
+    assert_eq!(loaded.use_lut, state.use_lut);
+    assert_eq!(loaded.still, state.still);
+    assert_eq!(loaded.progressive, state.progressive);
+    assert_eq!(loaded.checkerboard, state.checkerboard);
+    assert_eq!(loaded.anaglyph, state.anaglyph);
+    assert_eq!(loaded.stereo_separation, state.stereo_separation);
+    assert_eq!(loaded.minimap, state.minimap);
+    assert_eq!(loaded.show_labels, state.show_labels);
+    assert_eq!(loaded.frame_diff, state.frame_diff);
+    assert_eq!(loaded.loupe, state.loupe);
+    // Por si se agrega un campo y no se compara arriba
+    assert_eq!(loaded, state);
+}
+
+#[test]
+fn session_missing_and_unknown_fields() {
+    // Una sesión vieja con pocos campos y uno que ya no existe
+    let source = "
+        scene = 4
+        bloom = true
+
+        [options]
+        toon = true
+        fog_density = 0.3
+
+        [camera]
+        eye = [0.0, 5.0, 5.0]
+    ";
+    let loaded = AppState::from_toml(source).expect("no se pudo leer la sesión");
+    let defaults = AppState::default();
+
+    assert_eq!(loaded.scene, 4);
+    assert!(loaded.options.toon);
+    assert_eq!(loaded.options.fragment_budget, defaults.options.fragment_budget);
+    assert_eq!(loaded.camera.eye, Vec3::new(0.0, 5.0, 5.0));
+    assert_eq!(loaded.camera.center, defaults.camera.center);
+    assert_eq!(loaded.camera.projection, Projection::Perspective);
+    assert_eq!(loaded.clock, SimClock::new());
+    assert_eq!(loaded.quality, QualitySettings::default());
+    assert_eq!(loaded.stereo_separation, STEREO_SEPARATION);
+    assert!(loaded.show_labels);
+    assert!(loaded.use_lut);
+    assert_eq!(loaded.loupe, None);
+}
+
+#[test]
+fn session_out_of_range_values() {
+    // Valores editados a mano que no se pueden dibujar vuelven al rango
+    let source = "
+        stereo_separation = 50.0
+        loupe = { magnification = 0 }
+
+        [clock]
+        speed = 0.0
+
+        [camera]
+        eye = [1.0, 1.0, 1.0]
+        center = [1.0, 1.0, 1.0]
+    ";
+    let loaded = AppState::from_toml(source).expect("no se pudo leer la sesión");
+    let defaults = AppState::default();
+
+    assert_eq!(loaded.camera.eye, defaults.camera.eye);
+    assert_eq!(loaded.clock.label(), "x1");
+    assert!(loaded.stereo_separation <= 0.2);
+    assert!(loaded.loupe.is_some_and(|loupe| loupe.magnification >= 2));
+    assert!(AppState::from_toml("scene = \"ocho\"").is_err());
+}
+
+#[test]
+fn command_line_options_win_over_the_restored_session() {
+    // Una sesión guardada con otra semilla y sin caricatura ni dithering, retomada con --seed 7 --toon --dither
+    let mut saved = populated_state();
+    saved.options.toon = false;
+    saved.dither = false;
+    let restored = AppState::from_toml(&saved.to_toml().unwrap()).unwrap();
+    let command_line = CommandLineOptions { seed: Some(7), toon: true, dither: true, model_shader: Some(ShaderType::Moon), fragment_budget: Some(1000), ..CommandLineOptions::default() };
+    let state = restored.clone().with_command_line(&command_line);
+
+    assert_eq!(state.options.seed, 7);
+    assert!(state.options.toon && state.dither);
+    assert_eq!(state.options.model_shader, Some(ShaderType::Moon));
+    assert_eq!(state.options.fragment_budget, 1000);
+    // Lo que no se pasó queda como en la sesión, también lo que estaba prendido
+    assert_eq!(state.scene, saved.scene);
+    assert_eq!(state.camera.eye, saved.camera.eye);
+    assert!(state.options.graticule && state.options.thermal && state.progressive);
+    assert_eq!(state, AppState { options: state.options, dither: true, ..restored.clone() });
+
+    // Sin nada en la línea de comandos la sesión vuelve tal cual
+    assert_eq!(restored.clone().with_command_line(&CommandLineOptions::default()), restored);
+}
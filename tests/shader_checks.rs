@@ -0,0 +1,16 @@
+// Las mismas pruebas de los shaders que corre --check-shaders, para que también fallen con cargo test
+use lab4_shaders::shader_checks::run_checks;
+
+#[test]
+fn every_shader_check_passes() {
+    let checks = run_checks();
+    for check in &checks {
+        if check.passed {
+            println!("ok     {}: {}", check.name, check.detail);
+        } else {
+            println!("FALLA  {}: {}", check.name, check.detail);
+        }
+    }
+    let failed: Vec<&str> = checks.iter().filter(|check| !check.passed).map(|check| check.name.as_str()).collect();
+    assert!(failed.is_empty(), "{} de {} pruebas fallaron: {}", failed.len(), checks.len(), failed.join(", "));
+}
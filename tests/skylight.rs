@@ -0,0 +1,90 @@
+// Luz ambiental del fondo: el promedio por direcciones de un fondo liso, de uno mitad claro y mitad oscuro
+// (procedural y como imagen) y la que arma la escena según lo que se ve detrás de los cuerpos
+use nalgebra_glm::Vec3;
+use lab4_shaders::color::Color;
+use lab4_shaders::scene::{skylight, RenderOptions, SceneConfig, BACKGROUND_COLOR, SKYLIGHT_STRENGTH};
+use lab4_shaders::texture::{AmbientCube, Texture};
+
+const EPSILON: f32 = 0.01;
+
+fn assert_color(actual: Color, expected: Color, context: &str) {
+    let close = (actual.r - expected.r).abs() < EPSILON && (actual.g - expected.g).abs() < EPSILON && (actual.b - expected.b).abs() < EPSILON;
+    assert!(close, "{}: {:?} en lugar de {:?}", context, actual, expected);
+}
+
+fn gray(value: f32) -> Color {
+    Color { r: value, g: value, b: value }
+}
+
+// Normales de los ejes, en el orden de AmbientCube::faces, y algunas intermedias
+fn normals() -> Vec<Vec3> {
+    vec![
+        Vec3::x(), -Vec3::x(), Vec3::y(), -Vec3::y(), Vec3::z(), -Vec3::z(),
+        Vec3::new(1.0, 1.0, 0.0), Vec3::new(-0.3, 0.8, -0.5), Vec3::new(0.2, -0.9, 0.4),
+    ]
+}
+
+#[test]
+fn uniform_background_gives_exactly_its_color() {
+    let teal = Color::from_hex(BACKGROUND_COLOR);
+    let texture = Texture::new(8, 4, vec![teal; 32]).unwrap();
+    for (name, cube) in [
+        ("uniforme", AmbientCube::uniform(teal)),
+        ("procedural", AmbientCube::from_environment(|_| teal)),
+        ("imagen", AmbientCube::from_equirect(&texture)),
+    ] {
+        for normal in normals() {
+            assert_color(cube.sample(&normal), teal, &format!("{} hacia {:?}", name, normal));
+        }
+        assert_color(cube.average(), teal, name);
+    }
+}
+
+#[test]
+fn half_bright_half_dark_sky_lights_each_direction() {
+    // Blanco arriba (y > 0) y negro abajo, primero procedural y después como imagen (la mitad de arriba
+    // de las filas es el norte)
+    let procedural = AmbientCube::from_environment(|direction| if direction.y > 0.0 { gray(255.0) } else { Color::BLACK });
+    let texels = (0..64 * 32).map(|index| if index / 64 < 16 { gray(255.0) } else { Color::BLACK }).collect();
+    let image = AmbientCube::from_equirect(&Texture::new(64, 32, texels).unwrap());
+
+    for (name, cube) in [("procedural", procedural), ("imagen", image)] {
+        // Arriba ve todo el cielo claro, abajo todo el oscuro y los costados mitad y mitad
+        let expected = [127.5, 127.5, 255.0, 0.0, 127.5, 127.5];
+        for (face, value) in expected.iter().enumerate() {
+            assert!((cube.faces[face].r - value).abs() < 0.5, "{} cara {}: {:?}", name, face, cube.faces[face]);
+        }
+        // Entre dos caras se mezclan según el coseno al cuadrado
+        let diagonal = cube.sample(&Vec3::new(1.0, 1.0, 0.0));
+        assert!((diagonal.r - (127.5 + 255.0) / 2.0).abs() < 0.5, "{} diagonal: {:?}", name, diagonal);
+        assert!(cube.sample(&Vec3::new(0.0, -1.0, 0.0)).r < 0.5, "{}", name);
+    }
+}
+
+#[test]
+fn scene_skylight_follows_the_background() {
+    let mut scene = SceneConfig::builtin(2);
+    let mut options = RenderOptions::default();
+    let teal = Color::from_hex(BACKGROUND_COLOR) * SKYLIGHT_STRENGTH;
+    assert_color(skylight(&scene, &options).sample(&-Vec3::z()), teal, "fondo liso");
+
+    // El cielo de estrellas es mucho más oscuro que el teal
+    options.starfield = true;
+    assert!(skylight(&scene, &options).average().g < teal.g / 2.0);
+
+    // En infrarrojo y con la fuerza en 0 el fondo no ilumina
+    options.thermal = true;
+    assert_eq!(skylight(&scene, &options), AmbientCube::BLACK);
+    options.thermal = false;
+    scene.skylight = 0.0;
+    assert_eq!(skylight(&scene, &options), AmbientCube::BLACK);
+}
+
+#[test]
+fn scene_file_reads_skylight_strength() {
+    let scene = SceneConfig::from_toml("skylight = 0.5\n").unwrap();
+    assert_eq!(scene.skylight, 0.5);
+    assert_eq!(SceneConfig::from_toml("").unwrap().skylight, SKYLIGHT_STRENGTH);
+    assert!(SceneConfig::from_toml("skylight = -1.0\n").is_err());
+    assert!(SceneConfig::from_toml("skybox = \"no_existe.png\"\n").is_err());
+}
@@ -0,0 +1,118 @@
+// Fragmentos en streaming: render entrega cada fragmento al sombreado apenas sale del triángulo en vez de
+// juntar primero todos en un Vec (como hacía antes con `triangle`). El resultado tiene que ser el mismo
+// pixel por pixel, y con el RenderScratch ya usado una vez dibujar de nuevo no pide memoria
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use nalgebra_glm::Vec3;
+use lab4_shaders::camera::Camera;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::mesh::generate_uv_sphere;
+use lab4_shaders::pipeline::{build_uniforms, create_model_matrix, diffuse_light, render, BodyUniforms, FragmentBudget, FrameUniforms, RenderScratch, RenderStats, DEFAULT_DEPTH_RANGE};
+use lab4_shaders::shaders::{fragment_shader, vertex_shader, FragmentShader, ShaderType};
+use lab4_shaders::triangle::triangle;
+use lab4_shaders::vertex::Vertex;
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 48;
+
+// Cuenta las reservas de memoria de cada hilo, así las pruebas que corren en paralelo no se mezclan
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations_during(action: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    action();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+fn frame() -> FrameUniforms {
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::y());
+    build_uniforms(&camera, 1.5, WIDTH, HEIGHT, DEFAULT_DEPTH_RANGE)
+}
+
+fn body() -> BodyUniforms {
+    BodyUniforms::new(create_model_matrix(Vec3::new(0.3, -0.2, 0.0), Vec3::repeat(3.0), Vec3::new(0.4, 0.8, 0.0)))
+}
+
+// El camino de antes: todos los fragmentos de cada triángulo en un Vec y después el sombreado y la
+// prueba de profundidad de cada uno
+fn render_materialized(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, vertices: &[Vertex], shader: &ShaderType) {
+    framebuffer.set_current_id(body.body_id);
+    let transformed: Vec<Vertex> = vertices.iter().map(|vertex| vertex_shader(vertex, frame, body)).collect();
+    for tri in transformed.chunks_exact(3) {
+        for mut fragment in triangle(&tri[0], &tri[1], &tri[2], &frame.viewport) {
+            fragment.normal = fragment.normal.normalize();
+            fragment.light = diffuse_light(&fragment.normal, &fragment.world_pos, &frame.lights, &frame.ambient_light(&fragment.normal));
+            fragment.intensity = (fragment.light.x + fragment.light.y + fragment.light.z) / 3.0;
+            framebuffer.set_current_color(fragment_shader(&fragment, frame, body, shader));
+            framebuffer.point(fragment.position.x as usize, fragment.position.y as usize, fragment.depth);
+        }
+    }
+}
+
+fn render_streamed(framebuffer: &mut Framebuffer, frame: &FrameUniforms, body: &BodyUniforms, vertices: &[Vertex], shader: &ShaderType, scratch: &mut RenderScratch) -> RenderStats {
+    let mut stats = RenderStats::default();
+    render(framebuffer, frame, body, vertices, shader, &mut FragmentBudget::default(), &mut stats, scratch);
+    stats
+}
+
+#[test]
+fn streaming_matches_materializing_every_fragment() {
+    let (frame, body) = (frame(), body());
+    let sphere = generate_uv_sphere(12, 24);
+    for shader in [ShaderType::Earth, ShaderType::Moon, ShaderType::Triplanar] {
+        assert!(!shader.flat_shading());
+        let mut materialized = Framebuffer::new(WIDTH, HEIGHT);
+        render_materialized(&mut materialized, &frame, &body, &sphere, &shader);
+        let mut streamed = Framebuffer::new(WIDTH, HEIGHT);
+        let stats = render_streamed(&mut streamed, &frame, &body, &sphere, &shader, &mut RenderScratch::new());
+
+        assert!(stats.depth_passed > 0 && stats.invalid_fragments == 0, "{:?}", shader);
+        assert!(materialized.buffer == streamed.buffer, "{:?}: colores distintos", shader);
+        assert!(materialized.zbuffer == streamed.zbuffer, "{:?}: profundidades distintas", shader);
+        assert!(materialized.ids == streamed.ids, "{:?}: ids distintos", shader);
+    }
+}
+
+#[test]
+fn streaming_with_a_used_scratch_does_not_allocate() {
+    let (frame, body) = (frame(), body());
+    let sphere = generate_uv_sphere(12, 24);
+    // Un shader que no pide memoria por fragmento, así lo que se cuenta es solo lo del pipeline
+    let shader = ShaderType::Earth;
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let mut scratch = RenderScratch::new();
+
+    // La primera vez el scratch (y los shaders con tablas precalculadas) crecen a su tamaño
+    let stats = render_streamed(&mut framebuffer, &frame, &body, &sphere, &shader, &mut scratch);
+    let drawn_triangles = stats.triangles - stats.culled_triangles;
+    assert!(drawn_triangles > 100);
+
+    framebuffer.clear();
+    let streamed = allocations_during(|| {
+        render_streamed(&mut framebuffer, &frame, &body, &sphere, &shader, &mut scratch);
+    });
+    // Antes: un Vec de vértices más uno por cada triángulo que da fragmentos
+    framebuffer.clear();
+    let materialized = allocations_during(|| render_materialized(&mut framebuffer, &frame, &body, &sphere, &shader));
+
+    assert_eq!(streamed, 0, "render pidió memoria con el scratch ya usado");
+    assert!(materialized > drawn_triangles / 2, "{} reservas para {} triángulos", materialized, drawn_triangles);
+}
@@ -0,0 +1,129 @@
+// Rasterizado por teselas: la imagen (color, profundidad e ids) es idéntica a la de un solo hilo desde
+// varias cámaras, las listas de cada tesela, y la copia de una tesela del framebuffer de ida y vuelta
+use nalgebra_glm::Vec3;
+use lab4_shaders::camera::Camera;
+use lab4_shaders::framebuffer::{Framebuffer, Rect};
+use lab4_shaders::pipeline::{build_uniforms, RenderStats};
+use lab4_shaders::scene::{procedural_meshes, render_scene, scene_depth_range, RenderOptions, SceneConfig, SceneMeshes, SceneState};
+use lab4_shaders::tiles::{histogram_bucket, histogram_label, TileBins, TILE_HISTOGRAM_BUCKETS, TILE_SIZE};
+
+const WIDTH: usize = 300;
+const HEIGHT: usize = 200;
+const TIME: f32 = 90.0;
+
+// Frame de la escena desde `eye` y las estadísticas sumadas de todos los cuerpos
+fn draw(number: u32, eye: Vec3, meshes: &SceneMeshes, options: &RenderOptions) -> (Framebuffer, RenderStats) {
+    let scene = SceneConfig::builtin(number);
+    let camera = Camera::new(eye, Vec3::zeros(), Vec3::y());
+    let mut state = SceneState::new(&scene, options.seed, meshes);
+    state.update(TIME);
+    let depth_range = scene_depth_range(&state.bodies_seen_from(&scene, TIME, camera.eye, meshes), meshes, &camera);
+    let uniforms = build_uniforms(&camera, TIME, WIDTH, HEIGHT, depth_range);
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    framebuffer.set_background_color(scene.background);
+    framebuffer.dither = true;
+    let mut total = RenderStats::default();
+    for body in render_scene(&mut framebuffer, number, &scene, &uniforms, meshes, &mut state, options) {
+        total.add(&body.stats);
+    }
+    (framebuffer, total)
+}
+
+#[test]
+fn tiled_image_is_identical_to_single_thread() {
+    let meshes = procedural_meshes();
+    // El sistema solar de lejos y de cerca, los anillos translúcidos de frente y de canto y un planeta que
+    // llena la pantalla, también en caricatura y con anillos de partículas
+    let cases = [
+        (8, Vec3::new(0.0, 6.0, 18.0), RenderOptions::default()),
+        (8, Vec3::new(-3.0, 1.0, 4.0), RenderOptions::default()),
+        (4, Vec3::new(0.0, 2.0, 3.0), RenderOptions::default()),
+        (4, Vec3::new(3.0, 0.05, 0.5), RenderOptions { toon: true, ..RenderOptions::default() }),
+        (4, Vec3::new(0.5, 1.0, 2.5), RenderOptions { particle_rings: true, ..RenderOptions::default() }),
+        (2, Vec3::new(0.3, 0.2, 1.3), RenderOptions { starfield: true, ..RenderOptions::default() }),
+    ];
+    for (number, eye, options) in cases {
+        let (single, single_stats) = draw(number, eye, &meshes, &RenderOptions { threads: 1, ..options });
+        let (tiled, tiled_stats) = draw(number, eye, &meshes, &RenderOptions { threads: 4, ..options });
+        let context = format!("escena {} desde {:?}", number, eye);
+        assert_eq!(single_stats.tiles, 0, "{}", context);
+        assert!(tiled_stats.tiles > 1, "{}: no se usaron teselas", context);
+        assert_eq!(tiled_stats.tile_triangles.iter().sum::<usize>(), tiled_stats.tiles, "{}", context);
+
+        let differing = (0..single.buffer.len()).filter(|&index| single.buffer[index] != tiled.buffer[index]).count();
+        assert_eq!(differing, 0, "{}: {} pixeles distintos", context, differing);
+        assert!(single.zbuffer.iter().zip(&tiled.zbuffer).all(|(a, b)| a.to_bits() == b.to_bits()), "{}: profundidad distinta", context);
+        assert_eq!(single.ids, tiled.ids, "{}", context);
+        assert_eq!(
+            (single_stats.triangles, single_stats.culled_triangles, single_stats.skipped_triangles, single_stats.fragments, single_stats.depth_passed),
+            (tiled_stats.triangles, tiled_stats.culled_triangles, tiled_stats.skipped_triangles, tiled_stats.fragments, tiled_stats.depth_passed),
+            "{}", context,
+        );
+    }
+}
+
+#[test]
+fn small_budget_falls_back_to_single_thread() {
+    // Si el presupuesto no alcanza para todas las cajas, importa qué triángulo llega primero y se
+    // dibuja en orden en un solo hilo
+    let meshes = procedural_meshes();
+    let eye = Vec3::new(0.0, 6.0, 18.0);
+    let options = RenderOptions { fragment_budget: 2000, ..RenderOptions::default() };
+    let (single, _) = draw(8, eye, &meshes, &RenderOptions { threads: 1, ..options });
+    let (threaded, stats) = draw(8, eye, &meshes, &RenderOptions { threads: 4, ..options });
+    assert!(stats.skipped_triangles > 0);
+    assert_eq!(single.buffer, threaded.buffer);
+}
+
+#[test]
+fn bins_follow_the_triangle_bounds() {
+    let mut bins = TileBins::new(150, 100);
+    let size = TILE_SIZE as i32;
+    // Dentro de la primera tesela, cruzando cuatro y saliéndose del framebuffer por la derecha y abajo
+    bins.insert(0, (0, 0, 10, 10));
+    bins.insert(3, (size - 1, size - 1, size, size));
+    bins.insert(6, (140, 90, 400, 300));
+    bins.insert(9, (-20, -20, -1, -1));
+
+    let occupied = bins.occupied();
+    let lists: Vec<(Rect, Vec<usize>)> = occupied.iter().map(|(rect, triangles)| (*rect, triangles.to_vec())).collect();
+    let last = Rect::new(2 * TILE_SIZE, TILE_SIZE, 150 - 2 * TILE_SIZE, 100 - TILE_SIZE);
+    assert_eq!(lists, vec![
+        (Rect::new(0, 0, TILE_SIZE, TILE_SIZE), vec![0, 3]),
+        (Rect::new(TILE_SIZE, 0, TILE_SIZE, TILE_SIZE), vec![3]),
+        (Rect::new(0, TILE_SIZE, TILE_SIZE, 100 - TILE_SIZE), vec![3]),
+        (Rect::new(TILE_SIZE, TILE_SIZE, TILE_SIZE, 100 - TILE_SIZE), vec![3]),
+        (last, vec![6]),
+    ]);
+
+    let mut histogram = [0; TILE_HISTOGRAM_BUCKETS];
+    (histogram[0], histogram[1]) = (4, 1);
+    assert_eq!(bins.histogram(), histogram);
+    assert_eq!(histogram_label(&histogram), "1: 4, 2-3: 1");
+    assert_eq!([1, 2, 3, 4, 127, 128, 100_000].map(histogram_bucket), [0, 1, 1, 2, 6, 7, 7]);
+
+    // Al reusarlas para otro tamaño quedan vacías
+    bins.reset(64, 64);
+    assert!(bins.occupied().is_empty());
+}
+
+#[test]
+fn tile_copy_round_trips() {
+    let mut framebuffer = Framebuffer::new(100, 80);
+    for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+        *pixel = index as u32;
+    }
+    let rect = Rect::new(64, 64, 36, 16);
+    let mut tile = framebuffer.tile(rect);
+    assert_eq!((tile.width, tile.height), (36, 16));
+    assert_eq!(tile.buffer[0], (64 * 100 + 64) as u32);
+    assert!(tile.point(1, 2, 0.5));
+
+    framebuffer.write_tile(&tile, rect);
+    assert_eq!(framebuffer.zbuffer[(64 + 2) * 100 + 64 + 1], 0.5);
+    assert_eq!(framebuffer.zbuffer.iter().filter(|depth| depth.is_finite()).count(), 1);
+    assert_eq!(framebuffer.buffer[(64 + 2) * 100 + 64], ((64 + 2) * 100 + 64) as u32, "lo demás no cambia");
+
+    assert_eq!(Rect::new(0, 0, 10, 10).intersection(&Rect::new(5, 8, 10, 10)), Some(Rect::new(5, 8, 5, 2)));
+    assert_eq!(Rect::new(0, 0, 10, 10).intersection(&Rect::new(10, 0, 5, 5)), None);
+}
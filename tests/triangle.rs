@@ -0,0 +1,82 @@
+// Rasterizado de un triángulo ya en pantalla: la caja envolvente se recorta a la vista y lo que queda
+// fuera no genera fragmentos, aunque tenga coordenadas negativas o gigantes
+use nalgebra_glm::{Vec2, Vec3};
+use lab4_shaders::framebuffer::Rect;
+use lab4_shaders::triangle::{screen_bounds, screen_coverage, triangle};
+use lab4_shaders::vertex::Vertex;
+
+// Vértice con la posición ya en pixeles
+fn at(x: f32, y: f32) -> Vertex {
+    Vertex { transformed_position: Vec3::new(x, y, 0.5), ..Vertex::new(Vec3::zeros(), Vec3::z(), Vec2::zeros()) }
+}
+
+fn inside(clip: &Rect, position: &Vec2) -> bool {
+    clip.contains(position.x as usize, position.y as usize) && position.x >= 0.0 && position.y >= 0.0
+}
+
+#[test]
+fn off_screen_triangles_have_no_box_and_no_fragments() {
+    let clip = Rect::new(0, 0, 100, 80);
+    for (name, [a, b, c]) in [
+        ("a la izquierda", [at(-50.0, 10.0), at(-10.0, 10.0), at(-30.0, 40.0)]),
+        ("arriba", [at(10.0, -60.0), at(50.0, -60.0), at(30.0, -5.0)]),
+        ("a la derecha", [at(120.0, 10.0), at(160.0, 10.0), at(140.0, 40.0)]),
+        ("abajo", [at(10.0, 90.0), at(50.0, 90.0), at(30.0, 200.0)]),
+    ] {
+        assert_eq!(screen_bounds(&a, &b, &c, &clip), None, "{}", name);
+        assert_eq!(screen_coverage(&a, &b, &c, &clip), None, "{}", name);
+        assert!(triangle(&a, &b, &c, &clip).is_empty(), "{}", name);
+    }
+
+    // Fuera de una vista que no empieza en la esquina, aunque esté dentro del framebuffer
+    let viewport = Rect::new(50, 40, 50, 40);
+    let [a, b, c] = [at(5.0, 5.0), at(40.0, 5.0), at(20.0, 30.0)];
+    assert_eq!(screen_bounds(&a, &b, &c, &viewport), None);
+    assert!(triangle(&a, &b, &c, &viewport).is_empty());
+}
+
+#[test]
+fn partially_visible_triangles_are_clipped_to_the_view() {
+    let clip = Rect::new(0, 0, 100, 80);
+    let [a, b, c] = [at(60.0, 50.0), at(300.0, 60.0), at(90.0, 400.0)];
+    assert_eq!(screen_bounds(&a, &b, &c, &clip), Some((60, 50, 99, 79)));
+    assert_eq!(screen_coverage(&a, &b, &c, &clip), Some(40 * 30));
+
+    let fragments = triangle(&a, &b, &c, &clip);
+    assert!(!fragments.is_empty());
+    assert!(fragments.iter().all(|fragment| inside(&clip, &fragment.position)), "fragmentos fuera de la vista");
+
+    // Con una vista corrida solo quedan los pixeles dentro de ella
+    let viewport = Rect::new(70, 55, 20, 10);
+    assert_eq!(screen_bounds(&a, &b, &c, &viewport), Some((70, 55, 89, 64)));
+    let fragments = triangle(&a, &b, &c, &viewport);
+    assert!(!fragments.is_empty());
+    assert!(fragments.iter().all(|fragment| inside(&viewport, &fragment.position)));
+}
+
+#[test]
+fn negative_coordinates_clamp_to_the_corner_instead_of_wrapping() {
+    // Un triángulo enorme que empieza en coordenadas negativas y tapa la esquina de arriba a la izquierda
+    // hasta la diagonal x + y = 60
+    let clip = Rect::new(0, 0, 100, 80);
+    let [a, b, c] = [at(-1000.0, -1000.0), at(1060.0, -1000.0), at(-1000.0, 1060.0)];
+    assert_eq!(screen_bounds(&a, &b, &c, &clip), Some((0, 0, 99, 79)));
+    // El mismo desde más lejos pero que no llega a cruzar la pantalla
+    assert_eq!(screen_bounds(&at(-1000.0, -1000.0), &at(30.0, -1000.0), &at(-1000.0, 30.0), &clip), Some((0, 0, 30, 30)));
+
+    let fragments = triangle(&a, &b, &c, &clip);
+    assert!(fragments.iter().all(|fragment| inside(&clip, &fragment.position)));
+    assert!(fragments.iter().any(|fragment| fragment.position == Vec2::new(0.0, 0.0)));
+    // Cada pixel con el centro debajo de la diagonal, y como mucho los 60 que la tocan
+    let expected = (0..100).map(|x| (0..80).filter(|y| x + y + 1 < 60).count()).sum::<usize>();
+    assert!(fragments.iter().all(|fragment| fragment.position.x + fragment.position.y + 1.0 <= 60.0));
+    assert!((expected..=expected + 60).contains(&fragments.len()), "{} fragmentos, se esperaban {}", fragments.len(), expected);
+}
+
+#[test]
+fn triangles_without_area_have_no_box() {
+    let clip = Rect::new(0, 0, 100, 80);
+    let [a, b, c] = [at(10.0, 10.0), at(50.0, 50.0), at(90.0, 90.0)];
+    assert_eq!(screen_bounds(&a, &b, &c, &clip), None);
+    assert!(triangle(&a, &b, &c, &clip).is_empty());
+}
@@ -0,0 +1,101 @@
+// Cuerpos de dos caras y orden de los vértices de los OBJ: una cara trasera de un cuerpo de dos caras se
+// ilumina igual que su espejo de frente, y un OBJ con caras al revés se lee con todas hacia afuera
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+use lab4_shaders::camera::Camera;
+use lab4_shaders::color::Color;
+use lab4_shaders::fragments::Fragments;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::obj_loader::Obj;
+use lab4_shaders::pipeline::{build_uniforms, render, BodyUniforms, FragmentBudget, FrameUniforms, Light, RenderScratch, RenderStats, DEFAULT_DEPTH_RANGE};
+use lab4_shaders::shaders::FragmentShader;
+use lab4_shaders::vertex::Vertex;
+
+const SIZE: usize = 64;
+
+// Solo la luz difusa sobre un gris, así cualquier diferencia en la normal cambia el color
+struct Lit;
+
+impl FragmentShader for Lit {
+    fn shade(&self, fragment: &Fragments, _frame: &FrameUniforms, _body: &BodyUniforms) -> Color {
+        Color::new(200, 200, 200) * fragment.light
+    }
+}
+
+// Triángulo en el plano z = 0 con la normal hacia la cámara (antihorario visto desde +z) o, dado
+// vuelta, con el orden al revés y la normal hacia atrás
+fn triangle(mirrored: bool) -> Vec<Vertex> {
+    let corners = [Vec3::new(-0.5, -0.5, 0.0), Vec3::new(0.5, -0.5, 0.0), Vec3::new(0.0, 0.5, 0.0)];
+    let (order, normal) = if mirrored { ([0, 2, 1], Vec3::new(0.0, 0.0, -1.0)) } else { ([0, 1, 2], Vec3::new(0.0, 0.0, 1.0)) };
+    order.iter().map(|&corner| Vertex::new(corners[corner], normal, Vec2::new(0.0, 0.0))).collect()
+}
+
+fn draw(vertices: &[Vertex], two_sided: bool, cull_backfaces: bool) -> (Vec<u32>, RenderStats) {
+    let camera = Camera::new(Vec3::new(0.0, 0.0, 3.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+    let frame = FrameUniforms {
+        lights: vec![Light::directional(Vec3::new(0.3, 0.2, 1.0), Color::new(255, 255, 255), 1.0)],
+        cull_backfaces,
+        ..build_uniforms(&camera, 0.0, SIZE, SIZE, DEFAULT_DEPTH_RANGE)
+    };
+    let mut body = BodyUniforms::new(Mat4::identity());
+    body.two_sided = two_sided;
+    let mut framebuffer = Framebuffer::new(SIZE, SIZE);
+    let mut stats = RenderStats::default();
+    render(&mut framebuffer, &frame, &body, vertices, &Lit, &mut FragmentBudget::default(), &mut stats, &mut RenderScratch::new());
+    (framebuffer.buffer, stats)
+}
+
+#[test]
+fn two_sided_back_face_matches_front() {
+    let (front, front_stats) = draw(&triangle(false), false, true);
+    let (back, _) = draw(&triangle(true), true, true);
+    assert!(front_stats.depth_passed > 0, "el triángulo de frente no se dibujó");
+    assert!(front == back, "la cara trasera de dos caras no se ve igual que su espejo de frente");
+
+    // Sin dos caras la cara trasera se descarta, o sin descartar se ilumina de espaldas a la luz
+    let (_, culled) = draw(&triangle(true), false, true);
+    assert_eq!((culled.culled_triangles, culled.depth_passed), (1, 0));
+    let (unlit, _) = draw(&triangle(true), false, false);
+    assert!(unlit != front, "la cara trasera de una cara se iluminó como la de frente");
+}
+
+// Octaedro con la normal de cada vértice hacia afuera y la mitad de las caras al revés
+const HALF_REVERSED_OCTAHEDRON: &str = "
+v 1 0 0
+v -1 0 0
+v 0 1 0
+v 0 -1 0
+v 0 0 1
+v 0 0 -1
+vn 1 0 0
+vn -1 0 0
+vn 0 1 0
+vn 0 -1 0
+vn 0 0 1
+vn 0 0 -1
+f 1//1 3//3 5//5
+f 2//2 3//3 5//5
+f 1//1 5//5 4//4
+f 2//2 5//5 4//4
+f 1//1 6//6 3//3
+f 2//2 6//6 3//3
+f 1//1 4//4 6//6
+f 2//2 4//4 6//6
+";
+
+#[test]
+fn reversed_faces_are_fixed_on_load() {
+    let obj = Obj::parse(HALF_REVERSED_OCTAHEDRON).expect("no se pudo leer el octaedro");
+    assert_eq!(obj.winding_fixes(), 4);
+
+    let vertices = obj.get_vertex_array();
+    assert_eq!(vertices.len(), 24);
+    for (face, triangle) in vertices.chunks_exact(3).enumerate() {
+        let [a, b, c] = [0, 1, 2].map(|corner| triangle[corner].position);
+        let outward = (b - a).cross(&(c - a)).dot(&((a + b + c) / 3.0));
+        assert!(outward > 0.0, "la cara {} quedó hacia adentro", face + 1);
+    }
+
+    // Un triángulo que ya está de acuerdo con su normal no se toca
+    let consistent = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n";
+    assert_eq!(Obj::parse(consistent).expect("no se pudo leer el triángulo").winding_fixes(), 0);
+}
@@ -0,0 +1,123 @@
+// Luz en el espacio del mundo: al girar un cuerpo 180° el lado iluminado, el reflejo del planeta de hielo y
+// la luz del anillo se quedan donde está la luz, mientras que lo pintado en la superficie gira con él
+use std::f32::consts::PI;
+use nalgebra_glm::Vec3;
+use lab4_shaders::camera::Camera;
+use lab4_shaders::color::Color;
+use lab4_shaders::framebuffer::Framebuffer;
+use lab4_shaders::mesh::{generate_ring, generate_uv_sphere};
+use lab4_shaders::pipeline::{build_uniforms, create_model_matrix, render, BodyUniforms, FragmentBudget, Light, RenderScratch, RenderStats, DEFAULT_DEPTH_RANGE};
+use lab4_shaders::shaders::ShaderType;
+use lab4_shaders::vertex::Vertex;
+
+const WIDTH: usize = 96;
+const HEIGHT: usize = 72;
+
+// Modos de depuración del planeta de hielo
+const CRACKS_ONLY: u32 = 2;
+const SPECULAR_ONLY: u32 = 3;
+
+// Cuerpo girado `spin` en y, con la cámara en `eye` y una luz blanca desde +x
+fn draw(vertices: &[Vertex], shader: ShaderType, spin: f32, eye: Vec3, debug_mode: u32) -> Framebuffer {
+    let camera = Camera::new(eye, Vec3::zeros(), Vec3::y());
+    let mut frame = build_uniforms(&camera, 0.0, WIDTH, HEIGHT, DEFAULT_DEPTH_RANGE);
+    frame.lights = vec![Light::directional(Vec3::x(), Color::new(255, 255, 255), 1.0)];
+    frame.debug_mode = debug_mode;
+    let mut body = BodyUniforms::new(create_model_matrix(Vec3::zeros(), Vec3::repeat(3.0), Vec3::new(0.0, spin, 0.0)));
+    body.two_sided = shader == ShaderType::Ring;
+
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    render(&mut framebuffer, &frame, &body, vertices, &shader, &mut FragmentBudget::default(), &mut RenderStats::default(), &mut RenderScratch::new());
+    framebuffer
+}
+
+// Brillo de cada pixel dibujado, con su posición
+fn drawn_pixels(framebuffer: &Framebuffer) -> Vec<(usize, usize, f32)> {
+    let mut pixels = Vec::new();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let (color, depth) = framebuffer.get_pixel(x, y).unwrap();
+            if depth.is_finite() {
+                pixels.push((x, y, (color.r + color.g + color.b) / 3.0));
+            }
+        }
+    }
+    pixels
+}
+
+// Brillo medio de la mitad izquierda y de la derecha de la pantalla
+fn halves(framebuffer: &Framebuffer) -> (f32, f32) {
+    let pixels = drawn_pixels(framebuffer);
+    let mean = |right: bool| {
+        let side: Vec<f32> = pixels.iter().filter(|(x, _, _)| (*x >= WIDTH / 2) == right).map(|(_, _, value)| *value).collect();
+        side.iter().sum::<f32>() / side.len() as f32
+    };
+    (mean(false), mean(true))
+}
+
+// Centro del brillo en pantalla, pesado por el brillo de cada pixel
+fn bright_center(framebuffer: &Framebuffer) -> (f32, f32) {
+    let pixels = drawn_pixels(framebuffer);
+    let total: f32 = pixels.iter().map(|(_, _, value)| value).sum();
+    assert!(total > 0.0, "no hay nada iluminado");
+    let x = pixels.iter().map(|(x, _, value)| *x as f32 * value).sum::<f32>() / total;
+    let y = pixels.iter().map(|(_, y, value)| *y as f32 * value).sum::<f32>() / total;
+    (x, y)
+}
+
+// Pixeles que cambian de brillo en más de `threshold` entre las dos imágenes
+fn changed_pixels(a: &Framebuffer, b: &Framebuffer, threshold: f32) -> usize {
+    drawn_pixels(a).iter().zip(drawn_pixels(b)).filter(|(first, second)| (first.2 - second.2).abs() > threshold).count()
+}
+
+#[test]
+fn the_lit_side_stays_with_the_light_while_the_planet_spins() {
+    let sphere = generate_uv_sphere(24, 48);
+    let eye = Vec3::new(0.0, 0.0, 6.0);
+    let front = draw(&sphere, ShaderType::IcyPlanet, 0.0, eye, 0);
+    let back = draw(&sphere, ShaderType::IcyPlanet, PI, eye, 0);
+    assert_eq!(drawn_pixels(&front).len(), drawn_pixels(&back).len());
+
+    // La luz viene desde +x: la mitad derecha es la de día en las dos
+    for (name, framebuffer) in [("sin girar", &front), ("girado", &back)] {
+        let (left, right) = halves(framebuffer);
+        assert!(right > left * 2.0, "{}: izquierda {} y derecha {}", name, left, right);
+    }
+
+    // Las grietas están pintadas en el planeta y se mueven con él
+    let cracks = (draw(&sphere, ShaderType::IcyPlanet, 0.0, eye, CRACKS_ONLY), draw(&sphere, ShaderType::IcyPlanet, PI, eye, CRACKS_ONLY));
+    let drawn = drawn_pixels(&cracks.0).len();
+    assert!(changed_pixels(&cracks.0, &cracks.1, 16.0) > drawn / 4, "las grietas no giraron con el planeta");
+}
+
+#[test]
+fn the_icy_highlight_does_not_spin_with_the_planet() {
+    // El reflejo queda entre la luz (+x) y la cámara (+z), del lado derecho, gire como gire el planeta
+    let sphere = generate_uv_sphere(24, 48);
+    let eye = Vec3::new(0.0, 0.0, 6.0);
+    let front = draw(&sphere, ShaderType::IcyPlanet, 0.0, eye, SPECULAR_ONLY);
+    let (x, y) = bright_center(&front);
+    assert!(x > WIDTH as f32 / 2.0 + 4.0 && (y - HEIGHT as f32 / 2.0).abs() < 2.0, "reflejo en ({}, {})", x, y);
+
+    for spin in [PI / 2.0, PI, 1.5 * PI] {
+        let spun = draw(&sphere, ShaderType::IcyPlanet, spin, eye, SPECULAR_ONLY);
+        let (spun_x, spun_y) = bright_center(&spun);
+        assert!((spun_x - x).abs() < 0.5 && (spun_y - y).abs() < 0.5, "girado {}: reflejo en ({}, {}) en lugar de ({}, {})", spin, spun_x, spun_y, x, y);
+    }
+}
+
+#[test]
+fn the_ring_light_does_not_spin_with_the_ring() {
+    // El anillo es simétrico al girarlo en su eje, así que de arriba se tiene que ver igual
+    let ring = generate_ring(0.6, 1.0, 128);
+    let eye = Vec3::new(0.0, 4.0, 5.0);
+    let front = draw(&ring, ShaderType::Ring, 0.0, eye, 0);
+    let back = draw(&ring, ShaderType::Ring, PI, eye, 0);
+
+    let (front_left, front_right) = halves(&front);
+    let (back_left, back_right) = halves(&back);
+    assert!((front_left - back_left).abs() < 1.0 && (front_right - back_right).abs() < 1.0,
+        "sin girar {:?}, girado {:?}", (front_left, front_right), (back_left, back_right));
+    let drawn = drawn_pixels(&front).len();
+    assert!(changed_pixels(&front, &back, 8.0) < drawn / 50, "el anillo cambió al girarlo");
+}